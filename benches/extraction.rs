@@ -0,0 +1,110 @@
+//! Interpreted vs compiled extraction over a large CSV.
+//!
+//! Run with `cargo bench --bench extraction`. The row count defaults to
+//! 1,000,000 and can be lowered with `NOMNOM_BENCH_ROWS` for quick runs.
+
+use std::sync::Arc;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use serde_json::{json, Value};
+
+use nomnom::codegen::parse_entity;
+use nomnom::runtime::builtin_registry;
+use nomnom::{CompiledExtractor, DefinitionExtractor, EntityExtractor, ExtractionContext};
+
+const ENTITY: &str = r#"
+entity:
+  name: OrderLine
+  source_type: root
+  fields:
+    - name: raw_line
+      type: String
+    - name: order_id
+      type: String
+      computed_from:
+        transform: extract_csv_field
+        sources:
+          - source: self
+            field: raw_line
+        args:
+          column_index: 0
+    - name: quantity
+      type: Integer
+      computed_from:
+        transform: parse_int
+        sources:
+          - source: self
+            field: quantity_text
+    - name: quantity_text
+      type: String
+      computed_from:
+        transform: extract_csv_field
+        sources:
+          - source: self
+            field: raw_line
+        args:
+          column_index: 1
+    - name: price
+      type: Decimal
+      computed_from:
+        transform: parse_decimal
+        sources:
+          - source: self
+            field: price_text
+    - name: price_text
+      type: String
+      computed_from:
+        transform: extract_csv_field
+        sources:
+          - source: self
+            field: raw_line
+        args:
+          column_index: 2
+    - name: comment
+      type: String
+      computed_from:
+        transform: extract_csv_field
+        sources:
+          - source: self
+            field: raw_line
+        args:
+          column_index: 3
+"#;
+
+fn rows() -> Vec<Value> {
+    let count = std::env::var("NOMNOM_BENCH_ROWS")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(1_000_000);
+
+    (0..count)
+        .map(|i: usize| json!({
+            "raw_line": format!("O{},{},{}.{:02},\"note, {}\"", i, i % 50, i % 1000, i % 100, i),
+        }))
+        .collect()
+}
+
+fn run(extractor: &dyn EntityExtractor, rows: &[Value]) {
+    let context = ExtractionContext::empty();
+    for row in rows {
+        black_box(extractor.extract(&context, row).unwrap());
+    }
+}
+
+fn bench_extraction(c: &mut Criterion) {
+    let entity = parse_entity(ENTITY).unwrap();
+    let rows = rows();
+
+    let interpreted = DefinitionExtractor::new(entity.clone(), Arc::new(builtin_registry()));
+    let compiled = CompiledExtractor::compile(&entity, &builtin_registry()).unwrap();
+
+    let mut group = c.benchmark_group("csv_extraction");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(rows.len() as u64));
+    group.bench_function("interpreted", |b| b.iter(|| run(&interpreted, &rows)));
+    group.bench_function("compiled", |b| b.iter(|| run(&compiled, &rows)));
+    group.finish();
+}
+
+criterion_group!(benches, bench_extraction);
+criterion_main!(benches);
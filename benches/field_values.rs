@@ -0,0 +1,100 @@
+//! Allocations of borrowed vs owned field extraction.
+//!
+//! Run with `cargo bench --bench field_values`. Allocation counts per row are
+//! printed before timing; borrowed extraction should allocate nothing for
+//! columns that need no unescaping.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+use nomnom::{Extractor, FieldPath, FieldValue, PathSegment};
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// CSV line extractor; quoted columns with doubled quotes are unescaped into owned strings
+struct CsvLine<'a>(&'a str);
+
+impl Extractor for CsvLine<'_> {
+    fn extract(&self, path: &FieldPath) -> Option<Cow<'_, str>> {
+        let Some(PathSegment::Index(index)) = path.segments.first() else {
+            return None;
+        };
+        let column = self.0.split(',').nth(*index)?;
+        match column.strip_prefix('"').and_then(|c| c.strip_suffix('"')) {
+            Some(quoted) if quoted.contains("\"\"") => Some(Cow::Owned(quoted.replace("\"\"", "\""))),
+            Some(quoted) => Some(Cow::Borrowed(quoted)),
+            None => Some(Cow::Borrowed(column)),
+        }
+    }
+}
+
+fn lines() -> Vec<String> {
+    (0..10_000)
+        .map(|i| format!("O{},{},{}.{:02},\"note {}\"", i, i % 50, i % 1000, i % 100, i))
+        .collect()
+}
+
+fn paths() -> Vec<FieldPath> {
+    (0..4).map(|i| FieldPath::parse(&format!("[{}]", i), ".")).collect()
+}
+
+fn borrowed(lines: &[String], paths: &[FieldPath]) {
+    for line in lines {
+        let row = CsvLine(line);
+        let values: [Option<FieldValue<'_>>; 4] = std::array::from_fn(|i| row.extract_value(&paths[i]));
+        black_box(&values);
+    }
+}
+
+fn owned(lines: &[String], paths: &[FieldPath]) {
+    for line in lines {
+        let row = CsvLine(line);
+        let values: [Option<String>; 4] = std::array::from_fn(|i| row.extract_owned(&paths[i]));
+        black_box(&values);
+    }
+}
+
+fn allocations_per_row(f: fn(&[String], &[FieldPath]), lines: &[String], paths: &[FieldPath]) -> f64 {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f(lines, paths);
+    (ALLOCATIONS.load(Ordering::Relaxed) - before) as f64 / lines.len() as f64
+}
+
+fn bench_field_values(c: &mut Criterion) {
+    let lines = lines();
+    let paths = paths();
+
+    println!(
+        "allocations per row: borrowed {:.2}, owned {:.2}",
+        allocations_per_row(borrowed, &lines, &paths),
+        allocations_per_row(owned, &lines, &paths),
+    );
+
+    let mut group = c.benchmark_group("field_values");
+    group.throughput(Throughput::Elements(lines.len() as u64));
+    group.bench_function("borrowed", |b| b.iter(|| borrowed(&lines, &paths)));
+    group.bench_function("owned", |b| b.iter(|| owned(&lines, &paths)));
+    group.finish();
+}
+
+criterion_group!(benches, bench_field_values);
+criterion_main!(benches);
@@ -45,7 +45,11 @@ fn main() {
         python_dependency_imports: None,
         python_rust_shim_output: None,
         python_package_init_output: None,
+        python_stubs_output: None,
         rust_transforms,
+        naming: Default::default(),
+        timestamps: false,
+        database_type: nomnom::codegen::DatabaseType::PostgreSQL,
     };
 
     generate_all_from_config(&config).unwrap();
@@ -3,7 +3,7 @@
 //! This CLI tool generates Rust code and Python bindings from YAML entity and transform definitions.
 
 use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process;
 
 #[derive(Parser)]
@@ -25,6 +25,10 @@ enum Commands {
         /// Output directory for generated code
         #[arg(short, long, default_value = ".build")]
         output: PathBuf,
+
+        /// Keep running and regenerate whenever a config file changes
+        #[arg(short, long)]
+        watch: bool,
     },
 
     /// Build Rust extension and Python wheel from YAML configurations
@@ -49,6 +53,91 @@ enum Commands {
         config: PathBuf,
     },
 
+    /// Print the resolved extraction plan (order, parents, transforms, persistence)
+    Explain {
+        /// Path to config directory containing entities/
+        #[arg(short, long, default_value = "config")]
+        config: PathBuf,
+    },
+
+    /// Write an entity relationship diagram (Mermaid or Graphviz DOT)
+    GenerateDiagram {
+        /// Path to config directory containing entities/
+        #[arg(short, long, default_value = "config")]
+        config: PathBuf,
+
+        /// Output format: mermaid (default) or dot
+        #[arg(short, long, default_value = "mermaid")]
+        format: String,
+
+        /// File to write the diagram to (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Run a single transform against sample input and print the result
+    TestTransform {
+        /// Transform name (builtin or defined in the transforms directory)
+        #[arg(short, long)]
+        name: String,
+
+        /// Value bound to the transform's first parameter
+        #[arg(short, long)]
+        input: String,
+
+        /// Extra argument as key=value (value parsed as JSON when possible, repeatable)
+        #[arg(short, long = "arg")]
+        args: Vec<String>,
+
+        /// Directory of transform YAMLs
+        #[arg(short, long, default_value = "config/transforms")]
+        transforms: PathBuf,
+    },
+
+    /// Run the `tests:` cases declared in transform YAMLs and report pass/fail
+    TestTransforms {
+        /// Path to config directory containing transforms/
+        #[arg(short, long, default_value = "config")]
+        config: PathBuf,
+    },
+
+    /// Print a catalog of the transforms defined in a config directory
+    ListTransforms {
+        /// Path to config directory containing transforms/
+        #[arg(short, long, default_value = "config")]
+        config: PathBuf,
+
+        /// Output format: markdown (default) or json
+        #[arg(short, long, default_value = "markdown")]
+        format: String,
+    },
+
+    /// Scaffold a new entity YAML (prompts for anything not given as a flag)
+    NewEntity {
+        /// Entity name (PascalCase)
+        name: Option<String>,
+
+        /// Template: root, csv, json, or derived
+        #[arg(short, long)]
+        template: Option<String>,
+
+        /// Parent entity for csv/json/derived templates
+        #[arg(short, long)]
+        parent: Option<String>,
+
+        /// Field as name or name:Type (repeatable)
+        #[arg(short, long = "field")]
+        fields: Vec<String>,
+
+        /// Directory to write the entity YAML into
+        #[arg(short, long, default_value = "config/entities")]
+        dir: PathBuf,
+
+        /// Overwrite an existing file
+        #[arg(long)]
+        force: bool,
+    },
+
     /// Build parser binary with Python bindings (PyO3 + Diesel + generated entities)
     BuildParserBinary {
         /// Path to nomnom.yaml configuration file
@@ -112,6 +201,15 @@ enum Commands {
         /// Server name for Cargo.toml
         #[arg(short, long, default_value = "ingestion-server")]
         name: String,
+
+        /// Emit trace-level diagnostics (full message bodies) in the generated server
+        #[arg(long)]
+        verbose: bool,
+
+        /// Validate messages with the worker's parser before publishing
+        /// (default for the server's VALIDATE_ON_INGEST)
+        #[arg(long)]
+        validate_on_ingest: bool,
     },
 
     /// Generate NATS worker binary (consumes from NATS JetStream)
@@ -131,6 +229,66 @@ enum Commands {
         /// Worker name for Cargo.toml
         #[arg(short, long, default_value = "worker")]
         name: String,
+
+        /// Entities published to NATS by default (all, persistent, transient, none);
+        /// PUBLISH_ENTITIES overrides it at runtime
+        #[arg(long, default_value = "all")]
+        publish_entities: String,
+
+        /// Subject captured by the worker's stream (repeatable; default messages.ingest.>)
+        #[arg(long = "stream-subject")]
+        stream_subjects: Vec<String>,
+
+        /// Subject the worker's consumer is limited to, e.g. messages.ingest.hl7.>
+        /// (repeatable; default: the stream subjects)
+        #[arg(long = "filter-subject")]
+        filter_subjects: Vec<String>,
+
+        /// Seconds before an unacknowledged message is redelivered (ACK_WAIT_SECS at runtime)
+        #[arg(long, default_value_t = 60)]
+        ack_wait_secs: u64,
+
+        /// Unacknowledged messages allowed in flight (MAX_ACK_PENDING at runtime)
+        #[arg(long, default_value_t = 1000)]
+        max_ack_pending: i64,
+
+        /// What happens when an entity fails (abort, skip, dlq); defaults to
+        /// nomnom.yaml's on_entity_error. ON_ENTITY_ERROR overrides it at runtime
+        #[arg(long)]
+        on_entity_error: Option<String>,
+
+        /// Consumer durability: durable (replicas share NATS_CONSUMER) or ephemeral
+        /// (removed when idle); NATS_CONSUMER_DURABILITY overrides it at runtime
+        #[arg(long, default_value = "durable")]
+        consumer_durability: String,
+
+        /// Name of the ephemeral consumer replicas share; without it each pod gets
+        /// its own (NATS_QUEUE_GROUP at runtime)
+        #[arg(long)]
+        queue_group: Option<String>,
+
+        /// Process only a fraction of an entity type's messages, e.g. Order=0.1
+        /// (repeatable; SAMPLE_RATES overrides it at runtime)
+        #[arg(long = "sample-rate", value_parser = nomnom::codegen::worker::parse_sample_rate)]
+        sample_rates: Vec<(String, f64)>,
+
+        /// Emit trace-level diagnostics (poll iterations, every parser tried) in the generated worker
+        #[arg(long)]
+        verbose: bool,
+
+        /// Archive each message's body and extracted entities by message id for
+        /// replay/audit (SIDE_OUTPUT=nats_kv|custom|none at runtime)
+        #[arg(long)]
+        side_output: bool,
+
+        /// Rust file implementing side_output::SideOutput for another store (e.g. S3),
+        /// used with SIDE_OUTPUT=custom; implies --side-output
+        #[arg(long)]
+        side_output_sink: Option<PathBuf>,
+
+        /// Deepest entity dependency nesting accepted when generating
+        #[arg(long, default_value_t = nomnom::codegen::worker::DEFAULT_MAX_DEPENDENCY_DEPTH)]
+        max_dependency_depth: usize,
     },
 
     /// Generate Benthos pipelines for NATS to MySQL streaming
@@ -194,6 +352,28 @@ enum Commands {
         #[arg(short, long, default_value = "mysql")]
         database: String,
     },
+
+    /// Republish dead-lettered messages (messages.dlq.>) to messages.ingest.> and
+    /// reset their message_status (NATS_URL and auth/TLS settings from the environment)
+    ReplayDlq {
+        /// Only replay messages ingested as this entity type (the envelope's
+        /// entity_type, not the entity they were dead-lettered under)
+        #[arg(long)]
+        entity_type: Option<String>,
+
+        /// Stop after this many messages
+        #[arg(long)]
+        max: Option<usize>,
+
+        /// DLQ stream (default: <NATS_STREAM>DLQ)
+        #[arg(long)]
+        dlq_stream: Option<String>,
+
+        /// Database whose message_status rows are reset (default: DATABASE_URL;
+        /// without one statuses are left as they are)
+        #[arg(long)]
+        database_url: Option<String>,
+    },
 }
 
 /// Determine database type with precedence: CLI > ENV > config file > DATABASE_URL > default
@@ -201,60 +381,36 @@ fn detect_database_type(
     cli_override: Option<String>,
     config_db_type: Option<String>,
 ) -> Result<String, String> {
+    use nomnom::codegen::DatabaseType;
+
     // 1. CLI flag (highest priority)
     if let Some(db_type) = cli_override {
-        let normalized = db_type.to_lowercase();
-        if matches!(normalized.as_str(), "postgresql" | "postgres" | "pg" | "mysql" | "mariadb") {
-            println!("  ℹ Using database type from CLI flag: {}", normalized);
-            return Ok(match normalized.as_str() {
-                "postgres" | "pg" => "postgresql".to_string(),
-                other => other.to_string(),
-            });
-        } else {
-            return Err(format!(
-                "Unsupported database type: '{}'. Supported types: postgresql, mysql, mariadb",
-                db_type
-            ));
-        }
+        let db_type = DatabaseType::from_str(&db_type)?;
+        println!("  ℹ Using database type from CLI flag: {}", db_type.as_str());
+        return Ok(db_type.as_str().to_string());
     }
 
     // 2. Environment variable NOMNOM_DATABASE_TYPE
     if let Ok(db_type) = std::env::var("NOMNOM_DATABASE_TYPE") {
-        let normalized = db_type.to_lowercase();
-        if matches!(normalized.as_str(), "postgresql" | "postgres" | "pg" | "mysql" | "mariadb") {
-            println!("  ℹ Using database type from NOMNOM_DATABASE_TYPE: {}", normalized);
-            return Ok(match normalized.as_str() {
-                "postgres" | "pg" => "postgresql".to_string(),
-                other => other.to_string(),
-            });
+        if let Ok(db_type) = DatabaseType::from_str(&db_type) {
+            println!("  ℹ Using database type from NOMNOM_DATABASE_TYPE: {}", db_type.as_str());
+            return Ok(db_type.as_str().to_string());
         }
     }
 
     // 3. Config file database.type
     if let Some(db_type) = config_db_type {
-        let normalized = db_type.to_lowercase();
-        if matches!(normalized.as_str(), "postgresql" | "postgres" | "pg" | "mysql" | "mariadb") {
-            println!("  ℹ Using database type from config file: {}", normalized);
-            return Ok(match normalized.as_str() {
-                "postgres" | "pg" => "postgresql".to_string(),
-                other => other.to_string(),
-            });
-        } else {
-            return Err(format!(
-                "Unsupported database type in config: '{}'. Supported types: postgresql, mysql, mariadb",
-                db_type
-            ));
-        }
+        let db_type = DatabaseType::from_str(&db_type)
+            .map_err(|e| format!("Invalid database type in config: {}", e))?;
+        println!("  ℹ Using database type from config file: {}", db_type.as_str());
+        return Ok(db_type.as_str().to_string());
     }
 
     // 4. Detect from DATABASE_URL scheme
     if let Ok(database_url) = std::env::var("DATABASE_URL") {
-        if database_url.starts_with("postgres://") || database_url.starts_with("postgresql://") {
-            println!("  ℹ Detected PostgreSQL from DATABASE_URL");
-            return Ok("postgresql".to_string());
-        } else if database_url.starts_with("mysql://") {
-            println!("  ℹ Detected MySQL from DATABASE_URL");
-            return Ok("mysql".to_string());
+        if let Some(db_type) = DatabaseType::from_url_scheme(&database_url) {
+            println!("  ℹ Detected {} from DATABASE_URL", db_type.as_str());
+            return Ok(db_type.as_str().to_string());
         }
     }
 
@@ -266,9 +422,22 @@ fn detect_database_type(
 fn main() {
     let cli = Cli::parse();
 
+    // Generator diagnostics go to stderr; enable them with e.g. RUST_LOG=nomnom=debug
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn")),
+        )
+        .with_writer(std::io::stderr)
+        .init();
+
     let result = match cli.command {
-        Commands::Generate { config, output } => {
-            generate_code(config, output)
+        Commands::Generate { config, output, watch } => {
+            if watch {
+                watch_and_generate(config, output)
+            } else {
+                generate_code(config, output)
+            }
         }
         Commands::Build { config, output, release } => {
             build_project(config, output, release)
@@ -276,17 +445,67 @@ fn main() {
         Commands::Validate { config } => {
             validate_config(config)
         }
+        Commands::Explain { config } => {
+            explain_config(config)
+        }
+        Commands::GenerateDiagram { config, format, output } => {
+            generate_diagram(config, format, output)
+        }
+        Commands::TestTransform { name, input, args, transforms } => {
+            test_transform(name, input, args, transforms)
+        }
+        Commands::TestTransforms { config } => {
+            test_transforms(config)
+        }
+        Commands::ListTransforms { config, format } => {
+            list_transforms(config, format)
+        }
+        Commands::NewEntity { name, template, parent, fields, dir, force } => {
+            new_entity(name, template, parent, fields, dir, force)
+        }
         Commands::BuildParserBinary { config, output, release, test, database } => {
             build_parser_binary(config, output, release, test, database)
         }
         Commands::GenerateDashboard { entities, output, database, backend } => {
             generate_dashboard(entities, output, database, backend)
         }
-        Commands::GenerateIngestionServer { entities, output, database, port, name } => {
-            generate_ingestion_server(entities, output, database, port, name)
+        Commands::GenerateIngestionServer { entities, output, database, port, name, verbose, validate_on_ingest } => {
+            generate_ingestion_server(entities, output, database, port, name, verbose, validate_on_ingest)
         }
-        Commands::GenerateWorker { entities, output, database, name } => {
-            generate_worker(entities, output, database, name)
+        Commands::GenerateWorker {
+            entities, output, database, name, publish_entities, stream_subjects, filter_subjects, ack_wait_secs, max_ack_pending,
+            on_entity_error, consumer_durability, queue_group, sample_rates, verbose, side_output, side_output_sink,
+            max_dependency_depth,
+        } => {
+            let on_entity_error = on_entity_error.as_deref()
+                .map(nomnom::runtime::OnEntityError::from_str)
+                .transpose();
+            on_entity_error.and_then(|on_entity_error| {
+                Ok((nomnom::codegen::worker::EntityPublishing::from_str(&publish_entities)?, on_entity_error))
+            }).and_then(|(publish_entities, on_entity_error)| {
+                let consumer_durability = nomnom::codegen::worker::ConsumerDurability::from_str(&consumer_durability)?;
+                // The consumer filters on the stream subjects unless told otherwise
+                let defaults = nomnom::codegen::worker::WorkerConfig::default();
+                let stream_subjects = if stream_subjects.is_empty() { defaults.stream_subjects.clone() } else { stream_subjects };
+                let filter_subjects = if filter_subjects.is_empty() { stream_subjects.clone() } else { filter_subjects };
+                let config = nomnom::codegen::worker::WorkerConfig {
+                    worker_name: name,
+                    publish_entities,
+                    stream_subjects,
+                    filter_subjects,
+                    ack_wait_secs,
+                    max_ack_pending,
+                    consumer_durability,
+                    queue_group,
+                    sample_rates: sample_rates.into_iter().collect(),
+                    verbose,
+                    side_output: side_output || side_output_sink.is_some(),
+                    side_output_sink,
+                    max_dependency_depth,
+                    ..defaults
+                };
+                generate_worker(entities, output, database, config, on_entity_error)
+            })
         }
         Commands::GenerateBenthos { entities, output, format, helm_chart_path, database, nats_url, mysql_host, mysql_port, mysql_database } => {
             generate_benthos(entities, output, format, helm_chart_path, database, nats_url, mysql_host, mysql_port, mysql_database)
@@ -294,6 +513,9 @@ fn main() {
         Commands::GenerateHelmChart { entities, output, chart_version, app_version, database } => {
             generate_helm_chart(entities, output, chart_version, app_version, database)
         }
+        Commands::ReplayDlq { entity_type, max, dlq_stream, database_url } => {
+            replay_dlq(entity_type, max, dlq_stream, database_url)
+        }
     };
 
     if let Err(e) = result {
@@ -302,6 +524,83 @@ fn main() {
     }
 }
 
+/// Regenerate code whenever a file under the config directory changes
+///
+/// Bursts of writes (editors often save in several steps) are debounced into
+/// a single regeneration. Load and generation errors are reported without
+/// exiting so the loop survives half-edited YAML.
+fn watch_and_generate(config: PathBuf, output: PathBuf) -> Result<(), String> {
+    use notify::{EventKind, RecursiveMode, Watcher};
+    use std::collections::BTreeSet;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    const DEBOUNCE: Duration = Duration::from_millis(300);
+
+    if !config.exists() {
+        return Err(format!("Config directory not found: {}", config.display()));
+    }
+
+    let regenerate = |config: &PathBuf, output: &PathBuf| {
+        if let Err(e) = generate_code(config.clone(), output.clone()) {
+            eprintln!("❌ {}", e);
+        }
+    };
+
+    regenerate(&config, &output);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)
+        .map_err(|e| format!("Failed to start file watcher: {}", e))?;
+    watcher.watch(&config, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch {}: {}", config.display(), e))?;
+
+    println!("👀 Watching {} for changes (Ctrl+C to stop)...", config.display());
+
+    let is_relevant = |event: &notify::Event| {
+        !matches!(event.kind, EventKind::Access(_))
+            && event.paths.iter().any(|p| {
+                matches!(p.extension().and_then(|e| e.to_str()), Some("yaml" | "yml"))
+            })
+    };
+
+    loop {
+        let first = match rx.recv() {
+            Ok(Ok(event)) => event,
+            Ok(Err(e)) => {
+                eprintln!("⚠️  Watch error: {}", e);
+                continue;
+            }
+            Err(_) => return Err("File watcher stopped unexpectedly".to_string()),
+        };
+
+        let mut changed = BTreeSet::new();
+        if is_relevant(&first) {
+            changed.extend(first.paths);
+        }
+
+        // Debounce: keep collecting until the directory has been quiet for a moment
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            if let Ok(event) = event {
+                if is_relevant(&event) {
+                    changed.extend(event.paths);
+                }
+            }
+        }
+
+        if changed.is_empty() {
+            continue;
+        }
+
+        println!();
+        for path in &changed {
+            let shown = path.strip_prefix(&config).unwrap_or(path);
+            println!("📝 Changed: {}", shown.display());
+        }
+        regenerate(&config, &output);
+    }
+}
+
 /// Generate Rust code from YAML configurations
 fn generate_code(config: PathBuf, output: PathBuf) -> Result<(), String> {
     println!("🔧 Generating code from {}...", config.display());
@@ -352,6 +651,7 @@ fn generate_code(config: PathBuf, output: PathBuf) -> Result<(), String> {
     // Configure code generation with transform registry
     let codegen_config = nomnom::codegen::RustCodegenConfig {
         transform_registry_type: Some("crate::transform_registry::TransformRegistry".to_string()),
+        ..Default::default()
     };
 
     nomnom::codegen::generate_rust_code(&mut file, &entities, &codegen_config)
@@ -536,6 +836,15 @@ fn validate_config(config: PathBuf) -> Result<(), String> {
 
     println!("  ✓ {} entities validated", entities.len());
 
+    let orphans = nomnom::codegen::yaml_loader::find_orphan_entities(&entities);
+    if !orphans.is_empty() {
+        println!(
+            "  ⚠ Warning: {} entities are not reachable from a root entity and will never be extracted: {}",
+            orphans.len(),
+            orphans.join(", ")
+        );
+    }
+
     // Validate transform configurations (optional)
     let transforms_dir = config.join("transforms");
     if transforms_dir.exists() {
@@ -549,6 +858,257 @@ fn validate_config(config: PathBuf) -> Result<(), String> {
     Ok(())
 }
 
+/// Print the resolved extraction plan for an entity configuration
+fn explain_config(config: PathBuf) -> Result<(), String> {
+    let entities_dir = config.join("entities");
+    if !entities_dir.exists() {
+        return Err(format!("Entities directory not found: {}", entities_dir.display()));
+    }
+
+    let entities = nomnom::codegen::load_entities(&entities_dir)
+        .map_err(|e| format!("Failed to load entities: {}", e))?;
+
+    let plan = nomnom::codegen::explain::explain_entities(&entities)?;
+    print!("{}", plan);
+
+    Ok(())
+}
+
+/// Render the entity graph of a config directory as Mermaid or DOT
+fn generate_diagram(config: PathBuf, format: String, output: Option<PathBuf>) -> Result<(), String> {
+    let entities_dir = config.join("entities");
+    if !entities_dir.exists() {
+        return Err(format!("Entities directory not found: {}", entities_dir.display()));
+    }
+
+    let entities = nomnom::codegen::load_entities(&entities_dir)
+        .map_err(|e| format!("Failed to load entities: {}", e))?;
+
+    let format: nomnom::codegen::diagram::DiagramFormat = format.parse()?;
+    let diagram = nomnom::codegen::diagram::render_diagram(&entities, format)?;
+    match output {
+        Some(path) => {
+            std::fs::write(&path, diagram)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            println!("Wrote {}", path.display());
+        }
+        None => print!("{}", diagram),
+    }
+
+    Ok(())
+}
+
+/// Run one transform standalone and print its result
+fn test_transform(name: String, input: String, args: Vec<String>, transforms_dir: PathBuf) -> Result<(), String> {
+    use std::collections::HashMap;
+    use serde_json::Value;
+
+    let mut registry = nomnom::runtime::TransformRegistryLoader::new();
+    if transforms_dir.exists() {
+        registry.load_transforms_from_dir(&transforms_dir)?;
+    }
+    let builtins = nomnom::runtime::builtin_registry();
+
+    if !registry.has_transform(&name) && !builtins.has_transform(&name) {
+        return Err(format!(
+            "Unknown transform '{}' (not in {} and not a builtin: {})",
+            name,
+            transforms_dir.display(),
+            nomnom::runtime::BUILTIN_TRANSFORMS.join(", ")
+        ));
+    }
+
+    let input_param = registry.get_transform(&name)
+        .and_then(|t| t.parameters.first())
+        .map(|p| p.name.clone())
+        .unwrap_or_else(|| "input".to_string());
+
+    let mut call_args: HashMap<String, Value> = HashMap::new();
+    call_args.insert(input_param, Value::String(input));
+    for arg in &args {
+        let (key, value) = arg.split_once('=')
+            .ok_or_else(|| format!("Invalid --arg '{}' (expected key=value)", arg))?;
+        let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+        call_args.insert(key.trim().to_string(), value);
+    }
+
+    let result = registry.call(&name, &call_args, &builtins)
+        .map_err(|e| format!("Transform '{}' failed: {}", name, e))?;
+
+    println!("✅ {} returned:", name);
+    println!("{}", serde_json::to_string_pretty(&result).map_err(|e| e.to_string())?);
+
+    Ok(())
+}
+
+/// Run every declared transform test case and print one line per case
+fn test_transforms(config_dir: PathBuf) -> Result<(), String> {
+    use nomnom::runtime::TransformTestOutcome;
+
+    let transforms_dir = config_dir.join("transforms");
+    if !transforms_dir.exists() {
+        return Err(format!("Transforms directory not found: {}", transforms_dir.display()));
+    }
+
+    let mut registry = nomnom::runtime::TransformRegistryLoader::new();
+    registry.load_transforms_from_dir(&transforms_dir)?;
+    let results = registry.run_tests(&nomnom::runtime::builtin_registry());
+
+    let (mut passed, mut failed, mut skipped) = (0, 0, 0);
+    for result in &results {
+        let case = format!("{}::{}", result.transform, result.test);
+        match result.outcome {
+            TransformTestOutcome::Passed => {
+                passed += 1;
+                println!("✅ {}", case);
+            }
+            TransformTestOutcome::Failed { ref actual } => {
+                failed += 1;
+                println!("❌ {}: expected {}, got {}", case, result.expected, actual);
+            }
+            TransformTestOutcome::Error(ref e) => {
+                failed += 1;
+                println!("❌ {}: {}", case, e);
+            }
+            TransformTestOutcome::Skipped(ref reason) => {
+                skipped += 1;
+                println!("⏭️  {}: skipped ({})", case, reason);
+            }
+        }
+    }
+
+    println!();
+    println!("{} passed, {} failed, {} skipped", passed, failed, skipped);
+
+    if failed > 0 {
+        return Err(format!("{} transform test(s) failed", failed));
+    }
+    Ok(())
+}
+
+/// Print every transform's name, doc, parameters and return type
+fn list_transforms(config_dir: PathBuf, format: String) -> Result<(), String> {
+    let transforms_dir = config_dir.join("transforms");
+    if !transforms_dir.exists() {
+        return Err(format!("Transforms directory not found: {}", transforms_dir.display()));
+    }
+
+    let mut registry = nomnom::runtime::TransformRegistryLoader::new();
+    registry.load_transforms_from_dir(&transforms_dir)?;
+
+    match format.as_str() {
+        "markdown" | "md" => print!("{}", registry.catalog_markdown()),
+        "json" => println!(
+            "{}",
+            serde_json::to_string_pretty(&registry.catalog_json()).map_err(|e| e.to_string())?
+        ),
+        other => return Err(format!("Unknown format '{}' (expected markdown or json)", other)),
+    }
+
+    Ok(())
+}
+
+/// Scaffold a new entity YAML file, prompting for missing values on a terminal
+fn new_entity(
+    name: Option<String>,
+    template: Option<String>,
+    parent: Option<String>,
+    fields: Vec<String>,
+    dir: PathBuf,
+    force: bool,
+) -> Result<(), String> {
+    use nomnom::codegen::scaffold::{EntityScaffold, EntityTemplate, ScaffoldField, SCAFFOLD_FIELD_TYPES};
+    use std::io::IsTerminal;
+
+    let interactive = std::io::stdin().is_terminal();
+    let ask = |question: &str, default: Option<&str>| -> Result<String, String> {
+        if !interactive {
+            return default.map(str::to_string)
+                .ok_or_else(|| format!("{} is required (stdin is not a terminal)", question));
+        }
+        prompt(question, default)
+    };
+
+    let name = match name {
+        Some(name) => name,
+        None => ask("Entity name (PascalCase)", None)?,
+    };
+
+    let template = match template {
+        Some(template) => EntityTemplate::parse(&template)?,
+        None => {
+            if interactive {
+                println!("Templates:");
+                for t in EntityTemplate::ALL {
+                    println!("  {:<8} {}", t.name(), t.description());
+                }
+            }
+            EntityTemplate::parse(&ask("Template", Some("root"))?)?
+        }
+    };
+
+    let parent = match parent {
+        Some(parent) => Some(parent),
+        None if template.needs_parent() => {
+            Some(ask("Parent entity", template.default_parent())?)
+        }
+        None => None,
+    };
+
+    let mut fields = fields.iter()
+        .map(|spec| ScaffoldField::parse(spec))
+        .collect::<Result<Vec<_>, _>>()?;
+    if fields.is_empty() && interactive {
+        println!("Fields as name or name:Type ({}); blank line to finish", SCAFFOLD_FIELD_TYPES.join(", "));
+        loop {
+            let spec = prompt("Field", Some(""))?;
+            if spec.is_empty() {
+                break;
+            }
+            match ScaffoldField::parse(&spec) {
+                Ok(field) => fields.push(field),
+                Err(e) => eprintln!("  {}", e),
+            }
+        }
+    }
+
+    let scaffold = EntityScaffold { name, template, parent, fields };
+    let (yaml, entity) = scaffold.render_validated()?;
+
+    let path = dir.join(scaffold.file_name());
+    if path.exists() && !force {
+        return Err(format!("{} already exists (use --force to overwrite)", path.display()));
+    }
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    std::fs::write(&path, yaml)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    println!("✅ Wrote {} ({} fields) to {}", entity.name, entity.fields.len(), path.display());
+    Ok(())
+}
+
+/// Ask a question on stdin, returning the default for an empty answer
+fn prompt(question: &str, default: Option<&str>) -> Result<String, String> {
+    use std::io::Write;
+
+    match default {
+        Some(default) if !default.is_empty() => print!("{} [{}]: ", question, default),
+        _ => print!("{}: ", question),
+    }
+    std::io::stdout().flush().map_err(|e| e.to_string())?;
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).map_err(|e| e.to_string())?;
+    let answer = answer.trim();
+
+    match (answer.is_empty(), default) {
+        (true, Some(default)) => Ok(default.to_string()),
+        (true, None) => Err(format!("{} is required", question)),
+        (false, _) => Ok(answer.to_string()),
+    }
+}
+
 /// Build parser binary with Python bindings from nomnom.yaml
 fn build_parser_binary(
     config_file: PathBuf,
@@ -800,6 +1360,21 @@ fn build_parser_binary(
     Ok(())
 }
 
+/// nomnom.yaml of the project an entities directory belongs to, if any
+///
+/// Looks in the entities directory's parent, the current directory and the
+/// entities directory itself, in that order.
+fn find_nomnom_yaml(entities_dir: &Path) -> Option<PathBuf> {
+    let mut candidates = vec![];
+    if let Some(parent) = entities_dir.parent() {
+        candidates.push(parent.join("nomnom.yaml"));
+    }
+    candidates.push(PathBuf::from("nomnom.yaml"));
+    candidates.push(entities_dir.join("nomnom.yaml"));
+
+    candidates.into_iter().find(|path| path.exists())
+}
+
 /// Generate real-time dashboard for database monitoring
 fn generate_dashboard(
     entities_dir: PathBuf,
@@ -816,11 +1391,18 @@ fn generate_dashboard(
 
     // Load entities
     println!("📋 Loading entities from {}...", entities_dir.display());
-    let entities = nomnom::codegen::load_entities(&entities_dir)
+    let mut entities = nomnom::codegen::load_entities(&entities_dir)
         .map_err(|e| format!("Failed to load entities: {}", e))?;
 
     println!("  ✓ Loaded {} entities", entities.len());
 
+    // Query the tables under the names the naming strategy gives them
+    if let Some(nomnom_yaml_path) = find_nomnom_yaml(&entities_dir) {
+        let config = nomnom::codegen::project_config::BuildConfig::from_file(&nomnom_yaml_path)
+            .map_err(|e| format!("Failed to load {}: {}", nomnom_yaml_path.display(), e))?;
+        config.naming.apply(&mut entities);
+    }
+
     // Count persistent entities
     let persistent_count = entities.iter().filter(|e| e.is_persistent(&entities)).count();
     if persistent_count == 0 {
@@ -842,17 +1424,7 @@ fn generate_dashboard(
     println!();
 
     // Parse database type
-    let db_type = match database_str.to_lowercase().as_str() {
-        "postgresql" | "postgres" | "pg" => nomnom::codegen::dashboard::DatabaseType::PostgreSQL,
-        "mysql" => nomnom::codegen::dashboard::DatabaseType::MySQL,
-        "mariadb" => nomnom::codegen::dashboard::DatabaseType::MariaDB,
-        _ => {
-            return Err(format!(
-                "Unsupported database type: '{}'. Supported types: postgresql, mysql, mariadb",
-                database_str
-            ));
-        }
-    };
+    let db_type = nomnom::codegen::DatabaseType::from_str(&database_str)?;
 
     // Parse backend type
     let backend_type = match backend_str.to_lowercase().as_str() {
@@ -938,6 +1510,8 @@ fn generate_ingestion_server(
     database_str: String,
     port: u16,
     server_name: String,
+    verbose: bool,
+    validate_on_ingest: bool,
 ) -> Result<(), String> {
     println!("🚀 Generating Axum ingestion server...\n");
 
@@ -977,17 +1551,7 @@ fn generate_ingestion_server(
     println!();
 
     // Parse database type
-    let db_type = match database_str.to_lowercase().as_str() {
-        "postgresql" | "postgres" | "pg" => nomnom::codegen::ingestion_server::DatabaseType::PostgreSQL,
-        "mysql" => nomnom::codegen::ingestion_server::DatabaseType::MySQL,
-        "mariadb" => nomnom::codegen::ingestion_server::DatabaseType::MariaDB,
-        _ => {
-            return Err(format!(
-                "Unsupported database type: '{}'. Supported types: postgresql, mysql, mariadb",
-                database_str
-            ));
-        }
-    };
+    let db_type = nomnom::codegen::DatabaseType::from_str(&database_str)?;
 
     println!("🗄️  Database type: {}", db_type.as_str());
     println!();
@@ -997,6 +1561,8 @@ fn generate_ingestion_server(
         database_type: db_type,
         port,
         server_name: server_name.clone(),
+        verbose,
+        validate_on_ingest,
     };
 
     // Generate ingestion server
@@ -1037,7 +1603,8 @@ fn generate_worker(
     entities_dir: PathBuf,
     output: PathBuf,
     database_str: String,
-    worker_name: String,
+    options: nomnom::codegen::worker::WorkerConfig,
+    on_entity_error: Option<nomnom::runtime::OnEntityError>,
 ) -> Result<(), String> {
     println!("🚀 Generating NATS worker binary...\n");
 
@@ -1048,31 +1615,15 @@ fn generate_worker(
 
     // Load entities
     println!("📋 Loading entities from {}...", entities_dir.display());
-    let entities = nomnom::codegen::load_entities(&entities_dir)
+    let mut entities = nomnom::codegen::load_entities(&entities_dir)
         .map_err(|e| format!("Failed to load entities: {}", e))?;
 
     println!("  ✓ Loaded {} entities", entities.len());
 
     // Try to load nomnom.yaml for transforms (optional)
-    // Look for nomnom.yaml in: entities parent dir, current dir, and entities_dir itself
-    let mut nomnom_yaml_candidates = vec![];
-
-    // Try parent of entities dir
-    if let Some(parent) = entities_dir.parent() {
-        nomnom_yaml_candidates.push(parent.join("nomnom.yaml"));
-    }
-
-    // Try current directory
-    nomnom_yaml_candidates.push(PathBuf::from("nomnom.yaml"));
+    let nomnom_yaml = find_nomnom_yaml(&entities_dir);
 
-    // Try entities dir itself
-    nomnom_yaml_candidates.push(entities_dir.join("nomnom.yaml"));
-
-    let nomnom_yaml = nomnom_yaml_candidates.iter()
-        .find(|path| path.exists())
-        .cloned();
-
-    let (transforms, dependencies) = if let Some(nomnom_yaml_path) = nomnom_yaml {
+    let (transforms, dependencies, naming, timestamps, project_on_entity_error) = if let Some(nomnom_yaml_path) = nomnom_yaml {
         println!("📋 Loading transforms from {}...", nomnom_yaml_path.display());
         match nomnom::codegen::project_config::BuildConfig::from_file(&nomnom_yaml_path) {
             Ok(config) => {
@@ -1095,18 +1646,25 @@ fn generate_worker(
                     })
                     .unwrap_or_else(Vec::new);
 
-                (config.transforms.map(|t| t.rust), deps)
+                (config.transforms.map(|t| t.rust), deps, config.naming, config.timestamps, config.on_entity_error)
             }
             Err(e) => {
                 println!("  ⚠ Warning: Failed to load nomnom.yaml: {}", e);
                 println!("  ℹ Continuing without custom transforms...");
-                (None, Vec::new())
+                (None, Vec::new(), Default::default(), false, Default::default())
             }
         }
     } else {
         println!("  ℹ No nomnom.yaml found, generating without custom transforms");
-        (None, Vec::new())
+        (None, Vec::new(), Default::default(), false, Default::default())
     };
+    naming.apply(&mut entities);
+    nomnom::codegen::types::apply_default_timestamps(&mut entities, timestamps);
+    if let Some(ref transforms) = transforms {
+        for entity in &mut entities {
+            entity.qualify_transforms(|name| transforms.contains_key(name));
+        }
+    }
 
     // Count persistent entities
     let persistent_count = entities.iter()
@@ -1132,17 +1690,7 @@ fn generate_worker(
     println!();
 
     // Parse database type
-    let db_type = match database_str.to_lowercase().as_str() {
-        "postgresql" | "postgres" | "pg" => nomnom::codegen::worker::DatabaseType::PostgreSQL,
-        "mysql" => nomnom::codegen::worker::DatabaseType::MySQL,
-        "mariadb" => nomnom::codegen::worker::DatabaseType::MariaDB,
-        _ => {
-            return Err(format!(
-                "Unsupported database type: '{}'. Supported types: postgresql, mysql, mariadb",
-                database_str
-            ));
-        }
-    };
+    let db_type = nomnom::codegen::DatabaseType::from_str(&database_str)?;
 
     println!("🗄️  Database type: {}", db_type.as_str());
     println!();
@@ -1150,8 +1698,9 @@ fn generate_worker(
     // Create worker config
     let config = nomnom::codegen::worker::WorkerConfig {
         database_type: db_type,
-        worker_name: worker_name.clone(),
         additional_dependencies: dependencies,
+        on_entity_error: on_entity_error.unwrap_or(project_on_entity_error),
+        ..options
     };
 
     // Generate worker
@@ -1237,17 +1786,7 @@ fn generate_benthos(
     println!("  ✓ Loaded {} entities", entities.len());
 
     // Parse database type
-    let db_type = match database_str.to_lowercase().as_str() {
-        "postgresql" | "postgres" | "pg" => nomnom::codegen::benthos::DatabaseType::PostgreSQL,
-        "mysql" => nomnom::codegen::benthos::DatabaseType::MySQL,
-        "mariadb" => nomnom::codegen::benthos::DatabaseType::MariaDB,
-        _ => {
-            return Err(format!(
-                "Unsupported database type: '{}'. Supported types: postgresql, mysql, mariadb",
-                database_str
-            ));
-        }
-    };
+    let db_type = nomnom::codegen::DatabaseType::from_str(&database_str)?;
 
     println!("🗄️  Database type: {}", db_type.as_str());
     println!("📦 Output format: {}", format_lower);
@@ -1394,3 +1933,54 @@ fn generate_helm_chart(
 
     Ok(())
 }
+
+fn replay_dlq(
+    entity_type: Option<String>,
+    max: Option<usize>,
+    dlq_stream: Option<String>,
+    database_url: Option<String>,
+) -> Result<(), String> {
+    dotenv::dotenv().ok();
+    let defaults = nomnom::nats::ReplayOptions::default();
+    let options = nomnom::nats::ReplayOptions {
+        dlq_stream: dlq_stream.unwrap_or(defaults.dlq_stream.clone()),
+        entity_type,
+        max_messages: max,
+        ..defaults
+    };
+    let config = nomnom::NatsConfig::default();
+
+    match options.entity_type {
+        Some(ref entity_type) => println!("🔁 Replaying {} messages from stream {}...", entity_type, options.dlq_stream),
+        None => println!("🔁 Replaying messages.dlq.> from stream {}...", options.dlq_stream),
+    }
+    // Statuses are reset message by message, before each is republished
+    let mut conn = match database_url.or_else(|| std::env::var("DATABASE_URL").ok()) {
+        Some(url) => {
+            let database = nomnom::Database::new(&url)
+                .map_err(|e| format!("Failed to connect to database: {}", e))?;
+            Some(database.get_connection()
+                .map_err(|e| format!("Failed to get database connection: {}", e))?)
+        }
+        None => {
+            println!("  ⚠ Warning: no DATABASE_URL, message_status left unchanged");
+            None
+        }
+    };
+
+    let runtime = tokio::runtime::Runtime::new()
+        .map_err(|e| format!("Failed to start async runtime: {}", e))?;
+    let summary = runtime.block_on(async {
+        let client = config.connect_options().await?.connect(&config.url).await?;
+        nomnom::nats::replay_dlq(&async_nats::jetstream::new(client), &options, conn.as_deref_mut()).await
+    }).map_err(|e| format!("Replay failed: {}", e))?;
+    println!("  ✓ Replayed {} message(s)", summary.replayed);
+    if summary.duplicates > 0 {
+        println!("  ✓ Removed {} duplicate copies of replayed messages", summary.duplicates);
+    }
+    if conn.is_some() {
+        println!("  ✓ Reset message_status of {} message(s) to accepted", summary.reset);
+    }
+
+    Ok(())
+}
@@ -26,36 +26,7 @@ pub use docker_compose::generate_docker_compose;
 pub use kubernetes::generate_kubernetes_manifests;
 pub use helm::generate_helm_templates;
 
-/// Database type enumeration
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum DatabaseType {
-    PostgreSQL,
-    MySQL,
-    MariaDB,
-}
-
-impl DatabaseType {
-    pub fn as_str(&self) -> &str {
-        match self {
-            DatabaseType::PostgreSQL => "postgresql",
-            DatabaseType::MySQL => "mysql",
-            DatabaseType::MariaDB => "mariadb",
-        }
-    }
-
-    pub fn from_str(s: &str) -> Result<DatabaseType, String> {
-        match s.to_lowercase().as_str() {
-            "postgresql" | "postgres" | "pg" => Ok(DatabaseType::PostgreSQL),
-            "mysql" => Ok(DatabaseType::MySQL),
-            "mariadb" => Ok(DatabaseType::MariaDB),
-            _ => Err(format!("Unsupported database type: '{}'", s)),
-        }
-    }
-
-    pub fn is_mysql_like(&self) -> bool {
-        matches!(self, DatabaseType::MySQL | DatabaseType::MariaDB)
-    }
-}
+pub use crate::codegen::database_type::DatabaseType;
 
 /// Benthos generation configuration
 #[derive(Debug, Clone)]
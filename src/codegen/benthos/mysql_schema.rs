@@ -24,6 +24,9 @@ pub fn generate_mysql_schema(
 
     // Add metadata columns
     columns.push("  `created_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP".to_string());
+    if entity.persistence.as_ref().is_some_and(|p| p.has_timestamps()) {
+        columns.push("  `updated_at` TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP".to_string());
+    }
 
     // Add indexes
     columns.push("  INDEX `idx_created_at` (`created_at`)".to_string());
@@ -67,9 +67,10 @@ serde_json = "1.0"
 serde_yaml = "0.9"
 once_cell = "1.19"
 thiserror = "1.0"
+regex = "1.10"
 
 # Diesel ORM with connection pooling
-diesel = {{ version = "2.1", features = ["mysql", "sqlite", "r2d2", "chrono"] }}
+diesel = {{ version = "2.1", features = ["mysql", "sqlite", "r2d2", "chrono", "serde_json"] }}
 diesel_migrations = "2.1"
 r2d2 = "0.8"
 chrono = {{ version = "0.4", features = ["serde"] }}
@@ -196,8 +197,9 @@ pub fn generate_lib_rs(has_entities: bool, has_transforms: bool) -> String {
 
     code.push_str("//! Auto-generated library module.\n");
     code.push_str("//!\n");
-    code.push_str("//! This file is auto-generated by nomnom.\n");
-    code.push_str("//! DO NOT EDIT MANUALLY - changes will be overwritten.\n\n");
+    code.push_str("//! This file is auto-generated by nomnom. Only the region between the\n");
+    code.push_str("//! `// BEGIN nomnom generated` and `// END nomnom generated` markers is\n");
+    code.push_str("//! regenerated; code added outside it is preserved.\n\n");
 
     code.push_str("use pyo3::prelude::*;\n");
     code.push_str("use serde::{Serialize, Deserialize};\n\n");
@@ -352,7 +354,7 @@ pub fn write_build_configs<P: AsRef<Path>>(
     let lib_rs = generate_lib_rs(has_entities, has_transforms);
     std::fs::create_dir_all(output_dir.join("src"))
         .map_err(|e| format!("Failed to create src directory: {}", e))?;
-    fs_utils::write_file(output_dir.join("src/lib.rs"), lib_rs)
+    fs_utils::write_managed(output_dir.join("src/lib.rs"), &lib_rs)
         .map_err(|e| format!("Failed to write lib.rs: {}", e))?;
 
     // Generate README.md
@@ -395,6 +397,8 @@ mod tests {
         assert!(lib_rs.contains("pub mod entities"));
         assert!(lib_rs.contains("pub mod transforms"));
         assert!(lib_rs.contains("#[pymodule]"));
+        assert!(lib_rs.contains("code added outside it is preserved"));
+        assert!(!lib_rs.contains("DO NOT EDIT MANUALLY"));
     }
 
     #[test]
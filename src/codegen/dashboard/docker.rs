@@ -91,6 +91,7 @@ pub fn generate_docker_compose(output_dir: &Path, _db_type: DatabaseType) -> Res
     writeln!(output, "      - \"3000:3000\"")?;
     writeln!(output, "    environment:")?;
     writeln!(output, "      - DATABASE_URL=${{DATABASE_URL}}")?;
+    writeln!(output, "      - DATABASE_READ_URL=${{DATABASE_READ_URL:-}}")?;
     writeln!(output, "      - PORT=3000")?;
     writeln!(output, "      - HOST=0.0.0.0")?;
     writeln!(output, "      - RUST_LOG=info")?;
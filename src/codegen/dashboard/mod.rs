@@ -20,6 +20,8 @@
 /// ### Axum Backend (Recommended)
 /// - **No SQL Triggers**: Directly polls entity tables
 /// - **Axum Backend**: Rust server with table polling + WebSocket
+/// - **Throughput**: Per-minute insert rates at `/api/stats/rates`, charted
+///   by the frontend (Axum only)
 /// - **React Frontend**: Same frontend, connects to Axum backend
 ///
 /// ## Database Support
@@ -1,6 +1,6 @@
 /// React frontend generation for real-time dashboard.
 
-use super::utils::generate_entity_display_config;
+use super::utils::{generate_entity_display_config, DashboardConfig};
 use crate::codegen::EntityDef;
 use std::path::Path;
 use std::error::Error;
@@ -46,6 +46,10 @@ pub fn generate_frontend(
     // Generate WebSocket hook
     generate_use_realtime_data_hook(entities, output_dir)?;
 
+    // Generate insert-rate hook and chart
+    generate_use_insert_rates_hook(output_dir)?;
+    generate_throughput_chart_component(output_dir)?;
+
     // Generate EntityCard component
     generate_entity_card_component(output_dir)?;
 
@@ -169,6 +173,7 @@ fn generate_index_html(output_dir: &Path) -> Result<(), Box<dyn Error>> {
 fn generate_entities_ts(entities: &[EntityDef], output_dir: &Path) -> Result<(), Box<dyn Error>> {
     let entities_file = output_dir.join("src/generated/entities.ts");
     let mut output = std::fs::File::create(&entities_file)?;
+    let config = DashboardConfig::default();
 
     use std::io::Write;
 
@@ -182,7 +187,9 @@ fn generate_entities_ts(entities: &[EntityDef], output_dir: &Path) -> Result<(),
     writeln!(output, "  color: string;")?;
     writeln!(output, "  icon: string;")?;
     writeln!(output, "  fields: string[];")?;
+    writeln!(output, "  labels: string[];")?;
     writeln!(output, "  maxRecords: number;")?;
+    writeln!(output, "  pollIntervalMs: number;")?;
     writeln!(output, "}}\n")?;
 
     writeln!(output, "export const ENTITIES: Entity[] = [")?;
@@ -196,7 +203,7 @@ fn generate_entities_ts(entities: &[EntityDef], output_dir: &Path) -> Result<(),
             continue;
         }
 
-        let display_config = generate_entity_display_config(entity, entities);
+        let display_config = generate_entity_display_config(entity, entities, &config);
 
         writeln!(output, "  {{")?;
         writeln!(output, "    name: \"{}\",", display_config.name)?;
@@ -211,7 +218,12 @@ fn generate_entities_ts(entities: &[EntityDef], output_dir: &Path) -> Result<(),
             write!(output, "\"{}\"", field)?;
         }
         writeln!(output, "],")?;
+        writeln!(output, "    labels: [{}],", display_config.field_labels.iter()
+            .map(|l| format!("{:?}", l))
+            .collect::<Vec<_>>()
+            .join(", "))?;
         writeln!(output, "    maxRecords: {},", display_config.max_records)?;
+        writeln!(output, "    pollIntervalMs: {},", display_config.poll_interval_ms)?;
         writeln!(output, "  }},")?;
     }
 
@@ -297,6 +309,9 @@ fn generate_index_css(output_dir: &Path) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 /// Generate src/hooks/useRealtimeData.ts
+///
+/// Connects over WebSocket and falls back to the `/events` SSE stream when
+/// the WebSocket never opens (e.g. a proxy strips the upgrade).
 fn generate_use_realtime_data_hook(_entities: &[EntityDef], output_dir: &Path) -> Result<(), Box<dyn Error>> {
     let hook_file = output_dir.join("src/hooks/useRealtimeData.ts");
     let mut output = std::fs::File::create(&hook_file)?;
@@ -328,7 +343,9 @@ fn generate_use_realtime_data_hook(_entities: &[EntityDef], output_dir: &Path) -
     writeln!(output, "  const port = host === 'localhost' ? '8080' : '32390';")?;
     writeln!(output, "  return `${{protocol}}//${{host}}:${{port}}/ws`;")?;
     writeln!(output, "}};")?;
-    writeln!(output, "const BACKEND_URL = getWebSocketUrl();\n")?;
+    writeln!(output, "const BACKEND_URL = getWebSocketUrl();")?;
+    writeln!(output, "// Server-Sent Events fallback on the same host and port")?;
+    writeln!(output, "const EVENTS_URL = BACKEND_URL.replace(/^ws/, 'http').replace(/\\/ws$/, '/events');\n")?;
 
     writeln!(output, "export function useRealtimeData(): RealtimeData {{")?;
     writeln!(output, "  const [records, setRecords] = useState<Map<string, EntityRecord[]>>(new Map());")?;
@@ -344,18 +361,10 @@ fn generate_use_realtime_data_hook(_entities: &[EntityDef], output_dir: &Path) -
     writeln!(output, "    }});")?;
     writeln!(output, "    setRecords(initialRecords);\n")?;
 
-    writeln!(output, "    // Connect to WebSocket")?;
-    writeln!(output, "    const ws = new WebSocket(BACKEND_URL);\n")?;
-
-    writeln!(output, "    ws.onopen = () => {{")?;
-    writeln!(output, "      console.log('WebSocket connected');")?;
-    writeln!(output, "      setConnected(true);")?;
-    writeln!(output, "      setError(null);")?;
-    writeln!(output, "    }};\n")?;
-
-    writeln!(output, "    ws.onmessage = (event) => {{")?;
+    writeln!(output, "    // Handle one event; WebSocket and SSE carry the same JSON payload")?;
+    writeln!(output, "    const handleMessage = (raw: string) => {{")?;
     writeln!(output, "      try {{")?;
-    writeln!(output, "        const message = JSON.parse(event.data);")?;
+    writeln!(output, "        const message = JSON.parse(raw);")?;
     writeln!(output, "        const {{ entity, data, timestamp }} = message;\n")?;
 
     writeln!(output, "        // Add new record to the appropriate entity")?;
@@ -375,26 +384,68 @@ fn generate_use_realtime_data_hook(_entities: &[EntityDef], output_dir: &Path) -
     writeln!(output, "          return newRecords;")?;
     writeln!(output, "        }});")?;
     writeln!(output, "      }} catch (err) {{")?;
-    writeln!(output, "        console.error('Failed to parse WebSocket message:', err);")?;
+    writeln!(output, "        console.error('Failed to parse event:', err);")?;
     writeln!(output, "      }}")?;
     writeln!(output, "    }};\n")?;
 
+    writeln!(output, "    let closing = false;")?;
+    writeln!(output, "    let eventSource: EventSource | null = null;\n")?;
+
+    writeln!(output, "    // Fall back to Server-Sent Events when WebSockets are unavailable")?;
+    writeln!(output, "    const connectEventSource = () => {{")?;
+    writeln!(output, "      console.log('WebSocket unavailable, falling back to Server-Sent Events');")?;
+    writeln!(output, "      const source = new EventSource(EVENTS_URL);")?;
+    writeln!(output, "      source.onopen = () => {{")?;
+    writeln!(output, "        // The stream replays initial records on every (re)connect")?;
+    writeln!(output, "        setRecords(new Map(initialRecords));")?;
+    writeln!(output, "        setConnected(true);")?;
+    writeln!(output, "        setError(null);")?;
+    writeln!(output, "      }};")?;
+    writeln!(output, "      source.onmessage = (event) => handleMessage(event.data);")?;
+    writeln!(output, "      source.onerror = () => {{")?;
+    writeln!(output, "        // EventSource reconnects by itself")?;
+    writeln!(output, "        setError('Event stream connection error');")?;
+    writeln!(output, "        setConnected(false);")?;
+    writeln!(output, "      }};")?;
+    writeln!(output, "      eventSource = source;")?;
+    writeln!(output, "    }};\n")?;
+
+    writeln!(output, "    // Connect to WebSocket")?;
+    writeln!(output, "    const ws = new WebSocket(BACKEND_URL);")?;
+    writeln!(output, "    let opened = false;\n")?;
+
+    writeln!(output, "    ws.onopen = () => {{")?;
+    writeln!(output, "      console.log('WebSocket connected');")?;
+    writeln!(output, "      opened = true;")?;
+    writeln!(output, "      setConnected(true);")?;
+    writeln!(output, "      setError(null);")?;
+    writeln!(output, "    }};\n")?;
+
+    writeln!(output, "    ws.onmessage = (event) => handleMessage(event.data);\n")?;
+
     writeln!(output, "    ws.onerror = (event) => {{")?;
     writeln!(output, "      console.error('WebSocket error:', event);")?;
-    writeln!(output, "      setError('WebSocket connection error');")?;
-    writeln!(output, "      setConnected(false);")?;
+    writeln!(output, "      if (opened) {{")?;
+    writeln!(output, "        setError('WebSocket connection error');")?;
+    writeln!(output, "        setConnected(false);")?;
+    writeln!(output, "      }}")?;
     writeln!(output, "    }};\n")?;
 
     writeln!(output, "    ws.onclose = () => {{")?;
     writeln!(output, "      console.log('WebSocket disconnected');")?;
     writeln!(output, "      setConnected(false);")?;
+    writeln!(output, "      if (!opened && !closing && !eventSource) {{")?;
+    writeln!(output, "        connectEventSource();")?;
+    writeln!(output, "      }}")?;
     writeln!(output, "    }};\n")?;
 
     writeln!(output, "    wsRef.current = ws;\n")?;
 
     writeln!(output, "    // Cleanup on unmount")?;
     writeln!(output, "    return () => {{")?;
+    writeln!(output, "      closing = true;")?;
     writeln!(output, "      ws.close();")?;
+    writeln!(output, "      eventSource?.close();")?;
     writeln!(output, "    }};")?;
     writeln!(output, "  }}, []);\n")?;
 
@@ -404,6 +455,134 @@ fn generate_use_realtime_data_hook(_entities: &[EntityDef], output_dir: &Path) -
     Ok(())
 }
 
+/// Generate src/hooks/useInsertRates.ts
+///
+/// Polls the backend's `/api/stats/rates`; backends without that endpoint
+/// leave the rates empty.
+fn generate_use_insert_rates_hook(output_dir: &Path) -> Result<(), Box<dyn Error>> {
+    let hook_file = output_dir.join("src/hooks/useInsertRates.ts");
+    let mut output = std::fs::File::create(&hook_file)?;
+
+    use std::io::Write;
+
+    writeln!(output, "// Auto-generated hook for per-entity insert rates")?;
+    writeln!(output, "import {{ useState, useEffect }} from 'react';\n")?;
+
+    writeln!(output, "export interface InsertRates {{")?;
+    writeln!(output, "  bucketSecs: number;")?;
+    writeln!(output, "  /** Unix time at which the newest bucket ends */")?;
+    writeln!(output, "  end: number;")?;
+    writeln!(output, "  /** Rows per bucket by entity, oldest first */")?;
+    writeln!(output, "  entities: Record<string, number[]>;")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "const REFRESH_MS = 15000;\n")?;
+
+    writeln!(output, "// Same host and port as the WebSocket endpoint")?;
+    writeln!(output, "const getRatesUrl = () => {{")?;
+    writeln!(output, "  const host = window.location.hostname;")?;
+    writeln!(output, "  const port = host === 'localhost' ? '8080' : '32390';")?;
+    writeln!(output, "  return `${{window.location.protocol}}//${{host}}:${{port}}/api/stats/rates`;")?;
+    writeln!(output, "}};\n")?;
+
+    writeln!(output, "export function useInsertRates(): InsertRates | null {{")?;
+    writeln!(output, "  const [rates, setRates] = useState<InsertRates | null>(null);\n")?;
+
+    writeln!(output, "  useEffect(() => {{")?;
+    writeln!(output, "    let cancelled = false;")?;
+    writeln!(output, "    const load = async () => {{")?;
+    writeln!(output, "      try {{")?;
+    writeln!(output, "        const response = await fetch(getRatesUrl());")?;
+    writeln!(output, "        if (!response.ok) return;")?;
+    writeln!(output, "        const body = await response.json();")?;
+    writeln!(output, "        if (!cancelled) {{")?;
+    writeln!(output, "          setRates({{ bucketSecs: body.bucket_secs, end: body.end, entities: body.entities }});")?;
+    writeln!(output, "        }}")?;
+    writeln!(output, "      }} catch (err) {{")?;
+    writeln!(output, "        console.error('Failed to load insert rates:', err);")?;
+    writeln!(output, "      }}")?;
+    writeln!(output, "    }};\n")?;
+
+    writeln!(output, "    load();")?;
+    writeln!(output, "    const timer = setInterval(load, REFRESH_MS);")?;
+    writeln!(output, "    return () => {{")?;
+    writeln!(output, "      cancelled = true;")?;
+    writeln!(output, "      clearInterval(timer);")?;
+    writeln!(output, "    }};")?;
+    writeln!(output, "  }}, []);\n")?;
+
+    writeln!(output, "  return rates;")?;
+    writeln!(output, "}}")?;
+
+    Ok(())
+}
+
+/// Generate src/components/ThroughputChart.tsx (inline SVG, no chart library)
+fn generate_throughput_chart_component(output_dir: &Path) -> Result<(), Box<dyn Error>> {
+    let component_file = output_dir.join("src/components/ThroughputChart.tsx");
+    let mut output = std::fs::File::create(&component_file)?;
+
+    use std::io::Write;
+
+    writeln!(output, "// Auto-generated insert-rate chart")?;
+    writeln!(output, "import {{ ENTITIES }} from '../generated/entities';")?;
+    writeln!(output, "import {{ useInsertRates }} from '../hooks/useInsertRates';\n")?;
+
+    writeln!(output, "const WIDTH = 240;")?;
+    writeln!(output, "const HEIGHT = 48;\n")?;
+
+    writeln!(output, "function Bars({{ counts, color }}: {{ counts: number[]; color: string }}) {{")?;
+    writeln!(output, "  const peak = Math.max(1, ...counts);")?;
+    writeln!(output, "  const barWidth = WIDTH / Math.max(1, counts.length);")?;
+    writeln!(output, "  return (")?;
+    writeln!(output, "    <svg width={{WIDTH}} height={{HEIGHT}} className=\"bg-gray-50 rounded\">")?;
+    writeln!(output, "      {{counts.map((count, i) => {{")?;
+    writeln!(output, "        const height = (count / peak) * HEIGHT;")?;
+    writeln!(output, "        return (")?;
+    writeln!(output, "          <rect")?;
+    writeln!(output, "            key={{i}}")?;
+    writeln!(output, "            x={{i * barWidth}}")?;
+    writeln!(output, "            y={{HEIGHT - height}}")?;
+    writeln!(output, "            width={{Math.max(1, barWidth - 1)}}")?;
+    writeln!(output, "            height={{height}}")?;
+    writeln!(output, "            fill={{color}}")?;
+    writeln!(output, "          />")?;
+    writeln!(output, "        );")?;
+    writeln!(output, "      }})}}")?;
+    writeln!(output, "    </svg>")?;
+    writeln!(output, "  );")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "export function ThroughputChart() {{")?;
+    writeln!(output, "  const rates = useInsertRates();")?;
+    writeln!(output, "  if (!rates) return null;")?;
+    writeln!(output, "  const perMinute = 60 / rates.bucketSecs;\n")?;
+
+    writeln!(output, "  return (")?;
+    writeln!(output, "    <div className=\"bg-white rounded-lg shadow-lg p-6 mb-6\">")?;
+    writeln!(output, "      <h2 className=\"text-xl font-bold mb-4\">Ingestion throughput (last hour)</h2>")?;
+    writeln!(output, "      <div className=\"grid grid-cols-1 md:grid-cols-2 gap-4\">")?;
+    writeln!(output, "        {{ENTITIES.map(entity => {{")?;
+    writeln!(output, "          const counts = rates.entities[entity.name] ?? [];")?;
+    writeln!(output, "          const latest = counts.length > 0 ? counts[counts.length - 1] * perMinute : 0;")?;
+    writeln!(output, "          return (")?;
+    writeln!(output, "            <div key={{entity.name}} className=\"flex items-center gap-4\">")?;
+    writeln!(output, "              <div className=\"w-40\">")?;
+    writeln!(output, "                <div className=\"font-semibold\">{{entity.icon}} {{entity.name}}</div>")?;
+    writeln!(output, "                <div className=\"text-sm text-gray-500\">{{latest}} rows/min</div>")?;
+    writeln!(output, "              </div>")?;
+    writeln!(output, "              <Bars counts={{counts}} color={{entity.color}} />")?;
+    writeln!(output, "            </div>")?;
+    writeln!(output, "          );")?;
+    writeln!(output, "        }})}}")?;
+    writeln!(output, "      </div>")?;
+    writeln!(output, "    </div>")?;
+    writeln!(output, "  );")?;
+    writeln!(output, "}}")?;
+
+    Ok(())
+}
+
 /// Generate src/components/EntityCard.tsx
 fn generate_entity_card_component(output_dir: &Path) -> Result<(), Box<dyn Error>> {
     let component_file = output_dir.join("src/components/EntityCard.tsx");
@@ -427,7 +606,9 @@ fn generate_entity_card_component(output_dir: &Path) -> Result<(), Box<dyn Error
     writeln!(output, "        <span className=\"text-3xl\">{{entity.icon}}</span>")?;
     writeln!(output, "        <div>")?;
     writeln!(output, "          <h2 className=\"text-2xl font-bold\">{{entity.name}}</h2>")?;
-    writeln!(output, "          <p className=\"text-sm text-gray-500\">Table: {{entity.table}}</p>")?;
+    writeln!(output, "          <p className=\"text-sm text-gray-500\">")?;
+    writeln!(output, "            Table: {{entity.table}} · polled every {{entity.pollIntervalMs / 1000}}s")?;
+    writeln!(output, "          </p>")?;
     writeln!(output, "        </div>")?;
     writeln!(output, "        <div className=\"ml-auto\">")?;
     writeln!(output, "          <span className=\"text-sm font-semibold text-gray-700\">")?;
@@ -443,12 +624,12 @@ fn generate_entity_card_component(output_dir: &Path) -> Result<(), Box<dyn Error
     writeln!(output, "              <th className=\"px-4 py-3 text-left text-xs font-medium text-gray-500 uppercase\">")?;
     writeln!(output, "                Time")?;
     writeln!(output, "              </th>")?;
-    writeln!(output, "              {{entity.fields.map(field => (")?;
+    writeln!(output, "              {{entity.fields.map((field, i) => (")?;
     writeln!(output, "                <th")?;
     writeln!(output, "                  key={{field}}")?;
     writeln!(output, "                  className=\"px-4 py-3 text-left text-xs font-medium text-gray-500 uppercase\"")?;
     writeln!(output, "                >")?;
-    writeln!(output, "                  {{field}}")?;
+    writeln!(output, "                  {{entity.labels[i] ?? field}}")?;
     writeln!(output, "                </th>")?;
     writeln!(output, "              ))}}")?;
     writeln!(output, "            </tr>")?;
@@ -500,7 +681,8 @@ fn generate_dashboard_component(output_dir: &Path) -> Result<(), Box<dyn Error>>
     writeln!(output, "// Auto-generated Dashboard component")?;
     writeln!(output, "import {{ ENTITIES }} from '../generated/entities';")?;
     writeln!(output, "import {{ useRealtimeData }} from '../hooks/useRealtimeData';")?;
-    writeln!(output, "import {{ EntityCard }} from './EntityCard';\n")?;
+    writeln!(output, "import {{ EntityCard }} from './EntityCard';")?;
+    writeln!(output, "import {{ ThroughputChart }} from './ThroughputChart';\n")?;
 
     writeln!(output, "export function Dashboard() {{")?;
     writeln!(output, "  const {{ records, connected, error }} = useRealtimeData();\n")?;
@@ -532,6 +714,8 @@ fn generate_dashboard_component(output_dir: &Path) -> Result<(), Box<dyn Error>>
     writeln!(output, "          </div>")?;
     writeln!(output, "        )}}\n")?;
 
+    writeln!(output, "        <ThroughputChart />\n")?;
+
     writeln!(output, "        {{ENTITIES.map(entity => (")?;
     writeln!(output, "          <EntityCard")?;
     writeln!(output, "            key={{entity.name}}")?;
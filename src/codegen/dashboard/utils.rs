@@ -3,56 +3,12 @@
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
-/// Database type enumeration
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum DatabaseType {
-    PostgreSQL,
-    MySQL,
-    MariaDB,
-}
-
-impl DatabaseType {
-    /// Get the database type as a string
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            DatabaseType::PostgreSQL => "postgresql",
-            DatabaseType::MySQL => "mysql",
-            DatabaseType::MariaDB => "mariadb",
-        }
-    }
-
-    /// Check if this is MySQL or MariaDB (similar syntax)
-    pub fn is_mysql_like(&self) -> bool {
-        matches!(self, DatabaseType::MySQL | DatabaseType::MariaDB)
-    }
-
-    /// Detect database type from DATABASE_URL
-    ///
-    /// # Examples
-    /// ```no_run
-    /// use nomnom::codegen::dashboard::DatabaseType;
-    ///
-    /// let db_type = DatabaseType::from_url("postgres://localhost/mydb");
-    /// assert_eq!(db_type, DatabaseType::PostgreSQL);
-    ///
-    /// let db_type = DatabaseType::from_url("mysql://localhost/mydb");
-    /// assert_eq!(db_type, DatabaseType::MySQL);
-    /// ```
-    pub fn from_url(url: &str) -> DatabaseType {
-        if url.starts_with("postgres://") || url.starts_with("postgresql://") {
-            DatabaseType::PostgreSQL
-        } else if url.starts_with("mysql://") {
-            DatabaseType::MySQL
-        } else {
-            // Default to PostgreSQL for backward compatibility
-            DatabaseType::PostgreSQL
-        }
-    }
-}
+pub use crate::codegen::database_type::DatabaseType;
 
 /// Dashboard configuration
 #[derive(Debug, Clone)]
 pub struct DashboardConfig {
+    /// Poll interval for entities without their own `dashboard.poll_interval_ms`
     pub polling_interval_ms: u32,
     pub max_events_per_poll: u32,
     pub frontend_port: u16,
@@ -78,7 +34,10 @@ pub struct EntityDisplayConfig {
     pub color: String,
     pub icon: String,
     pub display_fields: Vec<String>,
+    /// Column headers, parallel to `display_fields`
+    pub field_labels: Vec<String>,
     pub max_records: usize,
+    pub poll_interval_ms: u32,
 }
 
 /// Generate a consistent color for an entity based on its name hash
@@ -157,31 +116,16 @@ pub fn select_display_fields(
     }
 }
 
-/// Convert PascalCase to snake_case
-pub fn to_snake_case(s: &str) -> String {
-    let mut result = String::new();
-    let mut prev_is_lower = false;
-
-    for (i, ch) in s.chars().enumerate() {
-        if ch.is_uppercase() {
-            if i > 0 && prev_is_lower {
-                result.push('_');
-            }
-            result.push(ch.to_lowercase().next().unwrap());
-            prev_is_lower = false;
-        } else {
-            result.push(ch);
-            prev_is_lower = true;
-        }
-    }
-
-    result
-}
+pub use crate::codegen::utils::to_snake_case;
 
 /// Generate entity display configuration
+///
+/// The entity's `dashboard` section, when present, picks the columns, their
+/// labels and the poll interval; anything it leaves out comes from `config`.
 pub fn generate_entity_display_config(
     entity: &crate::codegen::EntityDef,
     all_entities: &[crate::codegen::EntityDef],
+    config: &DashboardConfig,
 ) -> EntityDisplayConfig {
     let table = if let Some(db_config) = entity.get_database_config(all_entities) {
         db_config.conformant_table.clone()
@@ -189,13 +133,28 @@ pub fn generate_entity_display_config(
         to_snake_case(&entity.name)
     };
 
+    let configured = entity.dashboard.as_ref();
+    let (display_fields, field_labels) = match configured {
+        Some(dashboard) if !dashboard.fields.is_empty() => dashboard.fields.iter()
+            .map(|f| (f.name().to_string(), f.label().to_string()))
+            .unzip(),
+        _ => {
+            let fields = select_display_fields(entity, 5); // First 5 fields
+            (fields.clone(), fields)
+        }
+    };
+
     EntityDisplayConfig {
         name: entity.name.clone(),
         table,
         color: entity_color(&entity.name),
         icon: entity_icon(&entity.name).to_string(),
-        display_fields: select_display_fields(entity, 5), // First 5 fields
+        display_fields,
+        field_labels,
         max_records: 500, // Default cap
+        poll_interval_ms: configured
+            .and_then(|d| d.poll_interval_ms)
+            .unwrap_or(config.polling_interval_ms),
     }
 }
 
@@ -226,6 +185,39 @@ mod tests {
         assert_eq!(to_snake_case("ProductCategory"), "product_category");
     }
 
+    #[test]
+    fn test_entity_dashboard_overrides() {
+        let entity = crate::codegen::parse_entity(r#"
+entity:
+  name: Order
+  source_type: root
+  fields:
+    - name: order_key
+      type: String
+    - name: total_price
+      type: Float
+    - name: comment
+      type: String
+  dashboard:
+    poll_interval_ms: 1000
+    fields:
+      - order_key
+      - {name: total_price, label: Total}
+"#).unwrap();
+        let config = DashboardConfig::default();
+
+        let display = generate_entity_display_config(&entity, std::slice::from_ref(&entity), &config);
+        assert_eq!(display.display_fields, vec!["order_key", "total_price"]);
+        assert_eq!(display.field_labels, vec!["order_key", "Total"]);
+        assert_eq!(display.poll_interval_ms, 1000);
+
+        let plain = crate::codegen::EntityDef { dashboard: None, ..entity };
+        let display = generate_entity_display_config(&plain, std::slice::from_ref(&plain), &config);
+        assert_eq!(display.display_fields, vec!["order_key", "total_price", "comment"]);
+        assert_eq!(display.field_labels, display.display_fields);
+        assert_eq!(display.poll_interval_ms, config.polling_interval_ms);
+    }
+
     #[test]
     fn test_database_type_is_mysql_like() {
         assert!(!DatabaseType::PostgreSQL.is_mysql_like());
@@ -0,0 +1,101 @@
+//! Target database shared by every generator.
+//!
+//! The worker, ingestion server, Benthos and dashboard generators all
+//! re-export this type so names and URL detection stay in sync; SQL syntax
+//! differences live in [`SqlDialect`](crate::codegen::sql_dialect::SqlDialect).
+
+/// Database type enumeration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatabaseType {
+    PostgreSQL,
+    MySQL,
+    MariaDB,
+}
+
+impl DatabaseType {
+    /// Canonical name, as accepted by `--database`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DatabaseType::PostgreSQL => "postgresql",
+            DatabaseType::MySQL => "mysql",
+            DatabaseType::MariaDB => "mariadb",
+        }
+    }
+
+    /// Parse database type from string (case-insensitive)
+    ///
+    /// # Examples
+    /// ```
+    /// use nomnom::codegen::DatabaseType;
+    ///
+    /// assert_eq!(DatabaseType::from_str("pg").unwrap(), DatabaseType::PostgreSQL);
+    /// assert_eq!(DatabaseType::from_str("MySQL").unwrap(), DatabaseType::MySQL);
+    /// assert!(DatabaseType::from_str("oracle").is_err());
+    /// ```
+    pub fn from_str(s: &str) -> Result<DatabaseType, String> {
+        match s.to_lowercase().as_str() {
+            "postgresql" | "postgres" | "pg" => Ok(DatabaseType::PostgreSQL),
+            "mysql" => Ok(DatabaseType::MySQL),
+            "mariadb" => Ok(DatabaseType::MariaDB),
+            _ => Err(format!(
+                "Unsupported database type: '{}'. Supported types: postgresql, mysql, mariadb",
+                s
+            )),
+        }
+    }
+
+    /// Database type named by a DATABASE_URL scheme, if it is a known one
+    pub fn from_url_scheme(url: &str) -> Option<DatabaseType> {
+        let (scheme, _) = url.split_once("://")?;
+        match scheme.to_lowercase().as_str() {
+            "postgres" | "postgresql" => Some(DatabaseType::PostgreSQL),
+            "mysql" => Some(DatabaseType::MySQL),
+            "mariadb" => Some(DatabaseType::MariaDB),
+            _ => None,
+        }
+    }
+
+    /// Detect database type from DATABASE_URL
+    ///
+    /// Unknown schemes default to PostgreSQL for backward compatibility.
+    ///
+    /// # Examples
+    /// ```
+    /// use nomnom::codegen::DatabaseType;
+    ///
+    /// assert_eq!(DatabaseType::from_url("postgres://localhost/mydb"), DatabaseType::PostgreSQL);
+    /// assert_eq!(DatabaseType::from_url("mysql://localhost/mydb"), DatabaseType::MySQL);
+    /// ```
+    pub fn from_url(url: &str) -> DatabaseType {
+        Self::from_url_scheme(url).unwrap_or(DatabaseType::PostgreSQL)
+    }
+
+    /// Check if this is MySQL or MariaDB (similar syntax)
+    pub fn is_mysql_like(&self) -> bool {
+        matches!(self, DatabaseType::MySQL | DatabaseType::MariaDB)
+    }
+
+    /// Cargo feature selecting this database in generated crates
+    pub fn cargo_feature(&self) -> &'static str {
+        match self {
+            DatabaseType::PostgreSQL => "postgres",
+            DatabaseType::MySQL | DatabaseType::MariaDB => "mysql",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_url_and_from_str_agree() {
+        for db_type in [DatabaseType::PostgreSQL, DatabaseType::MySQL, DatabaseType::MariaDB] {
+            let url = format!("{}://localhost/db", db_type.as_str());
+            assert_eq!(DatabaseType::from_url(&url), db_type);
+            assert_eq!(DatabaseType::from_str(db_type.as_str()).unwrap(), db_type);
+        }
+        assert_eq!(DatabaseType::from_url_scheme("sqlite://db"), None);
+        assert_eq!(DatabaseType::from_url("sqlite://db"), DatabaseType::PostgreSQL);
+    }
+}
@@ -4,7 +4,7 @@
 /// and computes topological processing order.
 
 use crate::codegen::EntityDef;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::error::Error;
 
 #[derive(Debug, Clone)]
@@ -16,14 +16,14 @@ pub struct DependencyNode {
 
 #[derive(Debug)]
 pub struct DependencyGraph {
-    pub nodes: HashMap<String, DependencyNode>,
+    pub nodes: BTreeMap<String, DependencyNode>,
     pub levels: Vec<Vec<String>>,
 }
 
 impl DependencyGraph {
     /// Build dependency graph from entity definitions
     pub fn build(entities: &[EntityDef]) -> Result<Self, Box<dyn Error>> {
-        let mut nodes = HashMap::new();
+        let mut nodes = BTreeMap::new();
 
         // First pass: collect all entities and their direct dependencies
         for entity in entities {
@@ -36,57 +36,12 @@ impl DependencyGraph {
 
             // Get dependencies from derivation.source_entities
             if let Some(ref derivation) = entity.derivation {
-                if let Some(ref source_entities) = derivation.source_entities {
-                    match source_entities {
-                        serde_yaml::Value::Mapping(map) => {
-                            for (_key, value) in map {
-                                match value {
-                                    // Simple string format: alias: EntityName
-                                    serde_yaml::Value::String(source_entity_name) => {
-                                        // Only exclude abstract entities
-                                        if let Some(source_entity) = entities.iter().find(|e| &e.name == source_entity_name) {
-                                            if !source_entity.is_abstract {
-                                                depends_on.push(source_entity_name.clone());
-                                            }
-                                        }
-                                    }
-                                    // Detailed object format: alias: {entity: EntityName, ancillary: bool}
-                                    serde_yaml::Value::Mapping(obj) => {
-                                        if let Some(serde_yaml::Value::String(source_entity_name)) =
-                                            obj.get(&serde_yaml::Value::String("entity".to_string())) {
-                                            // Only exclude abstract entities
-                                            if let Some(source_entity) = entities.iter().find(|e| &e.name == source_entity_name) {
-                                                if !source_entity.is_abstract {
-                                                    depends_on.push(source_entity_name.clone());
-                                                }
-                                            }
-                                        }
-                                    }
-                                    _ => {}
-                                }
-                            }
-                        }
-                        serde_yaml::Value::String(s) => {
-                            // Only exclude abstract entities
-                            if let Some(source_entity) = entities.iter().find(|e| &e.name == s) {
-                                if !source_entity.is_abstract {
-                                    depends_on.push(s.clone());
-                                }
-                            }
-                        }
-                        serde_yaml::Value::Sequence(seq) => {
-                            for item in seq {
-                                if let serde_yaml::Value::String(s) = item {
-                                    // Only exclude abstract entities
-                                    if let Some(source_entity) = entities.iter().find(|e| &e.name == s) {
-                                        if !source_entity.is_abstract {
-                                            depends_on.push(s.clone());
-                                        }
-                                    }
-                                }
-                            }
+                for source_entity_name in derivation.source_entities.entity_names() {
+                    // Only exclude abstract entities
+                    if let Some(source_entity) = entities.iter().find(|e| e.name == source_entity_name) {
+                        if !source_entity.is_abstract {
+                            depends_on.push(source_entity_name.to_string());
                         }
-                        _ => {}
                     }
                 }
             }
@@ -110,6 +65,13 @@ impl DependencyGraph {
                 }
             }
 
+            // Ancestors read by name (e.g. a grandparent) are passed in too
+            for ancestor in entity.ancestor_sources(entities) {
+                if !depends_on.contains(&ancestor) {
+                    depends_on.push(ancestor);
+                }
+            }
+
             nodes.insert(
                 entity.name.clone(),
                 DependencyNode {
@@ -137,11 +99,11 @@ impl DependencyGraph {
 
     /// Compute processing levels using topological sort (Kahn's algorithm)
     fn compute_levels(
-        nodes: &HashMap<String, DependencyNode>,
+        nodes: &BTreeMap<String, DependencyNode>,
     ) -> Result<Vec<Vec<String>>, Box<dyn Error>> {
         // Build reverse dependency map (who depends on this entity)
-        let mut reverse_deps: HashMap<String, Vec<String>> = HashMap::new();
-        let mut in_degree: HashMap<String, usize> = HashMap::new();
+        let mut reverse_deps: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut in_degree: BTreeMap<String, usize> = BTreeMap::new();
 
         for (entity_name, node) in nodes {
             // Only count dependencies on entities that are in the graph (non-root, non-abstract)
@@ -167,7 +129,7 @@ impl DependencyGraph {
             .collect();
 
         let mut levels: Vec<Vec<String>> = Vec::new();
-        let mut processed = HashSet::new();
+        let mut processed = BTreeSet::new();
 
         // Process level by level
         while !queue.is_empty() {
@@ -258,7 +220,7 @@ mod tests {
         // A depends on nothing
         // B depends on A
         // C depends on B
-        let mut nodes = HashMap::new();
+        let mut nodes = BTreeMap::new();
 
         nodes.insert(
             "A".to_string(),
@@ -300,7 +262,7 @@ mod tests {
         // Create mock entities:
         // A, B depend on nothing
         // C depends on both A and B
-        let mut nodes = HashMap::new();
+        let mut nodes = BTreeMap::new();
 
         nodes.insert(
             "A".to_string(),
@@ -341,7 +303,7 @@ mod tests {
     #[test]
     fn test_circular_dependency_detection() {
         // Create circular dependency: A -> B -> C -> A
-        let mut nodes = HashMap::new();
+        let mut nodes = BTreeMap::new();
 
         nodes.insert(
             "A".to_string(),
@@ -0,0 +1,297 @@
+//! Entity relationship diagrams for `nomnom generate-diagram`.
+//!
+//! Renders the entity graph as Mermaid or Graphviz DOT. Nodes are entities
+//! in extraction order, styled by [`EntityKind`]; edges run from parent to
+//! child, labeled `repeated` for repeated extraction. Ancestors read by name
+//! in `computed_from` get a dashed `source` edge and base classes a dashed
+//! `extends` edge. [`EntityDiagram`] writes the same diagram as a codegen
+//! plugin.
+
+use crate::codegen::explain::EntityKind;
+use crate::codegen::parser_binary::compute_extraction_order;
+use crate::codegen::plugins::CodegenCallbacks;
+use crate::codegen::EntityDef;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Output syntax of a diagram
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiagramFormat {
+    /// Mermaid flowchart (renders inline on GitHub/GitLab)
+    #[default]
+    Mermaid,
+    /// Graphviz DOT
+    Dot,
+}
+
+impl DiagramFormat {
+    /// Conventional file extension
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DiagramFormat::Mermaid => "mmd",
+            DiagramFormat::Dot => "dot",
+        }
+    }
+}
+
+impl std::str::FromStr for DiagramFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mermaid" | "mmd" => Ok(DiagramFormat::Mermaid),
+            "dot" | "graphviz" => Ok(DiagramFormat::Dot),
+            other => Err(format!("Unknown diagram format '{}' (expected mermaid or dot)", other)),
+        }
+    }
+}
+
+/// Kind of relationship an edge stands for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EdgeKind {
+    Parent,
+    Repeated,
+    Source,
+    Extends,
+}
+
+impl EdgeKind {
+    fn label(&self) -> Option<&'static str> {
+        match self {
+            EdgeKind::Parent => None,
+            EdgeKind::Repeated => Some("repeated"),
+            EdgeKind::Source => Some("source"),
+            EdgeKind::Extends => Some("extends"),
+        }
+    }
+
+    fn dashed(&self) -> bool {
+        matches!(self, EdgeKind::Source | EdgeKind::Extends)
+    }
+}
+
+/// Render the entity graph of a set of entities
+pub fn render_diagram(entities: &[EntityDef], format: DiagramFormat) -> Result<String, String> {
+    let nodes = diagram_nodes(entities)?;
+    let edges = diagram_edges(&nodes, entities);
+
+    let mut out = String::new();
+    match format {
+        DiagramFormat::Mermaid => {
+            out.push_str("flowchart TD\n");
+            for (kind, style) in NODE_STYLES {
+                out.push_str(&format!("    classDef {} {}\n", kind.name(), style.mermaid));
+            }
+            for node in &nodes {
+                out.push_str(&format!(
+                    "    {}[\"{}\"]:::{}\n",
+                    node.name, node.name, EntityKind::of(node, entities).name()
+                ));
+            }
+            for (from, to, kind) in &edges {
+                let arrow = if kind.dashed() { "-.->" } else { "-->" };
+                match kind.label() {
+                    Some(label) => out.push_str(&format!("    {} {}|{}| {}\n", from, arrow, label, to)),
+                    None => out.push_str(&format!("    {} {} {}\n", from, arrow, to)),
+                }
+            }
+        }
+        DiagramFormat::Dot => {
+            out.push_str("digraph entities {\n");
+            out.push_str("    rankdir=TB;\n");
+            out.push_str("    node [shape=box, style=\"rounded,filled\"];\n");
+            for node in &nodes {
+                let kind = EntityKind::of(node, entities);
+                let style = NODE_STYLES.iter()
+                    .find(|(k, _)| *k == kind)
+                    .map(|(_, style)| style.dot)
+                    .unwrap_or_default();
+                out.push_str(&format!("    {} [{}];\n", node.name, style));
+            }
+            for (from, to, kind) in &edges {
+                let mut attrs = Vec::new();
+                if let Some(label) = kind.label() {
+                    attrs.push(format!("label=\"{}\"", label));
+                }
+                if kind.dashed() {
+                    attrs.push("style=dashed".to_string());
+                }
+                if attrs.is_empty() {
+                    out.push_str(&format!("    {} -> {};\n", from, to));
+                } else {
+                    out.push_str(&format!("    {} -> {} [{}];\n", from, to, attrs.join(", ")));
+                }
+            }
+            out.push_str("}\n");
+        }
+    }
+
+    Ok(out)
+}
+
+/// Node styling per entity kind
+struct NodeStyle {
+    mermaid: &'static str,
+    dot: &'static str,
+}
+
+const NODE_STYLES: [(EntityKind, NodeStyle); 4] = [
+    (EntityKind::Persistent, NodeStyle {
+        mermaid: "fill:#cfe2ff,stroke:#1f5fbf",
+        dot: "fillcolor=\"#cfe2ff\", color=\"#1f5fbf\"",
+    }),
+    (EntityKind::Transient, NodeStyle {
+        mermaid: "fill:#f2f2f2,stroke:#808080",
+        dot: "fillcolor=\"#f2f2f2\", color=\"#808080\"",
+    }),
+    (EntityKind::Reference, NodeStyle {
+        mermaid: "fill:#d9f2d0,stroke:#3d8b2f",
+        dot: "fillcolor=\"#d9f2d0\", color=\"#3d8b2f\"",
+    }),
+    (EntityKind::Abstract, NodeStyle {
+        mermaid: "fill:#ffffff,stroke:#808080,stroke-dasharray:4 2",
+        dot: "fillcolor=\"#ffffff\", color=\"#808080\", style=\"rounded,dashed\"",
+    }),
+];
+
+/// Entities in extraction order, then any the extraction doesn't reach
+/// (reference data, base classes) in declaration order
+fn diagram_nodes(entities: &[EntityDef]) -> Result<Vec<EntityDef>, String> {
+    let processable: Vec<&EntityDef> = entities.iter()
+        .filter(|e| EntityKind::of(e, entities) != EntityKind::Reference)
+        .collect();
+
+    let root = processable.iter()
+        .find(|e| e.is_root())
+        .ok_or("No root entity found in entity configurations. Expected at least one entity with type: root")?;
+
+    let mut nodes = compute_extraction_order(&processable, root)?;
+    for entity in entities {
+        if !nodes.iter().any(|n| n.name == entity.name) {
+            nodes.push(entity.clone());
+        }
+    }
+    Ok(nodes)
+}
+
+/// `(from, to, kind)` edges between the diagram's nodes
+fn diagram_edges(nodes: &[EntityDef], all_entities: &[EntityDef]) -> Vec<(String, String, EdgeKind)> {
+    let known = |name: &str| nodes.iter().any(|n| n.name == name);
+    let mut edges = Vec::new();
+
+    for entity in nodes {
+        let repeats_parent = entity.repetition.as_deref() == Some("repeated");
+        for parent in entity.get_parents() {
+            let kind = if repeats_parent || entity.repeating_parent() == Some(parent.as_str()) {
+                EdgeKind::Repeated
+            } else {
+                EdgeKind::Parent
+            };
+            if known(&parent) {
+                edges.push((parent, entity.name.clone(), kind));
+            }
+        }
+        for ancestor in entity.ancestor_sources(all_entities) {
+            edges.push((ancestor, entity.name.clone(), EdgeKind::Source));
+        }
+        if let Some(ref extends) = entity.extends {
+            if known(extends) {
+                edges.push((extends.clone(), entity.name.clone(), EdgeKind::Extends));
+            }
+        }
+    }
+    edges
+}
+
+/// Codegen plugin writing the entity diagram next to the generated code
+pub struct EntityDiagram {
+    pub format: DiagramFormat,
+}
+
+impl CodegenCallbacks for EntityDiagram {
+    fn after_python_bindings(&self, _: &[EntityDef], _: &[EntityDef], _: &Path) {}
+
+    fn output_file(&self) -> Option<PathBuf> {
+        Some(PathBuf::from(format!("entities.{}", self.format.extension())))
+    }
+
+    fn end_output(&self, entities: &[EntityDef], out: &mut dyn Write) -> io::Result<()> {
+        let diagram = render_diagram(entities, self.format).map_err(io::Error::other)?;
+        out.write_all(diagram.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::parse_entity;
+
+    fn entities() -> Vec<EntityDef> {
+        let root = parse_entity(r#"
+entity:
+  name: Hl7Message
+  source_type: root
+  fields:
+    - name: body
+      type: String
+"#).unwrap();
+        let patient = parse_entity(r#"
+entity:
+  name: Patient
+  source_type: derived
+  parent: Hl7Message
+  fields:
+    - name: mrn
+      type: String
+"#).unwrap();
+        let observation = parse_entity(r#"
+entity:
+  name: Observation
+  source_type: derived
+  repeated_for:
+    entity: Patient
+    field: mrn
+    each_known_as: segment
+  fields:
+    - name: message
+      type: String
+      computed_from:
+        transform: copy_field
+        sources:
+          - source: Hl7Message
+            field: body
+"#).unwrap();
+        let facility = parse_entity(r#"
+entity:
+  name: Facility
+  source_type: reference
+  fields: []
+"#).unwrap();
+        vec![observation, facility, patient, root]
+    }
+
+    #[test]
+    fn test_render_mermaid() {
+        let diagram = render_diagram(&entities(), DiagramFormat::Mermaid).unwrap();
+        assert!(diagram.starts_with("flowchart TD\n"));
+        let root_pos = diagram.find("Hl7Message[\"Hl7Message\"]:::transient").unwrap();
+        let patient_pos = diagram.find("Patient[\"Patient\"]:::transient").unwrap();
+        assert!(root_pos < patient_pos);
+        assert!(diagram.contains("Facility[\"Facility\"]:::reference"));
+        assert!(diagram.contains("    Hl7Message --> Patient\n"));
+        assert!(diagram.contains("    Patient -->|repeated| Observation\n"));
+        assert!(diagram.contains("    Hl7Message -.->|source| Observation\n"));
+    }
+
+    #[test]
+    fn test_render_dot() {
+        let diagram = render_diagram(&entities(), DiagramFormat::Dot).unwrap();
+        assert!(diagram.starts_with("digraph entities {\n"));
+        assert!(diagram.contains("    Patient -> Observation [label=\"repeated\"];\n"));
+        assert!(diagram.contains("    Hl7Message -> Observation [label=\"source\", style=dashed];\n"));
+        assert!(diagram.ends_with("}\n"));
+
+        assert_eq!("graphviz".parse::<DiagramFormat>().unwrap(), DiagramFormat::Dot);
+        assert!("svg".parse::<DiagramFormat>().is_err());
+    }
+}
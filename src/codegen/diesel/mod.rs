@@ -7,14 +7,44 @@ mod models;
 mod operations;
 mod pyo3;
 
-pub use schema::generate_schema;
+pub use schema::{generate_schema, generate_schema_for};
 pub use models::generate_models;
 pub use operations::generate_operations;
 pub use pyo3::generate_pyo3_persistence;
+pub(crate) use models::GENERATED_MODEL_DERIVES;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::error::Error;
 
+/// Where [`generate_all_to`] writes each generated file
+///
+/// Relative paths are resolved against the output directory. The generated
+/// code still refers to `crate::schema`, `crate::models` and
+/// `crate::db::operations`, so relocated files must be declared under those
+/// module paths (e.g. with `#[path = "..."]`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DieselOutputPaths {
+    /// Diesel `table!` definitions
+    pub schema: PathBuf,
+    /// Model structs
+    pub models: PathBuf,
+    /// Database operations
+    pub operations: PathBuf,
+    /// PyO3 persistence bindings
+    pub pyo3_persistence: PathBuf,
+}
+
+impl Default for DieselOutputPaths {
+    fn default() -> Self {
+        Self {
+            schema: PathBuf::from("schema.rs"),
+            models: PathBuf::from("models/mod.rs"),
+            operations: PathBuf::from("db/generated_operations.rs"),
+            pyo3_persistence: PathBuf::from("python/generated_persistence.rs"),
+        }
+    }
+}
+
 /// Generate all Diesel code from entity definitions.
 ///
 /// This is a convenience function that generates all Diesel-related code:
@@ -42,9 +72,62 @@ pub fn generate_all(
     output_dir: &Path,
     config_dir: &str,
 ) -> Result<(), Box<dyn Error>> {
-    generate_schema(entities, &output_dir.join("schema.rs"), config_dir)?;
-    generate_models(entities, &output_dir.join("models/mod.rs"), config_dir)?;
-    generate_operations(entities, &output_dir.join("db/generated_operations.rs"), config_dir)?;
-    generate_pyo3_persistence(entities, &output_dir.join("python/generated_persistence.rs"), config_dir)?;
+    generate_all_to(entities, output_dir, config_dir, &DieselOutputPaths::default())
+}
+
+/// Generate all Diesel code like [`generate_all`], writing each file to its
+/// entry in `paths`
+///
+/// # Example
+///
+/// ```ignore
+/// use nomnom::codegen::diesel::{self, DieselOutputPaths};
+///
+/// let paths = DieselOutputPaths {
+///     schema: "generated/schema.rs".into(),
+///     ..Default::default()
+/// };
+/// diesel::generate_all_to(&entities, Path::new("src"), "../config/entities", &paths)?;
+/// ```
+pub fn generate_all_to(
+    entities: &[crate::codegen::EntityDef],
+    output_dir: &Path,
+    config_dir: &str,
+    paths: &DieselOutputPaths,
+) -> Result<(), Box<dyn Error>> {
+    generate_schema(entities, &output_dir.join(&paths.schema), config_dir)?;
+    generate_models(entities, &output_dir.join(&paths.models), config_dir)?;
+    generate_operations(entities, &output_dir.join(&paths.operations), config_dir)?;
+    generate_pyo3_persistence(entities, &output_dir.join(&paths.pyo3_persistence), config_dir)?;
     Ok(())
 }
+
+/// Table name for an entity, preferring the loaded definition (which has the
+/// naming strategy applied) over the raw `conformant_table` read from YAML
+fn conformant_table<'a>(entity: &'a crate::codegen::EntityDef, yaml_table: &'a str) -> &'a str {
+    entity.persistence.as_ref()
+        .and_then(|p| p.database.as_ref())
+        .or(entity.database.as_ref())
+        .map_or(yaml_table, |db| db.conformant_table.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_all_to_custom_paths() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let paths = DieselOutputPaths {
+            schema: PathBuf::from("generated/schema.rs"),
+            operations: PathBuf::from("generated/ops.rs"),
+            ..Default::default()
+        };
+        generate_all_to(&[], temp_dir.path(), "config/entities", &paths).unwrap();
+
+        for path in ["generated/schema.rs", "models/mod.rs", "generated/ops.rs", "python/generated_persistence.rs"] {
+            assert!(temp_dir.path().join(path).is_file(), "{} not generated", path);
+        }
+        assert!(!temp_dir.path().join("schema.rs").exists());
+    }
+}
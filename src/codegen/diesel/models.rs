@@ -4,6 +4,7 @@ use std::io::Write;
 use std::path::Path;
 use std::error::Error;
 use crate::codegen::fs_utils;
+use crate::codegen::types::{SoftDeleteConfig, CREATED_AT_COLUMN, UPDATED_AT_COLUMN};
 use serde::Deserialize;
 
 #[derive(Deserialize)]
@@ -30,6 +31,8 @@ struct PersistenceConfig {
     primary_key: Option<PrimaryKeyConfig>,
     #[serde(default)]
     field_overrides: Vec<FieldOverride>,
+    #[serde(default)]
+    soft_delete: Option<SoftDeleteConfig>,
 }
 
 #[derive(Deserialize)]
@@ -52,6 +55,24 @@ struct EntityWrapper {
     entity: EntityYaml,
 }
 
+/// Derives every generated model struct may carry; an entity's `derives:`
+/// are added to them and must not repeat them
+pub(crate) const GENERATED_MODEL_DERIVES: &[&str] = &[
+    "Debug", "Clone", "Queryable", "Insertable", "Serialize", "Deserialize",
+];
+
+/// `#[derive(...)]` attribute of an entity's model struct, with its extra
+/// derives after the generated ones
+///
+/// `New*` insertable structs keep the generated derives only: they hold a
+/// subset of the columns, so derives such as `utoipa::ToSchema` don't apply.
+fn model_derive_attr(entity: &crate::codegen::EntityDef) -> String {
+    let derives: Vec<&str> = ["Debug", "Clone", "Queryable", "Serialize", "Deserialize"].into_iter()
+        .chain(entity.derives.iter().map(String::as_str))
+        .collect();
+    format!("#[derive({})]", derives.join(", "))
+}
+
 /// Generate Diesel model structs from entity field definitions.
 ///
 /// Creates model structs annotated with Diesel's `#[derive(Queryable, Insertable)]`
@@ -67,12 +88,14 @@ pub fn generate_models(
     output_path: &Path,
     config_dir: &str,
 ) -> Result<(), Box<dyn Error>> {
-    let mut output = fs_utils::create_file(output_path)?;
+    // Written as a managed region so hand-written impls in the file survive
+    let mut output: Vec<u8> = Vec::new();
 
     writeln!(output, "//! Diesel models generated from entity YAML configs\n")?;
     writeln!(output, "use diesel::prelude::*;")?;
     writeln!(output, "use serde::{{Serialize, Deserialize}};")?;
-    writeln!(output, "use bigdecimal::{{BigDecimal, FromPrimitive}};")?;
+    writeln!(output, "use bigdecimal::BigDecimal;")?;
+    writeln!(output, "use std::str::FromStr;")?;
     writeln!(output, "use crate::schema::*;\n")?;
 
     // For each entity with persistence, generate a model struct
@@ -83,8 +106,8 @@ pub fn generate_models(
                 if let Some(persistence) = yaml.entity.persistence {
                     if let Some(db_config) = persistence.database {
                         // Generate main struct (Queryable only - for reading from DB)
-                        writeln!(output, "#[derive(Debug, Clone, Queryable, Serialize, Deserialize)]")?;
-                        writeln!(output, "#[diesel(table_name = {})]", db_config.conformant_table)?;
+                        writeln!(output, "{}", model_derive_attr(entity))?;
+                        writeln!(output, "#[diesel(table_name = {})]", super::conformant_table(entity, &db_config.conformant_table))?;
                         writeln!(output, "pub struct {} {{", entity.name)?;
 
                         // If primary_key section exists, output it first
@@ -105,9 +128,10 @@ pub fn generate_models(
                             let rust_type = match field.field_type.as_str() {
                                 "String" => "String",
                                 "Integer" => "i32",
-                                "Float" => "BigDecimal",
+                                "Float" | "Decimal" => "BigDecimal",
                                 "Boolean" => "bool",
                                 "DateTime" => "chrono::NaiveDateTime",
+                                "Json" | "Object" => "serde_json::Value",
                                 _ => "String",
                             };
 
@@ -120,12 +144,22 @@ pub fn generate_models(
                             writeln!(output, "    pub {}: {},", field.name, final_type)?;
                         }
 
+                        // Database-managed columns follow the fields in the schema and
+                        // are never inserted, so they are absent from New*
+                        if entity.has_timestamps(entities) {
+                            writeln!(output, "    pub {}: chrono::NaiveDateTime,", CREATED_AT_COLUMN)?;
+                            writeln!(output, "    pub {}: chrono::NaiveDateTime,", UPDATED_AT_COLUMN)?;
+                        }
+                        if let Some(ref soft_delete) = persistence.soft_delete {
+                            writeln!(output, "    pub {}: Option<chrono::NaiveDateTime>,", soft_delete.column)?;
+                        }
+
                         writeln!(output, "}}\n")?;
 
                         // Generate New* struct for insertion
                         // This excludes auto-generated primary key fields
                         writeln!(output, "#[derive(Debug, Clone, Insertable)]")?;
-                        writeln!(output, "#[diesel(table_name = {})]", db_config.conformant_table)?;
+                        writeln!(output, "#[diesel(table_name = {})]", super::conformant_table(entity, &db_config.conformant_table))?;
                         writeln!(output, "pub struct New{} {{", entity.name)?;
 
                         // Skip primary key if it's auto-generated
@@ -153,9 +187,10 @@ pub fn generate_models(
                             let rust_type = match field.field_type.as_str() {
                                 "String" => "String",
                                 "Integer" => "i32",
-                                "Float" => "BigDecimal",
+                                "Float" | "Decimal" => "BigDecimal",
                                 "Boolean" => "bool",
                                 "DateTime" => "chrono::NaiveDateTime",
+                                "Json" | "Object" => "serde_json::Value",
                                 _ => "String",
                             };
 
@@ -212,15 +247,10 @@ pub fn generate_models(
                             }
 
                             match field.field_type.as_str() {
-                                "Float" => {
-                                    // Convert f64 to BigDecimal
-                                    if field.nullable {
-                                        writeln!(output, "            {}: core.{}.and_then(BigDecimal::from_f64),",
-                                            field.name, field.name)?;
-                                    } else {
-                                        writeln!(output, "            {}: BigDecimal::from_f64(core.{}).unwrap_or_else(|| BigDecimal::from(0)),",
-                                            field.name, field.name)?;
-                                    }
+                                "Float" | "Decimal" => {
+                                    // Convert to BigDecimal via decimal text
+                                    writeln!(output, "            {}: {},", field.name,
+                                        core_to_model_expr(&field.name, &field.field_type, field.nullable, field.nullable))?;
                                 },
                                 "Integer" => {
                                     // Convert i64 to i32
@@ -264,19 +294,10 @@ pub fn generate_models(
     for entity in entities {
         // Check if this entity extends another entity
         if let Some(ref parent_name) = entity.extends {
-            // Load the derived entity's YAML to get its field list
-            let derived_yaml_path = format!("{}/{}.yaml", config_dir, entity.name.to_lowercase());
-            let derived_field_names: std::collections::HashSet<String> =
-                if let Ok(derived_yaml_content) = std::fs::read_to_string(&derived_yaml_path) {
-                    if let Ok(derived_yaml) = serde_yaml::from_str::<EntityWrapper>(&derived_yaml_content) {
-                        // Collect all field names from the derived entity
-                        entity.fields.iter().map(|f| f.name.clone()).collect()
-                    } else {
-                        std::collections::HashSet::new()
-                    }
-                } else {
-                    std::collections::HashSet::new()
-                };
+            // The derived Core only carries the entity's own fields; inherited ones
+            // are not on it and fall back to defaults below
+            let derived_fields: std::collections::HashMap<&str, &crate::codegen::FieldDef> =
+                entity.fields.iter().map(|f| (f.name.as_str(), f)).collect();
 
             // Load the parent entity's YAML to check if it has persistence
             let parent_yaml_path = format!("{}/{}.yaml", config_dir, parent_name.to_lowercase());
@@ -313,45 +334,11 @@ pub fn generate_models(
                                 }
 
                                 // Check if this field exists in the derived entity
-                                let field_exists = derived_field_names.contains(&field.name);
-
-                                if field_exists {
-                                    // Field exists in derived entity - use it
-                                    match field.field_type.as_str() {
-                                        "Float" => {
-                                            // Convert f64 to BigDecimal
-                                            if field.nullable {
-                                                writeln!(output, "            {}: core.{}.and_then(BigDecimal::from_f64),",
-                                                    field.name, field.name)?;
-                                            } else {
-                                                writeln!(output, "            {}: BigDecimal::from_f64(core.{}).unwrap_or_else(|| BigDecimal::from(0)),",
-                                                    field.name, field.name)?;
-                                            }
-                                        },
-                                        "Integer" => {
-                                            // Convert i64 to i32
-                                            if field.nullable {
-                                                writeln!(output, "            {}: core.{}.map(|v| v as i32),",
-                                                    field.name, field.name)?;
-                                            } else {
-                                                writeln!(output, "            {}: core.{} as i32,",
-                                                    field.name, field.name)?;
-                                            }
-                                        },
-                                        _ => {
-                                            // String, Boolean, DateTime
-                                            if field.nullable {
-                                                // Database field is nullable - direct clone
-                                                writeln!(output, "            {}: core.{}.clone(),",
-                                                    field.name, field.name)?;
-                                            } else {
-                                                // Database field is non-nullable - unwrap Option or clone
-                                                // Assume core field might be Option<String>, unwrap with default
-                                                writeln!(output, "            {}: core.{}.clone().unwrap_or_default(),",
-                                                    field.name, field.name)?;
-                                            }
-                                        }
-                                    }
+                                if let Some(core_field) = derived_fields.get(field.name.as_str()) {
+                                    // Field exists in derived entity - convert from the Core's
+                                    // actual nullability (types are checked at load time)
+                                    writeln!(output, "            {}: {},", field.name,
+                                        core_to_model_expr(&field.name, &field.field_type, field.nullable, core_field.nullable))?;
                                 } else {
                                     // Field doesn't exist in derived entity - use default value
                                     if field.nullable {
@@ -359,7 +346,7 @@ pub fn generate_models(
                                     } else {
                                         match field.field_type.as_str() {
                                             "Integer" => writeln!(output, "            {}: 0,", field.name)?,
-                                            "Float" => writeln!(output, "            {}: BigDecimal::from(0),", field.name)?,
+                                            "Float" | "Decimal" => writeln!(output, "            {}: BigDecimal::from(0),", field.name)?,
                                             "Boolean" => writeln!(output, "            {}: false,", field.name)?,
                                             _ => writeln!(output, "            {}: String::new(),", field.name)?,
                                         }
@@ -377,6 +364,86 @@ pub fn generate_models(
         }
     }
 
+    fs_utils::write_managed(output_path, &String::from_utf8(output)?)?;
+
     println!("cargo:rerun-if-changed={}", output_path.display());
     Ok(())
 }
+
+/// Expression converting `core.<field>` into the model's column value
+///
+/// `column_nullable` is the model column's nullability, `core_nullable` the
+/// Core struct field's; mismatches are bridged with `Some(..)` or a default.
+/// Numeric columns are built from decimal text (the f64's shortest
+/// round-trip form, or a Decimal field's string as-is) so values keep the
+/// digits they were written with instead of the f64's binary expansion.
+fn core_to_model_expr(field: &str, column_type: &str, column_nullable: bool, core_nullable: bool) -> String {
+    match (column_type, column_nullable, core_nullable) {
+        // f64 -> BigDecimal
+        ("Float", true, true) => format!("core.{}.and_then(|v| BigDecimal::from_str(&v.to_string()).ok())", field),
+        ("Float", true, false) => format!("BigDecimal::from_str(&core.{}.to_string()).ok()", field),
+        ("Float", false, true) => format!(
+            "core.{}.and_then(|v| BigDecimal::from_str(&v.to_string()).ok()).unwrap_or_else(|| BigDecimal::from(0))",
+            field
+        ),
+        ("Float", false, false) => format!(
+            "BigDecimal::from_str(&core.{}.to_string()).unwrap_or_else(|_| BigDecimal::from(0))",
+            field
+        ),
+        // decimal text -> BigDecimal
+        ("Decimal", true, true) => format!("core.{}.as_deref().and_then(|v| BigDecimal::from_str(v).ok())", field),
+        ("Decimal", true, false) => format!("BigDecimal::from_str(&core.{}).ok()", field),
+        ("Decimal", false, true) => format!(
+            "core.{}.as_deref().and_then(|v| BigDecimal::from_str(v).ok()).unwrap_or_else(|| BigDecimal::from(0))",
+            field
+        ),
+        ("Decimal", false, false) => format!(
+            "BigDecimal::from_str(&core.{}).unwrap_or_else(|_| BigDecimal::from(0))",
+            field
+        ),
+        // i64 -> i32
+        ("Integer", true, true) => format!("core.{}.map(|v| v as i32)", field),
+        ("Integer", true, false) => format!("Some(core.{} as i32)", field),
+        ("Integer", false, true) => format!("core.{}.map(|v| v as i32).unwrap_or_default()", field),
+        ("Integer", false, false) => format!("core.{} as i32", field),
+        // String, Boolean, DateTime
+        (_, true, true) | (_, false, false) => format!("core.{}.clone()", field),
+        (_, true, false) => format!("Some(core.{}.clone())", field),
+        (_, false, true) => format!("core.{}.clone().unwrap_or_default()", field),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::EntityDef;
+
+    #[test]
+    fn test_extra_derives_only_on_model_struct() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("order.yaml"), r#"
+entity:
+  persistence:
+    database:
+      conformant_table: orders
+      conformant_id_column: id
+    field_overrides:
+      - name: total
+        type: Integer
+"#).unwrap();
+
+        let entity = EntityDef {
+            name: "Order".to_string(),
+            derives: vec!["PartialEq".to_string(), "utoipa::ToSchema".to_string()],
+            ..Default::default()
+        };
+        let output_path = temp_dir.path().join("models.rs");
+        generate_models(&[entity], &output_path, temp_dir.path().to_str().unwrap()).unwrap();
+        let models = std::fs::read_to_string(output_path).unwrap();
+
+        assert!(models.contains(
+            "#[derive(Debug, Clone, Queryable, Serialize, Deserialize, PartialEq, utoipa::ToSchema)]\n#[diesel(table_name = orders)]\npub struct Order {"
+        ));
+        assert!(models.contains("#[derive(Debug, Clone, Insertable)]\n#[diesel(table_name = orders)]\npub struct NewOrder {"));
+    }
+}
@@ -1,9 +1,12 @@
-//! Diesel get-or-create operations generation from entity YAML configurations.
+//! Diesel get-or-create operations and typed repository generation from
+//! entity YAML configurations.
 
 use std::io::Write;
 use std::path::Path;
 use std::error::Error;
 use crate::codegen::fs_utils;
+use crate::codegen::utils::to_snake_case;
+use crate::codegen::types::{SoftDeleteConfig, UPDATED_AT_COLUMN};
 use serde::Deserialize;
 use std::collections::HashMap;
 
@@ -28,6 +31,8 @@ struct PersistenceConfig {
     primary_key: Option<PrimaryKeyConfig>,
     #[serde(default)]
     field_overrides: Vec<FieldOverride>,
+    #[serde(default)]
+    soft_delete: Option<SoftDeleteConfig>,
 }
 
 #[derive(Deserialize)]
@@ -53,7 +58,10 @@ struct EntityWrapper {
 /// Generate Diesel get-or-create database operations.
 ///
 /// Creates database operation functions that implement idempotent get-or-create
-/// logic based on entity unicity fields.
+/// logic based on entity unicity fields, plus a `<entity>_repository` module
+/// per entity with typed `find_by_unicity`, `list`, `count` and
+/// `delete_by_unicity` queries. With `persistence.soft_delete`, queries skip
+/// soft-deleted rows and `delete_by_unicity` sets the deletion timestamp.
 ///
 /// # Arguments
 ///
@@ -92,9 +100,9 @@ pub fn generate_operations(
         if let Ok(yaml_content) = std::fs::read_to_string(&yaml_path) {
             if let Ok(yaml) = serde_yaml::from_str::<EntityWrapper>(&yaml_content) {
                 if let Some(persistence) = yaml.entity.persistence {
-                    if let Some(db_config) = persistence.database {
+                    if let Some(ref db_config) = persistence.database {
                         let entity_name = &entity.name;
-                        let table_name = &db_config.conformant_table;
+                        let table_name = super::conformant_table(entity, &db_config.conformant_table);
 
                         writeln!(output, "// ============================================================================")?;
                         writeln!(output, "// {} - get_or_create implementation", entity_name)?;
@@ -126,27 +134,56 @@ pub fn generate_operations(
                                 .map(|f| (f.name.clone(), f.nullable))
                                 .collect();
 
+                            let write_unicity_filters = |output: &mut std::fs::File, var: &str, indent: &str| -> std::io::Result<()> {
+                                for field_name in &db_config.unicity_fields {
+                                    let is_nullable = field_nullable.get(field_name).copied().unwrap_or(false);
+
+                                    if is_nullable {
+                                        writeln!(output, "{}{} = match &instance.{} {{", indent, var, field_name)?;
+                                        writeln!(output, "{}    Some(val) => {}.filter({}.eq(val)),", indent, var, field_name)?;
+                                        writeln!(output, "{}    None => {}.filter({}.is_null()),", indent, var, field_name)?;
+                                        writeln!(output, "{}}};", indent)?;
+                                    } else {
+                                        writeln!(output, "{}{} = {}.filter({}.eq(&instance.{}));", indent, var, var, field_name, field_name)?;
+                                    }
+                                }
+                                Ok(())
+                            };
+
                             writeln!(output, "        // Check if exists by unicity fields")?;
                             writeln!(output, "        let mut query = {}.into_boxed();", table_name)?;
                             writeln!(output, "")?;
+                            write_unicity_filters(&mut output, "query", "        ")?;
 
-                            for field_name in &db_config.unicity_fields {
-                                let is_nullable = field_nullable.get(field_name).copied().unwrap_or(false);
-
-                                if is_nullable {
-                                    writeln!(output, "        query = match &instance.{} {{", field_name)?;
-                                    writeln!(output, "            Some(val) => query.filter({}.eq(val)),", field_name)?;
-                                    writeln!(output, "            None => query.filter({}.is_null()),", field_name)?;
-                                    writeln!(output, "        }};")?;
-                                } else {
-                                    writeln!(output, "        query = query.filter({}.eq(&instance.{}));", field_name, field_name)?;
+                            // Soft-deleted rows either don't count as existing or get revived
+                            let revived = match persistence.soft_delete {
+                                Some(ref soft_delete) if !soft_delete.revive => {
+                                    writeln!(output, "        query = query.filter({}.is_null());", soft_delete.column)?;
+                                    None
                                 }
-                            }
+                                Some(ref soft_delete) => Some(&soft_delete.column),
+                                None => None,
+                            };
 
                             writeln!(output, "")?;
                             writeln!(output, "        let existing = query.first::<{}>(conn).optional()?;", entity_name)?;
                             writeln!(output, "")?;
                             writeln!(output, "        match existing {{")?;
+                            if let Some(column) = revived {
+                                writeln!(output, "            Some(found) if found.{}.is_some() => {{", column)?;
+                                writeln!(output, "                // Revive the soft-deleted row")?;
+                                writeln!(output, "                let mut revive = diesel::update({})", table_name)?;
+                                if entity.has_timestamps(entities) {
+                                    writeln!(output, "                    .set(({}.eq(None::<chrono::NaiveDateTime>), {}.eq(diesel::dsl::now)))", column, UPDATED_AT_COLUMN)?;
+                                } else {
+                                    writeln!(output, "                    .set({}.eq(None::<chrono::NaiveDateTime>))", column)?;
+                                }
+                                writeln!(output, "                    .into_boxed();")?;
+                                write_unicity_filters(&mut output, "revive", "                ")?;
+                                writeln!(output, "                revive.execute(conn)?;")?;
+                                writeln!(output, "                Ok(Self {{ {}: None, ..found }})", column)?;
+                                writeln!(output, "            }}")?;
+                            }
                             writeln!(output, "            Some(found) => Ok(found),")?;
                             writeln!(output, "            None => {{")?;
                         }
@@ -224,6 +261,8 @@ pub fn generate_operations(
                         writeln!(output, "]")?;
                         writeln!(output, "    }}")?;
                         writeln!(output, "}}\n")?;
+
+                        generate_repository(&mut output, entity_name, table_name, db_config, &persistence, entity.has_timestamps(entities))?;
                     }
                 }
             }
@@ -233,3 +272,113 @@ pub fn generate_operations(
     println!("cargo:rerun-if-changed={}", output_path.display());
     Ok(())
 }
+
+/// Rust parameter type for looking up a column of the given YAML type
+fn param_type(field_type: &str) -> &'static str {
+    match field_type {
+        "String" => "&str",
+        "Integer" => "i32",
+        "Float" | "Decimal" => "&bigdecimal::BigDecimal",
+        "Boolean" => "bool",
+        "DateTime" => "&chrono::NaiveDateTime",
+        "Json" | "Object" => "&serde_json::Value",
+        _ => "&str",
+    }
+}
+
+/// Generate the `<entity>_repository` module of typed queries
+fn generate_repository(
+    output: &mut std::fs::File,
+    entity_name: &str,
+    table_name: &str,
+    db_config: &DatabaseConfig,
+    persistence: &PersistenceConfig,
+    timestamps: bool,
+) -> Result<(), Box<dyn Error>> {
+    // (name, parameter type) for each unicity field; nullable ones are Option
+    let unicity_params: Vec<(&str, String)> = db_config.unicity_fields.iter()
+        .map(|name| {
+            let field = persistence.field_overrides.iter().find(|f| &f.name == name);
+            let ty = param_type(field.map_or("String", |f| f.field_type.as_str()));
+            let ty = if field.is_some_and(|f| f.nullable) { format!("Option<{}>", ty) } else { ty.to_string() };
+            (name.as_str(), ty)
+        })
+        .collect();
+    let params: String = unicity_params.iter()
+        .map(|(name, ty)| format!(", {}: {}", name, ty))
+        .collect();
+
+    let write_unicity_filters = |output: &mut std::fs::File| -> std::io::Result<()> {
+        // Soft-deleted rows are invisible to every query
+        if let Some(ref soft_delete) = persistence.soft_delete {
+            writeln!(output, "        query = query.filter({}::{}.is_null());", table_name, soft_delete.column)?;
+        }
+        for (name, ty) in &unicity_params {
+            if ty.starts_with("Option<") {
+                writeln!(output, "        query = match {} {{", name)?;
+                writeln!(output, "            Some(value) => query.filter({}::{}.eq(value)),", table_name, name)?;
+                writeln!(output, "            None => query.filter({}::{}.is_null()),", table_name, name)?;
+                writeln!(output, "        }};")?;
+            } else {
+                writeln!(output, "        query = query.filter({}::{}.eq({}));", table_name, name, name)?;
+            }
+        }
+        Ok(())
+    };
+
+    writeln!(output, "/// Typed queries for {}", entity_name)?;
+    writeln!(output, "pub mod {}_repository {{", to_snake_case(entity_name))?;
+    writeln!(output, "    use super::*;\n")?;
+
+    if !unicity_params.is_empty() {
+        writeln!(output, "    /// Find the {} with the given unicity fields", entity_name)?;
+        writeln!(output, "    pub fn find_by_unicity(conn: &mut DbConnection{}) -> Result<Option<{}>, DieselError> {{", params, entity_name)?;
+        writeln!(output, "        let mut query = {}::table.into_boxed();", table_name)?;
+        write_unicity_filters(output)?;
+        writeln!(output, "        query.first::<{}>(conn).optional()", entity_name)?;
+        writeln!(output, "    }}\n")?;
+    }
+
+    writeln!(output, "    /// Page through {} rows", entity_name)?;
+    let live_rows = match persistence.soft_delete {
+        Some(ref soft_delete) => format!("{}::table.filter({}::{}.is_null())", table_name, table_name, soft_delete.column),
+        None => format!("{}::table", table_name),
+    };
+
+    writeln!(output, "    pub fn list(conn: &mut DbConnection, limit: i64, offset: i64) -> Result<Vec<{}>, DieselError> {{", entity_name)?;
+    match persistence.primary_key {
+        Some(ref pk) => writeln!(output, "        {}.order({}::{}.asc()).limit(limit).offset(offset).load::<{}>(conn)", live_rows, table_name, pk.name, entity_name)?,
+        None => writeln!(output, "        {}.limit(limit).offset(offset).load::<{}>(conn)", live_rows, entity_name)?,
+    }
+    writeln!(output, "    }}\n")?;
+
+    writeln!(output, "    /// Number of {} rows", entity_name)?;
+    writeln!(output, "    pub fn count(conn: &mut DbConnection) -> Result<i64, DieselError> {{")?;
+    writeln!(output, "        {}.count().get_result(conn)", live_rows)?;
+    writeln!(output, "    }}")?;
+
+    if !unicity_params.is_empty() {
+        writeln!(output)?;
+        writeln!(output, "    /// Delete the {} with the given unicity fields, returning the number of rows deleted", entity_name)?;
+        writeln!(output, "    pub fn delete_by_unicity(conn: &mut DbConnection{}) -> Result<usize, DieselError> {{", params)?;
+        match persistence.soft_delete {
+            Some(ref soft_delete) => {
+                writeln!(output, "        let mut query = diesel::update({}::table)", table_name)?;
+                if timestamps {
+                    writeln!(output, "            .set(({}::{}.eq(diesel::dsl::now.nullable()), {}::{}.eq(diesel::dsl::now)))",
+                        table_name, soft_delete.column, table_name, UPDATED_AT_COLUMN)?;
+                } else {
+                    writeln!(output, "            .set({}::{}.eq(diesel::dsl::now.nullable()))", table_name, soft_delete.column)?;
+                }
+                writeln!(output, "            .into_boxed();")?;
+            }
+            None => writeln!(output, "        let mut query = diesel::delete({}::table).into_boxed();", table_name)?,
+        }
+        write_unicity_filters(output)?;
+        writeln!(output, "        query.execute(conn)")?;
+        writeln!(output, "    }}")?;
+    }
+
+    writeln!(output, "}}\n")?;
+    Ok(())
+}
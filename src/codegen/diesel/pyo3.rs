@@ -6,6 +6,7 @@ use std::error::Error;
 use serde::Deserialize;
 
 use crate::codegen::fs_utils;
+use crate::codegen::types::{SoftDeleteConfig, CREATED_AT_COLUMN, UPDATED_AT_COLUMN};
 
 #[derive(Deserialize)]
 struct DatabaseConfig {
@@ -41,6 +42,8 @@ struct PersistenceConfig {
     field_overrides: Vec<FieldOverride>,
     #[serde(default)]
     primary_key: Option<PrimaryKeyConfig>,
+    #[serde(default)]
+    soft_delete: Option<SoftDeleteConfig>,
 }
 
 #[derive(Deserialize)]
@@ -181,12 +184,24 @@ pub fn generate_pyo3_persistence(
                             }
                         }
 
+                        // Set by the database; the column-based insert ignores them
+                        if entity.has_timestamps(entities) {
+                            writeln!(output, "        {}: chrono::Utc::now().naive_utc(),", CREATED_AT_COLUMN)?;
+                            writeln!(output, "        {}: chrono::Utc::now().naive_utc(),", UPDATED_AT_COLUMN)?;
+                        }
+
+                        // New rows are live
+                        if let Some(ref soft_delete) = persistence.soft_delete {
+                            writeln!(output, "        {}: None,", soft_delete.column)?;
+                        }
+
                         writeln!(output, "    }};\n")?;
 
                         writeln!(output, "    // Get connection and perform get_or_create")?;
                         writeln!(output, "    let mut conn = database.get_connection()?;")?;
-                        writeln!(output, "    let result = {}::get_or_create(&mut conn, &diesel_model)", entity_name)?;
-                        writeln!(output, "        .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(")?;
+                        writeln!(output, "    // Release the GIL while the query blocks")?;
+                        writeln!(output, "    let result = py.allow_threads(|| {}::get_or_create(&mut conn, &diesel_model))", entity_name)?;
+                        writeln!(output, "        .map_err(|e| nomnom::python_bridge::exceptions::DatabaseError::new_err(")?;
                         writeln!(output, "            format!(\"Database error: {{}}\", e)))?;\n")?;
 
                         writeln!(output, "    // Convert back to PyObject (Core class)")?;
@@ -3,7 +3,8 @@
 use std::io::Write;
 use std::path::Path;
 use std::error::Error;
-use crate::codegen::fs_utils;
+use crate::codegen::{fs_utils, DatabaseType};
+use crate::codegen::types::{SoftDeleteConfig, CREATED_AT_COLUMN, UPDATED_AT_COLUMN};
 use serde::Deserialize;
 
 #[derive(Deserialize)]
@@ -28,6 +29,8 @@ struct PersistenceConfig {
     primary_key: Option<PrimaryKeyConfig>,
     #[serde(default)]
     field_overrides: Vec<FieldOverride>,
+    #[serde(default)]
+    soft_delete: Option<SoftDeleteConfig>,
 }
 
 #[derive(Deserialize)]
@@ -68,6 +71,17 @@ pub fn generate_schema(
     entities: &[crate::codegen::EntityDef],
     output_path: &Path,
     config_dir: &str,
+) -> Result<(), Box<dyn Error>> {
+    generate_schema_for(entities, output_path, config_dir, DatabaseType::PostgreSQL)
+}
+
+/// Generate Diesel schema.rs like [`generate_schema`], using the SQL types
+/// of `database_type` (e.g. `Json` instead of `Jsonb` on MySQL/MariaDB)
+pub fn generate_schema_for(
+    entities: &[crate::codegen::EntityDef],
+    output_path: &Path,
+    config_dir: &str,
+    database_type: DatabaseType,
 ) -> Result<(), Box<dyn Error>> {
     let mut output = fs_utils::create_file(output_path)?;
 
@@ -77,16 +91,16 @@ pub fn generate_schema(
     for entity in entities {
         // Read the entity YAML file to get persistence section
         let yaml_path = format!("{}/{}.yaml", config_dir, entity.name.to_lowercase());
-        eprintln!("DEBUG: Generating Diesel schema for entity: {}", entity.name);
+        tracing::debug!(entity = %entity.name, "Generating Diesel schema");
         if let Ok(yaml_content) = std::fs::read_to_string(&yaml_path) {
-            eprintln!("DEBUG: YAML file {} contains persistence keyword", yaml_path);
+            tracing::trace!(path = %yaml_path, "Read entity YAML");
             if let Ok(yaml) = serde_yaml::from_str::<EntityWrapper>(&yaml_content) {
                 if let Some(persistence) = yaml.entity.persistence {
-                    eprintln!("  Has primary_key: {}", persistence.primary_key.is_some());
+                    tracing::trace!(entity = %entity.name, has_primary_key = persistence.primary_key.is_some(), "Entity persistence");
                     if let Some(db_config) = persistence.database {
                         // Generate table! macro for conformant table
                         writeln!(output, "\ndiesel::table! {{")?;
-                        writeln!(output, "    {} (", db_config.conformant_table)?;
+                        writeln!(output, "    {} (", super::conformant_table(entity, &db_config.conformant_table))?;
 
                         // Find primary key field - check primary_key section first, then field_overrides
                         let pk_field = if let Some(ref pk_config) = persistence.primary_key {
@@ -122,9 +136,11 @@ pub fn generate_schema(
                                     }
                                 },
                                 "Integer" => "Integer".to_string(),
-                                "Float" => "Numeric".to_string(),  // Use Numeric for BigDecimal compatibility
+                                "Float" | "Decimal" => "Numeric".to_string(),  // Use Numeric for BigDecimal compatibility
                                 "Boolean" => "Bool".to_string(),
                                 "DateTime" => "Timestamp".to_string(),
+                                "Json" | "Object" if database_type.is_mysql_like() => "Json".to_string(),
+                                "Json" | "Object" => "Jsonb".to_string(),
                                 _ => "Text".to_string(),
                             };
 
@@ -134,9 +150,23 @@ pub fn generate_schema(
                                 diesel_type.clone()
                             };
 
+                            // Keep the field name as the DSL identifier, map it to the real column
+                            let column = entity.column_name(&field.name);
+                            if column != field.name {
+                                writeln!(output, "        #[sql_name = \"{}\"]", column)?;
+                            }
                             writeln!(output, "        {} -> {},", field.name, type_spec)?;
                         }
 
+                        if entity.has_timestamps(entities) {
+                            writeln!(output, "        {} -> Timestamp,", CREATED_AT_COLUMN)?;
+                            writeln!(output, "        {} -> Timestamp,", UPDATED_AT_COLUMN)?;
+                        }
+
+                        if let Some(ref soft_delete) = persistence.soft_delete {
+                            writeln!(output, "        {} -> Nullable<Timestamp>,", soft_delete.column)?;
+                        }
+
                         writeln!(output, "    }}")?;
                         writeln!(output, "}}")?;
                     }
@@ -148,3 +178,45 @@ pub fn generate_schema(
     println!("cargo:rerun-if-changed={}", output_path.display());
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::EntityDef;
+
+    fn schema_for(database_type: DatabaseType) -> String {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("event.yaml"), r#"
+entity:
+  persistence:
+    database:
+      conformant_table: events
+      conformant_id_column: id
+    field_overrides:
+      - name: payload
+        type: Json
+      - name: metadata
+        type: Object
+        nullable: true
+"#).unwrap();
+
+        let entity = EntityDef { name: "Event".to_string(), ..Default::default() };
+        let output_path = temp_dir.path().join("schema.rs");
+        generate_schema_for(&[entity], &output_path, temp_dir.path().to_str().unwrap(), database_type).unwrap();
+        std::fs::read_to_string(output_path).unwrap()
+    }
+
+    #[test]
+    fn test_json_column_type_per_backend() {
+        let postgres = schema_for(DatabaseType::PostgreSQL);
+        assert!(postgres.contains("payload -> Jsonb,"));
+        assert!(postgres.contains("metadata -> Nullable<Jsonb>,"));
+
+        for database_type in [DatabaseType::MySQL, DatabaseType::MariaDB] {
+            let mysql = schema_for(database_type);
+            assert!(mysql.contains("payload -> Json,"));
+            assert!(mysql.contains("metadata -> Nullable<Json>,"));
+            assert!(!mysql.contains("Jsonb"));
+        }
+    }
+}
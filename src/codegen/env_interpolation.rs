@@ -0,0 +1,154 @@
+//! Environment variable interpolation in configuration YAML.
+//!
+//! A document opts in with a top-level `interpolate_env: true`; without it
+//! `$` stays literal. In an opted-in document every string value may use:
+//!
+//! - `${VAR}`: value of `VAR`, an error when it is unset
+//! - `${VAR:-default}`: value of `VAR`, or `default` when unset or empty
+//! - `$$`: a literal `$`
+//!
+//! ```yaml
+//! interpolate_env: true
+//! database:
+//!   url: postgresql://${DB_USER:-nomnom}@${DB_HOST}/${DATABASE_NAME}
+//!   port: ${DB_PORT:-5432}
+//! ```
+//!
+//! A value made of a single `${...}` is re-read as a YAML scalar, so
+//! `port` above is a number. Mapping keys are never interpolated.
+
+use serde_yaml::Value;
+
+/// Top-level key opting a document into interpolation
+pub const OPT_IN_KEY: &str = "interpolate_env";
+
+/// Interpolate a parsed YAML document in place if it opts in
+///
+/// The opt-in key is removed so typed deserialization doesn't see it.
+pub fn interpolate_document(document: &mut Value) -> Result<(), String> {
+    interpolate_document_with(document, &|name| std::env::var(name).ok())
+}
+
+fn interpolate_document_with(
+    document: &mut Value,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<(), String> {
+    let Value::Mapping(mapping) = document else {
+        return Ok(());
+    };
+    match mapping.remove(OPT_IN_KEY) {
+        None | Some(Value::Bool(false)) => Ok(()),
+        Some(Value::Bool(true)) => interpolate_value(document, lookup),
+        Some(other) => Err(format!("'{}' must be true or false, got {:?}", OPT_IN_KEY, other)),
+    }
+}
+
+fn interpolate_value(value: &mut Value, lookup: &dyn Fn(&str) -> Option<String>) -> Result<(), String> {
+    match value {
+        Value::String(text) if text.contains('$') => {
+            let single_reference = text.starts_with("${") && text.find('}') == Some(text.len() - 1);
+            let interpolated = interpolate_str(text, lookup)?;
+            *value = match serde_yaml::from_str::<Value>(&interpolated) {
+                Ok(scalar @ (Value::Bool(_) | Value::Number(_))) if single_reference => scalar,
+                _ => Value::String(interpolated),
+            };
+            Ok(())
+        }
+        Value::Sequence(items) => items.iter_mut().try_for_each(|item| interpolate_value(item, lookup)),
+        Value::Mapping(mapping) => mapping.values_mut().try_for_each(|item| interpolate_value(item, lookup)),
+        Value::Tagged(tagged) => interpolate_value(&mut tagged.value, lookup),
+        _ => Ok(()),
+    }
+}
+
+/// Substitute `${VAR}`, `${VAR:-default}` and `$$` in one string
+fn interpolate_str(text: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(after) = rest.strip_prefix("$$") {
+            output.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let end = after.find('}')
+                .ok_or_else(|| format!("Unterminated '${{' in '{}'", text))?;
+            let (name, default) = match after[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&after[..end], None),
+            };
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(format!("Invalid environment variable name '{}' in '{}'", name, text));
+            }
+            let value = match (lookup(name), default) {
+                (Some(value), Some(default)) if value.is_empty() => default.to_string(),
+                (Some(value), _) => value,
+                (None, Some(default)) => default.to_string(),
+                (None, None) => return Err(format!(
+                    "Environment variable '{}' is not set and has no default (use ${{{}:-default}})",
+                    name, name
+                )),
+            };
+            output.push_str(&value);
+            rest = &after[end + 1..];
+        } else {
+            output.push('$');
+            rest = &rest[1..];
+        }
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "DB_HOST" => Some("db.internal".to_string()),
+            "EMPTY" => Some(String::new()),
+            "DB_PORT" => Some("6543".to_string()),
+            _ => None,
+        }
+    }
+
+    fn interpolate(yaml: &str) -> Result<Value, String> {
+        let mut document: Value = serde_yaml::from_str(yaml).unwrap();
+        interpolate_document_with(&mut document, &lookup)?;
+        Ok(document)
+    }
+
+    #[test]
+    fn test_interpolation_is_opt_in() {
+        let document = interpolate("url: ${DB_HOST}\n").unwrap();
+        assert_eq!(document["url"], Value::from("${DB_HOST}"));
+    }
+
+    #[test]
+    fn test_interpolate_values() {
+        let document = interpolate(r#"
+interpolate_env: true
+url: "postgres://${DB_HOST}/${DATABASE_NAME:-nomnom}"
+port: ${DB_PORT}
+user: ${EMPTY:-admin}
+price: "$$5"
+nested: [{host: "${DB_HOST}"}]
+"#).unwrap();
+        assert!(document.get(OPT_IN_KEY).is_none());
+        assert_eq!(document["url"], Value::from("postgres://db.internal/nomnom"));
+        assert_eq!(document["port"], Value::from(6543));
+        assert_eq!(document["user"], Value::from("admin"));
+        assert_eq!(document["price"], Value::from("$5"));
+        assert_eq!(document["nested"][0]["host"], Value::from("db.internal"));
+    }
+
+    #[test]
+    fn test_unset_variable_without_default_fails() {
+        let err = interpolate("interpolate_env: true\nname: ${DATABASE_NAME}\n").err().unwrap();
+        assert!(err.contains("DATABASE_NAME"), "{}", err);
+        assert!(interpolate("interpolate_env: true\nname: ${DB_HOST\n").is_err());
+    }
+}
@@ -0,0 +1,239 @@
+//! Human-readable extraction plan for `nomnom explain`.
+//!
+//! Resolves the same extraction order the parser binary uses and describes,
+//! per entity, where it comes from, how each field is computed and whether
+//! it is persisted.
+
+use crate::codegen::parser_binary::compute_extraction_order;
+use crate::codegen::types::{ComputedFrom, FieldSource};
+use crate::codegen::EntityDef;
+
+/// How an entity is treated at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityKind {
+    /// Written to the database
+    Persistent,
+    /// Extracted in memory only (feeds other entities)
+    Transient,
+    /// Pre-loaded reference data, never extracted
+    Reference,
+    /// Base class only, never instantiated
+    Abstract,
+}
+
+impl EntityKind {
+    /// Classify an entity against the full entity set
+    pub fn of(entity: &EntityDef, all_entities: &[EntityDef]) -> Self {
+        if entity.source_type.to_lowercase() == "reference" {
+            EntityKind::Reference
+        } else if entity.is_abstract {
+            EntityKind::Abstract
+        } else if entity.is_persistent(all_entities) {
+            EntityKind::Persistent
+        } else {
+            EntityKind::Transient
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EntityKind::Persistent => "persistent",
+            EntityKind::Transient => "transient",
+            EntityKind::Reference => "reference",
+            EntityKind::Abstract => "abstract",
+        }
+    }
+}
+
+/// Render the resolved extraction plan for a set of entities
+pub fn explain_entities(entities: &[EntityDef]) -> Result<String, String> {
+    let processable: Vec<&EntityDef> = entities.iter()
+        .filter(|e| EntityKind::of(e, entities) != EntityKind::Reference)
+        .collect();
+
+    let root = processable.iter()
+        .find(|e| e.is_root())
+        .ok_or("No root entity found in entity configurations. Expected at least one entity with type: root")?;
+
+    let order = compute_extraction_order(&processable, root)?;
+
+    let mut out = String::new();
+    out.push_str(&format!(
+        "Extraction plan ({} entities, root: {})\n",
+        order.len(),
+        root.name
+    ));
+
+    for (i, entity) in order.iter().enumerate() {
+        out.push('\n');
+        out.push_str(&explain_entity(i + 1, entity, entities));
+    }
+
+    let references: Vec<&EntityDef> = entities.iter()
+        .filter(|e| EntityKind::of(e, entities) == EntityKind::Reference)
+        .collect();
+    if !references.is_empty() {
+        out.push_str("\nReference entities (pre-loaded, not extracted):\n");
+        for entity in references {
+            out.push_str(&format!("  - {}\n", entity.name));
+        }
+    }
+
+    Ok(out)
+}
+
+/// Describe one entity of the plan
+fn explain_entity(position: usize, entity: &EntityDef, all_entities: &[EntityDef]) -> String {
+    let kind = EntityKind::of(entity, all_entities);
+    let mut out = String::new();
+
+    let mut tags = vec![kind.name().to_string()];
+    if entity.is_root() {
+        tags.push("root".to_string());
+    }
+    if entity.repeating_parent().is_some() || entity.repetition.as_deref() == Some("repeated") {
+        tags.push("repeated".to_string());
+    }
+    out.push_str(&format!("{}. {} [{}]\n", position, entity.name, tags.join(", ")));
+
+    let parents = entity.get_parents();
+    out.push_str(&format!(
+        "   parents: {}\n",
+        if parents.is_empty() { "(none)".to_string() } else { parents.join(", ") }
+    ));
+    if let Some(ref repeated_for) = entity.repeated_for {
+        out.push_str(&format!(
+            "   repeated for: each {} in {}.{}\n",
+            repeated_for.each_known_as, repeated_for.entity, repeated_for.field
+        ));
+    }
+    if let Some(ref by_count) = entity.repeated_by_count {
+        out.push_str(&format!(
+            "   repeated by count: each {} of {}.{} x {} bytes in {}.{}\n",
+            by_count.each_known_as, by_count.entity, by_count.count_field,
+            by_count.stride, by_count.entity, by_count.field
+        ));
+    }
+    if let Some(ref extends) = entity.extends {
+        out.push_str(&format!("   extends: {}\n", extends));
+    }
+    if kind == EntityKind::Persistent {
+        if let Some(db) = entity.get_database_config(all_entities) {
+            out.push_str(&format!("   table: {}\n", db.conformant_table));
+        }
+    }
+
+    if entity.fields.is_empty() {
+        out.push_str("   fields: (none)\n");
+        return out;
+    }
+    out.push_str("   fields:\n");
+    for field in &entity.fields {
+        let field_type = if field.nullable {
+            format!("{}?", field.field_type)
+        } else {
+            field.field_type.clone()
+        };
+        let origin = match field.computed_from {
+            Some(ref computed) => describe_computed(computed),
+            None => "(input)".to_string(),
+        };
+        out.push_str(&format!("     {}: {} <- {}\n", field.name, field_type, origin));
+    }
+
+    out
+}
+
+/// `transform(Source.field, ...) [key=value, ...]`
+fn describe_computed(computed: &ComputedFrom) -> String {
+    let sources: Vec<String> = computed.sources.iter()
+        .map(|source| match source {
+            FieldSource::Direct(name) => name.clone(),
+            FieldSource::Parent { source, field, .. } => format!("{}.{}", source, field),
+        })
+        .collect();
+
+    let mut out = format!("{}({})", computed.transform, sources.join(", "));
+
+    if let Some(serde_yaml::Value::Mapping(ref args)) = computed.args {
+        let args: Vec<String> = args.iter()
+            .map(|(k, v)| format!("{}={}", yaml_scalar(k), yaml_scalar(v)))
+            .collect();
+        if !args.is_empty() {
+            out.push_str(&format!(" [{}]", args.join(", ")));
+        }
+    }
+
+    out
+}
+
+/// Compact single-line rendering of a YAML value
+fn yaml_scalar(value: &serde_yaml::Value) -> String {
+    match value {
+        serde_yaml::Value::String(s) => s.clone(),
+        other => serde_yaml::to_string(other)
+            .map(|s| s.trim().replace('\n', " "))
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::parse_entity;
+
+    #[test]
+    fn test_explain_orders_and_classifies_entities() {
+        let root = parse_entity(r#"
+entity:
+  name: CsvFile
+  source_type: root
+  fields:
+    - name: raw_line
+      type: String
+"#).unwrap();
+        let user = parse_entity(r#"
+entity:
+  name: User
+  source_type: derived
+  parent: CsvFile
+  fields:
+    - name: user_id
+      type: String
+      nullable: true
+      computed_from:
+        transform: extract_csv_field
+        sources:
+          - source: CsvFile
+            field: raw_line
+        args:
+          column_index: 0
+"#).unwrap();
+        let country = parse_entity(r#"
+entity:
+  name: Country
+  source_type: reference
+  fields: []
+"#).unwrap();
+
+        let plan = explain_entities(&[user, country, root]).unwrap();
+
+        let root_pos = plan.find("1. CsvFile [transient, root]").unwrap();
+        let user_pos = plan.find("2. User [transient]").unwrap();
+        assert!(root_pos < user_pos);
+        assert!(plan.contains("parents: CsvFile"));
+        assert!(plan.contains("user_id: String? <- extract_csv_field(CsvFile.raw_line) [column_index=0]"));
+        assert!(plan.contains("Reference entities (pre-loaded, not extracted):\n  - Country"));
+    }
+
+    #[test]
+    fn test_explain_requires_root() {
+        let orphan = EntityDef {
+            name: "Orphan".to_string(),
+            source_type: "derived".to_string(),
+            parent: Some("Missing".to_string()),
+            ..Default::default()
+        };
+        assert!(explain_entities(&[orphan]).is_err());
+    }
+}
@@ -29,3 +29,87 @@ pub fn create_file<P: AsRef<Path>>(path: P) -> io::Result<fs::File> {
     // Create the file
     fs::File::create(path)
 }
+
+/// First line of a managed region
+pub const MANAGED_BEGIN: &str = "// BEGIN nomnom generated";
+
+/// Last line of a managed region
+pub const MANAGED_END: &str = "// END nomnom generated";
+
+/// Write generated code to a file as a managed region, keeping hand-written
+/// code around it
+///
+/// If the file already has a `// BEGIN nomnom generated` ... `// END nomnom
+/// generated` region, only the region is replaced. A new file is written with
+/// `contents` wrapped in the markers, so code added above or below them
+/// survives the next generation. An existing file without markers is left
+/// alone and reported as an error: nomnom can't tell its code from the user's.
+/// Inner attributes and `//!` docs in the generated code require the region
+/// to stay at the top of the file.
+pub fn write_managed<P: AsRef<Path>>(path: P, contents: &str) -> io::Result<()> {
+    let path = path.as_ref();
+    let existing = match fs::read_to_string(path) {
+        Ok(existing) => Some(existing),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
+    let spliced = splice_managed_region(existing.as_deref(), contents)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))?;
+    write_file(path, spliced)
+}
+
+/// Replace the managed region of `existing` with `generated`
+///
+/// Without an existing file the result is just the marked region. An
+/// existing file with no markers, or an unterminated region, is an error
+/// rather than a guess.
+pub fn splice_managed_region(existing: Option<&str>, generated: &str) -> Result<String, String> {
+    let mut region = format!("{}\n{}", MANAGED_BEGIN, generated);
+    if !region.ends_with('\n') {
+        region.push('\n');
+    }
+    region.push_str(MANAGED_END);
+    region.push('\n');
+
+    let Some(existing) = existing else {
+        return Ok(region);
+    };
+    let Some(begin) = existing.find(MANAGED_BEGIN) else {
+        return Err(format!(
+            "file has no generated region; wrap the code nomnom should manage in '{}' and '{}' lines, or remove the file",
+            MANAGED_BEGIN, MANAGED_END
+        ));
+    };
+    let end = existing[begin..].find(MANAGED_END)
+        .map(|offset| begin + offset + MANAGED_END.len())
+        .ok_or_else(|| format!("'{}' has no matching '{}'", MANAGED_BEGIN, MANAGED_END))?;
+    // The region's own trailing newline replaces the one after the old END
+    let end = if existing[end..].starts_with('\n') { end + 1 } else { end };
+
+    Ok(format!("{}{}{}", &existing[..begin], region, &existing[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_splice_managed_region() {
+        let fresh = splice_managed_region(None, "pub mod entities;\n").unwrap();
+        assert_eq!(fresh, "// BEGIN nomnom generated\npub mod entities;\n// END nomnom generated\n");
+
+        let edited = format!("// user header\n{}\nimpl Extra for Thing {{}}\n", fresh);
+        let regenerated = splice_managed_region(Some(&edited), "pub mod entities;\npub mod transforms;").unwrap();
+        assert_eq!(
+            regenerated,
+            "// user header\n// BEGIN nomnom generated\npub mod entities;\npub mod transforms;\n// END nomnom generated\n\nimpl Extra for Thing {}\n"
+        );
+
+        // Files without markers may hold user code, so they're never overwritten
+        let unmarked = splice_managed_region(Some("pub mod old;\n"), "pub mod entities;\n").unwrap_err();
+        assert!(unmarked.contains("no generated region"), "{}", unmarked);
+
+        let unterminated = "// BEGIN nomnom generated\npub mod entities;\n";
+        assert!(splice_managed_region(Some(unterminated), "").is_err());
+    }
+}
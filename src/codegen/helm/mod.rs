@@ -1,5 +1,5 @@
 use crate::codegen::EntityDef;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -95,7 +95,7 @@ fn generate_chart_yaml(chart_dir: &Path, config: &HelmChartConfig) -> Result<(),
     let template_path = template_dir.join("Chart.yaml");
     let template = fs::read_to_string(template_path)?;
 
-    let mut replacements = HashMap::new();
+    let mut replacements = BTreeMap::new();
     replacements.insert("{{VERSION}}", config.chart_version.as_str());
     replacements.insert("{{APP_VERSION}}", config.app_version.as_str());
 
@@ -199,12 +199,17 @@ fn generate_readme(
         .filter(|e| e.source_type.to_lowercase() == "derived")
         .count();
 
-    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    // Honor SOURCE_DATE_EPOCH so reproducible builds get a stable README
+    let timestamp = std::env::var("SOURCE_DATE_EPOCH").ok()
+        .and_then(|epoch| epoch.parse::<i64>().ok())
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string());
     let entity_count_str = entities.len().to_string();
     let permanent_count_str = permanent_count.to_string();
     let transient_count_str = transient_count.to_string();
 
-    let mut replacements = HashMap::new();
+    let mut replacements = BTreeMap::new();
     replacements.insert("{{VERSION}}", config.chart_version.as_str());
     replacements.insert("{{ENTITY_COUNT}}", &entity_count_str);
     replacements.insert("{{PERMANENT_COUNT}}", &permanent_count_str);
@@ -218,7 +223,7 @@ fn generate_readme(
 }
 
 /// Simple string replacement helper
-fn replace_all(template: &str, replacements: &HashMap<&str, &str>) -> String {
+fn replace_all(template: &str, replacements: &BTreeMap<&str, &str>) -> String {
     let mut result = template.to_string();
     for (key, value) in replacements {
         result = result.replace(key, value);
@@ -23,15 +23,18 @@ pub fn generate_cargo_toml(
         DatabaseType::PostgreSQL => {
             writeln!(output, "default = [\"postgres\"]")?;
             writeln!(output, "postgres = [\"diesel/postgres\"]")?;
-            writeln!(output, "mysql = [\"diesel/mysql\"]\n")?;
+            writeln!(output, "mysql = [\"diesel/mysql\"]")?;
         }
         DatabaseType::MySQL | DatabaseType::MariaDB => {
             writeln!(output, "default = [\"mysql\"]")?;
             writeln!(output, "postgres = [\"diesel/postgres\"]")?;
-            writeln!(output, "mysql = [\"diesel/mysql\"]\n")?;
+            writeln!(output, "mysql = [\"diesel/mysql\"]")?;
         }
     }
 
+    crate::codegen::telemetry::write_otel_feature(&mut output)?;
+    writeln!(output)?;
+
     writeln!(output, "[dependencies]")?;
     writeln!(output, "# Web framework")?;
     writeln!(output, "axum = \"0.7\"")?;
@@ -44,6 +47,11 @@ pub fn generate_cargo_toml(
     writeln!(output, "serde_json = \"1\"")?;
     writeln!(output, "base64 = \"0.21\"\n")?;
 
+    writeln!(output, "# Envelope body compression (NATS_BODY_CODEC)")?;
+    writeln!(output, "flate2 = \"1\"")?;
+    writeln!(output, "zstd = \"0.13\"")?;
+    writeln!(output, "sha2 = \"0.10\"\n")?;
+
     writeln!(output, "# Database")?;
     writeln!(output, "diesel = {{ version = \"2\", features = [\"r2d2\", \"chrono\", \"numeric\", \"uuid\"] }}")?;
     writeln!(output, "r2d2 = \"0.8\"\n")?;
@@ -57,12 +65,13 @@ pub fn generate_cargo_toml(
     writeln!(output, "async-nats = \"0.35\"\n")?;
 
     writeln!(output, "# OpenAPI documentation")?;
-    writeln!(output, "utoipa = {{ version = \"4\", features = [\"axum_extras\", \"chrono\"] }}")?;
+    writeln!(output, "utoipa = {{ version = \"4\", features = [\"axum_extras\", \"chrono\", \"decimal\", \"uuid\"] }}")?;
     writeln!(output, "utoipa-swagger-ui = {{ version = \"6\", features = [\"axum\"] }}\n")?;
 
     writeln!(output, "# Observability")?;
     writeln!(output, "tracing = \"0.1\"")?;
-    writeln!(output, "tracing-subscriber = {{ version = \"0.3\", features = [\"env-filter\"] }}\n")?;
+    writeln!(output, "tracing-subscriber = {{ version = \"0.3\", features = [\"env-filter\", \"json\"] }}\n")?;
+    crate::codegen::telemetry::write_otel_dependencies(&mut output)?;
 
     writeln!(output, "# Environment")?;
     writeln!(output, "dotenv = \"0.15\"\n")?;
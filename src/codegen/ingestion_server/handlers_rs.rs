@@ -5,9 +5,12 @@ use std::path::Path;
 use std::error::Error;
 use std::io::Write;
 
+/// `validate_on_ingest` is the default for the generated `VALIDATE_ON_INGEST`
 pub fn generate_handlers_rs(
     entities: &[EntityDef],
     output_dir: &Path,
+    verbose: bool,
+    validate_on_ingest: bool,
 ) -> Result<(), Box<dyn Error>> {
     let handlers_file = output_dir.join("src/handlers.rs");
     let mut output = std::fs::File::create(&handlers_file)?;
@@ -17,19 +20,22 @@ pub fn generate_handlers_rs(
     writeln!(output, "use axum::{{")?;
     writeln!(output, "    extract::{{State, Path}},")?;
     writeln!(output, "    http::StatusCode,")?;
+    writeln!(output, "    http::HeaderMap,")?;
     writeln!(output, "    response::IntoResponse,")?;
     writeln!(output, "    Json,")?;
     writeln!(output, "}};")?;
     writeln!(output, "use diesel::prelude::*;")?;
     writeln!(output, "use std::sync::Arc;")?;
+    writeln!(output, "use tracing::Instrument;")?;
     writeln!(output, "use uuid::Uuid;")?;
     writeln!(output, "use crate::{{")?;
     writeln!(output, "    database::DbPool,")?;
     writeln!(output, "    models::{{IngestRequest, IngestResponse, BatchResponse, HealthResponse}},")?;
-    writeln!(output, "    parsers::{{MessageParser, ParsedMessage}},")?;
+    writeln!(output, "    parsers::MessageParser,")?;
     writeln!(output, "    error::AppError,")?;
     writeln!(output, "    nats_client::NatsClient,")?;
     writeln!(output, "    message_envelope::{{MessageEnvelope, IngestionResponse, IngestionStatus}},")?;
+    writeln!(output, "    telemetry,")?;
     writeln!(output, "}};\n")?;
 
     // Add database-agnostic UUID type handling
@@ -50,6 +56,31 @@ pub fn generate_handlers_rs(
     writeln!(output, "fn uuid_to_sql_value(uuid: &uuid::Uuid) -> String {{")?;
     writeln!(output, "    uuid.to_string()")?;
     writeln!(output, "}}\n")?;
+    writeln!(output, "/// Header carrying a caller-supplied correlation ID")?;
+    writeln!(output, "const CORRELATION_ID_HEADER: &str = \"x-correlation-id\";\n")?;
+
+    writeln!(output, "/// Correlation ID from the request header, or a fresh one")?;
+    writeln!(output, "///")?;
+    writeln!(output, "/// Caller values are accepted when non-empty, at most 128 characters and")?;
+    writeln!(output, "/// printable ASCII (so they are safe to log and store).")?;
+    writeln!(output, "fn correlation_id_from(headers: &HeaderMap) -> String {{")?;
+    writeln!(output, "    headers.get(CORRELATION_ID_HEADER)")?;
+    writeln!(output, "        .and_then(|v| v.to_str().ok())")?;
+    writeln!(output, "        .map(str::trim)")?;
+    writeln!(output, "        .filter(|v| !v.is_empty() && v.len() <= 128 && v.bytes().all(|b| b.is_ascii_graphic()))")?;
+    writeln!(output, "        .map(String::from)")?;
+    writeln!(output, "        .unwrap_or_else(|| Uuid::new_v4().to_string())")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// Whether to parse messages before publishing them (VALIDATE_ON_INGEST)")?;
+    writeln!(output, "fn validate_on_ingest() -> bool {{")?;
+    writeln!(output, "    static VALIDATE: std::sync::OnceLock<bool> = std::sync::OnceLock::new();")?;
+    writeln!(output, "    *VALIDATE.get_or_init(|| match std::env::var(\"VALIDATE_ON_INGEST\") {{")?;
+    writeln!(output, "        Ok(value) => matches!(value.trim().to_lowercase().as_str(), \"1\" | \"true\" | \"yes\"),")?;
+    writeln!(output, "        Err(_) => {},", validate_on_ingest)?;
+    writeln!(output, "    }})")?;
+    writeln!(output, "}}\n")?;
+
     writeln!(output, "/// Application state shared across handlers")?;
     writeln!(output, "#[derive(Clone)]")?;
     writeln!(output, "pub struct AppState {{")?;
@@ -58,7 +89,7 @@ pub fn generate_handlers_rs(
     writeln!(output, "}}\n")?;
 
     // Generate ingest_message handler
-    generate_ingest_message_handler(&mut output, entities)?;
+    generate_ingest_message_handler(&mut output, entities, verbose)?;
 
     // Generate ingest_batch handler
     generate_ingest_batch_handler(&mut output, entities)?;
@@ -78,9 +109,11 @@ pub fn generate_handlers_rs(
     Ok(())
 }
 
+/// `verbose` adds trace events with the full message body
 fn generate_ingest_message_handler(
     output: &mut std::fs::File,
     entities: &[EntityDef],
+    verbose: bool,
 ) -> Result<(), Box<dyn Error>> {
     writeln!(output, "/// Ingest a single message (async via NATS)")?;
     writeln!(output, "#[utoipa::path(")?;
@@ -94,36 +127,43 @@ fn generate_ingest_message_handler(
     writeln!(output, ")]")?;
     writeln!(output, "pub async fn ingest_message(")?;
     writeln!(output, "    State(state): State<Arc<AppState>>,")?;
+    writeln!(output, "    headers: HeaderMap,")?;
     writeln!(output, "    Json(request): Json<IngestRequest>,")?;
     writeln!(output, ") -> Result<(StatusCode, Json<IngestionResponse>), AppError> {{")?;
-    writeln!(output, "    eprintln!(\"[INGESTION-SERVER] Received message (base64 {{}} bytes)\", request.body_base64.len());\n")?;
+    writeln!(output, "    tracing::debug!(base64_bytes = request.body_base64.len(), \"Received message\");\n")?;
 
     writeln!(output, "    // Decode base64 body")?;
     writeln!(output, "    let body_bytes = base64::decode(&request.body_base64)")?;
     writeln!(output, "        .map_err(|e| {{")?;
-    writeln!(output, "            eprintln!(\"[INGESTION-SERVER] Base64 decode error: {{}}\", e);")?;
+    writeln!(output, "            tracing::warn!(error = %e, \"Base64 decode error\");")?;
     writeln!(output, "            AppError::ValidationError(format!(\"Invalid base64: {{}}\", e))")?;
     writeln!(output, "        }})?;")?;
     writeln!(output, "    let body = String::from_utf8(body_bytes)")?;
     writeln!(output, "        .map_err(|e| {{")?;
-    writeln!(output, "            eprintln!(\"[INGESTION-SERVER] UTF-8 decode error: {{}}\", e);")?;
+    writeln!(output, "            tracing::warn!(error = %e, \"UTF-8 decode error\");")?;
     writeln!(output, "            AppError::ValidationError(format!(\"Invalid UTF-8: {{}}\", e))")?;
     writeln!(output, "        }})?;\n")?;
 
-    writeln!(output, "    eprintln!(\"[INGESTION-SERVER] Decoded message ({{}} bytes)\", body.len());\n")?;
+    writeln!(output, "    tracing::debug!(bytes = body.len(), \"Decoded message\");\n")?;
 
     writeln!(output, "    // Optionally validate JSON format (but don't parse entities yet)")?;
     writeln!(output, "    let json_value: serde_json::Value = serde_json::from_str(&body)")?;
     writeln!(output, "        .map_err(|e| {{")?;
-    writeln!(output, "            eprintln!(\"[INGESTION-SERVER] JSON parse error: {{}}\", e);")?;
-    writeln!(output, "            eprintln!(\"[INGESTION-SERVER] Raw body: {{}}\", body);")?;
+    writeln!(output, "            tracing::warn!(error = %e, \"JSON parse error\");")?;
+    if verbose {
+        writeln!(output, "            tracing::trace!(body = %body, \"Raw body\");")?;
+    }
     writeln!(output, "            AppError::ValidationError(format!(\"Invalid JSON: {{}}\", e))")?;
     writeln!(output, "        }})?;\n")?;
 
-    writeln!(output, "    // Pretty-print the JSON for debugging")?;
-    writeln!(output, "    if let Ok(pretty) = serde_json::to_string_pretty(&json_value) {{")?;
-    writeln!(output, "        eprintln!(\"[INGESTION-SERVER] Parsed JSON:\\n{{}}\", pretty);")?;
-    writeln!(output, "    }}\n")?;
+    if verbose {
+        writeln!(output, "    // Pretty-print the JSON for debugging")?;
+        writeln!(output, "    if tracing::enabled!(tracing::Level::TRACE) {{")?;
+        writeln!(output, "        if let Ok(pretty) = serde_json::to_string_pretty(&json_value) {{")?;
+        writeln!(output, "            tracing::trace!(\"Parsed JSON:\\n{{}}\", pretty);")?;
+        writeln!(output, "        }}")?;
+        writeln!(output, "    }}\n")?;
+    }
 
     writeln!(output, "    // Use entity_type from request or extract from JSON")?;
     writeln!(output, "    let entity_type = request.entity_type.or_else(|| {{")?;
@@ -132,47 +172,60 @@ fn generate_ingest_message_handler(
     writeln!(output, "            .map(String::from)")?;
     writeln!(output, "    }});\n")?;
 
-    writeln!(output, "    if let Some(ref et) = entity_type {{")?;
-    writeln!(output, "        eprintln!(\"[INGESTION-SERVER] Entity type hint: {{}}\", et);")?;
-    writeln!(output, "    }} else {{")?;
-    writeln!(output, "        eprintln!(\"[INGESTION-SERVER] No entity_type hint provided\");")?;
+    writeln!(output, "    tracing::debug!(entity_type = entity_type.as_deref().unwrap_or(\"none\"), \"Entity type hint\");\n")?;
+
+    writeln!(output, "    // Reject messages the worker could not parse")?;
+    writeln!(output, "    if validate_on_ingest() {{")?;
+    writeln!(output, "        MessageParser::parse_json(&body, entity_type.as_deref()).map_err(|e| {{")?;
+    writeln!(output, "            tracing::warn!(error = ?e, \"Message failed validation\");")?;
+    writeln!(output, "            e")?;
+    writeln!(output, "        }})?;")?;
     writeln!(output, "    }}\n")?;
 
     writeln!(output, "    // Create message envelope")?;
-    writeln!(output, "    let envelope = MessageEnvelope::new(body, entity_type.clone());\n")?;
+    writeln!(output, "    let correlation_id = correlation_id_from(&headers);")?;
+    writeln!(output, "    let mut envelope = MessageEnvelope::new(body, entity_type.clone());")?;
+    writeln!(output, "    envelope.correlation_id = Some(correlation_id.clone());")?;
+    writeln!(output, "    let span = tracing::info_span!(")?;
+    writeln!(output, "        \"ingest_message\",")?;
+    writeln!(output, "        message_id = %envelope.message_id,")?;
+    writeln!(output, "        correlation_id = %correlation_id,")?;
+    writeln!(output, "        entity_type = entity_type.as_deref().unwrap_or(\"unknown\"),")?;
+    writeln!(output, "    );")?;
+    writeln!(output, "    envelope.trace_context = span.in_scope(telemetry::current_trace_context);\n")?;
 
     writeln!(output, "    // Publish to NATS JetStream")?;
-    writeln!(output, "    eprintln!(\"[INGESTION-SERVER] Publishing message {{}} to NATS\", envelope.message_id);")?;
-    writeln!(output, "    state.nats.publish_message(&envelope).await")?;
+    writeln!(output, "    tracing::debug!(message_id = %envelope.message_id, \"Publishing message to NATS\");")?;
+    writeln!(output, "    state.nats.publish_message(&envelope).instrument(span).await")?;
     writeln!(output, "        .map_err(|e| {{")?;
-    writeln!(output, "            eprintln!(\"[INGESTION-SERVER] NATS publish failed: {{}}\", e);")?;
+    writeln!(output, "            tracing::error!(error = %e, \"NATS publish failed\");")?;
     writeln!(output, "            AppError::InternalError(format!(\"NATS publish failed: {{}}\", e))")?;
     writeln!(output, "        }})?;\n")?;
 
-    writeln!(output, "    eprintln!(\"[INGESTION-SERVER] Message {{}} successfully queued\", envelope.message_id);\n")?;
-
     writeln!(output, "    // Record message status in database")?;
     writeln!(output, "    let mut conn = state.db_pool.get()?;")?;
     writeln!(output, "    diesel::sql_query(")?;
-    writeln!(output, "        \"INSERT INTO message_status (message_id, entity_type, status, received_at) VALUES (?, ?, ?, ?)\"")?;
+    writeln!(output, "        \"INSERT INTO message_status (message_id, entity_type, status, received_at, correlation_id) VALUES (?, ?, ?, ?, ?)\"")?;
     writeln!(output, "    )")?;
     writeln!(output, "    .bind::<diesel::sql_types::Text, _>(envelope.message_id.to_string())  // MySQL stores UUIDs as strings")?;
     writeln!(output, "    .bind::<diesel::sql_types::Text, _>(entity_type.as_deref().unwrap_or(\"unknown\"))")?;
     writeln!(output, "    .bind::<diesel::sql_types::Text, _>(\"accepted\")")?;
     writeln!(output, "    .bind::<diesel::sql_types::Timestamp, _>(envelope.received_at.naive_utc())")?;
+    writeln!(output, "    .bind::<diesel::sql_types::Text, _>(&correlation_id)")?;
     writeln!(output, "    .execute(&mut conn)")?;
     writeln!(output, "    .map_err(|e| {{")?;
-    writeln!(output, "        eprintln!(\"[INGESTION-SERVER] Failed to insert message_status: {{:?}}\", e);")?;
+    writeln!(output, "        tracing::error!(error = ?e, \"Failed to insert message_status\");")?;
     writeln!(output, "        AppError::Database(e)")?;
     writeln!(output, "    }})?;\n")?;
 
-    writeln!(output, "    tracing::info!(\"Message {{}} queued for processing\", envelope.message_id);\n")?;
+    writeln!(output, "    tracing::info!(message_id = %envelope.message_id, correlation_id = %correlation_id, \"Message queued for processing\");\n")?;
 
     writeln!(output, "    Ok((")?;
     writeln!(output, "        StatusCode::ACCEPTED,")?;
     writeln!(output, "        Json(IngestionResponse {{")?;
     writeln!(output, "            message_id: envelope.message_id.to_string(),")?;
     writeln!(output, "            status: IngestionStatus::Accepted,")?;
+    writeln!(output, "            correlation_id,")?;
     writeln!(output, "            timestamp: envelope.received_at,")?;
     writeln!(output, "        }})")?;
     writeln!(output, "    ))")?;
@@ -189,17 +242,20 @@ fn generate_ingest_batch_handler(
     writeln!(output, "#[utoipa::path(")?;
     writeln!(output, "    post,")?;
     writeln!(output, "    path = \"/ingest/batch\",")?;
-    writeln!(output, "    request_body = String,")?;
+    writeln!(output, "    request_body(content = String, content_type = \"text/plain\", description = \"One JSON message per line\"),")?;
     writeln!(output, "    responses(")?;
     writeln!(output, "        (status = 202, description = \"Batch accepted for processing\", body = BatchResponse)")?;
     writeln!(output, "    )")?;
     writeln!(output, ")]")?;
     writeln!(output, "pub async fn ingest_batch(")?;
     writeln!(output, "    State(state): State<Arc<AppState>>,")?;
+    writeln!(output, "    headers: HeaderMap,")?;
     writeln!(output, "    body: String,")?;
     writeln!(output, ") -> Result<(StatusCode, Json<BatchResponse>), AppError> {{")?;
     writeln!(output, "    let start = std::time::Instant::now();")?;
-    writeln!(output, "    let lines: Vec<&str> = body.lines().collect();\n")?;
+    writeln!(output, "    let lines: Vec<&str> = body.lines().collect();")?;
+    writeln!(output, "    // All messages of one batch request share its correlation ID")?;
+    writeln!(output, "    let correlation_id = correlation_id_from(&headers);\n")?;
 
     writeln!(output, "    let mut processed = 0;")?;
     writeln!(output, "    let mut inserted = 0;")?;
@@ -209,12 +265,28 @@ fn generate_ingest_batch_handler(
     writeln!(output, "    for (line_num, line) in lines.iter().enumerate() {{")?;
     writeln!(output, "        processed += 1;\n")?;
 
-    writeln!(output, "        // Validate JSON format")?;
-    writeln!(output, "        match serde_json::from_str::<serde_json::Value>(line) {{")?;
+    writeln!(output, "        // Validate JSON format, and that the worker can parse it if enabled")?;
+    writeln!(output, "        let checked = serde_json::from_str::<serde_json::Value>(line)")?;
+    writeln!(output, "            .map_err(|e| format!(\"Invalid JSON: {{}}\", e))")?;
+    writeln!(output, "            .and_then(|_| match validate_on_ingest() {{")?;
+    writeln!(output, "                true => MessageParser::parse_json(line, None)")?;
+    writeln!(output, "                    .map(|_| ())")?;
+    writeln!(output, "                    .map_err(|e| format!(\"Validation failed: {{:?}}\", e)),")?;
+    writeln!(output, "                false => Ok(()),")?;
+    writeln!(output, "            }});")?;
+    writeln!(output, "        match checked {{")?;
     writeln!(output, "            Ok(_) => {{")?;
     writeln!(output, "                // Create envelope and publish to NATS")?;
-    writeln!(output, "                let envelope = MessageEnvelope::new(line.to_string(), None);")?;
-    writeln!(output, "                match state.nats.publish_message(&envelope).await {{")?;
+    writeln!(output, "                let mut envelope = MessageEnvelope::new(line.to_string(), None);")?;
+    writeln!(output, "                envelope.correlation_id = Some(correlation_id.clone());")?;
+    writeln!(output, "                let span = tracing::info_span!(")?;
+    writeln!(output, "                    \"ingest_message\",")?;
+    writeln!(output, "                    message_id = %envelope.message_id,")?;
+    writeln!(output, "                    correlation_id = %correlation_id,")?;
+    writeln!(output, "                    line = line_num + 1,")?;
+    writeln!(output, "                );")?;
+    writeln!(output, "                envelope.trace_context = span.in_scope(telemetry::current_trace_context);")?;
+    writeln!(output, "                match state.nats.publish_message(&envelope).instrument(span).await {{")?;
     writeln!(output, "                    Ok(_) => inserted += 1,")?;
     writeln!(output, "                    Err(e) => {{")?;
     writeln!(output, "                        failed += 1;")?;
@@ -224,7 +296,7 @@ fn generate_ingest_batch_handler(
     writeln!(output, "            }}")?;
     writeln!(output, "            Err(e) => {{")?;
     writeln!(output, "                failed += 1;")?;
-    writeln!(output, "                errors.push(format!(\"Line {{}}: Invalid JSON: {{}}\", line_num + 1, e));")?;
+    writeln!(output, "                errors.push(format!(\"Line {{}}: {{}}\", line_num + 1, e));")?;
     writeln!(output, "            }}")?;
     writeln!(output, "        }}")?;
     writeln!(output, "    }}\n")?;
@@ -333,8 +405,10 @@ fn generate_status_check_handler(
     writeln!(output, "#[utoipa::path(")?;
     writeln!(output, "    get,")?;
     writeln!(output, "    path = \"/ingest/status/{{message_id}}\",")?;
+    writeln!(output, "    params((\"message_id\" = String, Path, description = \"Message ID returned at ingestion (UUID)\")),")?;
     writeln!(output, "    responses(")?;
     writeln!(output, "        (status = 200, description = \"Message status retrieved\"),")?;
+    writeln!(output, "        (status = 400, description = \"Invalid UUID\"),")?;
     writeln!(output, "        (status = 404, description = \"Message not found\")")?;
     writeln!(output, "    )")?;
     writeln!(output, ")]")?;
@@ -23,7 +23,6 @@ pub fn generate_main_rs(
     writeln!(output, "}};")?;
     writeln!(output, "use std::net::SocketAddr;")?;
     writeln!(output, "use std::sync::Arc;")?;
-    writeln!(output, "use tower_http::cors::CorsLayer;")?;
     writeln!(output, "use utoipa::OpenApi;")?;
     writeln!(output, "use utoipa_swagger_ui::SwaggerUi;\n")?;
 
@@ -33,15 +32,23 @@ pub fn generate_main_rs(
     writeln!(output, "mod database;")?;
     writeln!(output, "mod error;")?;
     writeln!(output, "mod nats_client;")?;
-    writeln!(output, "mod message_envelope;\n")?;
+    writeln!(output, "mod message_envelope;")?;
+    writeln!(output, "mod telemetry;\n")?;
 
     writeln!(output, "use database::create_pool;")?;
     writeln!(output, "use nats_client::{{NatsClient, NatsConfig}};")?;
     writeln!(output, "use handlers::AppState;\n")?;
 
-    // Generate OpenAPI spec
+    // Generate OpenAPI spec; entity message schemas document what
+    // `body_base64` decodes to
+    let message_schemas: Vec<String> = entities.iter()
+        .filter(|e| e.is_root() && !e.is_abstract && e.source_type.to_lowercase() != "reference")
+        .map(|e| format!("        parsers::{}Message,", e.name))
+        .collect();
+
     writeln!(output, "#[derive(OpenApi)]")?;
     writeln!(output, "#[openapi(")?;
+    writeln!(output, "    info(title = \"{} ingestion API\"),", config.server_name)?;
     writeln!(output, "    paths(")?;
     writeln!(output, "        handlers::ingest_message,")?;
     writeln!(output, "        handlers::ingest_batch,")?;
@@ -50,22 +57,38 @@ pub fn generate_main_rs(
     writeln!(output, "        handlers::check_status,")?;
     writeln!(output, "    ),")?;
     writeln!(output, "    components(schemas(")?;
+    writeln!(output, "        models::IngestRequest,")?;
     writeln!(output, "        models::IngestResponse,")?;
     writeln!(output, "        models::BatchResponse,")?;
     writeln!(output, "        models::HealthResponse,")?;
+    writeln!(output, "        message_envelope::IngestionResponse,")?;
+    writeln!(output, "        message_envelope::IngestionStatus,")?;
+    writeln!(output, "        message_envelope::MessageEnvelope,")?;
+    for schema in &message_schemas {
+        writeln!(output, "{}", schema)?;
+    }
     writeln!(output, "    ))")?;
     writeln!(output, ")]")?;
     writeln!(output, "struct ApiDoc;\n")?;
 
+    write!(output, "{}", crate::codegen::utils::generate_init_tracing_fn())?;
+    write!(output, "{}", crate::codegen::utils::generate_cors_layer_fn())?;
+
     // Main function
     writeln!(output, "#[tokio::main]")?;
     writeln!(output, "async fn main() {{")?;
-    writeln!(output, "    // Initialize tracing")?;
-    writeln!(output, "    tracing_subscriber::fmt::init();\n")?;
-
-    writeln!(output, "    // Load environment variables")?;
+    writeln!(output, "    // Load environment variables (before tracing, so LOG_FORMAT/RUST_LOG can come from .env)")?;
     writeln!(output, "    dotenv::dotenv().ok();\n")?;
 
+    writeln!(output, "    // `--print-openapi` writes the spec to stdout for client generation")?;
+    writeln!(output, "    if std::env::args().any(|arg| arg == \"--print-openapi\") {{")?;
+    writeln!(output, "        println!(\"{{}}\", ApiDoc::openapi().to_pretty_json().expect(\"OpenAPI spec serializes\"));")?;
+    writeln!(output, "        return;")?;
+    writeln!(output, "    }}\n")?;
+
+    writeln!(output, "    // Initialize tracing")?;
+    writeln!(output, "    init_tracing();\n")?;
+
     writeln!(output, "    // Create database pool")?;
     writeln!(output, "    let db_pool = create_pool()")?;
     writeln!(output, "        .expect(\"Failed to create database pool\");\n")?;
@@ -95,7 +118,7 @@ pub fn generate_main_rs(
     writeln!(output, "        .merge(SwaggerUi::new(\"/swagger-ui\")")?;
     writeln!(output, "            .url(\"/api-docs/openapi.json\", ApiDoc::openapi()))")?;
     writeln!(output, "        // Middleware")?;
-    writeln!(output, "        .layer(CorsLayer::permissive())")?;
+    writeln!(output, "        .layer(cors_layer())")?;
     writeln!(output, "        .with_state(state);\n")?;
 
     writeln!(output, "    // Run server")?;
@@ -13,17 +13,34 @@ pub fn generate_message_envelope_rs(output_dir: &Path) -> Result<(), Box<dyn Err
     writeln!(file, "/// Wraps raw message body with metadata for tracking and processing")?;
     writeln!(file)?;
     writeln!(file, "use serde::{{Deserialize, Serialize}};")?;
+    writeln!(file, "use std::collections::HashMap;")?;
     writeln!(file, "use uuid::Uuid;")?;
     writeln!(file, "use chrono::{{DateTime, Utc}};")?;
     writeln!(file)?;
-    writeln!(file, "#[derive(Debug, Clone, Serialize, Deserialize)]")?;
+    writeln!(file, "/// Current envelope schema version (bump when the envelope shape changes)")?;
+    writeln!(file, "pub const ENVELOPE_SCHEMA_VERSION: u32 = 1;")?;
+    writeln!(file)?;
+    writeln!(file, "#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]")?;
     writeln!(file, "pub struct MessageEnvelope {{")?;
+    writeln!(file, "    /// Envelope schema version (0 = pre-versioning envelope)")?;
+    writeln!(file, "    #[serde(default)]")?;
+    writeln!(file, "    pub schema_version: u32,")?;
+    writeln!(file)?;
     writeln!(file, "    /// Unique message ID for tracking")?;
     writeln!(file, "    pub message_id: Uuid,")?;
     writeln!(file)?;
-    writeln!(file, "    /// Raw message body (before parsing)")?;
+    writeln!(file, "    /// Raw message body (before parsing), base64 of the compressed bytes")?;
+    writeln!(file, "    /// when `content_encoding` is set")?;
     writeln!(file, "    pub body: String,")?;
     writeln!(file)?;
+    writeln!(file, "    /// Codec `body` is compressed with (`gzip`, `zstd`); absent when uncompressed")?;
+    writeln!(file, "    #[serde(default, skip_serializing_if = \"Option::is_none\")]")?;
+    writeln!(file, "    pub content_encoding: Option<String>,")?;
+    writeln!(file)?;
+    writeln!(file, "    /// Hex SHA-256 of the uncompressed body, checked by workers with VERIFY_BODY_SHA256")?;
+    writeln!(file, "    #[serde(default, skip_serializing_if = \"Option::is_none\")]")?;
+    writeln!(file, "    pub body_sha256: Option<String>,")?;
+    writeln!(file)?;
     writeln!(file, "    /// Entity type hint (from URL path or header)")?;
     writeln!(file, "    pub entity_type: Option<String>,")?;
     writeln!(file)?;
@@ -36,31 +53,72 @@ pub fn generate_message_envelope_rs(output_dir: &Path) -> Result<(), Box<dyn Err
     writeln!(file)?;
     writeln!(file, "    /// Source IP or identifier")?;
     writeln!(file, "    pub source: Option<String>,")?;
+    writeln!(file)?;
+    writeln!(file, "    /// Correlation ID carried through ingestion, worker logs and message_status")?;
+    writeln!(file, "    #[serde(default, skip_serializing_if = \"Option::is_none\")]")?;
+    writeln!(file, "    pub correlation_id: Option<String>,")?;
+    writeln!(file)?;
+    writeln!(file, "    /// W3C trace context (traceparent/tracestate) of the ingesting span")?;
+    writeln!(file, "    #[serde(default, skip_serializing_if = \"Option::is_none\")]")?;
+    writeln!(file, "    pub trace_context: Option<HashMap<String, String>>,")?;
     writeln!(file, "}}")?;
     writeln!(file)?;
     writeln!(file, "impl MessageEnvelope {{")?;
     writeln!(file, "    /// Create a new message envelope")?;
     writeln!(file, "    pub fn new(body: String, entity_type: Option<String>) -> Self {{")?;
     writeln!(file, "        Self {{")?;
+    writeln!(file, "            schema_version: ENVELOPE_SCHEMA_VERSION,")?;
     writeln!(file, "            message_id: Uuid::new_v4(),")?;
+    writeln!(file, "            body_sha256: Some(body_sha256(&body)),")?;
     writeln!(file, "            body,")?;
+    writeln!(file, "            content_encoding: None,")?;
     writeln!(file, "            entity_type,")?;
     writeln!(file, "            received_at: Utc::now(),")?;
     writeln!(file, "            retry_count: 0,")?;
     writeln!(file, "            source: None,")?;
+    writeln!(file, "            correlation_id: None,")?;
+    writeln!(file, "            trace_context: None,")?;
     writeln!(file, "        }}")?;
     writeln!(file, "    }}")?;
+    writeln!(file)?;
+    writeln!(file, "    /// Compress the body with `codec` (`gzip` or `zstd`), storing it base64-encoded")?;
+    writeln!(file, "    pub fn compress_body(&mut self, codec: &str) -> Result<(), String> {{")?;
+    writeln!(file, "        use base64::Engine;")?;
+    writeln!(file, "        use std::io::Write;")?;
+    writeln!(file)?;
+    writeln!(file, "        let compressed = match codec {{")?;
+    writeln!(file, "            \"gzip\" => {{")?;
+    writeln!(file, "                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());")?;
+    writeln!(file, "                encoder.write_all(self.body.as_bytes())")?;
+    writeln!(file, "                    .and_then(|_| encoder.finish())")?;
+    writeln!(file, "                    .map_err(|e| format!(\"gzip compression failed: {{}}\", e))?")?;
+    writeln!(file, "            }}")?;
+    writeln!(file, "            \"zstd\" => zstd::encode_all(self.body.as_bytes(), 0)")?;
+    writeln!(file, "                .map_err(|e| format!(\"zstd compression failed: {{}}\", e))?,")?;
+    writeln!(file, "            other => return Err(format!(\"Unknown body codec '{{}}' (expected gzip or zstd)\", other)),")?;
+    writeln!(file, "        }};")?;
+    writeln!(file, "        self.body = base64::engine::general_purpose::STANDARD.encode(compressed);")?;
+    writeln!(file, "        self.content_encoding = Some(codec.to_string());")?;
+    writeln!(file, "        Ok(())")?;
+    writeln!(file, "    }}")?;
+    writeln!(file, "}}")?;
+    writeln!(file)?;
+    writeln!(file, "/// Hex SHA-256 of a message body, as stored in `body_sha256`")?;
+    writeln!(file, "pub fn body_sha256(body: &str) -> String {{")?;
+    writeln!(file, "    use sha2::{{Digest, Sha256}};")?;
+    writeln!(file, "    format!(\"{{:x}}\", Sha256::digest(body.as_bytes()))")?;
     writeln!(file, "}}")?;
     writeln!(file)?;
     writeln!(file, "/// Response returned to client after ingestion")?;
-    writeln!(file, "#[derive(Debug, Serialize)]")?;
+    writeln!(file, "#[derive(Debug, Serialize, utoipa::ToSchema)]")?;
     writeln!(file, "pub struct IngestionResponse {{")?;
     writeln!(file, "    pub message_id: String,")?;
     writeln!(file, "    pub status: IngestionStatus,")?;
+    writeln!(file, "    pub correlation_id: String,")?;
     writeln!(file, "    pub timestamp: DateTime<Utc>,")?;
     writeln!(file, "}}")?;
     writeln!(file)?;
-    writeln!(file, "#[derive(Debug, Serialize)]")?;
+    writeln!(file, "#[derive(Debug, Serialize, utoipa::ToSchema)]")?;
     writeln!(file, "#[serde(rename_all = \"lowercase\")]")?;
     writeln!(file, "pub enum IngestionStatus {{")?;
     writeln!(file, "    Accepted,   // Queued in NATS")?;
@@ -24,57 +24,19 @@ pub use error_rs::generate_error_rs;
 pub use message_envelope_rs::generate_message_envelope_rs;
 pub use nats_client_rs::generate_nats_client_rs;
 
-/// Database type enumeration
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum DatabaseType {
-    PostgreSQL,
-    MySQL,
-    MariaDB,
-}
-
-impl DatabaseType {
-    pub fn as_str(&self) -> &str {
-        match self {
-            DatabaseType::PostgreSQL => "postgresql",
-            DatabaseType::MySQL => "mysql",
-            DatabaseType::MariaDB => "mariadb",
-        }
-    }
-
-    /// Check if this is MySQL or MariaDB (similar syntax)
-    pub fn is_mysql_like(&self) -> bool {
-        matches!(self, DatabaseType::MySQL | DatabaseType::MariaDB)
-    }
-
-    /// Detect database type from DATABASE_URL
-    ///
-    /// # Examples
-    /// ```no_run
-    /// use nomnom::codegen::ingestion_server::DatabaseType;
-    ///
-    /// let db_type = DatabaseType::from_url("postgres://localhost/mydb");
-    /// assert_eq!(db_type, DatabaseType::PostgreSQL);
-    ///
-    /// let db_type = DatabaseType::from_url("mysql://localhost/mydb");
-    /// assert_eq!(db_type, DatabaseType::MySQL);
-    /// ```
-    pub fn from_url(url: &str) -> DatabaseType {
-        if url.starts_with("postgres://") || url.starts_with("postgresql://") {
-            DatabaseType::PostgreSQL
-        } else if url.starts_with("mysql://") {
-            DatabaseType::MySQL
-        } else {
-            // Default to PostgreSQL for backward compatibility
-            DatabaseType::PostgreSQL
-        }
-    }
-}
+pub use crate::codegen::database_type::DatabaseType;
 
 #[derive(Debug, Clone)]
 pub struct IngestionServerConfig {
     pub port: u16,
     pub database_type: DatabaseType,
     pub server_name: String,
+    /// Emit trace-level diagnostics (full message bodies) into the generated
+    /// handlers; without it they are left out entirely
+    pub verbose: bool,
+    /// Default for `VALIDATE_ON_INGEST`: parse messages with the worker's
+    /// parser before publishing and reject unparseable ones with 400
+    pub validate_on_ingest: bool,
 }
 
 impl Default for IngestionServerConfig {
@@ -83,6 +45,8 @@ impl Default for IngestionServerConfig {
             port: 8080,
             database_type: DatabaseType::PostgreSQL,
             server_name: "ingestion-server".to_string(),
+            verbose: false,
+            validate_on_ingest: false,
         }
     }
 }
@@ -117,11 +81,14 @@ pub fn generate_all(
     println!("  ✓ Generating message_envelope.rs...");
     generate_message_envelope_rs(output_dir)?;
 
+    println!("  ✓ Generating telemetry.rs...");
+    crate::codegen::telemetry::generate_telemetry_rs(output_dir, &config.server_name)?;
+
     println!("  ✓ Generating nats_client.rs...");
     generate_nats_client_rs(output_dir)?;
 
     println!("  ✓ Generating handlers.rs...");
-    generate_handlers_rs(entities, output_dir)?;
+    generate_handlers_rs(entities, output_dir, config.verbose, config.validate_on_ingest)?;
 
     println!("  ✓ Generating models.rs...");
     generate_models_rs(output_dir)?;
@@ -161,6 +128,7 @@ pub fn generate_all(
     println!("🌐 Server will be available at:");
     println!("  API:     http://localhost:{}", config.port);
     println!("  Swagger: http://localhost:{}/swagger-ui", config.port);
+    println!("  OpenAPI: http://localhost:{}/api-docs/openapi.json (or run with --print-openapi)", config.port);
     println!();
 
     Ok(())
@@ -185,13 +153,33 @@ fn generate_env_example(output_dir: &Path, config: &IngestionServerConfig) -> Re
     writeln!(output, "# Server configuration")?;
     writeln!(output, "PORT={}", config.port)?;
     writeln!(output, "HOST=0.0.0.0")?;
+    writeln!(output, "# Comma-separated origins allowed to call the API (unset: any in debug builds, none in release)")?;
+    writeln!(output, "# CORS_ALLOWED_ORIGINS=https://app.example.com")?;
     writeln!(output)?;
     writeln!(output, "# NATS Configuration")?;
     writeln!(output, "NATS_URL=nats://localhost:4222")?;
+    writeln!(output, "# Auth/TLS (managed NATS): credentials file, token or nkey seed; NATS_TLS=true")?;
+    writeln!(output, "# requires TLS, NATS_TLS_CA adds root certs, NATS_TLS_CERT/KEY enable mutual TLS")?;
+    writeln!(output, "# NATS_CREDS=/etc/nats/user.creds")?;
+    writeln!(output, "# NATS_TOKEN=")?;
+    writeln!(output, "# NATS_NKEY=")?;
+    writeln!(output, "NATS_TLS=false")?;
+    writeln!(output, "# NATS_TLS_CA=/etc/nats/ca.pem")?;
+    writeln!(output, "# NATS_TLS_CERT=/etc/nats/client.pem")?;
+    writeln!(output, "# NATS_TLS_KEY=/etc/nats/client-key.pem")?;
     writeln!(output, "NATS_STREAM=MESSAGES")?;
     writeln!(output)?;
+    writeln!(output, "# Parse messages before publishing and reject unparseable ones with 400 (costs CPU)")?;
+    writeln!(output, "VALIDATE_ON_INGEST={}", config.validate_on_ingest)?;
+    writeln!(output)?;
     writeln!(output, "# Logging")?;
     writeln!(output, "RUST_LOG=info")?;
+    writeln!(output, "# Set to json for one JSON object per log line")?;
+    writeln!(output, "LOG_FORMAT=text")?;
+    writeln!(output)?;
+    writeln!(output, "# OpenTelemetry (requires building with --features otel)")?;
+    writeln!(output, "OTEL_ENABLED=false")?;
+    writeln!(output, "# OTEL_EXPORTER_OTLP_ENDPOINT=http://localhost:4317")?;
 
     Ok(())
 }
@@ -22,6 +22,8 @@ pub fn generate_nats_client_rs(output_dir: &Path) -> Result<(), Box<dyn Error>>
     writeln!(file, "    pub stream_name: String,")?;
     writeln!(file, "    pub max_age: Duration,")?;
     writeln!(file, "    pub max_bytes: i64,")?;
+    writeln!(file, "    /// Body compression before publishing (`gzip`, `zstd`), from NATS_BODY_CODEC")?;
+    writeln!(file, "    pub body_codec: Option<String>,")?;
     writeln!(file, "}}")?;
     writeln!(file)?;
     writeln!(file, "impl Default for NatsConfig {{")?;
@@ -33,22 +35,35 @@ pub fn generate_nats_client_rs(output_dir: &Path) -> Result<(), Box<dyn Error>>
     writeln!(file, "                .unwrap_or_else(|_| \"MESSAGES\".to_string()),")?;
     writeln!(file, "            max_age: Duration::from_secs(24 * 60 * 60), // 24 hours")?;
     writeln!(file, "            max_bytes: 100 * 1024 * 1024, // 100MB")?;
+    writeln!(file, "            body_codec: std::env::var(\"NATS_BODY_CODEC\")")?;
+    writeln!(file, "                .ok()")?;
+    writeln!(file, "                .map(|codec| codec.trim().to_lowercase())")?;
+    writeln!(file, "                .filter(|codec| !codec.is_empty() && codec != \"none\"),")?;
     writeln!(file, "        }}")?;
     writeln!(file, "    }}")?;
     writeln!(file, "}}")?;
     writeln!(file)?;
+    write!(file, "{}", crate::codegen::utils::generate_nats_connect_options_fn())?;
     writeln!(file, "#[derive(Clone)]")?;
     writeln!(file, "pub struct NatsClient {{")?;
     writeln!(file, "    client: async_nats::Client,")?;
     writeln!(file, "    jetstream: jetstream::Context,")?;
     writeln!(file, "    stream_name: String,")?;
+    writeln!(file, "    body_codec: Option<String>,")?;
     writeln!(file, "}}")?;
     writeln!(file)?;
     writeln!(file, "impl NatsClient {{")?;
     writeln!(file, "    /// Connect to NATS and initialize JetStream")?;
     writeln!(file, "    pub async fn connect(config: NatsConfig) -> Result<Self, async_nats::Error> {{")?;
+    writeln!(file, "        if let Some(ref codec) = config.body_codec {{")?;
+    writeln!(file, "            if codec != \"gzip\" && codec != \"zstd\" {{")?;
+    writeln!(file, "                return Err(format!(\"Unknown NATS_BODY_CODEC '{{}}' (expected none, gzip or zstd)\", codec).into());")?;
+    writeln!(file, "            }}")?;
+    writeln!(file, "            tracing::info!(\"Compressing message bodies with {{}}\", codec);")?;
+    writeln!(file, "        }}")?;
+    writeln!(file)?;
     writeln!(file, "        // Connect to NATS")?;
-    writeln!(file, "        let client = async_nats::connect(&config.url).await?;")?;
+    writeln!(file, "        let client = nats_connect_options().await?.connect(&config.url).await?;")?;
     writeln!(file, "        tracing::info!(\"Connected to NATS at {{}}\", config.url);")?;
     writeln!(file)?;
     writeln!(file, "        // Get JetStream context")?;
@@ -89,6 +104,7 @@ pub fn generate_nats_client_rs(output_dir: &Path) -> Result<(), Box<dyn Error>>
     writeln!(file, "            client,")?;
     writeln!(file, "            jetstream,")?;
     writeln!(file, "            stream_name: config.stream_name,")?;
+    writeln!(file, "            body_codec: config.body_codec,")?;
     writeln!(file, "        }})")?;
     writeln!(file, "    }}")?;
     writeln!(file)?;
@@ -100,7 +116,14 @@ pub fn generate_nats_client_rs(output_dir: &Path) -> Result<(), Box<dyn Error>>
     writeln!(file, "        let subject = format!(\"messages.ingest.{{}}\",")?;
     writeln!(file, "            envelope.entity_type.as_deref().unwrap_or(\"default\"));")?;
     writeln!(file)?;
-    writeln!(file, "        let payload = serde_json::to_vec(envelope)?;")?;
+    writeln!(file, "        let payload = match self.body_codec {{")?;
+    writeln!(file, "            Some(ref codec) if envelope.content_encoding.is_none() => {{")?;
+    writeln!(file, "                let mut compressed = envelope.clone();")?;
+    writeln!(file, "                compressed.compress_body(codec)?;")?;
+    writeln!(file, "                serde_json::to_vec(&compressed)?")?;
+    writeln!(file, "            }}")?;
+    writeln!(file, "            _ => serde_json::to_vec(envelope)?,")?;
+    writeln!(file, "        }};")?;
     writeln!(file)?;
     writeln!(file, "        // Publish with JetStream (durable, acknowledged)")?;
     writeln!(file, "        let ack = self.jetstream")?;
@@ -23,16 +23,26 @@ pub mod project_config;
 pub mod fs_utils;
 pub mod parser_binary;
 pub mod lineage;
+pub mod telemetry;
 pub mod dependency_graph;
+pub mod scaffold;
+pub mod explain;
+pub mod diagram;
+pub mod naming;
+pub mod env_interpolation;
+pub mod database_type;
+pub mod sql_dialect;
 
 // Re-export key types
 pub use types::{EntityDef, FieldDef, ComputedFrom, SourceType, Repetition};
-pub use yaml_loader::{load_entities, load_entity};
+pub use yaml_loader::{load_entities, load_entity, parse_entities, parse_entity};
 pub use rust_codegen::{generate_rust_code, RustCodegenConfig};
 pub use python_codegen::{generate_python_bindings, generate_python_core_mapping};
-pub use pyo3_codegen::{generate_python_bindings as generate_pyo3_bindings, PyO3Config};
+pub use pyo3_codegen::{generate_python_bindings as generate_pyo3_bindings, generate_python_stubs, PyO3Config};
 pub use plugins::{CodegenCallbacks, CodegenPipeline, NoOpCallbacks};
 pub use orchestration::{GenerationConfig, generate_all_from_config};
+pub use naming::{NameCase, NamingStrategy};
+pub use database_type::DatabaseType;
 pub use transform_codegen::{
     generate_rust_transform, generate_pyo3_binding, generate_python_transform,
     generate_transform_tests, generate_transforms_module, generate_python_transforms
@@ -0,0 +1,226 @@
+//! Table and column naming strategy.
+//!
+//! Entity YAML names tables and columns explicitly. A `NamingStrategy` rewrites
+//! those names once, right after loading, so every generator (Diesel schema and
+//! models, worker inserts, parser binary, foreign key references) emits the same
+//! house-style names. The default strategy leaves names untouched.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::codegen::types::DatabaseConfig;
+use crate::codegen::utils::to_snake_case;
+use crate::codegen::EntityDef;
+
+/// Case applied to table and column names
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NameCase {
+    /// Keep names as written in the entity YAML
+    #[default]
+    Preserve,
+    /// snake_case
+    Snake,
+    /// lowercase
+    Lower,
+    /// UPPERCASE
+    Upper,
+}
+
+impl NameCase {
+    /// Apply the case to a name
+    pub fn apply(&self, name: &str) -> String {
+        match self {
+            NameCase::Preserve => name.to_string(),
+            NameCase::Snake => to_snake_case(name),
+            NameCase::Lower => name.to_lowercase(),
+            NameCase::Upper => name.to_uppercase(),
+        }
+    }
+}
+
+/// Naming strategy for generated tables and columns
+///
+/// ```yaml
+/// naming:
+///   prefix: tbl_
+///   pluralize: true
+///   case: snake
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct NamingStrategy {
+    /// Prepended to every table name
+    #[serde(default)]
+    pub prefix: String,
+    /// Appended to every table name
+    #[serde(default)]
+    pub suffix: String,
+    /// Pluralize table names (`order` -> `orders`, `category` -> `categories`)
+    #[serde(default)]
+    pub pluralize: bool,
+    /// Case applied to table and column names
+    #[serde(default)]
+    pub case: NameCase,
+}
+
+impl NamingStrategy {
+    /// Whether the strategy leaves every name unchanged
+    pub fn is_identity(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Table name for a table configured as `base`
+    pub fn table_name(&self, base: &str) -> String {
+        let base = if self.pluralize { pluralize(base) } else { base.to_string() };
+        self.case.apply(&format!("{}{}{}", self.prefix, base, self.suffix))
+    }
+
+    /// Column name for a column configured as `base`
+    pub fn column_name(&self, base: &str) -> String {
+        self.case.apply(base)
+    }
+
+    /// Rewrite table, column and foreign key names on loaded entities
+    ///
+    /// Must be applied exactly once per load; the names it produces are not
+    /// fed back through the strategy.
+    pub fn apply(&self, entities: &mut [EntityDef]) {
+        if self.is_identity() {
+            return;
+        }
+
+        // Map configured table names first so FK references to any entity resolve
+        let tables: HashMap<String, String> = entities.iter()
+            .flat_map(|e| database_configs(e))
+            .map(|db| (db.conformant_table.clone(), self.table_name(&db.conformant_table)))
+            .collect();
+
+        for entity in entities.iter_mut() {
+            for db in database_configs_mut(entity) {
+                db.conformant_table = tables[&db.conformant_table].clone();
+                for fk in &mut db.foreign_keys {
+                    if let Some((table, column)) = fk.references.split_once('.') {
+                        let table = tables.get(table).cloned().unwrap_or_else(|| table.to_string());
+                        fk.references = format!("{}.{}", table, self.column_name(column));
+                    }
+                }
+            }
+
+            if self.case != NameCase::Preserve {
+                for field in &mut entity.fields {
+                    let column = self.column_name(field.column_name());
+                    field.column = (column != field.name).then_some(column);
+                }
+            }
+        }
+    }
+}
+
+/// Database configs declared on an entity (new `persistence.database` and legacy `database`)
+fn database_configs(entity: &EntityDef) -> impl Iterator<Item = &DatabaseConfig> {
+    entity.persistence.as_ref()
+        .and_then(|p| p.database.as_ref())
+        .into_iter()
+        .chain(entity.database.as_ref())
+}
+
+fn database_configs_mut(entity: &mut EntityDef) -> impl Iterator<Item = &mut DatabaseConfig> {
+    entity.persistence.as_mut()
+        .and_then(|p| p.database.as_mut())
+        .into_iter()
+        .chain(entity.database.as_mut())
+}
+
+/// English plural of the last word of a name (names already ending in `s` are kept)
+fn pluralize(name: &str) -> String {
+    let lower = name.to_lowercase();
+    if lower.ends_with("ss") || lower.ends_with("us") || lower.ends_with("is")
+        || lower.ends_with('x') || lower.ends_with('z') || lower.ends_with("ch") || lower.ends_with("sh")
+    {
+        return format!("{}es", name);
+    }
+    if lower.ends_with('s') {
+        return name.to_string();
+    }
+    if let Some(stem) = name.strip_suffix('y').or_else(|| name.strip_suffix('Y')) {
+        if !stem.to_lowercase().ends_with(['a', 'e', 'i', 'o', 'u']) {
+            let ies = if name.ends_with('Y') { "IES" } else { "ies" };
+            return format!("{}{}", stem, ies);
+        }
+    }
+    let s = if name.chars().last().is_some_and(|c| c.is_uppercase()) { "S" } else { "s" };
+    format!("{}{}", name, s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::parse_entity;
+
+    #[test]
+    fn test_table_names_follow_strategy() {
+        let strategy = NamingStrategy {
+            prefix: "tbl_".to_string(),
+            pluralize: true,
+            ..Default::default()
+        };
+        assert_eq!(strategy.table_name("order"), "tbl_orders");
+        assert_eq!(strategy.table_name("category"), "tbl_categories");
+        assert_eq!(strategy.table_name("address"), "tbl_addresses");
+        assert_eq!(strategy.table_name("orders"), "tbl_orders");
+        assert_eq!(NamingStrategy::default().table_name("Order"), "Order");
+
+        let upper = NamingStrategy { case: NameCase::Upper, ..strategy };
+        assert_eq!(upper.table_name("day"), "TBL_DAYS");
+    }
+
+    #[test]
+    fn test_apply_rewrites_tables_columns_and_foreign_keys() {
+        let mut entities = vec![
+            parse_entity(r#"
+entity:
+  name: Customer
+  source_type: root
+  fields:
+    - name: customerName
+      type: String
+  persistence:
+    database:
+      conformant_table: customer
+      conformant_id_column: id
+"#).unwrap(),
+            parse_entity(r#"
+entity:
+  name: Order
+  source_type: root
+  fields:
+    - name: total
+      type: Float
+      column: orderTotal
+  persistence:
+    database:
+      conformant_table: order
+      conformant_id_column: id
+      foreign_keys:
+        - name: customer_id
+          references: customer.id
+          parent_entity: Customer
+"#).unwrap(),
+        ];
+
+        let strategy = NamingStrategy {
+            prefix: "tbl_".to_string(),
+            pluralize: true,
+            case: NameCase::Snake,
+            ..Default::default()
+        };
+        strategy.apply(&mut entities);
+
+        let db = entities[1].get_database_config(&entities).unwrap();
+        assert_eq!(db.conformant_table, "tbl_orders");
+        assert_eq!(db.foreign_keys[0].references, "tbl_customers.id");
+        assert_eq!(entities[0].fields[0].column_name(), "customer_name");
+        assert_eq!(entities[1].fields[0].column_name(), "order_total");
+    }
+}
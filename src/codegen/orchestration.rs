@@ -10,8 +10,8 @@ use std::path::Path;
 
 use crate::codegen::{
     EntityDef, RustCodegenConfig, PyO3Config, load_entities,
-    generate_rust_code, generate_pyo3_bindings, generate_python_core_mapping,
-    fs_utils,
+    generate_rust_code, generate_pyo3_bindings, generate_python_stubs, generate_python_core_mapping,
+    fs_utils, DatabaseType,
 };
 
 /// Configuration for code generation orchestration.
@@ -87,9 +87,22 @@ pub struct GenerationConfig {
     /// This generates the main package __init__.py that re-exports from _rust extension module
     pub python_package_init_output: Option<String>,
 
+    /// Optional: Output path for Python type stubs of the extension module (e.g., "data_processor/_rust.pyi")
+    /// A `py.typed` marker is written alongside so the wheel ships the stubs
+    pub python_stubs_output: Option<String>,
+
     /// Optional: Rust transform definitions from nomnom.yaml
     /// Maps transform name to transform definition (args, return_type, code)
-    pub rust_transforms: Option<std::collections::HashMap<String, crate::codegen::project_config::RustTransformDef>>,
+    pub rust_transforms: Option<std::collections::BTreeMap<String, crate::codegen::project_config::RustTransformDef>>,
+
+    /// Table/column naming strategy applied to every entity after loading
+    pub naming: crate::codegen::NamingStrategy,
+
+    /// Project-wide default for `persistence.timestamps`
+    pub timestamps: bool,
+
+    /// Database the Diesel schema targets (selects e.g. `Jsonb` vs `Json`)
+    pub database_type: DatabaseType,
 }
 
 /// Generate all code artifacts from entity configurations.
@@ -129,7 +142,14 @@ pub fn generate_all_from_config(config: &GenerationConfig) -> Result<(), Box<dyn
     println!("cargo:rerun-if-changed={}", config.config_dir);
 
     // 1. Load all entities from YAML configs
-    let all_entities = load_entities(&config.config_dir)?;
+    let mut all_entities = load_entities(&config.config_dir)?;
+    config.naming.apply(&mut all_entities);
+    crate::codegen::types::apply_default_timestamps(&mut all_entities, config.timestamps);
+    if let Some(ref transforms) = config.rust_transforms {
+        for entity in &mut all_entities {
+            entity.qualify_transforms(|name| transforms.contains_key(name));
+        }
+    }
     println!("Loaded {} entities from {}:", all_entities.len(), config.config_dir);
     for entity in &all_entities {
         println!("  - {} (source: {:?})", entity.name, entity.source_type);
@@ -139,6 +159,7 @@ pub fn generate_all_from_config(config: &GenerationConfig) -> Result<(), Box<dyn
     println!("Generating Rust structs for {} entities", all_entities.len());
     let rust_config = RustCodegenConfig {
         transform_registry_type: Some(config.transform_registry_type.clone()),
+        ..Default::default()
     };
 
     let mut rust_output = fs_utils::create_file(&config.rust_output)?;
@@ -183,6 +204,16 @@ pub fn generate_all_from_config(config: &GenerationConfig) -> Result<(), Box<dyn
     println!("cargo:rerun-if-changed={}", config.pyo3_bindings_output);
     println!("✓ PyO3 bindings generation completed");
 
+    if let Some(stubs_path) = &config.python_stubs_output {
+        let mut stubs_output = fs_utils::create_file(stubs_path)?;
+        generate_python_stubs(&mut stubs_output, &all_entities, &pyo3_config)?;
+
+        let marker_path = Path::new(stubs_path).with_file_name("py.typed");
+        fs_utils::create_file(&marker_path)?;
+
+        println!("✓ Python type stubs generated at {}", stubs_path);
+    }
+
     // 4. Generate Diesel artifacts (if configured)
     if config.diesel_schema_output.is_some()
         || config.diesel_models_output.is_some()
@@ -193,7 +224,7 @@ pub fn generate_all_from_config(config: &GenerationConfig) -> Result<(), Box<dyn
         use crate::codegen::diesel;
 
         if let Some(schema_path) = &config.diesel_schema_output {
-            diesel::generate_schema(&all_entities, Path::new(schema_path), &config.config_dir)?;
+            diesel::generate_schema_for(&all_entities, Path::new(schema_path), &config.config_dir, config.database_type)?;
             println!("  ✓ Diesel schema: {}", schema_path);
         }
 
@@ -271,8 +302,8 @@ pub fn generate_all_from_config(config: &GenerationConfig) -> Result<(), Box<dyn
 
         let lib_code = generate_lib_rs_full(config);
 
-        let mut file = fs_utils::create_file(lib_path)?;
-        file.write_all(lib_code.as_bytes())?;
+        // Only the managed region is replaced; user code around it is kept
+        fs_utils::write_managed(lib_path, &lib_code)?;
 
         println!("✓ lib.rs generated at {}", lib_path);
     }
@@ -359,6 +390,7 @@ fn detect_permanent_entities(config_dir: &str) -> Result<Vec<EntityDef>, Box<dyn
                                 parent: None,
                                 parents: vec![],
                                 repeated_for: None,
+                                repeated_by_count: None,
                                 fields: vec![],
                                 doc: None,
                                 persistence: None,  // Will be loaded properly by load_entities
@@ -370,6 +402,18 @@ fn detect_permanent_entities(config_dir: &str) -> Result<Vec<EntityDef>, Box<dyn
                                 serialization: vec![],
                                 prefix: None,
                                 minimal_existence: None,
+                                when: None,
+                                join_on: None,
+                                transform_namespace: None,
+                                publish_subject: None,
+                                examples: vec![],
+                                dashboard: None,
+                                derives: vec![],
+                                json_input: None,
+                                encoding: None,
+                                csv: None,
+                                record_separator: None,
+                                data_field: None,
                             });
                         }
                     }
@@ -550,6 +594,14 @@ fn generate_python_bindings(module_name: &str, transform_functions: &[String]) -
     code.push_str("    // Register Database class for persistence\n");
     code.push_str("    m.add_class::<crate::python::PyDatabase>()?;\n\n");
 
+    // Register nomnom exception hierarchy
+    code.push_str("    // Register nomnom exception hierarchy (NomnomError, TransformError, EntityError, DatabaseError)\n");
+    code.push_str("    nomnom::python_bridge::register_exceptions(_py, m)?;\n\n");
+
+    // Register TransformRegistry class for transform introspection
+    code.push_str("    // Register TransformRegistry class for transform introspection\n");
+    code.push_str("    m.add_class::<nomnom::PyTransformRegistry>()?;\n\n");
+
     // Register persistence functions
     // DISABLED: Parser binary doesn't need persistence functions
     // code.push_str("    // Register get_or_create persistence functions\n");
@@ -583,8 +635,9 @@ fn generate_lib_rs_full(config: &GenerationConfig) -> String {
     // Header with documentation
     code.push_str("//! Auto-generated library module.\n");
     code.push_str("//!\n");
-    code.push_str("//! This file is auto-generated by nomnom.\n");
-    code.push_str("//! DO NOT EDIT MANUALLY - changes will be overwritten.\n");
+    code.push_str("//! This file is auto-generated by nomnom. Only the region between the\n");
+    code.push_str("//! `// BEGIN nomnom generated` and `// END nomnom generated` markers is\n");
+    code.push_str("//! regenerated; code added outside it is preserved.\n");
     code.push_str("//!\n");
     code.push_str("//! High-performance data transformation library with:\n");
     code.push_str("//! - Fast, safe data extraction\n");
@@ -801,7 +854,11 @@ mod tests {
             python_dependency_imports: None,
             python_rust_shim_output: None,
             python_package_init_output: None,
+            python_stubs_output: None,
             rust_transforms: None,
+            naming: Default::default(),
+            timestamps: false,
+            database_type: DatabaseType::PostgreSQL,
         };
 
         assert_eq!(config.config_dir, "config/entities");
@@ -880,7 +937,11 @@ mod tests {
             python_dependency_imports: None,
             python_rust_shim_output: None,
             python_package_init_output: None,
+            python_stubs_output: None,
             rust_transforms: None,
+            naming: Default::default(),
+            timestamps: false,
+            database_type: DatabaseType::PostgreSQL,
         };
 
         let code = generate_lib_rs_full(&config);
@@ -902,8 +963,9 @@ mod tests {
         assert!(code.contains("Segment"));
         assert!(code.contains("pub use generated::*;"));
 
-        // Check that it's marked as auto-generated
+        // Check that it's marked as auto-generated, with its managed region preserved
         assert!(code.contains("auto-generated by nomnom"));
-        assert!(code.contains("DO NOT EDIT MANUALLY"));
+        assert!(code.contains("code added outside it is preserved"));
+        assert!(!code.contains("DO NOT EDIT MANUALLY"));
     }
 }
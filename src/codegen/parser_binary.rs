@@ -13,9 +13,10 @@
 //! - Persistence config (from entity.persistence.database)
 //! - Unicity fields (from persistence.database.unicity_fields)
 
-use crate::codegen::types::EntityDef;
+use crate::codegen::types::{EntityDef, InsertValue, SqlPart};
 use crate::codegen::utils::to_snake_case;
 use crate::codegen::ProjectBuildConfig;
+use crate::runtime::OnEntityError;
 use crate::codegen::lineage::{generate_lineage_code, generate_entity_to_fields_helper};
 use std::collections::{HashMap, VecDeque};
 
@@ -24,9 +25,15 @@ use std::collections::{HashMap, VecDeque};
 
 /// Generate the complete parser binary source code
 pub fn generate_parser_binary(
-    _config: &ProjectBuildConfig,
+    config: &ProjectBuildConfig,
     entities: &[EntityDef],
 ) -> Result<String, String> {
+    // 0. Apply the table/column naming strategy so SQL and inserts match the schema
+    let mut named_entities = entities.to_vec();
+    config.naming.apply(&mut named_entities);
+    crate::codegen::types::apply_default_timestamps(&mut named_entities, config.timestamps);
+    let entities = named_entities.as_slice();
+
     // 1. Filter out reference entities - they're not processed by the parser
     // Reference entities are pre-loaded data (e.g., Customer, Product tables)
     let processable_entities: Vec<&EntityDef> = entities.iter()
@@ -53,13 +60,30 @@ pub fn generate_parser_binary(
     // Check if database support is needed (any persistent entities exist)
     let has_database_support = !permanent_entities.is_empty();
 
+    // Generated ids reach children only from singleton parents: a repeated
+    // parent's rows can't be matched back to the children extracted from them
+    for entity in &extraction_order {
+        let Some(id_field) = entity.generated_id_field(entities) else { continue };
+        if is_repeated_entity(entity) {
+            if let Some((child, _)) = generated_id_copies(entity, &id_field.name, &extraction_order).first() {
+                return Err(format!(
+                    "Entity '{}': the parser binary can't link it to the generated ids of repeated entity '{}'",
+                    child.name, entity.name
+                ));
+            }
+        }
+    }
+
     // 4. Generate code sections
     let mut code = String::new();
     code.push_str(&generate_header());
     code.push_str(&generate_imports(has_database_support));
     code.push_str(&generate_lineage_code());
     code.push_str(&generate_entity_to_fields_helper());
-    code.push_str(&generate_cli_struct(has_database_support));
+    let default_encoding = root_entity.encoding.as_deref().unwrap_or("utf-8");
+    let default_separator = root_entity.record_separator.as_deref().unwrap_or("newline");
+    code.push_str(&generate_cli_struct(has_database_support, config.on_entity_error, default_encoding, default_separator));
+    code.push_str(&generate_entity_error_policy());
     code.push_str(&generate_parse_results_struct(&extraction_order));
     if has_database_support {
         code.push_str(&generate_execution_stats_struct(&extraction_order, entities));
@@ -67,9 +91,9 @@ pub fn generate_parser_binary(
     code.push_str(&generate_main_function(root_entity, has_database_support, &extraction_order, entities));
     code.push_str(&generate_extraction_function(root_entity, &extraction_order, entities));
     code.push_str(&generate_json_output_function(&extraction_order));
-    code.push_str(&generate_sql_output_function(&permanent_entities, entities));
+    code.push_str(&generate_sql_output_function(&permanent_entities, entities)?);
     if has_database_support {
-        code.push_str(&generate_execute_to_database_function(&extraction_order, &permanent_entities, entities));
+        code.push_str(&generate_execute_to_database_function(&extraction_order, &permanent_entities, entities)?);
     }
     code.push_str(&generate_sql_helpers());
 
@@ -77,7 +101,7 @@ pub fn generate_parser_binary(
 }
 
 /// Compute topological ordering of entities for extraction
-fn compute_extraction_order(
+pub(crate) fn compute_extraction_order(
     entities: &[&EntityDef],
     root_entity: &EntityDef,
 ) -> Result<Vec<EntityDef>, String> {
@@ -195,7 +219,7 @@ fn generate_imports(has_database_support: bool) -> String {
     let mut code = String::new();
 
     // Basic imports
-    code.push_str("use std::io::{self, BufRead};\n");
+    code.push_str("use std::io::{self, BufRead, Write};\n");
     code.push_str("use std::error::Error;\n");
     code.push_str("use clap::Parser;\n");
     code.push_str("use std::collections::HashMap;\n");
@@ -211,7 +235,7 @@ fn generate_imports(has_database_support: bool) -> String {
     // Database imports for --execute-db mode
     if has_database_support {
         code.push_str("// Database imports for --execute-db mode\n");
-        code.push_str("use _rust::db::{Database, DatabaseConfig, Pool, operations::GetOrCreate};\n");
+        code.push_str("use _rust::db::{Database, DatabaseConfig, Pool, PooledConnection, operations::GetOrCreate};\n");
         code.push_str("use _rust::models::*;\n");
         code.push_str("\n");
     }
@@ -224,7 +248,12 @@ fn generate_imports(has_database_support: bool) -> String {
 }
 
 /// Generate CLI argument parser
-fn generate_cli_struct(has_database_support: bool) -> String {
+fn generate_cli_struct(
+    has_database_support: bool,
+    on_entity_error: OnEntityError,
+    default_encoding: &str,
+    default_separator: &str,
+) -> String {
     let mut code = String::new();
 
     code.push_str(r#"/// CLI arguments for parser binary
@@ -257,12 +286,88 @@ struct Cli {
     lineage_format: String,
 "#);
 
+    code.push_str(&format!(r#"
+    /// What happens when an entity fails: abort the file, skip the entity, or
+    /// skip it and write it to --dead-letter (dlq)
+    #[arg(long, value_enum, default_value = "{}")]
+    on_entity_error: OnEntityError,
+
+    /// JSON Lines file receiving failed entities under --on-entity-error dlq
+    #[arg(long, default_value = "dead_letter.jsonl")]
+    dead_letter: std::path::PathBuf,
+
+    /// Encoding of stdin (e.g. utf-8, latin1, windows-1252, utf-16le), or
+    /// auto to detect a BOM and fall back to Windows-1252 for non-UTF-8 lines
+    #[arg(long, default_value = "{}")]
+    encoding: String,
+
+    /// Where records end: newline, blank_line, csv (newlines inside quoted
+    /// columns don't), hl7 (one record per MSH segment), or
+    /// terminator:<text> (a line equal to <text> ends the record)
+    #[arg(long, default_value = "{}")]
+    record_separator: String,
+
+    /// Decompression of input files: auto (by .gz/.zst extension or magic
+    /// bytes), none, gzip or zstd
+    #[arg(long, default_value = "auto")]
+    decompress: String,
+
+    /// Read stdin as one input document instead of a list of file paths
+    #[arg(long)]
+    stdin_content: bool,
+
+    /// Parse the files matching a glob pattern (e.g. "data/**/*.csv")
+    /// instead of reading paths from stdin; repeatable
+    #[arg(long = "glob")]
+    globs: Vec<String>,
+
+    /// Parse the files in a directory instead of reading paths from stdin;
+    /// repeatable
+    #[arg(long = "dir")]
+    dirs: Vec<std::path::PathBuf>,
+
+    /// Descend into subdirectories of --dir
+    #[arg(long)]
+    recursive: bool,
+
+    /// Only parse --glob/--dir files with these extensions (e.g. csv,hl7)
+    #[arg(long = "ext", value_delimiter = ',')]
+    extensions: Vec<String>,
+
+    /// Print files, records, entities per type and errors to stderr
+    /// periodically, and totals at the end
+    #[arg(long)]
+    progress: bool,
+
+    /// Like --progress, as one JSON object per line
+    #[arg(long)]
+    progress_json: bool,
+
+    /// Seconds between --progress reports
+    #[arg(long, default_value = "5")]
+    progress_interval: u64,
+
+    /// Exit with status 2 if any file, record or entity failed
+    #[arg(long)]
+    fail_on_error: bool,
+
+    /// JSON Lines report with one line per input file that had errors,
+    /// listing them (failed entities themselves go to --dead-letter)
+    #[arg(long)]
+    error_log: Option<std::path::PathBuf>,
+"#, on_entity_error.as_str(), default_encoding, default_separator.escape_default()));
+
     if has_database_support {
         code.push_str(r#"
     /// Execute statements directly against database (requires DATABASE_URL env var)
     #[arg(long)]
     execute_db: bool,
 
+    /// With --execute-db, commit every N records instead of writing each
+    /// file in one transaction (a failed record still only rolls back itself)
+    #[arg(long)]
+    commit_every: Option<usize>,
+
     /// Show verbose output (detailed execution logs)
     #[arg(long, short)]
     verbose: bool,
@@ -273,9 +378,140 @@ struct Cli {
     code
 }
 
+/// Generate the --on-entity-error policy and the helper recording failures
+fn generate_entity_error_policy() -> String {
+    r#"/// What happens when an entity fails to extract
+///
+/// Only repeated entities can be skipped: a failed singleton always fails
+/// the file.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum OnEntityError {
+    Abort,
+    Skip,
+    Dlq,
+}
+
+/// Why an input, record or entity failed
+#[derive(Debug, thiserror::Error)]
+enum ParserError {
+    /// An input file couldn't be opened, decompressed or decoded
+    #[error("Failed to read input: {message}")]
+    Input { message: String },
+    /// The root entity couldn't be built from a record
+    #[error("Failed to parse {entity}: {message}")]
+    Parse { entity: &'static str, message: String },
+    /// An entity's transforms failed
+    #[error("Failed to extract {entity}: {message}")]
+    Transform { entity: &'static str, message: String },
+    /// Writing the extracted entities to the database failed
+    #[error("Database error: {message}")]
+    Persistence { message: String },
+    /// Printing JSON or SQL output failed
+    #[error("Failed to write output: {message}")]
+    Output { message: String },
+}
+
+impl ParserError {
+    /// Short name of the error variant, for --error-log and the dead letter file
+    fn kind(&self) -> &'static str {
+        match self {
+            ParserError::Input { .. } => "input",
+            ParserError::Parse { .. } => "parse",
+            ParserError::Transform { .. } => "transform",
+            ParserError::Persistence { .. } => "persistence",
+            ParserError::Output { .. } => "output",
+        }
+    }
+
+    /// Entity the error is about, if any
+    fn entity(&self) -> Option<&'static str> {
+        match self {
+            ParserError::Parse { entity, .. } | ParserError::Transform { entity, .. } => Some(entity),
+            _ => None,
+        }
+    }
+}
+
+/// An entity skipped under the skip/dlq policies
+struct EntityFailure {
+    entity: &'static str,
+    error: ParserError,
+}
+
+/// Record a failed entity, or return the error under the abort policy
+fn entity_failed(
+    policy: OnEntityError,
+    entity: &'static str,
+    error: impl std::fmt::Display,
+    failures: &mut Vec<EntityFailure>,
+) -> Result<(), ParserError> {
+    let error = ParserError::Transform { entity, message: error.to_string() };
+    if policy == OnEntityError::Abort {
+        return Err(error);
+    }
+    failures.push(EntityFailure { entity, error });
+    Ok(())
+}
+
+/// Errors of a run, for --error-log and --fail-on-error
+struct RunErrors {
+    count: usize,
+    log: Option<std::fs::File>,
+    /// File being processed
+    file: String,
+    /// Errors of the file being processed
+    file_errors: Vec<serde_json::Value>,
+}
+
+impl RunErrors {
+    /// Start collecting the errors of `file`
+    fn file_started(&mut self, file: &str) {
+        self.file = file.to_string();
+    }
+
+    /// Count an error in the file being processed
+    fn record(&mut self, error: &ParserError) {
+        self.count += 1;
+        if self.log.is_some() {
+            self.file_errors.push(serde_json::json!({
+                "kind": error.kind(),
+                "entity": error.entity(),
+                "message": error.to_string(),
+            }));
+        }
+    }
+
+    /// Write the errors of a finished file to the error log as one JSON line
+    fn file_done(&mut self) -> io::Result<()> {
+        if let Some(ref mut log) = self.log {
+            if !self.file_errors.is_empty() {
+                let report = serde_json::json!({
+                    "file": self.file,
+                    "errors": std::mem::take(&mut self.file_errors),
+                });
+                writeln!(log, "{}", report)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for RunErrors {
+    /// Write the current file's errors when the run stops on an error mid-file
+    fn drop(&mut self) {
+        if let Err(e) = self.file_done() {
+            eprintln!("Error writing error log: {}", e);
+        }
+    }
+}
+
+"#.to_string()
+}
+
 /// Generate ParseResults struct to hold all extracted entities
 fn generate_parse_results_struct(extraction_order: &[EntityDef]) -> String {
     let mut code = String::new();
+    let mut counts = Vec::new();
 
     code.push_str("/// Results from parsing a single file\n");
     code.push_str("#[derive(Debug)]\n");
@@ -292,17 +528,26 @@ fn generate_parse_results_struct(extraction_order: &[EntityDef]) -> String {
 
         // Check if entity is repeated (either via repetition field or repeated_for)
         let is_repeated = entity.repetition.as_ref().map(|r| r == "repeated").unwrap_or(false)
-            || entity.repeated_for.is_some();
+            || entity.repeating_parent().is_some();
 
         let field_type = if is_repeated {
+            counts.push(format!("(\"{}\", self.{}.len())", entity.name, field_name));
             format!("Vec<{}>", type_name)
         } else {
+            counts.push(format!("(\"{}\", 1)", entity.name));
             type_name
         };
 
         code.push_str(&format!("    {}: {},\n", field_name, field_type));
     }
 
+    code.push_str("}\n\n");
+
+    code.push_str("impl ParseResults {\n");
+    code.push_str("    /// Number of extracted entities per type, for --progress\n");
+    code.push_str(&format!("    fn entity_counts(&self) -> [(&'static str, usize); {}] {{\n", counts.len()));
+    code.push_str(&format!("        [{}]\n", counts.join(", ")));
+    code.push_str("    }\n");
     code.push_str("}\n\n");
     code
 }
@@ -386,43 +631,168 @@ fn generate_main_function(root_entity: &EntityDef, has_database_support: bool, e
         code.push_str("        Some(database.pool().clone())\n");
         code.push_str("    } else {\n");
         code.push_str("        None\n");
-        code.push_str("    };\n\n");
+        code.push_str("    };\n");
+        code.push_str("    let mut db_writer = db_pool.as_ref()\n");
+        code.push_str("        .map(|pool| DbWriter::new(pool, cli.commit_every))\n");
+        code.push_str("        .transpose()?;\n\n");
     }
 
+    code.push_str("    let mut dead_letter = if cli.on_entity_error == OnEntityError::Dlq {\n");
+    code.push_str("        Some(std::fs::OpenOptions::new().create(true).append(true).open(&cli.dead_letter)?)\n");
+    code.push_str("    } else {\n");
+    code.push_str("        None\n");
+    code.push_str("    };\n\n");
+    code.push_str("    let mut errors = RunErrors {\n");
+    code.push_str("        count: 0,\n");
+    code.push_str("        log: cli.error_log.as_ref().map(std::fs::File::create).transpose()?,\n");
+    code.push_str("        file: String::new(),\n");
+    code.push_str("        file_errors: Vec::new(),\n");
+    code.push_str("    };\n\n");
+
     // Process files from stdin
-    code.push_str("    // Read file paths from stdin (one per line)\n");
-    code.push_str("    let stdin = io::stdin();\n");
-    code.push_str("    for line in stdin.lock().lines() {\n");
-    code.push_str("        let file_path = line?;\n\n");
-    code.push_str("        // Process file\n");
+    code.push_str("    // Inputs are the files named on stdin (one per line), the files matched\n");
+    code.push_str("    // by --glob/--dir in sorted order, or stdin itself with --stdin-content;\n");
+    code.push_str("    // each is decompressed, transcoded to UTF-8 and split into records. Failing\n");
+    code.push_str("    // to read the list of paths itself aborts the run\n");
+    code.push_str("    let codec: nomnom::runtime::InputCodec = cli.decompress.parse()?;\n");
+    code.push_str("    let encoding: nomnom::runtime::InputEncoding = cli.encoding.parse()?;\n");
+    code.push_str("    let separator: nomnom::runtime::RecordSeparator = cli.record_separator.parse()?;\n");
+    if let Some(ref csv) = root_entity.csv {
+        code.push_str(&format!("    let separator = separator.with_quote({:?}, {:?});\n", csv.quote, csv.escape));
+    }
+    code.push_str("    let inputs: Box<dyn Iterator<Item = io::Result<(String, io::Result<Box<dyn BufRead>>)>>> = if cli.stdin_content {\n");
+    code.push_str("        let stdin = nomnom::runtime::decompress_reader(io::stdin().lock(), codec);\n");
+    code.push_str("        Box::new(std::iter::once(Ok((\"<stdin>\".to_string(), stdin))))\n");
+    code.push_str("    } else if !cli.globs.is_empty() || !cli.dirs.is_empty() {\n");
+    code.push_str("        let files = nomnom::runtime::expand_inputs(&cli.globs, &cli.dirs, cli.recursive, &cli.extensions)?;\n");
+    code.push_str("        Box::new(files.into_iter().map(move |path| {\n");
+    code.push_str("            let reader = nomnom::runtime::open_input(&path, codec);\n");
+    code.push_str("            Ok((path.display().to_string(), reader))\n");
+    code.push_str("        }))\n");
+    code.push_str("    } else {\n");
+    code.push_str("        Box::new(io::stdin().lock().lines()\n");
+    code.push_str("            .filter(|path| path.as_ref().map_or(true, |path| !path.trim().is_empty()))\n");
+    code.push_str("            .map(move |path| {\n");
+    code.push_str("                let path = path?;\n");
+    code.push_str("                let reader = nomnom::runtime::open_input(Path::new(&path), codec);\n");
+    code.push_str("                Ok((path, reader))\n");
+    code.push_str("            }))\n");
+    code.push_str("    };\n\n");
+    code.push_str("    let mut progress = (cli.progress || cli.progress_json).then(|| {\n");
+    code.push_str("        nomnom::runtime::Progress::new(std::time::Duration::from_secs(cli.progress_interval), cli.progress_json)\n");
+    code.push_str("    });\n\n");
+    code.push_str("    for input in inputs {\n");
+    code.push_str("        let (file_path, reader) = input.map_err(|e| format!(\"Error reading input paths from stdin: {}\", e))?;\n");
+    code.push_str("        errors.file_started(&file_path);\n");
+    code.push_str("        let reader = match reader {\n");
+    code.push_str("            Ok(reader) => reader,\n");
+    code.push_str("            Err(e) => {\n");
+    code.push_str("                let e = ParserError::Input { message: e.to_string() };\n");
+    code.push_str("                eprintln!(\"Error reading file '{}': {}\", file_path, e);\n");
+    code.push_str("                errors.record(&e);\n");
+    code.push_str("                errors.file_done()?;\n");
+    code.push_str("                if let Some(ref mut progress) = progress {\n");
+    code.push_str("                    progress.add_errors(1);\n");
+    code.push_str("                }\n");
+    code.push_str("                continue;\n");
+    code.push_str("            }\n");
+    code.push_str("        };\n");
+    code.push_str("        let records = nomnom::runtime::DecodedRecords::new(\n");
+    code.push_str("            nomnom::runtime::DecodedLines::new(reader, encoding),\n");
+    code.push_str("            separator.clone(),\n");
+    code.push_str("        );\n");
+    if root_entity.csv.as_ref().is_some_and(|csv| csv.has_header) {
+        code.push_str("        // The first CSV row is a header, not a record\n");
+        code.push_str("        for record in records.skip(1) {\n");
+    } else {
+        code.push_str("        for record in records {\n");
+    }
+    code.push_str("            let record = match record {\n");
+    code.push_str("                Ok(record) => record,\n");
+    code.push_str("                Err(e) => {\n");
+    code.push_str("                    let e = ParserError::Input { message: e };\n");
+    code.push_str("                    eprintln!(\"Error reading file '{}': {}\", file_path, e);\n");
+    code.push_str("                    errors.record(&e);\n");
+    code.push_str("                    if let Some(ref mut progress) = progress {\n");
+    code.push_str("                        progress.add_errors(1);\n");
+    code.push_str("                    }\n");
+    code.push_str("                    break;\n");
+    code.push_str("                }\n");
+    code.push_str("            };\n\n");
 
     if has_database_support {
-        code.push_str("        match process_file(\n");
-        code.push_str("            &file_path,\n");
-        code.push_str("            show_json,\n");
-        code.push_str("            show_sql,\n");
-        code.push_str("            cli.lineage,\n");
-        code.push_str("            cli.show_lineage,\n");
-        code.push_str("            lineage_format,\n");
-        code.push_str("            db_pool.as_ref(),\n");
-        code.push_str("            cli.verbose,\n");
-        code.push_str("        ) {\n");
+        code.push_str("            match process_file(\n");
+        code.push_str("                &record,\n");
+        code.push_str("                show_json,\n");
+        code.push_str("                show_sql,\n");
+        code.push_str("                cli.lineage,\n");
+        code.push_str("                cli.show_lineage,\n");
+        code.push_str("                lineage_format,\n");
+        code.push_str("                cli.on_entity_error,\n");
+        code.push_str("                db_writer.as_mut(),\n");
+        code.push_str("                cli.verbose,\n");
+        code.push_str("                progress.as_mut(),\n");
+        code.push_str("            ) {\n");
     } else {
-        code.push_str("        match process_file(&file_path, show_json, show_sql, cli.lineage, cli.show_lineage, lineage_format) {\n");
+        code.push_str("            match process_file(&record, show_json, show_sql, cli.lineage, cli.show_lineage, lineage_format, cli.on_entity_error, progress.as_mut()) {\n");
     }
 
-    code.push_str("            Ok(_) => {},\n");
-    code.push_str("            Err(e) => {\n");
-    code.push_str("                eprintln!(\"Error processing file '{}': {}\", file_path, e);\n");
-    code.push_str("                // Continue to next file\n");
+    code.push_str("                Ok(failures) => {\n");
+    code.push_str("                    if let Some(ref mut progress) = progress {\n");
+    code.push_str("                        progress.add_errors(failures.len());\n");
+    code.push_str("                    }\n");
+    code.push_str("                    for failure in failures {\n");
+    code.push_str("                        eprintln!(\"Skipped {} in '{}': {}\", failure.entity, file_path, failure.error);\n");
+    code.push_str("                        errors.record(&failure.error);\n");
+    code.push_str("                        // Dead letters carry the record so the entity can be replayed\n");
+    code.push_str("                        if let Some(ref mut dead_letter) = dead_letter {\n");
+    code.push_str("                            let entry = serde_json::json!({\n");
+    code.push_str("                                \"file\": file_path,\n");
+    code.push_str("                                \"entity\": failure.entity,\n");
+    code.push_str("                                \"kind\": failure.error.kind(),\n");
+    code.push_str("                                \"error\": failure.error.to_string(),\n");
+    code.push_str("                                \"record\": record,\n");
+    code.push_str("                            });\n");
+    code.push_str("                            writeln!(dead_letter, \"{}\", entry)?;\n");
+    code.push_str("                        }\n");
+    code.push_str("                    }\n");
+    code.push_str("                }\n");
+    code.push_str("                Err(e) => {\n");
+    code.push_str("                    eprintln!(\"Error processing record in '{}': {}\", file_path, e);\n");
+    code.push_str("                    errors.record(&e);\n");
+    code.push_str("                    if let Some(ref mut progress) = progress {\n");
+    code.push_str("                        progress.add_errors(1);\n");
+    code.push_str("                    }\n");
+    code.push_str("                    // Continue to next record\n");
+    code.push_str("                }\n");
+    code.push_str("            }\n");
+    code.push_str("            if let Some(ref mut progress) = progress {\n");
+    code.push_str("                progress.tick();\n");
     code.push_str("            }\n");
     code.push_str("        }\n");
+    if has_database_support {
+        code.push_str("        // A file's records are committed before the next file is read\n");
+        code.push_str("        if let Some(ref mut db_writer) = db_writer {\n");
+        code.push_str("            db_writer.commit()?;\n");
+        code.push_str("        }\n");
+    }
+    code.push_str("        errors.file_done()?;\n");
+    code.push_str("        if let Some(ref mut progress) = progress {\n");
+    code.push_str("            progress.file_done();\n");
+    code.push_str("        }\n");
+    code.push_str("    }\n\n");
+    code.push_str("    if let Some(ref progress) = progress {\n");
+    code.push_str("        progress.finish();\n");
+    code.push_str("    }\n\n");
+    code.push_str("    if cli.fail_on_error && errors.count > 0 {\n");
+    code.push_str("        eprintln!(\"Failed with {} error(s)\", errors.count);\n");
+    code.push_str("        std::process::exit(2);\n");
     code.push_str("    }\n\n");
     code.push_str("    Ok(())\n");
     code.push_str("}\n\n");
 
     // process_file function
-    code.push_str("/// Process a single file path\n");
+    code.push_str("/// Process a single record of an input file\n");
     code.push_str("fn process_file(\n");
     code.push_str("    file_path: &str,\n");
     code.push_str("    show_json: bool,\n");
@@ -430,13 +800,18 @@ fn generate_main_function(root_entity: &EntityDef, has_database_support: bool, e
     code.push_str("    enable_lineage: bool,\n");
     code.push_str("    show_lineage: bool,\n");
     code.push_str("    lineage_format: LineageFormat,\n");
+    code.push_str("    on_entity_error: OnEntityError,\n");
     if has_database_support {
-        code.push_str("    db_pool: Option<&Pool>,\n");
+        code.push_str("    db_writer: Option<&mut DbWriter>,\n");
         code.push_str("    verbose: bool,\n");
     }
-    code.push_str(") -> Result<(), Box<dyn Error>> {\n");
+    code.push_str("    progress: Option<&mut nomnom::runtime::Progress>,\n");
+    code.push_str(") -> Result<Vec<EntityFailure>, ParserError> {\n");
     code.push_str(&format!("    // Create root entity from file path (no registry - transforms are injected)\n"));
-    code.push_str(&format!("    let {} = {}::from_string(file_path)?;\n\n", root_snake, root_core));
+    code.push_str(&format!(
+        "    let {} = {}::from_string(file_path)\n        .map_err(|e| ParserError::Parse {{ entity: \"{}\", message: e.to_string() }})?;\n\n",
+        root_snake, root_core, root_entity.name
+    ));
 
     code.push_str("    // Initialize lineage tracker if needed\n");
     code.push_str("    let mut lineage_tracker = if enable_lineage || show_lineage {\n");
@@ -446,7 +821,13 @@ fn generate_main_function(root_entity: &EntityDef, has_database_support: bool, e
     code.push_str("    };\n\n");
 
     code.push_str("    // Extract all entities (pass ownership of root)\n");
-    code.push_str(&format!("    let (results, entity_shas) = extract_all_entities({}, lineage_tracker.as_mut())?;\n\n", root_snake));
+    // Persisting stores generated ids in the results (see execute_to_database)
+    let results_binding = if has_database_support { "mut results" } else { "results" };
+    code.push_str(&format!("    let ({}, entity_shas, failures) = extract_all_entities({}, lineage_tracker.as_mut(), on_entity_error)?;\n\n", results_binding, root_snake));
+
+    code.push_str("    if let Some(progress) = progress {\n");
+    code.push_str("        progress.record_done(results.entity_counts());\n");
+    code.push_str("    }\n\n");
 
     code.push_str("    // Show lineage tree if requested\n");
     code.push_str("    if show_lineage {\n");
@@ -455,14 +836,15 @@ fn generate_main_function(root_entity: &EntityDef, has_database_support: bool, e
     code.push_str("            eprintln!(\"{}\\\\n\", tree);\n");
     code.push_str("        }\n");
     code.push_str("        // When showing lineage, suppress JSON/SQL output\n");
-    code.push_str("        return Ok(());\n");
+    code.push_str("        return Ok(failures);\n");
     code.push_str("    }\n\n");
 
     // Database execution if supported
     if has_database_support {
         code.push_str("    // Execute to database if requested\n");
-        code.push_str("    if let Some(pool) = db_pool {\n");
-        code.push_str("        let stats = execute_to_database(&results, pool, verbose)?;\n\n");
+        code.push_str("    if let Some(db_writer) = db_writer {\n");
+        code.push_str("        let stats = db_writer.write(&mut results, verbose)\n");
+        code.push_str("            .map_err(|e| ParserError::Persistence { message: e.to_string() })?;\n\n");
         code.push_str("        if verbose {\n");
         code.push_str("            eprintln!(\"✓ Database execution complete:\");\n");
 
@@ -489,21 +871,23 @@ fn generate_main_function(root_entity: &EntityDef, has_database_support: bool, e
         code.push_str("            eprintln!(\"✓ Database execution successful\");\n");
         code.push_str("        }\n\n");
         code.push_str("        // When executing to database, suppress JSON/SQL output\n");
-        code.push_str("        return Ok(());\n");
+        code.push_str("        return Ok(failures);\n");
         code.push_str("    }\n\n");
     }
 
     code.push_str("    // Output JSON if requested\n");
     code.push_str("    if show_json {\n");
-    code.push_str("        output_json_entities(&results, lineage_tracker.as_ref(), &entity_shas, enable_lineage)?;\n");
+    code.push_str("        output_json_entities(&results, lineage_tracker.as_ref(), &entity_shas, enable_lineage)\n");
+    code.push_str("            .map_err(|e| ParserError::Output { message: e.to_string() })?;\n");
     code.push_str("    }\n\n");
 
     code.push_str("    // Output SQL if requested\n");
     code.push_str("    if show_sql {\n");
-    code.push_str("        output_sql_statements(&results)?;\n");
+    code.push_str("        output_sql_statements(&results)\n");
+    code.push_str("            .map_err(|e| ParserError::Output { message: e.to_string() })?;\n");
     code.push_str("    }\n\n");
 
-    code.push_str("    Ok(())\n");
+    code.push_str("    Ok(failures)\n");
     code.push_str("}\n\n");
 
     code
@@ -523,10 +907,12 @@ fn generate_extraction_function(
     code.push_str("fn extract_all_entities(\n");
     code.push_str(&format!("    {}: {},\n", root_snake, root_type));
     code.push_str("    mut lineage_tracker: Option<&mut LineageTracker>,\n");
-    code.push_str(") -> Result<(ParseResults, HashMap<String, String>), Box<dyn Error>> {\n");
+    code.push_str("    on_entity_error: OnEntityError,\n");
+    code.push_str(") -> Result<(ParseResults, HashMap<String, String>, Vec<EntityFailure>), ParserError> {\n");
 
     // Initialize SHA storage if lineage tracking is enabled
-    code.push_str("    let mut entity_shas: HashMap<String, String> = HashMap::new();\n\n");
+    code.push_str("    let mut entity_shas: HashMap<String, String> = HashMap::new();\n");
+    code.push_str("    let mut failures: Vec<EntityFailure> = Vec::new();\n\n");
 
     // Compute SHA for root entity if lineage tracking is enabled
     code.push_str("    if let Some(tracker) = lineage_tracker.as_mut() {\n");
@@ -550,6 +936,10 @@ fn generate_extraction_function(
         let var_name = to_snake_case(&entity.name);
         let type_name = format!("{}Core", entity.name);
         let parents = entity.get_parents();
+        // Singleton ancestors read by name follow the parents in the constructor
+        let ancestor_args: String = entity.ancestor_sources(all_entities).iter()
+            .map(|ancestor| format!(", &{}", to_snake_case(ancestor)))
+            .collect();
 
         // Skip if no parents (should not happen after topological sort)
         if parents.is_empty() {
@@ -559,17 +949,27 @@ fn generate_extraction_function(
 
         // Determine extraction method based on repetition and parents
         let is_repeated = entity.repetition.as_ref().map(|r| r == "repeated").unwrap_or(false)
-            || entity.repeated_for.is_some();
+            || entity.repeating_parent().is_some();
         let has_single_parent = parents.len() == 1;
 
         if is_repeated && has_single_parent {
             // Repeated entity with single parent: Vec<EntityCore>
             // Use from_parent_repeated which returns Vec<Self>
+            // A failed repeated entity is left empty under skip/dlq
             let parent_var = to_snake_case(&parents[0]);
             code.push_str(&format!(
-                "    let {} = {}::from_parent_repeated(&{})?;\n",
-                var_name, type_name, parent_var
+                "    let {} = match {}::from_parent_repeated(&{}{}) {{\n",
+                var_name, type_name, parent_var, ancestor_args
             ));
+            code.push_str("        Ok(items) => items,\n");
+            code.push_str("        Err(e) => {\n");
+            code.push_str(&format!(
+                "            entity_failed(on_entity_error, \"{}\", e, &mut failures)?;\n",
+                entity.name
+            ));
+            code.push_str("            Vec::new()\n");
+            code.push_str("        }\n");
+            code.push_str("    };\n");
 
             // Compute SHA for each item in repeated entity
             code.push_str("    if let Some(tracker) = lineage_tracker.as_mut() {\n");
@@ -615,6 +1015,7 @@ fn generate_extraction_function(
                 }
 
                 let repeated_parent_var = to_snake_case(&parents[repeated_idx]);
+                let primary_item_var = to_snake_case(&format!("{}_item", parents[repeated_idx]));
                 code.push_str(&format!("    let mut {} = Vec::new();\n", var_name));
 
                 // Use indexed iteration if there are other repeated parents
@@ -630,7 +1031,51 @@ fn generate_extraction_function(
                     ));
                 }
 
-                code.push_str(&format!("        let item = {}::from_sources(",  type_name));
+                let other_index = |parent_var: &str| match entity.join_on {
+                    Some(_) => format!("{}_idx", parent_var),
+                    None => "idx".to_string(),
+                };
+
+                // With join_on, correlate the other repeated parents by key instead of
+                // position (rows without a matching key are skipped). Like SQL, a
+                // missing key never matches anything, including another missing key.
+                if let Some(ref key) = entity.join_on {
+                    let key_nullable = |parent_name: &str| {
+                        all_entities.iter()
+                            .find(|e| e.name == parent_name)
+                            .and_then(|e| e.fields.iter().find(|f| &f.name == key))
+                            .map(|f| f.nullable)
+                            .unwrap_or(false)
+                    };
+
+                    if key_nullable(&parents[repeated_idx]) {
+                        code.push_str(&format!(
+                            "        let Some(ref join_key) = {}.{} else {{\n",
+                            primary_item_var, key
+                        ));
+                        code.push_str("            continue;\n");
+                        code.push_str("        };\n");
+                    } else {
+                        code.push_str(&format!("        let join_key = &{}.{};\n", primary_item_var, key));
+                    }
+
+                    for &i in &other_repeated_parents {
+                        let parent_var = to_snake_case(&parents[i]);
+                        let matches = if key_nullable(&parents[i]) {
+                            format!("p.{}.as_ref() == Some(join_key)", key)
+                        } else {
+                            format!("&p.{} == join_key", key)
+                        };
+                        code.push_str(&format!(
+                            "        let Some({}_idx) = {}.iter().position(|p| {}) else {{\n",
+                            parent_var, parent_var, matches
+                        ));
+                        code.push_str("            continue;\n");
+                        code.push_str("        };\n");
+                    }
+                }
+
+                code.push_str(&format!("        let item = match {}::from_sources(",  type_name));
                 for (i, parent) in parents.iter().enumerate() {
                     if i > 0 {
                         code.push_str(", ");
@@ -639,16 +1084,26 @@ fn generate_extraction_function(
                         // Use the loop item variable
                         code.push_str(&to_snake_case(&format!("{}_item", parents[i])));
                     } else if other_repeated_parents.contains(&i) {
-                        // Other repeated parent - access by index
+                        // Other repeated parent - access by matched key or by index
                         let parent_var = to_snake_case(parent);
-                        code.push_str(&format!("&{}[idx]", parent_var));
+                        code.push_str(&format!("&{}[{}]", parent_var, other_index(&parent_var)));
                     } else {
                         // Non-repeated parent - use reference
                         let parent_var = to_snake_case(parent);
                         code.push_str(&format!("&{}", parent_var));
                     }
                 }
-                code.push_str(")?;\n");
+                code.push_str(&ancestor_args);
+                code.push_str(") {\n");
+                code.push_str("            Ok(item) => item,\n");
+                code.push_str("            Err(e) => {\n");
+                code.push_str(&format!(
+                    "                entity_failed(on_entity_error, \"{}\", e, &mut failures)?;\n",
+                    entity.name
+                ));
+                code.push_str("                continue;\n");
+                code.push_str("            }\n");
+                code.push_str("        };\n");
 
                 // Compute SHA for the repeated multi-parent entity (before pushing)
                 code.push_str("\n        if let Some(tracker) = lineage_tracker.as_mut() {\n");
@@ -665,7 +1120,7 @@ fn generate_extraction_function(
                         }
                     } else if other_repeated_parents.contains(&i) {
                         // Other repeated parent
-                        code.push_str(&format!("                entity_shas.get(&format!(\"{}[{{}}]\", idx)).cloned().unwrap_or_default(),\n", parent));
+                        code.push_str(&format!("                entity_shas.get(&format!(\"{}[{{}}]\", {})).cloned().unwrap_or_default(),\n", parent, other_index(&to_snake_case(parent))));
                     } else {
                         // Singleton parent
                         code.push_str(&format!("                entity_shas.get(\"{}\").cloned().unwrap_or_default(),\n", parent));
@@ -692,7 +1147,8 @@ fn generate_extraction_function(
                     let parent_var = to_snake_case(parent);
                     code.push_str(&format!("&{}", parent_var));
                 }
-                code.push_str(")?;\n");
+                code.push_str(&ancestor_args);
+                code.push_str(&format!(").map_err(|e| ParserError::Transform {{ entity: \"{}\", message: e.to_string() }})?;\n", entity.name));
             }
         } else {
             // Singleton entity: use from_sources
@@ -708,7 +1164,8 @@ fn generate_extraction_function(
                 let parent_var = to_snake_case(parent);
                 code.push_str(&format!("&{}", parent_var));
             }
-            code.push_str(")?;\n");
+            code.push_str(&ancestor_args);
+            code.push_str(&format!(").map_err(|e| ParserError::Transform {{ entity: \"{}\", message: e.to_string() }})?;\n", entity.name));
 
             // Compute SHA for singleton entity if lineage tracking is enabled
             code.push_str("    if let Some(tracker) = lineage_tracker.as_mut() {\n");
@@ -738,7 +1195,7 @@ fn generate_extraction_function(
         let var_name = to_snake_case(&entity.name);
         code.push_str(&format!("        {},\n", var_name));
     }
-    code.push_str("    }, entity_shas))\n");
+    code.push_str("    }, entity_shas, failures))\n");
     code.push_str("}\n\n");
 
     code
@@ -765,7 +1222,7 @@ fn generate_json_output_function(extraction_order: &[EntityDef]) -> String {
         let var_name = to_snake_case(&entity.name);
         let entity_name = &entity.name;
         let is_repeated = entity.repetition.as_ref().map(|r| r == "repeated").unwrap_or(false)
-            || entity.repeated_for.is_some();
+            || entity.repeating_parent().is_some();
 
         if is_repeated {
             // Repeated entity: output with index
@@ -773,6 +1230,11 @@ fn generate_json_output_function(extraction_order: &[EntityDef]) -> String {
                 "    for (i, entity) in results.{}.iter().enumerate() {{\n",
                 var_name
             ));
+            if entity.when.is_some() {
+                code.push_str("        if !entity.when_satisfied() {\n");
+                code.push_str("            continue;\n");
+                code.push_str("        }\n");
+            }
             code.push_str("        let mut json = serde_json::json!({\n");
             code.push_str(&format!(
                 "            \"entity_type\": format!(\"{}[{{}}]\", i),\n",
@@ -795,8 +1257,12 @@ fn generate_json_output_function(extraction_order: &[EntityDef]) -> String {
             code.push_str("        println!(\"{}\", serde_json::to_string(&json)?);\n");
             code.push_str("    }\n");
         } else {
-            // Singleton entity
-            code.push_str("    {\n");
+            // Singleton entity (skipped when its `when` condition fails)
+            if entity.when.is_some() {
+                code.push_str(&format!("    if results.{}.when_satisfied() {{\n", var_name));
+            } else {
+                code.push_str("    {\n");
+            }
             code.push_str("        let mut json = serde_json::json!({\n");
             code.push_str(&format!("            \"entity_type\": \"{}\",\n", entity_name));
             code.push_str(&format!("            \"data\": serde_json::to_value(&results.{})?,\n", var_name));
@@ -823,7 +1289,7 @@ fn generate_json_output_function(extraction_order: &[EntityDef]) -> String {
 }
 
 /// Generate SQL output function
-fn generate_sql_output_function(permanent_entities: &[&EntityDef], all_entities: &[EntityDef]) -> String {
+fn generate_sql_output_function(permanent_entities: &[&EntityDef], all_entities: &[EntityDef]) -> Result<String, String> {
     let mut code = String::new();
 
     code.push_str("/// Output SQL statements for permanent entities\n");
@@ -843,15 +1309,24 @@ fn generate_sql_output_function(permanent_entities: &[&EntityDef], all_entities:
         let function_name = format!("output_{}_sql", to_snake_case(&entity.name));
         let var_name = to_snake_case(&entity.name);
         let is_repeated = entity.repetition.as_ref().map(|r| r == "repeated").unwrap_or(false)
-            || entity.repeated_for.is_some();
+            || entity.repeating_parent().is_some();
 
         if is_repeated {
             code.push_str(&format!(
                 "    for (i, entity) in results.{}.iter().enumerate() {{\n",
                 var_name
             ));
+            if entity.when.is_some() {
+                code.push_str("        if !entity.when_satisfied() {\n");
+                code.push_str("            continue;\n");
+                code.push_str("        }\n");
+            }
             code.push_str(&format!("        {}(entity, Some(i))?;\n", function_name));
             code.push_str("    }\n");
+        } else if entity.when.is_some() {
+            code.push_str(&format!("    if results.{}.when_satisfied() {{\n", var_name));
+            code.push_str(&format!("        {}(&results.{}, None)?;\n", function_name, var_name));
+            code.push_str("    }\n");
         } else {
             code.push_str(&format!("    {}(&results.{}, None)?;\n", function_name, var_name));
         }
@@ -862,10 +1337,10 @@ fn generate_sql_output_function(permanent_entities: &[&EntityDef], all_entities:
 
     // Generate SQL output function for each permanent entity
     for entity in permanent_entities {
-        code.push_str(&generate_entity_sql_function(entity, all_entities));
+        code.push_str(&generate_entity_sql_function(entity, all_entities)?);
     }
 
-    code
+    Ok(code)
 }
 
 /// Get SQL helper function name for a field type
@@ -874,6 +1349,8 @@ fn get_sql_opt_function(field_type: &str, nullable: bool) -> &'static str {
         ("String", false) => "sql_opt_string",
         ("String", true) => "sql_opt_string_option",
         ("List[Object]" | "List[Json]", _) => "sql_opt_json_array",
+        ("Object" | "Json", false) => "sql_opt_json",
+        ("Object" | "Json", true) => "sql_opt_json_option",
         (_, true) => "sql_opt_option",
         (_, false) => "sql_opt",
     }
@@ -890,7 +1367,7 @@ fn get_sql_cmp_function(field_type: &str, nullable: bool) -> &'static str {
 }
 
 /// Generate SQL output function for a single entity
-fn generate_entity_sql_function(entity: &EntityDef, all_entities: &[EntityDef]) -> String {
+fn generate_entity_sql_function(entity: &EntityDef, all_entities: &[EntityDef]) -> Result<String, String> {
     let db_config = entity.get_database_config(all_entities)
         .expect("Permanent entity must have database config");
 
@@ -939,7 +1416,7 @@ fn generate_entity_sql_function(entity: &EntityDef, all_entities: &[EntityDef])
         };
         code.push_str(&format!(
             "    println!(\"{}{} {{}}\", {}(&entity.{}));\n",
-            separator, field_name, cmp_fn, field_name
+            separator, entity.column_name(field_name), cmp_fn, field_name
         ));
     }
 
@@ -960,20 +1437,38 @@ fn generate_entity_sql_function(entity: &EntityDef, all_entities: &[EntityDef])
         insert_fields.push((&field.name, &field.field_type, field.nullable));
     }
 
+    // `pre_insert_sql` columns print their expression around the field literals
+    let field_names: Vec<&str> = insert_fields.iter().map(|(name, _, _)| name.as_str()).collect();
+    let insert_values = db_config.insert_values(&field_names, |name| entity.column_name(name).to_string())?;
+
     code.push_str(&format!(
         "    println!(\"INSERT INTO {} ({})\");\n",
         table_name,
-        insert_fields.iter().map(|(name, _, _)| name.as_str()).collect::<Vec<_>>().join(", ")
+        insert_values.iter().map(|(column, _)| column.as_str()).collect::<Vec<_>>().join(", ")
     ));
     code.push_str("    println!(\"VALUES\");\n");
     code.push_str("    print!(\"  (\");\n");
 
-    for (i, (field_name, field_type, nullable)) in insert_fields.iter().enumerate() {
+    for (i, (_, value)) in insert_values.iter().enumerate() {
         if i > 0 {
             code.push_str("    print!(\", \");\n");
         }
-        let opt_fn = get_sql_opt_function(field_type, *nullable);
-        code.push_str(&format!("    print!(\"{{}}\", {}(&entity.{}));\n", opt_fn, field_name));
+        let parts = match value {
+            InsertValue::Bind(field) => vec![SqlPart::Field(field.clone())],
+            InsertValue::Sql(parts) => parts.clone(),
+        };
+        for part in parts {
+            match part {
+                SqlPart::Text(sql) => code.push_str(&format!("    print!(\"{{}}\", {:?});\n", sql)),
+                SqlPart::Field(field_name) => {
+                    let (_, field_type, nullable) = insert_fields.iter()
+                        .find(|(name, _, _)| **name == field_name)
+                        .ok_or_else(|| format!("{}: no inserted field '{}'", entity.name, field_name))?;
+                    let opt_fn = get_sql_opt_function(field_type, *nullable);
+                    code.push_str(&format!("    print!(\"{{}}\", {}(&entity.{}));\n", opt_fn, field_name));
+                }
+            }
+        }
     }
 
     code.push_str("    println!(\")\");\n");
@@ -983,7 +1478,59 @@ fn generate_entity_sql_function(entity: &EntityDef, all_entities: &[EntityDef])
     code.push_str("    Ok(())\n");
     code.push_str("}\n\n");
 
-    code
+    Ok(code)
+}
+
+/// Generate an UPDATE applying an entity's `pre_insert_sql` expressions to the
+/// row just inserted through Diesel (which can only bind values)
+///
+/// Field references become column references, so the expressions see the
+/// inserted values. The row is found by its unicity fields on `core_expr`.
+fn generate_pre_insert_sql_update(entity: &EntityDef, all_entities: &[EntityDef], core_expr: &str, indent: &str) -> Result<String, String> {
+    let db_config = entity.get_database_config(all_entities)
+        .ok_or_else(|| format!("Entity {} has no database config", entity.name))?;
+    if db_config.pre_insert_sql.is_empty() {
+        return Ok(String::new());
+    }
+
+    let field_names: Vec<&str> = entity.fields.iter().map(|f| f.name.as_str()).collect();
+    let assignments = db_config.insert_values(&field_names, |name| entity.column_name(name).to_string())?
+        .into_iter()
+        .filter_map(|(column, value)| match value {
+            InsertValue::Sql(_) => Some(format!(
+                "{} = {}",
+                column,
+                value.to_sql(&mut |field| entity.column_name(field).to_string())
+            )),
+            InsertValue::Bind(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut conditions = Vec::new();
+    let mut args = Vec::new();
+    for field_name in &db_config.unicity_fields {
+        let cmp_fn = entity.fields.iter()
+            .find(|f| &f.name == field_name)
+            .map(|f| get_sql_cmp_function(&f.field_type, f.nullable))
+            .unwrap_or("sql_cmp_string_option");
+        conditions.push(format!("{} {{}}", entity.column_name(field_name)));
+        args.push(format!("{}(&{}.{})", cmp_fn, core_expr, field_name));
+    }
+    if conditions.is_empty() {
+        return Err(format!("{}: pre_insert_sql with --execute-db needs unicity_fields to find the inserted row", entity.name));
+    }
+
+    let template = format!(
+        "UPDATE {} SET {} WHERE ",
+        db_config.conformant_table,
+        assignments.join(", ")
+    ).replace('{', "{{").replace('}', "}}") + &conditions.join(" AND ");
+
+    let mut code = String::new();
+    code.push_str(&format!("{}// Apply pre_insert_sql expressions to the new row\n", indent));
+    code.push_str(&format!("{}diesel::sql_query(format!({:?}, {}))\n", indent, template, args.join(", ")));
+    code.push_str(&format!("{}    .execute(conn)?;\n\n", indent));
+    Ok(code)
 }
 
 /// Generate database execution function
@@ -991,25 +1538,26 @@ fn generate_execute_to_database_function(
     extraction_order: &[EntityDef],
     permanent_entities: &[&EntityDef],
     all_entities: &[EntityDef],
-) -> String {
+) -> Result<String, String> {
     let mut code = String::new();
 
     code.push_str("/// Execute entities to database using Diesel\n");
+    code.push_str("///\n");
+    code.push_str("/// A singleton's generated id is stored in its id field once its row is\n");
+    code.push_str("/// written, and copied into the entities that `copy_field` it.\n");
     code.push_str("fn execute_to_database(\n");
-    code.push_str("    results: &ParseResults,\n");
-    code.push_str("    pool: &Pool,\n");
+    code.push_str("    results: &mut ParseResults,\n");
+    code.push_str("    conn: &mut PooledConnection,\n");
     code.push_str("    verbose: bool,\n");
     code.push_str(") -> Result<ExecutionStats, Box<dyn Error>> {\n");
     code.push_str("    use diesel::prelude::*;\n\n");
-    code.push_str("    let mut stats = ExecutionStats::default();\n");
-    code.push_str("    let mut conn = pool.get()\n");
-    code.push_str("        .map_err(|e| format!(\"Failed to get database connection: {}\", e))?;\n\n");
+    code.push_str("    let mut stats = ExecutionStats::default();\n\n");
 
-    code.push_str("    // Execute in transaction for atomicity\n");
+    code.push_str("    // Execute in transaction for atomicity (a savepoint of the DbWriter batch)\n");
     code.push_str("    conn.transaction::<_, Box<dyn Error>, _>(|conn| {\n");
 
     // Collect all unique table names for imports
-    let mut table_names = std::collections::HashSet::new();
+    let mut table_names = std::collections::BTreeSet::new();
     for entity in extraction_order {
         if entity.is_persistent(all_entities) && !entity.is_abstract && entity.source_type.to_lowercase() != "reference" {
             if let Some(db_config) = entity.get_database_config(all_entities) {
@@ -1055,7 +1603,7 @@ fn generate_execute_to_database_function(
         let unicity_fields = &db_config.unicity_fields;
 
         let is_repeated = entity.repetition.as_ref().map(|r| r == "repeated").unwrap_or(false)
-            || entity.repeated_for.is_some();
+            || entity.repeating_parent().is_some();
 
         if is_repeated {
             // Process repeated entity (Vec<EntityCore>)
@@ -1065,6 +1613,11 @@ fn generate_execute_to_database_function(
             code.push_str("        }\n\n");
 
             code.push_str(&format!("        for (idx, item_core) in results.{}.iter().enumerate() {{\n", var_name));
+            if entity.when.is_some() {
+                code.push_str("            if !item_core.when_satisfied() {\n");
+                code.push_str("                continue;\n");
+                code.push_str("            }\n");
+            }
             code.push_str(&format!("            let new_item: {} = item_core.into();\n\n", new_type_name));
 
             code.push_str("            if verbose {\n");
@@ -1106,6 +1659,7 @@ fn generate_execute_to_database_function(
             code.push_str(&format!("                    diesel::insert_into({}::table)\n", table_name));
             code.push_str("                        .values(new_item)\n");
             code.push_str("                        .execute(conn)?;\n\n");
+            code.push_str(&generate_pre_insert_sql_update(entity, all_entities, "item_core", "                    ")?);
 
             code.push_str("                    if verbose {\n");
             code.push_str("                        eprintln!(\"    ✓ Created new\");\n");
@@ -1117,6 +1671,9 @@ fn generate_execute_to_database_function(
         } else {
             // Process singleton entity
             code.push_str(&format!("        // Process {} (singleton)\n", entity.name));
+            if entity.when.is_some() {
+                code.push_str(&format!("        if results.{}.when_satisfied() {{\n", var_name));
+            }
             code.push_str("        if verbose {\n");
             code.push_str(&format!("            eprintln!(\"Inserting {}: {{:?}}\", results.{}.{});\n",
                 entity.name, var_name, unicity_fields[0]));
@@ -1146,10 +1703,13 @@ fn generate_execute_to_database_function(
             code.push_str("            None => {\n");
 
             // Insert new record
+            // The generated id is read back by the unicity fields of new_item
+            let has_generated_id = entity.generated_id_field(all_entities).is_some();
             code.push_str("                // Insert new record\n");
             code.push_str(&format!("                diesel::insert_into({}::table)\n", table_name));
-            code.push_str("                    .values(new_item)\n");
+            code.push_str(if has_generated_id { "                    .values(&new_item)\n" } else { "                    .values(new_item)\n" });
             code.push_str("                    .execute(conn)?;\n\n");
+            code.push_str(&generate_pre_insert_sql_update(entity, all_entities, &format!("results.{}", var_name), "                ")?);
 
             code.push_str("                if verbose {\n");
             code.push_str("                    eprintln!(\"  ✓ Created new\");\n");
@@ -1157,6 +1717,12 @@ fn generate_execute_to_database_function(
             code.push_str(&format!("                stats.{}_created += 1;\n", var_name));
             code.push_str("            }\n");
             code.push_str("        }\n\n");
+            if let Some(id_field) = entity.generated_id_field(all_entities) {
+                code.push_str(&generate_generated_id_linkage(entity, &id_field.name, extraction_order, &model_type_name, table_name, unicity_fields));
+            }
+            if entity.when.is_some() {
+                code.push_str("        }\n\n");
+            }
         }
     }
 
@@ -1164,9 +1730,124 @@ fn generate_execute_to_database_function(
     code.push_str("    })\n");
     code.push_str("}\n\n");
 
+    code.push_str(DB_WRITER);
+
+    Ok(code)
+}
+
+/// Whether a parse result holds a `Vec` of the entity
+fn is_repeated_entity(entity: &EntityDef) -> bool {
+    entity.repetition.as_ref().map(|r| r == "repeated").unwrap_or(false)
+        || entity.repeating_parent().is_some()
+}
+
+/// Fields of entities extracted after `parent` that `copy_field` its
+/// generated id field `id_field`
+fn generated_id_copies<'a>(
+    parent: &EntityDef,
+    id_field: &str,
+    extraction_order: &'a [EntityDef],
+) -> Vec<(&'a EntityDef, &'a str)> {
+    extraction_order.iter()
+        .filter(|entity| !entity.is_abstract && entity.name != parent.name)
+        .flat_map(|entity| entity.fields.iter().map(move |field| (entity, field)))
+        .filter(|(_, field)| field.computed_from.as_ref().is_some_and(|computed| {
+            computed.transform == "copy_field"
+                && computed.sources.first().is_some_and(|source| {
+                    source.source_name() == parent.name && source.field_name() == Some(id_field)
+                })
+        }))
+        .map(|(entity, field)| (entity, field.name.as_str()))
+        .collect()
+}
+
+/// Store a singleton's generated id (read back from its row, whether just
+/// inserted or already stored) and copy it into the fields linking to it
+fn generate_generated_id_linkage(
+    entity: &EntityDef,
+    id_field: &str,
+    extraction_order: &[EntityDef],
+    model_type_name: &str,
+    table_name: &str,
+    unicity_fields: &[String],
+) -> String {
+    let var_name = to_snake_case(&entity.name);
+    let mut code = String::new();
+
+    code.push_str(&format!("        // Store {}'s generated id for the entities linking to it\n", entity.name));
+    code.push_str(&format!("        let row = {}::table\n", table_name));
+    for field in unicity_fields {
+        code.push_str(&format!("            .filter({}::{}.eq(&new_item.{}))\n", table_name, field, field));
+    }
+    code.push_str(&format!("            .first::<{}>(conn)?;\n", model_type_name));
+    code.push_str(&format!("        results.{}.{} = Some(row.{}.into());\n", var_name, id_field, id_field));
+    for (child, field) in generated_id_copies(entity, id_field, extraction_order) {
+        let child_var = to_snake_case(&child.name);
+        if is_repeated_entity(child) {
+            code.push_str(&format!("        for item in results.{}.iter_mut() {{\n", child_var));
+            code.push_str(&format!("            item.{} = results.{}.{};\n", field, var_name, id_field));
+            code.push_str("        }\n");
+        } else {
+            code.push_str(&format!("        results.{}.{} = results.{}.{};\n", child_var, field, var_name, id_field));
+        }
+    }
+    code.push('\n');
     code
 }
 
+/// `DbWriter`, grouping --execute-db records into --commit-every transactions
+const DB_WRITER: &str = r#"/// Database connection of an --execute-db run
+///
+/// A file's records are written in one transaction, committed when the file
+/// ends, or with --commit-every N in transactions of N records. Each record
+/// is a savepoint of that transaction, so a failed one only rolls back
+/// itself.
+struct DbWriter {
+    conn: PooledConnection,
+    batches: nomnom::runtime::CommitBatches,
+}
+
+impl DbWriter {
+    fn new(pool: &Pool, commit_every: Option<usize>) -> Result<Self, Box<dyn Error>> {
+        let conn = pool.get()
+            .map_err(|e| format!("Failed to get database connection: {}", e))?;
+        Ok(Self { conn, batches: nomnom::runtime::CommitBatches::new(commit_every) })
+    }
+
+    /// Write one record's entities, committing the batch once it is full
+    fn write(&mut self, results: &mut ParseResults, verbose: bool) -> Result<ExecutionStats, Box<dyn Error>> {
+        use diesel::connection::{Connection, TransactionManager};
+
+        if self.batches.begins_batch() {
+            <PooledConnection as Connection>::TransactionManager::begin_transaction(&mut self.conn)?;
+        }
+        let stats = execute_to_database(results, &mut self.conn, verbose);
+        if let Some(records) = self.batches.record_done() {
+            self.commit_batch(records)?;
+        }
+        stats
+    }
+
+    /// Commit the open batch at the end of a file
+    fn commit(&mut self) -> Result<(), Box<dyn Error>> {
+        if let Some(records) = self.batches.file_done() {
+            self.commit_batch(records)?;
+        }
+        Ok(())
+    }
+
+    /// Commit a batch of `records` records (a failed commit rolls them all back)
+    fn commit_batch(&mut self, records: usize) -> Result<(), Box<dyn Error>> {
+        use diesel::connection::{Connection, TransactionManager};
+
+        <PooledConnection as Connection>::TransactionManager::commit_transaction(&mut self.conn)
+            .map_err(|e| format!("Failed to commit {} record(s), rolled back: {}", records, e))?;
+        Ok(())
+    }
+}
+
+"#;
+
 /// Generate SQL helper functions
 fn generate_sql_helpers() -> String {
     r#"/// Format any value as SQL literal
@@ -1203,6 +1884,19 @@ fn sql_opt_json_array(arr: &Vec<serde_json::Value>) -> String {
     }
 }
 
+/// Format serde_json::Value as SQL (JSON document)
+fn sql_opt_json(value: &serde_json::Value) -> String {
+    format!("'{}'", sql_escape(&value.to_string()))
+}
+
+/// Format Option<serde_json::Value> as SQL (JSON document or NULL)
+fn sql_opt_json_option(opt: &Option<serde_json::Value>) -> String {
+    match opt {
+        Some(v) => sql_opt_json(v),
+        None => "NULL".to_string(),
+    }
+}
+
 /// Format String as SQL comparison (field = 'value')
 fn sql_cmp_string(s: &String) -> String {
     format!("= '{}'", sql_escape(s))
@@ -1235,3 +1929,124 @@ fn sql_escape(s: &str) -> String {
 }
 "#.to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_config() -> ProjectBuildConfig {
+        serde_yaml::from_str(r#"
+project:
+  name: orders
+  module_name: orders
+
+paths:
+  config_dir: config/entities
+  outputs:
+    rust_entities: src/generated.rs
+    pyo3_bindings: src/generated_bindings.rs
+"#).unwrap()
+    }
+
+    fn order_entities() -> Vec<EntityDef> {
+        crate::codegen::parse_entities(r#"
+entity:
+  name: Order
+  source_type: root
+  fields:
+    - name: code
+      type: String
+"#).unwrap()
+    }
+
+    #[test]
+    fn test_error_log_reports_files_and_dead_letter_keeps_records() {
+        let code = generate_parser_binary(&build_config(), &order_entities()).unwrap();
+
+        // --error-log: one line per failed file, listing its errors
+        assert!(code.contains("fn file_done(&mut self) -> io::Result<()>"), "{}", code);
+        assert!(code.contains("\"errors\": std::mem::take(&mut self.file_errors),"), "{}", code);
+        assert!(code.contains("errors.file_started(&file_path);"), "{}", code);
+        assert_eq!(code.matches("errors.file_done()?;").count(), 2, "{}", code);
+        // Errors buffered for a file are still written when the run stops mid-file
+        assert!(code.contains("impl Drop for RunErrors {"), "{}", code);
+
+        // --dead-letter: one line per failed entity, with its record for replay
+        assert!(code.contains("\"record\": record,"), "{}", code);
+        assert_eq!(code.matches("\"error\": failure.error.to_string(),").count(), 1, "{}", code);
+    }
+
+    /// Order with a generated id, linked from a singleton and a repeated child
+    fn linked_entities(order_repeated: bool) -> Vec<EntityDef> {
+        let repetition = if order_repeated { "repetition: repeated" } else { "" };
+        crate::codegen::parse_entities(&format!(r#"
+entity:
+  name: Order
+  source_type: root
+  {}
+  fields:
+    - name: order_key
+      type: String
+    - name: id
+      type: Integer
+      nullable: true
+    - name: lines
+      type: List[String]
+  persistence:
+    database: {{conformant_table: orders, conformant_id_column: id, unicity_fields: [order_key]}}
+    primary_key: {{name: id, type: Integer, autogenerate: true}}
+---
+entity:
+  name: Shipment
+  source_type: derived
+  parent: Order
+  fields:
+    - name: order_id
+      type: Integer
+      nullable: true
+      computed_from:
+        transform: copy_field
+        sources:
+          - source: Order
+            field: id
+---
+entity:
+  name: LineItem
+  source_type: derived
+  parent: Order
+  repeated_for: {{entity: Order, field: lines, each_known_as: line}}
+  fields:
+    - name: order_id
+      type: Integer
+      nullable: true
+      computed_from:
+        transform: copy_field
+        sources:
+          - source: Order
+            field: id
+"#, repetition)).unwrap()
+    }
+
+    #[test]
+    fn test_execute_to_database_links_generated_ids() {
+        let code = generate_parser_binary(&build_config(), &linked_entities(false)).unwrap();
+
+        assert!(code.contains("fn execute_to_database(\n    results: &mut ParseResults,"), "{}", code);
+        assert!(code.contains("db_writer.write(&mut results, verbose)"), "{}", code);
+        assert!(code.contains(".values(&new_item)"), "{}", code);
+
+        // The parent's id is read back from its row, then copied into children
+        let linkage = &code[code.find("// Store Order's generated id").unwrap()..];
+        assert!(linkage.contains(
+            "let row = orders::table\n            .filter(orders::order_key.eq(&new_item.order_key))\n            .first::<Order>(conn)?;\n        results.order.id = Some(row.id.into());"
+        ), "{}", linkage);
+        assert!(linkage.contains("results.shipment.order_id = results.order.id;"), "{}", linkage);
+        assert!(linkage.contains("for item in results.line_item.iter_mut() {\n            item.order_id = results.order.id;\n        }"), "{}", linkage);
+    }
+
+    #[test]
+    fn test_generated_ids_of_repeated_parents_are_rejected() {
+        let err = generate_parser_binary(&build_config(), &linked_entities(true)).unwrap_err();
+        assert!(err.contains("generated ids of repeated entity 'Order'"), "{}", err);
+    }
+}
@@ -3,15 +3,23 @@
 //! This module provides a callback/plugin architecture that allows
 //! domain-specific libraries (like data_processor) to inject custom
 //! code generation logic without modifying nomnom's core.
+//!
+//! Besides the phase hooks, a plugin can emit its own output format: when
+//! [`CodegenCallbacks::output_file`] names a file, [`CodegenPipeline`] opens
+//! it and walks the entities, calling `begin_output`, then `before_entity`,
+//! `on_field` for each field and `after_entity` per entity, then
+//! `end_output`. `finalize` runs last, after the file is written.
 
-use crate::codegen::types::EntityDef;
+use crate::codegen::types::{EntityDef, FieldDef};
 use crate::codegen::fs_utils;
-use std::path::Path;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
 /// Callback trait for domain-specific code generation hooks
 ///
 /// Implement this trait to add custom generation logic that runs
-/// after the main codegen phases.
+/// after the main codegen phases, or to write a custom output format
+/// entity by entity (see the module docs).
 ///
 /// # Example
 ///
@@ -56,6 +64,43 @@ pub trait CodegenCallbacks: Send + Sync {
         let _ = (entities, output_dir);
     }
 
+    /// File the entity hooks write to, relative to the output directory
+    ///
+    /// Without one (the default) the entity hooks below aren't called.
+    fn output_file(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Called once before the first entity, e.g. for a file header
+    fn begin_output(&self, entities: &[EntityDef], out: &mut dyn Write) -> io::Result<()> {
+        let _ = (entities, out);
+        Ok(())
+    }
+
+    /// Called before the fields of each entity
+    fn before_entity(&self, entity: &EntityDef, out: &mut dyn Write) -> io::Result<()> {
+        let _ = (entity, out);
+        Ok(())
+    }
+
+    /// Called for each field of an entity, in declaration order
+    fn on_field(&self, entity: &EntityDef, field: &FieldDef, out: &mut dyn Write) -> io::Result<()> {
+        let _ = (entity, field, out);
+        Ok(())
+    }
+
+    /// Called after the fields of each entity
+    fn after_entity(&self, entity: &EntityDef, out: &mut dyn Write) -> io::Result<()> {
+        let _ = (entity, out);
+        Ok(())
+    }
+
+    /// Called once after the last entity
+    fn end_output(&self, entities: &[EntityDef], out: &mut dyn Write) -> io::Result<()> {
+        let _ = (entities, out);
+        Ok(())
+    }
+
     /// Called at the very end of code generation
     ///
     /// Use this for final post-processing steps like:
@@ -98,8 +143,8 @@ pub struct CodegenPipeline<'a> {
     /// PyO3 configuration (optional)
     pub pyo3_config: Option<crate::codegen::pyo3_codegen::PyO3Config>,
 
-    /// Custom callbacks for domain-specific generation
-    pub callbacks: Option<&'a dyn CodegenCallbacks>,
+    /// Custom callbacks for domain-specific generation, run in registration order
+    callbacks: Vec<&'a dyn CodegenCallbacks>,
 }
 
 impl<'a> CodegenPipeline<'a> {
@@ -110,7 +155,7 @@ impl<'a> CodegenPipeline<'a> {
             output_dir,
             rust_config: Default::default(),
             pyo3_config: None,
-            callbacks: None,
+            callbacks: Vec::new(),
         }
     }
 
@@ -126,12 +171,23 @@ impl<'a> CodegenPipeline<'a> {
         self
     }
 
-    /// Set custom callbacks
+    /// Register a plugin; plugins run in registration order
     pub fn with_callbacks(mut self, callbacks: &'a dyn CodegenCallbacks) -> Self {
-        self.callbacks = Some(callbacks);
+        self.add_callback(callbacks);
         self
     }
 
+    /// Register a plugin on an existing pipeline
+    pub fn add_callback(&mut self, callbacks: &'a dyn CodegenCallbacks) -> &mut Self {
+        self.callbacks.push(callbacks);
+        self
+    }
+
+    /// Registered plugins, in registration order
+    pub fn callbacks(&self) -> &[&'a dyn CodegenCallbacks] {
+        &self.callbacks
+    }
+
     /// Run the complete codegen pipeline
     pub fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
         use crate::codegen::rust_codegen::generate_rust_code;
@@ -145,7 +201,7 @@ impl<'a> CodegenPipeline<'a> {
         println!("✓ Generated Rust entity code: {}", rust_output_path.display());
 
         // Invoke after_rust_code callback
-        if let Some(callbacks) = self.callbacks {
+        for callbacks in &self.callbacks {
             callbacks.after_rust_code(self.entities, self.output_dir);
         }
 
@@ -157,20 +213,28 @@ impl<'a> CodegenPipeline<'a> {
             println!("✓ Generated Python bindings: {}", bindings_output_path.display());
 
             // Invoke after_python_bindings callback
-            if let Some(callbacks) = self.callbacks {
-                let permanent_entities: Vec<&EntityDef> = self.entities.iter()
-                    .filter(|e| e.database.is_some())
-                    .collect();
-                callbacks.after_python_bindings(
-                    self.entities,
-                    &permanent_entities.iter().copied().cloned().collect::<Vec<_>>(),
-                    self.output_dir,
-                );
+            let permanent_entities: Vec<EntityDef> = self.entities.iter()
+                .filter(|e| e.database.is_some())
+                .cloned()
+                .collect();
+            for callbacks in &self.callbacks {
+                callbacks.after_python_bindings(self.entities, &permanent_entities, self.output_dir);
+            }
+        }
+
+        // Phase 3: Plugin output formats
+        for callbacks in &self.callbacks {
+            if let Some(file) = callbacks.output_file() {
+                let path = self.output_dir.join(file);
+                let mut output = io::BufWriter::new(fs_utils::create_file(&path)?);
+                write_plugin_output(*callbacks, self.entities, &mut output)?;
+                output.flush()?;
+                println!("✓ Generated plugin output: {}", path.display());
             }
         }
 
-        // Phase 3: Finalize
-        if let Some(callbacks) = self.callbacks {
+        // Phase 4: Finalize
+        for callbacks in &self.callbacks {
             callbacks.finalize(self.output_dir);
         }
 
@@ -179,10 +243,98 @@ impl<'a> CodegenPipeline<'a> {
     }
 }
 
+/// Walk the entities through a plugin's output hooks
+fn write_plugin_output(
+    callbacks: &dyn CodegenCallbacks,
+    entities: &[EntityDef],
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    callbacks.begin_output(entities, out)?;
+    for entity in entities {
+        callbacks.before_entity(entity, out)?;
+        for field in &entity.fields {
+            callbacks.on_field(entity, field, out)?;
+        }
+        callbacks.after_entity(entity, out)?;
+    }
+    callbacks.end_output(entities, out)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Example plugin writing a Thrift IDL struct per concrete entity
+    struct ThriftIdl;
+
+    impl CodegenCallbacks for ThriftIdl {
+        fn after_python_bindings(&self, _: &[EntityDef], _: &[EntityDef], _: &Path) {}
+
+        fn output_file(&self) -> Option<PathBuf> {
+            Some(PathBuf::from("entities.thrift"))
+        }
+
+        fn begin_output(&self, _entities: &[EntityDef], out: &mut dyn Write) -> io::Result<()> {
+            writeln!(out, "namespace rs entities")
+        }
+
+        fn before_entity(&self, entity: &EntityDef, out: &mut dyn Write) -> io::Result<()> {
+            writeln!(out, "\nstruct {} {{", entity.name)
+        }
+
+        fn on_field(&self, entity: &EntityDef, field: &FieldDef, out: &mut dyn Write) -> io::Result<()> {
+            let id = entity.fields.iter().position(|f| f.name == field.name).unwrap_or(0) + 1;
+            let thrift_type = match field.field_type.as_str() {
+                "Integer" | "Int" => "i64",
+                "Float" | "Double" => "double",
+                "Boolean" | "Bool" => "bool",
+                _ => "string",
+            };
+            let requiredness = if field.nullable { "optional" } else { "required" };
+            writeln!(out, "  {}: {} {} {}", id, requiredness, thrift_type, field.name)
+        }
+
+        fn after_entity(&self, _entity: &EntityDef, out: &mut dyn Write) -> io::Result<()> {
+            writeln!(out, "}}")
+        }
+    }
+
+    #[test]
+    fn test_plugin_output_hooks() {
+        let field = |name: &str, field_type: &str, nullable: bool| FieldDef {
+            name: name.to_string(),
+            field_type: field_type.to_string(),
+            nullable,
+            ..Default::default()
+        };
+        let entities = vec![EntityDef {
+            name: "Order".to_string(),
+            fields: vec![field("order_key", "Integer", false), field("comment", "String", true)],
+            ..Default::default()
+        }];
+
+        let mut out = Vec::new();
+        write_plugin_output(&ThriftIdl, &entities, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "namespace rs entities\n\nstruct Order {\n  1: required i64 order_key\n  2: optional string comment\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_pipeline_registers_callbacks_in_order() {
+        let thrift = ThriftIdl;
+        let no_op = NoOpCallbacks;
+
+        let mut pipeline = CodegenPipeline::new(&[], Path::new("/tmp"))
+            .with_callbacks(&thrift)
+            .with_callbacks(&no_op);
+        pipeline.add_callback(&thrift);
+
+        let output_files: Vec<_> = pipeline.callbacks().iter().map(|c| c.output_file()).collect();
+        assert_eq!(output_files, [Some(PathBuf::from("entities.thrift")), None, Some(PathBuf::from("entities.thrift"))]);
+    }
+
     #[test]
     fn test_no_op_callbacks() {
         let callbacks = NoOpCallbacks;
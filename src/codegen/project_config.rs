@@ -116,11 +116,19 @@ fn default_output_dir() -> String {
 
 impl ProjectConfig {
     /// Load project configuration from nomnom.yaml
+    ///
+    /// `${VAR}` references are substituted when the file sets
+    /// `interpolate_env: true`.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
         let contents = std::fs::read_to_string(path.as_ref())
             .map_err(|e| format!("Failed to read nomnom.yaml: {}", e))?;
 
-        serde_yaml::from_str(&contents)
+        let mut document: serde_yaml::Value = serde_yaml::from_str(&contents)
+            .map_err(|e| format!("Failed to parse nomnom.yaml: {}", e))?;
+        super::env_interpolation::interpolate_document(&mut document)
+            .map_err(|e| format!("Failed to interpolate nomnom.yaml: {}", e))?;
+
+        serde_yaml::from_value(document)
             .map_err(|e| format!("Failed to parse nomnom.yaml: {}", e))
     }
 
@@ -162,6 +170,17 @@ pub struct BuildConfig {
     pub database: Option<DatabaseConfig>,
     pub transforms: Option<BuildTransformsConfig>,
     pub helpers: Option<Vec<BuildHelperConfig>>,
+    /// Table/column naming strategy (default keeps names as configured)
+    #[serde(default)]
+    pub naming: super::NamingStrategy,
+    /// Add `created_at`/`updated_at` columns to every persistent entity
+    /// that doesn't set `persistence.timestamps` itself
+    #[serde(default)]
+    pub timestamps: bool,
+    /// What the parser binary and worker do when an entity fails to extract
+    /// or persist: abort, skip or dlq
+    #[serde(default)]
+    pub on_entity_error: crate::runtime::OnEntityError,
 }
 
 /// Build project information
@@ -266,6 +285,7 @@ pub struct BuildOutputsConfig {
     pub python_mapping: Option<String>,
     pub python_rust_shim: Option<String>,
     pub python_package_init: Option<String>,
+    pub python_stubs: Option<String>,
 }
 
 /// Build transforms configuration
@@ -285,7 +305,7 @@ pub struct BuildTransformsConfig {
 
     /// Rust transform definitions with inline code
     #[serde(default)]
-    pub rust: std::collections::HashMap<String, RustTransformDef>,
+    pub rust: std::collections::BTreeMap<String, RustTransformDef>,
 
     /// PyO3-wrapped transforms (require PyO3 feature, not available in standalone)
     #[serde(default)]
@@ -365,11 +385,19 @@ pub struct BuildHelperConfig {
 
 impl BuildConfig {
     /// Load build configuration from YAML file
+    ///
+    /// `${VAR}` references are substituted when the file sets
+    /// `interpolate_env: true`.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
         let contents = std::fs::read_to_string(path.as_ref())
             .map_err(|e| format!("Failed to read nomnom.yaml: {}", e))?;
 
-        serde_yaml::from_str(&contents)
+        let mut document: serde_yaml::Value = serde_yaml::from_str(&contents)
+            .map_err(|e| format!("Failed to parse nomnom.yaml: {}", e))?;
+        super::env_interpolation::interpolate_document(&mut document)
+            .map_err(|e| format!("Failed to interpolate nomnom.yaml: {}", e))?;
+
+        serde_yaml::from_value(document)
             .map_err(|e| format!("Failed to parse nomnom.yaml: {}", e))
     }
 
@@ -448,6 +476,16 @@ impl BuildConfig {
             opt_path.as_ref().map(|p| resolve_path(p))
         };
 
+        // Database type from `database.type`, else the `database.url` scheme
+        let database_type = match self.database.as_ref() {
+            Some(db) => match (db.r#type.as_deref(), db.url.as_deref()) {
+                (Some(db_type), _) => super::DatabaseType::from_str(db_type)?,
+                (None, Some(url)) => super::DatabaseType::from_url(url),
+                (None, None) => super::DatabaseType::PostgreSQL,
+            },
+            None => super::DatabaseType::PostgreSQL,
+        };
+
         Ok(super::GenerationConfig {
             config_dir: self.paths.config_dir.clone(),
             rust_output: resolve_path(&self.paths.outputs.rust_entities),
@@ -473,7 +511,11 @@ impl BuildConfig {
             python_dependency_imports,
             python_rust_shim_output: resolve_opt_path(&self.paths.outputs.python_rust_shim),
             python_package_init_output: resolve_opt_path(&self.paths.outputs.python_package_init),
+            python_stubs_output: resolve_opt_path(&self.paths.outputs.python_stubs),
             rust_transforms: self.transforms.as_ref().map(|t| t.rust.clone()),
+            naming: self.naming.clone(),
+            timestamps: self.timestamps,
+            database_type,
         })
     }
 
@@ -567,7 +609,7 @@ regex = "1.10"
 # Diesel ORM with connection pooling
 # Backend features (postgres/mysql/mariadb) are controlled by crate features below
 # Disable default features to prevent postgres backend from being enabled
-diesel = {{ version = "2.3", default-features = false, features = ["r2d2", "chrono", "numeric", "32-column-tables"] }}
+diesel = {{ version = "2.3", default-features = false, features = ["r2d2", "chrono", "numeric", "serde_json", "32-column-tables"] }}
 diesel_migrations = {{ version = "2.1", default-features = false }}
 r2d2 = "0.8"
 chrono = {{ version = "0.4", features = ["serde"] }}
@@ -684,7 +726,7 @@ dev = [
 [tool.maturin]
 module-name = "{}.{}"
 features = ["pyo3/extension-module"]
-include = ["config/**/*.yaml"]
+include = ["config/**/*.yaml", "**/*.pyi", "**/py.typed"]
 
 [tool.pytest.ini_options]
 minversion = "8.0"
@@ -78,10 +78,10 @@ pub fn generate_python_bindings<W: Write>(
     println!("Generating Python wrappers for {} derived entities", derived_entities.len());
     for entity in derived_entities {
         println!("Generating Python wrapper for derived entity: {}", entity.name);
-        if entity.repeated_for.is_some() {
-            generate_repeated_for_wrapper(writer, entity, config)?;
+        if entity.repeating_parent().is_some() {
+            generate_repeated_for_wrapper(writer, entity, all_entities, config)?;
         } else {
-            generate_derived_python_wrapper(writer, entity, config)?;
+            generate_derived_python_wrapper(writer, entity, all_entities, config)?;
         }
     }
 
@@ -119,7 +119,7 @@ fn generate_root_python_wrapper<W: Write>(
     writeln!(writer, "        // Use singleton transform registry (lazy_static or once_cell)")?;
     writeln!(writer, "        // No registry needed - transforms are injected directly")?;
     writeln!(writer, "        let inner = {}::from_string(raw_input)", core_name)?;
-    writeln!(writer, "            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e))?;")?;
+    writeln!(writer, "            .map_err(nomnom::python_bridge::exceptions::EntityError::new_err)?;")?;
     writeln!(writer, "        Ok(Self {{ inner }})")?;
     writeln!(writer, "    }}\n")?;
 
@@ -162,14 +162,15 @@ fn generate_root_python_wrapper<W: Write>(
 fn generate_derived_python_wrapper<W: Write>(
     writer: &mut W,
     entity: &EntityDef,
+    all_entities: &[EntityDef],
     config: &PyO3Config,
 ) -> Result<(), std::io::Error> {
     // Entity names in YAML are already PascalCase, use as-is
     let core_name = format!("{}Core", entity.name);
     let py_class_name = format!("Py{}", core_name);
 
-    // Get parent information
-    let parents = entity.get_parents();
+    // Get parent information (ancestors read by name are extra sources)
+    let parents = entity.constructor_sources(all_entities);
     let is_single_parent = parents.len() == 1;
 
     // Generate PyClass wrapper struct
@@ -204,18 +205,18 @@ fn generate_derived_python_wrapper<W: Write>(
     Ok(())
 }
 
-/// Generate Python wrapper for a repeated_for entity
+/// Generate Python wrapper for a repeated_for or repeated_by_count entity
 fn generate_repeated_for_wrapper<W: Write>(
     writer: &mut W,
     entity: &EntityDef,
+    all_entities: &[EntityDef],
     config: &PyO3Config,
 ) -> Result<(), std::io::Error> {
     // Entity names in YAML are already PascalCase, use as-is
     let core_name = format!("{}Core", entity.name);
     let py_class_name = format!("Py{}", core_name);
 
-    let repeated_for = entity.repeated_for.as_ref().unwrap();
-    let parent_entity = &repeated_for.entity;
+    let parent_entity = entity.repeating_parent().unwrap();
     let parent_snake = to_snake_case(parent_entity);
     let parent_py_class = format!("Py{}Core", parent_entity);
 
@@ -242,14 +243,21 @@ fn generate_repeated_for_wrapper<W: Write>(
     // from_parent_repeated static method
     writeln!(writer, "    /// Create multiple {} instances from parent entity's list field.", entity.name)?;
     writeln!(writer, "    #[staticmethod]")?;
-    writeln!(writer, "    fn from_parent_repeated({}: &{}) -> PyResult<Vec<Self>> {{",
-             parent_snake, parent_py_class)?;
+    let ancestors = entity.ancestor_sources(all_entities);
+    let ancestor_params: String = ancestors.iter()
+        .map(|a| format!(", {}: &Py{}Core", to_snake_case(a), a))
+        .collect();
+    let ancestor_args: String = ancestors.iter()
+        .map(|a| format!(", &{}.inner", to_snake_case(a)))
+        .collect();
+    writeln!(writer, "    fn from_parent_repeated({}: &{}{}) -> PyResult<Vec<Self>> {{",
+             parent_snake, parent_py_class, ancestor_params)?;
     writeln!(writer, "        // Use singleton transform registry (lazy_static or once_cell)")?;
     writeln!(writer, "        use once_cell::sync::Lazy;")?;
     writeln!(writer, "        // No registry needed - transforms are injected directly")?;
-    writeln!(writer, "        let instances = {}::from_parent_repeated(&{}.inner)",
-             core_name, parent_snake)?;
-    writeln!(writer, "            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(\"{{:?}}\", e)))?;")?;
+    writeln!(writer, "        let instances = {}::from_parent_repeated(&{}.inner{})",
+             core_name, parent_snake, ancestor_args)?;
+    writeln!(writer, "            .map_err(nomnom::python_bridge::exceptions::EntityError::new_err)?;")?;
     writeln!(writer, "        Ok(instances.into_iter().map(|inner| Self {{ inner }}).collect())")?;
     writeln!(writer, "    }}\n")?;
 
@@ -280,7 +288,7 @@ fn generate_single_parent_constructor<W: Write>(
     writeln!(writer, "        // No registry needed - transforms are injected directly")?;
     writeln!(writer, "        let inner = {}::{}(&{}.inner)",
              core_name, method_name, parent_snake)?;
-    writeln!(writer, "            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(\"{{:?}}\", e)))?;")?;
+    writeln!(writer, "            .map_err(nomnom::python_bridge::exceptions::EntityError::new_err)?;")?;
     writeln!(writer, "        Ok(Self {{ inner }})")?;
     writeln!(writer, "    }}\n")?;
 
@@ -321,7 +329,7 @@ fn generate_multi_parent_constructor<W: Write>(
 
     writeln!(writer, "        let inner = {}::from_sources({})",
              core_name, args_str)?;
-    writeln!(writer, "            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(\"{{:?}}\", e)))?;")?;
+    writeln!(writer, "            .map_err(nomnom::python_bridge::exceptions::EntityError::new_err)?;")?;
     writeln!(writer, "        Ok(Self {{ inner }})")?;
     writeln!(writer, "    }}\n")?;
 
@@ -495,6 +503,136 @@ fn generate_module_registration<W: Write>(
     Ok(())
 }
 
+/// Generate Python type stubs (`.pyi`) for the generated bindings
+///
+/// Mirrors the classes emitted by [`generate_python_bindings`] so that
+/// type checkers and IDEs can see constructors and field types of the
+/// compiled extension module.
+pub fn generate_python_stubs<W: Write>(
+    writer: &mut W,
+    all_entities: &[EntityDef],
+    config: &PyO3Config,
+) -> Result<(), std::io::Error> {
+    writeln!(writer, "# Auto-generated Python type stubs")?;
+    writeln!(writer, "# DO NOT EDIT - regenerate with build script\n")?;
+    writeln!(writer, "from typing import Any, Optional\n")?;
+
+    writeln!(writer, "class NomnomError(Exception): ...")?;
+    writeln!(writer, "class TransformError(NomnomError): ...")?;
+    writeln!(writer, "class EntityError(NomnomError): ...")?;
+    writeln!(writer, "class DatabaseError(NomnomError): ...")?;
+
+    for entity in all_entities.iter().filter(|e| e.source_type == "root" && !e.is_abstract) {
+        let class_name = python_class_name(&entity.name, all_entities);
+
+        writeln!(writer)?;
+        writeln!(writer, "class {}:", class_name)?;
+        write_stub_docstring(writer, entity)?;
+        write_stub_fields(writer, entity)?;
+        writeln!(writer, "    @staticmethod")?;
+        writeln!(writer, "    def from_string(raw_input: str) -> \"{}\": ...", class_name)?;
+        writeln!(writer, "    def to_dict(self) -> dict[str, Any]: ...")?;
+        writeln!(writer, "    def __getattr__(self, name: str) -> Any: ...")?;
+        writeln!(writer, "    def __repr__(self) -> str: ...")?;
+    }
+
+    for entity in all_entities.iter()
+        .filter(|e| e.source_type != "root" && !e.is_abstract && !e.fields.is_empty())
+    {
+        let class_name = python_class_name(&entity.name, all_entities);
+
+        writeln!(writer)?;
+        writeln!(writer, "class {}:", class_name)?;
+        write_stub_docstring(writer, entity)?;
+        write_stub_fields(writer, entity)?;
+
+        if entity.repeating_parent().is_some() {
+            let params = entity.constructor_sources(all_entities).iter()
+                .map(|source| format!("{}: \"{}\"", to_snake_case(source), python_class_name(source, all_entities)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(writer, "    @staticmethod")?;
+            writeln!(writer, "    def from_parent_repeated({}) -> list[\"{}\"]: ...", params, class_name)?;
+        } else {
+            let params = entity.constructor_sources(all_entities).iter()
+                .map(|parent| format!("{}: \"{}\"", to_snake_case(parent), python_class_name(parent, all_entities)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(writer, "    @staticmethod")?;
+            writeln!(writer, "    def from_sources({}) -> \"{}\": ...", params, class_name)?;
+
+            if config.generate_database_constructors {
+                if let Some(ref db_config) = entity.database {
+                    let params = entity.fields.iter()
+                        .filter(|f| !f.primary_key || !db_config.autogenerate_conformant_id)
+                        .map(|f| format!("{}: Optional[str] = None", f.name))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    writeln!(writer, "    def __init__(self, {}) -> None: ...", params)?;
+                }
+            }
+        }
+
+        writeln!(writer, "    def to_dict(self) -> dict[str, Any]: ...")?;
+        writeln!(writer, "    def to_json(self) -> str: ...")?;
+        writeln!(writer, "    def __getattr__(self, name: str) -> Any: ...")?;
+        writeln!(writer, "    def __repr__(self) -> str: ...")?;
+    }
+
+    Ok(())
+}
+
+/// Python-visible class name for an entity (root entities drop the `Core` suffix)
+fn python_class_name(entity_name: &str, all_entities: &[EntityDef]) -> String {
+    let is_root = all_entities.iter()
+        .any(|e| e.name == entity_name && e.source_type == "root");
+    if is_root {
+        entity_name.to_string()
+    } else {
+        format!("{}Core", entity_name)
+    }
+}
+
+/// Write the entity doc as a class docstring
+fn write_stub_docstring<W: Write>(writer: &mut W, entity: &EntityDef) -> Result<(), std::io::Error> {
+    if let Some(ref doc) = entity.doc {
+        let doc = doc.trim();
+        if !doc.is_empty() {
+            writeln!(writer, "    \"\"\"{}\"\"\"", doc.replace("\"\"\"", "\\\"\\\"\\\""))?;
+        }
+    }
+    Ok(())
+}
+
+/// Write typed attribute declarations for each entity field
+fn write_stub_fields<W: Write>(writer: &mut W, entity: &EntityDef) -> Result<(), std::io::Error> {
+    for field in &entity.fields {
+        writeln!(writer, "    {}: {}", field.serialized_name(), map_python_type(&field.field_type, field.nullable))?;
+    }
+    Ok(())
+}
+
+/// Map field type to Python type annotation
+fn map_python_type(field_type: &str, nullable: bool) -> String {
+    let base_type = match field_type {
+        "String" => "str",
+        "Int" | "Integer" => "int",
+        "Float" | "Double" => "float",
+        "Bool" | "Boolean" => "bool",
+        "DateTime" | "Date" => "str",
+        "List[String]" => "list[str]",
+        "List[Object]" | "List[Json]" => "list[Any]",
+        "Object" | "Json" => "Any",
+        _ => "str",
+    }.to_string();
+
+    if nullable && !field_type.starts_with("List[") && base_type != "Any" {
+        format!("Optional[{}]", base_type)
+    } else {
+        base_type
+    }
+}
+
 /// Map field type to Rust type
 fn map_field_type(field_type: &str, nullable: bool) -> String {
     let base_type = match field_type {
@@ -515,3 +653,54 @@ fn map_field_type(field_type: &str, nullable: bool) -> String {
         base_type
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_python_type() {
+        assert_eq!(map_python_type("String", false), "str");
+        assert_eq!(map_python_type("String", true), "Optional[str]");
+        assert_eq!(map_python_type("Integer", true), "Optional[int]");
+        assert_eq!(map_python_type("List[String]", true), "list[str]");
+        assert_eq!(map_python_type("Json", true), "Any");
+    }
+
+    #[test]
+    fn test_generate_python_stubs() {
+        let root = EntityDef {
+            name: "Message".to_string(),
+            source_type: "root".to_string(),
+            fields: vec![FieldDef {
+                name: "raw".to_string(),
+                field_type: "String".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let derived = EntityDef {
+            name: "Header".to_string(),
+            source_type: "derived".to_string(),
+            parent: Some("Message".to_string()),
+            fields: vec![FieldDef {
+                name: "count".to_string(),
+                field_type: "Integer".to_string(),
+                nullable: true,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        generate_python_stubs(&mut output, &[root, derived], &PyO3Config::default()).unwrap();
+
+        let generated = String::from_utf8(output).unwrap();
+        assert!(generated.contains("class Message:"));
+        assert!(generated.contains("    raw: str"));
+        assert!(generated.contains("def from_string(raw_input: str) -> \"Message\": ..."));
+        assert!(generated.contains("class HeaderCore:"));
+        assert!(generated.contains("    count: Optional[int]"));
+        assert!(generated.contains("def from_sources(message: \"Message\") -> \"HeaderCore\": ..."));
+    }
+}
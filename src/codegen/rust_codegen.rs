@@ -13,11 +13,11 @@
 //! - `copy_field_conditional`: Conditional field selection based on condition
 //! - `coalesce`: First non-None value via .or_else() chain (zero overhead)
 
-use crate::codegen::types::{EntityDef, FieldDef, ComputedFrom};
-use crate::codegen::utils::to_snake_case;
+use crate::codegen::types::{EntityDef, EntityExample, FieldDef, ComputedFrom};
+use crate::codegen::utils::{generate_coercion_expr, generate_json_subtree_expr, generate_validation_checks, generate_when_predicate, to_snake_case, transform_fn_name};
 use crate::codegen::project_config::RustTransformDef;
 use std::io::Write;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet};
 
 /// Configuration for Rust code generation
 #[derive(Debug, Clone)]
@@ -25,12 +25,15 @@ pub struct RustCodegenConfig {
     /// Transform registry type path (e.g., "crate::transform_registry::TransformRegistry")
     /// If None, entities with computed_from fields will cause an error
     pub transform_registry_type: Option<String>,
+    /// Turn each entity's `examples:` into `#[test]`s of the generated crate
+    pub generate_example_tests: bool,
 }
 
 impl Default for RustCodegenConfig {
     fn default() -> Self {
         Self {
             transform_registry_type: None,
+            generate_example_tests: true,
         }
     }
 }
@@ -55,15 +58,15 @@ impl Default for RustCodegenConfig {
 /// Set of transform names that were generated
 pub fn generate_transform_functions<W: Write>(
     writer: &mut W,
-    transforms: &HashMap<String, RustTransformDef>,
+    transforms: &BTreeMap<String, RustTransformDef>,
     entities: &[EntityDef],
-) -> Result<HashSet<String>, std::io::Error> {
+) -> Result<BTreeSet<String>, std::io::Error> {
     writeln!(writer, "// ============================================================================")?;
     writeln!(writer, "// Auto-generated Transform Functions")?;
     writeln!(writer, "// Generated from nomnom.yaml transforms section")?;
     writeln!(writer, "// ============================================================================\n")?;
 
-    let mut generated = HashSet::new();
+    let mut generated = BTreeSet::new();
 
     // Generate provided transforms
     for (name, def) in transforms {
@@ -128,7 +131,7 @@ fn generate_single_transform<W: Write>(
     writeln!(writer, "/// {}", def.return_type)?;
 
     // Function signature
-    write!(writer, "pub fn {}(", name)?;
+    write!(writer, "pub fn {}(", transform_fn_name(name))?;
     for (i, arg) in def.args.iter().enumerate() {
         if i > 0 {
             write!(writer, ", ")?;
@@ -160,8 +163,8 @@ fn generate_single_transform<W: Write>(
 }
 
 /// Collect all transform names used by entities
-fn collect_required_transforms(entities: &[EntityDef]) -> HashSet<String> {
-    let mut transforms = HashSet::new();
+fn collect_required_transforms(entities: &[EntityDef]) -> BTreeSet<String> {
+    let mut transforms = BTreeSet::new();
 
     for entity in entities {
         for field in &entity.fields {
@@ -197,6 +200,7 @@ fn collect_required_transforms(entities: &[EntityDef]) -> HashSet<String> {
 /// let mut output = File::create("src/generated.rs").unwrap();
 /// let config = RustCodegenConfig {
 ///     transform_registry_type: Some("crate::transform_registry::TransformRegistry".to_string()),
+///     ..Default::default()
 /// };
 /// generate_rust_code(&mut output, &entities, &config).unwrap();
 /// ```
@@ -208,14 +212,192 @@ pub fn generate_rust_code<W: Write>(
     // NOTE: Header with imports should be generated by caller (e.g., build.rs)
     // to allow domain-specific imports and configuration
 
+    if entities.iter().any(|e| e.json_input.as_ref().is_some_and(|j| j.numbers_from_strings)) {
+        generate_lenient_numbers_module(writer)?;
+    }
+
     // Generate each entity
     for entity in entities {
         generate_entity(writer, entity, entities, config)?;
     }
 
+    if config.generate_example_tests {
+        generate_example_tests(writer, entities)?;
+    }
+
+    Ok(())
+}
+
+/// Generate the `deserialize_with` helpers behind `json_input.numbers_from_strings`
+fn generate_lenient_numbers_module<W: Write>(writer: &mut W) -> Result<(), std::io::Error> {
+    writeln!(writer, "/// Deserializers accepting numbers sent as JSON strings")?;
+    writeln!(writer, "#[allow(dead_code)]")?;
+    writeln!(writer, "mod lenient_numbers {{")?;
+    writeln!(writer, "    use serde::Deserialize;\n")?;
+    writeln!(writer, "    #[derive(Deserialize)]")?;
+    writeln!(writer, "    #[serde(untagged)]")?;
+    writeln!(writer, "    enum NumberOrString<T> {{")?;
+    writeln!(writer, "        Number(T),")?;
+    writeln!(writer, "        String(String),")?;
+    writeln!(writer, "    }}\n")?;
+    writeln!(writer, "    pub fn number<'de, D, T>(deserializer: D) -> Result<T, D::Error>")?;
+    writeln!(writer, "    where")?;
+    writeln!(writer, "        D: serde::Deserializer<'de>,")?;
+    writeln!(writer, "        T: Deserialize<'de> + std::str::FromStr,")?;
+    writeln!(writer, "        T::Err: std::fmt::Display,")?;
+    writeln!(writer, "    {{")?;
+    writeln!(writer, "        match NumberOrString::<T>::deserialize(deserializer)? {{")?;
+    writeln!(writer, "            NumberOrString::Number(n) => Ok(n),")?;
+    writeln!(writer, "            NumberOrString::String(s) => s.trim().parse().map_err(serde::de::Error::custom),")?;
+    writeln!(writer, "        }}")?;
+    writeln!(writer, "    }}\n")?;
+    writeln!(writer, "    /// Like `number`, reading null and blank strings as `None`")?;
+    writeln!(writer, "    pub fn option_number<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>")?;
+    writeln!(writer, "    where")?;
+    writeln!(writer, "        D: serde::Deserializer<'de>,")?;
+    writeln!(writer, "        T: Deserialize<'de> + std::str::FromStr,")?;
+    writeln!(writer, "        T::Err: std::fmt::Display,")?;
+    writeln!(writer, "    {{")?;
+    writeln!(writer, "        match Option::<NumberOrString<T>>::deserialize(deserializer)? {{")?;
+    writeln!(writer, "            None => Ok(None),")?;
+    writeln!(writer, "            Some(NumberOrString::Number(n)) => Ok(Some(n)),")?;
+    writeln!(writer, "            Some(NumberOrString::String(s)) if s.trim().is_empty() => Ok(None),")?;
+    writeln!(writer, "            Some(NumberOrString::String(s)) => s.trim().parse().map(Some).map_err(serde::de::Error::custom),")?;
+    writeln!(writer, "        }}")?;
+    writeln!(writer, "    }}")?;
+    writeln!(writer, "}}\n")?;
+    Ok(())
+}
+
+/// Generate a `#[test]` per entity example, checking the listed field values
+fn generate_example_tests<W: Write>(
+    writer: &mut W,
+    entities: &[EntityDef],
+) -> Result<(), std::io::Error> {
+    let with_examples: Vec<&EntityDef> = entities.iter()
+        .filter(|e| !e.examples.is_empty() && !e.is_abstract)
+        .collect();
+    if with_examples.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(writer, "#[cfg(test)]")?;
+    writeln!(writer, "mod entity_examples {{")?;
+    writeln!(writer, "    use super::*;\n")?;
+    writeln!(writer, "    fn fixture(json: &str) -> serde_json::Value {{")?;
+    writeln!(writer, "        serde_json::from_str(json).expect(\"invalid example fixture\")")?;
+    writeln!(writer, "    }}\n")?;
+    writeln!(writer, "    fn assert_field<T: Serialize>(field: &str, actual: &T, expected: &str) {{")?;
+    writeln!(writer, "        let actual = serde_json::to_value(actual).expect(\"field is not serializable\");")?;
+    writeln!(writer, "        assert_eq!(actual, fixture(expected), \"field '{{}}'\", field);")?;
+    writeln!(writer, "    }}\n")?;
+
+    for entity in with_examples {
+        for example in &entity.examples {
+            generate_example_test(writer, entity, example, entities)?;
+        }
+    }
+
+    writeln!(writer, "}}\n")?;
+    Ok(())
+}
+
+fn generate_example_test<W: Write>(
+    writer: &mut W,
+    entity: &EntityDef,
+    example: &EntityExample,
+    all_entities: &[EntityDef],
+) -> Result<(), std::io::Error> {
+    let invalid = |reason: String| std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("Entity '{}', example '{}': {}", entity.name, example.name, reason),
+    );
+    if example.name.is_empty() || !example.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(invalid("name must be a snake_case identifier".to_string()));
+    }
+
+    let core_name = format!("{}Core", entity.name);
+    writeln!(writer, "    #[test]")?;
+    writeln!(writer, "    fn {}_{}() {{", to_snake_case(&entity.name), example.name)?;
+
+    let constructor = if entity.is_root() {
+        let input = example.input.as_ref()
+            .ok_or_else(|| invalid("root entity examples need an `input`".to_string()))?;
+        format!("{}::from_string({})", core_name, raw_string_literal(input))
+    } else {
+        let parents = entity.constructor_sources(all_entities);
+        for parent in &parents {
+            let source = example.sources.get(parent)
+                .ok_or_else(|| invalid(format!("missing source '{}'", parent)))?;
+            writeln!(
+                writer,
+                "        let {}: {}Core = serde_json::from_value(fixture({})).expect(\"invalid {} source\");",
+                to_snake_case(parent), parent, raw_string_literal(&example_json(source)), parent
+            )?;
+        }
+        let args: Vec<String> = parents.iter().map(|p| format!("&{}", to_snake_case(p))).collect();
+        let method = if entity.repeating_parent().is_some() { "from_parent_repeated" } else { "from_sources" };
+        format!("{}::{}({})", core_name, method, args.join(", "))
+    };
+
+    let field_checks = |expected: &serde_yaml::Value| -> Result<Vec<(String, String)>, std::io::Error> {
+        let serde_yaml::Value::Mapping(expected) = expected else {
+            return Err(invalid("`expected` must map field names to values".to_string()));
+        };
+        expected.iter()
+            .map(|(field, value)| {
+                let field = field.as_str()
+                    .filter(|f| entity.fields.iter().any(|def| def.name == *f))
+                    .ok_or_else(|| invalid(format!("unknown expected field {:?}", field)))?;
+                Ok((field.to_string(), example_json(value)))
+            })
+            .collect()
+    };
+
+    if entity.repeating_parent().is_some() {
+        let serde_yaml::Value::Sequence(ref instances) = example.expected else {
+            return Err(invalid("`expected` must list one entry per repeated instance".to_string()));
+        };
+        writeln!(writer, "        let entities = {}.expect(\"extraction failed\");", constructor)?;
+        writeln!(writer, "        assert_eq!(entities.len(), {});", instances.len())?;
+        for (i, instance) in instances.iter().enumerate() {
+            for (field, value) in field_checks(instance)? {
+                writeln!(
+                    writer,
+                    "        assert_field(\"{}[{}].{}\", &entities[{}].{}, {});",
+                    entity.name, i, field, i, field, raw_string_literal(&value)
+                )?;
+            }
+        }
+    } else {
+        writeln!(writer, "        let entity = {}.expect(\"extraction failed\");", constructor)?;
+        for (field, value) in field_checks(&example.expected)? {
+            writeln!(
+                writer,
+                "        assert_field(\"{}.{}\", &entity.{}, {});",
+                entity.name, field, field, raw_string_literal(&value)
+            )?;
+        }
+    }
+
+    writeln!(writer, "    }}\n")?;
     Ok(())
 }
 
+/// JSON text of a YAML example value
+fn example_json(value: &serde_yaml::Value) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "null".to_string())
+}
+
+/// Rust raw string literal holding `text`
+fn raw_string_literal(text: &str) -> String {
+    let mut hashes = "#".to_string();
+    while text.contains(&format!("\"{}", hashes)) {
+        hashes.push('#');
+    }
+    format!("r{}\"{}\"{}", hashes, text, hashes)
+}
+
 /// Generate code for a single entity
 fn generate_entity<W: Write>(
     writer: &mut W,
@@ -235,7 +417,7 @@ fn generate_entity<W: Write>(
         if entity.is_root() {
             generate_root_impl(writer, entity, &core_name, all_entities, config)?;
         } else if entity.is_derived() {
-            if entity.repeated_for.is_some() {
+            if entity.repeating_parent().is_some() {
                 generate_repeated_impl(writer, entity, &core_name, all_entities, config)?;
             } else {
                 generate_derived_impl(writer, entity, &core_name, all_entities, config)?;
@@ -263,6 +445,11 @@ fn generate_struct<W: Write>(
 
     // Derive macros
     writeln!(writer, "#[derive(Debug, Clone, Serialize, Deserialize)]")?;
+    let json_input = entity.json_input.as_ref();
+    if let Some(rule) = json_input.and_then(|j| j.rename_all.as_ref()) {
+        // Deserialize-only, so `to_json` keeps the declared names
+        writeln!(writer, "#[serde(rename_all(deserialize = \"{}\"))]", rule)?;
+    }
     writeln!(writer, "pub struct {} {{", struct_name)?;
 
     // Fields
@@ -276,6 +463,24 @@ fn generate_struct<W: Write>(
             }
         }
 
+        if let Some(ref serialized_as) = field.serialized_as {
+            writeln!(writer, "    #[serde(rename = \"{}\")]", serialized_as)?;
+        }
+
+        if let Some(json_input) = json_input {
+            for alias in json_input.aliases.get(&field.name).into_iter().flatten() {
+                writeln!(writer, "    #[serde(alias = \"{}\")]", alias)?;
+            }
+            let is_number = matches!(field.field_type.as_str(), "Int" | "Integer" | "Float" | "Double");
+            if json_input.numbers_from_strings && is_number {
+                if field.nullable {
+                    writeln!(writer, "    #[serde(default, deserialize_with = \"lenient_numbers::option_number\")]")?;
+                } else {
+                    writeln!(writer, "    #[serde(deserialize_with = \"lenient_numbers::number\")]")?;
+                }
+            }
+        }
+
         let rust_type = map_field_type(&field.field_type, field.nullable);
         writeln!(writer, "    pub {}: {},", field.name, rust_type)?;
     }
@@ -311,8 +516,8 @@ fn generate_root_impl<W: Write>(
     });
 
     if has_extraction {
-        // Generate field extraction for each field
-        for field in &entity.fields {
+        // Generate field extraction for each field (sibling dependencies first)
+        for field in ordered_fields(entity)? {
             if let Some(ref computed) = field.computed_from {
                 // Field computed via transform
                 generate_field_extraction(writer, entity, field, computed, all_entities, "        ")?;
@@ -325,11 +530,7 @@ fn generate_root_impl<W: Write>(
 
         // Build and return struct
         writeln!(writer)?;
-        writeln!(writer, "        Ok(Self {{")?;
-        for field in &entity.fields {
-            writeln!(writer, "            {},", field.name)?;
-        }
-        writeln!(writer, "        }})")?;
+        generate_struct_return(writer, entity, "        ")?;
     } else {
         // No extraction logic - deserialize from JSON
         writeln!(writer, "        // Deserialize from JSON")?;
@@ -339,7 +540,12 @@ fn generate_root_impl<W: Write>(
 
     writeln!(writer, "    }}\n")?;
 
-    // Serialization methods
+    if let Some(ref encoding) = entity.encoding {
+        generate_encoded_constructors(writer, encoding)?;
+    }
+
+    // Validation and serialization methods
+    generate_validate_method(writer, entity)?;
     generate_serialization_methods(writer, entity)?;
 
     writeln!(writer, "}}\n")?;
@@ -347,6 +553,24 @@ fn generate_root_impl<W: Write>(
     Ok(())
 }
 
+/// Generate `from_bytes`/`from_reader`, transcoding the entity's `encoding:` to UTF-8
+fn generate_encoded_constructors<W: Write>(writer: &mut W, encoding: &str) -> Result<(), std::io::Error> {
+    writeln!(writer, "    /// Create root entity from raw bytes in the `{}` encoding", encoding)?;
+    writeln!(writer, "    pub fn from_bytes(raw_input: &[u8]) -> Result<Self, String> {{")?;
+    writeln!(writer, "        let encoding: nomnom::runtime::InputEncoding = {:?}.parse()?;", encoding)?;
+    writeln!(writer, "        Self::from_string(&encoding.decode(raw_input)?)")?;
+    writeln!(writer, "    }}\n")?;
+
+    writeln!(writer, "    /// Create root entity from everything `reader` yields, transcoded like `from_bytes`")?;
+    writeln!(writer, "    pub fn from_reader<R: std::io::Read>(mut reader: R) -> Result<Self, String> {{")?;
+    writeln!(writer, "        let mut raw_input = Vec::new();")?;
+    writeln!(writer, "        reader.read_to_end(&mut raw_input)")?;
+    writeln!(writer, "            .map_err(|e| format!(\"Failed to read input: {{}}\", e))?;")?;
+    writeln!(writer, "        Self::from_bytes(&raw_input)")?;
+    writeln!(writer, "    }}\n")?;
+    Ok(())
+}
+
 /// Generate impl block for derived entities
 fn generate_derived_impl<W: Write>(
     writer: &mut W,
@@ -359,6 +583,7 @@ fn generate_derived_impl<W: Write>(
 
     // Get parent information
     let parents = entity.get_parents();
+    let ancestors = entity.ancestor_sources(all_entities);
 
     // Generate constructor signature - always use from_sources for consistency
     if !parents.is_empty() {
@@ -371,10 +596,13 @@ fn generate_derived_impl<W: Write>(
             let param_name = to_snake_case(parent_name);
             writeln!(writer, "    /// * `{}` - Source {} entity", param_name, parent_name)?;
         }
+        for ancestor in &ancestors {
+            writeln!(writer, "    /// * `{}` - Ancestor {} entity", to_snake_case(ancestor), ancestor)?;
+        }
 
-        // Generate parameters - parent entities only (no registry)
+        // Generate parameters - parent entities, then ancestors read by name (no registry)
         write!(writer, "    pub fn from_sources(")?;
-        for (i, parent_name) in parents.iter().enumerate() {
+        for (i, parent_name) in parents.iter().chain(&ancestors).enumerate() {
             let param_name = to_snake_case(parent_name);
             let parent_type = format!("{}Core", parent_name);
 
@@ -387,7 +615,7 @@ fn generate_derived_impl<W: Write>(
         writeln!(writer, "    ) -> Result<Self, String> {{")?;
 
         // Generate field extraction for each field with computed_from
-        for field in &entity.fields {
+        for field in ordered_fields(entity)? {
             if let Some(ref computed) = field.computed_from {
                 generate_field_extraction(writer, entity, field, computed, all_entities, "        ")?;
             }
@@ -405,15 +633,12 @@ fn generate_derived_impl<W: Write>(
 
         // Build and return struct
         writeln!(writer)?;
-        writeln!(writer, "        Ok(Self {{")?;
-        for field in &entity.fields {
-            writeln!(writer, "            {},", field.name)?;
-        }
-        writeln!(writer, "        }})")?;
+        generate_struct_return(writer, entity, "        ")?;
         writeln!(writer, "    }}\n")?;
     }
 
-    // Serialization methods
+    // Validation and serialization methods
+    generate_validate_method(writer, entity)?;
     generate_serialization_methods(writer, entity)?;
 
     writeln!(writer, "}}\n")?;
@@ -421,7 +646,7 @@ fn generate_derived_impl<W: Write>(
     Ok(())
 }
 
-/// Generate impl block for repeated entities (repeated_for pattern)
+/// Generate impl block for repeated entities (repeated_for and repeated_by_count patterns)
 fn generate_repeated_impl<W: Write>(
     writer: &mut W,
     entity: &EntityDef,
@@ -431,31 +656,61 @@ fn generate_repeated_impl<W: Write>(
 ) -> Result<(), std::io::Error> {
     writeln!(writer, "impl {} {{", struct_name)?;
 
-    if let Some(ref repeated_for) = entity.repeated_for {
-        let parent_type = format!("{}Core", repeated_for.entity);
-        let parent_param = to_snake_case(&repeated_for.entity);
-        let list_field = &repeated_for.field;
-        let item_var = &repeated_for.each_known_as;
+    if let (Some(parent), Some(item_var)) = (entity.repeating_parent(), entity.each_known_as()) {
+        let parent_type = format!("{}Core", parent);
+        let parent_param = to_snake_case(parent);
+        let per_item = match (&entity.repeated_for, &entity.repeated_by_count) {
+            (Some(repeated_for), _) => format!("item in parent.{}", repeated_for.field),
+            (None, Some(by_count)) => format!(
+                "of the parent.{} repetitions in parent.{}",
+                by_count.count_field, by_count.field
+            ),
+            (None, None) => unreachable!("entity has a repeating parent"),
+        };
 
         writeln!(writer, "    /// Create entity instances from repeated parent data")?;
         writeln!(writer, "    ///")?;
         writeln!(writer, "    /// # Arguments")?;
         writeln!(writer, "    ///")?;
-        writeln!(writer, "    /// * `{}` - Parent {} entity", parent_param, repeated_for.entity)?;
+        let ancestors = entity.ancestor_sources(all_entities);
+
+        writeln!(writer, "    /// * `{}` - Parent {} entity", parent_param, parent)?;
+        for ancestor in &ancestors {
+            writeln!(writer, "    /// * `{}` - Ancestor {} entity", to_snake_case(ancestor), ancestor)?;
+        }
         writeln!(writer, "    ///")?;
         writeln!(writer, "    /// # Returns")?;
         writeln!(writer, "    ///")?;
-        writeln!(writer, "    /// Vector of {} instances, one per item in parent.{}", entity.name, list_field)?;
+        writeln!(writer, "    /// Vector of {} instances, one per {}", entity.name, per_item)?;
         writeln!(writer, "    pub fn from_parent_repeated(")?;
-        writeln!(writer, "        {}: &{}", parent_param, parent_type)?;
+        write!(writer, "        {}: &{}", parent_param, parent_type)?;
+        for ancestor in &ancestors {
+            write!(writer, ", {}: &{}Core", to_snake_case(ancestor), ancestor)?;
+        }
+        writeln!(writer)?;
         writeln!(writer, "    ) -> Result<Vec<Self>, String> {{")?;
         writeln!(writer, "        let mut instances = Vec::new();")?;
         writeln!(writer)?;
-        writeln!(writer, "        // Iterate over parent.{}", list_field)?;
-        writeln!(writer, "        for {} in &{}.{} {{", item_var, parent_param, list_field)?;
+        if let Some(ref repeated_for) = entity.repeated_for {
+            writeln!(writer, "        // Iterate over parent.{}", repeated_for.field)?;
+            writeln!(writer, "        for {} in &{}.{} {{", item_var, parent_param, repeated_for.field)?;
+        } else if let Some(ref by_count) = entity.repeated_by_count {
+            let parent_entity = all_entities.iter()
+                .find(|e| e.name == parent)
+                .ok_or_else(|| std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("repeated_by_count parent '{}' of '{}' not found", parent, entity.name),
+                ))?;
+            let slices = by_count.slices_expr(parent_entity, &parent_param)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+            writeln!(writer, "        // Slice parent.{} into parent.{} repetitions of {} bytes",
+                by_count.field, by_count.count_field, by_count.stride)?;
+            writeln!(writer, "        for {}_slice in {} {{", item_var, slices)?;
+            writeln!(writer, "            let {} = &{}_slice.to_string();", item_var, item_var)?;
+        }
 
         // Generate field extraction for each field (indent by 12 spaces)
-        for field in &entity.fields {
+        for field in ordered_fields(entity)? {
             if let Some(ref computed) = field.computed_from {
                 generate_field_extraction(writer, entity, field, computed, all_entities, "            ")?;
             }
@@ -463,18 +718,23 @@ fn generate_repeated_impl<W: Write>(
 
         // Build instance and add to vector
         writeln!(writer)?;
-        writeln!(writer, "            instances.push(Self {{")?;
+        writeln!(writer, "            let instance = Self {{")?;
         for field in &entity.fields {
             writeln!(writer, "                {},", field.name)?;
         }
-        writeln!(writer, "            }});")?;
+        writeln!(writer, "            }};")?;
+        if has_validation_rules(entity) {
+            writeln!(writer, "            instance.validate()?;")?;
+        }
+        writeln!(writer, "            instances.push(instance);")?;
         writeln!(writer, "        }}")?;
         writeln!(writer)?;
         writeln!(writer, "        Ok(instances)")?;
         writeln!(writer, "    }}\n")?;
     }
 
-    // Serialization methods
+    // Validation and serialization methods
+    generate_validate_method(writer, entity)?;
     generate_serialization_methods(writer, entity)?;
 
     writeln!(writer, "}}\n")?;
@@ -482,6 +742,64 @@ fn generate_repeated_impl<W: Write>(
     Ok(())
 }
 
+/// Whether any field declares `validate:` rules
+fn has_validation_rules(entity: &EntityDef) -> bool {
+    entity.fields.iter().any(|f| f.validate.is_some())
+}
+
+/// Emit the constructor's return, running `validate()` first when the entity has rules
+fn generate_struct_return<W: Write>(
+    writer: &mut W,
+    entity: &EntityDef,
+    indent: &str,
+) -> Result<(), std::io::Error> {
+    if !has_validation_rules(entity) {
+        writeln!(writer, "{}Ok(Self {{", indent)?;
+        for field in &entity.fields {
+            writeln!(writer, "{}    {},", indent, field.name)?;
+        }
+        writeln!(writer, "{}}})", indent)?;
+        return Ok(());
+    }
+
+    writeln!(writer, "{}let entity = Self {{", indent)?;
+    for field in &entity.fields {
+        writeln!(writer, "{}    {},", indent, field.name)?;
+    }
+    writeln!(writer, "{}}};", indent)?;
+    writeln!(writer, "{}entity.validate()?;", indent)?;
+    writeln!(writer, "{}Ok(entity)", indent)?;
+    Ok(())
+}
+
+/// Generate validate() enforcing the fields' `validate:` rules, and
+/// when_satisfied() evaluating the entity's `when:` condition
+fn generate_validate_method<W: Write>(
+    writer: &mut W,
+    entity: &EntityDef,
+) -> Result<(), std::io::Error> {
+    writeln!(writer, "    /// Check field values against their validate rules")?;
+    writeln!(writer, "    pub fn validate(&self) -> Result<(), String> {{")?;
+    for field in &entity.fields {
+        let checks = generate_validation_checks(
+            &entity.name,
+            field,
+            &format!("self.{}", field.name),
+            "        ",
+            &|msg| format!("return Err({});", msg),
+        );
+        write!(writer, "{}", checks)?;
+    }
+    writeln!(writer, "        Ok(())")?;
+    writeln!(writer, "    }}\n")?;
+
+    writeln!(writer, "    /// Whether this entity passes its `when` condition (and should be emitted)")?;
+    writeln!(writer, "    pub fn when_satisfied(&self) -> bool {{")?;
+    writeln!(writer, "        {}", generate_when_predicate(entity, "self"))?;
+    writeln!(writer, "    }}\n")?;
+    Ok(())
+}
+
 /// Generate serialization methods (to_json, to_dict, etc.)
 fn generate_serialization_methods<W: Write>(
     writer: &mut W,
@@ -492,7 +810,7 @@ fn generate_serialization_methods<W: Write>(
     writeln!(writer, "    pub fn to_dict(&self) -> HashMap<String, serde_json::Value> {{")?;
     writeln!(writer, "        let mut map = HashMap::new();")?;
     for field in &entity.fields {
-        writeln!(writer, "        map.insert(\"{}\".to_string(), serde_json::to_value(&self.{}).unwrap_or(serde_json::Value::Null));", field.name, field.name)?;
+        writeln!(writer, "        map.insert(\"{}\".to_string(), serde_json::to_value(&self.{}).unwrap_or(serde_json::Value::Null));", field.serialized_name(), field.name)?;
     }
     writeln!(writer, "        map")?;
     writeln!(writer, "    }}\n")?;
@@ -519,7 +837,13 @@ fn generate_serialization_methods<W: Write>(
     Ok(())
 }
 
-/// Generate field extraction code
+/// Entity fields in extraction order (fields read via `source: self` come first)
+fn ordered_fields(entity: &EntityDef) -> Result<Vec<&FieldDef>, std::io::Error> {
+    entity.fields_in_dependency_order()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+}
+
+/// Generate field extraction code, falling back to the field's `default:` when absent
 fn generate_field_extraction<W: Write>(
     writer: &mut W,
     current_entity: &EntityDef,
@@ -527,6 +851,40 @@ fn generate_field_extraction<W: Write>(
     computed: &ComputedFrom,
     all_entities: &[EntityDef],
     indent: &str,
+) -> Result<(), std::io::Error> {
+    generate_field_value(writer, current_entity, field, computed, all_entities, indent)?;
+
+    // Repeated entities are built in a loop, so unparsable records can be skipped
+    let in_loop = !current_entity.is_root() && current_entity.repeating_parent().is_some();
+    let coerced = generate_coercion_expr(
+        &current_entity.name,
+        field,
+        &field.name,
+        map_field_type(&field.field_type, false).as_str(),
+        in_loop.then_some("continue;"),
+        &|msg| format!("return Err({});", msg),
+    );
+    if let Some(coerced) = coerced {
+        writeln!(writer, "{}let {} = {};", indent, field.name, coerced)?;
+    }
+
+    let default = field.default_literal()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    if let Some(default) = default {
+        writeln!(writer, "{}let {} = {}.or_else(|| Some({}));", indent, field.name, field.name, default)?;
+    }
+
+    Ok(())
+}
+
+/// Generate the binding for a field's computed value
+fn generate_field_value<W: Write>(
+    writer: &mut W,
+    current_entity: &EntityDef,
+    field: &FieldDef,
+    computed: &ComputedFrom,
+    all_entities: &[EntityDef],
+    indent: &str,
 ) -> Result<(), std::io::Error> {
     writeln!(writer, "{}// Extract field: {}", indent, field.name)?;
 
@@ -712,6 +1070,46 @@ fn generate_field_extraction<W: Write>(
         return Ok(());
     }
 
+    // Special case: extract_json_subtree - take a sub-object out of a JSON source field
+    if computed.transform == "extract_json_subtree" {
+        let invalid = |msg: &str| std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("extract_json_subtree for '{}' {}", field.name, msg)
+        );
+        let path = computed.arg_str("path").ok_or_else(|| invalid("requires a 'path' argument"))?;
+
+        let (source_var, source_def) = match computed.sources.first() {
+            Some(crate::codegen::types::FieldSource::Parent { source: src, field: src_field, .. }) => {
+                if src.to_lowercase() == "self" {
+                    // Self-reference: the already-extracted local variable
+                    (src_field.clone(), current_entity.fields.iter().find(|f| &f.name == src_field))
+                } else {
+                    let source_def = all_entities.iter()
+                        .find(|e| &e.name == src)
+                        .and_then(|e| e.fields.iter().find(|f| &f.name == src_field));
+                    (format!("{}.{}", to_snake_case(src), src_field), source_def)
+                }
+            }
+            _ => return Err(invalid("requires exactly one source field")),
+        };
+
+        let nullable = source_def.map(|f| f.nullable).unwrap_or(true);
+        let is_json = source_def.map(|f| f.is_json()).unwrap_or(false);
+        let source_expr = if nullable {
+            source_var
+        } else {
+            format!("Some({}.clone())", source_var)
+        };
+
+        let value = generate_json_subtree_expr(&source_expr, is_json, path);
+        if field.nullable {
+            writeln!(writer, "{}let {} = {};", indent, field.name, value)?;
+        } else {
+            writeln!(writer, "{}let {} = {}.unwrap_or(serde_json::Value::Null);", indent, field.name, value)?;
+        }
+        return Ok(());
+    }
+
     // General case: call transform function directly
     // Build function call: transform_name(arg1, arg2, ...)
 
@@ -838,13 +1236,9 @@ fn generate_field_extraction<W: Write>(
             writeln!(
                 writer,
                 "{}    {}({})",
-                indent, computed.transform, unwrapped_str
-            )?;
-            writeln!(
-                writer,
-                "{}        .map_err(|e| format!(\"Failed to extract '{}': {{}}\", e))?",
-                indent, field.name
+                indent, transform_fn_name(&computed.transform), unwrapped_str
             )?;
+            writeln!(writer, "{}        {}?", indent, transform_error(current_entity, field, computed, &unwrapped_args))?;
             writeln!(writer, "{}}} else {{", indent)?;
             writeln!(writer, "{}    None", indent)?;
             writeln!(writer, "{}}};", indent)?;
@@ -855,18 +1249,28 @@ fn generate_field_extraction<W: Write>(
         writeln!(
             writer,
             "{}let {} = {}({})",
-            indent, field.name, computed.transform, args_str
-        )?;
-        writeln!(
-            writer,
-            "{}    .map_err(|e| format!(\"Failed to extract '{}': {{}}\", e))?;",
-            indent, field.name
+            indent, field.name, transform_fn_name(&computed.transform), args_str
         )?;
+        writeln!(writer, "{}    {}?;", indent, transform_error(current_entity, field, computed, &call_args))?;
     }
 
     Ok(())
 }
 
+/// `.map_err(..)` turning a transform failure into an [`EntityError`] message
+/// naming the entity, field and the first source value
+fn transform_error(entity: &EntityDef, field: &FieldDef, computed: &ComputedFrom, call_args: &[String]) -> String {
+    // Sources come first in the call, before literal args
+    let excerpt = match call_args.first().filter(|_| !computed.sources.is_empty()) {
+        Some(source) => format!(".with_source_excerpt(format!(\"{{:?}}\", {}))", source),
+        None => String::new(),
+    };
+    format!(
+        ".map_err(|e| EntityError::transform_failed(\"{}\", \"{}\", e){}.to_string())",
+        entity.name, field.name, excerpt
+    )
+}
+
 /// Convert YAML value to Rust literal
 fn yaml_value_to_rust_literal(value: &serde_yaml::Value) -> String {
     match value {
@@ -955,6 +1359,8 @@ mod tests {
                     filename_component: None,
                     derived_from: None,
                     root_source: None,
+                    serialized_as: None,
+                    column: None,
                 },
             ],
             doc: Some("Test entity".to_string()),
@@ -978,6 +1384,67 @@ mod tests {
         assert!(generated.contains("/// Test field"));
     }
 
+    #[test]
+    fn test_generate_field_extraction_default() {
+        use crate::codegen::types::FieldSource;
+
+        let field = FieldDef {
+            name: "status".to_string(),
+            field_type: "String".to_string(),
+            nullable: true,
+            computed_from: Some(ComputedFrom {
+                transform: "copy_field".to_string(),
+                sources: vec![FieldSource::Parent {
+                    source: "Order".to_string(),
+                    field: "status".to_string(),
+                    alias: None,
+                }],
+                args: None,
+                condition: None,
+            }),
+            default: Some(serde_yaml::Value::String("pending".to_string())),
+            ..Default::default()
+        };
+        let entity = EntityDef {
+            name: "OrderStatus".to_string(),
+            fields: vec![field.clone()],
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        let computed = field.computed_from.as_ref().unwrap();
+        generate_field_extraction(&mut output, &entity, &field, computed, &[], "").unwrap();
+
+        let generated = String::from_utf8(output).unwrap();
+        assert!(generated.contains("let status = order.status.clone();"));
+        assert!(generated.contains("let status = status.or_else(|| Some(\"pending\".to_string()));"));
+    }
+
+    #[test]
+    fn test_generate_struct_serialized_as() {
+        let entity = EntityDef {
+            name: "Patient".to_string(),
+            source_type: "derived".to_string(),
+            fields: vec![
+                crate::codegen::types::FieldDef {
+                    name: "f_pid_5".to_string(),
+                    field_type: "String".to_string(),
+                    serialized_as: Some("patient_name".to_string()),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        generate_struct(&mut output, &entity, "PatientCore").unwrap();
+        generate_serialization_methods(&mut output, &entity).unwrap();
+
+        let generated = String::from_utf8(output).unwrap();
+        assert!(generated.contains("#[serde(rename = \"patient_name\")]\n    pub f_pid_5: String"));
+        assert!(generated.contains("map.insert(\"patient_name\".to_string(), serde_json::to_value(&self.f_pid_5)"));
+    }
+
     #[test]
     fn test_generate_root_entity() {
         let entity = EntityDef {
@@ -1091,4 +1558,151 @@ mod tests {
         assert!(generated.contains("parent_1: &Parent1Core"));
         assert!(generated.contains("parent_2: &Parent2Core"));
     }
+
+    #[test]
+    fn test_generate_example_tests() {
+        let entities = crate::codegen::parse_entities(r#"
+entity:
+  name: Order
+  source_type: root
+  fields:
+    - name: code
+      type: String
+  examples:
+    - name: parses_code
+      input: 'A1,"x"'
+      expected:
+        code: A1
+---
+entity:
+  name: Shipment
+  source_type: derived
+  parent: Order
+  fields:
+    - name: code
+      type: String
+  examples:
+    - name: copies_code
+      sources:
+        Order: {code: A1}
+      expected:
+        code: A1
+"#).unwrap();
+
+        let mut output = Vec::new();
+        generate_example_tests(&mut output, &entities).unwrap();
+        let generated = String::from_utf8(output).unwrap();
+        assert!(generated.contains("fn order_parses_code()"));
+        assert!(generated.contains("OrderCore::from_string(r#\"A1,\"x\"\"#)"));
+        assert!(generated.contains("let order: OrderCore = serde_json::from_value(fixture(r#\"{\"code\":\"A1\"}\"#))"));
+        assert!(generated.contains("ShipmentCore::from_sources(&order)"));
+        assert!(generated.contains("assert_field(\"Shipment.code\", &entity.code, r#\"\"A1\"\"#);"));
+
+        let mut missing_source = entities.clone();
+        missing_source[1].examples[0].sources.clear();
+        let err = generate_example_tests(&mut Vec::new(), &missing_source).err().unwrap();
+        assert!(err.to_string().contains("missing source 'Order'"), "{}", err);
+    }
+
+    #[test]
+    fn test_generate_repeated_by_count_entity() {
+        let entities = crate::codegen::parse_entities(r#"
+entity:
+  name: Claim
+  source_type: root
+  fields:
+    - name: line_count
+      type: String
+    - name: lines
+      type: String
+      nullable: true
+---
+entity:
+  name: ClaimLine
+  source_type: derived
+  repetition: repeated
+  repeated_by_count:
+    entity: Claim
+    count_field: line_count
+    field: lines
+    stride: 8
+    offset: 2
+    each_known_as: line
+  fields:
+    - name: code
+      type: String
+      computed_from:
+        transform: extract_fixed_width
+        sources:
+          - line
+        args:
+          start: 0
+          length: 4
+"#).unwrap();
+
+        let mut output = Vec::new();
+        generate_entity(&mut output, &entities[1], &entities, &RustCodegenConfig::default()).unwrap();
+        let generated = String::from_utf8(output).unwrap();
+        assert!(generated.contains("pub fn from_parent_repeated(\n        claim: &ClaimCore"), "{}", generated);
+        assert!(generated.contains("let data: &str = claim.lines.as_deref().unwrap_or(\"\");"), "{}", generated);
+        assert!(generated.contains("(0..claim.line_count.trim().parse::<usize>().unwrap_or(0))"), "{}", generated);
+        assert!(generated.contains("let start = 2 + i * 8;"), "{}", generated);
+        assert!(generated.contains("let line = &line_slice.to_string();"), "{}", generated);
+    }
+
+    #[test]
+    fn test_generate_json_input_attributes() {
+        let entities = crate::codegen::parse_entities(r#"
+entity:
+  name: Order
+  source_type: root
+  json_input:
+    rename_all: camelCase
+    numbers_from_strings: true
+    aliases:
+      order_key: [OrderID]
+  fields:
+    - name: order_key
+      type: Integer
+      nullable: false
+    - name: total_price
+      type: Float
+      nullable: true
+    - name: status
+      type: String
+"#).unwrap();
+
+        let mut output = Vec::new();
+        generate_rust_code(&mut output, &entities, &RustCodegenConfig::default()).unwrap();
+        let generated = String::from_utf8(output).unwrap();
+        assert!(generated.contains("mod lenient_numbers {"), "{}", generated);
+        assert!(generated.contains("#[serde(rename_all(deserialize = \"camelCase\"))]\npub struct OrderCore"), "{}", generated);
+        assert!(generated.contains(
+            "    #[serde(alias = \"OrderID\")]\n    #[serde(deserialize_with = \"lenient_numbers::number\")]\n    pub order_key: i64,"
+        ), "{}", generated);
+        assert!(generated.contains(
+            "    #[serde(default, deserialize_with = \"lenient_numbers::option_number\")]\n    pub total_price: Option<f64>,"
+        ), "{}", generated);
+        assert!(!generated.contains("lenient_numbers::number\")]\n    pub status"), "{}", generated);
+    }
+
+    #[test]
+    fn test_generate_encoded_constructors() {
+        let entities = crate::codegen::parse_entities(r#"
+entity:
+  name: Claim
+  source_type: root
+  encoding: windows-1252
+  fields:
+    - name: raw
+      type: String
+"#).unwrap();
+
+        let mut output = Vec::new();
+        generate_entity(&mut output, &entities[0], &entities, &RustCodegenConfig::default()).unwrap();
+        let generated = String::from_utf8(output).unwrap();
+        assert!(generated.contains("pub fn from_bytes(raw_input: &[u8]) -> Result<Self, String>"), "{}", generated);
+        assert!(generated.contains("let encoding: nomnom::runtime::InputEncoding = \"windows-1252\".parse()?;"), "{}", generated);
+        assert!(generated.contains("pub fn from_reader<R: std::io::Read>(mut reader: R)"), "{}", generated);
+    }
 }
@@ -0,0 +1,270 @@
+//! Entity YAML scaffolding for `nomnom new-entity`.
+//!
+//! Renders a commented entity skeleton for the common starting points (plain
+//! root entity, CSV line, JSON document, derived from a parent) and validates
+//! it with the same loader used for real configs.
+
+use crate::codegen::yaml_loader::parse_entity;
+use crate::codegen::EntityDef;
+
+/// Field types accepted by the scaffolder
+pub const SCAFFOLD_FIELD_TYPES: &[&str] = &[
+    "String", "Integer", "Float", "Boolean", "DateTime", "Date", "Json", "Object", "List[String]",
+];
+
+/// Starting point for a new entity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityTemplate {
+    /// Root entity with plain fields (loaded directly from JSON/CSV input)
+    Root,
+    /// Derived entity reading columns from a parent's CSV line
+    Csv,
+    /// Derived entity reading paths from a parent's JSON document
+    Json,
+    /// Derived entity copying fields from a parent entity
+    Derived,
+}
+
+impl EntityTemplate {
+    /// All templates, in the order they are offered
+    pub const ALL: [EntityTemplate; 4] = [
+        EntityTemplate::Root,
+        EntityTemplate::Csv,
+        EntityTemplate::Json,
+        EntityTemplate::Derived,
+    ];
+
+    /// Template name as used on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            EntityTemplate::Root => "root",
+            EntityTemplate::Csv => "csv",
+            EntityTemplate::Json => "json",
+            EntityTemplate::Derived => "derived",
+        }
+    }
+
+    /// One-line description for prompts and help
+    pub fn description(&self) -> &'static str {
+        match self {
+            EntityTemplate::Root => "root entity with plain fields",
+            EntityTemplate::Csv => "fields read by column from a parent's CSV line",
+            EntityTemplate::Json => "fields read by path from a parent's JSON document",
+            EntityTemplate::Derived => "fields copied from a parent entity",
+        }
+    }
+
+    /// Parent used when none is given
+    pub fn default_parent(&self) -> Option<&'static str> {
+        match self {
+            EntityTemplate::Csv => Some("CsvFile"),
+            EntityTemplate::Json => Some("JsonDocument"),
+            EntityTemplate::Root | EntityTemplate::Derived => None,
+        }
+    }
+
+    /// Whether the template derives from a parent entity
+    pub fn needs_parent(&self) -> bool {
+        !matches!(self, EntityTemplate::Root)
+    }
+
+    /// Parse a template name (case-insensitive)
+    pub fn parse(name: &str) -> Result<Self, String> {
+        Self::ALL.iter()
+            .copied()
+            .find(|t| t.name().eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| format!(
+                "Unknown template '{}' (expected one of: {})",
+                name,
+                Self::ALL.iter().map(|t| t.name()).collect::<Vec<_>>().join(", ")
+            ))
+    }
+}
+
+/// A field to scaffold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScaffoldField {
+    pub name: String,
+    pub field_type: String,
+}
+
+impl ScaffoldField {
+    /// Parse `name` or `name:Type` (type defaults to String)
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (name, field_type) = match spec.split_once(':') {
+            Some((name, ty)) => (name.trim(), ty.trim()),
+            None => (spec.trim(), "String"),
+        };
+
+        let valid_name = name.chars().next().map_or(false, |c| c.is_ascii_lowercase() || c == '_')
+            && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if !valid_name {
+            return Err(format!("Invalid field name '{}' (use snake_case)", name));
+        }
+
+        let field_type = SCAFFOLD_FIELD_TYPES.iter()
+            .find(|t| t.eq_ignore_ascii_case(field_type))
+            .ok_or_else(|| format!(
+                "Unknown type '{}' for field '{}' (expected one of: {})",
+                field_type, name, SCAFFOLD_FIELD_TYPES.join(", ")
+            ))?;
+
+        Ok(Self { name: name.to_string(), field_type: field_type.to_string() })
+    }
+}
+
+/// Everything needed to render a new entity YAML file
+#[derive(Debug, Clone)]
+pub struct EntityScaffold {
+    pub name: String,
+    pub template: EntityTemplate,
+    pub parent: Option<String>,
+    pub fields: Vec<ScaffoldField>,
+}
+
+impl EntityScaffold {
+    /// File name the entity is written to (matches the loaders' lowercase lookup)
+    pub fn file_name(&self) -> String {
+        format!("{}.yaml", self.name.to_lowercase())
+    }
+
+    /// Parent entity name, falling back to the template default
+    pub fn parent_name(&self) -> Option<&str> {
+        self.parent.as_deref().or(self.template.default_parent())
+    }
+
+    /// Render the YAML skeleton
+    pub fn render(&self) -> String {
+        let mut yaml = String::new();
+        let parent = self.parent_name().unwrap_or("Parent");
+
+        yaml.push_str(&format!("# {} entity ({} template)\n", self.name, self.template.name()));
+        match self.template {
+            EntityTemplate::Root => {
+                yaml.push_str("# Root entities are loaded directly from input records;\n");
+                yaml.push_str("# add a `persistence:` section to store them in the database.\n");
+            }
+            EntityTemplate::Csv => {
+                yaml.push_str(&format!("# Each field reads one column of {}.raw_line (column_index is 0-based).\n", parent));
+                yaml.push_str("# Register an `extract_csv_field` transform in your transforms config.\n");
+            }
+            EntityTemplate::Json => {
+                yaml.push_str(&format!("# Each field reads a JSONPath out of {}.raw_json.\n", parent));
+                yaml.push_str("# Register an `extract_json_field` transform in your transforms config.\n");
+            }
+            EntityTemplate::Derived => {
+                yaml.push_str(&format!("# Each field is copied from the same-named field on {}.\n", parent));
+                yaml.push_str("# Swap `copy_field` for any registered transform to compute values instead.\n");
+            }
+        }
+        yaml.push('\n');
+
+        yaml.push_str("entity:\n");
+        yaml.push_str(&format!("  name: {}\n", self.name));
+        if self.template.needs_parent() {
+            yaml.push_str("  source_type: derived\n");
+            yaml.push_str(&format!("  parent: {}\n", parent));
+        } else {
+            yaml.push_str("  source_type: root\n");
+        }
+        yaml.push_str(&format!("  doc: \"TODO: describe {}\"\n", self.name));
+        yaml.push('\n');
+
+        yaml.push_str("  fields:\n");
+        if self.fields.is_empty() {
+            yaml.push_str("    # - name: example_field\n");
+            yaml.push_str("    #   type: String\n");
+            yaml.push_str("    #   nullable: true\n");
+            yaml.push_str("    []\n");
+            return yaml;
+        }
+
+        for (i, field) in self.fields.iter().enumerate() {
+            if i > 0 {
+                yaml.push('\n');
+            }
+            yaml.push_str(&format!("    - name: {}\n", field.name));
+            yaml.push_str(&format!("      type: {}\n", field.field_type));
+            yaml.push_str("      nullable: true\n");
+            yaml.push_str(&format!("      doc: \"TODO: describe {}\"\n", field.name));
+
+            let (transform, source_field, arg) = match self.template {
+                EntityTemplate::Root => continue,
+                EntityTemplate::Csv => ("extract_csv_field", "raw_line", format!("column_index: {}", i)),
+                EntityTemplate::Json => ("extract_json_field", "raw_json", format!("json_path: \"$.{}\"", field.name)),
+                EntityTemplate::Derived => ("copy_field", field.name.as_str(), String::new()),
+            };
+            yaml.push_str("      computed_from:\n");
+            yaml.push_str(&format!("        transform: {}\n", transform));
+            yaml.push_str("        sources:\n");
+            yaml.push_str(&format!("          - source: {}\n", parent));
+            yaml.push_str(&format!("            field: {}\n", source_field));
+            if !arg.is_empty() {
+                yaml.push_str("        args:\n");
+                yaml.push_str(&format!("          {}\n", arg));
+            }
+        }
+
+        yaml
+    }
+
+    /// Render the skeleton and check it loads as a valid entity
+    pub fn render_validated(&self) -> Result<(String, EntityDef), String> {
+        let valid_name = self.name.chars().next().map_or(false, |c| c.is_ascii_uppercase())
+            && self.name.chars().all(|c| c.is_ascii_alphanumeric());
+        if !valid_name {
+            return Err(format!("Invalid entity name '{}' (use PascalCase)", self.name));
+        }
+
+        let yaml = self.render();
+        let entity = parse_entity(&yaml)
+            .map_err(|e| format!("Generated YAML for '{}' is invalid: {}", self.name, e))?;
+        Ok((yaml, entity))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scaffold(template: EntityTemplate) -> EntityScaffold {
+        EntityScaffold {
+            name: "User".to_string(),
+            template,
+            parent: None,
+            fields: vec![
+                ScaffoldField::parse("user_id").unwrap(),
+                ScaffoldField::parse("age:integer").unwrap(),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_every_template_renders_valid_yaml() {
+        for template in EntityTemplate::ALL {
+            let (_, entity) = scaffold(template).render_validated().unwrap();
+            assert_eq!(entity.name, "User");
+            assert_eq!(entity.fields.len(), 2);
+            assert_eq!(entity.fields[1].field_type, "Integer");
+            assert_eq!(entity.is_root(), template == EntityTemplate::Root);
+        }
+    }
+
+    #[test]
+    fn test_csv_template_uses_column_index() {
+        let (yaml, entity) = scaffold(EntityTemplate::Csv).render_validated().unwrap();
+        assert!(yaml.contains("column_index: 1"));
+        assert_eq!(entity.parent.as_deref(), Some("CsvFile"));
+        assert_eq!(entity.fields[0].computed_from.as_ref().unwrap().transform, "extract_csv_field");
+    }
+
+    #[test]
+    fn test_scaffold_rejects_bad_names() {
+        assert!(ScaffoldField::parse("UserId").is_err());
+        assert!(ScaffoldField::parse("id:Uuid").is_err());
+
+        let mut bad = scaffold(EntityTemplate::Root);
+        bad.name = "user record".to_string();
+        assert!(bad.render_validated().is_err());
+    }
+}
@@ -0,0 +1,204 @@
+//! SQL syntax that differs between target databases.
+//!
+//! Generators writing SQL ask a [`SqlDialect`] for placeholders, conflict
+//! handling and timestamps instead of branching on the database themselves,
+//! so every generator emits the same syntax for the same database.
+
+use crate::codegen::DatabaseType;
+use std::error::Error;
+use std::io::Write;
+
+/// Dialect-specific pieces of generated SQL
+pub trait SqlDialect {
+    /// Bind placeholder for the 1-based parameter `index`
+    fn placeholder(&self, index: usize) -> String;
+
+    /// INSERT that skips rows conflicting on `conflict_columns`
+    ///
+    /// `predicate` matches a partial unique index (e.g. soft-deleted rows
+    /// excluded) where the database needs it. Without conflict columns this
+    /// is a plain INSERT.
+    fn insert_ignore_clause(
+        &self,
+        table: &str,
+        columns: &[&str],
+        values: &str,
+        conflict_columns: &[&str],
+        predicate: Option<&str>,
+    ) -> String;
+
+    /// Clause making an INSERT update `update_columns` of the conflicting row,
+    /// appended after `VALUES (...)`
+    fn upsert_clause(&self, conflict_columns: &[&str], update_columns: &[&str]) -> String;
+
+    /// Expression for the current timestamp
+    fn now_expr(&self) -> &'static str;
+
+    /// Select item reading `expr` as the BIGINT column `generated_id`
+    fn generated_id_select(&self, expr: &str) -> String;
+
+    /// `insert` returning the new row's `id_column` as `generated_id`, and no
+    /// row when the insert was skipped
+    ///
+    /// `None` where an INSERT can't return values (MySQL); the id is then
+    /// read with [`SqlDialect::last_insert_id_query`] after the insert.
+    fn insert_returning_id(&self, insert: &str, id_column: &str) -> Option<String>;
+
+    /// Query reading the id generated by the connection's last INSERT as
+    /// `generated_id`
+    fn last_insert_id_query(&self) -> String;
+}
+
+impl SqlDialect for DatabaseType {
+    fn placeholder(&self, index: usize) -> String {
+        match self {
+            DatabaseType::PostgreSQL => format!("${}", index),
+            DatabaseType::MySQL | DatabaseType::MariaDB => "?".to_string(),
+        }
+    }
+
+    fn insert_ignore_clause(
+        &self,
+        table: &str,
+        columns: &[&str],
+        values: &str,
+        conflict_columns: &[&str],
+        predicate: Option<&str>,
+    ) -> String {
+        let insert = format!("INSERT INTO {} ({}) VALUES ({})", table, columns.join(", "), values);
+        if conflict_columns.is_empty() {
+            return insert;
+        }
+        match self {
+            DatabaseType::PostgreSQL => {
+                let predicate = predicate.map(|p| format!(" WHERE {}", p)).unwrap_or_default();
+                format!("{} ON CONFLICT ({}){} DO NOTHING", insert, conflict_columns.join(", "), predicate)
+            }
+            // MySQL has no partial indexes, the predicate doesn't apply
+            DatabaseType::MySQL | DatabaseType::MariaDB => insert.replacen("INSERT", "INSERT IGNORE", 1),
+        }
+    }
+
+    fn upsert_clause(&self, conflict_columns: &[&str], update_columns: &[&str]) -> String {
+        match self {
+            DatabaseType::PostgreSQL if update_columns.is_empty() => {
+                format!(" ON CONFLICT ({}) DO NOTHING", conflict_columns.join(", "))
+            }
+            DatabaseType::PostgreSQL => {
+                let updates: Vec<String> = update_columns.iter()
+                    .map(|c| format!("{} = EXCLUDED.{}", c, c))
+                    .collect();
+                format!(" ON CONFLICT ({}) DO UPDATE SET {}", conflict_columns.join(", "), updates.join(", "))
+            }
+            DatabaseType::MySQL | DatabaseType::MariaDB => {
+                // MySQL resolves conflicts on any unique key; a no-op update
+                // of the first conflict column keeps the existing row
+                let updates: Vec<String> = if update_columns.is_empty() {
+                    conflict_columns.iter().take(1).map(|c| format!("{} = {}", c, c)).collect()
+                } else {
+                    update_columns.iter().map(|c| format!("{} = VALUES({})", c, c)).collect()
+                };
+                format!(" ON DUPLICATE KEY UPDATE {}", updates.join(", "))
+            }
+        }
+    }
+
+    fn now_expr(&self) -> &'static str {
+        match self {
+            DatabaseType::PostgreSQL => "now()",
+            DatabaseType::MySQL | DatabaseType::MariaDB => "CURRENT_TIMESTAMP",
+        }
+    }
+
+    fn generated_id_select(&self, expr: &str) -> String {
+        match self {
+            DatabaseType::PostgreSQL => format!("CAST({} AS BIGINT) AS generated_id", expr),
+            DatabaseType::MySQL | DatabaseType::MariaDB => format!("CAST({} AS SIGNED) AS generated_id", expr),
+        }
+    }
+
+    fn insert_returning_id(&self, insert: &str, id_column: &str) -> Option<String> {
+        match self {
+            DatabaseType::PostgreSQL => Some(format!("{} RETURNING {}", insert, self.generated_id_select(id_column))),
+            DatabaseType::MySQL | DatabaseType::MariaDB => None,
+        }
+    }
+
+    fn last_insert_id_query(&self) -> String {
+        match self {
+            DatabaseType::PostgreSQL => format!("SELECT {}", self.generated_id_select("lastval()")),
+            DatabaseType::MySQL | DatabaseType::MariaDB => format!("SELECT {}", self.generated_id_select("LAST_INSERT_ID()")),
+        }
+    }
+}
+
+/// Write one `#[cfg(feature = ...)]` block per database feature of a
+/// generated crate, each evaluating to a `diesel::sql_query` built from the
+/// SQL `statement` returns for that dialect
+pub(crate) fn write_sql_query_per_feature(
+    output: &mut std::fs::File,
+    indent: &str,
+    statement: impl Fn(&dyn SqlDialect) -> String,
+) -> Result<(), Box<dyn Error>> {
+    for db_type in [DatabaseType::PostgreSQL, DatabaseType::MySQL] {
+        writeln!(output, "{}#[cfg(feature = \"{}\")]", indent, db_type.cargo_feature())?;
+        writeln!(output, "{}{{", indent)?;
+        writeln!(output, "{}    diesel::sql_query(", indent)?;
+        writeln!(output, "{}        r#\"{}\"#", indent, statement(&db_type))?;
+        writeln!(output, "{}    )", indent)?;
+        writeln!(output, "{}}}", indent)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_ignore_clause() {
+        let pg = DatabaseType::PostgreSQL;
+        assert_eq!(pg.placeholder(2), "$2");
+        assert_eq!(
+            pg.insert_ignore_clause("orders", &["code", "site"], "$1, $2", &["code"], Some("deleted_at IS NULL")),
+            "INSERT INTO orders (code, site) VALUES ($1, $2) ON CONFLICT (code) WHERE deleted_at IS NULL DO NOTHING"
+        );
+        assert_eq!(
+            pg.insert_ignore_clause("orders", &["code"], "$1", &[], None),
+            "INSERT INTO orders (code) VALUES ($1)"
+        );
+
+        let mysql = DatabaseType::MariaDB;
+        assert_eq!(mysql.placeholder(2), "?");
+        assert_eq!(
+            mysql.insert_ignore_clause("orders", &["code"], "?", &["code"], Some("deleted_at IS NULL")),
+            "INSERT IGNORE INTO orders (code) VALUES (?)"
+        );
+    }
+
+    #[test]
+    fn test_upsert_clause() {
+        assert_eq!(
+            DatabaseType::PostgreSQL.upsert_clause(&["code"], &["name", "price"]),
+            " ON CONFLICT (code) DO UPDATE SET name = EXCLUDED.name, price = EXCLUDED.price"
+        );
+        assert_eq!(
+            DatabaseType::MySQL.upsert_clause(&["code"], &["name"]),
+            " ON DUPLICATE KEY UPDATE name = VALUES(name)"
+        );
+        assert_eq!(DatabaseType::MySQL.upsert_clause(&["code"], &[]), " ON DUPLICATE KEY UPDATE code = code");
+    }
+
+    #[test]
+    fn test_generated_id_queries() {
+        let pg = DatabaseType::PostgreSQL;
+        assert_eq!(
+            pg.insert_returning_id("INSERT INTO orders (code) VALUES ($1)", "id").unwrap(),
+            "INSERT INTO orders (code) VALUES ($1) RETURNING CAST(id AS BIGINT) AS generated_id"
+        );
+
+        let mysql = DatabaseType::MySQL;
+        assert!(mysql.insert_returning_id("INSERT INTO orders (code) VALUES (?)", "id").is_none());
+        assert_eq!(mysql.last_insert_id_query(), "SELECT CAST(LAST_INSERT_ID() AS SIGNED) AS generated_id");
+    }
+}
@@ -0,0 +1,127 @@
+//! OpenTelemetry support shared by the generated ingestion server and worker.
+//!
+//! Both services get a `telemetry.rs` module and an `otel` cargo feature.
+//! With the feature compiled in and `OTEL_ENABLED=true` at runtime, spans are
+//! exported over OTLP and the W3C trace context travels from the ingestion
+//! server to the worker in `MessageEnvelope::trace_context`. Without the
+//! feature the propagation helpers are no-ops.
+
+use std::error::Error;
+use std::io::Write;
+use std::path::Path;
+
+/// Write the `otel` feature line for a generated `[features]` table
+pub fn write_otel_feature<W: Write>(output: &mut W) -> std::io::Result<()> {
+    writeln!(
+        output,
+        "otel = [\"dep:opentelemetry\", \"dep:opentelemetry_sdk\", \"dep:opentelemetry-otlp\", \"dep:tracing-opentelemetry\"]"
+    )
+}
+
+/// Write the optional OpenTelemetry dependencies enabled by the `otel` feature
+pub fn write_otel_dependencies<W: Write>(output: &mut W) -> std::io::Result<()> {
+    writeln!(output, "# OpenTelemetry (optional, enabled with --features otel)")?;
+    writeln!(output, "opentelemetry = {{ version = \"0.22\", optional = true }}")?;
+    writeln!(output, "opentelemetry_sdk = {{ version = \"0.22\", features = [\"rt-tokio\"], optional = true }}")?;
+    writeln!(output, "opentelemetry-otlp = {{ version = \"0.15\", optional = true }}")?;
+    writeln!(output, "tracing-opentelemetry = {{ version = \"0.23\", optional = true }}\n")
+}
+
+/// Generate `src/telemetry.rs` for a service
+///
+/// `service_name` is the default `service.name` resource attribute
+/// (overridable at runtime with `OTEL_SERVICE_NAME`).
+pub fn generate_telemetry_rs(output_dir: &Path, service_name: &str) -> Result<(), Box<dyn Error>> {
+    let file_path = output_dir.join("src/telemetry.rs");
+    let mut output = std::fs::File::create(&file_path)?;
+
+    writeln!(output, "//! OpenTelemetry trace export and propagation")?;
+    writeln!(output, "//!")?;
+    writeln!(output, "//! Compiled in with the `otel` feature and switched on with OTEL_ENABLED=true.")?;
+    writeln!(output, "//! Spans are exported over OTLP (OTEL_EXPORTER_OTLP_ENDPOINT) and the W3C trace")?;
+    writeln!(output, "//! context is carried between services in the message envelope.")?;
+    writeln!(output)?;
+    writeln!(output, "use std::collections::HashMap;\n")?;
+
+    writeln!(output, "/// Whether OTEL_ENABLED is set to true/1")?;
+    writeln!(output, "#[cfg(feature = \"otel\")]")?;
+    writeln!(output, "pub fn enabled() -> bool {{")?;
+    writeln!(output, "    std::env::var(\"OTEL_ENABLED\")")?;
+    writeln!(output, "        .map(|v| v == \"1\" || v.eq_ignore_ascii_case(\"true\"))")?;
+    writeln!(output, "        .unwrap_or(false)")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// Tracing layer exporting spans over OTLP (None when disabled or misconfigured)")?;
+    writeln!(output, "#[cfg(feature = \"otel\")]")?;
+    writeln!(output, "pub fn otel_layer<S>() -> Option<tracing_opentelemetry::OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>>")?;
+    writeln!(output, "where")?;
+    writeln!(output, "    S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,")?;
+    writeln!(output, "{{")?;
+    writeln!(output, "    if !enabled() {{")?;
+    writeln!(output, "        return None;")?;
+    writeln!(output, "    }}\n")?;
+    writeln!(output, "    opentelemetry::global::set_text_map_propagator(")?;
+    writeln!(output, "        opentelemetry_sdk::propagation::TraceContextPropagator::new(),")?;
+    writeln!(output, "    );\n")?;
+    writeln!(output, "    let service_name = std::env::var(\"OTEL_SERVICE_NAME\")")?;
+    writeln!(output, "        .unwrap_or_else(|_| \"{}\".to_string());", service_name)?;
+    writeln!(output, "    let tracer = opentelemetry_otlp::new_pipeline()")?;
+    writeln!(output, "        .tracing()")?;
+    writeln!(output, "        .with_exporter(opentelemetry_otlp::new_exporter().tonic())")?;
+    writeln!(output, "        .with_trace_config(opentelemetry_sdk::trace::config().with_resource(")?;
+    writeln!(output, "            opentelemetry_sdk::Resource::new(vec![")?;
+    writeln!(output, "                opentelemetry::KeyValue::new(\"service.name\", service_name),")?;
+    writeln!(output, "            ]),")?;
+    writeln!(output, "        ))")?;
+    writeln!(output, "        .install_batch(opentelemetry_sdk::runtime::Tokio);\n")?;
+    writeln!(output, "    match tracer {{")?;
+    writeln!(output, "        Ok(tracer) => Some(tracing_opentelemetry::layer().with_tracer(tracer)),")?;
+    writeln!(output, "        Err(e) => {{")?;
+    writeln!(output, "            // Tracing is not initialized yet, so report on stderr")?;
+    writeln!(output, "            eprintln!(\"OpenTelemetry disabled: failed to install OTLP exporter: {{}}\", e);")?;
+    writeln!(output, "            None")?;
+    writeln!(output, "        }}")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// W3C trace context of the current span, to attach to an outgoing envelope")?;
+    writeln!(output, "#[cfg(feature = \"otel\")]")?;
+    writeln!(output, "pub fn current_trace_context() -> Option<HashMap<String, String>> {{")?;
+    writeln!(output, "    use opentelemetry::propagation::TextMapPropagator;")?;
+    writeln!(output, "    use tracing_opentelemetry::OpenTelemetrySpanExt;\n")?;
+    writeln!(output, "    if !enabled() {{")?;
+    writeln!(output, "        return None;")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "    let context = tracing::Span::current().context();")?;
+    writeln!(output, "    let mut carrier = HashMap::new();")?;
+    writeln!(output, "    opentelemetry::global::get_text_map_propagator(|propagator| {{")?;
+    writeln!(output, "        propagator.inject_context(&context, &mut carrier)")?;
+    writeln!(output, "    }});")?;
+    writeln!(output, "    (!carrier.is_empty()).then_some(carrier)")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// Make `span` a child of the trace carried by an incoming envelope")?;
+    writeln!(output, "#[cfg(feature = \"otel\")]")?;
+    writeln!(output, "pub fn set_parent(span: &tracing::Span, trace_context: &HashMap<String, String>) {{")?;
+    writeln!(output, "    use opentelemetry::propagation::TextMapPropagator;")?;
+    writeln!(output, "    use tracing_opentelemetry::OpenTelemetrySpanExt;\n")?;
+    writeln!(output, "    if !enabled() {{")?;
+    writeln!(output, "        return;")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "    let parent = opentelemetry::global::get_text_map_propagator(|propagator| {{")?;
+    writeln!(output, "        propagator.extract(trace_context)")?;
+    writeln!(output, "    }});")?;
+    writeln!(output, "    span.set_parent(parent);")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// Without the `otel` feature no trace context is propagated")?;
+    writeln!(output, "#[cfg(not(feature = \"otel\"))]")?;
+    writeln!(output, "pub fn current_trace_context() -> Option<HashMap<String, String>> {{")?;
+    writeln!(output, "    None")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "#[cfg(not(feature = \"otel\"))]")?;
+    writeln!(output, "pub fn set_parent(_span: &tracing::Span, _trace_context: &HashMap<String, String>) {{}}")?;
+
+    Ok(())
+}
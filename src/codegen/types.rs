@@ -4,7 +4,8 @@
 //! and are used during code generation.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use indexmap::IndexMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 fn default_fk_nullable() -> bool {
     true
@@ -46,6 +47,152 @@ pub struct DatabaseConfig {
     pub unicity_fields: Vec<String>,
     #[serde(default)]
     pub foreign_keys: Vec<ForeignKeyConfig>,
+    /// SQL expressions inserted instead of a bound value, keyed by column
+    ///
+    /// `{field}` refers to the bound value of another inserted field and
+    /// `{{`/`}}` are literal braces, e.g.
+    /// `search_vector: "to_tsvector('english', {title})"`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pre_insert_sql: BTreeMap<String, String>,
+}
+
+/// Piece of a `pre_insert_sql` expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SqlPart {
+    /// Literal SQL
+    Text(String),
+    /// Bound value of the named field
+    Field(String),
+}
+
+/// One slot of an INSERT's VALUES list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InsertValue {
+    /// Bound value of the named field
+    Bind(String),
+    /// `pre_insert_sql` expression
+    Sql(Vec<SqlPart>),
+}
+
+impl InsertValue {
+    /// Fields bound by this slot, in placeholder order
+    pub fn binds(&self) -> Vec<&str> {
+        match self {
+            InsertValue::Bind(field) => vec![field.as_str()],
+            InsertValue::Sql(parts) => parts.iter()
+                .filter_map(|part| match part {
+                    SqlPart::Field(field) => Some(field.as_str()),
+                    SqlPart::Text(_) => None,
+                })
+                .collect(),
+        }
+    }
+
+    /// SQL for this slot, calling `value` for each bound field in order
+    pub fn to_sql(&self, value: &mut impl FnMut(&str) -> String) -> String {
+        match self {
+            InsertValue::Bind(field) => value(field),
+            InsertValue::Sql(parts) => parts.iter()
+                .map(|part| match part {
+                    SqlPart::Text(sql) => sql.clone(),
+                    SqlPart::Field(field) => value(field),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Split a `pre_insert_sql` expression into literal SQL and `{field}` references
+pub fn parse_sql_expression(expr: &str) -> Result<Vec<SqlPart>, String> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut chars = expr.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut field = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    field.push(c);
+                }
+                if !closed {
+                    return Err(format!("unterminated '{{' in '{}'", expr));
+                }
+                if field.is_empty() || !field.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    return Err(format!("invalid field reference '{{{}}}' in '{}'", field, expr));
+                }
+                if !text.is_empty() {
+                    parts.push(SqlPart::Text(std::mem::take(&mut text)));
+                }
+                parts.push(SqlPart::Field(field));
+            }
+            '}' => return Err(format!("unmatched '}}' in '{}'", expr)),
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        parts.push(SqlPart::Text(text));
+    }
+    Ok(parts)
+}
+
+impl DatabaseConfig {
+    /// Columns and VALUES slots for inserting `fields` (in insert order)
+    ///
+    /// A field whose column (from `column_of`) or name has a `pre_insert_sql`
+    /// expression takes the expression instead of a bound value; expression
+    /// columns matching no field are appended. Expressions may only reference
+    /// fields in `fields`.
+    pub fn insert_values(
+        &self,
+        fields: &[&str],
+        column_of: impl Fn(&str) -> String,
+    ) -> Result<Vec<(String, InsertValue)>, String> {
+        let expression = |key: &str| -> Result<InsertValue, String> {
+            let parts = parse_sql_expression(&self.pre_insert_sql[key])
+                .map_err(|e| format!("pre_insert_sql for '{}' on '{}': {}", key, self.conformant_table, e))?;
+            if let Some(SqlPart::Field(unknown)) = parts.iter()
+                .find(|p| matches!(p, SqlPart::Field(f) if !fields.contains(&f.as_str())))
+            {
+                return Err(format!(
+                    "pre_insert_sql for '{}' on '{}' references '{}', which is not an inserted field",
+                    key, self.conformant_table, unknown
+                ));
+            }
+            Ok(InsertValue::Sql(parts))
+        };
+
+        let mut used = HashSet::new();
+        let mut values = Vec::new();
+        for &field in fields {
+            let column = column_of(field);
+            let key = [column.as_str(), field].into_iter().find(|k| self.pre_insert_sql.contains_key(*k));
+            let value = match key {
+                Some(key) => {
+                    used.insert(key.to_string());
+                    expression(key)?
+                }
+                None => InsertValue::Bind(field.to_string()),
+            };
+            values.push((column, value));
+        }
+        for key in self.pre_insert_sql.keys().filter(|k| !used.contains(*k)) {
+            values.push((key.clone(), expression(key)?));
+        }
+        Ok(values)
+    }
 }
 
 /// Field override configuration for persistence
@@ -64,6 +211,43 @@ pub struct FieldOverride {
     pub index: Option<bool>,
     #[serde(default)]
     pub doc: Option<String>,
+    /// What a unicity field must hold for the worker to insert the row;
+    /// unset uses [`UnicityCheck::default_for`] the field's type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unicity_check: Option<UnicityCheck>,
+}
+
+/// Check a unicity field's value must pass before the worker inserts a row,
+/// so rows aren't keyed on missing or placeholder values
+///
+/// ```yaml
+/// field_overrides:
+///   - name: claim_number
+///     type: Integer
+///     unicity_check: non_zero
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnicityCheck {
+    /// Always insert
+    None,
+    /// The value must not be null
+    Present,
+    /// The value must be present and not an empty string or list
+    NonEmpty,
+    /// The value must be present and not zero (numeric fields)
+    NonZero,
+}
+
+impl UnicityCheck {
+    /// `non_empty` for strings and lists, `present` otherwise
+    pub fn default_for(field_type: &str) -> Self {
+        if field_type == "String" || field_type.starts_with("List[") {
+            UnicityCheck::NonEmpty
+        } else {
+            UnicityCheck::Present
+        }
+    }
 }
 
 /// Persistence configuration wrapper
@@ -76,6 +260,93 @@ pub struct PersistenceConfig {
     pub primary_key: Option<PrimaryKeyConfig>,
     #[serde(default)]
     pub field_overrides: Vec<FieldOverride>,
+    /// Mark rows deleted instead of removing them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soft_delete: Option<SoftDeleteConfig>,
+    /// Add `created_at`/`updated_at` audit columns; unset follows the
+    /// project-wide `timestamps` default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamps: Option<bool>,
+}
+
+/// Audit column set by the database when a row is inserted
+pub const CREATED_AT_COLUMN: &str = "created_at";
+
+/// Audit column set on insert and refreshed by every generated update
+pub const UPDATED_AT_COLUMN: &str = "updated_at";
+
+/// Turn on audit timestamps for every persistent entity that doesn't set
+/// `persistence.timestamps` itself
+///
+/// Entities declaring their own `created_at`/`updated_at` overrides are left
+/// alone. Applied once after loading, like the naming strategy.
+pub fn apply_default_timestamps(entities: &mut [EntityDef], enabled: bool) {
+    if !enabled {
+        return;
+    }
+    for persistence in entities.iter_mut().filter_map(|e| e.persistence.as_mut()) {
+        if persistence.database.is_some()
+            && persistence.timestamps.is_none()
+            && !persistence.declares_audit_columns()
+        {
+            persistence.timestamps = Some(true);
+        }
+    }
+}
+
+impl PersistenceConfig {
+    /// Whether `created_at`/`updated_at` columns are generated
+    pub fn has_timestamps(&self) -> bool {
+        self.timestamps.unwrap_or(false)
+    }
+
+    /// Whether a field override already uses one of the audit column names
+    pub fn declares_audit_columns(&self) -> bool {
+        self.field_overrides.iter()
+            .any(|f| f.name == CREATED_AT_COLUMN || f.name == UPDATED_AT_COLUMN)
+    }
+
+    /// Soft-delete column the unicity constraint is restricted to, i.e. only
+    /// rows where it is NULL must be unique
+    ///
+    /// Set when soft-deleted rows are not revived, since a new row then has to
+    /// coexist with its deleted predecessor.
+    pub fn live_unicity_column(&self) -> Option<&str> {
+        self.soft_delete.as_ref()
+            .filter(|soft_delete| !soft_delete.revive)
+            .map(|soft_delete| soft_delete.column.as_str())
+    }
+}
+
+fn default_soft_delete_column() -> String {
+    "deleted_at".to_string()
+}
+
+/// Soft-delete configuration for a persistent entity
+///
+/// ```yaml
+/// persistence:
+///   soft_delete:
+///     column: deleted_at
+///     revive: true
+/// ```
+///
+/// Adds a nullable timestamp column. Generated deletes set it instead of
+/// removing the row, and generated lookups skip rows where it is set.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SoftDeleteConfig {
+    /// Timestamp column set when a row is deleted
+    #[serde(default = "default_soft_delete_column")]
+    pub column: String,
+    /// When `get_or_create` matches a soft-deleted row, clear its deletion
+    /// timestamp and return it instead of inserting a new row.
+    ///
+    /// With `revive: false` the unicity constraint only covers live rows,
+    /// which needs a partial index (PostgreSQL). On MySQL soft-deleted rows
+    /// still hold their unicity values, so `revive: true` should be used.
+    #[serde(default)]
+    pub revive: bool,
 }
 
 /// Derivation configuration for derived entities
@@ -83,11 +354,107 @@ pub struct PersistenceConfig {
 pub struct DerivationConfig {
     /// Source entities this entity derives from (1 or more)
     #[serde(default, alias = "source_entity", alias = "parent_entities")]
-    pub source_entities: Option<serde_yaml::Value>,
+    pub source_entities: SourceEntities,
+}
+
+/// `derivation.source_entities`: named source bindings in declaration order
+///
+/// All three YAML shapes are accepted:
+///
+/// ```yaml
+/// source_entities: Patient                        # one source, bound as Patient
+/// source_entities: [Patient, Visit]               # bound under their entity names
+/// source_entities:                                # bound under the keys
+///   patient: Patient
+///   visit: {entity: Visit, ancillary: true}
+/// ```
+///
+/// It serializes back as the mapping form.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceEntities(pub Vec<SourceBinding>);
+
+/// One named source of a derived entity
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceBinding {
+    /// Name the source is bound under
+    pub alias: String,
+    pub spec: SourceEntitySpec,
+}
+
+impl SourceEntities {
+    /// Bindings in declaration order
+    pub fn iter(&self) -> std::slice::Iter<'_, SourceBinding> {
+        self.0.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Source entity names in declaration order
+    pub fn entity_names(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|binding| binding.spec.entity_name())
+    }
+
+    /// Entity bound under `alias`
+    pub fn resolve(&self, alias: &str) -> Option<&str> {
+        self.0.iter()
+            .find(|binding| binding.alias == alias)
+            .map(|binding| binding.spec.entity_name())
+    }
+}
+
+impl<'de> Deserialize<'de> for SourceEntities {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            One(String),
+            List(Vec<SourceEntitySpec>),
+            // serde_yaml's Mapping keeps declaration order
+            Named(serde_yaml::Mapping),
+        }
+
+        let bound_as_entity = |spec: SourceEntitySpec| SourceBinding {
+            alias: spec.entity_name().to_string(),
+            spec,
+        };
+        let raw = Option::<Raw>::deserialize(deserializer)?;
+        Ok(SourceEntities(match raw {
+            None => Vec::new(),
+            Some(Raw::One(name)) => vec![bound_as_entity(SourceEntitySpec::Simple(name))],
+            Some(Raw::List(specs)) => specs.into_iter().map(bound_as_entity).collect(),
+            Some(Raw::Named(map)) => map.into_iter()
+                .map(|(alias, spec)| {
+                    let alias = alias.as_str()
+                        .ok_or_else(|| D::Error::custom(format!("source_entities key {:?} must be a string", alias)))?
+                        .to_string();
+                    let spec = serde_yaml::from_value(spec).map_err(|e| D::Error::custom(format!(
+                        "source_entities.{}: expected an entity name or {{entity, ancillary}}: {}",
+                        alias, e
+                    )))?;
+                    Ok(SourceBinding { alias, spec })
+                })
+                .collect::<Result<_, D::Error>>()?,
+        }))
+    }
+}
+
+impl Serialize for SourceEntities {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for binding in &self.0 {
+            map.serialize_entry(&binding.alias, &binding.spec)?;
+        }
+        map.end()
+    }
 }
 
 /// Source entity specification
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum SourceEntitySpec {
     /// Simple string format: just entity name (defaults to core)
@@ -246,6 +613,13 @@ pub struct ComputedFrom {
     pub condition: Option<FieldCondition>,
 }
 
+impl ComputedFrom {
+    /// String argument by key, when `args` is a mapping
+    pub fn arg_str(&self, key: &str) -> Option<&str> {
+        self.args.as_ref()?.get(key)?.as_str()
+    }
+}
+
 /// Field definition in entity YAML
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(deny_unknown_fields)]
@@ -294,6 +668,267 @@ pub struct FieldDef {
     /// Domain-specific: root source (e.g., "raw" for raw_message field)
     #[serde(default)]
     pub root_source: Option<String>,
+    /// Key used when serializing to JSON (defaults to `name`)
+    #[serde(default, alias = "alias")]
+    pub serialized_as: Option<String>,
+    /// SQL column name (defaults to `name`)
+    #[serde(default)]
+    pub column: Option<String>,
+    /// Value used when the computed value is absent (nullable fields only)
+    #[serde(default)]
+    pub default: Option<serde_yaml::Value>,
+    /// Data quality rules checked after extraction
+    #[serde(default)]
+    pub validate: Option<FieldValidation>,
+    /// Parse the extracted string into the field's declared type
+    #[serde(default)]
+    pub coerce: Option<FieldCoercion>,
+}
+
+/// What to do when a coerced value does not parse
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OnParseError {
+    /// Store NULL for the field
+    #[default]
+    Null,
+    /// Drop the record (no entity is created)
+    Skip,
+    /// Reject the record with an error
+    Error,
+}
+
+/// String to number/boolean coercion for a field (`coerce:` block)
+///
+/// Thousands separators are stripped and the decimal separator is
+/// normalized to `.` before parsing. `locale` picks both separators;
+/// explicit separators override it.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct FieldCoercion {
+    /// Policy for values that do not parse
+    #[serde(default)]
+    pub on_parse_error: OnParseError,
+    /// Digit group separator to strip (e.g. "," in "1,234")
+    #[serde(default)]
+    pub thousands_separator: Option<String>,
+    /// Decimal separator (e.g. "," in "3,5"); defaults to "."
+    #[serde(default)]
+    pub decimal_separator: Option<String>,
+    /// Locale supplying default separators (e.g. "en_US", "de_DE", "fr")
+    #[serde(default)]
+    pub locale: Option<String>,
+}
+
+impl FieldCoercion {
+    /// Separators for a locale as (thousands, decimal)
+    pub fn locale_separators(locale: &str) -> Option<(Option<&'static str>, &'static str)> {
+        let locale = locale.trim().replace('-', "_").to_lowercase();
+        match locale.as_str() {
+            "c" | "posix" => return Some((None, ".")),
+            "de_ch" | "fr_ch" | "it_ch" => return Some((Some("'"), ".")),
+            _ => {}
+        }
+        let language = locale.split('_').next().unwrap_or_default();
+        match language {
+            "en" | "ja" | "zh" | "ko" | "he" | "th" => Some((Some(","), ".")),
+            "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" => Some((Some("."), ",")),
+            "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "no" | "uk" | "hu" => Some((Some(" "), ",")),
+            _ => None,
+        }
+    }
+
+    /// Effective (thousands, decimal) separators
+    pub fn separators(&self) -> (Option<String>, String) {
+        let (locale_thousands, locale_decimal) = self.locale.as_deref()
+            .and_then(Self::locale_separators)
+            .unwrap_or((None, "."));
+        let thousands = self.thousands_separator.clone()
+            .or_else(|| locale_thousands.map(str::to_string))
+            .filter(|s| !s.is_empty());
+        let decimal = self.decimal_separator.clone().unwrap_or_else(|| locale_decimal.to_string());
+        (thousands, decimal)
+    }
+
+    /// Check the coercion makes sense for the field
+    pub fn check(&self, field: &FieldDef) -> Result<(), String> {
+        let field_type = field.field_type.to_lowercase();
+        if !matches!(
+            field_type.as_str(),
+            "integer" | "int" | "bigint" | "long" | "float" | "double" | "boolean" | "bool"
+        ) {
+            return Err(format!(
+                "Field '{}' has coerce but type '{}' is not numeric or boolean",
+                field.name, field.field_type
+            ));
+        }
+
+        if !field.nullable {
+            return Err(format!("Field '{}' has coerce but is not nullable", field.name));
+        }
+
+        if let Some(ref locale) = self.locale {
+            if Self::locale_separators(locale).is_none() {
+                return Err(format!("Field '{}': unknown coerce locale '{}'", field.name, locale));
+            }
+        }
+
+        let (thousands, decimal) = self.separators();
+        if decimal.is_empty() {
+            return Err(format!("Field '{}': decimal_separator cannot be empty", field.name));
+        }
+        if thousands.as_deref() == Some(decimal.as_str()) {
+            return Err(format!(
+                "Field '{}': thousands and decimal separators are both '{}'",
+                field.name, decimal
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Validation rules for a field (`validate:` block)
+///
+/// A record whose value breaks a rule is rejected at extraction time.
+/// Absent (None) values are not checked.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct FieldValidation {
+    /// Regular expression the value must match (String fields)
+    #[serde(default)]
+    pub regex: Option<String>,
+    /// Inclusive lower bound (numeric fields)
+    #[serde(default)]
+    pub min: Option<f64>,
+    /// Inclusive upper bound (numeric fields)
+    #[serde(default)]
+    pub max: Option<f64>,
+    /// Allowed values
+    #[serde(default)]
+    pub one_of: Vec<serde_yaml::Value>,
+}
+
+impl FieldValidation {
+    /// Check the rules make sense for the field's type
+    pub fn check(&self, field: &FieldDef) -> Result<(), String> {
+        let field_type = field.field_type.to_lowercase();
+        let is_string = field_type == "string";
+        let is_numeric = matches!(
+            field_type.as_str(),
+            "integer" | "int" | "bigint" | "long" | "float" | "double"
+        );
+
+        if !is_string && !is_numeric && !matches!(field_type.as_str(), "boolean" | "bool") {
+            return Err(format!(
+                "Field '{}' has validate rules but type '{}' cannot be validated",
+                field.name, field.field_type
+            ));
+        }
+
+        if let Some(ref pattern) = self.regex {
+            if !is_string {
+                return Err(format!("Field '{}': regex validation requires a String field", field.name));
+            }
+            regex::Regex::new(pattern)
+                .map_err(|e| format!("Field '{}': invalid regex '{}': {}", field.name, pattern, e))?;
+        }
+
+        if (self.min.is_some() || self.max.is_some()) && !is_numeric {
+            return Err(format!("Field '{}': min/max validation requires a numeric field", field.name));
+        }
+
+        if let (Some(min), Some(max)) = (self.min, self.max) {
+            if min > max {
+                return Err(format!("Field '{}': validate min {} is greater than max {}", field.name, min, max));
+            }
+        }
+
+        if self.one_of.iter().any(|v| Self::scalar_string(v).is_none()) {
+            return Err(format!("Field '{}': one_of values must be scalars", field.name));
+        }
+
+        Ok(())
+    }
+
+    /// Allowed values rendered as strings (compared against the value's `to_string()`)
+    pub fn one_of_strings(&self) -> Vec<String> {
+        self.one_of.iter().filter_map(Self::scalar_string).collect()
+    }
+
+    fn scalar_string(value: &serde_yaml::Value) -> Option<String> {
+        match value {
+            serde_yaml::Value::String(s) => Some(s.clone()),
+            serde_yaml::Value::Number(n) => Some(n.to_string()),
+            serde_yaml::Value::Bool(b) => Some(b.to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl FieldDef {
+    /// Whether the field holds a JSON document (`Json`/`Object`, stored as JSONB/JSON)
+    pub fn is_json(&self) -> bool {
+        matches!(self.field_type.as_str(), "Json" | "Object")
+    }
+
+    /// Key this field is written under in JSON output
+    pub fn serialized_name(&self) -> &str {
+        self.serialized_as.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Column this field is stored in
+    pub fn column_name(&self) -> &str {
+        self.column.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Rust expression for the configured `default:`, type-checked against `field_type`
+    pub fn default_literal(&self) -> Result<Option<String>, String> {
+        let Some(ref value) = self.default else {
+            return Ok(None);
+        };
+
+        let mismatch = || format!(
+            "Default {:?} for field '{}' does not match type '{}'",
+            value, self.name, self.field_type
+        );
+
+        let literal = match self.field_type.to_lowercase().as_str() {
+            "string" => {
+                let s = value.as_str().ok_or_else(mismatch)?;
+                format!("{:?}.to_string()", s)
+            }
+            "integer" | "int" | "bigint" | "long" => {
+                value.as_i64().ok_or_else(mismatch)?.to_string()
+            }
+            "float" | "double" => {
+                let f = value.as_f64().ok_or_else(mismatch)?;
+                format!("{:?}", f)
+            }
+            "boolean" | "bool" => value.as_bool().ok_or_else(mismatch)?.to_string(),
+            _ => {
+                return Err(format!(
+                    "Field '{}' has a default but type '{}' does not support defaults",
+                    self.name, self.field_type
+                ));
+            }
+        };
+
+        Ok(Some(literal))
+    }
+
+    /// Names of sibling fields this field reads via `source: self`
+    pub fn self_references(&self) -> Vec<&str> {
+        let Some(ref computed) = self.computed_from else {
+            return Vec::new();
+        };
+
+        computed.sources.iter()
+            .chain(computed.condition.iter().map(|c| &c.field))
+            .filter(|source| source.source_name().eq_ignore_ascii_case("self"))
+            .filter_map(|source| source.field_name())
+            .collect()
+    }
 }
 
 /// Repeated-for specification (for repeated derived entities)
@@ -307,6 +942,80 @@ pub struct RepeatedFor {
     pub each_known_as: String,
 }
 
+/// Repeated-by-count specification (for fixed-width repeating groups)
+///
+/// The parent's `field` holds `count` repetitions of `stride` bytes back to
+/// back, starting `offset` bytes in, with `count` read from the parent's
+/// `count_field`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepeatedByCount {
+    /// Parent entity name
+    pub entity: String,
+    /// Field in parent holding the number of repetitions
+    pub count_field: String,
+    /// Field in parent holding the repeated data
+    pub field: String,
+    /// Bytes per repetition
+    pub stride: usize,
+    /// Bytes to skip before the first repetition
+    #[serde(default)]
+    pub offset: usize,
+    /// Name to use for each repetition's slice
+    pub each_known_as: String,
+}
+
+impl RepeatedByCount {
+    /// Rust expression iterating the `&str` slice of each repetition, for
+    /// generated code holding the parent entity in `parent_var`
+    ///
+    /// The count may be an integer or numeric text; a missing or unparsable
+    /// count yields no repetitions. Data shorter than the count implies
+    /// yields only the repetitions present, the last possibly partial (e.g.
+    /// trailing padding trimmed).
+    pub fn slices_expr(&self, parent: &EntityDef, parent_var: &str) -> Result<String, String> {
+        let parent_field = |name: &str| {
+            parent.fields.iter().find(|f| f.name == name).ok_or_else(|| format!(
+                "repeated_by_count field '{}' not found in parent '{}'",
+                name, parent.name
+            ))
+        };
+
+        let count = parent_field(&self.count_field)?;
+        let count_access = format!("{}.{}", parent_var, count.name);
+        let is_integer = matches!(
+            count.field_type.to_lowercase().as_str(),
+            "int" | "integer" | "bigint" | "long"
+        );
+        let count_expr = match (is_integer, count.nullable) {
+            (true, false) => format!("usize::try_from({}).unwrap_or(0)", count_access),
+            (true, true) => format!("{}.and_then(|n| usize::try_from(n).ok()).unwrap_or(0)", count_access),
+            (false, false) => format!("{}.trim().parse::<usize>().unwrap_or(0)", count_access),
+            (false, true) => format!(
+                "{}.as_deref().and_then(|n| n.trim().parse::<usize>().ok()).unwrap_or(0)",
+                count_access
+            ),
+        };
+
+        let data = parent_field(&self.field)?;
+        let data_expr = if data.nullable {
+            format!("{}.{}.as_deref().unwrap_or(\"\")", parent_var, data.name)
+        } else {
+            format!("{}.{}.as_str()", parent_var, data.name)
+        };
+
+        Ok(format!(
+            "{{ let data: &str = {data}; (0..{count}).map_while(move |i| {{ \
+             let start = {offset} + i * {stride}; \
+             data.get(start..(start + {stride}).min(data.len())).filter(|s| !s.is_empty()) }}) }}",
+            data = data_expr,
+            count = count_expr,
+            offset = self.offset,
+            stride = self.stride,
+        ))
+    }
+}
+
 /// Parent definition with source metadata
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 #[serde(deny_unknown_fields)]
@@ -408,6 +1117,103 @@ impl MinimalExistence {
     }
 }
 
+/// Entity-level guard: the entity is only extracted/persisted when this holds
+///
+/// Replaces the implicit "all unicity strings non-empty" check for entities
+/// that declare it.
+///
+/// ```yaml
+/// when:
+///   field: allergy_code
+///   operator: one_of
+///   value: [DA, FA]
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EntityCondition {
+    /// Field of this entity the condition reads
+    pub field: String,
+    /// Comparison to apply (defaults to non_empty)
+    #[serde(default)]
+    pub operator: ConditionOperator,
+    /// Operand for equals/not_equals (scalar) and one_of (list)
+    #[serde(default)]
+    pub value: Option<serde_yaml::Value>,
+}
+
+/// Operators for entity `when:` conditions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionOperator {
+    /// Value is present and not blank
+    #[default]
+    NonEmpty,
+    /// Value is present (may be blank)
+    Present,
+    /// Value equals `value`
+    Equals,
+    /// Value is absent or differs from `value`
+    NotEquals,
+    /// Value is one of the `value` list
+    OneOf,
+}
+
+impl EntityCondition {
+    /// Operand(s) rendered as strings, compared against the value's `to_string()`
+    pub fn operands(&self) -> Vec<String> {
+        let scalar = |v: &serde_yaml::Value| match v {
+            serde_yaml::Value::String(s) => Some(s.clone()),
+            serde_yaml::Value::Number(n) => Some(n.to_string()),
+            serde_yaml::Value::Bool(b) => Some(b.to_string()),
+            _ => None,
+        };
+
+        match self.value {
+            Some(serde_yaml::Value::Sequence(ref items)) => items.iter().filter_map(scalar).collect(),
+            Some(ref v) => scalar(v).into_iter().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Validate the condition against the entity it guards
+    pub fn validate(&self, entity: &EntityDef) -> Result<(), String> {
+        let field = entity.fields.iter()
+            .find(|f| f.name == self.field)
+            .ok_or_else(|| format!(
+                "Entity '{}': Field '{}' in when condition not found in entity definition",
+                entity.name, self.field
+            ))?;
+
+        if field.field_type.starts_with("List[") || field.field_type.starts_with("Vec<") {
+            return Err(format!(
+                "Entity '{}': when condition cannot test list field '{}'",
+                entity.name, self.field
+            ));
+        }
+
+        let needs_operand = matches!(
+            self.operator,
+            ConditionOperator::Equals | ConditionOperator::NotEquals | ConditionOperator::OneOf
+        );
+        let is_list = matches!(self.value, Some(serde_yaml::Value::Sequence(_)));
+
+        if needs_operand && self.operands().is_empty() {
+            return Err(format!(
+                "Entity '{}': when operator {:?} requires a value",
+                entity.name, self.operator
+            ));
+        }
+        if is_list != (self.operator == ConditionOperator::OneOf) && self.value.is_some() {
+            return Err(format!(
+                "Entity '{}': when value must be a list for one_of and a scalar otherwise",
+                entity.name
+            ));
+        }
+
+        Ok(())
+    }
+}
+
 /// Entity definition from YAML
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(deny_unknown_fields)]
@@ -430,6 +1236,9 @@ pub struct EntityDef {
     /// Repeated-for specification
     #[serde(default)]
     pub repeated_for: Option<RepeatedFor>,
+    /// Repeated-by-count specification (alternative to `repeated_for`)
+    #[serde(default)]
+    pub repeated_by_count: Option<RepeatedByCount>,
     /// Entity fields
     #[serde(default)]
     pub fields: Vec<FieldDef>,
@@ -459,13 +1268,429 @@ pub struct EntityDef {
     pub serialization: Vec<String>,
     /// Message prefix for ingestion server parsing (e.g., "O" for Order, "L" for LineItem)
     #[serde(default)]
-    pub prefix: Option<String>,
-    /// Minimal existence constraint - which fields must be present for entity to exist
+    pub prefix: Option<String>,
+    /// Minimal existence constraint - which fields must be present for entity to exist
+    #[serde(default)]
+    pub minimal_existence: Option<MinimalExistence>,
+    /// Condition the entity must satisfy to be extracted/persisted at all
+    #[serde(default)]
+    pub when: Option<EntityCondition>,
+    /// Key field shared by repeated parents; rows are correlated by equal
+    /// values of this field instead of by position
+    #[serde(default)]
+    pub join_on: Option<String>,
+    /// Namespace for this entity's transforms; an unqualified `parse_name`
+    /// resolves to `<namespace>::parse_name` when that transform exists
+    #[serde(default)]
+    pub transform_namespace: Option<String>,
+    /// NATS subject template the worker publishes this entity's records to
+    /// (default `entities.{name}`)
+    #[serde(default)]
+    pub publish_subject: Option<String>,
+    /// Sample inputs with expected field values, generated into `#[test]`s
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<EntityExample>,
+    /// How the real-time dashboard shows this entity
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dashboard: Option<EntityDashboardConfig>,
+    /// Extra derives for the generated Diesel model struct (e.g. `PartialEq`,
+    /// `utoipa::ToSchema`); the `New*` insertable struct doesn't get them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub derives: Vec<String>,
+    /// Serde attributes relaxing how a root entity's JSON input is read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_input: Option<JsonInputConfig>,
+    /// Encoding of a root entity's raw input (`latin1`, `windows-1252`,
+    /// `utf-16le`, `auto` for BOM detection), transcoded to UTF-8 before extraction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+    /// CSV dialect of a root entity's input
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub csv: Option<CsvDialect>,
+    /// Where a root entity's records end in line-oriented input (`newline`,
+    /// `blank_line`, `csv`, `hl7` or `terminator:<text>`, e.g. `terminator:$$$$`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub record_separator: Option<String>,
+    /// Field of a root entity holding the raw payload, passed to transforms
+    /// that read the root entity as a whole (a source naming the entity
+    /// without a field); without it the worker passes the whole parsed message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_field: Option<String>,
+}
+
+/// CSV dialect of a root entity's input (`csv:` block)
+///
+/// ```yaml
+/// csv:
+///   delimiter: ";"
+///   escape: "\\"
+///   has_header: true
+///   columns: [order_id, customer, amount]
+/// ```
+///
+/// `preset: tsv` (or `psv`) picks the delimiter of a common format; an
+/// explicit `delimiter` overrides it.
+///
+/// `extract_csv_field` transforms reading from the entity get the dialect as
+/// default args, so with `columns` they can also select a column by name
+/// with `column:`. With `has_header`, the generated parser skips the first
+/// input row.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CsvDialect {
+    /// Named format supplying the default delimiter
+    #[serde(default)]
+    pub preset: CsvPreset,
+    /// Column separator (default from `preset`)
+    #[serde(default)]
+    pub delimiter: Option<char>,
+    /// Quote character; doubled inside a quoted column it stands for itself
+    #[serde(default = "default_csv_quote")]
+    pub quote: char,
+    /// Escape character for quotes inside quoted columns (e.g. `\\`)
+    #[serde(default)]
+    pub escape: Option<char>,
+    /// Whether the first row is a header
+    #[serde(default)]
+    pub has_header: bool,
+    /// Column names, in order
+    #[serde(default)]
+    pub columns: Vec<String>,
+}
+
+fn default_csv_quote() -> char {
+    '"'
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        Self {
+            preset: CsvPreset::default(),
+            delimiter: None,
+            quote: default_csv_quote(),
+            escape: None,
+            has_header: false,
+            columns: Vec::new(),
+        }
+    }
+}
+
+impl CsvDialect {
+    /// Column separator, from `delimiter` or else the preset
+    pub fn delimiter_char(&self) -> char {
+        self.delimiter.unwrap_or_else(|| self.preset.delimiter())
+    }
+
+    /// `extract_csv_field` args describing this dialect
+    pub fn transform_args(&self) -> serde_yaml::Mapping {
+        let mut args = serde_yaml::Mapping::new();
+        args.insert("delimiter".into(), self.delimiter_char().to_string().into());
+        args.insert("quote".into(), self.quote.to_string().into());
+        if let Some(escape) = self.escape {
+            args.insert("escape".into(), escape.to_string().into());
+        }
+        if !self.columns.is_empty() {
+            let header = self.columns.iter().map(|c| serde_yaml::Value::from(c.as_str())).collect();
+            args.insert("header".into(), serde_yaml::Value::Sequence(header));
+        }
+        args
+    }
+}
+
+/// Common delimited formats, selectable with `csv: {preset: ...}`
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CsvPreset {
+    /// Comma-separated (default)
+    #[default]
+    Csv,
+    /// Tab-separated
+    Tsv,
+    /// Pipe-separated
+    Psv,
+}
+
+impl CsvPreset {
+    /// Column separator of the format
+    pub fn delimiter(&self) -> char {
+        match self {
+            CsvPreset::Csv => ',',
+            CsvPreset::Tsv => '\t',
+            CsvPreset::Psv => '|',
+        }
+    }
+}
+
+/// Give `extract_csv_field` fields the `csv:` dialect of the entity they read
+///
+/// Args set on the field win over the dialect. Applied once after loading,
+/// like the default timestamps.
+pub fn apply_csv_dialects(entities: &mut [EntityDef]) {
+    let dialects: HashMap<String, CsvDialect> = entities.iter()
+        .filter_map(|e| Some((e.name.clone(), e.csv.clone()?)))
+        .collect();
+    if dialects.is_empty() {
+        return;
+    }
+
+    for entity in entities.iter_mut() {
+        for field in &mut entity.fields {
+            let Some(ref mut computed) = field.computed_from else {
+                continue;
+            };
+            if computed.transform.rsplit("::").next() != Some("extract_csv_field") {
+                continue;
+            }
+            let source = match computed.sources.first().map(FieldSource::source_name) {
+                Some("self") => entity.name.as_str(),
+                Some(source) => source,
+                None => continue,
+            };
+            let Some(dialect) = dialects.get(source) else {
+                continue;
+            };
+            let args = computed.args.get_or_insert_with(|| serde_yaml::Value::Mapping(Default::default()));
+            if let serde_yaml::Value::Mapping(args) = args {
+                for (key, value) in dialect.transform_args() {
+                    args.entry(key).or_insert(value);
+                }
+            }
+        }
+    }
+}
+
+/// How a root entity's generated struct deserializes its JSON input
+///
+/// ```yaml
+/// json_input:
+///   rename_all: camelCase
+///   numbers_from_strings: true
+///   aliases:
+///     order_key: [orderKey, OrderID]
+/// ```
+///
+/// Everything here only affects deserialization; `to_json` keeps the
+/// declared field names.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct JsonInputConfig {
+    /// Serde case convention of the input keys (e.g. `camelCase`)
+    #[serde(default)]
+    pub rename_all: Option<String>,
+    /// Accept numeric fields sent as JSON strings (`"42"`, `" 3.5 "`)
+    #[serde(default)]
+    pub numbers_from_strings: bool,
+    /// Extra input keys accepted for a field, by field name
+    #[serde(default)]
+    pub aliases: BTreeMap<String, Vec<String>>,
+}
+
+impl JsonInputConfig {
+    /// Case conventions serde's `rename_all` understands
+    pub const RENAME_RULES: &'static [&'static str] = &[
+        "lowercase", "UPPERCASE", "PascalCase", "camelCase", "snake_case",
+        "SCREAMING_SNAKE_CASE", "kebab-case", "SCREAMING-KEBAB-CASE",
+    ];
+
+    /// Input key of a (snake_case) field under `rename_all`, spelled the way
+    /// serde's derive spells it
+    pub fn renamed(&self, field: &str) -> String {
+        let pascal = || {
+            let mut result = String::with_capacity(field.len());
+            let mut capitalize = true;
+            for ch in field.chars() {
+                if ch == '_' {
+                    capitalize = true;
+                } else if capitalize {
+                    result.push(ch.to_ascii_uppercase());
+                    capitalize = false;
+                } else {
+                    result.push(ch);
+                }
+            }
+            result
+        };
+
+        match self.rename_all.as_deref() {
+            Some("lowercase") => field.to_ascii_lowercase(),
+            Some("UPPERCASE") | Some("SCREAMING_SNAKE_CASE") => field.to_ascii_uppercase(),
+            Some("PascalCase") => pascal(),
+            Some("camelCase") => {
+                let pascal = pascal();
+                let mut chars = pascal.chars();
+                chars.next()
+                    .map(|first| first.to_ascii_lowercase().to_string() + chars.as_str())
+                    .unwrap_or_default()
+            }
+            Some("kebab-case") => field.replace('_', "-"),
+            Some("SCREAMING-KEBAB-CASE") => field.to_ascii_uppercase().replace('_', "-"),
+            _ => field.to_string(),
+        }
+    }
+
+    /// Input keys accepted for a field: its `rename_all` spelling, then its aliases
+    pub fn input_keys(&self, field: &str) -> Vec<String> {
+        let mut keys = vec![self.renamed(field)];
+        keys.extend(self.aliases.get(field).into_iter().flatten().cloned());
+        keys
+    }
+}
+
+/// Per-entity dashboard display settings
+///
+/// ```yaml
+/// dashboard:
+///   poll_interval_ms: 1000
+///   fields:
+///     - order_key
+///     - {name: total_price, label: Total}
+/// ```
+///
+/// Without `fields` the dashboard shows the first five persisted fields;
+/// without `poll_interval_ms` it uses the dashboard-wide interval.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct EntityDashboardConfig {
+    /// How often the backend polls this entity's table
+    #[serde(default)]
+    pub poll_interval_ms: Option<u32>,
+    /// Columns to show, in order
+    #[serde(default)]
+    pub fields: Vec<DashboardField>,
+}
+
+/// Dashboard column: a field name, optionally with a header label
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum DashboardField {
+    /// Field shown under its own name
+    Name(String),
+    /// Field shown under a custom label
+    Labeled { name: String, label: String },
+}
+
+impl DashboardField {
+    /// Field name
+    pub fn name(&self) -> &str {
+        match self {
+            DashboardField::Name(name) => name,
+            DashboardField::Labeled { name, .. } => name,
+        }
+    }
+
+    /// Column header
+    pub fn label(&self) -> &str {
+        match self {
+            DashboardField::Name(name) => name,
+            DashboardField::Labeled { label, .. } => label,
+        }
+    }
+}
+
+/// Executable example of an entity's extraction
+///
+/// ```yaml
+/// examples:
+///   - name: parses_quantity
+///     input: "O1,5,2.50"          # root entities: raw input for from_string
+///     expected:
+///       quantity: 5
+///   - name: from_order
+///     sources:                    # derived entities: each parent as JSON
+///       Order: {raw_line: "O1,5,2.50", order_id: "O1"}
+///     expected:
+///       order_id: "O1"
+/// ```
+///
+/// `expected` lists only the fields to check. For `repeated_for` entities it
+/// is a list with one entry per produced instance.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct EntityExample {
+    /// Test function name (snake_case)
+    pub name: String,
+    /// Raw input passed to a root entity's `from_string`
+    #[serde(default)]
+    pub input: Option<String>,
+    /// Parent entities, by name, for derived entities
     #[serde(default)]
-    pub minimal_existence: Option<MinimalExistence>,
+    pub sources: BTreeMap<String, serde_yaml::Value>,
+    /// Expected field values
+    pub expected: serde_yaml::Value,
 }
 
 impl EntityDef {
+    /// Rewrite unqualified field transforms to their namespaced form
+    ///
+    /// `is_defined` reports whether a (qualified) transform exists; names
+    /// without a namespaced definition keep resolving globally.
+    pub fn qualify_transforms(&mut self, is_defined: impl Fn(&str) -> bool) {
+        let Some(namespace) = self.transform_namespace.clone() else {
+            return;
+        };
+
+        for field in &mut self.fields {
+            if let Some(ref mut computed) = field.computed_from {
+                if computed.transform.contains("::") {
+                    continue;
+                }
+                let qualified = format!("{}::{}", namespace, computed.transform);
+                if is_defined(&qualified) {
+                    computed.transform = qualified;
+                }
+            }
+        }
+    }
+
+    /// Resolve the SQL column for a field name, honoring `column:` on the field
+    pub fn column_name<'a>(&'a self, field_name: &'a str) -> &'a str {
+        self.fields
+            .iter()
+            .find(|f| f.name == field_name)
+            .map(|f| f.column_name())
+            .unwrap_or(field_name)
+    }
+
+    /// Fields ordered so that every `source: self` reference is extracted first
+    ///
+    /// Fields without intra-entity dependencies keep their YAML order.
+    /// Returns an error naming the fields involved if they form a cycle.
+    pub fn fields_in_dependency_order(&self) -> Result<Vec<&FieldDef>, String> {
+        let names: HashSet<&str> = self.fields.iter().map(|f| f.name.as_str()).collect();
+        let mut emitted: HashSet<&str> = HashSet::new();
+        let mut ordered = Vec::with_capacity(self.fields.len());
+
+        while ordered.len() < self.fields.len() {
+            // Pick the first pending field whose sibling dependencies are all emitted
+            let next = self.fields.iter().find(|field| {
+                !emitted.contains(field.name.as_str())
+                    && field.self_references().iter()
+                        .filter(|dep| names.contains(*dep))
+                        .all(|dep| emitted.contains(dep))
+            });
+
+            match next {
+                Some(field) => {
+                    emitted.insert(field.name.as_str());
+                    ordered.push(field);
+                }
+                None => {
+                    let pending: Vec<&str> = self.fields.iter()
+                        .map(|f| f.name.as_str())
+                        .filter(|name| !emitted.contains(name))
+                        .collect();
+                    return Err(format!(
+                        "Entity '{}' has a cycle in self-referencing fields: {}",
+                        self.name,
+                        pending.join(", ")
+                    ));
+                }
+            }
+        }
+
+        Ok(ordered)
+    }
+
     /// Get parent entity names (single or multiple)
     pub fn get_parents(&self) -> Vec<String> {
         if !self.parents.is_empty() {
@@ -473,25 +1698,10 @@ impl EntityDef {
             self.parents.iter().map(|p| p.parent_type.clone()).collect()
         } else if let Some(ref parent) = self.parent {
             vec![parent.clone()]
-        } else if let Some(ref repeated_for) = self.repeated_for {
-            vec![repeated_for.entity.clone()]
+        } else if let Some(parent) = self.repeating_parent() {
+            vec![parent.to_string()]
         } else if let Some(ref derivation) = self.derivation {
-            // Parse source_entities from derivation config
-            if let Some(ref source_entities) = derivation.source_entities {
-                match source_entities {
-                    // Single parent: "ParentEntity"
-                    serde_yaml::Value::String(parent) => vec![parent.clone()],
-                    // Multiple parents: {mpi: MPI, filename: Filename, ...}
-                    serde_yaml::Value::Mapping(map) => {
-                        map.values()
-                            .filter_map(|v| v.as_str().map(String::from))
-                            .collect()
-                    }
-                    _ => vec![]
-                }
-            } else {
-                vec![]
-            }
+            derivation.source_entities.entity_names().map(String::from).collect()
         } else {
             vec![]
         }
@@ -505,41 +1715,11 @@ impl EntityDef {
         let mut specs = HashMap::new();
 
         if let Some(ref derivation) = self.derivation {
-            if let Some(ref source_entities) = derivation.source_entities {
-                match source_entities {
-                    // Single source: "ParentEntity"
-                    serde_yaml::Value::String(entity_name) => {
-                        specs.insert(entity_name.clone(), (entity_name.clone(), false));
-                    }
-                    // Multiple sources: {alias: EntityName} or {alias: {entity: EntityName, ancillary: bool}}
-                    serde_yaml::Value::Mapping(map) => {
-                        for (key, value) in map {
-                            if let Some(alias) = key.as_str() {
-                                match value {
-                                    // Simple string: alias: EntityName
-                                    serde_yaml::Value::String(entity_name) => {
-                                        specs.insert(alias.to_string(), (entity_name.clone(), false));
-                                    }
-                                    // Detailed object: alias: {entity: EntityName, ancillary: true}
-                                    serde_yaml::Value::Mapping(obj) => {
-                                        let entity_name = obj.get(&serde_yaml::Value::String("entity".to_string()))
-                                            .and_then(|v| v.as_str())
-                                            .map(String::from);
-                                        let ancillary = obj.get(&serde_yaml::Value::String("ancillary".to_string()))
-                                            .and_then(|v| v.as_bool())
-                                            .unwrap_or(false);
-
-                                        if let Some(entity_name) = entity_name {
-                                            specs.insert(alias.to_string(), (entity_name, ancillary));
-                                        }
-                                    }
-                                    _ => {}
-                                }
-                            }
-                        }
-                    }
-                    _ => {}
-                }
+            for binding in derivation.source_entities.iter() {
+                specs.insert(
+                    binding.alias.clone(),
+                    (binding.spec.entity_name().to_string(), binding.spec.is_ancillary()),
+                );
             }
         }
 
@@ -556,6 +1736,25 @@ impl EntityDef {
         self.source_type.to_lowercase() == "derived"
     }
 
+    /// Parent entity a `repeated_for` or `repeated_by_count` entity repeats over
+    pub fn repeating_parent(&self) -> Option<&str> {
+        match (&self.repeated_for, &self.repeated_by_count) {
+            (Some(repeated_for), _) => Some(&repeated_for.entity),
+            (None, Some(by_count)) => Some(&by_count.entity),
+            (None, None) => None,
+        }
+    }
+
+    /// Variable bound to each repetition of a `repeated_for` or
+    /// `repeated_by_count` entity
+    pub fn each_known_as(&self) -> Option<&str> {
+        match (&self.repeated_for, &self.repeated_by_count) {
+            (Some(repeated_for), _) => Some(&repeated_for.each_known_as),
+            (None, Some(by_count)) => Some(&by_count.each_known_as),
+            (None, None) => None,
+        }
+    }
+
     /// Check if entity has multiple parents
     pub fn has_multiple_parents(&self) -> bool {
         self.parents.len() > 1 || (self.parent.is_none() && self.derivation.is_some())
@@ -585,6 +1784,51 @@ impl EntityDef {
         None
     }
 
+    /// Soft-delete column the entity's unicity constraint is limited to
+    /// (see [`PersistenceConfig::live_unicity_column`])
+    /// Also checks parent entities via the extends field for inheritance
+    pub fn live_unicity_column<'a>(&'a self, all_entities: &'a [EntityDef]) -> Option<&'a str> {
+        if let Some(ref persistence) = self.persistence {
+            return persistence.live_unicity_column();
+        }
+        let parent_name = self.extends.as_ref()?;
+        all_entities.iter()
+            .find(|e| &e.name == parent_name)
+            .and_then(|parent| parent.live_unicity_column(all_entities))
+    }
+
+    /// Field receiving the id the database generates for a persisted row
+    ///
+    /// A field named after an autogenerated `persistence.primary_key` (own or
+    /// inherited via `extends`) is not inserted. The worker's persist function
+    /// stores the inserted (or already stored) row's id in it, so entities
+    /// extracted later can copy it into a foreign key column.
+    pub fn generated_id_field<'a>(&'a self, all_entities: &'a [EntityDef]) -> Option<&'a FieldDef> {
+        let mut entity = self;
+        let primary_key = loop {
+            if let Some(ref persistence) = entity.persistence {
+                break persistence.primary_key.as_ref()?;
+            }
+            let parent_name = entity.extends.as_ref()?;
+            entity = all_entities.iter().find(|e| &e.name == parent_name)?;
+        };
+        if !primary_key.autogenerate {
+            return None;
+        }
+        self.fields.iter().find(|f| f.name == primary_key.name)
+    }
+
+    /// Whether the entity's table gets `created_at`/`updated_at` columns
+    /// Also checks parent entities via the extends field for inheritance
+    pub fn has_timestamps(&self, all_entities: &[EntityDef]) -> bool {
+        if let Some(ref persistence) = self.persistence {
+            return persistence.has_timestamps();
+        }
+        self.extends.as_ref()
+            .and_then(|parent_name| all_entities.iter().find(|e| &e.name == parent_name))
+            .is_some_and(|parent| parent.has_timestamps(all_entities))
+    }
+
     /// Check if entity is persistent (has database configuration)
     /// Also checks parent entities via the extends field for inheritance
     pub fn is_persistent(&self, all_entities: &[EntityDef]) -> bool {
@@ -676,6 +1920,299 @@ impl EntityDef {
 
         Ok(())
     }
+
+    /// Fields of the entity including inherited ones, by name, in a stable order
+    ///
+    /// Declaration order, parents first: the furthest ancestor's fields come
+    /// first, then each descendant's in YAML order. A field redeclared by a
+    /// descendant takes the descendant's definition but keeps the position
+    /// where it was first declared, so generated structs, inserts and binds
+    /// all see the same column order on every run.
+    pub fn field_defs<'a>(&'a self, all_entities: &'a [EntityDef]) -> Result<IndexMap<&'a str, &'a FieldDef>, String> {
+        let chain = self.extends_chain(all_entities)?;
+        let mut field_defs = IndexMap::new();
+        for entity in chain.into_iter().rev().chain(std::iter::once(self)) {
+            for field in &entity.fields {
+                field_defs.insert(field.name.as_str(), field);
+            }
+        }
+        Ok(field_defs)
+    }
+
+    /// Ancestors along the `extends` chain, nearest first
+    ///
+    /// Errors if an ancestor is unknown or the chain loops back on itself.
+    pub fn extends_chain<'a>(&self, all_entities: &'a [EntityDef]) -> Result<Vec<&'a EntityDef>, String> {
+        let mut chain: Vec<&EntityDef> = Vec::new();
+        let mut next = self.extends.as_ref();
+
+        while let Some(parent_name) = next {
+            if parent_name == &self.name || chain.iter().any(|e| &e.name == parent_name) {
+                return Err(format!(
+                    "Entity '{}': extends chain loops back to '{}'",
+                    self.name, parent_name
+                ));
+            }
+            let parent = all_entities.iter()
+                .find(|e| &e.name == parent_name)
+                .ok_or_else(|| format!(
+                    "Entity '{}' extends unknown entity '{}'",
+                    self.name, parent_name
+                ))?;
+            chain.push(parent);
+            next = parent.extends.as_ref();
+        }
+
+        Ok(chain)
+    }
+
+    /// Validate that fields redeclared from an `extends` ancestor keep a compatible type
+    ///
+    /// A child field must map to the same Rust type as the ancestor's field (and
+    /// the ancestor's persistence override, if typed), and may only be nullable
+    /// where the ancestor's field is nullable too.
+    pub fn validate_extends(&self, all_entities: &[EntityDef]) -> Result<(), String> {
+        use crate::codegen::utils::rust_type_from_string;
+
+        for ancestor in self.extends_chain(all_entities)? {
+            let overrides = ancestor.persistence.as_ref()
+                .map(|p| p.field_overrides.as_slice())
+                .unwrap_or_default();
+
+            for field in &self.fields {
+                let child_type = rust_type_from_string(&field.field_type);
+
+                if let Some(inherited) = ancestor.fields.iter().find(|f| f.name == field.name) {
+                    if rust_type_from_string(&inherited.field_type) != child_type {
+                        return Err(format!(
+                            "Entity '{}': field '{}' has type '{}' but overrides '{}.{}' of incompatible type '{}'",
+                            self.name, field.name, field.field_type, ancestor.name, inherited.name, inherited.field_type
+                        ));
+                    }
+                    if field.nullable && !inherited.nullable {
+                        return Err(format!(
+                            "Entity '{}': field '{}' is nullable but overrides non-nullable '{}.{}'",
+                            self.name, field.name, ancestor.name, inherited.name
+                        ));
+                    }
+                }
+
+                let column_type = overrides.iter()
+                    .find(|o| o.name == field.name)
+                    .and_then(|o| o.field_type.as_deref());
+                if let Some(column_type) = column_type {
+                    if rust_type_from_string(column_type) != child_type {
+                        return Err(format!(
+                            "Entity '{}': field '{}' has type '{}' but '{}' persists it as '{}'",
+                            self.name, field.name, field.field_type, ancestor.name, column_type
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate that `unicity_fields` and indexed field overrides name
+    /// fields of the entity (its own or inherited via `extends`), and that
+    /// `unicity_check`s are set on unicity fields of a matching type
+    ///
+    /// Indexes may also cover the id column and the generated audit and
+    /// soft-delete columns. A generated id field must be a nullable,
+    /// non-computed Integer or BigInt.
+    pub fn validate_persistence_fields(&self, all_entities: &[EntityDef]) -> Result<(), String> {
+        let Some(db) = self.get_database_config(all_entities) else {
+            return Ok(());
+        };
+        let chain = self.extends_chain(all_entities)?;
+        let has_field = |name: &str| {
+            std::iter::once(self).chain(chain.iter().copied())
+                .any(|e| e.fields.iter().any(|f| f.name == name))
+        };
+
+        for name in &db.unicity_fields {
+            if !has_field(name) {
+                return Err(format!(
+                    "Entity '{}': unicity field '{}' is not a field of the entity",
+                    self.name, name
+                ));
+            }
+        }
+
+        if let Some(id_field) = self.generated_id_field(all_entities) {
+            if !id_field.nullable
+                || id_field.computed_from.is_some()
+                || !matches!(id_field.field_type.as_str(), "Integer" | "BigInt")
+            {
+                return Err(format!(
+                    "Entity '{}': generated id field '{}' must be a nullable Integer or BigInt without computed_from",
+                    self.name, id_field.name
+                ));
+            }
+            if db.unicity_fields.contains(&id_field.name) {
+                return Err(format!(
+                    "Entity '{}': generated id field '{}' can't be a unicity field",
+                    self.name, id_field.name
+                ));
+            }
+        }
+
+        let Some(ref persistence) = self.persistence else {
+            return Ok(());
+        };
+        let soft_delete_column = persistence.soft_delete.as_ref().map(|s| s.column.as_str());
+        let is_generated_column = |name: &str| {
+            name == db.conformant_id_column
+                || (persistence.has_timestamps() && (name == CREATED_AT_COLUMN || name == UPDATED_AT_COLUMN))
+                || soft_delete_column == Some(name)
+        };
+        for index in persistence.field_overrides.iter().filter(|o| o.index == Some(true)) {
+            if !has_field(&index.name) && !is_generated_column(&index.name) {
+                return Err(format!(
+                    "Entity '{}': indexed column '{}' is not a field of the entity",
+                    self.name, index.name
+                ));
+            }
+        }
+
+        for field in &persistence.field_overrides {
+            let Some(check) = field.unicity_check else {
+                continue;
+            };
+            let field_type = field.field_type.as_deref().unwrap_or("String");
+            let applies = match check {
+                UnicityCheck::None | UnicityCheck::Present => true,
+                UnicityCheck::NonEmpty => UnicityCheck::default_for(field_type) == UnicityCheck::NonEmpty,
+                UnicityCheck::NonZero => matches!(field_type, "Integer" | "Float"),
+            };
+            if !db.unicity_fields.contains(&field.name) {
+                return Err(format!(
+                    "Entity '{}': field '{}' has a unicity_check but is not a unicity field",
+                    self.name, field.name
+                ));
+            }
+            if !applies {
+                return Err(format!(
+                    "Entity '{}': unicity_check does not apply to field '{}' of type '{}'",
+                    self.name, field.name, field_type
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate `join_on`: it needs several repeated parents that all carry the key field
+    pub fn validate_join_on(&self, all_entities: &[EntityDef]) -> Result<(), String> {
+        let Some(ref key) = self.join_on else {
+            return Ok(());
+        };
+
+        let repeated_parents: Vec<&EntityDef> = self.get_parents().iter()
+            .filter_map(|name| all_entities.iter().find(|e| &e.name == name))
+            .filter(|p| p.repetition.as_ref().map(|r| r == "repeated").unwrap_or(false))
+            .collect();
+
+        if repeated_parents.len() < 2 {
+            return Err(format!(
+                "Entity '{}': join_on '{}' requires at least two repeated parents",
+                self.name, key
+            ));
+        }
+
+        for parent in repeated_parents {
+            if !parent.fields.iter().any(|f| &f.name == key) {
+                return Err(format!(
+                    "Entity '{}': join_on field '{}' not found in repeated parent '{}'",
+                    self.name, key, parent.name
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Entities beyond the direct parents that `computed_from` sources read
+    /// from by name (e.g. a grandparent), in order of first use
+    pub fn ancestor_sources(&self, all_entities: &[EntityDef]) -> Vec<String> {
+        let parents = self.get_parents();
+        let is_parent = |name: &str| {
+            parents.iter().any(|p| p == name)
+                || self.parents.iter().any(|p| p.name == name)
+                || self.derivation.as_ref().is_some_and(|d| d.source_entities.resolve(name).is_some())
+        };
+
+        let mut ancestors: Vec<String> = Vec::new();
+        for computed in self.fields.iter().filter_map(|f| f.computed_from.as_ref()) {
+            let condition = computed.condition.as_ref().map(|c| &c.field);
+            for source in computed.sources.iter().chain(condition) {
+                let FieldSource::Parent { source, .. } = source else {
+                    continue;
+                };
+                if source.eq_ignore_ascii_case("self")
+                    || is_parent(source)
+                    || ancestors.contains(source)
+                    || !all_entities.iter().any(|e| &e.name == source)
+                {
+                    continue;
+                }
+                ancestors.push(source.clone());
+            }
+        }
+        ancestors
+    }
+
+    /// Parameters of the generated constructor: the direct parents (or the
+    /// repeating parent), then the ancestors read by name
+    pub fn constructor_sources(&self, all_entities: &[EntityDef]) -> Vec<String> {
+        let mut sources = match self.repeating_parent() {
+            Some(parent) => vec![parent.to_string()],
+            None => self.get_parents(),
+        };
+        sources.extend(self.ancestor_sources(all_entities));
+        sources
+    }
+
+    /// Validate that entities read by name are ancestors already extracted
+    /// (and so in scope) when this entity is
+    ///
+    /// Repeated ancestors are only in scope as the direct parent, so only
+    /// singleton ancestors can be read from further down.
+    pub fn validate_ancestor_sources(&self, all_entities: &[EntityDef]) -> Result<(), String> {
+        let ancestor_sources = self.ancestor_sources(all_entities);
+        if ancestor_sources.is_empty() {
+            return Ok(());
+        }
+
+        let mut ancestors: HashSet<String> = HashSet::new();
+        let mut pending = self.get_parents();
+        while let Some(name) = pending.pop() {
+            if let Some(entity) = all_entities.iter().find(|e| e.name == name) {
+                if ancestors.insert(name) {
+                    pending.extend(entity.get_parents());
+                }
+            }
+        }
+
+        for source in ancestor_sources {
+            if !ancestors.contains(&source) {
+                return Err(format!(
+                    "Entity '{}' reads from '{}', which is neither a parent nor an ancestor, so it isn't in scope",
+                    self.name, source
+                ));
+            }
+            let ancestor = all_entities.iter().find(|e| e.name == source).expect("ancestor exists");
+            let is_repeated = ancestor.repetition.as_deref() == Some("repeated") || ancestor.repeating_parent().is_some();
+            if is_repeated {
+                return Err(format!(
+                    "Entity '{}' reads from repeated ancestor '{}'; repeated entities are only in scope for their direct children",
+                    self.name, source
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 // ============================================================================
@@ -699,10 +2236,10 @@ pub struct EntityV1 {
 #[serde(rename_all = "camelCase")]
 pub struct MetadataV1 {
     pub name: String,  // Entity name in PascalCase
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub labels: HashMap<String, String>,
-    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub annotations: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
 }
 
 /// Spec section - entity specification
@@ -765,6 +2302,10 @@ pub struct FieldDefV1 {
     pub source: Option<SourceV1>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub doc: Option<String>,
+    #[serde(default, alias = "alias", skip_serializing_if = "Option::is_none")]
+    pub serialized_as: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<String>,
 }
 
 /// Field constraints v1 - unified database + validation
@@ -942,6 +2483,7 @@ impl EntityV1 {
                     .map(|u| u.fields.clone())
                     .unwrap_or_default(),
                 foreign_keys: vec![],
+                pre_insert_sql: BTreeMap::new(),
             });
 
             // Generate field_overrides from v1 field constraints
@@ -960,6 +2502,7 @@ impl EntityV1 {
                             primary_key: constraints.primary_key,
                             index: constraints.indexed,
                             doc: field.doc.clone(),
+                            unicity_check: None,
                         })
                     } else {
                         // Include field even without constraints
@@ -971,6 +2514,7 @@ impl EntityV1 {
                             primary_key: None,
                             index: None,
                             doc: field.doc.clone(),
+                            unicity_check: None,
                         })
                     }
                 })
@@ -980,6 +2524,8 @@ impl EntityV1 {
                 database: entity.database.clone(),
                 primary_key: None,
                 field_overrides,
+                soft_delete: None,
+                timestamps: None,
             });
         }
 
@@ -994,6 +2540,8 @@ impl FieldDefV1 {
             name: self.name.clone(),
             field_type: self.field_type.clone(),
             doc: self.doc.clone(),
+            serialized_as: self.serialized_as.clone(),
+            column: self.column.clone(),
             ..Default::default()
         };
 
@@ -1053,6 +2601,34 @@ impl FieldDefV1 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_insert_values_splice_pre_insert_sql() {
+        let db: DatabaseConfig = serde_yaml::from_str(r#"
+conformant_table: documents
+conformant_id_column: id
+pre_insert_sql:
+  search_vector: "to_tsvector('english', {title} || ' ' || {body})"
+  tags: "'{{}}'::text[]"
+"#).unwrap();
+
+        let values = db.insert_values(&["title", "body", "tags"], |f| f.to_string()).unwrap();
+        let columns: Vec<&str> = values.iter().map(|(c, _)| c.as_str()).collect();
+        assert_eq!(columns, vec!["title", "body", "tags", "search_vector"]);
+
+        let mut n = 0;
+        let sql: Vec<String> = values.iter()
+            .map(|(_, v)| v.to_sql(&mut |_| { n += 1; format!("${}", n) }))
+            .collect();
+        assert_eq!(sql, vec!["$1", "$2", "'{}'::text[]", "to_tsvector('english', $3 || ' ' || $4)"]);
+
+        let binds: Vec<&str> = values.iter().flat_map(|(_, v)| v.binds()).collect();
+        assert_eq!(binds, vec!["title", "body", "title", "body"]);
+
+        let err = db.insert_values(&["body", "tags"], |f| f.to_string()).unwrap_err();
+        assert!(err.contains("'title'"), "{}", err);
+        assert!(parse_sql_expression("lower({name)").is_err());
+    }
+
     #[test]
     fn test_entity_def_get_parents() {
         let entity = EntityDef {
@@ -1090,6 +2666,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_source_entities_shapes() {
+        let parse = |yaml: &str| serde_yaml::from_str::<DerivationConfig>(yaml).unwrap().source_entities;
+
+        let one = parse("source_entities: Patient");
+        assert_eq!(one.resolve("Patient"), Some("Patient"));
+
+        let list = parse("source_entities: [Patient, {entity: Visit, ancillary: true}]");
+        assert_eq!(list.entity_names().collect::<Vec<_>>(), vec!["Patient", "Visit"]);
+        assert!(list.0[1].spec.is_ancillary());
+
+        let named = parse("source_entities:\n  visit: {entity: Visit, ancillary: true}\n  patient: Patient\n");
+        assert_eq!(named.entity_names().collect::<Vec<_>>(), vec!["Visit", "Patient"]);
+        assert_eq!(named.resolve("patient"), Some("Patient"));
+        assert_eq!(named.resolve("Patient"), None);
+
+        let reparsed = parse(&serde_yaml::to_string(&DerivationConfig { source_entities: named.clone() }).unwrap());
+        assert_eq!(reparsed, named);
+
+        assert!(parse("{}").is_empty());
+        assert!(serde_yaml::from_str::<DerivationConfig>("source_entities: {patient: 3}").is_err());
+    }
+
     #[test]
     fn test_entity_v1_to_legacy_simple() {
         let entity_v1 = EntityV1 {
@@ -1097,8 +2696,8 @@ mod tests {
             kind: "Entity".to_string(),
             metadata: MetadataV1 {
                 name: "TestEntity".to_string(),
-                labels: HashMap::new(),
-                annotations: HashMap::new(),
+                labels: BTreeMap::new(),
+                annotations: BTreeMap::new(),
             },
             spec: SpecV1 {
                 entity_type: "root".to_string(),
@@ -1114,4 +2713,421 @@ mod tests {
         assert_eq!(legacy.name, "TestEntity");
         assert_eq!(legacy.source_type, "root");
     }
+
+    fn self_ref_field(name: &str, deps: &[&str]) -> FieldDef {
+        FieldDef {
+            name: name.to_string(),
+            field_type: "String".to_string(),
+            computed_from: Some(ComputedFrom {
+                transform: "concat".to_string(),
+                sources: deps.iter()
+                    .map(|dep| FieldSource::Parent {
+                        source: "self".to_string(),
+                        field: dep.to_string(),
+                        alias: None,
+                    })
+                    .collect(),
+                args: None,
+                condition: None,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_fields_in_dependency_order() {
+        let entity = EntityDef {
+            name: "Patient".to_string(),
+            fields: vec![
+                self_ref_field("full_name", &["first_name", "last_name"]),
+                self_ref_field("first_name", &[]),
+                self_ref_field("display", &["full_name"]),
+                self_ref_field("last_name", &[]),
+            ],
+            ..Default::default()
+        };
+
+        let order: Vec<&str> = entity.fields_in_dependency_order().unwrap()
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect();
+        assert_eq!(order, vec!["first_name", "last_name", "full_name", "display"]);
+    }
+
+    #[test]
+    fn test_fields_in_dependency_order_cycle() {
+        let entity = EntityDef {
+            name: "Patient".to_string(),
+            fields: vec![
+                self_ref_field("a", &["b"]),
+                self_ref_field("b", &["a"]),
+                self_ref_field("c", &[]),
+            ],
+            ..Default::default()
+        };
+
+        let err = entity.fields_in_dependency_order().unwrap_err();
+        assert!(err.contains("cycle"));
+        assert!(err.contains("a, b"));
+    }
+
+    #[test]
+    fn test_qualify_transforms() {
+        let mut fields = vec![self_ref_field("a", &[]), self_ref_field("b", &[])];
+        fields[1].computed_from.as_mut().unwrap().transform = "csv::concat".to_string();
+        let mut entity = EntityDef {
+            name: "Patient".to_string(),
+            transform_namespace: Some("hl7".to_string()),
+            fields,
+            ..Default::default()
+        };
+
+        entity.qualify_transforms(|name| name == "hl7::concat");
+        let transforms: Vec<&str> = entity.fields.iter()
+            .map(|f| f.computed_from.as_ref().unwrap().transform.as_str())
+            .collect();
+        assert_eq!(transforms, vec!["hl7::concat", "csv::concat"]);
+
+        // Without a namespaced definition the global transform is kept
+        let mut entity = EntityDef { transform_namespace: Some("x12".to_string()), ..entity };
+        entity.fields[0].computed_from.as_mut().unwrap().transform = "concat".to_string();
+        entity.qualify_transforms(|name| name == "hl7::concat");
+        assert_eq!(entity.fields[0].computed_from.as_ref().unwrap().transform, "concat");
+    }
+
+    #[test]
+    fn test_validate_join_on() {
+        let repeated = |name: &str, fields: &[&str]| EntityDef {
+            name: name.to_string(),
+            repetition: Some("repeated".to_string()),
+            fields: fields.iter().map(|f| self_ref_field(f, &[])).collect(),
+            ..Default::default()
+        };
+        let parent = |ty: &str| ParentDef {
+            name: ty.to_lowercase(),
+            parent_type: ty.to_string(),
+            ..Default::default()
+        };
+        let all = vec![
+            repeated("Procedure", &["set_id", "code"]),
+            repeated("Provider", &["set_id", "npi"]),
+            repeated("Note", &["text"]),
+        ];
+
+        let mut entity = EntityDef {
+            name: "ProcedureProvider".to_string(),
+            parents: vec![parent("Procedure"), parent("Provider")],
+            join_on: Some("set_id".to_string()),
+            ..Default::default()
+        };
+        assert!(entity.validate_join_on(&all).is_ok());
+
+        // Key must exist on every repeated parent
+        entity.parents.push(parent("Note"));
+        assert!(entity.validate_join_on(&all).unwrap_err().contains("'Note'"));
+
+        // A single repeated parent has nothing to join against
+        entity.parents = vec![parent("Procedure")];
+        assert!(entity.validate_join_on(&all).is_err());
+    }
+
+    #[test]
+    fn test_ancestor_sources() {
+        let mut entities = crate::codegen::parse_entities(r#"
+entity:
+  name: Message
+  source_type: root
+  fields:
+    - name: raw
+      type: String
+---
+entity:
+  name: Header
+  source_type: derived
+  parent: Message
+  fields:
+    - name: sender
+      type: String
+---
+entity:
+  name: Sibling
+  source_type: derived
+  parent: Message
+  fields:
+    - name: note
+      type: String
+---
+entity:
+  name: Detail
+  source_type: derived
+  parent: Header
+  fields:
+    - name: raw
+      type: String
+      computed_from:
+        transform: copy_field
+        sources:
+          - source: Message
+            field: raw
+    - name: sender
+      type: String
+      computed_from:
+        transform: copy_field
+        sources:
+          - source: Header
+            field: sender
+"#).unwrap();
+
+        assert_eq!(entities[3].ancestor_sources(&entities), ["Message"]);
+        assert_eq!(entities[3].constructor_sources(&entities), ["Header", "Message"]);
+        assert!(entities[3].validate_ancestor_sources(&entities).is_ok());
+
+        entities[3].fields[0].computed_from.as_mut().unwrap().sources[0] = FieldSource::Parent {
+            source: "Sibling".to_string(),
+            field: "note".to_string(),
+            alias: None,
+        };
+        assert!(entities[3].validate_ancestor_sources(&entities).unwrap_err().contains("neither a parent nor an ancestor"));
+
+        // Repeated ancestors are out of scope below their children
+        entities[0].repetition = Some("repeated".to_string());
+        entities[3].fields[0].computed_from.as_mut().unwrap().sources[0] = FieldSource::Parent {
+            source: "Message".to_string(),
+            field: "raw".to_string(),
+            alias: None,
+        };
+        assert!(entities[3].validate_ancestor_sources(&entities).unwrap_err().contains("repeated ancestor 'Message'"));
+    }
+
+    #[test]
+    fn test_validate_extends_override_types() {
+        let field = |name: &str, ty: &str, nullable: bool| FieldDef {
+            name: name.to_string(),
+            field_type: ty.to_string(),
+            nullable,
+            ..Default::default()
+        };
+        let all = vec![EntityDef {
+            name: "Provider".to_string(),
+            fields: vec![field("npi", "String", false), field("rank", "Integer", true)],
+            ..Default::default()
+        }];
+
+        let mut child = EntityDef {
+            name: "PrimaryCareProvider".to_string(),
+            extends: Some("Provider".to_string()),
+            fields: vec![field("npi", "String", false), field("rank", "Integer", true)],
+            ..Default::default()
+        };
+        assert!(child.validate_extends(&all).is_ok());
+
+        child.fields[1].field_type = "String".to_string();
+        let err = child.validate_extends(&all).unwrap_err();
+        assert!(err.contains("'Provider.rank' of incompatible type 'Integer'"));
+
+        child.fields[1].field_type = "Integer".to_string();
+        child.fields[0].nullable = true;
+        assert!(child.validate_extends(&all).unwrap_err().contains("nullable"));
+
+        child.extends = Some("Missing".to_string());
+        assert!(child.validate_extends(&all).is_err());
+    }
+
+    #[test]
+    fn test_soft_delete_live_unicity_column() {
+        let persistence = |yaml: &str| serde_yaml::from_str::<PersistenceConfig>(yaml).unwrap();
+
+        let ignore = persistence("soft_delete: {}");
+        assert_eq!(ignore.soft_delete.as_ref().unwrap().column, "deleted_at");
+        assert_eq!(ignore.live_unicity_column(), Some("deleted_at"));
+
+        // Revived rows keep a plain unicity constraint
+        assert_eq!(persistence("soft_delete: {column: removed_at, revive: true}").live_unicity_column(), None);
+        assert_eq!(persistence("field_overrides: []").live_unicity_column(), None);
+
+        let all = vec![
+            EntityDef { name: "Provider".to_string(), persistence: Some(ignore), ..Default::default() },
+            EntityDef { name: "Pcp".to_string(), extends: Some("Provider".to_string()), ..Default::default() },
+        ];
+        assert_eq!(all[1].live_unicity_column(&all), Some("deleted_at"));
+    }
+
+    #[test]
+    fn test_apply_csv_dialects() {
+        let mut entities = crate::codegen::parse_entities(r#"
+entity:
+  name: CsvFile
+  source_type: root
+  csv:
+    delimiter: ";"
+    columns: [id, name]
+  fields:
+    - name: raw_line
+      type: String
+---
+entity:
+  name: Row
+  source_type: derived
+  parent: CsvFile
+  fields:
+    - name: name
+      type: String
+      computed_from:
+        transform: extract_csv_field
+        sources:
+          - source: CsvFile
+            field: raw_line
+        args:
+          column: name
+          delimiter: "|"
+"#).unwrap();
+
+        apply_csv_dialects(&mut entities);
+        let computed = entities[1].fields[0].computed_from.as_ref().unwrap();
+        assert_eq!(computed.arg_str("delimiter"), Some("|"));
+        assert_eq!(computed.arg_str("quote"), Some("\""));
+        assert_eq!(computed.args.as_ref().unwrap()["header"][1], serde_yaml::Value::from("name"));
+    }
+
+    #[test]
+    fn test_csv_presets() {
+        let dialect = |yaml: &str| serde_yaml::from_str::<CsvDialect>(yaml);
+        assert_eq!(dialect("{}").unwrap().delimiter_char(), ',');
+        assert_eq!(dialect("preset: tsv").unwrap().delimiter_char(), '\t');
+        assert_eq!(dialect("preset: psv").unwrap().transform_args()["delimiter"], serde_yaml::Value::from("|"));
+        assert_eq!(dialect("{preset: tsv, delimiter: ';'}").unwrap().delimiter_char(), ';');
+        assert!(dialect("preset: ssv").is_err());
+    }
+
+    #[test]
+    fn test_apply_default_timestamps() {
+        let persistent = |name: &str, persistence: &str| EntityDef {
+            name: name.to_string(),
+            persistence: Some(serde_yaml::from_str(persistence).unwrap()),
+            ..Default::default()
+        };
+        let database = "database: {conformant_table: t, conformant_id_column: id}";
+        let mut all = vec![
+            persistent("Plain", database),
+            persistent("OptedOut", &format!("{}\ntimestamps: false", database)),
+            persistent("Manual", &format!("{}\nfield_overrides: [{{name: created_at, type: DateTime}}]", database)),
+            EntityDef { name: "Child".to_string(), extends: Some("Plain".to_string()), ..Default::default() },
+        ];
+
+        apply_default_timestamps(&mut all, false);
+        assert!(!all[0].has_timestamps(&all));
+
+        apply_default_timestamps(&mut all, true);
+        assert!(all[0].has_timestamps(&all));
+        assert!(!all[1].has_timestamps(&all));
+        assert!(!all[2].has_timestamps(&all));
+        assert!(all[3].has_timestamps(&all));
+    }
+
+    #[test]
+    fn test_validate_persistence_fields() {
+        let field = |name: &str| FieldDef {
+            name: name.to_string(),
+            field_type: "String".to_string(),
+            ..Default::default()
+        };
+        let base = EntityDef {
+            name: "Provider".to_string(),
+            is_abstract: true,
+            fields: vec![field("npi")],
+            ..Default::default()
+        };
+        let mut child = EntityDef {
+            name: "Clinic".to_string(),
+            extends: Some("Provider".to_string()),
+            fields: vec![field("clinic_code")],
+            persistence: Some(serde_yaml::from_str(r#"
+database: {conformant_table: clinics, conformant_id_column: id, unicity_fields: [npi, clinic_code]}
+field_overrides: [{name: clinic_code, index: true}, {name: id, index: true}]
+soft_delete: {column: deleted_at}
+"#).unwrap()),
+            ..Default::default()
+        };
+        let all = vec![base.clone(), child.clone()];
+        assert!(child.validate_persistence_fields(&all).is_ok());
+
+        child.persistence.as_mut().unwrap().database.as_mut().unwrap().unicity_fields.push("clinic_cod".to_string());
+        let err = child.validate_persistence_fields(&all).unwrap_err();
+        assert!(err.contains("unicity field 'clinic_cod'"), "{}", err);
+        child.persistence.as_mut().unwrap().database.as_mut().unwrap().unicity_fields.pop();
+
+        // Generated columns can be indexed; unknown ones can't
+        for (column, ok) in [("deleted_at", true), ("created_at", false), ("region", false)] {
+            child.persistence.as_mut().unwrap().field_overrides[1].name = column.to_string();
+            assert_eq!(child.validate_persistence_fields(&all).is_ok(), ok, "{}", column);
+        }
+        child.persistence.as_mut().unwrap().field_overrides[1].name = "id".to_string();
+
+        // Unicity checks must suit the field's type and sit on a unicity field
+        let overrides = &mut child.persistence.as_mut().unwrap().field_overrides;
+        overrides[0].unicity_check = Some(UnicityCheck::Present);
+        overrides[1].unicity_check = Some(UnicityCheck::Present);
+        assert!(child.validate_persistence_fields(&all).unwrap_err().contains("not a unicity field"));
+        let overrides = &mut child.persistence.as_mut().unwrap().field_overrides;
+        overrides[1].unicity_check = None;
+        overrides[0].unicity_check = Some(UnicityCheck::NonZero);
+        assert!(child.validate_persistence_fields(&all).unwrap_err().contains("does not apply"));
+    }
+
+    #[test]
+    fn test_field_defs_order() {
+        let field = |name: &str, field_type: &str| FieldDef {
+            name: name.to_string(),
+            field_type: field_type.to_string(),
+            ..Default::default()
+        };
+        let entity = |name: &str, extends: Option<&str>, fields: Vec<FieldDef>| EntityDef {
+            name: name.to_string(),
+            extends: extends.map(str::to_string),
+            fields,
+            ..Default::default()
+        };
+        let all = vec![
+            entity("Clinic", Some("Provider"), vec![field("clinic_code", "String"), field("npi", "Integer")]),
+            entity("Provider", Some("Party"), vec![field("npi", "String"), field("specialty", "String")]),
+            entity("Party", None, vec![field("name", "String")]),
+        ];
+
+        let field_defs = all[0].field_defs(&all).unwrap();
+        let names: Vec<&str> = field_defs.keys().copied().collect();
+        assert_eq!(names, ["name", "npi", "specialty", "clinic_code"]);
+        // The redeclared field keeps its inherited position with the own definition
+        assert_eq!(field_defs["npi"].field_type, "Integer");
+
+        let looping = vec![entity("A", Some("A"), vec![])];
+        assert!(looping[0].field_defs(&looping).is_err());
+    }
+
+    #[test]
+    fn test_generated_id_field() {
+        let mut order = EntityDef {
+            name: "Order".to_string(),
+            fields: vec![
+                FieldDef { name: "order_key".to_string(), field_type: "String".to_string(), ..Default::default() },
+                FieldDef { name: "id".to_string(), field_type: "Integer".to_string(), nullable: true, ..Default::default() },
+            ],
+            persistence: Some(serde_yaml::from_str(r#"
+database: {conformant_table: orders, conformant_id_column: id, unicity_fields: [order_key]}
+primary_key: {name: id, type: Integer, autogenerate: true}
+"#).unwrap()),
+            ..Default::default()
+        };
+        let all = vec![order.clone()];
+        assert_eq!(order.generated_id_field(&all).map(|f| f.name.as_str()), Some("id"));
+        assert!(order.validate_persistence_fields(&all).is_ok());
+
+        order.fields[1].nullable = false;
+        let all = vec![order.clone()];
+        assert!(order.validate_persistence_fields(&all).unwrap_err().contains("must be a nullable Integer"));
+
+        // Without autogenerate the field is an ordinary one
+        order.persistence.as_mut().unwrap().primary_key.as_mut().unwrap().autogenerate = false;
+        let all = vec![order.clone()];
+        assert!(order.generated_id_field(&all).is_none());
+    }
 }
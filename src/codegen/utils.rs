@@ -3,11 +3,48 @@
 //! This module will contain helper functions used during code generation.
 //! Implementation will be added during Phase 2 extraction.
 
+use crate::codegen::types::{ConditionOperator, EntityDef, FieldDef, OnParseError};
 use convert_case::{Case, Casing};
 
 /// Convert a string to snake_case
+///
+/// This is the single conversion used for Rust identifiers and SQL column
+/// names across all generators, so every module agrees on a name:
+/// - acronym runs stay together: `XMLHTTPRequest` -> `xmlhttp_request`
+/// - digits stick to the preceding word: `Customer2` -> `customer2`,
+///   `HL7v2MessageFile` -> `hl7v2_message_file`
+/// - a leading `f_` prefix and already-snake_case input are kept as-is
 pub fn to_snake_case(s: &str) -> String {
-    s.to_case(Case::Snake)
+    let (prefix, rest) = match s.strip_prefix("f_") {
+        Some(rest) => ("f_", rest),
+        None => ("", s),
+    };
+
+    let chars: Vec<char> = rest.chars().collect();
+    let mut result = String::with_capacity(s.len() + 4);
+    result.push_str(prefix);
+
+    for (i, &ch) in chars.iter().enumerate() {
+        if ch.is_uppercase() {
+            if i > 0 {
+                let prev = chars[i - 1];
+                let next_is_lower = chars.get(i + 1).map_or(false, |c| c.is_lowercase());
+                // Word boundary after a lowercase letter or digit, or at the
+                // last capital of an acronym run followed by a new word
+                let boundary = prev.is_lowercase()
+                    || prev.is_ascii_digit()
+                    || (prev.is_uppercase() && next_is_lower);
+                if boundary {
+                    result.push('_');
+                }
+            }
+            result.extend(ch.to_lowercase());
+        } else {
+            result.push(ch);
+        }
+    }
+
+    result
 }
 
 /// Convert a string to PascalCase
@@ -25,6 +62,14 @@ pub fn to_screaming_snake_case(s: &str) -> String {
     s.to_case(Case::ScreamingSnake)
 }
 
+/// Rust function name for a (possibly namespaced) transform
+///
+/// `hl7::parse_name` becomes `hl7__parse_name`, so transforms from different
+/// namespaces can be generated side by side in one module.
+pub fn transform_fn_name(transform: &str) -> String {
+    transform.replace("::", "__")
+}
+
 /// Escape a string for use in Rust string literals
 pub fn escape_rust_string(s: &str) -> String {
     s.replace('\\', "\\\\")
@@ -43,14 +88,379 @@ pub fn rust_type_from_string(type_str: &str) -> String {
         "Boolean" => "bool".to_string(),
         "DateTime" => "String".to_string(), // Could use chrono::DateTime later
         "List[String]" => "Vec<String>".to_string(),
+        "Json" | "Object" => "serde_json::Value".to_string(),
+        "List[Json]" | "List[Object]" => "Vec<serde_json::Value>".to_string(),
         _ => type_str.to_string(),
     }
 }
 
+/// Generate the post-extraction checks for a field's `validate:` rules
+///
+/// `value` is the expression holding the field (e.g. `entity.code`) and
+/// `on_failure` turns a message expression into the statement to run when a
+/// rule is broken. Returns an empty string if the field has no rules.
+pub fn generate_validation_checks(
+    entity_name: &str,
+    field: &FieldDef,
+    value: &str,
+    indent: &str,
+    on_failure: &dyn Fn(&str) -> String,
+) -> String {
+    let Some(ref rules) = field.validate else {
+        return String::new();
+    };
+
+    let is_list = field.field_type.starts_with("List[") || field.field_type.starts_with("Vec<");
+    let mut code = String::new();
+    let inner = format!("{}    ", indent);
+    let fail = |rule: &str| {
+        on_failure(&format!(
+            "format!(\"{{}}.{{}}: value {{:?}} {{}}\", {:?}, {:?}, value, {:?})",
+            entity_name, field.name, rule
+        ))
+    };
+
+    code.push_str(&format!("{}// Validate field: {}\n", indent, field.name));
+    if field.nullable && !is_list {
+        code.push_str(&format!("{}if let Some(value) = {}.as_ref() {{\n", indent, value));
+    } else {
+        code.push_str(&format!("{}{{\n", indent));
+        code.push_str(&format!("{}let value = &{};\n", inner, value));
+    }
+
+    if let Some(ref pattern) = rules.regex {
+        code.push_str(&format!("{}static PATTERN: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();\n", inner));
+        code.push_str(&format!(
+            "{}let pattern = PATTERN.get_or_init(|| regex::Regex::new({:?}).expect(\"pattern checked at load time\"));\n",
+            inner, pattern
+        ));
+        code.push_str(&format!("{}if !pattern.is_match(value) {{\n", inner));
+        code.push_str(&format!("{}    {}\n", inner, fail(&format!("does not match /{}/", pattern))));
+        code.push_str(&format!("{}}}\n", inner));
+    }
+
+    if let Some(min) = rules.min {
+        code.push_str(&format!("{}if (*value as f64) < {:?} {{\n", inner, min));
+        code.push_str(&format!("{}    {}\n", inner, fail(&format!("is below minimum {}", min))));
+        code.push_str(&format!("{}}}\n", inner));
+    }
+
+    if let Some(max) = rules.max {
+        code.push_str(&format!("{}if (*value as f64) > {:?} {{\n", inner, max));
+        code.push_str(&format!("{}    {}\n", inner, fail(&format!("is above maximum {}", max))));
+        code.push_str(&format!("{}}}\n", inner));
+    }
+
+    let allowed = rules.one_of_strings();
+    if !allowed.is_empty() {
+        code.push_str(&format!(
+            "{}if ![{}].contains(&value.to_string().as_str()) {{\n",
+            inner,
+            allowed.iter().map(|v| format!("{:?}", v)).collect::<Vec<_>>().join(", ")
+        ));
+        code.push_str(&format!("{}    {}\n", inner, fail(&format!("is not one of [{}]", allowed.join(", ")))));
+        code.push_str(&format!("{}}}\n", inner));
+    }
+
+    code.push_str(&format!("{}}}\n", indent));
+    code
+}
+
+/// Generate an expression coercing a field's extracted value per its `coerce:` block
+///
+/// `value` is an `Option<_>` expression whose items are rendered with
+/// `to_string()` and parsed as `rust_type` (`i32`, `i64`, `f64` or `bool`).
+/// Unparsable values become `None`, run `on_skip` (falling back to
+/// `on_failure` where records cannot be skipped), or run `on_failure` with a
+/// message expression. Returns `None` if the field has no coercion.
+pub fn generate_coercion_expr(
+    entity_name: &str,
+    field: &FieldDef,
+    value: &str,
+    rust_type: &str,
+    on_skip: Option<&str>,
+    on_failure: &dyn Fn(&str) -> String,
+) -> Option<String> {
+    let coerce = field.coerce.as_ref()?;
+
+    let parse = if rust_type == "bool" {
+        "match raw.trim().to_ascii_lowercase().as_str() { \
+            \"true\" | \"t\" | \"yes\" | \"y\" | \"1\" => Ok(true), \
+            \"false\" | \"f\" | \"no\" | \"n\" | \"0\" => Ok(false), \
+            _ => Err(raw) }".to_string()
+    } else if matches!(rust_type, "i32" | "i64" | "f32" | "f64") {
+        let (thousands, decimal) = coerce.separators();
+        let mut text = "raw.trim().to_string()".to_string();
+        if let Some(thousands) = thousands {
+            text.push_str(&format!(".replace({:?}, \"\")", thousands));
+            if thousands.trim().is_empty() {
+                // Space-grouped locales often use (narrow) no-break spaces
+                text.push_str(".replace('\\u{a0}', \"\").replace('\\u{202f}', \"\")");
+            }
+        }
+        if decimal != "." {
+            text.push_str(&format!(".replace({:?}, \".\")", decimal));
+        }
+        format!("{}.parse::<{}>().map_err(|_| raw)", text, rust_type)
+    } else {
+        return None;
+    };
+    let parsed = format!("({}).map(|raw| {{ let raw = raw.to_string(); {} }})", value, parse);
+
+    let error_arm = match (coerce.on_parse_error, on_skip) {
+        (OnParseError::Null, _) => return Some(format!("{}.and_then(Result::ok)", parsed)),
+        (OnParseError::Skip, Some(skip)) => format!("Err(_) => {{ {} }}", skip),
+        _ => format!(
+            "Err(raw) => {{ {} }}",
+            on_failure(&format!(
+                "format!(\"{{}}.{{}}: cannot parse {{:?}} as {{}}\", {:?}, {:?}, raw, {:?})",
+                entity_name, field.name, field.field_type
+            ))
+        ),
+    };
+    Some(format!("match {}.transpose() {{ Ok(value) => value, {} }}", parsed, error_arm))
+}
+
+/// Convert a JSONPath-style path (`$.a.b[0]` or `a.b.0`) into a JSON pointer (`/a/b/0`)
+pub fn json_pointer_from_path(path: &str) -> String {
+    let path = path.trim_start_matches('$').replace('[', ".").replace(']', "");
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// Generate an `Option<serde_json::Value>` expression for the `extract_json_subtree` transform
+///
+/// `source` is an `Option<String>` (raw JSON text) or, when `source_is_json`,
+/// an `Option<serde_json::Value>` expression. Missing paths and unparsable
+/// documents yield `None`.
+pub fn generate_json_subtree_expr(source: &str, source_is_json: bool, path: &str) -> String {
+    let pointer = json_pointer_from_path(path);
+    if source_is_json {
+        format!("{}.as_ref().and_then(|doc| doc.pointer({:?}).cloned())", source, pointer)
+    } else {
+        format!(
+            "{}.as_deref().and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok()).and_then(|doc| doc.pointer({:?}).cloned())",
+            source, pointer
+        )
+    }
+}
+
+/// Generate the `init_tracing()` function shared by the generated services
+///
+/// Logs are human-readable by default; `LOG_FORMAT=json` emits one JSON
+/// object per line (with event fields flattened) for log aggregators.
+/// `RUST_LOG` controls the filter and defaults to `info`. With the `otel`
+/// feature, spans are also exported through `telemetry::otel_layer()`.
+pub fn generate_init_tracing_fn() -> String {
+    let mut code = String::new();
+    code.push_str("/// Initialize tracing (LOG_FORMAT=json for structured logs, RUST_LOG for filtering)\n");
+    code.push_str("fn init_tracing() {\n");
+    code.push_str("    use tracing_subscriber::layer::SubscriberExt;\n");
+    code.push_str("    use tracing_subscriber::util::SubscriberInitExt;\n\n");
+    code.push_str("    let filter = tracing_subscriber::EnvFilter::try_from_default_env()\n");
+    code.push_str("        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(\"info\"));\n");
+    code.push_str("    let json = std::env::var(\"LOG_FORMAT\")\n");
+    code.push_str("        .map(|format| format.eq_ignore_ascii_case(\"json\"))\n");
+    code.push_str("        .unwrap_or(false);\n\n");
+    code.push_str("    let (json_layer, text_layer) = if json {\n");
+    code.push_str("        let layer = tracing_subscriber::fmt::layer()\n");
+    code.push_str("            .json()\n");
+    code.push_str("            .flatten_event(true)\n");
+    code.push_str("            .with_current_span(true);\n");
+    code.push_str("        (Some(layer), None)\n");
+    code.push_str("    } else {\n");
+    code.push_str("        (None, Some(tracing_subscriber::fmt::layer()))\n");
+    code.push_str("    };\n\n");
+    code.push_str("    let registry = tracing_subscriber::registry()\n");
+    code.push_str("        .with(filter)\n");
+    code.push_str("        .with(json_layer)\n");
+    code.push_str("        .with(text_layer);\n");
+    code.push_str("    #[cfg(feature = \"otel\")]\n");
+    code.push_str("    let registry = registry.with(telemetry::otel_layer());\n");
+    code.push_str("    registry.init();\n");
+    code.push_str("}\n\n");
+    code
+}
+
+/// Generate the `cors_layer()` function shared by the generated Axum servers
+///
+/// `CORS_ALLOWED_ORIGINS` is a comma-separated list of origins (`*` allows
+/// any). Unset, debug builds (the dev profile) allow any origin and release
+/// builds allow no cross-origin requests.
+pub fn generate_cors_layer_fn() -> String {
+    let mut code = String::new();
+    code.push_str("/// CORS policy from CORS_ALLOWED_ORIGINS (comma-separated, `*` for any origin)\n");
+    code.push_str("fn cors_layer() -> tower_http::cors::CorsLayer {\n");
+    code.push_str("    use tower_http::cors::{AllowOrigin, Any, CorsLayer};\n\n");
+    code.push_str("    let configured = std::env::var(\"CORS_ALLOWED_ORIGINS\").unwrap_or_default();\n");
+    code.push_str("    let origins: Vec<&str> = configured.split(',')\n");
+    code.push_str("        .map(str::trim)\n");
+    code.push_str("        .filter(|origin| !origin.is_empty())\n");
+    code.push_str("        .collect();\n\n");
+    code.push_str("    if origins.is_empty() {\n");
+    code.push_str("        if cfg!(debug_assertions) {\n");
+    code.push_str("            tracing::warn!(\"CORS_ALLOWED_ORIGINS not set, allowing any origin (dev build)\");\n");
+    code.push_str("            return CorsLayer::permissive();\n");
+    code.push_str("        }\n");
+    code.push_str("        tracing::info!(\"CORS_ALLOWED_ORIGINS not set, cross-origin requests are refused\");\n");
+    code.push_str("        return CorsLayer::new();\n");
+    code.push_str("    }\n\n");
+    code.push_str("    let allow_origin = if origins.contains(&\"*\") {\n");
+    code.push_str("        AllowOrigin::any()\n");
+    code.push_str("    } else {\n");
+    code.push_str("        AllowOrigin::list(origins.iter().filter_map(|origin| match origin.parse() {\n");
+    code.push_str("            Ok(value) => Some(value),\n");
+    code.push_str("            Err(_) => {\n");
+    code.push_str("                tracing::warn!(\"Ignoring invalid CORS origin '{}'\", origin);\n");
+    code.push_str("                None\n");
+    code.push_str("            }\n");
+    code.push_str("        }))\n");
+    code.push_str("    };\n");
+    code.push_str("    tracing::info!(\"CORS allowed origins: {}\", origins.join(\", \"));\n\n");
+    code.push_str("    CorsLayer::new()\n");
+    code.push_str("        .allow_origin(allow_origin)\n");
+    code.push_str("        .allow_methods(Any)\n");
+    code.push_str("        .allow_headers(Any)\n");
+    code.push_str("}\n\n");
+    code
+}
+
+/// Generate the `nats_connect_options()` function shared by the generated
+/// worker and ingestion server
+///
+/// Auth and TLS come from the environment: `NATS_CREDS` (credentials file),
+/// `NATS_TOKEN`, `NATS_NKEY` (seed), `NATS_TLS` (require TLS),
+/// `NATS_TLS_CA` (root certificates) and `NATS_TLS_CERT` / `NATS_TLS_KEY`
+/// (client certificate). Unset variables leave the plain connection as is;
+/// a certificate without its key (or the reverse) fails like
+/// [`crate::nats::client::tls_client_cert_pair`] does for `NatsConfig`.
+pub fn generate_nats_connect_options_fn() -> String {
+    let mut code = String::new();
+    code.push_str("/// NATS connect options with auth/TLS from NATS_CREDS, NATS_TOKEN, NATS_NKEY,\n");
+    code.push_str("/// NATS_TLS, NATS_TLS_CA and NATS_TLS_CERT/NATS_TLS_KEY\n");
+    code.push_str("async fn nats_connect_options() -> Result<async_nats::ConnectOptions, async_nats::Error> {\n");
+    code.push_str("    let env = |var: &str| std::env::var(var).ok()\n");
+    code.push_str("        .map(|value| value.trim().to_string())\n");
+    code.push_str("        .filter(|value| !value.is_empty());\n");
+    code.push_str("    let mut options = async_nats::ConnectOptions::new();\n\n");
+    code.push_str("    if let Some(creds) = env(\"NATS_CREDS\") {\n");
+    code.push_str("        tracing::info!(creds = %creds, \"Authenticating to NATS with credentials file\");\n");
+    code.push_str("        options = options.credentials_file(&creds).await\n");
+    code.push_str("            .map_err(|e| format!(\"Failed to read NATS_CREDS '{}': {}\", creds, e))?;\n");
+    code.push_str("    }\n");
+    code.push_str("    if let Some(token) = env(\"NATS_TOKEN\") {\n");
+    code.push_str("        options = options.token(token);\n");
+    code.push_str("    }\n");
+    code.push_str("    if let Some(seed) = env(\"NATS_NKEY\") {\n");
+    code.push_str("        options = options.nkey(seed);\n");
+    code.push_str("    }\n\n");
+    code.push_str("    let require_tls = env(\"NATS_TLS\")\n");
+    code.push_str("        .is_some_and(|value| matches!(value.to_lowercase().as_str(), \"1\" | \"true\" | \"yes\" | \"on\"));\n");
+    code.push_str("    if require_tls {\n");
+    code.push_str("        options = options.require_tls(true);\n");
+    code.push_str("    }\n");
+    code.push_str("    if let Some(ca) = env(\"NATS_TLS_CA\") {\n");
+    code.push_str("        options = options.add_root_certificates(ca.into());\n");
+    code.push_str("    }\n");
+    code.push_str("    match (env(\"NATS_TLS_CERT\"), env(\"NATS_TLS_KEY\")) {\n");
+    code.push_str("        (Some(cert), Some(key)) => options = options.add_client_certificate(cert.into(), key.into()),\n");
+    code.push_str("        (None, None) => {}\n");
+    code.push_str(&format!("        _ => return Err({:?}.into()),\n", crate::nats::client::TLS_CLIENT_CERT_PAIR_ERROR));
+    code.push_str("    }\n\n");
+    code.push_str("    Ok(options)\n");
+    code.push_str("}\n\n");
+    code
+}
+
+/// Generate a boolean expression evaluating an entity's `when:` condition
+///
+/// `receiver` is the expression holding the built entity (e.g. `self` or
+/// `entity`). Returns `"true"` when the entity has no condition.
+pub fn generate_when_predicate(entity: &EntityDef, receiver: &str) -> String {
+    let Some(ref cond) = entity.when else {
+        return "true".to_string();
+    };
+
+    let nullable = entity.fields.iter()
+        .find(|f| f.name == cond.field)
+        .map_or(true, |f| f.nullable);
+    let value = if nullable {
+        format!("{}.{}.as_ref().map(|v| v.to_string())", receiver, cond.field)
+    } else {
+        format!("Some({}.{}.to_string())", receiver, cond.field)
+    };
+
+    let operands = cond.operands();
+    match cond.operator {
+        ConditionOperator::Present => format!("{}.is_some()", value),
+        ConditionOperator::NonEmpty => {
+            format!("{}.as_deref().map_or(false, |v| !v.trim().is_empty())", value)
+        }
+        ConditionOperator::Equals => format!("{}.as_deref() == Some({:?})", value, operands[0]),
+        ConditionOperator::NotEquals => format!("{}.as_deref() != Some({:?})", value, operands[0]),
+        ConditionOperator::OneOf => format!(
+            "{}.as_deref().map_or(false, |v| [{}].contains(&v))",
+            value,
+            operands.iter().map(|o| format!("{:?}", o)).collect::<Vec<_>>().join(", ")
+        ),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_to_snake_case() {
+        assert_eq!(to_snake_case("CustomerOrder"), "customer_order");
+        assert_eq!(to_snake_case("customerName"), "customer_name");
+        assert_eq!(to_snake_case("XMLHTTPRequest"), "xmlhttp_request");
+        assert_eq!(to_snake_case("XMLParser"), "xml_parser");
+        assert_eq!(to_snake_case("HL7v2MessageFile"), "hl7v2_message_file");
+        assert_eq!(to_snake_case("HL7Message"), "hl7_message");
+        assert_eq!(to_snake_case("Customer2"), "customer2");
+        assert_eq!(to_snake_case("address2Line"), "address2_line");
+        assert_eq!(to_snake_case("f_customerName"), "f_customer_name");
+        assert_eq!(to_snake_case("f_customer_name"), "f_customer_name");
+        assert_eq!(to_snake_case("order_line_item"), "order_line_item");
+        assert_eq!(to_snake_case("my_FieldName"), "my_field_name");
+        assert_eq!(to_snake_case("ID"), "id");
+        assert_eq!(to_snake_case("MPI"), "mpi");
+        assert_eq!(to_snake_case("Hl7v2Message"), "hl7v2_message");
+        assert_eq!(to_snake_case(""), "");
+    }
+
+    #[test]
+    fn test_generate_init_tracing_fn() {
+        let code = generate_init_tracing_fn();
+        assert!(code.contains("fn init_tracing()"));
+        assert!(code.contains("std::env::var(\"LOG_FORMAT\")"));
+        assert!(code.contains(".json()"));
+        assert!(code.contains("#[cfg(feature = \"otel\")]"));
+    }
+
+    #[test]
+    fn test_generate_cors_layer_fn() {
+        let code = generate_cors_layer_fn();
+        assert!(code.contains("fn cors_layer() -> tower_http::cors::CorsLayer"));
+        assert!(code.contains("std::env::var(\"CORS_ALLOWED_ORIGINS\")"));
+        assert!(code.contains("if cfg!(debug_assertions)"));
+        assert!(code.contains("AllowOrigin::list("));
+    }
+
+    #[test]
+    fn test_generate_nats_connect_options_fn() {
+        let code = generate_nats_connect_options_fn();
+        assert!(code.contains("async fn nats_connect_options()"));
+        for var in ["NATS_CREDS", "NATS_TOKEN", "NATS_NKEY", "NATS_TLS", "NATS_TLS_CA", "NATS_TLS_CERT", "NATS_TLS_KEY"] {
+            assert!(code.contains(&format!("env(\"{}\")", var)), "missing {}", var);
+        }
+        assert!(code.contains(".credentials_file(&creds).await"));
+        assert!(code.contains(&format!("_ => return Err({:?}.into()),", crate::nats::client::TLS_CLIENT_CERT_PAIR_ERROR)));
+    }
+
     #[test]
     fn test_case_conversions() {
         assert_eq!(to_snake_case("HelloWorld"), "hello_world");
@@ -71,4 +481,114 @@ mod tests {
         assert_eq!(rust_type_from_string("Integer"), "i64");
         assert_eq!(rust_type_from_string("List[String]"), "Vec<String>");
     }
+
+    #[test]
+    fn test_generate_validation_checks() {
+        use crate::codegen::types::FieldValidation;
+
+        let field = FieldDef {
+            name: "status".to_string(),
+            field_type: "String".to_string(),
+            nullable: true,
+            validate: Some(FieldValidation {
+                one_of: vec![
+                    serde_yaml::Value::String("active".to_string()),
+                    serde_yaml::Value::String("inactive".to_string()),
+                ],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let code = generate_validation_checks("Account", &field, "entity.status", "", &|msg| {
+            format!("return Err({});", msg)
+        });
+
+        assert!(code.contains("if let Some(value) = entity.status.as_ref() {"));
+        assert!(code.contains("if ![\"active\", \"inactive\"].contains(&value.to_string().as_str()) {"));
+        assert!(code.contains("return Err(format!(\"{}.{}: value {:?} {}\", \"Account\", \"status\", value, \"is not one of [active, inactive]\"));"));
+
+        let plain = FieldDef { validate: None, ..field };
+        assert!(generate_validation_checks("Account", &plain, "entity.status", "", &|msg| msg.to_string()).is_empty());
+    }
+
+    #[test]
+    fn test_generate_coercion_expr() {
+        use crate::codegen::types::{FieldCoercion, OnParseError};
+
+        let mut field = FieldDef {
+            name: "amount".to_string(),
+            field_type: "Integer".to_string(),
+            nullable: true,
+            coerce: Some(FieldCoercion {
+                thousands_separator: Some(",".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let fail = |msg: &str| format!("return Err({});", msg);
+
+        let code = generate_coercion_expr("Order", &field, "value", "i32", None, &fail).unwrap();
+        assert!(code.contains("raw.trim().to_string().replace(\",\", \"\").parse::<i32>()"));
+        assert!(code.ends_with(".and_then(Result::ok)"));
+
+        field.coerce.as_mut().unwrap().on_parse_error = OnParseError::Skip;
+        let code = generate_coercion_expr("Order", &field, "value", "i32", Some("return Ok(None);"), &fail).unwrap();
+        assert!(code.contains("Err(_) => { return Ok(None); }"));
+
+        // Without a way to skip, skip falls back to failing the record
+        let code = generate_coercion_expr("Order", &field, "value", "i32", None, &fail).unwrap();
+        assert!(code.contains("Err(raw) => { return Err(format!(\"{}.{}: cannot parse {:?} as {}\", \"Order\", \"amount\", raw, \"Integer\")); }"));
+
+        field.coerce = None;
+        assert!(generate_coercion_expr("Order", &field, "value", "i32", None, &fail).is_none());
+    }
+
+    #[test]
+    fn test_generate_when_predicate() {
+        use crate::codegen::types::EntityCondition;
+
+        let mut entity = EntityDef {
+            name: "Allergy".to_string(),
+            fields: vec![FieldDef {
+                name: "code".to_string(),
+                field_type: "String".to_string(),
+                nullable: true,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert_eq!(generate_when_predicate(&entity, "self"), "true");
+
+        entity.when = Some(EntityCondition {
+            field: "code".to_string(),
+            operator: ConditionOperator::OneOf,
+            value: Some(serde_yaml::from_str("[DA, FA]").unwrap()),
+        });
+        assert_eq!(
+            generate_when_predicate(&entity, "entity"),
+            "entity.code.as_ref().map(|v| v.to_string()).as_deref().map_or(false, |v| [\"DA\", \"FA\"].contains(&v))"
+        );
+        assert!(entity.when.as_ref().unwrap().validate(&entity).is_ok());
+
+        // one_of needs a list, equals needs a scalar
+        entity.when.as_mut().unwrap().operator = ConditionOperator::Equals;
+        assert!(entity.when.as_ref().unwrap().validate(&entity).is_err());
+    }
+
+    #[test]
+    fn test_json_subtree_expr() {
+        assert_eq!(json_pointer_from_path("$.order.lines[0]"), "/order/lines/0");
+        assert_eq!(json_pointer_from_path("meta.a/b"), "/meta/a~1b");
+        assert_eq!(json_pointer_from_path("$"), "");
+
+        assert_eq!(
+            generate_json_subtree_expr("Some(doc.raw.clone())", false, "$.profile"),
+            "Some(doc.raw.clone()).as_deref().and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok()).and_then(|doc| doc.pointer(\"/profile\").cloned())"
+        );
+        assert_eq!(
+            generate_json_subtree_expr("user.profile", true, "address"),
+            "user.profile.as_ref().and_then(|doc| doc.pointer(\"/address\").cloned())"
+        );
+    }
 }
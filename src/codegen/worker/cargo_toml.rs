@@ -5,6 +5,27 @@ use std::path::Path;
 use std::error::Error;
 use std::io::Write;
 
+/// Crates the generated `error.rs` and `parsers.rs` use, with the worker's
+/// version specs; the fuzz crate compiles those files in and takes the same list
+pub(super) const PARSER_DEPENDENCIES: &[(&str, &str)] = &[
+    ("serde_json", "\"1\""),
+    ("thiserror", "\"1\""),
+    ("diesel", "{ version = \"2\", features = [\"r2d2\", \"chrono\", \"numeric\", \"uuid\", \"serde_json\"] }"),
+    ("r2d2", "\"0.8\""),
+    ("chrono", "{ version = \"0.4\", features = [\"serde\"] }"),
+    ("rust_decimal", "\"1.33\""),
+    ("async-nats", "\"0.35\""),
+    ("tracing", "\"0.1\""),
+];
+
+/// `name = spec` line of a crate in [`PARSER_DEPENDENCIES`]
+fn parser_dependency(name: &str) -> String {
+    let (_, spec) = PARSER_DEPENDENCIES.iter()
+        .find(|(crate_name, _)| *crate_name == name)
+        .expect("crate listed in PARSER_DEPENDENCIES");
+    format!("{} = {}", name, spec)
+}
+
 pub fn generate_cargo_toml(
     output_dir: &Path,
     config: &WorkerConfig,
@@ -23,15 +44,18 @@ pub fn generate_cargo_toml(
         DatabaseType::PostgreSQL => {
             writeln!(output, "default = [\"postgres\"]")?;
             writeln!(output, "postgres = [\"diesel/postgres\"]")?;
-            writeln!(output, "mysql = [\"diesel/mysql\"]\n")?;
+            writeln!(output, "mysql = [\"diesel/mysql\"]")?;
         }
         DatabaseType::MySQL | DatabaseType::MariaDB => {
             writeln!(output, "default = [\"mysql\"]")?;
             writeln!(output, "postgres = [\"diesel/postgres\"]")?;
-            writeln!(output, "mysql = [\"diesel/mysql\"]\n")?;
+            writeln!(output, "mysql = [\"diesel/mysql\"]")?;
         }
     }
 
+    crate::codegen::telemetry::write_otel_feature(&mut output)?;
+    writeln!(output)?;
+
     writeln!(output, "[dependencies]")?;
     writeln!(output, "# Async runtime")?;
     writeln!(output, "tokio = {{ version = \"1\", features = [\"full\"] }}")?;
@@ -40,23 +64,35 @@ pub fn generate_cargo_toml(
 
     writeln!(output, "# Serialization")?;
     writeln!(output, "serde = {{ version = \"1\", features = [\"derive\"] }}")?;
-    writeln!(output, "serde_json = \"1\"\n")?;
+    writeln!(output, "{}", parser_dependency("serde_json"))?;
+    writeln!(output, "rmp-serde = \"1.1\"")?;
+    writeln!(output, "ciborium = \"0.2\"\n")?;
+
+    writeln!(output, "# Error types")?;
+    writeln!(output, "{}\n", parser_dependency("thiserror"))?;
+
+    writeln!(output, "# Compressed envelope bodies (content_encoding)")?;
+    writeln!(output, "base64 = \"0.21\"")?;
+    writeln!(output, "flate2 = \"1\"")?;
+    writeln!(output, "zstd = \"0.13\"")?;
+    writeln!(output, "sha2 = \"0.10\"\n")?;
 
     writeln!(output, "# Database")?;
-    writeln!(output, "diesel = {{ version = \"2\", features = [\"r2d2\", \"chrono\", \"numeric\", \"uuid\"] }}")?;
-    writeln!(output, "r2d2 = \"0.8\"\n")?;
+    writeln!(output, "{}", parser_dependency("diesel"))?;
+    writeln!(output, "{}\n", parser_dependency("r2d2"))?;
 
     writeln!(output, "# Date/Time and numbers")?;
-    writeln!(output, "chrono = {{ version = \"0.4\", features = [\"serde\"] }}")?;
-    writeln!(output, "rust_decimal = \"1.33\"")?;
+    writeln!(output, "{}", parser_dependency("chrono"))?;
+    writeln!(output, "{}", parser_dependency("rust_decimal"))?;
     writeln!(output, "uuid = {{ version = \"1\", features = [\"v4\", \"serde\"] }}\n")?;
 
     writeln!(output, "# NATS JetStream (message queue)")?;
-    writeln!(output, "async-nats = \"0.35\"\n")?;
+    writeln!(output, "{}\n", parser_dependency("async-nats"))?;
 
     writeln!(output, "# Observability")?;
-    writeln!(output, "tracing = \"0.1\"")?;
-    writeln!(output, "tracing-subscriber = {{ version = \"0.3\", features = [\"env-filter\"] }}\n")?;
+    writeln!(output, "{}", parser_dependency("tracing"))?;
+    writeln!(output, "tracing-subscriber = {{ version = \"0.3\", features = [\"env-filter\", \"json\"] }}\n")?;
+    crate::codegen::telemetry::write_otel_dependencies(&mut output)?;
 
     writeln!(output, "# Environment")?;
     writeln!(output, "dotenv = \"0.15\"\n")?;
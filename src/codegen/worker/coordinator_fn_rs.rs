@@ -4,6 +4,7 @@
 
 use crate::codegen::EntityDef;
 use crate::codegen::dependency_graph::DependencyGraph;
+use crate::codegen::utils::to_snake_case;
 use crate::codegen::worker::DatabaseType;
 use std::collections::HashMap;
 use std::error::Error;
@@ -16,6 +17,7 @@ pub fn generate_coordinator_file(
     dependency_graph: &DependencyGraph,
     output_dir: &Path,
     _db_type: DatabaseType,
+    side_output: bool,
 ) -> Result<(), Box<dyn Error>> {
     let file_path = output_dir.join("src/coordinator.rs");
     let mut output = std::fs::File::create(&file_path)?;
@@ -28,11 +30,11 @@ pub fn generate_coordinator_file(
     writeln!(output, "use crate::persist_publish::*;")?;
     writeln!(output, "use crate::parsers;")?;
     writeln!(output, "use crate::database::DbConnection;")?;
-    writeln!(output, "use crate::error::AppError;")?;
+    writeln!(output, "use crate::error::{{entity_failed, AppError}};")?;
     writeln!(output, "use async_nats::jetstream;\n")?;
 
     // Generate coordinator function
-    generate_coordinator_function(&mut output, entities, dependency_graph)?;
+    generate_coordinator_function(&mut output, entities, dependency_graph, side_output)?;
 
     Ok(())
 }
@@ -42,14 +44,24 @@ fn generate_coordinator_function(
     output: &mut std::fs::File,
     entities: &[EntityDef],
     dependency_graph: &DependencyGraph,
+    side_output: bool,
 ) -> Result<(), Box<dyn Error>> {
     writeln!(output, "/// Process a message using dependency-based entity extraction")?;
+    writeln!(output, "///")?;
+    writeln!(output, "/// Entity failures follow `ON_ENTITY_ERROR`; returns the entities to dead-letter.")?;
+    if side_output {
+        writeln!(output, "/// Extracted entities are added to `extracted` for the side output.")?;
+    }
     writeln!(output, "pub async fn process_message(")?;
     writeln!(output, "    root_message: &parsers::Hl7v2MessageFileMessage,")?;
     writeln!(output, "    _raw_json: &serde_json::Value,")?;
     writeln!(output, "    conn: &mut DbConnection,")?;
     writeln!(output, "    jetstream: &jetstream::Context,")?;
-    writeln!(output, ") -> Result<(), AppError> {{")?;
+    if side_output {
+        writeln!(output, "    extracted: &mut crate::side_output::ExtractedEntities,")?;
+    }
+    writeln!(output, ") -> Result<Vec<AppError>, AppError> {{")?;
+    writeln!(output, "    let mut failures: Vec<AppError> = Vec::new();\n")?;
 
     // Generate entity storage maps for each level
     writeln!(output, "    // Storage for extracted entities")?;
@@ -93,13 +105,13 @@ fn generate_coordinator_function(
             writeln!(output, "\n    // Level {}: {}", level_idx, level_entities.join(", "))?;
 
             for entity_name in level_entities {
-                generate_entity_processing(output, entity_name, entities, dependency_graph)?;
+                generate_entity_processing(output, entity_name, entities, dependency_graph, side_output)?;
             }
         }
     }
 
     writeln!(output)?;
-    writeln!(output, "    Ok(())")?;
+    writeln!(output, "    Ok(failures)")?;
     writeln!(output, "}}")?;
 
     Ok(())
@@ -111,6 +123,7 @@ fn generate_entity_processing(
     entity_name: &str,
     entities: &[EntityDef],
     dependency_graph: &DependencyGraph,
+    side_output: bool,
 ) -> Result<(), Box<dyn Error>> {
     let entity = entities.iter()
         .find(|e| &e.name == entity_name)
@@ -125,6 +138,8 @@ fn generate_entity_processing(
         .unwrap_or(false);
 
     let is_persistent = entity.is_persistent(entities);
+    // Extraction failures follow the ON_ENTITY_ERROR policy
+    let on_failure = format!(".or_else(|e| entity_failed(\"{}\", e, &mut failures))", entity_name);
 
     writeln!(output, "\n    // Process {} ({})",
         entity_name,
@@ -141,6 +156,19 @@ fn generate_entity_processing(
         let entity_def = entities.iter().find(|e| &e.name == entity_name)
             .ok_or(format!("Entity {} not found", entity_name))?;
 
+        // Singleton ancestors read by name are passed after the segment, once extracted
+        let mut ancestor_checks: Vec<String> = Vec::new();
+        let mut ancestor_args = String::new();
+        for ancestor in entity_def.ancestor_sources(entities) {
+            if entities.iter().any(|e| e.name == ancestor && e.is_root()) {
+                ancestor_args.push_str(", root_message");
+            } else {
+                ancestor_checks.push(format!("{}_entity.is_some()", to_snake_case(&ancestor)));
+                ancestor_args.push_str(&format!(", &{}_entity.as_ref().unwrap()", to_snake_case(&ancestor)));
+            }
+        }
+        let indent = if ancestor_checks.is_empty() { "" } else { "    " };
+
         if let Some(ref repeated_for) = entity_def.repeated_for {
             // This entity repeats for each segment in a parent field
             let parent_entity = &repeated_for.entity;
@@ -148,16 +176,47 @@ fn generate_entity_processing(
             let each_name = &repeated_for.each_known_as;
 
             writeln!(output, "    // Process {} (repeated for each {} segment)", entity_name, field_name)?;
-            writeln!(output, "    if let Some(ref {}_entity_val) = {}_entity {{",
-                to_snake_case(parent_entity), to_snake_case(parent_entity))?;
-            writeln!(output, "        for {} in &{}_entity_val.{} {{",
-                each_name, to_snake_case(parent_entity), field_name)?;
-            writeln!(output, "            let entity_opt = extract_{}({});", to_snake_case(entity_name), each_name)?;
-            writeln!(output, "            if let Ok(Some(entity)) = entity_opt {{")?;
-            writeln!(output, "                {}_entities.push(entity);", to_snake_case(entity_name))?;
-            writeln!(output, "            }}")?;
-            writeln!(output, "        }}")?;
-            writeln!(output, "    }}")?;
+            if !ancestor_checks.is_empty() {
+                writeln!(output, "    if {} {{", ancestor_checks.join(" && "))?;
+            }
+            writeln!(output, "{}    if let Some(ref {}_entity_val) = {}_entity {{",
+                indent, to_snake_case(parent_entity), to_snake_case(parent_entity))?;
+            writeln!(output, "{}        for {} in &{}_entity_val.{} {{",
+                indent, each_name, to_snake_case(parent_entity), field_name)?;
+            writeln!(output, "{}            let entity = extract_{}({}{}){}?;", indent, to_snake_case(entity_name), each_name, ancestor_args, on_failure)?;
+            writeln!(output, "{}            if let Some(entity) = entity {{", indent)?;
+            writeln!(output, "{}                {}_entities.push(entity);", indent, to_snake_case(entity_name))?;
+            writeln!(output, "{}            }}", indent)?;
+            writeln!(output, "{}        }}", indent)?;
+            writeln!(output, "{}    }}", indent)?;
+            if !ancestor_checks.is_empty() {
+                writeln!(output, "    }}")?;
+            }
+        } else if let Some(ref by_count) = entity_def.repeated_by_count {
+            // This entity repeats count times over fixed-width slices of a parent field
+            let parent_entity = &by_count.entity;
+            let parent_def = entities.iter().find(|e| &e.name == parent_entity)
+                .ok_or(format!("repeated_by_count parent {} of {} not found", parent_entity, entity_name))?;
+            let parent_var = format!("{}_entity_val", to_snake_case(parent_entity));
+            let each_name = &by_count.each_known_as;
+
+            writeln!(output, "    // Process {} ({} repetitions of {} bytes in {})",
+                entity_name, by_count.count_field, by_count.stride, by_count.field)?;
+            if !ancestor_checks.is_empty() {
+                writeln!(output, "    if {} {{", ancestor_checks.join(" && "))?;
+            }
+            writeln!(output, "{}    if let Some(ref {}) = {}_entity {{", indent, parent_var, to_snake_case(parent_entity))?;
+            writeln!(output, "{}        for {}_slice in {} {{", indent, each_name, by_count.slices_expr(parent_def, &parent_var)?)?;
+            writeln!(output, "{}            let {} = {}_slice.to_string();", indent, each_name, each_name)?;
+            writeln!(output, "{}            let entity = extract_{}(&{}{}){}?;", indent, to_snake_case(entity_name), each_name, ancestor_args, on_failure)?;
+            writeln!(output, "{}            if let Some(entity) = entity {{", indent)?;
+            writeln!(output, "{}                {}_entities.push(entity);", indent, to_snake_case(entity_name))?;
+            writeln!(output, "{}            }}", indent)?;
+            writeln!(output, "{}        }}", indent)?;
+            writeln!(output, "{}    }}", indent)?;
+            if !ancestor_checks.is_empty() {
+                writeln!(output, "    }}")?;
+            }
         } else {
             // Regular repeated entity (depends on other entities, not repeated_for)
             // Check if this entity depends on any repeated entities - if so, loop over them
@@ -243,7 +302,7 @@ fn generate_entity_processing(
                         }
                     })
                     .collect();
-                writeln!(output, "{})?;", params.join(", "))?;
+                writeln!(output, "{}){}?;", params.join(", "), on_failure)?;
 
                 writeln!(output, "            if let Some(entity) = entity {{")?;
                 writeln!(output, "                {}_entities.push(entity);", to_snake_case(entity_name))?;
@@ -289,7 +348,7 @@ fn generate_entity_processing(
                         format!("&{}_entity.as_ref().unwrap()", to_snake_case(dep))
                     })
                     .collect();
-                writeln!(output, "{})?;", params.join(", "))?;
+                writeln!(output, "{}){}?;", params.join(", "), on_failure)?;
 
                 writeln!(output, "    if let Some(entity) = entity {{")?;
                 writeln!(output, "        {}_entities.push(entity);", to_snake_case(entity_name))?;
@@ -376,28 +435,44 @@ fn generate_entity_processing(
                 }
             })
             .collect();
-        writeln!(output, "{})?;", params.join(", "))?;
+        writeln!(output, "{}){}?;", params.join(", "), on_failure)?;
 
         if !checks.is_empty() {
             writeln!(output, "    }}")?;
         }
     }
 
-    // Generate persist/publish call
+    // Generate persist/publish call; persisting stores a generated id in the
+    // entity before later levels extract from it
+    let binding = if is_persistent && entity.generated_id_field(entities).is_some() { "mut " } else { "" };
     if is_repeated {
-        writeln!(output, "    for entity in &{}_entities {{", to_snake_case(entity_name))?;
+        writeln!(output, "    for entity in &{}{}_entities {{", binding, to_snake_case(entity_name))?;
         if is_persistent {
-            writeln!(output, "        persist_{}(entity, conn).await?;", to_snake_case(entity_name))?;
+            writeln!(output, "        if let Err(e) = persist_{}(entity, conn).await {{", to_snake_case(entity_name))?;
+            writeln!(output, "            entity_failed::<()>(\"{}\", e, &mut failures)?;", entity_name)?;
+            writeln!(output, "        }}")?;
         } else {
-            writeln!(output, "        publish_{}(entity, jetstream).await?;", to_snake_case(entity_name))?;
+            writeln!(output, "        if let Err(e) = publish_{}(entity, jetstream).await {{", to_snake_case(entity_name))?;
+            writeln!(output, "            entity_failed::<()>(\"{}\", e, &mut failures)?;", entity_name)?;
+            writeln!(output, "        }}")?;
+        }
+        if side_output {
+            writeln!(output, "        crate::side_output::record(extracted, \"{}\", entity);", entity_name)?;
         }
         writeln!(output, "    }}")?;
     } else {
-        writeln!(output, "    if let Some(ref entity) = {}_entity {{", to_snake_case(entity_name))?;
+        writeln!(output, "    if let Some(ref {}entity) = {}_entity {{", binding, to_snake_case(entity_name))?;
         if is_persistent {
-            writeln!(output, "        persist_{}(entity, conn).await?;", to_snake_case(entity_name))?;
+            writeln!(output, "        if let Err(e) = persist_{}(entity, conn).await {{", to_snake_case(entity_name))?;
+            writeln!(output, "            entity_failed::<()>(\"{}\", e, &mut failures)?;", entity_name)?;
+            writeln!(output, "        }}")?;
         } else {
-            writeln!(output, "        publish_{}(entity, jetstream).await?;", to_snake_case(entity_name))?;
+            writeln!(output, "        if let Err(e) = publish_{}(entity, jetstream).await {{", to_snake_case(entity_name))?;
+            writeln!(output, "            entity_failed::<()>(\"{}\", e, &mut failures)?;", entity_name)?;
+            writeln!(output, "        }}")?;
+        }
+        if side_output {
+            writeln!(output, "        crate::side_output::record(extracted, \"{}\", entity);", entity_name)?;
         }
         writeln!(output, "    }}")?;
     }
@@ -405,23 +480,3 @@ fn generate_entity_processing(
     Ok(())
 }
 
-/// Convert CamelCase to snake_case
-fn to_snake_case(s: &str) -> String {
-    let mut result = String::new();
-    let mut prev_is_upper = false;
-
-    for (i, ch) in s.chars().enumerate() {
-        if ch.is_uppercase() {
-            if i > 0 && !prev_is_upper {
-                result.push('_');
-            }
-            result.push(ch.to_lowercase().next().unwrap());
-            prev_is_upper = true;
-        } else {
-            result.push(ch);
-            prev_is_upper = false;
-        }
-    }
-
-    result
-}
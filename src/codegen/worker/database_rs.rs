@@ -1,47 +1,21 @@
 /// Generate database.rs for connection pooling
 
 use crate::codegen::EntityDef;
+use crate::codegen::types::{PersistenceConfig, CREATED_AT_COLUMN, UPDATED_AT_COLUMN};
+use crate::codegen::sql_dialect::SqlDialect;
 use super::{WorkerConfig, DatabaseType};
 use std::path::Path;
 use std::error::Error;
 use std::io::Write;
 
-/// Convert field names to snake_case for SQL
-/// Handles both camelCase and already-snake_case inputs
-fn to_snake_case(s: &str) -> String {
-    // If already contains underscores (except f_ prefix), likely already snake_case
-    let stripped = if s.starts_with("f_") {
-        &s[2..]
-    } else {
-        s
-    };
-
-    // Check if already snake_case (has underscores or all lowercase)
-    if stripped.contains('_') || stripped.chars().all(|c| !c.is_uppercase()) {
-        return s.to_string();
-    }
-
-    // Convert camelCase to snake_case
-    let mut result = String::new();
-    let mut prev_lowercase = false;
-
-    for (i, ch) in s.chars().enumerate() {
-        if ch.is_uppercase() {
-            // Add underscore before uppercase if:
-            // 1. Not at start
-            // 2. Previous char was lowercase
-            // 3. OR next char is lowercase (handles "XMLParser" -> "xml_parser")
-            if i > 0 && (prev_lowercase || s.chars().nth(i + 1).map_or(false, |c| c.is_lowercase())) {
-                result.push('_');
-            }
-            result.push(ch.to_lowercase().next().unwrap());
-            prev_lowercase = false;
-        } else {
-            result.push(ch);
-            prev_lowercase = ch.is_lowercase();
-        }
+/// Soft-delete column of a partial unique index over live rows
+///
+/// Only PostgreSQL has partial indexes; on MySQL the plain constraint is kept.
+fn live_unique_column<'a>(persistence: &'a PersistenceConfig, config: &WorkerConfig) -> Option<&'a str> {
+    match config.database_type {
+        DatabaseType::PostgreSQL => persistence.live_unicity_column(),
+        DatabaseType::MySQL | DatabaseType::MariaDB => None,
     }
-    result
 }
 
 pub fn generate_database_rs(
@@ -139,7 +113,7 @@ pub fn generate_database_rs(
 
             // FIX 1: Add primary key column FIRST if autogenerate=true
             if let Some(ref pk_config) = persistence.primary_key {
-                eprintln!("DEBUG: Found primary_key config for {}: name={}, autogenerate={}", entity.name, pk_config.name, pk_config.autogenerate);
+                tracing::debug!(entity = %entity.name, primary_key = %pk_config.name, autogenerate = pk_config.autogenerate, "Primary key config");
                 if pk_config.autogenerate {
                     let pk_type = match config.database_type {
                         DatabaseType::PostgreSQL => {
@@ -157,7 +131,7 @@ pub fn generate_database_rs(
                     };
                     field_lines.push(format!(
                         "            {} {} PRIMARY KEY",
-                        to_snake_case(&pk_config.name),
+                        entity.column_name(&pk_config.name),
                         pk_type
                     ));
                 }
@@ -165,9 +139,9 @@ pub fn generate_database_rs(
 
             // FIX 2: Add all field_overrides with proper SQL type mapping
             for field in &persistence.field_overrides {
-                let col_name = to_snake_case(&field.name);
+                let col_name = entity.column_name(&field.name);
                 let field_type_str = field.field_type.as_deref().unwrap_or("String");
-                eprintln!("DEBUG: field={}, type={}, args={:?}", field.name, field_type_str, field.args);
+                tracing::trace!(field = %field.name, field_type = field_type_str, args = ?field.args, "Mapping column type");
                 let sql_type = match field_type_str {
                     "String" => {
                         // Use VARCHAR(length) if args specified, otherwise TEXT
@@ -183,10 +157,9 @@ pub fn generate_database_rs(
                             });
 
                             if let Some(length) = length_opt {
-                                eprintln!("DEBUG: Using VARCHAR({})", length);
                                 format!("VARCHAR({})", length)
                             } else {
-                                eprintln!("DEBUG: Could not extract length from args, using TEXT");
+                                tracing::debug!(field = %field.name, "Could not extract VARCHAR length from args, using TEXT");
                                 "TEXT".to_string()
                             }
                         } else {
@@ -199,7 +172,7 @@ pub fn generate_database_rs(
                     "bool" | "Boolean" => "BOOLEAN".to_string(),
                     "NaiveDate" => "DATE".to_string(),
                     "NaiveDateTime" | "DateTime" => "TIMESTAMP".to_string(),
-                    "Json" | "Object" | "List[Object]" => {
+                    "Json" | "Object" | "List[Object]" | "List[Json]" => {
                         match config.database_type {
                             DatabaseType::PostgreSQL => "JSONB".to_string(),
                             DatabaseType::MySQL | DatabaseType::MariaDB => "JSON".to_string(),
@@ -212,6 +185,19 @@ pub fn generate_database_rs(
                 field_lines.push(format!("            {} {}{}", col_name, sql_type, nullable));
             }
 
+            // Audit columns: MySQL refreshes updated_at itself, PostgreSQL
+            // relies on generated updates setting it
+            if entity.has_timestamps(entities) {
+                let default = config.database_type.now_expr();
+                let on_update = if config.database_type.is_mysql_like() { " ON UPDATE CURRENT_TIMESTAMP" } else { "" };
+                field_lines.push(format!("            {} TIMESTAMP NOT NULL DEFAULT {}", CREATED_AT_COLUMN, default));
+                field_lines.push(format!("            {} TIMESTAMP NOT NULL DEFAULT {}{}", UPDATED_AT_COLUMN, default, on_update));
+            }
+
+            if let Some(ref soft_delete) = persistence.soft_delete {
+                field_lines.push(format!("            {} TIMESTAMP NULL", soft_delete.column));
+            }
+
             for (i, line) in field_lines.iter().enumerate() {
                 if i < field_lines.len() - 1 {
                     writeln!(output, "{},", line)?;
@@ -221,11 +207,12 @@ pub fn generate_database_rs(
             }
 
             // FIX 3: Add composite UNIQUE constraint for unicity_fields
+            // (live rows only get a partial index below instead)
             if let Some(ref db_config) = persistence.database {
-                if !db_config.unicity_fields.is_empty() {
+                if !db_config.unicity_fields.is_empty() && live_unique_column(persistence, config).is_none() {
                     let fields_list: Vec<String> = db_config.unicity_fields
                         .iter()
-                        .map(|f| to_snake_case(f))
+                        .map(|f| entity.column_name(f).to_string())
                         .collect();
                     writeln!(output, "            ,CONSTRAINT {}_unique UNIQUE ({})",
                         table_name,
@@ -239,6 +226,23 @@ pub fn generate_database_rs(
         writeln!(output, "    \"#)")?;
         writeln!(output, "    .execute(conn)?;\n")?;
 
+        // Unicity among live rows, so soft-deleted rows don't block re-creation
+        if let Some(persistence) = persistence_ref {
+            if let (Some(column), Some(ref db_config)) = (live_unique_column(persistence, config), &persistence.database) {
+                if !db_config.unicity_fields.is_empty() {
+                    let fields_list: Vec<String> = db_config.unicity_fields
+                        .iter()
+                        .map(|f| entity.column_name(f).to_string())
+                        .collect();
+                    writeln!(output, "    diesel::sql_query(r#\"")?;
+                    writeln!(output, "        CREATE UNIQUE INDEX IF NOT EXISTS {}_unique", table_name)?;
+                    writeln!(output, "        ON {}({}) WHERE {} IS NULL", table_name, fields_list.join(", "), column)?;
+                    writeln!(output, "    \"#)")?;
+                    writeln!(output, "    .execute(conn)?;\n")?;
+                }
+            }
+        }
+
         // FIX 4: Create indices for unicity fields
         if let Some(ref persistence) = entity.persistence {
             if let Some(ref db_config) = persistence.database {
@@ -247,11 +251,11 @@ pub fn generate_database_rs(
                     writeln!(output, "    diesel::sql_query(r#\"")?;
                     writeln!(output, "        CREATE INDEX IF NOT EXISTS idx_{}_{}",
                         table_name,
-                        to_snake_case(unicity_field)
+                        entity.column_name(unicity_field)
                     )?;
                     writeln!(output, "        ON {}({})",
                         table_name,
-                        to_snake_case(unicity_field)
+                        entity.column_name(unicity_field)
                     )?;
                     writeln!(output, "    \"#)")?;
                     writeln!(output, "    .execute(conn)?;\n")?;
@@ -271,11 +275,33 @@ pub fn generate_database_rs(
     writeln!(output, "            processed_at TIMESTAMP,")?;
     writeln!(output, "            retry_count INTEGER DEFAULT 0,")?;
     writeln!(output, "            error_message TEXT,")?;
-    writeln!(output, "            source VARCHAR(255)")?;
+    writeln!(output, "            source VARCHAR(255),")?;
+    writeln!(output, "            correlation_id VARCHAR(128)")?;
     writeln!(output, "        )")?;
     writeln!(output, "    \"#)")?;
     writeln!(output, "    .execute(conn)?;\n")?;
 
+    // Tables created before correlation IDs lack the column. MySQL has no
+    // ADD COLUMN IF NOT EXISTS, so there only the duplicate column error is expected
+    writeln!(output, "    // Tables created before correlation IDs lack the column")?;
+    if config.database_type == DatabaseType::MySQL {
+        writeln!(output, "    match diesel::sql_query(\"ALTER TABLE message_status ADD COLUMN correlation_id VARCHAR(128)\").execute(conn) {{")?;
+        writeln!(output, "        Ok(_) => {{}}")?;
+        writeln!(output, "        Err(diesel::result::Error::DatabaseError(_, info)) if info.message().contains(\"Duplicate column\") => {{}}")?;
+        writeln!(output, "        Err(e) => {{")?;
+        writeln!(output, "            tracing::error!(table = \"message_status\", column = \"correlation_id\", error = %e, \"Failed to add column\");")?;
+        writeln!(output, "            return Err(e.into());")?;
+        writeln!(output, "        }}")?;
+        writeln!(output, "    }}\n")?;
+    } else {
+        writeln!(output, "    diesel::sql_query(\"ALTER TABLE message_status ADD COLUMN IF NOT EXISTS correlation_id VARCHAR(128)\")")?;
+        writeln!(output, "        .execute(conn)")?;
+        writeln!(output, "        .map_err(|e| {{")?;
+        writeln!(output, "            tracing::error!(table = \"message_status\", column = \"correlation_id\", error = %e, \"Failed to add column\");")?;
+        writeln!(output, "            e")?;
+        writeln!(output, "        }})?;\n")?;
+    }
+
     writeln!(output, "    // Create indices for message_status")?;
     writeln!(output, "    diesel::sql_query(r#\"")?;
     writeln!(output, "        CREATE INDEX IF NOT EXISTS idx_message_status_received_at ON message_status(received_at)")?;
@@ -293,3 +319,43 @@ pub fn generate_database_rs(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::FieldDef;
+
+    #[test]
+    fn test_tables_and_indexes_use_field_columns() {
+        let claim = EntityDef {
+            name: "Claim".to_string(),
+            source_type: "derived".to_string(),
+            fields: vec![
+                FieldDef { name: "claimNumber".to_string(), field_type: "String".to_string(), ..Default::default() },
+                FieldDef {
+                    name: "member_id".to_string(),
+                    field_type: "String".to_string(),
+                    column: Some("member_ref".to_string()),
+                    ..Default::default()
+                },
+            ],
+            persistence: Some(serde_yaml::from_str(r#"
+database: {conformant_table: claims, conformant_id_column: id, unicity_fields: [claimNumber, member_id]}
+field_overrides:
+  - {name: claimNumber, type: String}
+  - {name: member_id, type: String}
+"#).unwrap()),
+            ..Default::default()
+        };
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        generate_database_rs(&[claim], dir.path(), &WorkerConfig::default()).unwrap();
+        let code = std::fs::read_to_string(dir.path().join("src/database.rs")).unwrap();
+
+        assert!(code.contains("            claimNumber TEXT NOT NULL,\n"), "{}", code);
+        assert!(code.contains("            member_ref TEXT NOT NULL\n"), "{}", code);
+        assert!(code.contains("CONSTRAINT claims_unique UNIQUE (claimNumber, member_ref)"), "{}", code);
+        assert!(code.contains("CREATE INDEX IF NOT EXISTS idx_claims_member_ref\n        ON claims(member_ref)"), "{}", code);
+        assert!(!code.contains("claim_number"), "{}", code);
+    }
+}
@@ -33,7 +33,7 @@ pub fn generate_entities_file(
             continue;
         }
 
-        generate_entity_struct(&mut output, entity)?;
+        generate_entity_struct(&mut output, entity, entities)?;
         writeln!(output)?;
     }
 
@@ -41,9 +41,13 @@ pub fn generate_entities_file(
 }
 
 /// Generate a single entity struct definition
+///
+/// Fields follow `field_defs` order: inherited fields first, parents before
+/// children, so the struct matches the persist function's column order.
 fn generate_entity_struct(
     output: &mut std::fs::File,
     entity: &EntityDef,
+    all_entities: &[EntityDef],
 ) -> Result<(), Box<dyn Error>> {
     // Generate doc comment using multiline syntax
     if let Some(ref doc) = entity.doc {
@@ -52,11 +56,11 @@ fn generate_entity_struct(
             writeln!(output, "/** {} */", sanitized.replace('\n', " "))?;
         }
     }
-    writeln!(output, "#[derive(Debug, Clone)]")?;
+    writeln!(output, "#[derive(Debug, Clone, serde::Serialize)]")?;
     writeln!(output, "pub struct {} {{", entity.name)?;
 
     // Generate fields
-    for field in &entity.fields {
+    for field in entity.field_defs(all_entities)?.values() {
         // Add field doc using multiline syntax
         if let Some(ref doc) = field.doc {
             let sanitized = sanitize_doc_string(doc);
@@ -67,7 +71,12 @@ fn generate_entity_struct(
 
         let field_type = map_field_type(&field.field_type);
 
+        // Serialized like published entities: JSON names, absent fields omitted
+        if field.serialized_name() != field.name {
+            writeln!(output, "    #[serde(rename = \"{}\")]", field.serialized_name())?;
+        }
         if field.nullable {
+            writeln!(output, "    #[serde(skip_serializing_if = \"Option::is_none\")]")?;
             writeln!(output, "    pub {}: Option<{}>,", field.name, field_type)?;
         } else {
             writeln!(output, "    pub {}: {},", field.name, field_type)?;
@@ -91,7 +100,7 @@ fn sanitize_doc_string(doc: &str) -> String {
 }
 
 /// Map entity field type to Rust type
-fn map_field_type(field_type: &str) -> &str {
+pub(super) fn map_field_type(field_type: &str) -> &str {
     match field_type.to_lowercase().as_str() {
         "string" => "String",
         "integer" | "int" => "i32",
@@ -101,6 +110,8 @@ fn map_field_type(field_type: &str) -> &str {
         "date" => "NaiveDate",
         "decimal" | "numeric" => "Decimal",
         "vec<string>" | "list[string]" => "Vec<String>",
+        "json" | "object" => "serde_json::Value",
+        "list[json]" | "list[object]" => "Vec<serde_json::Value>",
         _ => "String", // Default to String
     }
 }
@@ -108,6 +119,7 @@ fn map_field_type(field_type: &str) -> &str {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::codegen::FieldDef;
 
     #[test]
     fn test_field_type_mapping() {
@@ -116,5 +128,49 @@ mod tests {
         assert_eq!(map_field_type("BigInt"), "i64");
         assert_eq!(map_field_type("Date"), "NaiveDate");
         assert_eq!(map_field_type("Decimal"), "Decimal");
+        assert_eq!(map_field_type("Json"), "serde_json::Value");
+    }
+
+    #[test]
+    fn test_struct_fields_follow_field_defs_order() {
+        let field = |name: &str, field_type: &str| FieldDef {
+            name: name.to_string(),
+            field_type: field_type.to_string(),
+            ..Default::default()
+        };
+        let entities = vec![
+            EntityDef {
+                name: "Clinic".to_string(),
+                source_type: "derived".to_string(),
+                extends: Some("Provider".to_string()),
+                fields: vec![field("clinic_code", "String"), field("npi", "Integer")],
+                ..Default::default()
+            },
+            EntityDef {
+                name: "Provider".to_string(),
+                source_type: "derived".to_string(),
+                is_abstract: true,
+                fields: vec![field("name", "String"), field("npi", "String"), field("specialty", "String")],
+                ..Default::default()
+            },
+        ];
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        generate_entities_file(&entities, dir.path()).unwrap();
+        let code = std::fs::read_to_string(dir.path().join("src/entities.rs")).unwrap();
+
+        // Inherited fields first; the redeclared field keeps its place with the child's type
+        let fields: Vec<&str> = code.lines()
+            .map(str::trim)
+            .filter(|line| line.starts_with("pub ") && !line.starts_with("pub struct"))
+            .collect();
+        assert_eq!(fields, [
+            "pub name: String,",
+            "pub npi: i32,",
+            "pub specialty: String,",
+            "pub clinic_code: String,",
+        ]);
+        assert!(!code.contains("pub struct Provider"));
     }
 }
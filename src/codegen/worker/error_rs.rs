@@ -1,59 +1,319 @@
 /// Generate error.rs for error handling
 
+use crate::runtime::OnEntityError;
 use std::path::Path;
 use std::error::Error;
 use std::io::Write;
 
-pub fn generate_error_rs(output_dir: &Path) -> Result<(), Box<dyn Error>> {
+/// Generated statement failing with `AppError::InvalidFieldValue` for
+/// `entity.field`; `message` is a String expression
+pub(super) fn invalid_field_value(entity: &str, field: &str, message: &str) -> String {
+    format!(
+        "return Err(AppError::InvalidFieldValue {{ entity: \"{}\".to_string(), field: \"{}\".to_string(), message: {} }});",
+        entity, field, message
+    )
+}
+
+/// Generated `.map_err(...)` turning an error of `transform` into
+/// `AppError::Transform` for `entity.field`
+pub(super) fn transform_error(entity: &str, field: &str, transform: &str) -> String {
+    format!(
+        ".map_err(|e| AppError::Transform {{ entity: \"{}\".to_string(), field: \"{}\".to_string(), transform: \"{}\".to_string(), message: e.to_string() }})",
+        entity, field, transform
+    )
+}
+
+/// Generate the `ON_ENTITY_ERROR` policy applied when an entity fails to
+/// extract or persist
+fn generate_entity_error_policy(
+    output: &mut std::fs::File,
+    default: OnEntityError,
+) -> Result<(), Box<dyn Error>> {
+    let variant = |policy: OnEntityError| match policy {
+        OnEntityError::Abort => "OnEntityError::Abort",
+        OnEntityError::Skip => "OnEntityError::Skip",
+        OnEntityError::Dlq => "OnEntityError::Dlq",
+    };
+
+    writeln!(output, "/// What happens when an entity fails to extract or persist")?;
+    writeln!(output, "#[derive(Debug, Clone, Copy, PartialEq, Eq)]")?;
+    writeln!(output, "pub enum OnEntityError {{")?;
+    writeln!(output, "    /// Fail the message (retried, then dead-lettered whole)")?;
+    writeln!(output, "    Abort,")?;
+    writeln!(output, "    /// Drop an entity that failed to extract or validate, and its dependents")?;
+    writeln!(output, "    Skip,")?;
+    writeln!(output, "    /// Like Skip, and send the message to the DLQ once per failed entity")?;
+    writeln!(output, "    Dlq,")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// Failure policy from `ON_ENTITY_ERROR` (abort, skip, dlq), defaulting to `{}`", default.as_str())?;
+    writeln!(output, "pub fn on_entity_error() -> OnEntityError {{")?;
+    writeln!(output, "    static POLICY: std::sync::OnceLock<OnEntityError> = std::sync::OnceLock::new();")?;
+    writeln!(output, "    *POLICY.get_or_init(|| match std::env::var(\"ON_ENTITY_ERROR\") {{")?;
+    writeln!(output, "        Err(_) => {},", variant(default))?;
+    writeln!(output, "        Ok(value) => match value.to_lowercase().as_str() {{")?;
+    writeln!(output, "            \"abort\" => OnEntityError::Abort,")?;
+    writeln!(output, "            \"skip\" => OnEntityError::Skip,")?;
+    writeln!(output, "            \"dlq\" => OnEntityError::Dlq,")?;
+    writeln!(output, "            other => {{")?;
+    writeln!(output, "                tracing::warn!(value = other, \"Unknown ON_ENTITY_ERROR value, using default\");")?;
+    writeln!(output, "                {}", variant(default))?;
+    writeln!(output, "            }}")?;
+    writeln!(output, "        }},")?;
+    writeln!(output, "    }})")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// Apply the failure policy to an entity step that failed")?;
+    writeln!(output, "///")?;
+    writeln!(output, "/// Returns the error under abort, and for database and publish failures under")?;
+    writeln!(output, "/// every policy so the message is NAKed and retried; otherwise logs it, records")?;
+    writeln!(output, "/// it for the DLQ under dlq (as `AppError::EntityFailed`), and yields `None` so")?;
+    writeln!(output, "/// processing continues without the entity.")?;
+    writeln!(output, "pub fn entity_failed<T>(")?;
+    writeln!(output, "    entity: &'static str,")?;
+    writeln!(output, "    error: AppError,")?;
+    writeln!(output, "    failures: &mut Vec<AppError>,")?;
+    writeln!(output, ") -> Result<Option<T>, AppError> {{")?;
+    writeln!(output, "    let policy = on_entity_error();")?;
+    writeln!(output, "    let is_write_failure = matches!(")?;
+    writeln!(output, "        error,")?;
+    writeln!(output, "        AppError::Database(_) | AppError::Pool(_) | AppError::Persistence {{ .. }} | AppError::Publish {{ .. }}")?;
+    writeln!(output, "    );")?;
+    writeln!(output, "    if policy == OnEntityError::Abort || is_write_failure {{")?;
+    writeln!(output, "        return Err(error);")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "    tracing::warn!(entity, error = %error, policy = ?policy, \"Skipping entity that failed\");")?;
+    writeln!(output, "    if policy == OnEntityError::Dlq {{")?;
+    writeln!(output, "        failures.push(AppError::EntityFailed {{ entity: entity.to_string(), source: Box::new(error) }});")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "    Ok(None)")?;
+    writeln!(output, "}}\n")?;
+
+    Ok(())
+}
+
+pub fn generate_error_rs(output_dir: &Path, on_entity_error: OnEntityError) -> Result<(), Box<dyn Error>> {
     let error_file = output_dir.join("src/error.rs");
     let mut output = std::fs::File::create(&error_file)?;
 
     writeln!(output, "// Auto-generated error types and handlers")?;
     writeln!(output)?;
-    writeln!(output, "use std::fmt;\n")?;
-
-    writeln!(output, "#[derive(Debug)]")?;
+    writeln!(output, "/// Everything that can fail while processing a message")?;
+    writeln!(output, "///")?;
+    writeln!(output, "/// DLQ routing and headers go by variant (see `kind` and `is_permanent`),")?;
+    writeln!(output, "/// never by the message text.")?;
+    writeln!(output, "#[derive(Debug, thiserror::Error)]")?;
     writeln!(output, "pub enum AppError {{")?;
-    writeln!(output, "    Database(diesel::result::Error),")?;
-    writeln!(output, "    Pool(r2d2::Error),")?;
+    writeln!(output, "    #[error(\"Database error: {{0}}\")]")?;
+    writeln!(output, "    Database(#[from] diesel::result::Error),")?;
+    writeln!(output, "    #[error(\"Database pool error: {{0}}\")]")?;
+    writeln!(output, "    Pool(#[from] r2d2::Error),")?;
+    writeln!(output, "    #[error(\"Validation error: {{0}}\")]")?;
     writeln!(output, "    ValidationError(String),")?;
+    writeln!(output, "    #[error(\"Parse error: {{0}}\")]")?;
     writeln!(output, "    ParseError(String),")?;
+    writeln!(output, "    #[error(\"Invalid format: {{0}}\")]")?;
     writeln!(output, "    InvalidFormat(String),")?;
+    writeln!(output, "    #[error(\"Invalid or missing field: {{0}}\")]")?;
     writeln!(output, "    InvalidField(String),")?;
+    writeln!(output, "    #[error(\"Empty message\")]")?;
     writeln!(output, "    EmptyMessage,")?;
+    writeln!(output, "    #[error(\"Unknown message prefix: {{0}}\")]")?;
     writeln!(output, "    UnknownPrefix(String),")?;
+    writeln!(output, "    #[error(\"Unknown entity: {{0}}\")]")?;
     writeln!(output, "    UnknownEntity(String),")?;
+    writeln!(output, "    /// A field broke its validate rules (not retryable)")?;
+    writeln!(output, "    #[error(\"Invalid field value: {{message}}\")]")?;
+    writeln!(output, "    InvalidFieldValue {{ entity: String, field: String, message: String }},")?;
+    writeln!(output, "    /// A computed field's transform returned an error (not retryable)")?;
+    writeln!(output, "    #[error(\"Transform '{{transform}}' failed for {{entity}}.{{field}}: {{message}}\")]")?;
+    writeln!(output, "    Transform {{ entity: String, field: String, transform: String, message: String }},")?;
+    writeln!(output, "    /// Body doesn't match the envelope's body_sha256 (not retryable)")?;
+    writeln!(output, "    #[error(\"Integrity check failed: {{0}}\")]")?;
+    writeln!(output, "    IntegrityError(String),")?;
+    writeln!(output, "    /// The envelope or its body can't be decoded (not retryable)")?;
+    writeln!(output, "    #[error(\"Invalid envelope: {{message}}\")]")?;
+    writeln!(output, "    Envelope {{ message: String }},")?;
+    writeln!(output, "    /// Writing an entity to the database failed")?;
+    writeln!(output, "    #[error(\"Failed to persist {{entity}}: {{source}}\")]")?;
+    writeln!(output, "    Persistence {{ entity: String, source: diesel::result::Error }},")?;
+    writeln!(output, "    /// Serializing or publishing an entity to NATS failed")?;
+    writeln!(output, "    #[error(\"Failed to publish {{entity}}: {{message}}\")]")?;
+    writeln!(output, "    Publish {{ entity: String, message: String }},")?;
+    writeln!(output, "    /// An entity dropped under `ON_ENTITY_ERROR=dlq`, with what made it fail")?;
+    writeln!(output, "    #[error(\"{{source}}\")]")?;
+    writeln!(output, "    EntityFailed {{ entity: String, source: Box<AppError> }},")?;
     writeln!(output, "}}\n")?;
 
-    writeln!(output, "impl fmt::Display for AppError {{")?;
-    writeln!(output, "    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {{")?;
+    writeln!(output, "impl AppError {{")?;
+    writeln!(output, "    /// Short name of the error variant, sent as the Nomnom-Error-Kind DLQ header")?;
+    writeln!(output, "    pub fn kind(&self) -> &'static str {{")?;
     writeln!(output, "        match self {{")?;
-    writeln!(output, "            AppError::Database(e) => write!(f, \"Database error: {{}}\", e),")?;
-    writeln!(output, "            AppError::Pool(e) => write!(f, \"Database pool error: {{}}\", e),")?;
-    writeln!(output, "            AppError::ValidationError(msg) => write!(f, \"Validation error: {{}}\", msg),")?;
-    writeln!(output, "            AppError::ParseError(msg) => write!(f, \"Parse error: {{}}\", msg),")?;
-    writeln!(output, "            AppError::InvalidFormat(msg) => write!(f, \"Invalid format: {{}}\", msg),")?;
-    writeln!(output, "            AppError::InvalidField(field) => write!(f, \"Invalid or missing field: {{}}\", field),")?;
-    writeln!(output, "            AppError::EmptyMessage => write!(f, \"Empty message\"),")?;
-    writeln!(output, "            AppError::UnknownPrefix(prefix) => write!(f, \"Unknown message prefix: {{}}\", prefix),")?;
-    writeln!(output, "            AppError::UnknownEntity(entity) => write!(f, \"Unknown entity: {{}}\", entity),")?;
+    writeln!(output, "            AppError::Database(_) | AppError::Pool(_) => \"database\",")?;
+    writeln!(output, "            AppError::ValidationError(_) => \"validation\",")?;
+    writeln!(output, "            AppError::ParseError(_)")?;
+    writeln!(output, "            | AppError::InvalidFormat(_)")?;
+    writeln!(output, "            | AppError::InvalidField(_)")?;
+    writeln!(output, "            | AppError::EmptyMessage")?;
+    writeln!(output, "            | AppError::UnknownPrefix(_)")?;
+    writeln!(output, "            | AppError::UnknownEntity(_) => \"parse\",")?;
+    writeln!(output, "            AppError::InvalidFieldValue {{ .. }} => \"invalid_field_value\",")?;
+    writeln!(output, "            AppError::Transform {{ .. }} => \"transform\",")?;
+    writeln!(output, "            AppError::IntegrityError(_) => \"integrity\",")?;
+    writeln!(output, "            AppError::Envelope {{ .. }} => \"envelope\",")?;
+    writeln!(output, "            AppError::Persistence {{ .. }} => \"persistence\",")?;
+    writeln!(output, "            AppError::Publish {{ .. }} => \"publish\",")?;
+    writeln!(output, "            AppError::EntityFailed {{ source, .. }} => source.kind(),")?;
     writeln!(output, "        }}")?;
-    writeln!(output, "    }}")?;
-    writeln!(output, "}}\n")?;
+    writeln!(output, "    }}\n")?;
 
-    writeln!(output, "impl std::error::Error for AppError {{}}\n")?;
+    writeln!(output, "    /// Whether retrying the message can't succeed, so it goes to the DLQ at once")?;
+    writeln!(output, "    pub fn is_permanent(&self) -> bool {{")?;
+    writeln!(output, "        match self {{")?;
+    writeln!(output, "            AppError::ParseError(_)")?;
+    writeln!(output, "            | AppError::InvalidFormat(_)")?;
+    writeln!(output, "            | AppError::InvalidField(_)")?;
+    writeln!(output, "            | AppError::EmptyMessage")?;
+    writeln!(output, "            | AppError::UnknownPrefix(_)")?;
+    writeln!(output, "            | AppError::UnknownEntity(_)")?;
+    writeln!(output, "            | AppError::InvalidFieldValue {{ .. }}")?;
+    writeln!(output, "            | AppError::Transform {{ .. }}")?;
+    writeln!(output, "            | AppError::IntegrityError(_)")?;
+    writeln!(output, "            | AppError::Envelope {{ .. }} => true,")?;
+    writeln!(output, "            AppError::Database(_)")?;
+    writeln!(output, "            | AppError::Pool(_)")?;
+    writeln!(output, "            | AppError::ValidationError(_)")?;
+    writeln!(output, "            | AppError::Persistence {{ .. }}")?;
+    writeln!(output, "            | AppError::Publish {{ .. }} => false,")?;
+    writeln!(output, "            AppError::EntityFailed {{ source, .. }} => source.is_permanent(),")?;
+    writeln!(output, "        }}")?;
+    writeln!(output, "    }}\n")?;
 
-    writeln!(output, "impl From<diesel::result::Error> for AppError {{")?;
-    writeln!(output, "    fn from(e: diesel::result::Error) -> Self {{")?;
-    writeln!(output, "        AppError::Database(e)")?;
+    writeln!(output, "    /// Entity the error is about, if any")?;
+    writeln!(output, "    pub fn entity(&self) -> Option<&str> {{")?;
+    writeln!(output, "        match self {{")?;
+    writeln!(output, "            AppError::InvalidFieldValue {{ entity, .. }}")?;
+    writeln!(output, "            | AppError::Transform {{ entity, .. }}")?;
+    writeln!(output, "            | AppError::Persistence {{ entity, .. }}")?;
+    writeln!(output, "            | AppError::Publish {{ entity, .. }}")?;
+    writeln!(output, "            | AppError::EntityFailed {{ entity, .. }} => Some(entity),")?;
+    writeln!(output, "            _ => None,")?;
+    writeln!(output, "        }}")?;
+    writeln!(output, "    }}\n")?;
+
+    writeln!(output, "    /// Entity and field a field-level error is about")?;
+    writeln!(output, "    pub fn field_context(&self) -> Option<(&str, &str)> {{")?;
+    writeln!(output, "        match self {{")?;
+    writeln!(output, "            AppError::InvalidFieldValue {{ entity, field, .. }}")?;
+    writeln!(output, "            | AppError::Transform {{ entity, field, .. }} => Some((entity, field)),")?;
+    writeln!(output, "            AppError::EntityFailed {{ source, .. }} => source.field_context(),")?;
+    writeln!(output, "            _ => None,")?;
+    writeln!(output, "        }}")?;
+    writeln!(output, "    }}\n")?;
+
+    writeln!(output, "    /// Transform that raised a transform error")?;
+    writeln!(output, "    pub fn transform(&self) -> Option<&str> {{")?;
+    writeln!(output, "        match self {{")?;
+    writeln!(output, "            AppError::Transform {{ transform, .. }} => Some(transform),")?;
+    writeln!(output, "            AppError::EntityFailed {{ source, .. }} => source.transform(),")?;
+    writeln!(output, "            _ => None,")?;
+    writeln!(output, "        }}")?;
     writeln!(output, "    }}")?;
     writeln!(output, "}}\n")?;
 
-    writeln!(output, "impl From<r2d2::Error> for AppError {{")?;
-    writeln!(output, "    fn from(e: r2d2::Error) -> Self {{")?;
-    writeln!(output, "        AppError::Pool(e)")?;
+    generate_entity_error_policy(&mut output, on_entity_error)?;
+
+    writeln!(output, "/// DLQ headers describing a failure; the payload stays the original message")?;
+    writeln!(output, "pub fn dlq_headers(error: &AppError) -> async_nats::HeaderMap {{")?;
+    writeln!(output, "    let mut headers = async_nats::HeaderMap::new();")?;
+    writeln!(output, "    headers.insert(\"Nomnom-Error\", error.to_string().replace(['\\r', '\\n'], \" \").as_str());")?;
+    writeln!(output, "    headers.insert(\"Nomnom-Error-Kind\", error.kind());")?;
+    writeln!(output, "    if let Some(entity) = error.entity() {{")?;
+    writeln!(output, "        headers.insert(\"Nomnom-Error-Entity\", entity);")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "    if let Some((_, field)) = error.field_context() {{")?;
+    writeln!(output, "        headers.insert(\"Nomnom-Error-Field\", field);")?;
     writeln!(output, "    }}")?;
+    writeln!(output, "    if let Some(transform) = error.transform() {{")?;
+    writeln!(output, "        headers.insert(\"Nomnom-Error-Transform\", transform);")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "    headers")?;
     writeln!(output, "}}")?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Body of the generated function `name`, up to the next method
+    fn method_body<'a>(code: &'a str, name: &str) -> &'a str {
+        let start = code.find(&format!("pub fn {}(", name)).unwrap();
+        let end = code[start..].find("\n    }\n").unwrap();
+        &code[start..start + end]
+    }
+
+    #[test]
+    fn test_generate_error_rs_classifies_every_variant() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+
+        generate_error_rs(dir.path(), OnEntityError::Skip).unwrap();
+        let code = std::fs::read_to_string(dir.path().join("src/error.rs")).unwrap();
+
+        let enum_start = code.find("pub enum AppError {").unwrap();
+        let enum_body = &code[enum_start..enum_start + code[enum_start..].find("\n}\n").unwrap()];
+        let variants: Vec<&str> = enum_body.lines()
+            .map(str::trim)
+            .filter(|line| line.starts_with(|c: char| c.is_ascii_uppercase()))
+            .map(|line| line.split(|c: char| !c.is_alphanumeric()).next().unwrap())
+            .collect();
+        assert!(variants.contains(&"EntityFailed"), "{:?}", variants);
+
+        // Classification matches on variants, so a new one must be classified
+        let is_permanent = method_body(&code, "is_permanent");
+        assert!(!is_permanent.contains("kind()"), "{}", is_permanent);
+        assert!(!is_permanent.contains("_ =>"), "{}", is_permanent);
+        for variant in &variants {
+            assert!(method_body(&code, "kind").contains(&format!("AppError::{}", variant)), "kind: {}", variant);
+            assert!(is_permanent.contains(&format!("AppError::{}", variant)), "is_permanent: {}", variant);
+        }
+        let (permanent, transient) = is_permanent.split_once("=> true,").unwrap();
+        for variant in ["ParseError", "InvalidFieldValue", "Transform", "IntegrityError", "Envelope"] {
+            assert!(permanent.contains(&format!("AppError::{}", variant)), "{}", variant);
+        }
+        for variant in ["Database", "Pool", "Persistence", "Publish"] {
+            assert!(transient.contains(&format!("AppError::{}", variant)), "{}", variant);
+        }
+        assert!(transient.contains("AppError::EntityFailed { source, .. } => source.is_permanent(),"));
+
+        // Dead-lettered entities carry their name in the error only
+        assert!(!code.contains("struct EntityFailure"));
+        assert!(code.contains("failures: &mut Vec<AppError>,"));
+        assert!(method_body(&code, "entity").contains("AppError::EntityFailed { entity, .. } => Some(entity),"));
+        assert!(method_body(&code, "transform").contains("AppError::EntityFailed { source, .. } => source.transform(),"));
+        assert!(code.contains("headers.insert(\"Nomnom-Error-Transform\", transform);"));
+    }
+
+    #[test]
+    fn test_entity_failed_propagates_write_failures_under_skip() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+
+        generate_error_rs(dir.path(), OnEntityError::Skip).unwrap();
+        let code = std::fs::read_to_string(dir.path().join("src/error.rs")).unwrap();
+        assert!(code.contains("Err(_) => OnEntityError::Skip,"));
+
+        let start = code.find("pub fn entity_failed<T>(").unwrap();
+        let body = &code[start..start + code[start..].find("\n}\n").unwrap()];
+        // Persistence errors are returned (NAKed and retried) before the policy can drop them
+        let propagate = body.find("if policy == OnEntityError::Abort || is_write_failure {\n        return Err(error);").unwrap();
+        let skip = body.find("Ok(None)").unwrap();
+        assert!(propagate < skip, "{}", body);
+        for variant in ["Database(_)", "Pool(_)", "Persistence { .. }", "Publish { .. }"] {
+            assert!(body[..propagate].contains(&format!("AppError::{}", variant)), "{}", variant);
+        }
+        assert!(!body[..propagate].contains("InvalidFieldValue"));
+    }
+}
@@ -7,6 +7,7 @@
 
 use crate::codegen::{EntityDef, FieldDef, ComputedFrom};
 use crate::codegen::types::FieldSource;
+use crate::codegen::utils::{to_snake_case, transform_fn_name};
 use crate::codegen::dependency_graph::DependencyGraph;
 use std::error::Error;
 use std::io::Write;
@@ -70,10 +71,20 @@ fn generate_extract_function(
     // Generate parameters
     write!(output, "(")?;
 
-    // For entities with repeated_for, the parameter is the individual segment
-    if let Some(ref repeated_for) = entity.repeated_for {
-        let param_name = &repeated_for.each_known_as;
-        writeln!(output, "\n    {}: &String", param_name)?;
+    // For entities with repeated_for/repeated_by_count, the parameter is the individual segment
+    if let Some(param_name) = entity.each_known_as() {
+        write!(output, "\n    {}: &String", param_name)?;
+        // Singleton ancestors read by name follow the segment
+        for ancestor in entity.ancestor_sources(all_entities) {
+            let is_root = all_entities.iter().any(|e| e.name == ancestor && e.is_root());
+            let type_name = if is_root {
+                format!("parsers::{}Message", ancestor)
+            } else {
+                ancestor.clone()
+            };
+            write!(output, ",\n    {}: &{}", to_snake_case(&ancestor), type_name)?;
+        }
+        writeln!(output)?;
     } else if !source_entities.is_empty() {
         // For non-repeating entities, parameters are source entities
         // Get source entity specs to check for ancillary flag
@@ -115,9 +126,8 @@ fn generate_extract_function(
     // If no parameters needed (no dependencies), function takes no arguments
     writeln!(output, ") -> Result<Option<{}>, AppError> {{", entity.name)?;
 
-    // For repeated_for entities, wrap the segment parameter in Option for transform compatibility
-    if let Some(ref repeated_for) = entity.repeated_for {
-        let param_name = &repeated_for.each_known_as;
+    // For repeated entities, wrap the segment parameter in Option for transform compatibility
+    if let Some(param_name) = entity.each_known_as() {
         writeln!(output, "    // Wrap segment in Option for transform compatibility")?;
         writeln!(output, "    let {}_opt = Some({}.clone());", param_name, param_name)?;
         writeln!(output)?;
@@ -161,7 +171,8 @@ fn generate_extract_function(
     }
 
     // Generate local variables for fields that are referenced by other fields
-    for field in &entity.fields {
+    // (in dependency order so a local never reads a sibling that isn't bound yet)
+    for field in entity.fields_in_dependency_order()? {
         if fields_needing_locals.contains(&field.name) {
             let field_code = generate_field_extraction(field, entity, source_entities, all_entities, &optional_sources)?;
             writeln!(output, "    let {} = {};", field.name, field_code)?;
@@ -172,10 +183,11 @@ fn generate_extract_function(
         writeln!(output)?;
     }
 
-    // Generate struct initialization
+    // Generate struct initialization (inherited fields included, in struct order)
+    let field_defs = entity.field_defs(all_entities)?;
     writeln!(output, "    let entity = {} {{", entity.name)?;
 
-    for field in &entity.fields {
+    for field in field_defs.values() {
         if fields_needing_locals.contains(&field.name) {
             // Clone the local variable to avoid move errors
             writeln!(output, "        {}: {}.clone(),", field.name, field.name)?;
@@ -198,6 +210,30 @@ fn generate_extract_function(
         generate_default_existence_check(output, entity)?;
     }
 
+    // Entity-level when: condition (records that fail it are skipped, not errors)
+    if entity.when.is_some() {
+        writeln!(output, "    // Skip entity when its `when` condition does not hold")?;
+        writeln!(
+            output,
+            "    if !({}) {{",
+            crate::codegen::utils::generate_when_predicate(entity, "entity")
+        )?;
+        writeln!(output, "        return Ok(None);")?;
+        writeln!(output, "    }}")?;
+    }
+
+    // Enforce validate rules (failures are permanent, so the worker routes them to the DLQ)
+    for field in field_defs.values() {
+        let checks = crate::codegen::utils::generate_validation_checks(
+            &entity.name,
+            field,
+            &format!("entity.{}", field.name),
+            "    ",
+            &|msg| super::error_rs::invalid_field_value(&entity.name, &field.name, msg),
+        );
+        write!(output, "{}", checks)?;
+    }
+
     writeln!(output)?;
     writeln!(output, "    Ok(Some(entity))")?;
     writeln!(output, "}}")?;
@@ -214,7 +250,21 @@ fn generate_field_extraction(
     optional_sources: &std::collections::HashSet<String>,
 ) -> Result<String, Box<dyn Error>> {
     if let Some(ref computed) = field.computed_from {
-        generate_computed_field(field, computed, entity, source_entities, all_entities, optional_sources)
+        let value = generate_computed_field(field, computed, entity, source_entities, all_entities, optional_sources)?;
+        // Parse the extracted string into the declared type when `coerce:` is set
+        let value = crate::codegen::utils::generate_coercion_expr(
+            &entity.name,
+            field,
+            &value,
+            super::entities_rs::map_field_type(&field.field_type),
+            Some("return Ok(None);"),
+            &|msg| super::error_rs::invalid_field_value(&entity.name, &field.name, msg),
+        ).unwrap_or(value);
+        // Fall back to the configured default when the value is absent
+        match field.default_literal()? {
+            Some(default) => Ok(format!("({}).or_else(|| Some({}))", value, default)),
+            None => Ok(value),
+        }
     } else {
         // Field has no computed_from - should not happen for derived entities
         Ok("None".to_string())
@@ -249,28 +299,7 @@ fn generate_computed_field(
 
                 // Resolve alias to actual entity name
                 // source_name might be an alias like "diagnosis", need to find actual entity name "Diagnosis"
-                let actual_entity_name = entity.derivation.as_ref()
-                    .and_then(|d| d.source_entities.as_ref())
-                    .and_then(|se| {
-                        if let serde_yaml::Value::Mapping(map) = se {
-                            map.get(&serde_yaml::Value::String(source_name.to_string()))
-                                .and_then(|v| {
-                                    match v {
-                                        // Simple string format: facility: Facility
-                                        serde_yaml::Value::String(s) => Some(s.as_str()),
-                                        // Detailed object format: facility: {entity: Facility, ancillary: true}
-                                        serde_yaml::Value::Mapping(obj) => {
-                                            obj.get(&serde_yaml::Value::String("entity".to_string()))
-                                                .and_then(|e| e.as_str())
-                                        }
-                                        _ => None
-                                    }
-                                })
-                        } else {
-                            None
-                        }
-                    })
-                    .unwrap_or(source_name);
+                let actual_entity_name = resolve_source_alias(entity, source_name);
 
                 // Check if source entity is optional (repeated or ancillary)
                 let is_optional_source = optional_sources.contains(actual_entity_name);
@@ -299,6 +328,32 @@ fn generate_computed_field(
             }
         }
 
+        "extract_json_subtree" => {
+            // Take a sub-object out of a JSON source field (raw text or a Json field)
+            let source = computed.sources.first().ok_or("Missing source for extract_json_subtree")?;
+            let source_name = source.source_name();
+            let source_field = source.field_name().ok_or("Missing field name for extract_json_subtree")?;
+            let path = computed.arg_str("path").ok_or("Missing 'path' argument for extract_json_subtree")?;
+
+            let source_var = to_snake_case(source_name);
+            let actual_entity_name = resolve_source_alias(entity, source_name);
+            let source_def = all_entities.iter()
+                .find(|e| e.name == actual_entity_name)
+                .and_then(|e| e.fields.iter().find(|f| f.name == source_field));
+            let nullable = source_def.map(|f| f.nullable).unwrap_or(true);
+            let is_json = source_def.map(|f| f.is_json()).unwrap_or(false);
+
+            // Normalize the source to an Option<_> expression
+            let source_expr = match (optional_sources.contains(actual_entity_name), nullable) {
+                (true, true) => format!("{}.and_then(|e| e.{}.clone())", source_var, source_field),
+                (true, false) => format!("{}.map(|e| e.{}.clone())", source_var, source_field),
+                (false, true) => format!("{}.{}", source_var, source_field),
+                (false, false) => format!("Some({}.{}.clone())", source_var, source_field),
+            };
+
+            Ok(crate::codegen::utils::generate_json_subtree_expr(&source_expr, is_json, path))
+        }
+
         "constant_value" => {
             // Return a constant value
             if let Some(args) = &computed.args {
@@ -338,17 +393,7 @@ fn generate_computed_field(
                     let source_var = to_snake_case(source_name);
 
                     // Resolve alias to actual entity name
-                    let actual_entity_name = entity.derivation.as_ref()
-                        .and_then(|d| d.source_entities.as_ref())
-                        .and_then(|se| {
-                            if let serde_yaml::Value::Mapping(map) = se {
-                                map.get(&serde_yaml::Value::String(source_name.to_string()))
-                                    .and_then(|v| v.as_str())
-                            } else {
-                                None
-                            }
-                        })
-                        .unwrap_or(source_name);
+                    let actual_entity_name = resolve_source_alias(entity, source_name);
 
                     // Check if source entity is repeated (optional parameter)
                     let source_entity = all_entities.iter().find(|e| &e.name == actual_entity_name);
@@ -383,17 +428,7 @@ fn generate_computed_field(
                     let source_var = to_snake_case(source_name);
 
                     // Resolve alias to actual entity name
-                    let actual_entity_name = entity.derivation.as_ref()
-                        .and_then(|d| d.source_entities.as_ref())
-                        .and_then(|se| {
-                            if let serde_yaml::Value::Mapping(map) = se {
-                                map.get(&serde_yaml::Value::String(source_name.to_string()))
-                                    .and_then(|v| v.as_str())
-                            } else {
-                                None
-                            }
-                        })
-                        .unwrap_or(source_name);
+                    let actual_entity_name = resolve_source_alias(entity, source_name);
 
                     // Check if source entity is repeated (optional parameter)
                     let source_entity = all_entities.iter().find(|e| &e.name == actual_entity_name);
@@ -515,17 +550,7 @@ fn generate_transform_call(
                 let source_var = to_snake_case(source_name);
 
                 // Resolve alias to actual entity name
-                let actual_entity_name = entity.derivation.as_ref()
-                    .and_then(|d| d.source_entities.as_ref())
-                    .and_then(|se| {
-                        if let serde_yaml::Value::Mapping(map) = se {
-                            map.get(&serde_yaml::Value::String(source_name.to_string()))
-                                .and_then(|v| v.as_str())
-                        } else {
-                            None
-                        }
-                    })
-                    .unwrap_or(source_name);
+                let actual_entity_name = resolve_source_alias(entity, source_name);
 
                 // Check if source entity is repeated (and thus optional parameter)
                 let source_entity = all_entities.iter().find(|e| &e.name == actual_entity_name);
@@ -574,10 +599,24 @@ fn generate_transform_call(
 
                 // First check if this is a direct parameter (e.g., "segment" for repeating entities)
                 let snake_field = to_snake_case(field_name);
-                if source_entities.iter().any(|se| to_snake_case(se) == snake_field) {
+                let is_parameter = source_entities.iter().any(|se| to_snake_case(se) == snake_field);
+                // A root entity named as a whole passes its declared data_field
+                let root_data_field = all_entities.iter()
+                    .find(|e| is_parameter && e.is_root() && to_snake_case(&e.name) == snake_field)
+                    .and_then(|root| {
+                        let data_field = root.data_field.as_ref()?;
+                        root.fields.iter().find(|f| &f.name == data_field)
+                    });
+                if let Some(data_field) = root_data_field {
+                    if data_field.nullable {
+                        args.push(format!("&{}.{}", snake_field, data_field.name));
+                    } else {
+                        args.push(format!("&Some({}.{}.clone())", snake_field, data_field.name));
+                    }
+                } else if is_parameter {
                     // This is a direct parameter
                     // For repeated_for entities, use the _opt wrapper for transforms
-                    if entity.repeated_for.is_some() {
+                    if entity.each_known_as().is_some() {
                         args.push(format!("&{}_opt", snake_field));
                     } else {
                         args.push(format!("&{}", snake_field));
@@ -629,7 +668,7 @@ fn generate_transform_call(
                         // Field not found - might be a root entity parameter
                         // Try using it as a direct parameter
                         // For repeated_for entities, use the _opt wrapper for transforms
-                        if entity.repeated_for.is_some() {
+                        if entity.each_known_as().is_some() {
                             args.push(format!("&{}_opt", snake_field));
                         } else {
                             args.push(format!("&{}", snake_field));
@@ -658,18 +697,15 @@ fn generate_transform_call(
 
     // Call transform function
     let call = if args.is_empty() {
-        format!("{}()", transform_name)
+        format!("{}()", transform_fn_name(transform_name))
     } else {
-        format!("{}({})", transform_name, args.join(", "))
+        format!("{}({})", transform_fn_name(transform_name), args.join(", "))
     };
 
     // Handle Result unwrapping based on field type
-    if field.field_type.starts_with("List[") || field.field_type.starts_with("Vec<") {
-        // List/Vec types: unwrap with empty vec as default
-        Ok(format!("{}.unwrap_or_else(|_| Vec::new())", call))
-    } else if field.nullable {
-        // Nullable Option fields: unwrap with None as default
-        Ok(format!("{}.unwrap_or(None)", call))
+    if field.field_type.starts_with("List[") || field.field_type.starts_with("Vec<") || field.nullable {
+        // Transform errors fail the entity (ON_ENTITY_ERROR decides what follows)
+        Ok(format!("{}{}?", call, super::error_rs::transform_error(&entity.name, &field.name, transform_name)))
     } else {
         // Non-nullable fields: just return the Result (will be unwrapped at call site)
         Ok(call)
@@ -687,27 +723,6 @@ fn sanitize_doc_string(doc: &str) -> String {
        .collect()
 }
 
-/// Convert CamelCase to snake_case
-fn to_snake_case(s: &str) -> String {
-    let mut result = String::new();
-    let mut prev_is_upper = false;
-
-    for (i, ch) in s.chars().enumerate() {
-        if ch.is_uppercase() {
-            if i > 0 && !prev_is_upper {
-                result.push('_');
-            }
-            result.push(ch.to_lowercase().next().unwrap());
-            prev_is_upper = true;
-        } else {
-            result.push(ch);
-            prev_is_upper = false;
-        }
-    }
-
-    result
-}
-
 /// Generate existence check using explicit minimal_existence constraint
 fn generate_minimal_existence_check(
     output: &mut std::fs::File,
@@ -811,6 +826,13 @@ fn generate_field_nonempty_check(field: &FieldDef) -> String {
 
     if is_list {
         format!("!entity.{}.is_empty()", field.name)
+    } else if field.is_json() {
+        // JSON documents count as present unless null
+        if field.nullable {
+            format!("entity.{}.as_ref().map(|v| !v.is_null()).unwrap_or(false)", field.name)
+        } else {
+            format!("!entity.{}.is_null()", field.name)
+        }
     } else if field.nullable {
         format!(
             "entity.{}.as_ref().map(|s: &String| !s.is_empty()).unwrap_or(false)",
@@ -821,15 +843,174 @@ fn generate_field_nonempty_check(field: &FieldDef) -> String {
     }
 }
 
+/// Resolve a derivation source alias (e.g. "facility") to its entity name ("Facility")
+fn resolve_source_alias<'a>(entity: &'a EntityDef, source_name: &'a str) -> &'a str {
+    entity.derivation.as_ref()
+        .and_then(|d| d.source_entities.resolve(source_name))
+        .unwrap_or(source_name)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::codegen::worker::{generate_coordinator_file, DatabaseType};
+
+    #[test]
+    fn test_ancestor_sources_are_passed_to_extract_functions() {
+        let entities = crate::codegen::parse_entities(r#"
+entity:
+  name: Message
+  source_type: root
+  fields:
+    - name: raw
+      type: String
+---
+entity:
+  name: Header
+  source_type: derived
+  parent: Message
+  fields:
+    - name: sender
+      type: String
+      computed_from:
+        transform: copy_field
+        sources:
+          - source: Message
+            field: raw
+---
+entity:
+  name: Detail
+  source_type: derived
+  parent: Header
+  fields:
+    - name: code
+      type: String
+      computed_from:
+        transform: copy_field
+        sources:
+          - source: Header
+            field: sender
+---
+entity:
+  name: Line
+  source_type: derived
+  parent: Detail
+  fields:
+    - name: sender
+      type: String
+      computed_from:
+        transform: copy_field
+        sources:
+          - source: Header
+            field: sender
+"#).unwrap();
+        let graph = DependencyGraph::build(&entities).unwrap();
+        assert_eq!(graph.nodes["Line"].depends_on, ["Detail", "Header"]);
+        // The grandparent is extracted first, so it's in scope
+        assert!(graph.nodes["Header"].level < graph.nodes["Line"].level);
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        generate_extract_functions_file(&entities, &graph, dir.path()).unwrap();
+        generate_coordinator_file(&entities, &graph, dir.path(), DatabaseType::PostgreSQL, false).unwrap();
+        let extract = std::fs::read_to_string(dir.path().join("src/extract.rs")).unwrap();
+        let coordinator = std::fs::read_to_string(dir.path().join("src/coordinator.rs")).unwrap();
+
+        assert!(extract.contains("pub fn extract_line(\n    detail: &Detail,\n    header: &Header\n)"), "{}", extract);
+        assert!(extract.contains("copy_field(&Some(header.sender.clone()))"), "{}", extract);
+        assert!(coordinator.contains("if detail_entity.is_some() && header_entity.is_some() {"), "{}", coordinator);
+        assert!(coordinator.contains(
+            "line_entity = extract_line(&detail_entity.as_ref().unwrap(), &header_entity.as_ref().unwrap())"
+        ), "{}", coordinator);
+    }
+
+    #[test]
+    fn test_transform_errors_fail_the_entity() {
+        let entities = crate::codegen::parse_entities(r#"
+entity:
+  name: Message
+  source_type: root
+  fields:
+    - name: raw
+      type: String
+---
+entity:
+  name: Header
+  source_type: derived
+  parent: Message
+  fields:
+    - name: sender
+      type: String
+      nullable: true
+      computed_from:
+        transform: parse_sender
+        sources:
+          - source: Message
+            field: raw
+    - name: recipients
+      type: List[String]
+      computed_from:
+        transform: parse_recipients
+        sources:
+          - source: Message
+            field: raw
+"#).unwrap();
+        let graph = DependencyGraph::build(&entities).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        generate_extract_functions_file(&entities, &graph, dir.path()).unwrap();
+        let extract = std::fs::read_to_string(dir.path().join("src/extract.rs")).unwrap();
+
+        for (field, transform) in [("sender", "parse_sender"), ("recipients", "parse_recipients")] {
+            let call = format!(
+                "{}(&Some(message.raw.clone())).map_err(|e| AppError::Transform {{ entity: \"Header\".to_string(), field: \"{}\".to_string(), transform: \"{}\".to_string(), message: e.to_string() }})?",
+                transform, field, transform
+            );
+            assert!(extract.contains(&call), "{}", extract);
+        }
+        assert!(!extract.contains("unwrap_or(None)"));
+        assert!(!extract.contains("unwrap_or_else(|_| Vec::new())"));
+    }
 
     #[test]
-    fn test_snake_case_conversion() {
-        assert_eq!(to_snake_case("PatientVisit"), "patient_visit");
-        assert_eq!(to_snake_case("Anesthesiologist"), "anesthesiologist");
-        assert_eq!(to_snake_case("MPI"), "m_p_i");
-        assert_eq!(to_snake_case("Hl7v2Message"), "hl7v2_message");
+    fn test_root_data_field_is_passed_to_whole_entity_sources() {
+        let yaml = |data_field: &str| format!(r#"
+entity:
+  name: Upload
+  source_type: root
+  {}
+  fields:
+    - name: file_name
+      type: String
+    - name: payload
+      type: String
+---
+entity:
+  name: Summary
+  source_type: derived
+  parent: Upload
+  fields:
+    - name: digest
+      type: String
+      nullable: true
+      computed_from:
+        transform: digest_payload
+        sources:
+          - Upload
+"#, data_field);
+
+        let extract = |data_field: &str| {
+            let entities = crate::codegen::parse_entities(&yaml(data_field)).unwrap();
+            let graph = DependencyGraph::build(&entities).unwrap();
+            let dir = tempfile::tempdir().unwrap();
+            std::fs::create_dir(dir.path().join("src")).unwrap();
+            generate_extract_functions_file(&entities, &graph, dir.path()).unwrap();
+            std::fs::read_to_string(dir.path().join("src/extract.rs")).unwrap()
+        };
+
+        let declared = extract("data_field: payload");
+        assert!(declared.contains("digest_payload(&Some(upload.payload.clone()))"), "{}", declared);
+        let undeclared = extract("");
+        assert!(undeclared.contains("digest_payload(&upload)"), "{}", undeclared);
     }
 }
@@ -0,0 +1,96 @@
+/// Generate a cargo-fuzz crate for the worker's message parser
+///
+/// The worker is a binary crate, so the fuzz target compiles `error.rs` and
+/// `parsers.rs` in directly. Run it with `cargo +nightly fuzz run parse_json`
+/// from the worker directory.
+
+use super::WorkerConfig;
+use super::cargo_toml::PARSER_DEPENDENCIES;
+use crate::codegen::EntityDef;
+use std::path::Path;
+use std::error::Error;
+use std::io::Write;
+
+pub fn generate_fuzz_target(
+    entities: &[EntityDef],
+    output_dir: &Path,
+    config: &WorkerConfig,
+) -> Result<(), Box<dyn Error>> {
+    let fuzz_dir = output_dir.join("fuzz");
+    std::fs::create_dir_all(fuzz_dir.join("fuzz_targets"))?;
+
+    let mut output = std::fs::File::create(fuzz_dir.join("Cargo.toml"))?;
+    writeln!(output, "[package]")?;
+    writeln!(output, "name = \"{}-fuzz\"", config.worker_name)?;
+    writeln!(output, "version = \"0.0.0\"")?;
+    writeln!(output, "publish = false")?;
+    writeln!(output, "edition = \"2021\"\n")?;
+    writeln!(output, "[package.metadata]")?;
+    writeln!(output, "cargo-fuzz = true\n")?;
+    writeln!(output, "[dependencies]")?;
+    writeln!(output, "libfuzzer-sys = \"0.4\"")?;
+    writeln!(output, "# Dependencies of the included error.rs and parsers.rs")?;
+    for (name, spec) in PARSER_DEPENDENCIES {
+        writeln!(output, "{} = {}", name, spec)?;
+    }
+    writeln!(output)?;
+    writeln!(output, "[[bin]]")?;
+    writeln!(output, "name = \"parse_json\"")?;
+    writeln!(output, "path = \"fuzz_targets/parse_json.rs\"")?;
+    writeln!(output, "test = false")?;
+    writeln!(output, "doc = false")?;
+    writeln!(output, "bench = false\n")?;
+    writeln!(output, "# Keep the fuzz crate out of any enclosing workspace")?;
+    writeln!(output, "[workspace]")?;
+    writeln!(output, "members = [\".\"]")?;
+
+    let mut output = std::fs::File::create(fuzz_dir.join("fuzz_targets/parse_json.rs"))?;
+    writeln!(output, "// Auto-generated fuzz target for MessageParser::parse_json")?;
+    writeln!(output, "// Arbitrary input must yield Ok or Err, never a panic\n")?;
+    writeln!(output, "#![no_main]\n")?;
+    writeln!(output, "use libfuzzer_sys::fuzz_target;\n")?;
+    writeln!(output, "#[allow(dead_code)]")?;
+    writeln!(output, "#[path = \"../../src/error.rs\"]")?;
+    writeln!(output, "mod error;")?;
+    writeln!(output, "#[allow(dead_code)]")?;
+    writeln!(output, "#[path = \"../../src/parsers.rs\"]")?;
+    writeln!(output, "mod parsers;\n")?;
+
+    let hints: Vec<String> = entities.iter()
+        .filter(|e| e.is_root() && !e.is_abstract && e.source_type.to_lowercase() != "reference")
+        .map(|e| format!("Some(\"{}\")", e.name))
+        .collect();
+    writeln!(output, "/// Envelope `entity_type` hints to exercise, picked by the first input byte")?;
+    writeln!(output, "const HINTS: &[Option<&str>] = &[None, Some(\"unknown\"){}];\n",
+        hints.iter().map(|h| format!(", {}", h)).collect::<String>())?;
+
+    writeln!(output, "fuzz_target!(|data: &[u8]| {{")?;
+    writeln!(output, "    let Some((&selector, body)) = data.split_first() else {{")?;
+    writeln!(output, "        return;")?;
+    writeln!(output, "    }};")?;
+    writeln!(output, "    // Envelope bodies reach the parser as UTF-8 text")?;
+    writeln!(output, "    let body = String::from_utf8_lossy(body);")?;
+    writeln!(output, "    let hint = HINTS[selector as usize % HINTS.len()];")?;
+    writeln!(output, "    let _ = parsers::MessageParser::parse_json(&body, hint);")?;
+    writeln!(output, "}});")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzz_crate_takes_the_worker_parser_dependencies() {
+        let dir = tempfile::tempdir().unwrap();
+        generate_fuzz_target(&[], dir.path(), &WorkerConfig::default()).unwrap();
+        let cargo_toml = std::fs::read_to_string(dir.path().join("fuzz/Cargo.toml")).unwrap();
+
+        // error.rs derives thiserror::Error
+        assert!(cargo_toml.contains("thiserror = \"1\"\n"), "{}", cargo_toml);
+        for (name, spec) in PARSER_DEPENDENCIES {
+            assert!(cargo_toml.contains(&format!("{} = {}\n", name, spec)), "{}", name);
+        }
+    }
+}
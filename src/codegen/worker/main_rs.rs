@@ -1,53 +1,17 @@
 /// Generate main.rs with NATS consumer loop
 
 use crate::codegen::EntityDef;
-use super::WorkerConfig;
+use crate::codegen::utils::{to_snake_case, transform_fn_name};
+use crate::codegen::sql_dialect::{write_sql_query_per_feature, SqlDialect};
+use super::{subjects, WorkerConfig};
 use std::path::Path;
 use std::error::Error;
 use std::io::Write;
 
-/// Convert field names to snake_case for SQL/Rust
-/// Handles both camelCase and already-snake_case inputs
-fn to_snake_case(s: &str) -> String {
-    // If already contains underscores (except f_ prefix), likely already snake_case
-    let stripped = if s.starts_with("f_") {
-        &s[2..]
-    } else {
-        s
-    };
-
-    // Check if already snake_case (has underscores or all lowercase)
-    if stripped.contains('_') || stripped.chars().all(|c| !c.is_uppercase()) {
-        return s.to_string();
-    }
-
-    // Convert camelCase to snake_case
-    let mut result = String::new();
-    let mut prev_lowercase = false;
-
-    for (i, ch) in s.chars().enumerate() {
-        if ch.is_uppercase() {
-            // Add underscore before uppercase if:
-            // 1. Not at start
-            // 2. Previous char was lowercase
-            // 3. OR next char is lowercase (handles "XMLParser" -> "xml_parser")
-            if i > 0 && (prev_lowercase || s.chars().nth(i + 1).map_or(false, |c| c.is_lowercase())) {
-                result.push('_');
-            }
-            result.push(ch.to_lowercase().next().unwrap());
-            prev_lowercase = false;
-        } else {
-            result.push(ch);
-            prev_lowercase = ch.is_lowercase();
-        }
-    }
-    result
-}
-
 pub fn generate_main_rs(
     entities: &[EntityDef],
     output_dir: &Path,
-    _config: &WorkerConfig,
+    config: &WorkerConfig,
 ) -> Result<(), Box<dyn Error>> {
     let main_file = output_dir.join("src/main.rs");
     let mut output = std::fs::File::create(&main_file)?;
@@ -59,6 +23,8 @@ pub fn generate_main_rs(
     writeln!(output, "use diesel::prelude::*;")?;
     writeln!(output, "use diesel::sql_types::{{Text, Integer, BigInt, Double, Bool, Date, Numeric, Nullable}};")?;
     writeln!(output, "use futures::StreamExt;")?;
+    writeln!(output, "use std::collections::HashMap;")?;
+    writeln!(output, "use tracing::Instrument;")?;
     writeln!(output, "use std::time::Duration;\n")?;
 
     writeln!(output, "mod parsers;")?;
@@ -69,7 +35,14 @@ pub fn generate_main_rs(
     writeln!(output, "mod entities;")?;
     writeln!(output, "mod extract;")?;
     writeln!(output, "mod persist_publish;")?;
-    writeln!(output, "mod coordinator;\n")?;
+    writeln!(output, "mod coordinator;")?;
+    if config.side_output {
+        writeln!(output, "mod side_output;")?;
+        if config.side_output_sink.is_some() {
+            writeln!(output, "mod custom_side_output;")?;
+        }
+    }
+    writeln!(output, "mod telemetry;\n")?;
 
     writeln!(output, "use database::{{create_pool, ensure_tables, DbConnection}};")?;
     writeln!(output, "use parsers::{{MessageParser, ParsedMessage}};")?;
@@ -94,27 +67,169 @@ pub fn generate_main_rs(
     writeln!(output, "    uuid.to_string()")?;
     writeln!(output, "}}\n")?;
 
+    writeln!(output, "/// Envelope schema version this worker understands")?;
+    writeln!(output, "const ENVELOPE_SCHEMA_VERSION: u32 = 1;\n")?;
+
     writeln!(output, "/// Message envelope from NATS API")?;
+    writeln!(output, "///")?;
+    writeln!(output, "/// Unknown fields from newer producers are ignored; missing fields take defaults.")?;
     writeln!(output, "#[derive(Debug, serde::Deserialize)]")?;
     writeln!(output, "struct MessageEnvelope {{")?;
+    writeln!(output, "    #[serde(default)]")?;
+    writeln!(output, "    schema_version: u32,")?;
     writeln!(output, "    message_id: uuid::Uuid,")?;
     writeln!(output, "    body: String,")?;
+    writeln!(output, "    /// Codec of a compressed `body`; absent for envelopes published uncompressed")?;
+    writeln!(output, "    #[serde(default)]")?;
+    writeln!(output, "    content_encoding: Option<String>,")?;
+    writeln!(output, "    /// Hex SHA-256 of the uncompressed body, checked when VERIFY_BODY_SHA256 is set")?;
+    writeln!(output, "    #[serde(default)]")?;
+    writeln!(output, "    body_sha256: Option<String>,")?;
     writeln!(output, "    entity_type: Option<String>,")?;
     writeln!(output, "    received_at: chrono::DateTime<chrono::Utc>,")?;
+    writeln!(output, "    #[serde(default)]")?;
     writeln!(output, "    retry_count: u32,")?;
     writeln!(output, "    source: Option<String>,")?;
+    writeln!(output, "    #[serde(default)]")?;
+    writeln!(output, "    correlation_id: Option<String>,")?;
+    writeln!(output, "    #[serde(default)]")?;
+    writeln!(output, "    trace_context: Option<HashMap<String, String>>,")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// Envelope body as published by the client, decompressed per `content_encoding`")?;
+    writeln!(output, "fn decode_body(envelope: &MessageEnvelope) -> Result<std::borrow::Cow<'_, str>, String> {{")?;
+    writeln!(output, "    use base64::Engine;")?;
+    writeln!(output, "    use std::io::Read;\n")?;
+    writeln!(output, "    let codec = match envelope.content_encoding.as_deref() {{")?;
+    writeln!(output, "        None | Some(\"\") | Some(\"identity\") => return Ok(std::borrow::Cow::Borrowed(&envelope.body)),")?;
+    writeln!(output, "        Some(codec) => codec,")?;
+    writeln!(output, "    }};")?;
+    writeln!(output, "    let compressed = std::io::Cursor::new(")?;
+    writeln!(output, "        base64::engine::general_purpose::STANDARD.decode(&envelope.body)")?;
+    writeln!(output, "            .map_err(|e| format!(\"Compressed body is not valid base64: {{}}\", e))?,")?;
+    writeln!(output, "    );")?;
+    writeln!(output, "    let mut reader: Box<dyn Read> = match codec {{")?;
+    writeln!(output, "        \"gzip\" => Box::new(flate2::read::GzDecoder::new(compressed)),")?;
+    writeln!(output, "        \"zstd\" => Box::new(zstd::Decoder::new(compressed).map_err(|e| e.to_string())?),")?;
+    writeln!(output, "        other => return Err(format!(\"Unsupported content_encoding '{{}}'\", other)),")?;
+    writeln!(output, "    }};")?;
+    writeln!(output, "    // Cap the output so a small compressed payload can't expand without bound")?;
+    writeln!(output, "    const MAX_BODY_BYTES: u64 = {};", crate::nats::compression::MAX_DECOMPRESSED_BODY_BYTES)?;
+    writeln!(output, "    let mut body = Vec::new();")?;
+    writeln!(output, "    reader.take(MAX_BODY_BYTES + 1).read_to_end(&mut body)")?;
+    writeln!(output, "        .map_err(|e| format!(\"{{}} decompression failed: {{}}\", codec, e))?;")?;
+    writeln!(output, "    if body.len() as u64 > MAX_BODY_BYTES {{")?;
+    writeln!(output, "        return Err(format!(\"{{}} body exceeds {{}} bytes when decompressed\", codec, MAX_BODY_BYTES));")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "    String::from_utf8(body)")?;
+    writeln!(output, "        .map(std::borrow::Cow::Owned)")?;
+    writeln!(output, "        .map_err(|e| format!(\"{{}} decompression failed: {{}}\", codec, e))")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// Whether `body_sha256` is checked before parsing (`VERIFY_BODY_SHA256=true`)")?;
+    writeln!(output, "fn verify_body_sha256() -> bool {{")?;
+    writeln!(output, "    static VERIFY: std::sync::OnceLock<bool> = std::sync::OnceLock::new();")?;
+    writeln!(output, "    *VERIFY.get_or_init(|| std::env::var(\"VERIFY_BODY_SHA256\")")?;
+    writeln!(output, "        .map(|v| matches!(v.to_lowercase().as_str(), \"1\" | \"true\" | \"yes\"))")?;
+    writeln!(output, "        .unwrap_or(false))")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// Check a decoded body against the envelope's checksum, if it carries one")?;
+    writeln!(output, "fn check_body_sha256(envelope: &MessageEnvelope, body: &str) -> Result<(), AppError> {{")?;
+    writeln!(output, "    use sha2::{{Digest, Sha256}};\n")?;
+    writeln!(output, "    let Some(ref expected) = envelope.body_sha256 else {{")?;
+    writeln!(output, "        return Ok(());")?;
+    writeln!(output, "    }};")?;
+    writeln!(output, "    let actual = format!(\"{{:x}}\", Sha256::digest(body.as_bytes()));")?;
+    writeln!(output, "    if !expected.eq_ignore_ascii_case(&actual) {{")?;
+    writeln!(output, "        return Err(AppError::IntegrityError(format!(")?;
+    writeln!(output, "            \"body_sha256 {{}} does not match body ({{}} bytes, sha256 {{}}): truncated or corrupted payload\",")?;
+    writeln!(output, "            expected, body.len(), actual")?;
+    writeln!(output, "        )));")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "    Ok(())")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// Upgrade a raw envelope to the current schema version")?;
+    writeln!(output, "///")?;
+    writeln!(output, "/// Migration hook: add a match arm per version when the envelope shape changes.")?;
+    writeln!(output, "fn migrate_envelope(mut value: serde_json::Value) -> Result<serde_json::Value, String> {{")?;
+    writeln!(output, "    let mut version = value.get(\"schema_version\").and_then(|v| v.as_u64()).unwrap_or(0) as u32;")?;
+    writeln!(output, "    if version > ENVELOPE_SCHEMA_VERSION {{")?;
+    writeln!(output, "        tracing::warn!(\"Envelope schema version {{}} is newer than supported {{}}; processing known fields only\", version, ENVELOPE_SCHEMA_VERSION);")?;
+    writeln!(output, "        return Ok(value);")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "    while version < ENVELOPE_SCHEMA_VERSION {{")?;
+    writeln!(output, "        match version {{")?;
+    writeln!(output, "            // v0 -> v1: only adds schema_version, all other fields unchanged")?;
+    writeln!(output, "            0 => {{}}")?;
+    writeln!(output, "            other => return Err(format!(\"No envelope migration from version {{}}\", other)),")?;
+    writeln!(output, "        }}")?;
+    writeln!(output, "        version += 1;")?;
+    writeln!(output, "        if let Some(obj) = value.as_object_mut() {{")?;
+    writeln!(output, "            obj.insert(\"schema_version\".to_string(), serde_json::Value::from(version));")?;
+    writeln!(output, "        }}")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "    Ok(value)")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// Decode an envelope in the format named by its Content-Type header")?;
+    writeln!(output, "///")?;
+    writeln!(output, "/// Messages without the header are JSON. Older JSON envelopes are migrated;")?;
+    writeln!(output, "/// MessagePack and CBOR envelopes are always published at the current version.")?;
+    writeln!(output, "fn decode_envelope(payload: &[u8], content_type: Option<&str>) -> Result<MessageEnvelope, String> {{")?;
+    writeln!(output, "    match content_type.unwrap_or(\"application/json\") {{")?;
+    writeln!(output, "        \"application/json\" => serde_json::from_slice::<serde_json::Value>(payload)")?;
+    writeln!(output, "            .map_err(|e| e.to_string())")?;
+    writeln!(output, "            .and_then(migrate_envelope)")?;
+    writeln!(output, "            .and_then(|value| serde_json::from_value(value).map_err(|e| e.to_string())),")?;
+    writeln!(output, "        \"application/msgpack\" => rmp_serde::from_slice(payload).map_err(|e| e.to_string()),")?;
+    writeln!(output, "        \"application/cbor\" => ciborium::de::from_reader(payload).map_err(|e| e.to_string()),")?;
+    writeln!(output, "        other => Err(format!(\"Unsupported envelope Content-Type '{{}}'\", other)),")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// Content-Type header of a message, if set")?;
+    writeln!(output, "fn message_content_type(msg: &async_nats::Message) -> Option<&str> {{")?;
+    writeln!(output, "    msg.headers.as_ref()?.get(\"Content-Type\").map(|value| value.as_str())")?;
     writeln!(output, "}}\n")?;
 
+    let subject_list = |subjects: &[String]| subjects.iter()
+        .map(|s| format!("\"{}\"", s))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    writeln!(output, "/// Subjects the worker's stream captures (`NATS_STREAM_SUBJECTS`, comma-separated)")?;
+    writeln!(output, "const DEFAULT_STREAM_SUBJECTS: &[&str] = &[{}];\n", subject_list(&config.stream_subjects))?;
+    writeln!(output, "/// Subjects this worker's consumer receives (`NATS_FILTER_SUBJECTS`, comma-separated;")?;
+    writeln!(output, "/// empty consumes the whole stream)")?;
+    writeln!(output, "const DEFAULT_FILTER_SUBJECTS: &[&str] = &[{}];\n", subject_list(&config.filter_subjects))?;
+
+    writeln!(output, "/// Comma-separated subject list from an environment variable, or the default")?;
+    writeln!(output, "fn subjects_from_env(var: &str, default: &[&str]) -> Vec<String> {{")?;
+    writeln!(output, "    match std::env::var(var) {{")?;
+    writeln!(output, "        Ok(value) => value.split(',')")?;
+    writeln!(output, "            .map(str::trim)")?;
+    writeln!(output, "            .filter(|s| !s.is_empty())")?;
+    writeln!(output, "            .map(str::to_string)")?;
+    writeln!(output, "            .collect(),")?;
+    writeln!(output, "        Err(_) => default.iter().map(|s| s.to_string()).collect(),")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "}}\n")?;
+
+    generate_sampling_fns(output, config)?;
+
+    write!(output, "{}", crate::codegen::utils::generate_init_tracing_fn())?;
+    write!(output, "{}", crate::codegen::utils::generate_nats_connect_options_fn())?;
+
     writeln!(output, "#[tokio::main]")?;
     writeln!(output, "async fn main() {{")?;
-    writeln!(output, "    // Initialize tracing")?;
-    writeln!(output, "    eprintln!(\"[WORKER] Initializing worker...\");")?;
-    writeln!(output, "    tracing_subscriber::fmt::init();")?;
-    writeln!(output, "    eprintln!(\"[WORKER] Tracing initialized\");\n")?;
+    writeln!(output, "    // Load environment variables (before tracing, so LOG_FORMAT/RUST_LOG can come from .env)")?;
+    writeln!(output, "    dotenv::dotenv().ok();\n")?;
 
-    writeln!(output, "    // Load environment variables")?;
-    writeln!(output, "    dotenv::dotenv().ok();")?;
-    writeln!(output, "    eprintln!(\"[WORKER] Environment loaded\");\n")?;
+    writeln!(output, "    // Initialize tracing")?;
+    writeln!(output, "    init_tracing();")?;
+    writeln!(output, "    tracing::info!(\"Initializing worker\");\n")?;
 
     writeln!(output, "    // Get NATS configuration")?;
     writeln!(output, "    let nats_url = std::env::var(\"NATS_URL\")")?;
@@ -122,7 +237,24 @@ pub fn generate_main_rs(
     writeln!(output, "    let stream_name = std::env::var(\"NATS_STREAM\")")?;
     writeln!(output, "        .unwrap_or_else(|_| \"MESSAGES\".to_string());")?;
     writeln!(output, "    let consumer_name = std::env::var(\"NATS_CONSUMER\")")?;
-    writeln!(output, "        .unwrap_or_else(|_| \"workers\".to_string());\n")?;
+    writeln!(output, "        .unwrap_or_else(|_| \"workers\".to_string());")?;
+    writeln!(output, "    // durable: replicas share NATS_CONSUMER; ephemeral: removed when idle, one per")?;
+    writeln!(output, "    // pod unless pods share NATS_QUEUE_GROUP")?;
+    writeln!(output, "    let consumer_durability = std::env::var(\"NATS_CONSUMER_DURABILITY\")")?;
+    writeln!(output, "        .unwrap_or_else(|_| \"{}\".to_string());", config.consumer_durability.as_str())?;
+    match config.queue_group {
+        Some(ref group) => {
+            writeln!(output, "    let queue_group = std::env::var(\"NATS_QUEUE_GROUP\")")?;
+            writeln!(output, "        .unwrap_or_else(|_| \"{}\".to_string());", group)?;
+        }
+        None => writeln!(output, "    let queue_group = std::env::var(\"NATS_QUEUE_GROUP\").unwrap_or_default();")?,
+    }
+    writeln!(output, "    let consumer_inactive_secs = std::env::var(\"NATS_CONSUMER_INACTIVE_SECS\")")?;
+    writeln!(output, "        .ok()")?;
+    writeln!(output, "        .and_then(|s| s.parse::<u64>().ok())")?;
+    writeln!(output, "        .unwrap_or(300);")?;
+    writeln!(output, "    let stream_subjects = subjects_from_env(\"NATS_STREAM_SUBJECTS\", DEFAULT_STREAM_SUBJECTS);")?;
+    writeln!(output, "    let filter_subjects = subjects_from_env(\"NATS_FILTER_SUBJECTS\", DEFAULT_FILTER_SUBJECTS);\n")?;
 
     writeln!(output, "    // Get worker configuration")?;
     writeln!(output, "    let max_deliver = std::env::var(\"MAX_DELIVER\")")?;
@@ -133,45 +265,57 @@ pub fn generate_main_rs(
     writeln!(output, "        .ok()")?;
     writeln!(output, "        .and_then(|s| s.parse::<usize>().ok())")?;
     writeln!(output, "        .unwrap_or(10);")?;
+    writeln!(output, "    // Unacked deliveries are redelivered after ack_wait, and every redelivery")?;
+    writeln!(output, "    // counts towards max_deliver: ack_wait must exceed the slowest record's")?;
+    writeln!(output, "    // processing time or it is processed twice and may exhaust max_deliver")?;
+    writeln!(output, "    let ack_wait_secs = std::env::var(\"ACK_WAIT_SECS\")")?;
+    writeln!(output, "        .ok()")?;
+    writeln!(output, "        .and_then(|s| s.parse::<u64>().ok())")?;
+    writeln!(output, "        .unwrap_or({});", config.ack_wait_secs)?;
+    writeln!(output, "    // Deliveries in flight before the server stops handing out more")?;
+    writeln!(output, "    let max_ack_pending = std::env::var(\"MAX_ACK_PENDING\")")?;
+    writeln!(output, "        .ok()")?;
+    writeln!(output, "        .and_then(|s| s.parse::<i64>().ok())")?;
+    writeln!(output, "        .unwrap_or({});", config.max_ack_pending)?;
     writeln!(output, "    let poll_interval_ms = std::env::var(\"POLL_INTERVAL_MS\")")?;
     writeln!(output, "        .ok()")?;
     writeln!(output, "        .and_then(|s| s.parse::<u64>().ok())")?;
     writeln!(output, "        .unwrap_or(100);\n")?;
 
     writeln!(output, "    // Create database pool")?;
-    writeln!(output, "    eprintln!(\"[WORKER] Creating database pool...\");")?;
+    writeln!(output, "    tracing::debug!(\"Creating database pool\");")?;
     writeln!(output, "    let db_pool = create_pool()")?;
     writeln!(output, "        .expect(\"Failed to create database pool\");")?;
-    writeln!(output, "    eprintln!(\"[WORKER] Database pool created\");\n")?;
+    writeln!(output, "    tracing::info!(\"Database pool created\");\n")?;
 
     writeln!(output, "    // Ensure tables exist")?;
-    writeln!(output, "    eprintln!(\"[WORKER] Ensuring database tables exist...\");")?;
+    writeln!(output, "    tracing::debug!(\"Ensuring database tables exist\");")?;
     writeln!(output, "    {{")?;
     writeln!(output, "        let mut conn = db_pool.get()")?;
     writeln!(output, "            .expect(\"Failed to get database connection\");")?;
     writeln!(output, "        ensure_tables(&mut conn)")?;
     writeln!(output, "            .expect(\"Failed to ensure tables exist\");")?;
     writeln!(output, "    }}")?;
-    writeln!(output, "    eprintln!(\"[WORKER] Database tables ready\");\n")?;
+    writeln!(output, "    tracing::info!(\"Database tables ready\");\n")?;
 
     writeln!(output, "    // Connect to NATS")?;
-    writeln!(output, "    eprintln!(\"[WORKER] Connecting to NATS at {{}}...\", nats_url);")?;
-    writeln!(output, "    let client = async_nats::connect(&nats_url).await")?;
+    writeln!(output, "    tracing::debug!(nats_url = %nats_url, \"Connecting to NATS\");")?;
+    writeln!(output, "    let client = nats_connect_options().await")?;
+    writeln!(output, "        .expect(\"Invalid NATS auth/TLS configuration\")")?;
+    writeln!(output, "        .connect(&nats_url).await")?;
     writeln!(output, "        .expect(\"Failed to connect to NATS\");")?;
-    writeln!(output, "    eprintln!(\"[WORKER] Connected to NATS\");")?;
-    writeln!(output, "    tracing::info!(\"Connected to NATS at {{}}\", nats_url);\n")?;
+    writeln!(output, "    tracing::info!(nats_url = %nats_url, \"Connected to NATS\");\n")?;
 
     writeln!(output, "    // Get JetStream context")?;
-    writeln!(output, "    eprintln!(\"[WORKER] Creating JetStream context...\");")?;
     writeln!(output, "    let jetstream = jetstream::new(client);")?;
-    writeln!(output, "    eprintln!(\"[WORKER] JetStream context created\");\n")?;
+    writeln!(output, "    tracing::debug!(\"JetStream context created\");\n")?;
 
     writeln!(output, "    // Get or create stream")?;
-    writeln!(output, "    eprintln!(\"[WORKER] Getting/creating stream '{{}}'...\", stream_name);")?;
+    writeln!(output, "    tracing::debug!(stream = %stream_name, \"Getting/creating stream\");")?;
     writeln!(output, "    let stream = jetstream")?;
     writeln!(output, "        .get_or_create_stream(jetstream::stream::Config {{")?;
     writeln!(output, "            name: stream_name.clone(),")?;
-    writeln!(output, "            subjects: vec![\"messages.ingest.>\".to_string()],")?;
+    writeln!(output, "            subjects: stream_subjects.clone(),")?;
     writeln!(output, "            max_age: Duration::from_secs(24 * 60 * 60),")?;
     writeln!(output, "            max_bytes: 512 * 1024 * 1024,")?;
     writeln!(output, "            storage: jetstream::stream::StorageType::File,")?;
@@ -180,47 +324,85 @@ pub fn generate_main_rs(
     writeln!(output, "        }})")?;
     writeln!(output, "        .await")?;
     writeln!(output, "        .expect(\"Failed to get/create stream\");")?;
-    writeln!(output, "    eprintln!(\"[WORKER] Stream '{{}}' ready\", stream_name);\n")?;
+    writeln!(output, "    tracing::info!(stream = %stream_name, \"Stream ready\");\n")?;
+
+    if config.side_output {
+        writeln!(output, "    // Open the side output sink (SIDE_OUTPUT)")?;
+        writeln!(output, "    side_output::init(&jetstream).await")?;
+        writeln!(output, "        .expect(\"Failed to open side output\");\n")?;
+    }
 
     writeln!(output, "    // Create ENTITIES stream for entity publishing")?;
-    writeln!(output, "    eprintln!(\"[WORKER] Getting/creating ENTITIES stream...\");")?;
-    writeln!(output, "    let _entities_stream = jetstream")?;
-    writeln!(output, "        .get_or_create_stream(jetstream::stream::Config {{")?;
-    writeln!(output, "            name: \"ENTITIES\".to_string(),")?;
-    writeln!(output, "            subjects: vec![\"entities.*\".to_string()],")?;
-    writeln!(output, "            max_age: Duration::from_secs(24 * 60 * 60),")?;
-    writeln!(output, "            max_bytes: 512 * 1024 * 1024,")?;
-    writeln!(output, "            storage: jetstream::stream::StorageType::File,")?;
-    writeln!(output, "            num_replicas: 1,")?;
-    writeln!(output, "            ..Default::default()")?;
-    writeln!(output, "        }})")?;
-    writeln!(output, "        .await")?;
-    writeln!(output, "        .expect(\"Failed to get/create ENTITIES stream\");")?;
-    writeln!(output, "    eprintln!(\"[WORKER] ENTITIES stream ready\");")?;
-    writeln!(output, "    tracing::info!(\"ENTITIES stream ready for entity publishing\");\n")?;
-
-    writeln!(output, "    // Create or get consumer")?;
-    writeln!(output, "    eprintln!(\"[WORKER] Getting/creating consumer '{{}}'...\", consumer_name);")?;
-    writeln!(output, "    let consumer = stream")?;
-    writeln!(output, "        .get_or_create_consumer(")?;
-    writeln!(output, "            &consumer_name,")?;
-    writeln!(output, "            jetstream::consumer::pull::Config {{")?;
-    writeln!(output, "                durable_name: Some(consumer_name.clone()),")?;
-    writeln!(output, "                ack_policy: jetstream::consumer::AckPolicy::Explicit,")?;
-    writeln!(output, "                max_deliver,")?;
-    writeln!(output, "                filter_subject: \"messages.ingest.>\".to_string(),")?;
+    writeln!(output, "    if crate::persist_publish::entity_publishing() != crate::persist_publish::EntityPublishing::None {{")?;
+    writeln!(output, "        tracing::debug!(stream = \"ENTITIES\", \"Getting/creating stream\");")?;
+    writeln!(output, "        let _entities_stream = jetstream")?;
+    writeln!(output, "            .get_or_create_stream(jetstream::stream::Config {{")?;
+    writeln!(output, "                name: \"ENTITIES\".to_string(),")?;
+    let entity_subjects: Vec<String> = subjects::stream_subjects(entities)?.iter()
+        .map(|subject| format!("\"{}\".to_string()", subject))
+        .collect();
+    writeln!(output, "                subjects: vec![{}],", entity_subjects.join(", "))?;
+    writeln!(output, "                max_age: Duration::from_secs(24 * 60 * 60),")?;
+    writeln!(output, "                max_bytes: 512 * 1024 * 1024,")?;
+    writeln!(output, "                storage: jetstream::stream::StorageType::File,")?;
+    writeln!(output, "                num_replicas: 1,")?;
     writeln!(output, "                ..Default::default()")?;
+    writeln!(output, "            }})")?;
+    writeln!(output, "            .await")?;
+    writeln!(output, "            .expect(\"Failed to get/create ENTITIES stream\");")?;
+    writeln!(output, "        tracing::info!(stream = \"ENTITIES\", \"Stream ready for entity publishing\");")?;
+    writeln!(output, "    }}\n")?;
+
+    writeln!(output, "    // Create or get consumer; one filter uses filter_subject, several need")?;
+    writeln!(output, "    // filter_subjects (NATS 2.10+)")?;
+    writeln!(output, "    tracing::debug!(consumer = %consumer_name, filter_subjects = ?filter_subjects, \"Getting/creating consumer\");")?;
+    writeln!(output, "    let (filter_subject, filter_subjects) = match filter_subjects.len() {{")?;
+    writeln!(output, "        1 => (filter_subjects[0].clone(), Vec::new()),")?;
+    writeln!(output, "        _ => (String::new(), filter_subjects),")?;
+    writeln!(output, "    }};")?;
+    writeln!(output, "    let consumer_config = jetstream::consumer::pull::Config {{")?;
+    writeln!(output, "        ack_policy: jetstream::consumer::AckPolicy::Explicit,")?;
+    writeln!(output, "        max_deliver,")?;
+    writeln!(output, "        ack_wait: Duration::from_secs(ack_wait_secs),")?;
+    writeln!(output, "        max_ack_pending,")?;
+    writeln!(output, "        filter_subject,")?;
+    writeln!(output, "        filter_subjects,")?;
+    writeln!(output, "        ..Default::default()")?;
+    writeln!(output, "    }};")?;
+    writeln!(output, "    let consumer = match consumer_durability.as_str() {{")?;
+    writeln!(output, "        \"ephemeral\" => {{")?;
+    writeln!(output, "            let consumer_config = jetstream::consumer::pull::Config {{")?;
+    writeln!(output, "                inactive_threshold: Duration::from_secs(consumer_inactive_secs),")?;
+    writeln!(output, "                ..consumer_config")?;
+    writeln!(output, "            }};")?;
+    writeln!(output, "            if queue_group.is_empty() {{")?;
+    writeln!(output, "                // Unnamed: this pod's own consumer, every message delivered here")?;
+    writeln!(output, "                stream.create_consumer(consumer_config).await")?;
+    writeln!(output, "            }} else {{")?;
+    writeln!(output, "                // Named but not durable: shared by the group's pods while any is pulling")?;
+    writeln!(output, "                stream.get_or_create_consumer(&queue_group, jetstream::consumer::pull::Config {{")?;
+    writeln!(output, "                    name: Some(queue_group.clone()),")?;
+    writeln!(output, "                    ..consumer_config")?;
+    writeln!(output, "                }}).await")?;
     writeln!(output, "            }}")?;
-    writeln!(output, "        )")?;
-    writeln!(output, "        .await")?;
-    writeln!(output, "        .expect(\"Failed to create consumer\");")?;
-    writeln!(output, "    eprintln!(\"[WORKER] Consumer '{{}}' ready\", consumer_name);\n")?;
-
-    writeln!(output, "    eprintln!(\"[WORKER] ========== WORKER READY ==========\");")?;
+    writeln!(output, "        }}")?;
+    writeln!(output, "        other => {{")?;
+    writeln!(output, "            if other != \"durable\" {{")?;
+    writeln!(output, "                tracing::warn!(consumer_durability = other, \"Unknown NATS_CONSUMER_DURABILITY, using durable\");")?;
+    writeln!(output, "            }}")?;
+    writeln!(output, "            stream.get_or_create_consumer(&consumer_name, jetstream::consumer::pull::Config {{")?;
+    writeln!(output, "                durable_name: Some(consumer_name.clone()),")?;
+    writeln!(output, "                ..consumer_config")?;
+    writeln!(output, "            }}).await")?;
+    writeln!(output, "        }}")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "    .expect(\"Failed to create consumer\");")?;
     writeln!(output, "    tracing::info!(")?;
-    writeln!(output, "        \"Worker ready - consuming from stream '{{}}' with consumer '{{}}'\",")?;
-    writeln!(output, "        stream_name,")?;
-    writeln!(output, "        consumer_name")?;
+    writeln!(output, "        stream = %stream_name,")?;
+    writeln!(output, "        consumer = %consumer_name,")?;
+    writeln!(output, "        consumer_durability = %consumer_durability,")?;
+    writeln!(output, "        queue_group = %queue_group,")?;
+    writeln!(output, "        \"Worker ready\"")?;
     writeln!(output, "    );\n")?;
 
     // Count persistent entities for logging
@@ -228,27 +410,40 @@ pub fn generate_main_rs(
         .filter(|e| e.is_persistent(entities) && !e.is_abstract && e.source_type.to_lowercase() != "reference")
         .count();
     writeln!(output, "    tracing::info!(\"Processing messages for {} entities\");", entity_count)?;
-    writeln!(output, "    tracing::info!(\"Worker configuration: batch_size={{}}, poll_interval_ms={{}}, max_deliver={{}}\", batch_size, poll_interval_ms, max_deliver);")?;
+    writeln!(output, "    tracing::info!(\"Worker configuration: batch_size={{}}, poll_interval_ms={{}}, max_deliver={{}}, ack_wait_secs={{}}, max_ack_pending={{}}\", batch_size, poll_interval_ms, max_deliver, ack_wait_secs, max_ack_pending);")?;
+    writeln!(output, "    if (batch_size as i64) > max_ack_pending {{")?;
+    writeln!(output, "        tracing::warn!(batch_size, max_ack_pending, \"BATCH_SIZE exceeds MAX_ACK_PENDING; batches will be cut short\");")?;
+    writeln!(output, "    }}")?;
     writeln!(output)?;
 
     writeln!(output, "    // Main message processing loop")?;
     writeln!(output, "    let mut iteration = 0u64;")?;
     writeln!(output, "    loop {{")?;
     writeln!(output, "        iteration += 1;")?;
-    writeln!(output, "        tracing::debug!(\"Loop iteration {{}}: Fetching batch of up to {{}} messages...\", iteration, batch_size);")?;
+    if config.verbose {
+        writeln!(output, "        tracing::trace!(\"Loop iteration {{}}: Fetching batch of up to {{}} messages...\", iteration, batch_size);")?;
+    }
     writeln!(output)?;
     writeln!(output, "        // Fetch batch of messages")?;
-    writeln!(output, "        let mut messages = consumer")?;
+    writeln!(output, "        // A failed fetch (e.g. NATS reconnecting) is retried, not fatal")?;
+    writeln!(output, "        let mut messages = match consumer")?;
     writeln!(output, "            .fetch()")?;
     writeln!(output, "            .max_messages(batch_size)")?;
     writeln!(output, "            .messages()")?;
     writeln!(output, "            .await")?;
-    writeln!(output, "            .expect(\"Failed to fetch messages\");\n")?;
+    writeln!(output, "        {{")?;
+    writeln!(output, "            Ok(messages) => messages,")?;
+    writeln!(output, "            Err(e) => {{")?;
+    writeln!(output, "                tracing::error!(\"Failed to fetch messages: {{}}\", e);")?;
+    writeln!(output, "                tokio::time::sleep(Duration::from_millis(poll_interval_ms)).await;")?;
+    writeln!(output, "                continue;")?;
+    writeln!(output, "            }}")?;
+    writeln!(output, "        }};\n")?;
     writeln!(output, "        let mut msg_count = 0;")?;
 
     writeln!(output, "        while let Some(msg) = messages.next().await {{")?;
     writeln!(output, "            msg_count += 1;")?;
-    writeln!(output, "            tracing::info!(\"[Iteration {{}}] Processing message {{}} in batch\", iteration, msg_count);")?;
+    writeln!(output, "            tracing::debug!(\"[Iteration {{}}] Processing message {{}} in batch\", iteration, msg_count);")?;
     writeln!(output, "            let msg = match msg {{")?;
     writeln!(output, "                Ok(m) => m,")?;
     writeln!(output, "                Err(e) => {{")?;
@@ -258,8 +453,24 @@ pub fn generate_main_rs(
     writeln!(output, "            }};\n")?;
 
     writeln!(output, "            // Process message")?;
-    writeln!(output, "            match process_message(&msg.payload, &db_pool, &jetstream).await {{")?;
-    writeln!(output, "                Ok(_) => {{")?;
+    writeln!(output, "            match process_message(&msg.payload, message_content_type(&msg), &db_pool, &jetstream).await {{")?;
+    writeln!(output, "                Ok(failures) => {{")?;
+    writeln!(output, "                    // Entities dropped under ON_ENTITY_ERROR=dlq: dead-letter the message")?;
+    writeln!(output, "                    // once per failed entity, the rest is already stored")?;
+    writeln!(output, "                    for failure in failures {{")?;
+    writeln!(output, "                        let entity = failure.entity().unwrap_or(\"unknown\");")?;
+    writeln!(output, "                        let mut dlq_headers = error::dlq_headers(&failure);")?;
+    writeln!(output, "                        if let Some(content_type) = message_content_type(&msg) {{")?;
+    writeln!(output, "                            dlq_headers.insert(\"Content-Type\", content_type);")?;
+    writeln!(output, "                        }}")?;
+    writeln!(output, "                        let dlq_subject = format!(\"messages.dlq.{{}}\", entity);")?;
+    writeln!(output, "                        if let Err(dlq_err) = jetstream")?;
+    writeln!(output, "                            .publish_with_headers(dlq_subject, dlq_headers, msg.payload.clone())")?;
+    writeln!(output, "                            .await")?;
+    writeln!(output, "                        {{")?;
+    writeln!(output, "                            tracing::error!(entity, \"Failed to publish entity failure to DLQ: {{}}\", dlq_err);")?;
+    writeln!(output, "                        }}")?;
+    writeln!(output, "                    }}\n")?;
     writeln!(output, "                    // Acknowledge successful processing")?;
     writeln!(output, "                    if let Err(e) = msg.ack().await {{")?;
     writeln!(output, "                        tracing::error!(\"Failed to ACK message: {{}}\", e);")?;
@@ -274,75 +485,78 @@ pub fn generate_main_rs(
     writeln!(output, "                        .unwrap_or(1);\n")?;
 
     writeln!(output, "                    // Extract message info for status updates and DLQ routing")?;
-    writeln!(output, "                    if let Ok(envelope) = serde_json::from_slice::<serde_json::Value>(&msg.payload) {{")?;
-    writeln!(output, "                        if let Some(msg_id) = envelope.get(\"message_id\").and_then(|v| v.as_str()) {{")?;
-    writeln!(output, "                            if let Ok(uuid) = uuid::Uuid::parse_str(msg_id) {{")?;
-    writeln!(output, "                                if delivery_count >= max_deliver {{")?;
-    writeln!(output, "                                    // Max retries reached - route to DLQ")?;
-    writeln!(output, "                                    tracing::warn!(")?;
-    writeln!(output, "                                        \"Message {{}} failed after {{}} attempts, sending to DLQ\",")?;
-    writeln!(output, "                                        msg_id,")?;
-    writeln!(output, "                                        delivery_count")?;
-    writeln!(output, "                                    );\n")?;
-
-    writeln!(output, "                                    // Extract entity_type for DLQ subject")?;
-    writeln!(output, "                                    let entity_type = envelope.get(\"entity_type\")")?;
-    writeln!(output, "                                        .and_then(|v| v.as_str())")?;
-    writeln!(output, "                                        .unwrap_or(\"unknown\");\n")?;
-
-    writeln!(output, "                                    // Publish to DLQ stream")?;
-    writeln!(output, "                                    let dlq_subject = format!(\"messages.dlq.{{}}\", entity_type);")?;
-    writeln!(output, "                                    if let Err(dlq_err) = jetstream")?;
-    writeln!(output, "                                        .publish(dlq_subject.clone(), msg.payload.clone())")?;
-    writeln!(output, "                                        .await")?;
-    writeln!(output, "                                    {{")?;
-    writeln!(output, "                                        tracing::error!(\"Failed to publish to DLQ: {{}}\", dlq_err);")?;
-    writeln!(output, "                                    }} else {{")?;
-    writeln!(output, "                                        tracing::info!(\"Message {{}} routed to DLQ\", msg_id);")?;
-    writeln!(output, "                                    }}\n")?;
-
-    writeln!(output, "                                    // Update status to 'dlq'")?;
-    writeln!(output, "                                    if let Ok(mut conn) = db_pool.get() {{")?;
-    writeln!(output, "                                        diesel::sql_query(")?;
-    writeln!(output, "                                            \"UPDATE message_status SET status = ?, error_message = ? WHERE message_id = ?\"")?;
-    writeln!(output, "                                        )")?;
-    writeln!(output, "                                        .bind::<Text, _>(\"dlq\")")?;
-    writeln!(output, "                                        .bind::<Text, _>(&format!(\"Failed after {{}} attempts: {{:?}}\", delivery_count, e))")?;
-    writeln!(output, "                                        .bind::<Text, _>(uuid.to_string())")?;
-    writeln!(output, "                                        .execute(&mut conn)")?;
-    writeln!(output, "                                        .map_err(|e| {{")?;
-    writeln!(output, "                                            eprintln!(\"[WORKER] Failed to update message_status to dlq: {{:?}}\", e);")?;
-    writeln!(output, "                                            e")?;
-    writeln!(output, "                                        }})")?;
-    writeln!(output, "                                        .ok();")?;
-    writeln!(output, "                                    }}\n")?;
-
-    writeln!(output, "                                    // ACK the original message (remove from main queue)")?;
-    writeln!(output, "                                    if let Err(ack_err) = msg.ack().await {{")?;
-    writeln!(output, "                                        tracing::error!(\"Failed to ACK DLQ message: {{}}\", ack_err);")?;
-    writeln!(output, "                                    }}")?;
-    writeln!(output, "                                }} else {{")?;
-    writeln!(output, "                                    // Still have retries left - update status and NAK")?;
-    writeln!(output, "                                    if let Ok(mut conn) = db_pool.get() {{")?;
-    writeln!(output, "                                        diesel::sql_query(")?;
-    writeln!(output, "                                            \"UPDATE message_status SET status = ?, error_message = ?, retry_count = retry_count + 1 WHERE message_id = ?\"")?;
-    writeln!(output, "                                        )")?;
-    writeln!(output, "                                        .bind::<Text, _>(\"failed\")")?;
-    writeln!(output, "                                        .bind::<Text, _>(&format!(\"{{:?}}\", e))")?;
-    writeln!(output, "                                        .bind::<Text, _>(uuid.to_string())")?;
-    writeln!(output, "                                        .execute(&mut conn)")?;
-    writeln!(output, "                                        .map_err(|e| {{")?;
-    writeln!(output, "                                            eprintln!(\"[WORKER] Failed to update message_status retry: {{:?}}\", e);")?;
-    writeln!(output, "                                            e")?;
-    writeln!(output, "                                        }})")?;
-    writeln!(output, "                                        .ok();")?;
-    writeln!(output, "                                    }}\n")?;
-
-    writeln!(output, "                                    // NAK for retry")?;
-    writeln!(output, "                                    if let Err(nak_err) = msg.ack_with(jetstream::AckKind::Nak(None)).await {{")?;
-    writeln!(output, "                                        tracing::error!(\"Failed to NAK message: {{}}\", nak_err);")?;
-    writeln!(output, "                                    }}")?;
-    writeln!(output, "                                }}")?;
+    writeln!(output, "                    if let Ok(envelope) = decode_envelope(&msg.payload, message_content_type(&msg)) {{")?;
+    writeln!(output, "                        let uuid = envelope.message_id;")?;
+    writeln!(output, "                        let msg_id = uuid.to_string();")?;
+    writeln!(output, "                        // Parse, validation, integrity and envelope failures will never succeed on retry")?;
+    writeln!(output, "                        let permanent = e.is_permanent();")?;
+    writeln!(output, "                        if delivery_count >= max_deliver || permanent {{")?;
+    writeln!(output, "                            // Max retries reached (or permanent failure) - route to DLQ")?;
+    writeln!(output, "                            tracing::warn!(")?;
+    writeln!(output, "                                \"Message {{}} failed after {{}} attempts, sending to DLQ\",")?;
+    writeln!(output, "                                msg_id,")?;
+    writeln!(output, "                                delivery_count")?;
+    writeln!(output, "                            );\n")?;
+
+    writeln!(output, "                            // Extract entity_type for DLQ subject")?;
+    writeln!(output, "                            let entity_type = envelope.entity_type.as_deref().unwrap_or(\"unknown\");\n")?;
+
+    writeln!(output, "                            // Publish to DLQ stream, with the failure in headers so the")?;
+    writeln!(output, "                            // payload stays the original message")?;
+    writeln!(output, "                            let dlq_subject = format!(\"messages.dlq.{{}}\", entity_type);")?;
+    writeln!(output, "                            let mut dlq_headers = error::dlq_headers(&e);")?;
+    writeln!(output, "                            if let Some(content_type) = message_content_type(&msg) {{")?;
+    writeln!(output, "                                dlq_headers.insert(\"Content-Type\", content_type);")?;
+    writeln!(output, "                            }}")?;
+    writeln!(output, "                            if let Err(dlq_err) = jetstream")?;
+    writeln!(output, "                                .publish_with_headers(dlq_subject.clone(), dlq_headers, msg.payload.clone())")?;
+    writeln!(output, "                                .await")?;
+    writeln!(output, "                            {{")?;
+    writeln!(output, "                                tracing::error!(\"Failed to publish to DLQ: {{}}\", dlq_err);")?;
+    writeln!(output, "                            }} else {{")?;
+    writeln!(output, "                                tracing::info!(\"Message {{}} routed to DLQ\", msg_id);")?;
+    writeln!(output, "                            }}\n")?;
+
+    writeln!(output, "                            // Update status to 'dlq'")?;
+    writeln!(output, "                            if let Ok(mut conn) = db_pool.get() {{")?;
+    writeln!(output, "                                diesel::sql_query(")?;
+    writeln!(output, "                                    \"UPDATE message_status SET status = ?, error_message = ? WHERE message_id = ?\"")?;
+    writeln!(output, "                                )")?;
+    writeln!(output, "                                .bind::<Text, _>(\"dlq\")")?;
+    writeln!(output, "                                .bind::<Text, _>(&format!(\"Failed after {{}} attempts: {{:?}}\", delivery_count, e))")?;
+    writeln!(output, "                                .bind::<Text, _>(uuid.to_string())")?;
+    writeln!(output, "                                .execute(&mut conn)")?;
+    writeln!(output, "                                .map_err(|e| {{")?;
+    writeln!(output, "                                    tracing::warn!(message_id = %uuid, error = ?e, \"Failed to update message_status to dlq\");")?;
+    writeln!(output, "                                    e")?;
+    writeln!(output, "                                }})")?;
+    writeln!(output, "                                .ok();")?;
+    writeln!(output, "                            }}\n")?;
+
+    writeln!(output, "                            // ACK the original message (remove from main queue)")?;
+    writeln!(output, "                            if let Err(ack_err) = msg.ack().await {{")?;
+    writeln!(output, "                                tracing::error!(\"Failed to ACK DLQ message: {{}}\", ack_err);")?;
+    writeln!(output, "                            }}")?;
+    writeln!(output, "                        }} else {{")?;
+    writeln!(output, "                            // Still have retries left - update status and NAK")?;
+    writeln!(output, "                            if let Ok(mut conn) = db_pool.get() {{")?;
+    writeln!(output, "                                diesel::sql_query(")?;
+    writeln!(output, "                                    \"UPDATE message_status SET status = ?, error_message = ?, retry_count = retry_count + 1 WHERE message_id = ?\"")?;
+    writeln!(output, "                                )")?;
+    writeln!(output, "                                .bind::<Text, _>(\"failed\")")?;
+    writeln!(output, "                                .bind::<Text, _>(&format!(\"{{:?}}\", e))")?;
+    writeln!(output, "                                .bind::<Text, _>(uuid.to_string())")?;
+    writeln!(output, "                                .execute(&mut conn)")?;
+    writeln!(output, "                                .map_err(|e| {{")?;
+    writeln!(output, "                                    tracing::warn!(message_id = %uuid, error = ?e, \"Failed to update message_status retry\");")?;
+    writeln!(output, "                                    e")?;
+    writeln!(output, "                                }})")?;
+    writeln!(output, "                                .ok();")?;
+    writeln!(output, "                            }}\n")?;
+
+    writeln!(output, "                            // NAK for retry")?;
+    writeln!(output, "                            if let Err(nak_err) = msg.ack_with(jetstream::AckKind::Nak(None)).await {{")?;
+    writeln!(output, "                                tracing::error!(\"Failed to NAK message: {{}}\", nak_err);")?;
     writeln!(output, "                            }}")?;
     writeln!(output, "                        }}")?;
     writeln!(output, "                    }}")?;
@@ -352,8 +566,10 @@ pub fn generate_main_rs(
     writeln!(output)?;
     writeln!(output, "        if msg_count > 0 {{")?;
     writeln!(output, "            tracing::info!(\"[Iteration {{}}] Processed {{}} messages in batch\", iteration, msg_count);")?;
-    writeln!(output, "        }} else {{")?;
-    writeln!(output, "            tracing::debug!(\"[Iteration {{}}] No messages in batch, sleeping for {{}}ms\", iteration, poll_interval_ms);")?;
+    if config.verbose {
+        writeln!(output, "        }} else {{")?;
+        writeln!(output, "            tracing::trace!(\"[Iteration {{}}] No messages in batch, sleeping for {{}}ms\", iteration, poll_interval_ms);")?;
+    }
     writeln!(output, "        }}")?;
     writeln!(output)?;
     writeln!(output, "        // Small delay between batches")?;
@@ -364,26 +580,43 @@ pub fn generate_main_rs(
     writeln!(output, "/// Process a single message")?;
     writeln!(output, "async fn process_message(")?;
     writeln!(output, "    payload: &[u8],")?;
+    writeln!(output, "    content_type: Option<&str>,")?;
     writeln!(output, "    pool: &database::DbPool,")?;
     writeln!(output, "    jetstream: &jetstream::Context,")?;
-    writeln!(output, ") -> Result<(), AppError> {{")?;
-    writeln!(output, "    tracing::info!(\"========== PROCESSING MESSAGE ==========\");")?;
-    writeln!(output, "    eprintln!(\"[WORKER] Received message ({{}} bytes)\", payload.len());\n")?;
+    writeln!(output, ") -> Result<Vec<AppError>, AppError> {{")?;
+    writeln!(output, "    tracing::debug!(bytes = payload.len(), \"Received message\");\n")?;
 
-    writeln!(output, "    // Deserialize envelope")?;
-    writeln!(output, "    let envelope: MessageEnvelope = serde_json::from_slice(payload)")?;
+    writeln!(output, "    // Deserialize envelope (migrating older schema versions first)")?;
+    writeln!(output, "    let envelope = decode_envelope(payload, content_type)")?;
     writeln!(output, "        .map_err(|e| {{")?;
-    writeln!(output, "            eprintln!(\"[WORKER] Envelope deserialization error: {{}}\", e);")?;
-    writeln!(output, "            eprintln!(\"[WORKER] Raw payload: {{}}\", String::from_utf8_lossy(payload));")?;
-    writeln!(output, "            AppError::ValidationError(format!(\"Invalid envelope: {{}}\", e))")?;
+    writeln!(output, "            tracing::error!(error = %e, payload = %String::from_utf8_lossy(payload), \"Envelope deserialization error\");")?;
+    writeln!(output, "            AppError::Envelope {{ message: e }}")?;
     writeln!(output, "        }})?;\n")?;
 
-    writeln!(output, "    let message_id = envelope.message_id;")?;
-    writeln!(output, "    eprintln!(\"[WORKER] Processing message {{}}\", message_id);")?;
-    writeln!(output, "    if let Some(ref et) = envelope.entity_type {{")?;
-    writeln!(output, "        eprintln!(\"[WORKER] Entity type: {{}}\", et);")?;
+    writeln!(output, "    // Continue the ingestion server's trace (when propagated) around parse/persist")?;
+    writeln!(output, "    let span = tracing::info_span!(")?;
+    writeln!(output, "        \"process_message\",")?;
+    writeln!(output, "        message_id = %envelope.message_id,")?;
+    writeln!(output, "        correlation_id = envelope.correlation_id.as_deref().unwrap_or(\"\"),")?;
+    writeln!(output, "        entity_type = envelope.entity_type.as_deref().unwrap_or(\"unknown\"),")?;
+    writeln!(output, "    );")?;
+    writeln!(output, "    if let Some(ref trace_context) = envelope.trace_context {{")?;
+    writeln!(output, "        telemetry::set_parent(&span, trace_context);")?;
     writeln!(output, "    }}")?;
-    writeln!(output, "    tracing::debug!(\"Processing message {{}}\", message_id);\n")?;
+    writeln!(output, "    process_envelope(envelope, pool, jetstream).instrument(span).await")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// Parse and persist a decoded envelope (runs inside the message span)")?;
+    writeln!(output, "///")?;
+    writeln!(output, "/// Returns the entities dropped under `ON_ENTITY_ERROR=dlq`.")?;
+    writeln!(output, "async fn process_envelope(")?;
+    writeln!(output, "    envelope: MessageEnvelope,")?;
+    writeln!(output, "    pool: &database::DbPool,")?;
+    writeln!(output, "    jetstream: &jetstream::Context,")?;
+    writeln!(output, ") -> Result<Vec<AppError>, AppError> {{")?;
+    writeln!(output, "    let message_id = envelope.message_id;")?;
+    writeln!(output, "    let entity_type = envelope.entity_type.as_deref().unwrap_or(\"unknown\");")?;
+    writeln!(output, "    tracing::info!(message_id = %message_id, entity_type, \"Processing message\");\n")?;
 
     writeln!(output, "    // Get database connection")?;
     writeln!(output, "    let mut conn = pool.get()?;\n")?;
@@ -396,49 +629,88 @@ pub fn generate_main_rs(
     writeln!(output, "    .bind::<Text, _>(message_id.to_string())")?;
     writeln!(output, "    .execute(&mut conn)")?;
     writeln!(output, "    .map_err(|e| {{")?;
-    writeln!(output, "        eprintln!(\"[WORKER] Failed to update message_status to processing: {{:?}}\", e);")?;
+    writeln!(output, "        tracing::warn!(message_id = %message_id, error = ?e, \"Failed to update message_status to processing\");")?;
     writeln!(output, "        e")?;
     writeln!(output, "    }})")?;
     writeln!(output, "    .ok(); // Ignore errors - status tracking is optional\n")?;
 
     writeln!(output, "    // Parse message body using entity-specific parsers")?;
     writeln!(output, "    // Use entity_type hint from envelope if available")?;
-    writeln!(output, "    eprintln!(\"[WORKER] Parsing message body...\");")?;
-    writeln!(output, "    let (entity_name, parsed, raw_json) = MessageParser::parse_json(&envelope.body, envelope.entity_type.as_deref())")?;
+    writeln!(output, "    tracing::debug!(message_id = %message_id, entity_type, \"Parsing message body\");")?;
+    writeln!(output, "    let body = decode_body(&envelope).map_err(|e| {{")?;
+    writeln!(output, "        tracing::error!(message_id = %message_id, entity_type, error = %e, \"Failed to decode message body\");")?;
+    writeln!(output, "        AppError::Envelope {{ message: format!(\"undecodable body: {{}}\", e) }}")?;
+    writeln!(output, "    }})?;")?;
+    writeln!(output, "    if verify_body_sha256() {{")?;
+    writeln!(output, "        check_body_sha256(&envelope, &body).map_err(|e| {{")?;
+    writeln!(output, "            tracing::error!(message_id = %message_id, entity_type, error = %e, \"Message body failed its integrity check\");")?;
+    writeln!(output, "            e")?;
+    writeln!(output, "        }})?;")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "    let (entity_name, parsed, raw_json) = MessageParser::parse_json(&body, envelope.entity_type.as_deref())")?;
     writeln!(output, "        .map_err(|e| {{")?;
-    writeln!(output, "            eprintln!(\"[WORKER] Parse error: {{:?}}\", e);")?;
-    writeln!(output, "            // Try to pretty-print the JSON for debugging")?;
-    writeln!(output, "            if let Ok(json_val) = serde_json::from_str::<serde_json::Value>(&envelope.body) {{")?;
-    writeln!(output, "                if let Ok(pretty) = serde_json::to_string_pretty(&json_val) {{")?;
-    writeln!(output, "                    eprintln!(\"[WORKER] Failed to parse entity from JSON:\\n{{}}\", pretty);")?;
-    writeln!(output, "                }}")?;
-    writeln!(output, "            }} else {{")?;
-    writeln!(output, "                eprintln!(\"[WORKER] Raw body (not valid JSON): {{}}\", envelope.body);")?;
-    writeln!(output, "            }}")?;
+    writeln!(output, "            tracing::error!(")?;
+    writeln!(output, "                message_id = %message_id,")?;
+    writeln!(output, "                entity_type,")?;
+    writeln!(output, "                error = ?e,")?;
+    writeln!(output, "                body = %body,")?;
+    writeln!(output, "                \"Failed to parse message body\"")?;
+    writeln!(output, "            );")?;
     writeln!(output, "            e")?;
     writeln!(output, "        }})?;")?;
-    writeln!(output, "    eprintln!(\"[WORKER] Successfully parsed as entity: {{}}\", entity_name);\n")?;
+    writeln!(output, "    tracing::debug!(message_id = %message_id, entity_type = %entity_name, \"Parsed message body\");\n")?;
+
+    writeln!(output, "    // Sampling (SAMPLE_RATES) by parsed root entity type: messages outside the")?;
+    writeln!(output, "    // sample are acked unprocessed")?;
+    writeln!(output, "    if !sampled_in(&message_id, sample_rate(&entity_name)) {{")?;
+    writeln!(output, "        tracing::debug!(message_id = %message_id, entity_type = %entity_name, \"Message sampled out\");")?;
+    writeln!(output, "        diesel::sql_query(\"UPDATE message_status SET status = ? WHERE message_id = ?\")")?;
+    writeln!(output, "            .bind::<Text, _>(\"sampled_out\")")?;
+    writeln!(output, "            .bind::<Text, _>(message_id.to_string())")?;
+    writeln!(output, "            .execute(&mut conn)")?;
+    writeln!(output, "            .ok(); // Ignore errors - status tracking is optional")?;
+    writeln!(output, "        return Ok(Vec::new());")?;
+    writeln!(output, "    }}\n")?;
 
     writeln!(output, "    // Publish entity to its entity-specific NATS stream for testing/observability")?;
-    writeln!(output, "    let entity_stream_subject = format!(\"entities.{{}}\", entity_name);")?;
-    writeln!(output, "    let entity_json = serde_json::to_string(&raw_json)")?;
-    writeln!(output, "        .map_err(|e| {{")?;
-    writeln!(output, "            eprintln!(\"[WORKER] Failed to serialize entity for publishing: {{:?}}\", e);")?;
-    writeln!(output, "            AppError::ValidationError(format!(\"Entity serialization failed: {{}}\", e))")?;
-    writeln!(output, "        }})?;")?;
-    writeln!(output, "    jetstream.publish(entity_stream_subject.clone(), entity_json.clone().into()).await")?;
-    writeln!(output, "        .map_err(|e| {{")?;
-    writeln!(output, "            eprintln!(\"[WORKER] Failed to publish entity to stream {{}}: {{:?}}\", entity_stream_subject, e);")?;
-    writeln!(output, "            AppError::ValidationError(format!(\"Entity publishing failed: {{}}\", e))")?;
-    writeln!(output, "        }})?;")?;
-    writeln!(output, "    eprintln!(\"[WORKER] Published {{}} entity to stream {{}}\", entity_name, entity_stream_subject);\n")?;
+    writeln!(output, "    if crate::persist_publish::publishes_entity(&entity_name) {{")?;
+    writeln!(output, "        let entity_stream_subject = crate::persist_publish::entity_subject(&entity_name, &raw_json);")?;
+    writeln!(output, "        let entity_json = serde_json::to_string(&raw_json)")?;
+    writeln!(output, "            .map_err(|e| {{")?;
+    writeln!(output, "                tracing::error!(message_id = %message_id, entity_type = %entity_name, error = ?e, \"Failed to serialize entity for publishing\");")?;
+    writeln!(output, "                AppError::Publish {{ entity: entity_name.clone(), message: format!(\"serialization failed: {{}}\", e) }}")?;
+    writeln!(output, "            }})?;")?;
+    writeln!(output, "        jetstream.publish(entity_stream_subject.clone(), entity_json.clone().into()).await")?;
+    writeln!(output, "            .map_err(|e| {{")?;
+    writeln!(output, "                tracing::error!(message_id = %message_id, entity_type = %entity_name, subject = %entity_stream_subject, error = ?e, \"Failed to publish entity\");")?;
+    writeln!(output, "                AppError::Publish {{ entity: entity_name.clone(), message: e.to_string() }}")?;
+    writeln!(output, "            }})?;")?;
+    writeln!(output, "        tracing::debug!(message_id = %message_id, entity_type = %entity_name, subject = %entity_stream_subject, \"Published entity\");")?;
+    writeln!(output, "    }}\n")?;
 
     writeln!(output, "    // Process message using dependency-based coordinator")?;
     writeln!(output, "    match parsed {{")?;
     writeln!(output, "        ParsedMessage::Hl7v2MessageFile(ref msg) => {{")?;
-    writeln!(output, "            eprintln!(\"[WORKER] Processing message with coordinator...\");")?;
-    writeln!(output, "            coordinator::process_message(msg, &raw_json, &mut conn, jetstream).await?;")?;
-    writeln!(output, "            eprintln!(\"[WORKER] Successfully processed message\");")?;
+    writeln!(output, "            tracing::debug!(message_id = %message_id, \"Processing message with coordinator\");")?;
+    if config.side_output {
+        writeln!(output, "            let mut extracted = side_output::ExtractedEntities::new();")?;
+        writeln!(output, "            side_output::record(&mut extracted, &entity_name, &raw_json);")?;
+        writeln!(output, "            let failures = coordinator::process_message(msg, &raw_json, &mut conn, jetstream, &mut extracted).await?;")?;
+    } else {
+        writeln!(output, "            let failures = coordinator::process_message(msg, &raw_json, &mut conn, jetstream).await?;")?;
+    }
+    writeln!(output, "            tracing::debug!(message_id = %message_id, \"Coordinator finished\");")?;
+    if config.side_output {
+        writeln!(output)?;
+        writeln!(output, "            // Archive body and entities for replay/audit")?;
+        writeln!(output, "            side_output::write(&side_output::SideOutputRecord {{")?;
+        writeln!(output, "                message_id: message_id.to_string(),")?;
+        writeln!(output, "                entity_type: &entity_name,")?;
+        writeln!(output, "                correlation_id: envelope.correlation_id.as_deref(),")?;
+        writeln!(output, "                raw_body: &body,")?;
+        writeln!(output, "                entities: &extracted,")?;
+        writeln!(output, "            }}).await;")?;
+    }
     writeln!(output)?;
     writeln!(output, "            // Update status to 'processed'")?;
     writeln!(output, "            diesel::sql_query(")?;
@@ -449,8 +721,8 @@ pub fn generate_main_rs(
     writeln!(output, "            .execute(&mut conn)")?;
     writeln!(output, "            .ok(); // Ignore errors - status tracking is optional")?;
     writeln!(output)?;
-    writeln!(output, "            tracing::info!(\"========== MESSAGE PROCESSED SUCCESSFULLY ==========\");")?;
-    writeln!(output, "            Ok(())")?;
+    writeln!(output, "            tracing::info!(message_id = %message_id, \"Message processed\");")?;
+    writeln!(output, "            Ok(failures)")?;
     writeln!(output, "        }}")?;
     writeln!(output, "    }}")?;
     writeln!(output, "}}")?;
@@ -458,12 +730,62 @@ pub fn generate_main_rs(
     Ok(())
 }
 
+/// Generate `sample_rate` and `sampled_in`, the per-entity-type sampling of
+/// `process_message`
+fn generate_sampling_fns(
+    output: &mut std::fs::File,
+    config: &WorkerConfig,
+) -> Result<(), Box<dyn Error>> {
+    let default_rates: Vec<String> = config.sample_rates.iter()
+        .map(|(entity, rate)| format!("{}={}", entity, rate))
+        .collect();
+
+    writeln!(output, "/// Fraction of messages processed for a root entity type, 1.0 unless listed in")?;
+    writeln!(output, "/// `SAMPLE_RATES` (comma-separated `Entity=fraction`, e.g. `Order=0.1`)")?;
+    writeln!(output, "fn sample_rate(entity_type: &str) -> f64 {{")?;
+    writeln!(output, "    static RATES: std::sync::OnceLock<HashMap<String, f64>> = std::sync::OnceLock::new();")?;
+    writeln!(output, "    let rates = RATES.get_or_init(|| {{")?;
+    writeln!(output, "        let spec = std::env::var(\"SAMPLE_RATES\").unwrap_or_else(|_| \"{}\".to_string());", default_rates.join(","))?;
+    writeln!(output, "        spec.split(',')")?;
+    writeln!(output, "            .filter(|pair| !pair.trim().is_empty())")?;
+    writeln!(output, "            .filter_map(|pair| {{")?;
+    writeln!(output, "                let parsed = pair.split_once('=')")?;
+    writeln!(output, "                    .and_then(|(entity, rate)| Some((entity.trim().to_string(), rate.trim().parse::<f64>().ok()?)))")?;
+    writeln!(output, "                    .filter(|(_, rate)| (0.0..=1.0).contains(rate));")?;
+    writeln!(output, "                if parsed.is_none() {{")?;
+    writeln!(output, "                    tracing::warn!(entry = pair, \"Ignoring invalid SAMPLE_RATES entry\");")?;
+    writeln!(output, "                }}")?;
+    writeln!(output, "                parsed")?;
+    writeln!(output, "            }})")?;
+    writeln!(output, "            .collect()")?;
+    writeln!(output, "    }});")?;
+    writeln!(output, "    rates.get(entity_type).copied().unwrap_or(1.0)")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// Whether a message is in the sampled fraction")?;
+    writeln!(output, "///")?;
+    writeln!(output, "/// Decided by a hash of the message id, so a message gets the same answer on")?;
+    writeln!(output, "/// every redelivery and replica.")?;
+    writeln!(output, "fn sampled_in(message_id: &uuid::Uuid, rate: f64) -> bool {{")?;
+    writeln!(output, "    if rate >= 1.0 {{")?;
+    writeln!(output, "        return true;")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "    // FNV-1a: stable across builds, unlike std's DefaultHasher")?;
+    writeln!(output, "    let hash = message_id.as_bytes().iter()")?;
+    writeln!(output, "        .fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3));")?;
+    writeln!(output, "    ((hash % 10_000) as f64) < rate * 10_000.0")?;
+    writeln!(output, "}}\n")?;
+
+    Ok(())
+}
+
 /// Generate derived entity processor functions (legacy - now in entity_processor_rs.rs)
 #[allow(dead_code)]
 pub(super) fn generate_derived_entity_processors_for_single_root(
     output: &mut std::fs::File,
     root_entity: &EntityDef,
     all_entities: &[EntityDef],
+    max_depth: usize,
 ) -> Result<(), Box<dyn Error>> {
     // Find derived persistent entities for this root
     let derived_entities: Vec<&EntityDef> = all_entities.iter()
@@ -510,13 +832,13 @@ pub(super) fn generate_derived_entity_processors_for_single_root(
     // Process each derived transient entity FIRST (publish to NATS only, no database)
     // These must be extracted before persistent entities that depend on them
     for transient_entity in &transient_entities {
-        generate_transient_derived_entity_extraction(output, transient_entity, root_entity, all_entities)?;
+        generate_transient_derived_entity_extraction(output, transient_entity, root_entity, all_entities, max_depth)?;
     }
 
     // Process each derived persistent entity
     // (this also publishes transient intermediate entities to NATS inline)
     for derived_entity in &derived_entities {
-        generate_derived_entity_extraction(output, derived_entity, root_entity, all_entities)?;
+        generate_derived_entity_extraction(output, derived_entity, root_entity, all_entities, max_depth)?;
     }
 
     writeln!(output, "    tracing::info!(\"Processed derived entities for {}\");", root_entity.name)?;
@@ -526,7 +848,15 @@ pub(super) fn generate_derived_entity_processors_for_single_root(
     Ok(())
 }
 
-/// Recursively collect entity dependencies in topological order (dependencies first)
+/// Default limit on how deeply entity dependencies may nest, used unless
+/// `WorkerConfig::max_dependency_depth` sets another
+pub const DEFAULT_MAX_DEPENDENCY_DEPTH: usize = 64;
+
+/// Collect entity dependencies in topological order (dependencies first)
+///
+/// Walks parents, `derivation.source_entities` and field sources with an
+/// explicit stack. Entities in `seen` and the root entity are skipped. Fails
+/// on a dependency cycle or when dependencies nest deeper than `max_depth`.
 #[allow(dead_code)]
 pub(super) fn collect_entity_dependencies(
     entity_name: &str,
@@ -534,77 +864,90 @@ pub(super) fn collect_entity_dependencies(
     root_entity: &EntityDef,
     ordered_list: &mut Vec<String>,
     seen: &mut std::collections::HashSet<String>,
-) {
-    // Normalize entity name to lowercase for comparison
-    let entity_name_lower = entity_name.to_lowercase();
+    max_depth: usize,
+) -> Result<(), String> {
+    // Entity to visit, unless already processed or the root (case-insensitive)
+    let pending = |name: &str, seen: &std::collections::HashSet<String>| {
+        let name_lower = name.to_lowercase();
+        if seen.contains(&name_lower) || name_lower == root_entity.name.to_lowercase() {
+            return None;
+        }
+        all_entities.iter().find(|e| e.name.eq_ignore_ascii_case(name))
+    };
 
-    // Skip if already processed or is the root entity
-    if seen.contains(&entity_name_lower) || entity_name_lower == root_entity.name.to_lowercase() {
-        return;
-    }
+    let Some(start) = pending(entity_name, seen) else {
+        return Ok(());
+    };
 
-    // Find the entity definition (case-insensitive match)
-    if let Some(entity) = all_entities.iter().find(|e| e.name.eq_ignore_ascii_case(entity_name)) {
-        // First, process parent dependencies (for derived entities)
-        if let Some(ref parent_name) = entity.parent {
-            // Only process parent if it's not the root entity
-            let parent_is_root = parent_name.eq_ignore_ascii_case(&root_entity.name);
-            eprintln!("DEBUG: Entity {} has parent {}, is_root={}", entity_name, parent_name, parent_is_root);
-            if !parent_is_root {
-                collect_entity_dependencies(
-                    parent_name,
-                    all_entities,
-                    root_entity,
-                    ordered_list,
-                    seen
-                );
+    // Each frame holds an entity and its dependencies still to visit
+    let mut stack = vec![(start, direct_entity_dependencies(start, root_entity).into_iter())];
+    while let Some((_, dependencies)) = stack.last_mut() {
+        match dependencies.next() {
+            Some(dependency) => {
+                let Some(dependency) = pending(&dependency, seen) else {
+                    continue;
+                };
+                if stack.iter().any(|(e, _)| e.name == dependency.name) {
+                    return Err(format!(
+                        "Entity dependency cycle: {} -> {}",
+                        stack.iter().map(|(e, _)| e.name.as_str()).collect::<Vec<_>>().join(" -> "),
+                        dependency.name
+                    ));
+                }
+                if stack.len() >= max_depth {
+                    return Err(format!(
+                        "Dependencies of entity '{}' nest deeper than {} levels",
+                        entity_name, max_depth
+                    ));
+                }
+                stack.push((dependency, direct_entity_dependencies(dependency, root_entity).into_iter()));
             }
-        }
-
-        // Process derivation.source_entities dependencies
-        if let Some(ref derivation) = entity.derivation {
-            if let Some(ref source_entities) = derivation.source_entities {
-                match source_entities {
-                    serde_yaml::Value::Mapping(map) => {
-                        for (_key, value) in map {
-                            if let serde_yaml::Value::String(source_entity_name) = value {
-                                if !source_entity_name.eq_ignore_ascii_case(&root_entity.name) {
-                                    collect_entity_dependencies(
-                                        source_entity_name,
-                                        all_entities,
-                                        root_entity,
-                                        ordered_list,
-                                        seen
-                                    );
-                                }
-                            }
-                        }
-                    },
-                    _ => {}
+            None => {
+                if let Some((entity, _)) = stack.pop() {
+                    // Add the entity after its dependencies - use actual entity name, track lowercase
+                    seen.insert(entity.name.to_lowercase());
+                    ordered_list.push(entity.name.clone());
                 }
             }
         }
+    }
 
-        // Then, recursively process this entity's field dependencies
-        for field in &entity.fields {
-            if let Some(ref computed_from) = field.computed_from {
-                for source in &computed_from.sources {
-                    let source_entity = source.source_name();
-                    collect_entity_dependencies(
-                        source_entity,
-                        all_entities,
-                        root_entity,
-                        ordered_list,
-                        seen
-                    );
-                }
+    Ok(())
+}
+
+/// Entities `entity` reads from directly: its parent (unless the root),
+/// `derivation.source_entities` values and field sources
+fn direct_entity_dependencies(entity: &EntityDef, root_entity: &EntityDef) -> Vec<String> {
+    let mut dependencies = Vec::new();
+
+    // First, parent dependencies (for derived entities)
+    if let Some(ref parent_name) = entity.parent {
+        let parent_is_root = parent_name.eq_ignore_ascii_case(&root_entity.name);
+        tracing::trace!(entity = %entity.name, parent = %parent_name, parent_is_root, "Collecting entity dependencies");
+        if !parent_is_root {
+            dependencies.push(parent_name.clone());
+        }
+    }
+
+    // Then derivation.source_entities dependencies
+    if let Some(ref derivation) = entity.derivation {
+        for source_entity_name in derivation.source_entities.entity_names() {
+            if !source_entity_name.eq_ignore_ascii_case(&root_entity.name) {
+                dependencies.push(source_entity_name.to_string());
             }
         }
+    }
 
-        // Then add this entity (after its dependencies) - use actual entity name, track lowercase
-        seen.insert(entity_name_lower);
-        ordered_list.push(entity.name.clone());
+    // Then the entity's field dependencies
+    for field in &entity.fields {
+        if let Some(ref computed_from) = field.computed_from {
+            for source in &computed_from.sources {
+                dependencies.push(source.source_name().to_string());
+            }
+        }
     }
+
+    dependencies
 }
 
 /// Validate that at most one parent entity has repetition=repeated
@@ -660,8 +1003,9 @@ pub(super) fn generate_derived_entity_extraction(
     derived_entity: &EntityDef,
     root_entity: &EntityDef,
     all_entities: &[EntityDef],
+    max_depth: usize,
 ) -> Result<(), Box<dyn Error>> {
-    generate_derived_entity_extraction_internal(output, derived_entity, root_entity, all_entities, None)
+    generate_derived_entity_extraction_internal(output, derived_entity, root_entity, all_entities, max_depth, None)
 }
 
 /// Internal implementation with optional repeating context override
@@ -671,6 +1015,7 @@ pub(super) fn generate_derived_entity_extraction_internal(
     derived_entity: &EntityDef,
     root_entity: &EntityDef,
     all_entities: &[EntityDef],
+    max_depth: usize,
     repeating_context_override: Option<(&str, &str, &str)>, // (entity_name, segment_var, each_known_as)
 ) -> Result<(), Box<dyn Error>> {
     use std::collections::{HashMap, HashSet};
@@ -750,18 +1095,14 @@ pub(super) fn generate_derived_entity_extraction_internal(
         // Also check derivation.source_entities for repeated entities with repeated_for
         if repeating_parent_name.is_none() {
             if let Some(ref derivation) = derived_entity.derivation {
-                if let Some(ref source_entities) = derivation.source_entities {
-                    // Check each source entity for repeated_for
-                    for (_key, value) in source_entities.as_mapping().unwrap() {
-                        if let serde_yaml::Value::String(source_entity_name) = value {
-                            if let Some(source_entity) = entities_by_name.get(source_entity_name.as_str()) {
-                                if let Some(ref repeated_for) = source_entity.repeated_for {
-                                    repeating_parent_name = Some(source_entity_name.clone());
-                                    repeating_field_name = Some(repeated_for.field.clone());
-                                    segments_source_entity = Some(repeated_for.entity.clone());
-                                    break;
-                                }
-                            }
+                // Check each source entity for repeated_for
+                for source_entity_name in derivation.source_entities.entity_names() {
+                    if let Some(source_entity) = entities_by_name.get(source_entity_name) {
+                        if let Some(ref repeated_for) = source_entity.repeated_for {
+                            repeating_parent_name = Some(source_entity_name.to_string());
+                            repeating_field_name = Some(repeated_for.field.clone());
+                            segments_source_entity = Some(repeated_for.entity.clone());
+                            break;
                         }
                     }
                 }
@@ -778,14 +1119,17 @@ pub(super) fn generate_derived_entity_extraction_internal(
             repeated_for.each_known_as.clone()
         } else {
             // Fall back to checking the repeating parent entity's repeated_for
-            let parent_name = repeating_parent_name.as_ref().unwrap();
-            if let Some(parent_entity) = entities_by_name.get(parent_name.as_str()) {
-                parent_entity.repeated_for.as_ref()
-                    .expect(&format!("Parent entity {} with repetition: repeated must have repeated_for configuration", parent_name))
-                    .each_known_as.clone()
-            } else {
-                panic!("Repeating parent {} not found in entities", parent_name);
-            }
+            let parent_name = repeating_parent_name.as_deref().unwrap_or_default();
+            let parent_entity = entities_by_name.get(parent_name).ok_or_else(|| format!(
+                "Entity '{}': repeating parent '{}' not found in entities",
+                derived_entity.name, parent_name
+            ))?;
+            parent_entity.repeated_for.as_ref()
+                .ok_or_else(|| format!(
+                    "Entity '{}': parent '{}' has repetition: repeated but no repeated_for configuration",
+                    derived_entity.name, parent_name
+                ))?
+                .each_known_as.clone()
         }
     } else {
         String::new()
@@ -794,30 +1138,15 @@ pub(super) fn generate_derived_entity_extraction_internal(
     // Compute segments variable name if we have a repeating parent
     let segments_var = if has_repeating_parent {
         let source_entity = segments_source_entity.as_ref().unwrap();
-        let source_snake = crate::codegen::utils::to_snake_case(source_entity);
+        let source_snake = to_snake_case(source_entity);
         let field_name = repeating_field_name.as_ref().unwrap();
         Some(format!("{}_{}", source_snake, field_name))
     } else {
         None
     };
 
-    // Build a map of field names to their definitions in the full entity
-    // Include parent entity fields if entity extends another entity
-    let mut field_defs: HashMap<String, &crate::codegen::types::FieldDef> = HashMap::new();
-
-    // First, add parent entity fields (if extends)
-    if let Some(ref parent_name) = derived_entity.extends {
-        if let Some(parent_entity) = all_entities.iter().find(|e| &e.name == parent_name) {
-            for field in &parent_entity.fields {
-                field_defs.insert(field.name.clone(), field);
-            }
-        }
-    }
-
-    // Then, add/override with derived entity's own fields
-    for field in &derived_entity.fields {
-        field_defs.insert(field.name.clone(), field);
-    }
+    // Field definitions of the full entity, inherited ones included (parents first)
+    let field_defs = derived_entity.field_defs(all_entities)?;
 
     // Track which intermediate entities we need to instantiate (with dependencies)
     let mut needed_entities: Vec<String> = Vec::new();
@@ -831,8 +1160,9 @@ pub(super) fn generate_derived_entity_extraction_internal(
                 all_entities,
                 root_entity,
                 &mut needed_entities,
-                &mut seen
-            );
+                &mut seen,
+                max_depth,
+            )?;
         }
     }
 
@@ -845,44 +1175,24 @@ pub(super) fn generate_derived_entity_extraction_internal(
                 all_entities,
                 root_entity,
                 &mut needed_entities,
-                &mut seen
-            );
+                &mut seen,
+                max_depth,
+            )?;
         }
     }
 
-    // Check if entity has derivation.source_entity (for multi-parent entities)
+    // Check if entity has derivation.source_entities (for multi-parent entities)
     if let Some(ref derivation) = derived_entity.derivation {
-        if let Some(ref source_entities) = derivation.source_entities {
-            // Handle source_entity as string, array, or mapping
-            match source_entities {
-                serde_yaml::Value::String(s) => {
-                    if s.as_str() != root_entity.name.as_str() {
-                        collect_entity_dependencies(
-                            s.as_str(),
-                            all_entities,
-                            root_entity,
-                            &mut needed_entities,
-                            &mut seen
-                        );
-                    }
-                },
-                serde_yaml::Value::Mapping(map) => {
-                    // For mappings like {mpi: MPI, event_type: EventType}, collect all values
-                    for (_key, value) in map {
-                        if let serde_yaml::Value::String(entity_name) = value {
-                            if entity_name.as_str() != root_entity.name.as_str() {
-                                collect_entity_dependencies(
-                                    entity_name.as_str(),
-                                    all_entities,
-                                    root_entity,
-                                    &mut needed_entities,
-                                    &mut seen
-                                );
-                            }
-                        }
-                    }
-                },
-                _ => {} // Ignore other types
+        for entity_name in derivation.source_entities.entity_names() {
+            if entity_name != root_entity.name.as_str() {
+                collect_entity_dependencies(
+                    entity_name,
+                    all_entities,
+                    root_entity,
+                    &mut needed_entities,
+                    &mut seen,
+                    max_depth,
+                )?;
             }
         }
     }
@@ -891,7 +1201,7 @@ pub(super) fn generate_derived_entity_extraction_internal(
     for field in fields {
         let field_name = &field.name;
 
-        if let Some(field_def) = field_defs.get(field_name) {
+        if let Some(field_def) = field_defs.get(field_name.as_str()) {
             if let Some(ref computed_from) = field_def.computed_from {
                 for source in &computed_from.sources {
                     let source_entity = source.source_name();
@@ -901,8 +1211,9 @@ pub(super) fn generate_derived_entity_extraction_internal(
                             all_entities,
                             root_entity,
                             &mut needed_entities,
-                            &mut seen
-                        );
+                            &mut seen,
+                            max_depth,
+                        )?;
                     }
                 }
             }
@@ -921,13 +1232,15 @@ pub(super) fn generate_derived_entity_extraction_internal(
             writeln!(output, "    // Instantiate {} entity", entity_name)?;
 
             // For each field in the intermediate entity, generate extraction code
-            for field in &intermediate_entity.fields {
+            for field in intermediate_entity.fields_in_dependency_order()? {
                 if let Some(ref computed_from) = field.computed_from {
-                    let var_name = format!("{}_{}", crate::codegen::utils::to_snake_case(entity_name), field.name);
+                    let var_name = format!("{}_{}", to_snake_case(entity_name), field.name);
                     let field_type_str = field.field_type.as_str();
                     let is_nullable = field.nullable;
                     // Non-repeating intermediate entities are not from repeating parents, so pass None
                     generate_field_extraction(output, &var_name, field_type_str, computed_from, root_entity, &root_param_name, is_nullable, None, "    ", Some(intermediate_entity), true)?;
+                    write_field_coercion(output, &intermediate_entity.name, &var_name, field, "    ")?;
+                    write_field_default(output, &var_name, field, "    ")?;
                 }
             }
 
@@ -954,9 +1267,9 @@ pub(super) fn generate_derived_entity_extraction_internal(
                 writeln!(output, "        // Instantiate {} entity from current segment", repeating_parent)?;
 
                 // For each field in the repeating entity, generate extraction code
-                for field in &intermediate_entity.fields {
+                for field in intermediate_entity.fields_in_dependency_order()? {
                     if let Some(ref computed_from) = field.computed_from {
-                        let var_name = format!("{}_{}", crate::codegen::utils::to_snake_case(repeating_parent), field.name);
+                        let var_name = format!("{}_{}", to_snake_case(repeating_parent), field.name);
                         let field_type_str = field.field_type.as_str();
                         let is_nullable = field.nullable;
                         // Repeating parent fields are extracted from the loop variable
@@ -966,6 +1279,8 @@ pub(super) fn generate_derived_entity_extraction_internal(
                             each_known_as.as_str(),
                         ));
                         generate_field_extraction(output, &var_name, field_type_str, computed_from, root_entity, &root_param_name, is_nullable, repeating_info, "        ", Some(intermediate_entity), true)?;
+                        write_field_coercion(output, &intermediate_entity.name, &var_name, field, "        ")?;
+                        write_field_default(output, &var_name, field, "        ")?;
                     }
                 }
 
@@ -1007,13 +1322,24 @@ pub(super) fn generate_derived_entity_extraction_internal(
     // When we have a repeating_context_override, we're not creating our own loop, so keep indent at "    "
     let base_indent = if has_repeating_parent && repeating_context_override.is_none() { "        " } else { "    " };
 
+    // Order overrides by the entity's self-reference dependencies; overrides
+    // that aren't entity fields keep their YAML order after them
+    let field_order: Vec<&str> = derived_entity.fields_in_dependency_order()?
+        .iter()
+        .map(|f| f.name.as_str())
+        .collect();
+    let mut ordered_fields: Vec<_> = field_order.iter()
+        .filter_map(|name| fields.iter().find(|f| f.name == *name))
+        .collect();
+    ordered_fields.extend(fields.iter().filter(|f| !field_order.contains(&f.name.as_str())));
+
     // Generate field extraction for each field in derived entity (skip autogenerated ID unless it has computed_from)
-    for field in fields {
+    for field in ordered_fields {
         let field_name = &field.name;
         let is_nullable = field.nullable.unwrap_or(false);
 
         // Check if this field has computed_from defined
-        let has_computed_from = field_defs.get(field_name)
+        let has_computed_from = field_defs.get(field_name.as_str())
             .and_then(|f| f.computed_from.as_ref())
             .is_some();
 
@@ -1025,11 +1351,13 @@ pub(super) fn generate_derived_entity_extraction_internal(
         }
 
         // Try to find the field definition to get computed_from information
-        if let Some(field_def) = field_defs.get(field_name) {
+        if let Some(field_def) = field_defs.get(field_name.as_str()) {
             if let Some(ref computed_from) = field_def.computed_from {
                 // Generate extraction code based on computed_from configuration
                 let field_type_str = field_def.field_type.as_str();
                 generate_field_extraction(output, field_name, field_type_str, computed_from, root_entity, &root_param_name, is_nullable, repeating_parent_info, base_indent, Some(derived_entity), false)?;
+                write_field_coercion(output, &derived_entity.name, field_name, field_def, base_indent)?;
+                write_field_default(output, field_name, field_def, base_indent)?;
                 continue;
             }
         }
@@ -1046,8 +1374,9 @@ pub(super) fn generate_derived_entity_extraction_internal(
 
     writeln!(output)?;
 
-    // Build column names list (exclude autogenerated ID, use snake_case for SQL)
-    let col_names: Vec<String> = fields.iter()
+    // Build column names list (exclude autogenerated ID, use snake_case for SQL);
+    // `pre_insert_sql` columns take an expression instead of a bound value
+    let insert_fields: Vec<&str> = fields.iter()
         .filter(|f| {
             if let Some(auto_id) = autogenerated_id_field {
                 &f.name != auto_id
@@ -1055,108 +1384,68 @@ pub(super) fn generate_derived_entity_extraction_internal(
                 true
             }
         })
-        .map(|f| to_snake_case(&f.name))
+        .map(|f| f.name.as_str())
         .collect();
+    let insert_values = db_config.insert_values(&insert_fields, to_snake_case)?;
+    let col_names: Vec<&str> = insert_values.iter().map(|(column, _)| column.as_str()).collect();
+    let sql_values = |dialect: &dyn SqlDialect| {
+        let mut bind_count = 0;
+        insert_values.iter()
+            .map(|(_, value)| value.to_sql(&mut |_| {
+                bind_count += 1;
+                dialect.placeholder(bind_count)
+            }))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
 
     // Database-specific SQL generation will be done inline below
     // (placeholders and conflict handling vary by database)
 
-    // Find string-type unicity fields to check for emptiness
-    // Use field names directly (not prefixed with entity name)
-    let string_unicity_fields: Vec<String> = db_config.unicity_fields.iter()
+    // Check each unicity field before inserting, so rows aren't keyed on
+    // missing or placeholder values (variables are named after the fields)
+    let unicity_conditions: Vec<String> = db_config.unicity_fields.iter()
         .filter_map(|field_name| {
-            fields.iter().find(|f| &f.name == field_name)
-                .and_then(|f| {
-                    let field_type = f.field_type.as_deref().unwrap_or("String");
-                    if field_type == "String" {
-                        // Use field name directly (matches the variable name created above)
-                        Some(field_name.to_string())
-                    } else {
-                        None
-                    }
-                })
+            let field = fields.iter().find(|f| &f.name == field_name)?;
+            let is_nullable = field.nullable.unwrap_or(false);
+            unicity_check_condition(field, is_nullable)
         })
         .collect();
 
-    // Generate conditional INSERT based on string unicity fields
-    // Only insert if ALL string unicity fields have non-empty values
+    // Generate conditional INSERT: only insert if ALL unicity checks pass
     let base_indent = if has_repeating_parent { "        " } else { "    " };
     let query_indent = if has_repeating_parent { "            " } else { "        " };
 
-    if !string_unicity_fields.is_empty() {
-        writeln!(output, "{}// Insert {} entity only if all string unicity fields are non-empty", base_indent, derived_entity.name)?;
-        let conditions: Vec<String> = string_unicity_fields.iter()
-            .map(|f| format!("({}.is_some() && {}.as_ref().map(|s| !s.is_empty()).unwrap_or(false))", f, f))
-            .collect();
-        writeln!(output, "{}if {} {{", base_indent, conditions.join(" && "))?;
+    if !unicity_conditions.is_empty() {
+        writeln!(output, "{}// Insert {} entity only if all unicity fields pass their checks", base_indent, derived_entity.name)?;
+        writeln!(output, "{}if {} {{", base_indent, unicity_conditions.join(" && "))?;
     } else {
         writeln!(output, "{}// Insert {} entity", base_indent, derived_entity.name)?;
     }
 
     // Generate database-specific INSERT statement
-    writeln!(output, "{}#[cfg(feature = \"postgres\")]", base_indent)?;
-    writeln!(output, "{}{{", base_indent)?;
-
-    // PostgreSQL: $1, $2, ... placeholders with ON CONFLICT
-    let pg_placeholders: Vec<String> = (1..=col_names.len())
-        .map(|i| format!("${}", i))
+    let conflict_fields: Vec<String> = db_config.unicity_fields.iter()
+        .map(|f| to_snake_case(f))
         .collect();
-    let pg_on_conflict = if !db_config.unicity_fields.is_empty() {
-        let snake_case_fields: Vec<String> = db_config.unicity_fields.iter()
-            .map(|f| to_snake_case(f))
-            .collect();
-        format!(" ON CONFLICT ({}) DO NOTHING", snake_case_fields.join(", "))
-    } else {
-        String::new()
-    };
-
-    writeln!(output, "{}    diesel::sql_query(", base_indent)?;
-    writeln!(output, "{}        r#\"INSERT INTO {} ({}) VALUES ({}){}\"#",
-        base_indent,
-        table_name,
-        col_names.join(", "),
-        pg_placeholders.join(", "),
-        pg_on_conflict)?;
-    writeln!(output, "{}    )", base_indent)?;
-
-    writeln!(output, "{}}}", base_indent)?;
-    writeln!(output, "{}#[cfg(feature = \"mysql\")]", base_indent)?;
-    writeln!(output, "{}{{", base_indent)?;
-
-    // MySQL: ? placeholders with INSERT IGNORE
-    let mysql_placeholders = vec!["?"; col_names.len()].join(", ");
-    let insert_keyword = if !db_config.unicity_fields.is_empty() {
-        "INSERT IGNORE"
-    } else {
-        "INSERT"
-    };
-
-    writeln!(output, "{}    diesel::sql_query(", base_indent)?;
-    writeln!(output, "{}        r#\"{} INTO {} ({}) VALUES ({})\"#",
-        base_indent,
-        insert_keyword,
-        table_name,
-        col_names.join(", "),
-        mysql_placeholders)?;
-    writeln!(output, "{}    )", base_indent)?;
-
-    writeln!(output, "{}}}", base_indent)?;
-
-    // Bind each field (skip autogenerated ID)
-    for field in fields {
-        // Skip autogenerated ID field
-        if let Some(auto_id) = autogenerated_id_field {
-            if &field.name == auto_id {
-                continue;
-            }
-        }
-
+    let conflict_fields: Vec<&str> = conflict_fields.iter().map(String::as_str).collect();
+    // Match the partial unique index when soft-deleted rows are excluded
+    let predicate = derived_entity.live_unicity_column(all_entities)
+        .map(|column| format!("{} IS NULL", column));
+    write_sql_query_per_feature(output, base_indent, |dialect| {
+        dialect.insert_ignore_clause(table_name, &col_names, &sql_values(dialect), &conflict_fields, predicate.as_deref())
+    })?;
+
+    // Bind each field in placeholder order (autogenerated ID is never bound)
+    let bound_fields = insert_values.iter()
+        .flat_map(|(_, value)| value.binds())
+        .filter_map(|name| fields.iter().find(|f| f.name == name));
+    for field in bound_fields {
         let field_type_str = field.field_type.as_deref().unwrap_or("String");
         let diesel_type = map_to_diesel_type(field_type_str);
 
         // Look up the entity field definition to check if runtime type is Option<T>
         // (Same logic as for root entities - check entity field's nullable, not field_override's nullable)
-        let entity_field = field_defs.get(&field.name);
+        let entity_field = field_defs.get(field.name.as_str());
         let is_nullable = entity_field.map(|f| f.nullable).unwrap_or(false);
 
         if is_nullable {
@@ -1169,7 +1458,7 @@ pub(super) fn generate_derived_entity_extraction_internal(
     writeln!(output, "{}.execute(conn)?;", base_indent)?;
 
     // Close the conditional if statement
-    if !string_unicity_fields.is_empty() {
+    if !unicity_conditions.is_empty() {
         if has_repeating_parent {
             writeln!(output, "        }}")?;
         } else {
@@ -1194,8 +1483,9 @@ pub(super) fn generate_transient_derived_entity_extraction(
     derived_entity: &EntityDef,
     root_entity: &EntityDef,
     all_entities: &[EntityDef],
+    max_depth: usize,
 ) -> Result<(), Box<dyn Error>> {
-    generate_transient_derived_entity_extraction_internal(output, derived_entity, root_entity, all_entities, None)
+    generate_transient_derived_entity_extraction_internal(output, derived_entity, root_entity, all_entities, max_depth, None)
 }
 
 /// Internal implementation with optional repeating context
@@ -1205,45 +1495,32 @@ pub(super) fn generate_transient_derived_entity_extraction_internal(
     derived_entity: &EntityDef,
     root_entity: &EntityDef,
     all_entities: &[EntityDef],
+    max_depth: usize,
     repeating_context: Option<(&str, &str, &str)>, // (entity_name, segment_var, each_known_as)
 ) -> Result<(), Box<dyn Error>> {
-    use std::collections::{HashMap, HashSet};
+    use std::collections::HashSet;
 
     writeln!(output, "    // Extract and publish transient entity: {}", derived_entity.name)?;
 
-    let entity_prefix = crate::codegen::utils::to_snake_case(&derived_entity.name);
+    let entity_prefix = to_snake_case(&derived_entity.name);
     let root_param_name = root_entity.name.to_lowercase();
 
-    // Build field definitions map
-    let mut field_defs: HashMap<String, &crate::codegen::types::FieldDef> = HashMap::new();
-    for field in &derived_entity.fields {
-        field_defs.insert(field.name.clone(), field);
-    }
-
     // Collect dependencies
     let mut needed_entities: Vec<String> = Vec::new();
     let mut seen: HashSet<String> = HashSet::new();
 
     // Check derivation.source_entities for dependencies
     if let Some(ref derivation) = derived_entity.derivation {
-        if let Some(ref source_entities) = derivation.source_entities {
-            match source_entities {
-                serde_yaml::Value::Mapping(map) => {
-                    for (_key, value) in map {
-                        if let serde_yaml::Value::String(entity_name) = value {
-                            if entity_name.as_str() != root_entity.name.as_str() {
-                                collect_entity_dependencies(
-                                    entity_name.as_str(),
-                                    all_entities,
-                                    root_entity,
-                                    &mut needed_entities,
-                                    &mut seen
-                                );
-                            }
-                        }
-                    }
-                },
-                _ => {}
+        for entity_name in derivation.source_entities.entity_names() {
+            if entity_name != root_entity.name.as_str() {
+                collect_entity_dependencies(
+                    entity_name,
+                    all_entities,
+                    root_entity,
+                    &mut needed_entities,
+                    &mut seen,
+                    max_depth,
+                )?;
             }
         }
     }
@@ -1259,8 +1536,9 @@ pub(super) fn generate_transient_derived_entity_extraction_internal(
                         all_entities,
                         root_entity,
                         &mut needed_entities,
-                        &mut seen
-                    );
+                        &mut seen,
+                        max_depth,
+                    )?;
                 }
             }
         }
@@ -1270,12 +1548,14 @@ pub(super) fn generate_transient_derived_entity_extraction_internal(
     for entity_name in &needed_entities {
         if let Some(intermediate_entity) = all_entities.iter().find(|e| &e.name == entity_name) {
             writeln!(output, "    // Instantiate {} entity", entity_name)?;
-            for field in &intermediate_entity.fields {
+            for field in intermediate_entity.fields_in_dependency_order()? {
                 if let Some(ref computed_from) = field.computed_from {
-                    let var_name = format!("{}_{}", crate::codegen::utils::to_snake_case(entity_name), field.name);
+                    let var_name = format!("{}_{}", to_snake_case(entity_name), field.name);
                     let field_type_str = field.field_type.as_str();
                     let is_nullable = field.nullable;
                     generate_field_extraction(output, &var_name, field_type_str, computed_from, root_entity, &root_param_name, is_nullable, repeating_context, "    ", Some(intermediate_entity), true)?;
+                    write_field_coercion(output, &intermediate_entity.name, &var_name, field, "    ")?;
+                    write_field_default(output, &var_name, field, "    ")?;
                 }
             }
             if !intermediate_entity.is_persistent(all_entities) {
@@ -1286,12 +1566,14 @@ pub(super) fn generate_transient_derived_entity_extraction_internal(
     }
 
     // Extract this entity's fields (keep variables in main scope for downstream entities)
-    for field in &derived_entity.fields {
+    for field in derived_entity.fields_in_dependency_order()? {
         if let Some(ref computed_from) = field.computed_from {
             let var_name = format!("{}_{}", entity_prefix, field.name);
             let field_type_str = field.field_type.as_str();
             let is_nullable = field.nullable;
             generate_field_extraction(output, &var_name, field_type_str, computed_from, root_entity, &root_param_name, is_nullable, repeating_context, "    ", Some(derived_entity), false)?;
+            write_field_coercion(output, &derived_entity.name, &var_name, field, "    ")?;
+            write_field_default(output, &var_name, field, "    ")?;
         }
     }
 
@@ -1311,16 +1593,16 @@ pub(super) fn publish_transient_entity_to_nats(
     indent: &str,
 ) -> Result<(), Box<dyn Error>> {
     let entity_name = &entity.name;
-    let entity_prefix = crate::codegen::utils::to_snake_case(entity_name);
+    let entity_prefix = to_snake_case(entity_name);
 
     writeln!(output, "{}// Publish {} to NATS", indent, entity_name)?;
-    writeln!(output, "{}{{", indent)?;
+    writeln!(output, "{}if crate::persist_publish::publishes_entity(\"{}\") {{", indent, entity_name)?;
     writeln!(output, "{}    let mut entity_json = serde_json::Map::new();", indent)?;
 
     // Add all fields to the JSON map
     for field in &entity.fields {
-        let field_name = &field.name;
-        let var_name = format!("{}_{}", entity_prefix, field_name);
+        let field_name = field.serialized_name();
+        let var_name = format!("{}_{}", entity_prefix, field.name);
         let field_type = &field.field_type;
         let is_nullable = field.nullable;
 
@@ -1341,16 +1623,16 @@ pub(super) fn publish_transient_entity_to_nats(
     writeln!(output, "{}    // Only publish if entity has actual data", indent)?;
     writeln!(output, "{}    if !entity_json.is_empty() {{", indent)?;
     writeln!(output, "{}        let entity_json_str = serde_json::to_string(&entity_json)", indent)?;
-    writeln!(output, "{}            .map_err(|e| AppError::ValidationError(format!(\"Failed to serialize {}: {{}}\", e)))?;", indent, entity_name)?;
-    writeln!(output, "{}        let stream_subject = format!(\"entities.{}\");", indent, entity_name)?;
+    writeln!(output, "{}            .map_err(|e| AppError::Publish {{ entity: \"{}\".to_string(), message: format!(\"serialization failed: {{}}\", e) }})?;", indent, entity_name)?;
+    writeln!(output, "{}        let stream_subject = {};", indent, subjects::subject_expr(entity, "entity_json")?)?;
     writeln!(output, "{}        jetstream.publish(stream_subject.clone(), entity_json_str.into()).await", indent)?;
     writeln!(output, "{}            .map_err(|e| {{", indent)?;
-    writeln!(output, "{}                eprintln!(\"[WORKER] Failed to publish {} to {{}}: {{:?}}\", stream_subject, e);", indent, entity_name)?;
-    writeln!(output, "{}                AppError::ValidationError(format!(\"NATS publish failed: {{}}\", e))", indent)?;
+    writeln!(output, "{}                tracing::error!(entity_type = \"{}\", subject = %stream_subject, error = ?e, \"Failed to publish entity\");", indent, entity_name)?;
+    writeln!(output, "{}                AppError::Publish {{ entity: \"{}\".to_string(), message: e.to_string() }}", indent, entity_name)?;
     writeln!(output, "{}            }})?;", indent)?;
-    writeln!(output, "{}        eprintln!(\"[WORKER] ✓ Published {} to {{}}\", stream_subject);", indent, entity_name)?;
+    writeln!(output, "{}        tracing::debug!(entity_type = \"{}\", subject = %stream_subject, \"Published entity\");", indent, entity_name)?;
     writeln!(output, "{}    }} else {{", indent)?;
-    writeln!(output, "{}        eprintln!(\"[WORKER] Skipped {} (no data extracted from segments)\");", indent, entity_name)?;
+    writeln!(output, "{}        tracing::debug!(entity_type = \"{}\", \"Skipped entity (no data extracted)\");", indent, entity_name)?;
     writeln!(output, "{}    }}", indent)?;
     writeln!(output, "{}}}", indent)?;
 
@@ -1364,10 +1646,15 @@ pub(super) fn is_list_type(field_type: &str) -> bool {
 }
 
 /// Determine the main data field for a root entity
-/// For Hl7v2MessageFile, this is "hl7v2Message"
-/// For Filename, this is "fileName"
-#[allow(dead_code)]
-pub(super) fn determine_root_data_field(entity: &EntityDef) -> &str {
+///
+/// The entity's `data_field` if declared, else a field with a common payload
+/// name (e.g. "hl7v2Message" for Hl7v2MessageFile), else the first required
+/// string field.
+pub(super) fn determine_root_data_field(entity: &EntityDef) -> Result<&str, String> {
+    if let Some(ref data_field) = entity.data_field {
+        return Ok(data_field.as_str());
+    }
+
     // Strategy: Prioritize fields with common data payload names, then fall back to first non-nullable string
     let priority_names = ["message", "hl7v2Message", "body", "data", "content", "text"];
 
@@ -1376,7 +1663,7 @@ pub(super) fn determine_root_data_field(entity: &EntityDef) -> &str {
         if let Some(field) = entity.fields.iter().find(|f| {
             f.name.to_lowercase() == priority_name.to_lowercase()
         }) {
-            return field.name.as_str();
+            return Ok(field.name.as_str());
         }
     }
 
@@ -1388,7 +1675,10 @@ pub(super) fn determine_root_data_field(entity: &EntityDef) -> &str {
             is_string && is_required
         })
         .map(|f| f.name.as_str())
-        .unwrap_or("body") // final fallback
+        .ok_or_else(|| format!(
+            "Root entity '{}' has no payload field to pass to transforms; declare one with data_field",
+            entity.name
+        ))
 }
 
 /// Helper function to build variable name for a field source
@@ -1398,7 +1688,6 @@ fn build_source_variable_name(
     current_entity: Option<&EntityDef>,
     is_intermediate: bool, // true if generating for a dependency entity, false if main entity
 ) -> String {
-    use crate::codegen::utils::to_snake_case;
 
     let src_entity = source.source_name();
     let src_field = source.field_name();
@@ -1449,6 +1738,66 @@ fn build_source_variable_name(
     }
 }
 
+/// Parse the extracted string into the field's declared type when `coerce:` is set
+fn write_field_coercion(
+    output: &mut std::fs::File,
+    entity_name: &str,
+    var_name: &str,
+    field: &crate::codegen::types::FieldDef,
+    indent: &str,
+) -> Result<(), Box<dyn Error>> {
+    let coerced = crate::codegen::utils::generate_coercion_expr(
+        entity_name,
+        field,
+        var_name,
+        super::entities_rs::map_field_type(&field.field_type),
+        None,
+        &|msg| super::error_rs::invalid_field_value(entity_name, &field.name, msg),
+    );
+    if let Some(coerced) = coerced {
+        writeln!(output, "{}let {} = {};", indent, var_name, coerced)?;
+    }
+    Ok(())
+}
+
+/// Condition a unicity field's variable must meet before the row is inserted
+/// (None when every value passes)
+///
+/// String variables always hold `Option<String>`; other types are `Option`
+/// only when nullable, so a non-nullable number is always present.
+fn unicity_check_condition(field: &crate::codegen::types::FieldOverride, is_nullable: bool) -> Option<String> {
+    use crate::codegen::types::UnicityCheck;
+
+    let name = &field.name;
+    let field_type = field.field_type.as_deref().unwrap_or("String");
+    let check = field.unicity_check.unwrap_or_else(|| UnicityCheck::default_for(field_type));
+    let zero = if field_type == "Float" { "0.0" } else { "0" };
+    match check {
+        UnicityCheck::None => None,
+        UnicityCheck::Present if field_type == "String" || is_nullable => Some(format!("{}.is_some()", name)),
+        UnicityCheck::Present => None,
+        UnicityCheck::NonEmpty if field_type == "String" =>
+            Some(format!("({}.is_some() && {}.as_ref().map(|s| !s.is_empty()).unwrap_or(false))", name, name)),
+        UnicityCheck::NonEmpty if is_nullable => Some(format!("{}.as_ref().is_some_and(|v| !v.is_empty())", name)),
+        UnicityCheck::NonEmpty => Some(format!("!{}.is_empty()", name)),
+        UnicityCheck::NonZero if is_nullable => Some(format!("{}.is_some_and(|v| v != {})", name, zero)),
+        UnicityCheck::NonZero => Some(format!("{} != {}", name, zero)),
+    }
+}
+
+/// Fall back to the field's configured `default:` when the extracted value is None
+fn write_field_default(
+    output: &mut std::fs::File,
+    var_name: &str,
+    field: &crate::codegen::types::FieldDef,
+    indent: &str,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(default) = field.default_literal()? {
+        writeln!(output, "{}let {} = {}.or_else(|| Some({}));", indent, var_name, var_name, default)?;
+    }
+    Ok(())
+}
+
 /// Generate field extraction code based on computed_from configuration
 #[allow(dead_code)]
 pub(super) fn generate_field_extraction(
@@ -1468,7 +1817,7 @@ pub(super) fn generate_field_extraction(
     let sources = &computed_from.sources;
 
     // Convert transform function name to snake_case for Rust function calls
-    let transform_fn = to_snake_case(transform);
+    let transform_fn = transform_fn_name(&to_snake_case(transform));
 
     // For simple cases, generate direct extraction from root message fields
     // This handles: copy_field, extract_filename_component, etc.
@@ -1505,6 +1854,34 @@ pub(super) fn generate_field_extraction(
     let source = &sources[0];
     let source_field = source.field_name();
 
+    if let (true, Some(src_field), Some(path)) =
+        (transform == "extract_json_subtree", source_field, computed_from.arg_str("path"))
+    {
+        // Take a sub-object out of a JSON source field
+        let source_entity = source.source_name();
+        let (source_expr, is_json) = if source_entity == root_entity.name.as_str() {
+            let root_field = root_entity.fields.iter().find(|f| f.name == src_field);
+            let expr = if root_field.map(|f| f.nullable).unwrap_or(false) {
+                format!("{}.{}", root_param_name, src_field)
+            } else {
+                format!("Some({}.{}.clone())", root_param_name, src_field)
+            };
+            (expr, root_field.map(|f| f.is_json()).unwrap_or(false))
+        } else {
+            // Intermediate entity variables hold Option<String>
+            (format!("{}_{}", to_snake_case(source_entity), src_field), false)
+        };
+
+        let value = crate::codegen::utils::generate_json_subtree_expr(&source_expr, is_json, path);
+        if is_nullable {
+            writeln!(output, "{}let {}: Option<serde_json::Value> = {};", base_indent, field_name, value)?;
+        } else {
+            writeln!(output, "{}let {}: serde_json::Value = {}.unwrap_or(serde_json::Value::Null);",
+                base_indent, field_name, value)?;
+        }
+        return Ok(());
+    }
+
     if transform == "copy_field" && sources.len() == 1 && source_field.is_some() {
         // Direct copy from source field (only when field is specified)
         let source_entity = source.source_name();
@@ -1520,7 +1897,7 @@ pub(super) fn generate_field_extraction(
                 src_field)?;
         } else {
             // Access from intermediate entity variable
-            let intermediate_var = format!("{}_{}", crate::codegen::utils::to_snake_case(source_entity), src_field);
+            let intermediate_var = format!("{}_{}", to_snake_case(source_entity), src_field);
             writeln!(output, "{}let {}: Option<String> = {}.clone();",
                 base_indent,
                 field_name,
@@ -1573,7 +1950,7 @@ pub(super) fn generate_field_extraction(
                 }
             } else {
                 // Call transform with intermediate entity variable
-                let intermediate_var = format!("{}_{}", crate::codegen::utils::to_snake_case(source_entity), src_field);
+                let intermediate_var = format!("{}_{}", to_snake_case(source_entity), src_field);
 
                 // Pass &Option<String> to transform function
                 let all_args = if args_list.is_empty() {
@@ -1631,7 +2008,7 @@ pub(super) fn generate_field_extraction(
             // No source field - this is a Direct source (e.g., transform references entire entity)
             // For root entity direct sources, we need to determine which field to pass
             if source_entity == root_entity.name.as_str() {
-                let root_data_field = determine_root_data_field(root_entity);
+                let root_data_field = determine_root_data_field(root_entity)?;
 
                 let args_list = if let Some(ref args) = computed_from.args {
                     format_transform_args_list(args)
@@ -1882,3 +2259,107 @@ fn map_to_diesel_type(field_type: &str) -> &'static str {
         _ => "Text", // Default to Text for unknown types
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn entity(name: &str, parent: Option<&str>) -> EntityDef {
+        EntityDef {
+            name: name.to_string(),
+            source_type: if parent.is_some() { "derived" } else { "root" }.to_string(),
+            parent: parent.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn collect(entities: &[EntityDef], name: &str, max_depth: usize) -> Result<Vec<String>, String> {
+        let mut ordered = Vec::new();
+        collect_entity_dependencies(name, entities, &entities[0], &mut ordered, &mut HashSet::new(), max_depth)?;
+        Ok(ordered)
+    }
+
+    #[test]
+    fn test_collect_entity_dependencies_orders_parents_first() {
+        let entities = vec![entity("Root", None), entity("A", Some("Root")), entity("B", Some("A")), entity("C", Some("b"))];
+        assert_eq!(collect(&entities, "C", DEFAULT_MAX_DEPENDENCY_DEPTH).unwrap(), vec!["A", "B", "C"]);
+        assert!(collect(&entities, "Root", DEFAULT_MAX_DEPENDENCY_DEPTH).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_collect_entity_dependencies_accepts_every_source_entities_shape() {
+        for source_entities in ["A", "[A]", "{a: A}", "{a: {entity: A, ancillary: true}}"] {
+            let mut derived = entity("B", None);
+            derived.source_type = "derived".to_string();
+            derived.derivation = Some(serde_yaml::from_str(&format!("source_entities: {}", source_entities)).unwrap());
+            let entities = vec![entity("Root", None), entity("A", Some("Root")), derived];
+            assert_eq!(collect(&entities, "B", DEFAULT_MAX_DEPENDENCY_DEPTH).unwrap(), vec!["A", "B"], "{}", source_entities);
+        }
+    }
+
+    #[test]
+    fn test_collect_entity_dependencies_rejects_cycles_and_deep_graphs() {
+        let cyclic = vec![entity("Root", None), entity("A", Some("B")), entity("B", Some("A"))];
+        let err = collect(&cyclic, "A", DEFAULT_MAX_DEPENDENCY_DEPTH).err().unwrap();
+        assert!(err.contains("A -> B -> A"), "{}", err);
+
+        let mut chain = vec![entity("Root", None), entity("E0", Some("Root"))];
+        for i in 1..10 {
+            chain.push(entity(&format!("E{}", i), Some(&format!("E{}", i - 1))));
+        }
+        assert_eq!(collect(&chain, "E9", 10).unwrap().len(), 10);
+        assert!(collect(&chain, "E9", 5).err().unwrap().contains("deeper than 5"));
+    }
+
+    #[test]
+    fn test_sampling_keys_on_parsed_root_entity() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("src")).unwrap();
+        let config = WorkerConfig {
+            sample_rates: [("Order".to_string(), 0.1), ("Invoice".to_string(), 0.5)].into_iter().collect(),
+            ..Default::default()
+        };
+        generate_main_rs(&[], temp_dir.path(), &config).unwrap();
+        let main_rs = std::fs::read_to_string(temp_dir.path().join("src/main.rs")).unwrap();
+
+        // Configured rates are the SAMPLE_RATES default
+        assert!(main_rs.contains("unwrap_or_else(|_| \"Invoice=0.5,Order=0.1\".to_string())"));
+
+        // Sampling happens once the body is parsed, keyed on the parsed entity
+        // rather than the envelope's (optional) entity_type hint
+        let parsed_at = main_rs.find("MessageParser::parse_json(").unwrap();
+        let sampled_at = main_rs.find("if !sampled_in(&message_id, sample_rate(&entity_name))").unwrap();
+        assert!(parsed_at < sampled_at);
+        assert_eq!(main_rs.matches("sampled_in(&").count(), 1);
+    }
+
+    #[test]
+    fn test_parse_sample_rate() {
+        use crate::codegen::worker::parse_sample_rate;
+
+        assert_eq!(parse_sample_rate("Order=0.25").unwrap(), ("Order".to_string(), 0.25));
+        assert_eq!(parse_sample_rate(" Order = 1 ").unwrap(), ("Order".to_string(), 1.0));
+        assert!(parse_sample_rate("Order").unwrap_err().contains("expected Entity=fraction"));
+        assert!(parse_sample_rate("Order=most").unwrap_err().contains("not a number"));
+        assert!(parse_sample_rate("Order=1.5").unwrap_err().contains("between 0 and 1"));
+    }
+
+    #[test]
+    fn test_unicity_check_condition() {
+        use crate::codegen::types::FieldOverride;
+
+        let field = |yaml: &str| serde_yaml::from_str::<FieldOverride>(yaml).unwrap();
+        assert_eq!(
+            unicity_check_condition(&field("{name: mrn, type: String}"), false).unwrap(),
+            "(mrn.is_some() && mrn.as_ref().map(|s| !s.is_empty()).unwrap_or(false))"
+        );
+        assert_eq!(unicity_check_condition(&field("{name: claim_id, type: Integer}"), true).unwrap(), "claim_id.is_some()");
+        assert_eq!(unicity_check_condition(&field("{name: claim_id, type: Integer}"), false), None);
+        assert_eq!(
+            unicity_check_condition(&field("{name: amount, type: Float, unicity_check: non_zero}"), true).unwrap(),
+            "amount.is_some_and(|v| v != 0.0)"
+        );
+        assert_eq!(unicity_check_condition(&field("{name: mrn, type: String, unicity_check: none}"), false), None);
+    }
+}
@@ -3,40 +3,11 @@
 /// This handles routing parsed messages to the correct entity processor
 
 use crate::codegen::EntityDef;
+use crate::codegen::utils::to_snake_case;
 use std::path::Path;
 use std::error::Error;
 use std::io::Write;
 
-/// Convert field names to snake_case for SQL/Rust
-fn to_snake_case(s: &str) -> String {
-    let stripped = if s.starts_with("f_") {
-        &s[2..]
-    } else {
-        s
-    };
-
-    if stripped.contains('_') || stripped.chars().all(|c| !c.is_uppercase()) {
-        return s.to_string();
-    }
-
-    let mut result = String::new();
-    let mut prev_lowercase = false;
-
-    for (i, ch) in s.chars().enumerate() {
-        if ch.is_uppercase() {
-            if i > 0 && (prev_lowercase || s.chars().nth(i + 1).map_or(false, |c| c.is_lowercase())) {
-                result.push('_');
-            }
-            result.push(ch.to_lowercase().next().unwrap());
-            prev_lowercase = false;
-        } else {
-            result.push(ch);
-            prev_lowercase = ch.is_lowercase();
-        }
-    }
-    result
-}
-
 pub fn generate_message_processor_rs(
     entities: &[EntityDef],
     output_dir: &Path,
@@ -7,7 +7,9 @@
 /// - ACKs successful processing, NAKs failures
 
 use crate::codegen::EntityDef;
-use std::path::Path;
+use crate::runtime::OnEntityError;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::error::Error;
 
 mod cargo_toml;
@@ -22,10 +24,14 @@ mod entities_rs;
 mod extract_fn_rs;
 mod persist_publish_fn_rs;
 mod coordinator_fn_rs;
+mod side_output_rs;
+mod subjects;
+mod fuzz_rs;
 
 pub use cargo_toml::generate_cargo_toml;
-pub use main_rs::generate_main_rs;
+pub use main_rs::{generate_main_rs, DEFAULT_MAX_DEPENDENCY_DEPTH};
 pub use parsers_rs::generate_parsers_rs;
+pub(crate) use parsers_rs::write_parsers_rs;
 pub use models_rs::generate_models_rs;
 pub use database_rs::generate_database_rs;
 pub use error_rs::generate_error_rs;
@@ -35,74 +41,100 @@ pub use entities_rs::generate_entities_file;
 pub use extract_fn_rs::generate_extract_functions_file;
 pub use persist_publish_fn_rs::generate_persist_publish_file;
 pub use coordinator_fn_rs::generate_coordinator_file;
+pub use side_output_rs::generate_side_output_rs;
+pub use fuzz_rs::generate_fuzz_target;
 
-/// Database type enumeration
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum DatabaseType {
-    PostgreSQL,
-    MySQL,
-    MariaDB,
+pub use crate::codegen::database_type::DatabaseType;
+
+/// Which entities the worker publishes to NATS besides persisting them
+///
+/// Sets the default of the generated worker; `PUBLISH_ENTITIES` overrides it
+/// at runtime.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EntityPublishing {
+    /// Publish nothing, only persist
+    None,
+    /// Publish entities with database persistence
+    Persistent,
+    /// Publish entities without database persistence
+    Transient,
+    /// Publish every entity
+    #[default]
+    All,
 }
 
-impl DatabaseType {
+impl EntityPublishing {
     pub fn as_str(&self) -> &str {
         match self {
-            DatabaseType::PostgreSQL => "postgresql",
-            DatabaseType::MySQL => "mysql",
-            DatabaseType::MariaDB => "mariadb",
+            EntityPublishing::None => "none",
+            EntityPublishing::Persistent => "persistent",
+            EntityPublishing::Transient => "transient",
+            EntityPublishing::All => "all",
         }
     }
 
-    /// Parse database type from string (case-insensitive)
-    ///
-    /// # Examples
-    /// ```no_run
-    /// use nomnom::codegen::worker::DatabaseType;
-    ///
-    /// let db_type = DatabaseType::from_str("postgresql").unwrap();
-    /// assert_eq!(db_type, DatabaseType::PostgreSQL);
-    ///
-    /// let db_type = DatabaseType::from_str("mysql").unwrap();
-    /// assert_eq!(db_type, DatabaseType::MySQL);
-    /// ```
-    pub fn from_str(s: &str) -> Result<DatabaseType, String> {
+    /// Parse a publishing mode (case-insensitive; `true`/`false` mean all/none)
+    pub fn from_str(s: &str) -> Result<EntityPublishing, String> {
         match s.to_lowercase().as_str() {
-            "postgresql" | "postgres" | "pg" => Ok(DatabaseType::PostgreSQL),
-            "mysql" => Ok(DatabaseType::MySQL),
-            "mariadb" => Ok(DatabaseType::MariaDB),
-            _ => Err(format!("Unsupported database type: '{}'", s)),
+            "all" | "true" => Ok(EntityPublishing::All),
+            "persistent" => Ok(EntityPublishing::Persistent),
+            "transient" => Ok(EntityPublishing::Transient),
+            "none" | "false" => Ok(EntityPublishing::None),
+            _ => Err(format!("Unsupported entity publishing mode: '{}' (expected all, persistent, transient or none)", s)),
         }
     }
+}
 
-    /// Check if this is MySQL or MariaDB (similar syntax)
-    pub fn is_mysql_like(&self) -> bool {
-        matches!(self, DatabaseType::MySQL | DatabaseType::MariaDB)
+/// How the worker's JetStream pull consumer is shared between replicas
+///
+/// - `Durable`: every replica binds the consumer named `NATS_CONSUMER`, which
+///   survives restarts. Replicas split the messages between them, so scaling
+///   out adds throughput; this is the production setting.
+/// - `Ephemeral`: the consumer is removed once no replica has pulled from it
+///   for `NATS_CONSUMER_INACTIVE_SECS`. Without a queue group each pod gets
+///   its own consumer and sees every message (handy on debug subjects);
+///   replicas given the same `NATS_QUEUE_GROUP` share one consumer and split
+///   the messages as durable replicas do.
+///
+/// Sets the default of the generated worker; `NATS_CONSUMER_DURABILITY`
+/// overrides it at runtime.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConsumerDurability {
+    #[default]
+    Durable,
+    Ephemeral,
+}
+
+impl ConsumerDurability {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ConsumerDurability::Durable => "durable",
+            ConsumerDurability::Ephemeral => "ephemeral",
+        }
     }
 
-    /// Detect database type from DATABASE_URL
-    ///
-    /// # Examples
-    /// ```no_run
-    /// use nomnom::codegen::worker::DatabaseType;
-    ///
-    /// let db_type = DatabaseType::from_url("postgres://localhost/mydb");
-    /// assert_eq!(db_type, DatabaseType::PostgreSQL);
-    ///
-    /// let db_type = DatabaseType::from_url("mysql://localhost/mydb");
-    /// assert_eq!(db_type, DatabaseType::MySQL);
-    /// ```
-    pub fn from_url(url: &str) -> DatabaseType {
-        if url.starts_with("postgres://") || url.starts_with("postgresql://") {
-            DatabaseType::PostgreSQL
-        } else if url.starts_with("mysql://") {
-            DatabaseType::MySQL
-        } else {
-            // Default to PostgreSQL for backward compatibility
-            DatabaseType::PostgreSQL
+    /// Parse a consumer durability (case-insensitive)
+    pub fn from_str(s: &str) -> Result<ConsumerDurability, String> {
+        match s.to_lowercase().as_str() {
+            "durable" => Ok(ConsumerDurability::Durable),
+            "ephemeral" => Ok(ConsumerDurability::Ephemeral),
+            _ => Err(format!("Unsupported consumer durability: '{}' (expected durable or ephemeral)", s)),
         }
     }
 }
 
+/// Parse an `Entity=fraction` sample rate, as given to `--sample-rate`
+pub fn parse_sample_rate(s: &str) -> Result<(String, f64), String> {
+    let (entity, rate) = s.split_once('=')
+        .ok_or_else(|| format!("Invalid sample rate '{}' (expected Entity=fraction)", s))?;
+    let rate: f64 = rate.trim().parse()
+        .map_err(|_| format!("Invalid sample rate '{}': '{}' is not a number", s, rate))?;
+    if !(0.0..=1.0).contains(&rate) {
+        return Err(format!("Invalid sample rate '{}': fraction must be between 0 and 1", s));
+    }
+    Ok((entity.trim().to_string(), rate))
+}
+
 #[derive(Debug, Clone)]
 pub struct WorkerDependency {
     pub name: String,
@@ -115,6 +147,43 @@ pub struct WorkerConfig {
     pub database_type: DatabaseType,
     pub worker_name: String,
     pub additional_dependencies: Vec<WorkerDependency>,
+    /// Entities published to NATS by default
+    pub publish_entities: EntityPublishing,
+    /// Subjects of the stream the worker consumes from
+    pub stream_subjects: Vec<String>,
+    /// Subjects the worker's consumer is limited to (e.g. `messages.ingest.hl7.>`
+    /// for a per-format fleet); empty consumes the whole stream
+    pub filter_subjects: Vec<String>,
+    /// Seconds the server waits for an ack before redelivering
+    ///
+    /// Each redelivery counts towards `MAX_DELIVER`, so this must exceed the
+    /// slowest record's processing time; otherwise in-progress messages are
+    /// processed again and can be dropped once `MAX_DELIVER` is reached.
+    pub ack_wait_secs: u64,
+    /// Unacknowledged deliveries allowed in flight for the consumer
+    pub max_ack_pending: i64,
+    /// What happens when an entity fails to extract or persist
+    pub on_entity_error: OnEntityError,
+    /// Whether the pull consumer outlives the replicas using it
+    pub consumer_durability: ConsumerDurability,
+    /// Name of the ephemeral consumer replicas share (`NATS_QUEUE_GROUP`);
+    /// None gives each replica its own. Durable replicas always share
+    /// `NATS_CONSUMER`.
+    pub queue_group: Option<String>,
+    /// Fraction (0.0-1.0) of each entity type's messages to process; the
+    /// rest are acked unprocessed. `SAMPLE_RATES` overrides it at runtime
+    pub sample_rates: BTreeMap<String, f64>,
+    /// Emit trace-level diagnostics (poll iterations, every parser tried)
+    /// into the generated code; without it they are left out entirely
+    pub verbose: bool,
+    /// Archive each message's body and extracted entities by message id
+    /// (`SIDE_OUTPUT` picks the sink at runtime)
+    pub side_output: bool,
+    /// Rust file implementing `side_output::SideOutput` for another store,
+    /// used with `SIDE_OUTPUT=custom`
+    pub side_output_sink: Option<PathBuf>,
+    /// Deepest entity dependency nesting generation accepts
+    pub max_dependency_depth: usize,
 }
 
 impl Default for WorkerConfig {
@@ -123,6 +192,19 @@ impl Default for WorkerConfig {
             database_type: DatabaseType::PostgreSQL,
             worker_name: "worker".to_string(),
             additional_dependencies: Vec::new(),
+            publish_entities: EntityPublishing::default(),
+            stream_subjects: vec!["messages.ingest.>".to_string()],
+            filter_subjects: vec!["messages.ingest.>".to_string()],
+            ack_wait_secs: 60,
+            max_ack_pending: 1000,
+            on_entity_error: OnEntityError::default(),
+            consumer_durability: ConsumerDurability::default(),
+            queue_group: None,
+            sample_rates: BTreeMap::new(),
+            verbose: false,
+            side_output: false,
+            side_output_sink: None,
+            max_dependency_depth: DEFAULT_MAX_DEPENDENCY_DEPTH,
         }
     }
 }
@@ -132,7 +214,7 @@ pub fn generate_all(
     entities: &[EntityDef],
     output_dir: &Path,
     config: &WorkerConfig,
-    transforms: Option<&std::collections::HashMap<String, crate::codegen::project_config::RustTransformDef>>,
+    transforms: Option<&std::collections::BTreeMap<String, crate::codegen::project_config::RustTransformDef>>,
 ) -> Result<(), Box<dyn Error>> {
     // Create directory structure
     std::fs::create_dir_all(output_dir)?;
@@ -155,7 +237,7 @@ pub fn generate_all(
     generate_message_processor_rs(entities, output_dir)?;
 
     println!("  ✓ Generating parsers.rs...");
-    generate_parsers_rs(entities, output_dir)?;
+    generate_parsers_rs(entities, output_dir, config.verbose)?;
 
     println!("  ✓ Generating entities.rs...");
     generate_entities_file(entities, output_dir)?;
@@ -167,16 +249,27 @@ pub fn generate_all(
         dependency_graph.num_levels(),
         dependency_graph.nodes.len()
     );
+    if dependency_graph.num_levels() > config.max_dependency_depth {
+        return Err(format!(
+            "Entity dependencies nest {} levels deep, more than the maximum of {}",
+            dependency_graph.num_levels(), config.max_dependency_depth
+        ).into());
+    }
 
     // Generate new dependency-based processing modules
     println!("  ✓ Generating extract.rs (new architecture)...");
     generate_extract_functions_file(entities, &dependency_graph, output_dir)?;
 
     println!("  ✓ Generating persist_publish.rs (new architecture)...");
-    generate_persist_publish_file(entities, output_dir, config.database_type)?;
+    generate_persist_publish_file(entities, output_dir, config.database_type, config.publish_entities)?;
 
     println!("  ✓ Generating coordinator.rs (new architecture)...");
-    generate_coordinator_file(entities, &dependency_graph, output_dir, config.database_type)?;
+    generate_coordinator_file(entities, &dependency_graph, output_dir, config.database_type, config.side_output)?;
+
+    if config.side_output {
+        println!("  ✓ Generating side_output.rs...");
+        generate_side_output_rs(output_dir, config.side_output_sink.as_deref())?;
+    }
 
     println!("  ✓ Generating transforms.rs...");
     generate_transforms_rs(output_dir, transforms)?;
@@ -188,7 +281,10 @@ pub fn generate_all(
     generate_database_rs(entities, output_dir, config)?;
 
     println!("  ✓ Generating error.rs...");
-    generate_error_rs(output_dir)?;
+    generate_error_rs(output_dir, config.on_entity_error)?;
+
+    println!("  ✓ Generating telemetry.rs...");
+    crate::codegen::telemetry::generate_telemetry_rs(output_dir, &config.worker_name)?;
 
     // Generate .env.example
     println!("  ✓ Generating .env.example...");
@@ -198,6 +294,9 @@ pub fn generate_all(
     println!("  ✓ Generating Dockerfile...");
     generate_dockerfile(output_dir)?;
 
+    println!("  ✓ Generating fuzz/ (parse_json fuzz target)...");
+    generate_fuzz_target(entities, output_dir, config)?;
+
     println!();
     println!("✨ Worker binary generated successfully!");
     println!();
@@ -212,6 +311,9 @@ pub fn generate_all(
     println!("  docker build -t worker .");
     println!("  docker run --env-file .env worker");
     println!();
+    println!("🐛 Fuzz the message parser:");
+    println!("  cargo +nightly fuzz run parse_json");
+    println!();
 
     Ok(())
 }
@@ -234,11 +336,57 @@ fn generate_env_example(output_dir: &Path, config: &WorkerConfig) -> Result<(),
     writeln!(output)?;
     writeln!(output, "# NATS Configuration")?;
     writeln!(output, "NATS_URL=nats://localhost:4222")?;
+    writeln!(output, "# Auth/TLS (managed NATS): credentials file, token or nkey seed; NATS_TLS=true")?;
+    writeln!(output, "# requires TLS, NATS_TLS_CA adds root certs, NATS_TLS_CERT/KEY enable mutual TLS")?;
+    writeln!(output, "# NATS_CREDS=/etc/nats/user.creds")?;
+    writeln!(output, "# NATS_TOKEN=")?;
+    writeln!(output, "# NATS_NKEY=")?;
+    writeln!(output, "NATS_TLS=false")?;
+    writeln!(output, "# NATS_TLS_CA=/etc/nats/ca.pem")?;
+    writeln!(output, "# NATS_TLS_CERT=/etc/nats/client.pem")?;
+    writeln!(output, "# NATS_TLS_KEY=/etc/nats/client-key.pem")?;
     writeln!(output, "NATS_STREAM=MESSAGES")?;
     writeln!(output, "NATS_CONSUMER=workers")?;
+    writeln!(output, "# durable: replicas share NATS_CONSUMER and split messages (survives restarts)")?;
+    writeln!(output, "# ephemeral: consumer removed after NATS_CONSUMER_INACTIVE_SECS idle; each pod sees")?;
+    writeln!(output, "# every message unless pods share a NATS_QUEUE_GROUP")?;
+    writeln!(output, "NATS_CONSUMER_DURABILITY={}", config.consumer_durability.as_str())?;
+    writeln!(output, "NATS_QUEUE_GROUP={}", config.queue_group.as_deref().unwrap_or(""))?;
+    writeln!(output, "NATS_CONSUMER_INACTIVE_SECS=300")?;
+    writeln!(output, "# Redelivery after ACK_WAIT_SECS without an ack counts towards MAX_DELIVER;")?;
+    writeln!(output, "# keep it above the slowest record's processing time")?;
+    writeln!(output, "MAX_DELIVER=3")?;
+    writeln!(output, "ACK_WAIT_SECS={}", config.ack_wait_secs)?;
+    writeln!(output, "MAX_ACK_PENDING={}", config.max_ack_pending)?;
+    writeln!(output, "# Comma-separated; workers sharing a stream can each filter a subset")?;
+    writeln!(output, "NATS_STREAM_SUBJECTS={}", config.stream_subjects.join(","))?;
+    writeln!(output, "NATS_FILTER_SUBJECTS={}", config.filter_subjects.join(","))?;
+    writeln!(output, "# Entities published to NATS: all, persistent, transient or none")?;
+    writeln!(output, "PUBLISH_ENTITIES={}", config.publish_entities.as_str())?;
+    writeln!(output, "# When an entity fails: abort (fail the message), skip (store the rest)")?;
+    writeln!(output, "# or dlq (store the rest, dead-letter the failed entity)")?;
+    writeln!(output, "ON_ENTITY_ERROR={}", config.on_entity_error.as_str())?;
+    writeln!(output, "# Process only a fraction of an entity type's messages, e.g. Order=0.1;")?;
+    writeln!(output, "# the rest are acked unprocessed (status sampled_out)")?;
+    let sample_rates: Vec<String> = config.sample_rates.iter()
+        .map(|(entity, rate)| format!("{}={}", entity, rate))
+        .collect();
+    writeln!(output, "SAMPLE_RATES={}", sample_rates.join(","))?;
+    if config.side_output {
+        writeln!(output, "# Archive raw bodies and extracted entities by message id: nats_kv, {}none",
+            if config.side_output_sink.is_some() { "custom or " } else { "" })?;
+        writeln!(output, "SIDE_OUTPUT=nats_kv")?;
+        writeln!(output, "SIDE_OUTPUT_BUCKET=SIDE_OUTPUT")?;
+    }
     writeln!(output)?;
     writeln!(output, "# Logging")?;
     writeln!(output, "RUST_LOG=info")?;
+    writeln!(output, "# Set to json for one JSON object per log line")?;
+    writeln!(output, "LOG_FORMAT=text")?;
+    writeln!(output)?;
+    writeln!(output, "# OpenTelemetry (requires building with --features otel)")?;
+    writeln!(output, "OTEL_ENABLED=false")?;
+    writeln!(output, "# OTEL_EXPORTER_OTLP_ENDPOINT=http://localhost:4317")?;
 
     Ok(())
 }
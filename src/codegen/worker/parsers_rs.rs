@@ -5,9 +5,24 @@ use std::path::Path;
 use std::error::Error;
 use std::io::Write;
 
+/// `verbose` adds a trace event for every parser tried
 pub fn generate_parsers_rs(
     entities: &[EntityDef],
     output_dir: &Path,
+    verbose: bool,
+) -> Result<(), Box<dyn Error>> {
+    write_parsers_rs(entities, output_dir, verbose, false)
+}
+
+/// Write `src/parsers.rs`, shared by the worker and the ingestion server
+///
+/// `openapi_schemas` derives `utoipa::ToSchema` on the message structs so the
+/// ingestion server can reference them in its OpenAPI spec.
+pub(crate) fn write_parsers_rs(
+    entities: &[EntityDef],
+    output_dir: &Path,
+    verbose: bool,
+    openapi_schemas: bool,
 ) -> Result<(), Box<dyn Error>> {
     let parsers_file = output_dir.join("src/parsers.rs");
     let mut output = std::fs::File::create(&parsers_file)?;
@@ -44,7 +59,7 @@ pub fn generate_parsers_rs(
             && entity.source_type.to_lowercase() != "reference";
 
         if include_entity {
-            generate_message_struct(&mut output, entity)?;
+            generate_message_struct(&mut output, entity, openapi_schemas)?;
         }
     }
 
@@ -53,7 +68,7 @@ pub fn generate_parsers_rs(
     writeln!(output, "impl MessageParser {{")?;
 
     // Main parse_line function
-    generate_parse_line_function(&mut output, entities)?;
+    generate_parse_line_function(&mut output, entities, verbose)?;
 
     // Individual parser functions only for root entities
     for entity in entities {
@@ -75,8 +90,14 @@ pub fn generate_parsers_rs(
 fn generate_message_struct(
     output: &mut std::fs::File,
     entity: &EntityDef,
+    openapi_schemas: bool,
 ) -> Result<(), Box<dyn Error>> {
-    writeln!(output, "#[derive(Debug)]")?;
+    if openapi_schemas {
+        writeln!(output, "/// JSON body of a {} message", entity.name)?;
+        writeln!(output, "#[derive(Debug, utoipa::ToSchema)]")?;
+    } else {
+        writeln!(output, "#[derive(Debug)]")?;
+    }
     writeln!(output, "pub struct {}Message {{", entity.name)?;
 
     if let Some(ref persistence) = entity.persistence {
@@ -113,6 +134,7 @@ fn generate_message_struct(
 fn generate_parse_line_function(
     output: &mut std::fs::File,
     entities: &[EntityDef],
+    verbose: bool,
 ) -> Result<(), Box<dyn Error>> {
     writeln!(output, "    /// Parse JSON message and return entity name + parsed data + raw JSON value")?;
     writeln!(output, "    /// If entity_type_hint is provided, try that entity type first")?;
@@ -134,7 +156,7 @@ fn generate_parse_line_function(
     // Generate entity type hint matching first
     writeln!(output, "        // If entity_type hint is provided, try that first")?;
     writeln!(output, "        if let Some(entity_type) = entity_type_hint {{")?;
-    writeln!(output, "            eprintln!(\"[PARSER] Trying entity type hint: {{}}\", entity_type);")?;
+    writeln!(output, "            tracing::debug!(entity_type, \"Trying entity type hint\");")?;
     writeln!(output, "            match entity_type {{")?;
 
     for entity in entities {
@@ -150,27 +172,26 @@ fn generate_parse_line_function(
         writeln!(output, "                \"{}\" => {{", entity.name)?;
         writeln!(output, "                    match Self::parse_{}(obj) {{", entity.name.to_lowercase())?;
         writeln!(output, "                        Ok(msg) => {{")?;
-        writeln!(output, "                            eprintln!(\"[PARSER] Successfully parsed as {} (via hint)\");", entity.name)?;
+        writeln!(output, "                            tracing::debug!(entity_type = \"{}\", \"Parsed via type hint\");", entity.name)?;
         writeln!(output, "                            return Ok((\"{}\".to_string(), ParsedMessage::{}(msg), value.clone()));", entity.name, entity.name)?;
         writeln!(output, "                        }}")?;
         writeln!(output, "                        Err(e) => {{")?;
-        writeln!(output, "                            eprintln!(\"[PARSER] Failed to parse as {} (via hint): {{:?}}\", e);", entity.name)?;
+        writeln!(output, "                            tracing::warn!(entity_type = \"{}\", error = ?e, \"Failed to parse via type hint\");", entity.name)?;
         writeln!(output, "                        }}")?;
         writeln!(output, "                    }}")?;
         writeln!(output, "                }}")?;
     }
 
     writeln!(output, "                _ => {{")?;
-    writeln!(output, "                    eprintln!(\"[PARSER] Unknown entity type hint: {{}}\", entity_type);")?;
+    writeln!(output, "                    tracing::warn!(entity_type, \"Unknown entity type hint\");")?;
     writeln!(output, "                }}")?;
     writeln!(output, "            }}")?;
     writeln!(output, "        }}\n")?;
 
     // Generate fallback logic - only try root entities
     writeln!(output, "        // Fallback: Try to parse as each known root entity type")?;
-    writeln!(output, "        eprintln!(\"[PARSER] No type hint or hint failed, trying all known root entities...\");")?;
     writeln!(output, "        let available_fields: Vec<String> = obj.keys().map(|k| k.to_string()).collect();")?;
-    writeln!(output, "        eprintln!(\"[PARSER] Available fields in JSON: {{:?}}\", available_fields);\n")?;
+    writeln!(output, "        tracing::debug!(?available_fields, \"No usable type hint, trying all known root entities\");\n")?;
 
     for entity in entities {
         // Only include root entities
@@ -178,19 +199,25 @@ fn generate_parse_line_function(
             continue;
         }
 
-        writeln!(output, "        eprintln!(\"[PARSER] Trying to parse as {}...\");", entity.name)?;
+        if verbose {
+            writeln!(output, "        tracing::trace!(entity_type = \"{}\", \"Trying parser\");", entity.name)?;
+        }
         writeln!(output, "        match Self::parse_{}(obj) {{", entity.name.to_lowercase())?;
         writeln!(output, "            Ok(msg) => {{")?;
-        writeln!(output, "                eprintln!(\"[PARSER] Successfully parsed as {}\");", entity.name)?;
+        writeln!(output, "                tracing::debug!(entity_type = \"{}\", \"Parsed without type hint\");", entity.name)?;
         writeln!(output, "                return Ok((\"{}\".to_string(), ParsedMessage::{}(msg), value.clone()));", entity.name, entity.name)?;
         writeln!(output, "            }}")?;
-        writeln!(output, "            Err(e) => {{")?;
-        writeln!(output, "                eprintln!(\"[PARSER] Failed to parse as {}: {{:?}}\", e);", entity.name)?;
-        writeln!(output, "            }}")?;
+        if verbose {
+            writeln!(output, "            Err(e) => {{")?;
+            writeln!(output, "                tracing::trace!(entity_type = \"{}\", error = ?e, \"Parser did not match\");", entity.name)?;
+            writeln!(output, "            }}")?;
+        } else {
+            writeln!(output, "            Err(_) => {{}}")?;
+        }
         writeln!(output, "        }}")?;
     }
 
-    writeln!(output, "\n        eprintln!(\"[PARSER] No entity parsers succeeded\");")?;
+    writeln!(output, "\n        tracing::warn!(?available_fields, \"No entity parsers succeeded\");")?;
     writeln!(output, "        Err(AppError::InvalidFormat(\"Could not parse as any known entity type\".to_string()))")?;
     writeln!(output, "    }}\n")?;
 
@@ -220,14 +247,19 @@ fn generate_entity_parser(
         }
     }
 
-    // Add field checking logic
+    // Add field checking logic (a field is present under any of its input keys)
     if !required_fields.is_empty() {
+        let required: Vec<String> = required_fields.iter()
+            .map(|field| {
+                let keys: Vec<String> = input_keys(entity, field).iter().map(|k| format!("\"{}\"", k)).collect();
+                format!("(\"{}\", &[{}])", field, keys.join(", "))
+            })
+            .collect();
         writeln!(output, "        // Check for required fields")?;
-        writeln!(output, "        let required_fields = vec![{}];",
-            required_fields.iter().map(|f| format!("\"{}\"", f)).collect::<Vec<_>>().join(", "))?;
+        writeln!(output, "        let required_fields: &[(&str, &[&str])] = &[{}];", required.join(", "))?;
         writeln!(output, "        let missing_fields: Vec<String> = required_fields.iter()")?;
-        writeln!(output, "            .filter(|f| !obj.contains_key(**f))")?;
-        writeln!(output, "            .map(|f| f.to_string())")?;
+        writeln!(output, "            .filter(|(_, keys)| !keys.iter().any(|key| obj.contains_key(*key)))")?;
+        writeln!(output, "            .map(|(field, _)| field.to_string())")?;
         writeln!(output, "            .collect();")?;
         writeln!(output, "        if !missing_fields.is_empty() {{")?;
         writeln!(output, "            return Err(AppError::InvalidFormat(")?;
@@ -243,7 +275,7 @@ fn generate_entity_parser(
         for field in &persistence.field_overrides {
             let field_type_str = field.field_type.as_deref().unwrap_or("String");
             let is_nullable = field.nullable.unwrap_or(false);
-            let parse_expr = generate_json_parse_expression(field_type_str, &field.name, is_nullable);
+            let parse_expr = generate_json_parse_expression(entity, field_type_str, &field.name, is_nullable);
             writeln!(output, "            {}: {},", field.name, parse_expr)?;
         }
     } else {
@@ -251,7 +283,7 @@ fn generate_entity_parser(
         for field in &entity.fields {
             let field_type_str = &field.field_type;
             let is_nullable = field.nullable;
-            let parse_expr = generate_json_parse_expression(field_type_str, &field.name, is_nullable);
+            let parse_expr = generate_json_parse_expression(entity, field_type_str, &field.name, is_nullable);
             writeln!(output, "            {}: {},", field.name, parse_expr)?;
         }
     }
@@ -275,54 +307,139 @@ fn map_field_type(field_type: &str) -> String {
     }
 }
 
-fn generate_json_parse_expression(field_type: &str, field_name: &str, nullable: bool) -> String {
+/// Input keys of a root entity field, from its `json_input` (`rename_all`
+/// spelling, then aliases), matching what the serde derive accepts
+fn input_keys(entity: &EntityDef, field_name: &str) -> Vec<String> {
+    entity.json_input.as_ref()
+        .map_or_else(|| vec![field_name.to_string()], |json_input| json_input.input_keys(field_name))
+}
+
+fn generate_json_parse_expression(entity: &EntityDef, field_type: &str, field_name: &str, nullable: bool) -> String {
+    // First input key present in the object
+    let lookup: String = input_keys(entity, field_name).iter().enumerate()
+        .map(|(i, key)| match i {
+            0 => format!("obj.get(\"{}\")", key),
+            _ => format!(".or_else(|| obj.get(\"{}\"))", key),
+        })
+        .collect();
+
+    // `json_input.numbers_from_strings` also reads numbers sent as strings
+    let numbers_from_strings = entity.json_input.as_ref().is_some_and(|j| j.numbers_from_strings);
+    let (as_i64, as_f64) = if numbers_from_strings {
+        (
+            "v.as_i64().or_else(|| v.as_str().and_then(|s| s.trim().parse().ok()))",
+            "v.as_f64().or_else(|| v.as_str().and_then(|s| s.trim().parse().ok()))",
+        )
+    } else {
+        ("v.as_i64()", "v.as_f64()")
+    };
+
     if nullable {
         // For nullable fields, return Option<T>
         match field_type {
             "i32" | "Integer" => format!(
-                "obj.get(\"{}\").and_then(|v| if v.is_null() {{ None }} else {{ v.as_i64().map(|x| x as i32) }})",
-                field_name
+                "{}.and_then(|v| if v.is_null() {{ None }} else {{ {}.and_then(|x| i32::try_from(x).ok()) }})",
+                lookup, as_i64
             ),
             "i64" => format!(
-                "obj.get(\"{}\").and_then(|v| if v.is_null() {{ None }} else {{ v.as_i64() }})",
-                field_name
+                "{}.and_then(|v| if v.is_null() {{ None }} else {{ {} }})",
+                lookup, as_i64
             ),
             "Float" => format!(
-                "obj.get(\"{}\").and_then(|v| if v.is_null() {{ None }} else {{ v.as_f64() }})",
-                field_name
-            ),
-            "String" => format!(
-                "obj.get(\"{}\").and_then(|v| if v.is_null() {{ None }} else {{ v.as_str().map(|s| s.to_string()) }})",
-                field_name
+                "{}.and_then(|v| if v.is_null() {{ None }} else {{ {} }})",
+                lookup, as_f64
             ),
             _ => format!(
-                "obj.get(\"{}\").and_then(|v| if v.is_null() {{ None }} else {{ v.as_str().map(|s| s.to_string()) }})",
-                field_name
+                "{}.and_then(|v| if v.is_null() {{ None }} else {{ v.as_str().map(|s| s.to_string()) }})",
+                lookup
             ),
         }
     } else {
         // For required fields, return T with error handling
         match field_type {
             "i32" | "Integer" => format!(
-                "obj.get(\"{}\").and_then(|v| v.as_i64()).map(|v| v as i32).ok_or_else(|| AppError::InvalidField(\"{}\".to_string()))?",
-                field_name, field_name
+                "{}.and_then(|v| {}).and_then(|v| i32::try_from(v).ok()).ok_or_else(|| AppError::InvalidField(\"{}\".to_string()))?",
+                lookup, as_i64, field_name
             ),
             "i64" => format!(
-                "obj.get(\"{}\").and_then(|v| v.as_i64()).ok_or_else(|| AppError::InvalidField(\"{}\".to_string()))?",
-                field_name, field_name
+                "{}.and_then(|v| {}).ok_or_else(|| AppError::InvalidField(\"{}\".to_string()))?",
+                lookup, as_i64, field_name
             ),
             "Float" => format!(
-                "obj.get(\"{}\").and_then(|v| v.as_f64()).ok_or_else(|| AppError::InvalidField(\"{}\".to_string()))?",
-                field_name, field_name
-            ),
-            "String" => format!(
-                "obj.get(\"{}\").and_then(|v| v.as_str()).map(|s| s.to_string()).ok_or_else(|| AppError::InvalidField(\"{}\".to_string()))?",
-                field_name, field_name
+                "{}.and_then(|v| {}).ok_or_else(|| AppError::InvalidField(\"{}\".to_string()))?",
+                lookup, as_f64, field_name
             ),
             _ => format!(
-                "obj.get(\"{}\").and_then(|v| v.as_str()).map(|s| s.to_string()).ok_or_else(|| AppError::InvalidField(\"{}\".to_string()))?",
-                field_name, field_name
+                "{}.and_then(|v| v.as_str()).map(|s| s.to_string()).ok_or_else(|| AppError::InvalidField(\"{}\".to_string()))?",
+                lookup, field_name
             ),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_json_applies_json_input() {
+        let entities = crate::codegen::parse_entities(r#"
+entity:
+  name: Order
+  source_type: root
+  json_input:
+    rename_all: camelCase
+    numbers_from_strings: true
+    aliases:
+      order_key: [OrderID]
+  fields:
+    - name: order_key
+      type: Integer
+      nullable: false
+    - name: total_price
+      type: Float
+      nullable: true
+    - name: status
+      type: String
+      nullable: false
+"#).unwrap();
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("src")).unwrap();
+        write_parsers_rs(&entities, temp_dir.path(), false, false).unwrap();
+        let parsers = std::fs::read_to_string(temp_dir.path().join("src/parsers.rs")).unwrap();
+
+        // Required fields are present under their renamed key or an alias
+        assert!(parsers.contains(
+            r#"let required_fields: &[(&str, &[&str])] = &[("order_key", &["orderKey", "OrderID"]), ("status", &["status"])];"#
+        ), "{}", parsers);
+
+        // Numbers are read from the renamed/aliased keys, strings included
+        assert!(parsers.contains(
+            r#"order_key: obj.get("orderKey").or_else(|| obj.get("OrderID")).and_then(|v| v.as_i64().or_else(|| v.as_str().and_then(|s| s.trim().parse().ok()))).and_then(|v| i32::try_from(v).ok())"#
+        ), "{}", parsers);
+        assert!(parsers.contains(
+            r#"total_price: obj.get("totalPrice").and_then(|v| if v.is_null() { None } else { v.as_f64().or_else(|| v.as_str().and_then(|s| s.trim().parse().ok())) })"#
+        ), "{}", parsers);
+        assert!(!parsers.contains(r#"obj.get("order_key")"#), "{}", parsers);
+    }
+
+    #[test]
+    fn test_json_input_renamed_matches_serde() {
+        let rule = |rename_all: &str| crate::codegen::types::JsonInputConfig {
+            rename_all: Some(rename_all.to_string()),
+            ..Default::default()
+        };
+        let cases = [
+            ("camelCase", "total_price_usd", "totalPriceUsd"),
+            ("PascalCase", "total_price", "TotalPrice"),
+            ("SCREAMING_SNAKE_CASE", "total_price", "TOTAL_PRICE"),
+            ("kebab-case", "total_price", "total-price"),
+            ("SCREAMING-KEBAB-CASE", "total_price", "TOTAL-PRICE"),
+            ("snake_case", "total_price", "total_price"),
+        ];
+        for (rename_all, field, expected) in cases {
+            assert_eq!(rule(rename_all).renamed(field), expected, "{}", rename_all);
+        }
+    }
+}
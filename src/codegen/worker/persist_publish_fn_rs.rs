@@ -3,8 +3,12 @@
 /// - persist() for persistent entities: inserts to database
 /// - publish() for transient entities: publishes to NATS
 
-use crate::codegen::EntityDef;
-use crate::codegen::worker::DatabaseType;
+use crate::codegen::{EntityDef, FieldDef};
+use crate::codegen::types::UnicityCheck;
+use crate::codegen::utils::to_snake_case;
+use crate::codegen::worker::{DatabaseType, EntityPublishing};
+use crate::codegen::sql_dialect::{write_sql_query_per_feature, SqlDialect};
+use super::subjects::{self, generate_subject_functions};
 use std::error::Error;
 use std::io::Write;
 use std::path::Path;
@@ -13,7 +17,8 @@ use std::path::Path;
 pub fn generate_persist_publish_file(
     entities: &[EntityDef],
     output_dir: &Path,
-    db_type: DatabaseType,
+    _db_type: DatabaseType,
+    publishing: EntityPublishing,
 ) -> Result<(), Box<dyn Error>> {
     let file_path = output_dir.join("src/persist_publish.rs");
     let mut output = std::fs::File::create(&file_path)?;
@@ -29,6 +34,26 @@ pub fn generate_persist_publish_file(
     writeln!(output, "use diesel::prelude::*;")?;
     writeln!(output, "use diesel::sql_types::{{Text, Integer, BigInt, Double, Bool, Date, Numeric, Nullable}};\n")?;
 
+    // JSON documents are JSONB on Postgres and JSON on MySQL
+    writeln!(output, "#[cfg(feature = \"postgres\")]")?;
+    writeln!(output, "#[allow(dead_code)]")?;
+    writeln!(output, "type JsonSqlType = diesel::sql_types::Jsonb;")?;
+    writeln!(output, "#[cfg(feature = \"mysql\")]")?;
+    writeln!(output, "#[allow(dead_code)]")?;
+    writeln!(output, "type JsonSqlType = diesel::sql_types::Json;\n")?;
+
+    generate_publishing_functions(&mut output, entities, publishing)?;
+    generate_subject_functions(&mut output, entities)?;
+
+    if entities.iter().any(|e| e.generated_id_field(entities).is_some()) {
+        writeln!(output, "/// Id generated for a persisted row")?;
+        writeln!(output, "#[derive(QueryableByName)]")?;
+        writeln!(output, "struct GeneratedId {{")?;
+        writeln!(output, "    #[diesel(sql_type = BigInt)]")?;
+        writeln!(output, "    generated_id: i64,")?;
+        writeln!(output, "}}\n")?;
+    }
+
     // Generate functions for each entity
     for entity in entities {
         if entity.is_root() || entity.is_abstract {
@@ -36,9 +61,9 @@ pub fn generate_persist_publish_file(
         }
 
         if entity.is_persistent(entities) {
-            generate_persist_function(&mut output, entity, entities, db_type)?;
+            generate_persist_function(&mut output, entity, entities)?;
         } else {
-            generate_publish_function(&mut output, entity)?;
+            generate_publish_function(&mut output, entity, entities)?;
         }
         writeln!(output)?;
     }
@@ -46,12 +71,76 @@ pub fn generate_persist_publish_file(
     Ok(())
 }
 
+/// Generate the `PUBLISH_ENTITIES` switch deciding which entities go to NATS
+fn generate_publishing_functions(
+    output: &mut std::fs::File,
+    entities: &[EntityDef],
+    default: EntityPublishing,
+) -> Result<(), Box<dyn Error>> {
+    let variant = |mode: EntityPublishing| match mode {
+        EntityPublishing::None => "EntityPublishing::None",
+        EntityPublishing::Persistent => "EntityPublishing::Persistent",
+        EntityPublishing::Transient => "EntityPublishing::Transient",
+        EntityPublishing::All => "EntityPublishing::All",
+    };
+
+    writeln!(output, "/// Which entities are published to NATS")?;
+    writeln!(output, "#[derive(Debug, Clone, Copy, PartialEq, Eq)]")?;
+    writeln!(output, "#[allow(dead_code)]")?;
+    writeln!(output, "pub enum EntityPublishing {{")?;
+    writeln!(output, "    None,")?;
+    writeln!(output, "    Persistent,")?;
+    writeln!(output, "    Transient,")?;
+    writeln!(output, "    All,")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// Publishing mode from `PUBLISH_ENTITIES` (all/true, persistent, transient,")?;
+    writeln!(output, "/// none/false), defaulting to `{}`", default.as_str())?;
+    writeln!(output, "pub fn entity_publishing() -> EntityPublishing {{")?;
+    writeln!(output, "    static MODE: std::sync::OnceLock<EntityPublishing> = std::sync::OnceLock::new();")?;
+    writeln!(output, "    *MODE.get_or_init(|| match std::env::var(\"PUBLISH_ENTITIES\") {{")?;
+    writeln!(output, "        Err(_) => {},", variant(default))?;
+    writeln!(output, "        Ok(value) => match value.to_lowercase().as_str() {{")?;
+    writeln!(output, "            \"all\" | \"true\" => EntityPublishing::All,")?;
+    writeln!(output, "            \"persistent\" => EntityPublishing::Persistent,")?;
+    writeln!(output, "            \"transient\" => EntityPublishing::Transient,")?;
+    writeln!(output, "            \"none\" | \"false\" => EntityPublishing::None,")?;
+    writeln!(output, "            other => {{")?;
+    writeln!(output, "                tracing::warn!(value = other, \"Unknown PUBLISH_ENTITIES value, using default\");")?;
+    writeln!(output, "                {}", variant(default))?;
+    writeln!(output, "            }}")?;
+    writeln!(output, "        }},")?;
+    writeln!(output, "    }})")?;
+    writeln!(output, "}}\n")?;
+
+    let persistent: Vec<String> = entities.iter()
+        .filter(|e| e.is_persistent(entities))
+        .map(|e| format!("\"{}\"", e.name))
+        .collect();
+    writeln!(output, "/// Whether records of the entity are published to NATS")?;
+    writeln!(output, "pub fn publishes_entity(entity_name: &str) -> bool {{")?;
+    if persistent.is_empty() {
+        writeln!(output, "    let _ = entity_name;")?;
+        writeln!(output, "    let persistent = false;")?;
+    } else {
+        writeln!(output, "    let persistent = matches!(entity_name, {});", persistent.join(" | "))?;
+    }
+    writeln!(output, "    match entity_publishing() {{")?;
+    writeln!(output, "        EntityPublishing::None => false,")?;
+    writeln!(output, "        EntityPublishing::Persistent => persistent,")?;
+    writeln!(output, "        EntityPublishing::Transient => !persistent,")?;
+    writeln!(output, "        EntityPublishing::All => true,")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "}}\n")?;
+
+    Ok(())
+}
+
 /// Generate persist() function for a persistent entity
 fn generate_persist_function(
     output: &mut std::fs::File,
     entity: &EntityDef,
     all_entities: &[EntityDef],
-    db_type: DatabaseType,
 ) -> Result<(), Box<dyn Error>> {
     let db_config = entity.get_database_config(all_entities)
         .ok_or(format!("Entity {} is persistent but has no database config", entity.name))?;
@@ -76,40 +165,43 @@ fn generate_persist_function(
     } else {
         None
     };
+    let generated_id_field = entity.generated_id_field(all_entities);
+    // Inherited fields included, parents first, in struct declaration order
+    let field_defs = entity.field_defs(all_entities)?;
+    let column_name = |name: &str| field_defs.get(name)
+        .map(|f| f.column_name().to_string())
+        .unwrap_or_else(|| name.to_string());
 
     // Generate doc comment
-    writeln!(output, "/// Persist {} to database", entity.name)?;
-    writeln!(output, "pub async fn persist_{}(", to_snake_case(&entity.name))?;
-    writeln!(output, "    entity: &{},", entity.name)?;
+    if let Some(id_field) = generated_id_field {
+        writeln!(output, "/// Persist {} to database, storing the row's generated id in `entity.{}`", entity.name, id_field.name)?;
+        writeln!(output, "pub async fn persist_{}(", to_snake_case(&entity.name))?;
+        writeln!(output, "    entity: &mut {},", entity.name)?;
+    } else {
+        writeln!(output, "/// Persist {} to database", entity.name)?;
+        writeln!(output, "pub async fn persist_{}(", to_snake_case(&entity.name))?;
+        writeln!(output, "    entity: &{},", entity.name)?;
+    }
     writeln!(output, "    conn: &mut DbConnection,")?;
     writeln!(output, ") -> Result<(), AppError> {{")?;
 
-    // Generate unicity check if there are unicity fields
-    // Only require non-nullable, non-computed unicity fields to be non-empty
-    // Skip constant/derived fields (those with computed_from) from the check
-    let non_nullable_unicity_checks: Vec<String> = unicity_fields.iter()
+    // Check each unicity field (its `unicity_check`, or the default for its
+    // type) so rows aren't keyed on missing or placeholder values
+    // An explicit `when:` condition replaces these checks (it is enforced at extraction)
+    let unicity_checks: Vec<String> = unicity_fields.iter()
+        .filter(|_| entity.when.is_none())
         .filter_map(|field_name| {
-            // Find the field definition
-            entity.fields.iter()
-                .find(|f| &f.name == field_name)
-                .filter(|f| {
-                    // Only include non-nullable fields
-                    if f.nullable {
-                        return false;
-                    }
-                    // Skip computed/constant fields (from other entities)
-                    if f.computed_from.is_some() {
-                        return false;
-                    }
-                    true
-                })
-                .map(|_| format!("(entity.{}.is_some() && entity.{}.as_ref().map(|s| !s.is_empty()).unwrap_or(false))", field_name, field_name))
+            let field = field_defs.get(field_name.as_str())?;
+            let check = persistence_config
+                .and_then(|p| p.field_overrides.iter().find(|o| &o.name == field_name))
+                .and_then(|o| o.unicity_check);
+            unicity_check_condition(field, check)
         })
         .collect();
 
-    if !non_nullable_unicity_checks.is_empty() {
-        writeln!(output, "    // Check non-nullable, non-computed unicity fields are non-empty")?;
-        writeln!(output, "    if {} {{", non_nullable_unicity_checks.join(" && "))?;
+    if !unicity_checks.is_empty() {
+        writeln!(output, "    // Insert only if all unicity fields pass their checks")?;
+        writeln!(output, "    if {} {{", unicity_checks.join(" && "))?;
     } else {
         writeln!(output, "    {{")?;
     }
@@ -122,24 +214,19 @@ fn generate_persist_function(
 
     let mut field_names: Vec<String> = if let Some(persistence) = persistence_config {
         if !persistence.field_overrides.is_empty() {
-            // Get set of field names that actually exist in this entity
-            let entity_field_names: std::collections::HashSet<String> = entity.fields.iter()
-                .map(|f| f.name.clone())
-                .collect();
-
-            // Only include field_overrides that exist in the entity
-            persistence.field_overrides.iter()
-                .map(|f| f.name.clone())
-                .filter(|name| entity_field_names.contains(name))
+            // Only include field_overrides that exist in the entity, in field order
+            field_defs.keys()
+                .filter(|name| persistence.field_overrides.iter().any(|f| &f.name == *name))
+                .map(|name| name.to_string())
                 .collect()
         } else {
-            entity.fields.iter()
-                .map(|f| f.name.clone())
+            field_defs.keys()
+                .map(|name| name.to_string())
                 .collect()
         }
     } else {
-        entity.fields.iter()
-            .map(|f| f.name.clone())
+        field_defs.keys()
+            .map(|name| name.to_string())
             .collect()
     };
 
@@ -147,82 +234,180 @@ fn generate_persist_function(
     if let Some(ref pk_name) = autogenerated_pk_name {
         field_names.retain(|f| f != pk_name);
     }
+    if let Some(id_field) = generated_id_field {
+        field_names.retain(|f| f != &id_field.name);
+    }
 
-    let placeholders = if db_type == DatabaseType::PostgreSQL {
-        (1..=field_names.len())
-            .map(|i| format!("${}", i))
+    // SQL column names (field `column:` overrides the field name) and their
+    // VALUES slots; `pre_insert_sql` columns take an expression instead of a bind
+    let field_refs: Vec<&str> = field_names.iter().map(String::as_str).collect();
+    let insert_values = db_config.insert_values(&field_refs, |name| column_name(name))?;
+    let column_names: Vec<&str> = insert_values.iter()
+        .map(|(column, _)| column.as_str())
+        .collect();
+    let conflict_columns: Vec<String> = unicity_fields.iter()
+        .map(|name| column_name(name))
+        .collect();
+    let conflict_columns: Vec<&str> = conflict_columns.iter().map(String::as_str).collect();
+
+    // Match the partial unique index when soft-deleted rows are excluded
+    let predicate = entity.live_unicity_column(all_entities)
+        .map(|column| format!("{} IS NULL", column));
+    let insert_sql = |dialect: &dyn SqlDialect| {
+        let mut bind_count = 0;
+        let placeholders = insert_values.iter()
+            .map(|(_, value)| value.to_sql(&mut |_| {
+                bind_count += 1;
+                dialect.placeholder(bind_count)
+            }))
             .collect::<Vec<_>>()
-            .join(", ")
-    } else {
-        vec!["?"; field_names.len()].join(", ")
+            .join(", ");
+        dialect.insert_ignore_clause(table_name, &column_names, &placeholders, &conflict_columns, predicate.as_deref())
     };
 
-    writeln!(output, "        #[cfg(feature = \"postgres\")]")?;
-    writeln!(output, "        {{")?;
-    if !unicity_fields.is_empty() {
-        let conflict_fields = unicity_fields.join(", ");
-        writeln!(output, "            diesel::sql_query(")?;
-        writeln!(output, "                r#\"INSERT INTO {} ({}) VALUES ({}) ON CONFLICT ({}) DO NOTHING\"#",
-            table_name,
-            field_names.join(", "),
-            placeholders,
-            conflict_fields
-        )?;
-        writeln!(output, "            )")?;
+    // Bind field values in placeholder order
+    let binds: Vec<String> = insert_values.iter()
+        .flat_map(|(_, value)| value.binds())
+        .filter_map(|field_name| field_defs.get(field_name.as_str()).copied())
+        .map(bind_call)
+        .collect();
+
+    if let Some(id_field) = generated_id_field {
+        let id_column = id_field.column_name();
+        let lookup_binds: Vec<String> = unicity_fields.iter()
+            .filter_map(|name| field_defs.get(name.as_str()).copied())
+            .map(bind_call)
+            .collect();
+        let lookup_sql = |dialect: &dyn SqlDialect| {
+            let mut conditions: Vec<String> = conflict_columns.iter()
+                .enumerate()
+                .map(|(i, column)| format!("{} = {}", column, dialect.placeholder(i + 1)))
+                .collect();
+            conditions.extend(predicate.clone());
+            format!(
+                "SELECT {} FROM {} WHERE {}",
+                dialect.generated_id_select(id_column), table_name, conditions.join(" AND ")
+            )
+        };
+        write_insert_capturing_id(output, entity, id_field, &insert_sql, &binds, &lookup_sql, &lookup_binds)?;
     } else {
-        writeln!(output, "            diesel::sql_query(")?;
-        writeln!(output, "                r#\"INSERT INTO {} ({}) VALUES ({})\"#",
-            table_name,
-            field_names.join(", "),
-            placeholders
-        )?;
-        writeln!(output, "            )")?;
+        write_sql_query_per_feature(output, "        ", insert_sql)?;
+        for bind in &binds {
+            writeln!(output, "        {}", bind)?;
+        }
+        writeln!(output, "        .execute(conn)")?;
+        writeln!(output, "        .map_err(|source| AppError::Persistence {{ entity: \"{}\".to_string(), source }})?;", entity.name)?;
     }
-    writeln!(output, "        }}")?;
-
-    writeln!(output, "        #[cfg(feature = \"mysql\")]")?;
-    writeln!(output, "        {{")?;
-    if !unicity_fields.is_empty() {
-        writeln!(output, "            diesel::sql_query(")?;
-        writeln!(output, "                r#\"INSERT IGNORE INTO {} ({}) VALUES ({})\"#",
-            table_name,
-            field_names.join(", "),
-            placeholders
-        )?;
-        writeln!(output, "            )")?;
+
+    writeln!(output, "    }}")?;
+    writeln!(output, "    Ok(())")?;
+    writeln!(output, "}}")?;
+
+    Ok(())
+}
+
+/// Condition `entity.<field>` must meet before the row is inserted (None
+/// when every value passes); `check` defaults to the one for the field's type
+///
+/// Nullable fields are `Option`s, except lists which are never wrapped.
+fn unicity_check_condition(field: &FieldDef, check: Option<UnicityCheck>) -> Option<String> {
+    let value = format!("entity.{}", field.name);
+    let is_list = field.field_type.starts_with("List[") || field.field_type.starts_with("Vec<");
+    let is_optional = field.nullable && !is_list;
+    let zero = match super::entities_rs::map_field_type(&field.field_type) {
+        "f64" => "0.0",
+        "Decimal" => "rust_decimal::Decimal::ZERO",
+        _ => "0",
+    };
+    match check.unwrap_or_else(|| UnicityCheck::default_for(&field.field_type)) {
+        UnicityCheck::None => None,
+        UnicityCheck::Present if is_optional => Some(format!("{}.is_some()", value)),
+        UnicityCheck::Present => None,
+        UnicityCheck::NonEmpty if is_optional => Some(format!("{}.as_ref().is_some_and(|v| !v.is_empty())", value)),
+        UnicityCheck::NonEmpty => Some(format!("!{}.is_empty()", value)),
+        UnicityCheck::NonZero if is_optional => Some(format!("{}.as_ref().is_some_and(|v| *v != {})", value, zero)),
+        UnicityCheck::NonZero => Some(format!("{} != {}", value, zero)),
+    }
+}
+
+/// `.bind()` call passing an entity field
+fn bind_call(field: &FieldDef) -> String {
+    let sql_type = map_field_to_sql_type(&field.field_type);
+    // List types are never wrapped in Option, so never use Nullable binding
+    let is_list = field.field_type.starts_with("List[") || field.field_type.starts_with("Vec<");
+    if field.nullable && !is_list {
+        format!(".bind::<Nullable<{}>, _>(&entity.{})", sql_type, field.name)
     } else {
-        writeln!(output, "            diesel::sql_query(")?;
-        writeln!(output, "                r#\"INSERT INTO {} ({}) VALUES ({})\"#",
-            table_name,
-            field_names.join(", "),
-            placeholders
-        )?;
-        writeln!(output, "            )")?;
+        format!(".bind::<{}, _>(&entity.{})", sql_type, field.name)
     }
-    writeln!(output, "        }}")?;
-
-    // Bind field values (only those in field_names)
-    for field_name in &field_names {
-        // Find the field definition to get its type
-        if let Some(field) = entity.fields.iter().find(|f| &f.name == field_name) {
-            let sql_type = map_field_to_sql_type(&field.field_type);
-            // List types are never wrapped in Option, so never use Nullable binding
-            let is_list = field.field_type.starts_with("List[") || field.field_type.starts_with("Vec<");
-            if field.nullable && !is_list {
-                writeln!(output, "        .bind::<Nullable<{}>, _>(&entity.{})", sql_type, field.name)?;
-            } else {
-                writeln!(output, "        .bind::<{}, _>(&entity.{})", sql_type, field.name)?;
+}
+
+/// Write an INSERT storing the row's generated id in `id_field`
+///
+/// PostgreSQL returns the id from the INSERT, MySQL reads `LAST_INSERT_ID()`
+/// after it. An insert skipped as a duplicate looks up the stored row by the
+/// unicity columns instead.
+fn write_insert_capturing_id(
+    output: &mut std::fs::File,
+    entity: &EntityDef,
+    id_field: &FieldDef,
+    insert_sql: &dyn Fn(&dyn SqlDialect) -> String,
+    binds: &[String],
+    lookup_sql: &dyn Fn(&dyn SqlDialect) -> String,
+    lookup_binds: &[String],
+) -> Result<(), Box<dyn Error>> {
+    let persistence_error = format!(".map_err(|source| AppError::Persistence {{ entity: \"{}\".to_string(), source }})?", entity.name);
+
+    for db_type in [DatabaseType::PostgreSQL, DatabaseType::MySQL] {
+        writeln!(output, "        #[cfg(feature = \"{}\")]", db_type.cargo_feature())?;
+        match db_type.insert_returning_id(&insert_sql(&db_type), id_field.column_name()) {
+            Some(sql) => {
+                writeln!(output, "        let generated_id = diesel::sql_query(r#\"{}\"#)", sql)?;
+                for bind in binds {
+                    writeln!(output, "            {}", bind)?;
+                }
+                writeln!(output, "            .get_result::<GeneratedId>(conn)")?;
+                writeln!(output, "            .optional();")?;
+            }
+            None => {
+                writeln!(output, "        let generated_id = diesel::sql_query(r#\"{}\"#)", insert_sql(&db_type))?;
+                for bind in binds {
+                    writeln!(output, "            {}", bind)?;
+                }
+                writeln!(output, "            .execute(conn)")?;
+                writeln!(output, "            .and_then(|inserted| match inserted {{")?;
+                writeln!(output, "                0 => Ok(None),")?;
+                writeln!(output, "                _ => diesel::sql_query(\"{}\").get_result::<GeneratedId>(conn).map(Some),", db_type.last_insert_id_query())?;
+                writeln!(output, "            }});")?;
             }
         }
     }
 
-    writeln!(output, "        .execute(conn)")?;
-    writeln!(output, "        .map_err(|e| AppError::ValidationError(format!(\"Failed to insert {}: {{}}\", e)))?;", entity.name)?;
-
-    writeln!(output, "    }}")?;
-    writeln!(output, "    Ok(())")?;
-    writeln!(output, "}}")?;
+    if lookup_binds.is_empty() {
+        writeln!(output, "        let generated_id = generated_id{};", persistence_error)?;
+    } else {
+        writeln!(output, "        let mut generated_id = generated_id{};", persistence_error)?;
+        writeln!(output, "        // Skipped as a duplicate: use the stored row's id")?;
+        writeln!(output, "        if generated_id.is_none() {{")?;
+        for db_type in [DatabaseType::PostgreSQL, DatabaseType::MySQL] {
+            writeln!(output, "            #[cfg(feature = \"{}\")]", db_type.cargo_feature())?;
+            writeln!(output, "            let existing = diesel::sql_query(r#\"{}\"#)", lookup_sql(&db_type))?;
+            for bind in lookup_binds {
+                writeln!(output, "                {}", bind)?;
+            }
+            writeln!(output, "                .get_result::<GeneratedId>(conn)")?;
+            writeln!(output, "                .optional();")?;
+        }
+        writeln!(output, "            generated_id = existing{};", persistence_error)?;
+        writeln!(output, "        }}")?;
+    }
 
+    let id_type = super::entities_rs::map_field_type(&id_field.field_type);
+    if id_type == "i64" {
+        writeln!(output, "        entity.{} = generated_id.map(|row| row.generated_id);", id_field.name)?;
+    } else {
+        writeln!(output, "        entity.{} = generated_id.map(|row| row.generated_id as {});", id_field.name, id_type)?;
+    }
     Ok(())
 }
 
@@ -230,6 +415,7 @@ fn generate_persist_function(
 fn generate_publish_function(
     output: &mut std::fs::File,
     entity: &EntityDef,
+    all_entities: &[EntityDef],
 ) -> Result<(), Box<dyn Error>> {
     // Generate doc comment
     writeln!(output, "/// Publish {} to NATS", entity.name)?;
@@ -237,22 +423,25 @@ fn generate_publish_function(
     writeln!(output, "    entity: &{},", entity.name)?;
     writeln!(output, "    jetstream: &jetstream::Context,")?;
     writeln!(output, ") -> Result<(), AppError> {{")?;
+    writeln!(output, "    if !publishes_entity(\"{}\") {{", entity.name)?;
+    writeln!(output, "        return Ok(());")?;
+    writeln!(output, "    }}\n")?;
 
     writeln!(output, "    let mut entity_json = serde_json::Map::new();")?;
 
     // Add all fields to JSON
-    for field in &entity.fields {
+    for field in entity.field_defs(all_entities)?.values() {
         // List types are never wrapped in Option, non-nullable types are also not wrapped
         let is_list = field.field_type.starts_with("List[") || field.field_type.starts_with("Vec<");
         let is_optional = field.nullable && !is_list;
 
         if is_optional {
             writeln!(output, "    if let Some(ref val) = entity.{} {{", field.name)?;
-            writeln!(output, "        entity_json.insert(\"{}\".to_string(), serde_json::json!(val));", field.name)?;
+            writeln!(output, "        entity_json.insert(\"{}\".to_string(), serde_json::json!(val));", field.serialized_name())?;
             writeln!(output, "    }}")?;
         } else {
             // Non-optional field - always include
-            writeln!(output, "    entity_json.insert(\"{}\".to_string(), serde_json::json!(&entity.{}));", field.name, field.name)?;
+            writeln!(output, "    entity_json.insert(\"{}\".to_string(), serde_json::json!(&entity.{}));", field.serialized_name(), field.name)?;
         }
     }
 
@@ -260,16 +449,16 @@ fn generate_publish_function(
     writeln!(output, "    // Only publish if entity has actual data")?;
     writeln!(output, "    if !entity_json.is_empty() {{")?;
     writeln!(output, "        let entity_json_str = serde_json::to_string(&entity_json)")?;
-    writeln!(output, "            .map_err(|e| AppError::ValidationError(format!(\"Failed to serialize {}: {{}}\", e)))?;", entity.name)?;
-    writeln!(output, "        let stream_subject = format!(\"entities.{}\");", entity.name)?;
+    writeln!(output, "            .map_err(|e| AppError::Publish {{ entity: \"{}\".to_string(), message: format!(\"serialization failed: {{}}\", e) }})?;", entity.name)?;
+    writeln!(output, "        let stream_subject = {};", subjects::subject_expr(entity, "entity_json")?)?;
     writeln!(output, "        jetstream.publish(stream_subject.clone(), entity_json_str.into()).await")?;
     writeln!(output, "            .map_err(|e| {{")?;
-    writeln!(output, "                eprintln!(\"[WORKER] Failed to publish {} to {{}}: {{:?}}\", stream_subject, e);", entity.name)?;
-    writeln!(output, "                AppError::ValidationError(format!(\"NATS publish failed: {{}}\", e))")?;
+    writeln!(output, "                tracing::error!(entity_type = \"{}\", subject = %stream_subject, error = ?e, \"Failed to publish entity\");", entity.name)?;
+    writeln!(output, "                AppError::Publish {{ entity: \"{}\".to_string(), message: e.to_string() }}", entity.name)?;
     writeln!(output, "            }})?;")?;
-    writeln!(output, "        eprintln!(\"[WORKER] ✓ Published {} to {{}}\", stream_subject);", entity.name)?;
+    writeln!(output, "        tracing::debug!(entity_type = \"{}\", subject = %stream_subject, \"Published entity\");", entity.name)?;
     writeln!(output, "    }} else {{")?;
-    writeln!(output, "        eprintln!(\"[WORKER] Skipped {} (no data extracted from segments)\");", entity.name)?;
+    writeln!(output, "        tracing::debug!(entity_type = \"{}\", \"Skipped entity (no data extracted)\");", entity.name)?;
     writeln!(output, "    }}")?;
 
     writeln!(output, "    Ok(())")?;
@@ -289,27 +478,67 @@ fn map_field_to_sql_type(field_type: &str) -> &str {
         "date" => "Date",
         "decimal" | "numeric" => "Numeric",
         "vec<string>" | "list[string]" => "Text", // Serialized as JSON
+        "json" | "object" => "JsonSqlType",
         _ => "Text", // Default to Text
     }
 }
 
-/// Convert CamelCase to snake_case
-fn to_snake_case(s: &str) -> String {
-    let mut result = String::new();
-    let mut prev_is_upper = false;
 
-    for (i, ch) in s.chars().enumerate() {
-        if ch.is_uppercase() {
-            if i > 0 && !prev_is_upper {
-                result.push('_');
-            }
-            result.push(ch.to_lowercase().next().unwrap());
-            prev_is_upper = true;
-        } else {
-            result.push(ch);
-            prev_is_upper = false;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, field_type: &str, nullable: bool) -> FieldDef {
+        FieldDef {
+            name: name.to_string(),
+            field_type: field_type.to_string(),
+            nullable,
+            ..Default::default()
         }
     }
 
-    result
+    /// Generated persist function of an entity keyed on `unicity_fields`
+    fn persist_code(fields: Vec<FieldDef>, persistence: &str) -> String {
+        let claim = EntityDef {
+            name: "Claim".to_string(),
+            source_type: "derived".to_string(),
+            fields,
+            persistence: Some(serde_yaml::from_str(persistence).unwrap()),
+            ..Default::default()
+        };
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        generate_persist_publish_file(&[claim], dir.path(), DatabaseType::PostgreSQL, EntityPublishing::None).unwrap();
+        let code = std::fs::read_to_string(dir.path().join("src/persist_publish.rs")).unwrap();
+        let start = code.find("pub async fn persist_claim(").unwrap();
+        code[start..].to_string()
+    }
+
+    #[test]
+    fn test_persist_guards_every_unicity_field_type() {
+        let code = persist_code(
+            vec![
+                field("claim_number", "Integer", true),
+                field("member_id", "String", false),
+                field("service_date", "Date", false),
+            ],
+            "database: {conformant_table: claims, conformant_id_column: id, unicity_fields: [claim_number, member_id, service_date]}",
+        );
+        assert!(code.contains(
+            "    if entity.claim_number.is_some() && !entity.member_id.is_empty() {\n"
+        ), "{}", code);
+
+        let code = persist_code(
+            vec![field("claim_number", "Integer", true), field("amount", "Float", false)],
+            r#"
+database: {conformant_table: claims, conformant_id_column: id, unicity_fields: [claim_number, amount]}
+field_overrides:
+  - {name: claim_number, type: Integer, unicity_check: non_zero}
+  - {name: amount, type: Float, unicity_check: none}
+"#,
+        );
+        assert!(code.contains(
+            "    if entity.claim_number.as_ref().is_some_and(|v| *v != 0) {\n"
+        ), "{}", code);
+    }
 }
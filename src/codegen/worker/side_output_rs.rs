@@ -0,0 +1,192 @@
+/// Generate side_output.rs, archiving raw bodies and extracted entities
+///
+/// With side output enabled the worker writes, for every processed message,
+/// the decoded body and the entities extracted from it to a key/value sink
+/// keyed by message id, so messages can be reprocessed after a logic fix
+/// without re-ingesting them from the source. NATS KV is built in; any other
+/// store (e.g. S3) implements the generated `SideOutput` trait in a Rust file
+/// copied into the worker as `custom_side_output.rs`.
+
+use std::path::Path;
+use std::error::Error;
+use std::io::Write;
+
+/// Generate `src/side_output.rs` (and `src/custom_side_output.rs` from
+/// `custom_sink`, if given)
+pub fn generate_side_output_rs(
+    output_dir: &Path,
+    custom_sink: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+    let file_path = output_dir.join("src/side_output.rs");
+    let mut output = std::fs::File::create(&file_path)?;
+
+    writeln!(output, "//! Side output: raw bodies and extracted entities archived by message id")?;
+    writeln!(output, "//!")?;
+    writeln!(output, "//! `SIDE_OUTPUT` selects the sink: `nats_kv` (default, bucket `SIDE_OUTPUT_BUCKET`),")?;
+    writeln!(output, "//! `custom` (custom_side_output.rs) or `none`. A failed write is logged and")?;
+    writeln!(output, "//! never fails the message: the database stays the source of truth.\n")?;
+
+    writeln!(output, "use async_nats::jetstream;")?;
+    writeln!(output, "use futures::future::BoxFuture;")?;
+    writeln!(output, "use std::collections::BTreeMap;")?;
+    writeln!(output, "use std::sync::OnceLock;\n")?;
+
+    writeln!(output, "/// Entities extracted from one message, by entity type")?;
+    writeln!(output, "pub type ExtractedEntities = BTreeMap<String, Vec<serde_json::Value>>;\n")?;
+
+    writeln!(output, "/// Record an extracted entity (a no-op without a sink)")?;
+    writeln!(output, "pub fn record<T: serde::Serialize>(extracted: &mut ExtractedEntities, entity_type: &str, entity: &T) {{")?;
+    writeln!(output, "    if !enabled() {{")?;
+    writeln!(output, "        return;")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "    match serde_json::to_value(entity) {{")?;
+    writeln!(output, "        Ok(value) => extracted.entry(entity_type.to_string()).or_default().push(value),")?;
+    writeln!(output, "        Err(e) => tracing::warn!(entity_type, error = %e, \"Failed to serialize entity for side output\"),")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// What is archived for one message")?;
+    writeln!(output, "#[derive(Debug, serde::Serialize)]")?;
+    writeln!(output, "pub struct SideOutputRecord<'a> {{")?;
+    writeln!(output, "    pub message_id: String,")?;
+    writeln!(output, "    pub entity_type: &'a str,")?;
+    writeln!(output, "    pub correlation_id: Option<&'a str>,")?;
+    writeln!(output, "    /// Decoded message body, as parsed")?;
+    writeln!(output, "    pub raw_body: &'a str,")?;
+    writeln!(output, "    pub entities: &'a ExtractedEntities,")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// Key/value store the side output is written to")?;
+    writeln!(output, "pub trait SideOutput: Send + Sync {{")?;
+    writeln!(output, "    /// Store `value` under `key` (the message id), replacing any previous value")?;
+    writeln!(output, "    fn put<'a>(&'a self, key: &'a str, value: Vec<u8>) -> BoxFuture<'a, Result<(), String>>;")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// NATS JetStream key/value bucket")?;
+    writeln!(output, "pub struct NatsKvSideOutput {{")?;
+    writeln!(output, "    store: jetstream::kv::Store,")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "impl NatsKvSideOutput {{")?;
+    writeln!(output, "    /// Open the bucket, creating it if it doesn't exist")?;
+    writeln!(output, "    pub async fn open(jetstream: &jetstream::Context, bucket: &str) -> Result<Self, String> {{")?;
+    writeln!(output, "        let store = match jetstream.get_key_value(bucket).await {{")?;
+    writeln!(output, "            Ok(store) => store,")?;
+    writeln!(output, "            Err(_) => jetstream")?;
+    writeln!(output, "                .create_key_value(jetstream::kv::Config {{")?;
+    writeln!(output, "                    bucket: bucket.to_string(),")?;
+    writeln!(output, "                    history: 1,")?;
+    writeln!(output, "                    storage: jetstream::stream::StorageType::File,")?;
+    writeln!(output, "                    ..Default::default()")?;
+    writeln!(output, "                }})")?;
+    writeln!(output, "                .await")?;
+    writeln!(output, "                .map_err(|e| format!(\"Failed to create KV bucket '{{}}': {{}}\", bucket, e))?,")?;
+    writeln!(output, "        }};")?;
+    writeln!(output, "        Ok(Self {{ store }})")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "impl SideOutput for NatsKvSideOutput {{")?;
+    writeln!(output, "    fn put<'a>(&'a self, key: &'a str, value: Vec<u8>) -> BoxFuture<'a, Result<(), String>> {{")?;
+    writeln!(output, "        Box::pin(async move {{")?;
+    writeln!(output, "            self.store.put(key, value.into()).await")?;
+    writeln!(output, "                .map(|_| ())")?;
+    writeln!(output, "                .map_err(|e| e.to_string())")?;
+    writeln!(output, "        }})")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "static SINK: OnceLock<Option<Box<dyn SideOutput>>> = OnceLock::new();\n")?;
+
+    writeln!(output, "/// Open the sink selected by `SIDE_OUTPUT`")?;
+    writeln!(output, "pub async fn init(jetstream: &jetstream::Context) -> Result<(), String> {{")?;
+    writeln!(output, "    let kind = std::env::var(\"SIDE_OUTPUT\").unwrap_or_else(|_| \"nats_kv\".to_string());")?;
+    writeln!(output, "    let sink: Option<Box<dyn SideOutput>> = match kind.trim().to_lowercase().as_str() {{")?;
+    writeln!(output, "        \"\" | \"none\" => None,")?;
+    writeln!(output, "        \"nats_kv\" => {{")?;
+    writeln!(output, "            let bucket = std::env::var(\"SIDE_OUTPUT_BUCKET\").unwrap_or_else(|_| \"SIDE_OUTPUT\".to_string());")?;
+    writeln!(output, "            tracing::info!(bucket = %bucket, \"Side output to NATS KV\");")?;
+    writeln!(output, "            Some(Box::new(NatsKvSideOutput::open(jetstream, &bucket).await?))")?;
+    writeln!(output, "        }}")?;
+    if custom_sink.is_some() {
+        writeln!(output, "        \"custom\" => {{")?;
+        writeln!(output, "            tracing::info!(\"Side output to custom sink\");")?;
+        writeln!(output, "            Some(crate::custom_side_output::open().await?)")?;
+        writeln!(output, "        }}")?;
+        writeln!(output, "        other => return Err(format!(\"Unknown SIDE_OUTPUT '{{}}' (expected nats_kv, custom or none)\", other)),")?;
+    } else {
+        writeln!(output, "        other => return Err(format!(\"Unknown SIDE_OUTPUT '{{}}' (expected nats_kv or none)\", other)),")?;
+    }
+    writeln!(output, "    }};")?;
+    writeln!(output, "    SINK.set(sink).map_err(|_| \"Side output already initialized\".to_string())")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// Whether a sink is configured")?;
+    writeln!(output, "pub fn enabled() -> bool {{")?;
+    writeln!(output, "    matches!(SINK.get(), Some(Some(_)))")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// Archive a processed message")?;
+    writeln!(output, "pub async fn write(record: &SideOutputRecord<'_>) {{")?;
+    writeln!(output, "    let Some(Some(sink)) = SINK.get() else {{")?;
+    writeln!(output, "        return;")?;
+    writeln!(output, "    }};")?;
+    writeln!(output, "    let value = match serde_json::to_vec(record) {{")?;
+    writeln!(output, "        Ok(value) => value,")?;
+    writeln!(output, "        Err(e) => {{")?;
+    writeln!(output, "            tracing::warn!(message_id = %record.message_id, error = %e, \"Failed to serialize side output\");")?;
+    writeln!(output, "            return;")?;
+    writeln!(output, "        }}")?;
+    writeln!(output, "    }};")?;
+    writeln!(output, "    match sink.put(&record.message_id, value).await {{")?;
+    writeln!(output, "        Ok(()) => tracing::debug!(message_id = %record.message_id, \"Wrote side output\"),")?;
+    writeln!(output, "        Err(e) => tracing::warn!(message_id = %record.message_id, error = %e, \"Failed to write side output\"),")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "}}")?;
+
+    if let Some(custom_sink) = custom_sink {
+        let code = std::fs::read_to_string(custom_sink)
+            .map_err(|e| format!("Failed to read side output sink {}: {}", custom_sink.display(), e))?;
+        if !code.contains("fn open(") {
+            return Err(format!(
+                "Side output sink {} must define `pub async fn open() -> Result<Box<dyn crate::side_output::SideOutput>, String>`",
+                custom_sink.display()
+            ).into());
+        }
+        let mut custom = std::fs::File::create(output_dir.join("src/custom_side_output.rs"))?;
+        writeln!(custom, "// Copied from {}", custom_sink.display())?;
+        writeln!(custom, "// Implements crate::side_output::SideOutput; opened when SIDE_OUTPUT=custom\n")?;
+        write!(custom, "{}", code)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_side_output_rs() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+
+        generate_side_output_rs(dir.path(), None).unwrap();
+        let code = std::fs::read_to_string(dir.path().join("src/side_output.rs")).unwrap();
+        assert!(code.contains("pub trait SideOutput: Send + Sync"));
+        assert!(code.contains("impl SideOutput for NatsKvSideOutput"));
+        assert!(!code.contains("custom_side_output::open"));
+        assert!(!dir.path().join("src/custom_side_output.rs").exists());
+
+        let sink = dir.path().join("s3_sink.rs");
+        std::fs::write(&sink, "pub async fn open() -> Result<Box<dyn crate::side_output::SideOutput>, String> { todo!() }\n").unwrap();
+        generate_side_output_rs(dir.path(), Some(&sink)).unwrap();
+        let code = std::fs::read_to_string(dir.path().join("src/side_output.rs")).unwrap();
+        assert!(code.contains("Some(crate::custom_side_output::open().await?)"));
+        let custom = std::fs::read_to_string(dir.path().join("src/custom_side_output.rs")).unwrap();
+        assert!(custom.contains("pub async fn open()"));
+
+        std::fs::write(&sink, "// no constructor\n").unwrap();
+        assert!(generate_side_output_rs(dir.path(), Some(&sink)).is_err());
+    }
+}
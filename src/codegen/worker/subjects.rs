@@ -0,0 +1,208 @@
+/// NATS subject templates for published entities
+///
+/// An entity's `publish_subject` is a dot-separated subject where tokens may
+/// contain placeholders:
+///
+/// - `{name}`: the entity name
+/// - `{env:VAR}`: environment variable `VAR`, read when publishing
+/// - `{field}`: value of one of the entity's fields in the published record,
+///   named by the key it is published under (`serialized_as` when set)
+///
+/// ```yaml
+/// entity:
+///   name: Order
+///   publish_subject: "entities.{env:DEPLOY_ENV}.{tenant_id}.{name}"
+/// ```
+///
+/// Runtime values are sanitized into a single token; missing values become `_`.
+
+use crate::codegen::EntityDef;
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::io::Write;
+
+/// Subject used when an entity doesn't set `publish_subject`
+const DEFAULT_SUBJECT: &str = "entities.{name}";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SubjectPart {
+    Text(String),
+    Name,
+    Env(String),
+    Field(String),
+}
+
+/// Parse and validate an entity's subject template
+fn parse_subject(entity: &EntityDef) -> Result<Vec<SubjectPart>, String> {
+    let template = entity.publish_subject.as_deref().unwrap_or(DEFAULT_SUBJECT);
+    let invalid = |reason: &str| format!(
+        "Entity '{}': invalid publish_subject '{}': {}", entity.name, template, reason
+    );
+
+    if template.split('.').any(str::is_empty) {
+        return Err(invalid("empty subject token"));
+    }
+    if template.contains(['*', '>', ' ']) {
+        return Err(invalid("wildcards and spaces are not allowed in a publish subject"));
+    }
+    if template.split('.').next().is_some_and(|first| first.contains('{')) {
+        return Err(invalid("the first token must be literal so the stream can subscribe to it"));
+    }
+
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        if start > 0 {
+            parts.push(SubjectPart::Text(rest[..start].to_string()));
+        }
+        let end = rest[start..].find('}')
+            .ok_or_else(|| invalid("unterminated '{'"))?;
+        let token = &rest[start + 1..start + end];
+        parts.push(match token.split_once(':') {
+            None if token == "name" => SubjectPart::Name,
+            Some(("env", var)) if !var.is_empty() => SubjectPart::Env(var.to_string()),
+            None if entity.fields.iter().any(|f| f.serialized_name() == token) => SubjectPart::Field(token.to_string()),
+            _ => return Err(invalid(&format!("unknown placeholder '{{{}}}'", token))),
+        });
+        rest = &rest[start + end + 1..];
+    }
+    if rest.contains('}') {
+        return Err(invalid("unmatched '}'"));
+    }
+    if !rest.is_empty() {
+        parts.push(SubjectPart::Text(rest.to_string()));
+    }
+    Ok(parts)
+}
+
+/// Rust expression building the entity's subject
+///
+/// `record` names a generated variable with a `get(&str) -> Option<&Value>`
+/// method (a JSON object or map) holding the published fields.
+pub(super) fn subject_expr(entity: &EntityDef, record: &str) -> Result<String, String> {
+    let mut format_str = String::new();
+    let mut args = Vec::new();
+    for part in parse_subject(entity)? {
+        match part {
+            SubjectPart::Text(text) => format_str.push_str(&text),
+            SubjectPart::Name => format_str.push_str(&entity.name),
+            SubjectPart::Env(var) => {
+                format_str.push_str("{}");
+                args.push(format!(
+                    "crate::persist_publish::subject_token(std::env::var(\"{}\").ok().map(serde_json::Value::from).as_ref())",
+                    var
+                ));
+            }
+            SubjectPart::Field(field) => {
+                format_str.push_str("{}");
+                args.push(format!("crate::persist_publish::subject_token({}.get(\"{}\"))", record, field));
+            }
+        }
+    }
+
+    if args.is_empty() {
+        Ok(format!("\"{}\".to_string()", format_str))
+    } else {
+        Ok(format!("format!(\"{}\", {})", format_str, args.join(", ")))
+    }
+}
+
+/// Subjects the ENTITIES stream must capture: `<first token>.>` for every
+/// subject in use
+pub(super) fn stream_subjects(entities: &[EntityDef]) -> Result<Vec<String>, String> {
+    let mut prefixes = BTreeSet::from(["entities".to_string()]);
+    for entity in entities {
+        if let Some(SubjectPart::Text(text)) = parse_subject(entity)?.first() {
+            let first = text.split('.').next().unwrap_or_default();
+            prefixes.insert(first.to_string());
+        }
+    }
+    Ok(prefixes.into_iter().map(|prefix| format!("{}.>", prefix)).collect())
+}
+
+/// Generate `subject_token` and `entity_subject` for the worker's persist_publish.rs
+pub(super) fn generate_subject_functions(
+    output: &mut std::fs::File,
+    entities: &[EntityDef],
+) -> Result<(), Box<dyn Error>> {
+    writeln!(output, "/// Subject token for a runtime value")?;
+    writeln!(output, "///")?;
+    writeln!(output, "/// Missing or empty values become `_`; separators, wildcards and spaces")?;
+    writeln!(output, "/// are replaced so the value stays a single token.")?;
+    writeln!(output, "pub fn subject_token(value: Option<&serde_json::Value>) -> String {{")?;
+    writeln!(output, "    let text = match value {{")?;
+    writeln!(output, "        None | Some(serde_json::Value::Null) => return \"_\".to_string(),")?;
+    writeln!(output, "        Some(serde_json::Value::String(s)) => s.clone(),")?;
+    writeln!(output, "        Some(other) => other.to_string(),")?;
+    writeln!(output, "    }};")?;
+    writeln!(output, "    if text.is_empty() {{")?;
+    writeln!(output, "        return \"_\".to_string();")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "    text.replace(['.', '*', '>', ' '], \"_\")")?;
+    writeln!(output, "}}\n")?;
+
+    writeln!(output, "/// NATS subject a parsed entity record is published to")?;
+    writeln!(output, "#[allow(dead_code)]")?;
+    writeln!(output, "pub fn entity_subject(entity_name: &str, record: &serde_json::Value) -> String {{")?;
+    writeln!(output, "    match entity_name {{")?;
+    for entity in entities.iter().filter(|e| e.publish_subject.is_some()) {
+        writeln!(output, "        \"{}\" => {},", entity.name, subject_expr(entity, "record")?)?;
+    }
+    writeln!(output, "        _ => format!(\"entities.{{}}\", entity_name),")?;
+    writeln!(output, "    }}")?;
+    writeln!(output, "}}\n")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::FieldDef;
+
+    fn entity(subject: Option<&str>) -> EntityDef {
+        EntityDef {
+            name: "Order".to_string(),
+            fields: vec![FieldDef { name: "tenant".to_string(), ..Default::default() }],
+            publish_subject: subject.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_subject_expr() {
+        assert_eq!(subject_expr(&entity(None), "record").unwrap(), "\"entities.Order\".to_string()");
+        assert_eq!(
+            subject_expr(&entity(Some("orders.{env:ENV}.{tenant}.{name}")), "record").unwrap(),
+            "format!(\"orders.{}.{}.Order\", \
+             crate::persist_publish::subject_token(std::env::var(\"ENV\").ok().map(serde_json::Value::from).as_ref()), \
+             crate::persist_publish::subject_token(record.get(\"tenant\")))"
+        );
+    }
+
+    #[test]
+    fn test_invalid_subjects_are_rejected() {
+        for subject in ["{tenant}.orders", "entities.{region}", "entities.*", "entities..x", "entities.{name"] {
+            assert!(parse_subject(&entity(Some(subject))).is_err(), "{}", subject);
+        }
+    }
+
+    #[test]
+    fn test_stream_subjects() {
+        let entities = vec![entity(None), entity(Some("orders.{tenant}.{name}"))];
+        assert_eq!(stream_subjects(&entities).unwrap(), vec!["entities.>", "orders.>"]);
+    }
+
+    #[test]
+    fn test_renamed_fields_are_read_by_their_serialized_name() {
+        let mut renamed = entity(Some("orders.{tenantId}"));
+        renamed.fields[0].serialized_as = Some("tenantId".to_string());
+        assert_eq!(
+            subject_expr(&renamed, "record").unwrap(),
+            "format!(\"orders.{}\", crate::persist_publish::subject_token(record.get(\"tenantId\")))"
+        );
+
+        renamed.publish_subject = Some("orders.{tenant}".to_string());
+        assert!(parse_subject(&renamed).is_err());
+    }
+}
@@ -3,12 +3,13 @@
 use std::path::Path;
 use std::error::Error;
 use std::io::Write;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use crate::codegen::project_config::{RustTransformDef, TestExpectation};
+use crate::codegen::utils::transform_fn_name;
 
 pub fn generate_transforms_rs(
     output_dir: &Path,
-    transforms: Option<&HashMap<String, RustTransformDef>>,
+    transforms: Option<&BTreeMap<String, RustTransformDef>>,
 ) -> Result<(), Box<dyn Error>> {
     let transforms_file = output_dir.join("src/transforms.rs");
     let mut output = std::fs::File::create(&transforms_file)?;
@@ -30,12 +31,12 @@ pub fn generate_transforms_rs(
 /// Generate custom transform functions from nomnom.yaml
 fn generate_custom_transforms(
     output: &mut std::fs::File,
-    transforms: &HashMap<String, RustTransformDef>,
+    transforms: &BTreeMap<String, RustTransformDef>,
 ) -> Result<(), Box<dyn Error>> {
     writeln!(output, "// Custom transform functions from nomnom.yaml\n")?;
 
     // Collect all unique imports
-    let mut all_imports = std::collections::HashSet::new();
+    let mut all_imports = std::collections::BTreeSet::new();
     for transform in transforms.values() {
         for import in &transform.imports {
             all_imports.insert(import.clone());
@@ -59,7 +60,7 @@ fn generate_custom_transforms(
         }
 
         // Generate function signature
-        write!(output, "pub fn {}(", name)?;
+        write!(output, "pub fn {}(", transform_fn_name(name))?;
         for (i, arg) in transform.args.iter().enumerate() {
             if i > 0 {
                 write!(output, ", ")?;
@@ -85,7 +86,7 @@ fn generate_custom_transforms(
 /// Generate test module for transforms with test definitions
 fn generate_transform_tests(
     output: &mut std::fs::File,
-    transforms: &HashMap<String, RustTransformDef>,
+    transforms: &BTreeMap<String, RustTransformDef>,
 ) -> Result<(), Box<dyn Error>> {
     // Collect all tests from all transforms
     let mut has_tests = false;
@@ -122,7 +123,7 @@ fn generate_transform_tests(
             }
 
             // Generate function call
-            write!(output, "        let result = {}(", transform_name)?;
+            write!(output, "        let result = {}(", transform_fn_name(transform_name))?;
             for (i, arg) in transform.args.iter().enumerate() {
                 if i > 0 {
                     write!(output, ", ")?;
@@ -6,23 +6,56 @@
 //! Supports both:
 //! - Entity Schema v1 (K8s-style with apiVersion, kind, metadata, spec)
 //! - Legacy format (entity wrapper with snake_case fields)
+//!
+//! Entities can live one per file in a directory, or together in a single
+//! multi-document YAML file (one `---`-separated document per entity).
+//!
+//! An entity can pull in shared field blocks with `include:`, naming
+//! fragments stored in a `fragments/` directory next to the entity files:
+//!
+//! ```yaml
+//! # fragments/audit_fields.yaml
+//! fields:
+//!   - name: created_by
+//!     type: String
+//!
+//! # order.yaml
+//! entity:
+//!   name: Order
+//!   include: [audit_fields]
+//!   fields: [...]
+//! ```
+//!
+//! Included fields come first, in `include` order, followed by the entity's
+//! own. A field the entity defines itself replaces the included one of the
+//! same name; two fragments defining the same field is an error.
+//!
+//! Documents with a top-level `interpolate_env: true` get `${VAR}`
+//! substitution (see [`env_interpolation`](crate::codegen::env_interpolation)),
+//! applied after fragments are merged in.
 
-use crate::codegen::types::{EntityDef, EntityV1, FieldDef};
+use crate::codegen::env_interpolation;
+use crate::codegen::types::{apply_csv_dialects, EntityDef, EntityV1, FieldDef, JsonInputConfig};
 use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 
+/// Directory, next to the entity files, holding includable fragments
+pub const FRAGMENTS_DIR: &str = "fragments";
+
 /// Wrapper for legacy entity YAML structure
 #[derive(Debug, Deserialize)]
 struct EntitySpec {
     entity: EntityDef,
 }
 
-/// Load all entity definitions from a directory
+/// Load all entity definitions from a directory or a multi-document YAML file
 ///
 /// # Arguments
 ///
-/// * `dir` - Path to directory containing YAML entity files
+/// * `dir` - Path to a directory containing YAML entity files, or to a
+///   single YAML file holding one `---`-separated document per entity
 ///
 /// # Returns
 ///
@@ -34,6 +67,7 @@ struct EntitySpec {
 /// use nomnom::codegen::load_entities;
 ///
 /// let entities = load_entities("config/entities").unwrap();
+/// let entities = load_entities("config/entities.yaml").unwrap();
 /// ```
 pub fn load_entities<P: AsRef<Path>>(dir: P) -> Result<Vec<EntityDef>, String> {
     let dir_path = dir.as_ref();
@@ -42,8 +76,15 @@ pub fn load_entities<P: AsRef<Path>>(dir: P) -> Result<Vec<EntityDef>, String> {
         return Err(format!("Directory does not exist: {}", dir_path.display()));
     }
 
-    if !dir_path.is_dir() {
-        return Err(format!("Path is not a directory: {}", dir_path.display()));
+    if dir_path.is_file() {
+        let yaml_content = fs::read_to_string(dir_path)
+            .map_err(|e| format!("Failed to read file {}: {}", dir_path.display(), e))?;
+        let fragments_dir = dir_path.parent().unwrap_or(Path::new(".")).join(FRAGMENTS_DIR);
+        let mut entities = parse_documents(&yaml_content, Some(&fragments_dir))
+            .map_err(|e| format!("Failed to load {}: {}", dir_path.display(), e))?;
+        validate_entity_set(&entities)?;
+        apply_csv_dialects(&mut entities);
+        return Ok(entities);
     }
 
     let mut entities = Vec::new();
@@ -67,15 +108,45 @@ pub fn load_entities<P: AsRef<Path>>(dir: P) -> Result<Vec<EntityDef>, String> {
         }
     }
 
+    validate_entity_set(&entities)?;
+    apply_csv_dialects(&mut entities);
+    Ok(entities)
+}
+
+/// Parse every entity of a multi-document YAML stream
+///
+/// Each `---`-separated document holds one entity in either supported
+/// format; empty documents are ignored.
+pub fn parse_entities(yaml_content: &str) -> Result<Vec<EntityDef>, String> {
+    parse_documents(yaml_content, None)
+}
+
+fn parse_documents(yaml_content: &str, fragments_dir: Option<&Path>) -> Result<Vec<EntityDef>, String> {
+    let mut entities = Vec::new();
+    for (i, document) in serde_yaml::Deserializer::from_str(yaml_content).enumerate() {
+        let value = serde_yaml::Value::deserialize(document)
+            .map_err(|e| format!("Failed to parse YAML document {}: {}", i + 1, e))?;
+        if value.is_null() {
+            continue;
+        }
+        let entity = parse_entity_value(value, fragments_dir)
+            .map_err(|e| format!("Document {}: {}", i + 1, e))?;
+        entities.push(entity);
+    }
+    Ok(entities)
+}
+
+/// Cross-entity validation run once every entity is loaded
+fn validate_entity_set(entities: &[EntityDef]) -> Result<(), String> {
     // Validate ancillary source entity rules
-    for entity in &entities {
-        if let Err(e) = entity.validate_ancillary_sources(&entities) {
+    for entity in entities {
+        if let Err(e) = entity.validate_ancillary_sources(entities) {
             return Err(e);
         }
     }
 
     // Validate minimal existence constraints
-    for entity in &entities {
+    for entity in entities {
         if let Some(ref minimal_existence) = entity.minimal_existence {
             if let Err(e) = minimal_existence.validate(entity) {
                 return Err(e);
@@ -83,7 +154,70 @@ pub fn load_entities<P: AsRef<Path>>(dir: P) -> Result<Vec<EntityDef>, String> {
         }
     }
 
-    Ok(entities)
+    // Validate that fields overriding an extends ancestor keep compatible types
+    for entity in entities {
+        entity.validate_extends(entities)?;
+    }
+
+    // Validate join keys for multi-parent repeated entities
+    for entity in entities {
+        entity.validate_join_on(entities)?;
+    }
+
+    // Validate that unicity fields and indexes name existing fields
+    for entity in entities {
+        entity.validate_persistence_fields(entities)?;
+    }
+
+    // Validate that sources naming an ancestor can reach it
+    for entity in entities {
+        entity.validate_ancestor_sources(entities)?;
+    }
+
+    // Validate entity-level when conditions
+    for entity in entities {
+        if let Some(ref when) = entity.when {
+            when.validate(entity)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Names of entities no root leads to, in declaration order
+///
+/// An entity is reachable when it is a root, when one of its parents is
+/// reachable, or (for abstract entities) when a reachable entity extends it.
+/// Reference entities are pre-loaded rather than extracted and are never
+/// reported. Orphans are left-over config the parser binary would never
+/// extract, so callers report them as warnings rather than errors.
+pub fn find_orphan_entities(entities: &[EntityDef]) -> Vec<String> {
+    let mut reachable: HashSet<&str> = entities.iter()
+        .filter(|e| e.is_root())
+        .map(|e| e.name.as_str())
+        .collect();
+
+    loop {
+        let before = reachable.len();
+        for entity in entities {
+            if reachable.contains(entity.name.as_str()) {
+                if let Some(ref extends) = entity.extends {
+                    reachable.insert(extends.as_str());
+                }
+            } else if entity.get_parents().iter().any(|p| reachable.contains(p.as_str())) {
+                reachable.insert(entity.name.as_str());
+            }
+        }
+        if reachable.len() == before {
+            break;
+        }
+    }
+
+    entities.iter()
+        .filter(|e| e.source_type.to_lowercase() != "reference")
+        .filter(|e| !reachable.contains(e.name.as_str()))
+        .map(|e| e.name.clone())
+        .collect()
 }
 
 /// Load a single entity definition from a YAML file
@@ -103,16 +237,41 @@ pub fn load_entity<P: AsRef<Path>>(path: P) -> Result<EntityDef, String> {
 
     let yaml_content = fs::read_to_string(path)
         .map_err(|e| format!("Failed to read file: {}", e))?;
+    let value: serde_yaml::Value = serde_yaml::from_str(&yaml_content)
+        .map_err(|e| format!("Failed to parse YAML: {}", e))?;
+
+    let fragments_dir = path.parent().unwrap_or(Path::new(".")).join(FRAGMENTS_DIR);
+    parse_entity_value(value, Some(&fragments_dir))
+}
+
+/// Parse and validate a single entity definition from YAML text
+///
+/// Accepts the same formats as [`load_entity`]. There is no fragments
+/// directory to resolve `include:` against, so use `load_entity` for
+/// entities that include fragments.
+pub fn parse_entity(yaml_content: &str) -> Result<EntityDef, String> {
+    let value: serde_yaml::Value = serde_yaml::from_str(yaml_content)
+        .map_err(|e| format!("Failed to parse YAML: {}", e))?;
+    parse_entity_value(value, None)
+}
+
+/// Parse and validate one entity from an already-parsed YAML document
+fn parse_entity_value(
+    mut value: serde_yaml::Value,
+    fragments_dir: Option<&Path>,
+) -> Result<EntityDef, String> {
+    resolve_includes(&mut value, fragments_dir)?;
+    env_interpolation::interpolate_document(&mut value)?;
 
     // Try Entity Schema v1 first (K8s-style with apiVersion, kind, metadata, spec)
-    if let Ok(entity_v1) = serde_yaml::from_str::<EntityV1>(&yaml_content) {
+    if let Ok(entity_v1) = serde_yaml::from_value::<EntityV1>(value.clone()) {
         let entity = entity_v1.to_legacy();
         validate_entity(&entity)?;
         return Ok(entity);
     }
 
     // Fall back to legacy format (entity wrapper)
-    let spec: EntitySpec = serde_yaml::from_str(&yaml_content)
+    let spec: EntitySpec = serde_yaml::from_value(value)
         .map_err(|e| format!("Failed to parse YAML (tried both v1 and legacy formats): {}", e))?;
 
     // Validate entity
@@ -121,12 +280,89 @@ pub fn load_entity<P: AsRef<Path>>(path: P) -> Result<EntityDef, String> {
     Ok(spec.entity)
 }
 
+/// Merge the fields of the fragments listed in an entity's `include:`
+///
+/// Works on the raw document (`entity:` for the legacy format, `spec:` for
+/// v1), so fragment fields use the same format as the including entity.
+fn resolve_includes(value: &mut serde_yaml::Value, fragments_dir: Option<&Path>) -> Result<(), String> {
+    use serde_yaml::Value;
+
+    let key = if value.get("entity").is_some() { "entity" } else { "spec" };
+    let Some(Value::Mapping(body)) = value.get_mut(key) else {
+        return Ok(());
+    };
+    let Some(include) = body.remove("include") else {
+        return Ok(());
+    };
+    let names: Vec<String> = serde_yaml::from_value(include)
+        .map_err(|e| format!("Invalid include list (expected fragment names): {}", e))?;
+    let fragments_dir = fragments_dir
+        .ok_or("include: fragments can only be resolved when loading entities from files")?;
+
+    let own = match body.remove("fields") {
+        Some(Value::Sequence(fields)) => fields,
+        None => Vec::new(),
+        Some(_) => return Err("'fields' must be a list".to_string()),
+    };
+    let own_names: HashSet<String> = own.iter()
+        .filter_map(|f| f.get("name").and_then(Value::as_str).map(str::to_string))
+        .collect();
+
+    let mut fields = Vec::new();
+    let mut included_from: HashMap<String, &str> = HashMap::new();
+    for name in &names {
+        for field in load_fragment(fragments_dir, name)? {
+            let field_name = field.get("name").and_then(Value::as_str)
+                .ok_or_else(|| format!("Fragment '{}' has a field without a name", name))?
+                .to_string();
+            if own_names.contains(&field_name) {
+                continue;
+            }
+            if let Some(other) = included_from.insert(field_name.clone(), name) {
+                return Err(format!(
+                    "Field '{}' is defined by both included fragments '{}' and '{}'",
+                    field_name, other, name
+                ));
+            }
+            fields.push(field);
+        }
+    }
+    fields.extend(own);
+
+    body.insert(Value::from("fields"), Value::Sequence(fields));
+    Ok(())
+}
+
+/// Read the field list of `<fragments_dir>/<name>.yaml` (or `.yml`)
+fn load_fragment(fragments_dir: &Path, name: &str) -> Result<Vec<serde_yaml::Value>, String> {
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Fragment {
+        fields: Vec<serde_yaml::Value>,
+    }
+
+    if name.is_empty() || name.contains(['/', '\\']) {
+        return Err(format!("Invalid fragment name '{}'", name));
+    }
+    let path = ["yaml", "yml"].iter()
+        .map(|ext| fragments_dir.join(format!("{}.{}", name, ext)))
+        .find(|path| path.is_file())
+        .ok_or_else(|| format!("Fragment '{}' not found in {}", name, fragments_dir.display()))?;
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read fragment {}: {}", path.display(), e))?;
+    let fragment: Fragment = serde_yaml::from_str(&content)
+        .map_err(|e| format!("Failed to parse fragment {}: {}", path.display(), e))?;
+    Ok(fragment.fields)
+}
+
 /// Validate entity definition
 ///
 /// Checks for:
 /// - Valid entity name
 /// - Proper source_type and parent configuration
 /// - Valid field definitions
+/// - No cycles between fields that reference each other via `self`
 pub fn validate_entity(entity: &EntityDef) -> Result<(), String> {
     // Check entity name is not empty
     if entity.name.is_empty() {
@@ -136,23 +372,94 @@ pub fn validate_entity(entity: &EntityDef) -> Result<(), String> {
     // Check that derived entities have parent(s)
     if entity.is_derived() {
         let parents = entity.get_parents();
-        if parents.is_empty() && entity.repeated_for.is_none() {
+        if parents.is_empty() && entity.repeating_parent().is_none() {
             return Err(format!(
-                "Derived entity '{}' must specify parent, parents, or repeated_for",
+                "Derived entity '{}' must specify parent, parents, repeated_for or repeated_by_count",
                 entity.name
             ));
         }
     }
 
+    if entity.repeated_for.is_some() && entity.repeated_by_count.is_some() {
+        return Err(format!(
+            "Entity '{}' cannot set both repeated_for and repeated_by_count",
+            entity.name
+        ));
+    }
+    if let Some(ref by_count) = entity.repeated_by_count {
+        if by_count.stride == 0 {
+            return Err(format!("Entity '{}': repeated_by_count.stride must be at least 1", entity.name));
+        }
+        if entity.repetition.as_deref() != Some("repeated") {
+            return Err(format!("Entity '{}' uses repeated_by_count and must set repetition: repeated", entity.name));
+        }
+    }
+
     // Validate fields
     for field in &entity.fields {
         validate_field(field, &entity.name)?;
     }
 
+    // Audit columns are generated, so they can't also be declared
+    if let Some(ref persistence) = entity.persistence {
+        if persistence.has_timestamps() && persistence.declares_audit_columns() {
+            return Err(format!(
+                "Entity '{}' sets persistence.timestamps but also declares a created_at/updated_at field override",
+                entity.name
+            ));
+        }
+    }
+
+    // Fields reading siblings via `source: self` must not form a cycle
+    entity.fields_in_dependency_order()?;
+
+    validate_derives(entity)?;
+    validate_json_input(entity)?;
+
+    if let Some(ref encoding) = entity.encoding {
+        if !entity.is_root() {
+            return Err(format!("Entity '{}': encoding only applies to root entities", entity.name));
+        }
+        encoding.parse::<crate::runtime::InputEncoding>()
+            .map_err(|e| format!("Entity '{}': {}", entity.name, e))?;
+    }
+
+    if let Some(ref csv) = entity.csv {
+        if !entity.is_root() {
+            return Err(format!("Entity '{}': csv only applies to root entities", entity.name));
+        }
+        for (name, c) in [("delimiter", Some(csv.delimiter_char())), ("quote", Some(csv.quote)), ("escape", csv.escape)] {
+            if c.is_some_and(|c| !c.is_ascii()) {
+                return Err(format!("Entity '{}': csv.{} must be an ASCII character", entity.name, name));
+            }
+        }
+        let mut seen = HashSet::new();
+        if let Some(column) = csv.columns.iter().find(|c| !seen.insert(c.as_str())) {
+            return Err(format!("Entity '{}': csv column '{}' is listed twice", entity.name, column));
+        }
+    }
+
+    if let Some(ref separator) = entity.record_separator {
+        if !entity.is_root() {
+            return Err(format!("Entity '{}': record_separator only applies to root entities", entity.name));
+        }
+        separator.parse::<crate::runtime::RecordSeparator>()
+            .map_err(|e| format!("Entity '{}': {}", entity.name, e))?;
+    }
+
+    if let Some(ref data_field) = entity.data_field {
+        if !entity.is_root() {
+            return Err(format!("Entity '{}': data_field only applies to root entities", entity.name));
+        }
+        if !entity.fields.iter().any(|f| &f.name == data_field) {
+            return Err(format!("Entity '{}': data_field '{}' is not a field of the entity", entity.name, data_field));
+        }
+    }
+
     // Validate root entity has no parent
     if entity.is_root() && !entity.get_parents().is_empty() {
         return Err(format!(
-            "Root entity '{}' cannot have parent, parents, or repeated_for",
+            "Root entity '{}' cannot have parent, parents, repeated_for or repeated_by_count",
             entity.name
         ));
     }
@@ -160,6 +467,62 @@ pub fn validate_entity(entity: &EntityDef) -> Result<(), String> {
     Ok(())
 }
 
+/// Check an entity's extra model `derives:` are Rust paths that don't repeat
+/// each other or the derives nomnom already generates
+fn validate_derives(entity: &EntityDef) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for derive in &entity.derives {
+        let is_path = derive.split("::").all(|segment| {
+            segment.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && segment.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+        if !is_path {
+            return Err(format!("Entity '{}': derive '{}' is not a Rust path", entity.name, derive));
+        }
+
+        // `serde::Serialize` and `Serialize` name the same derive
+        let name = derive.rsplit("::").next().unwrap_or(derive);
+        if crate::codegen::diesel::GENERATED_MODEL_DERIVES.contains(&name) {
+            return Err(format!(
+                "Entity '{}': derive '{}' is already generated on the models ({})",
+                entity.name, derive, crate::codegen::diesel::GENERATED_MODEL_DERIVES.join(", ")
+            ));
+        }
+        if !seen.insert(name) {
+            return Err(format!("Entity '{}': derive '{}' is listed twice", entity.name, derive));
+        }
+    }
+    Ok(())
+}
+
+/// Check `json_input:` only decorates root entities, uses a serde case
+/// convention and aliases declared fields
+fn validate_json_input(entity: &EntityDef) -> Result<(), String> {
+    let Some(ref json_input) = entity.json_input else {
+        return Ok(());
+    };
+    if !entity.is_root() {
+        return Err(format!("Entity '{}': json_input only applies to root entities", entity.name));
+    }
+    if let Some(ref rule) = json_input.rename_all {
+        if !JsonInputConfig::RENAME_RULES.contains(&rule.as_str()) {
+            return Err(format!(
+                "Entity '{}': json_input.rename_all '{}' is not one of {}",
+                entity.name, rule, JsonInputConfig::RENAME_RULES.join(", ")
+            ));
+        }
+    }
+    for (field, aliases) in &json_input.aliases {
+        if !entity.fields.iter().any(|f| &f.name == field) {
+            return Err(format!("Entity '{}': json_input.aliases names unknown field '{}'", entity.name, field));
+        }
+        if let Some(alias) = aliases.iter().find(|a| a.is_empty() || a.contains('"') || a.contains('\\')) {
+            return Err(format!("Entity '{}': invalid json_input alias {:?} for field '{}'", entity.name, alias, field));
+        }
+    }
+    Ok(())
+}
+
 /// Validate field definition
 fn validate_field(field: &FieldDef, entity_name: &str) -> Result<(), String> {
     // Check field name is not empty
@@ -181,6 +544,50 @@ fn validate_field(field: &FieldDef, entity_name: &str) -> Result<(), String> {
 
         // Note: sources can be empty for transforms that get data from context
         // (e.g., extract_filename_component gets filename from parent context)
+
+        // extract_json_subtree is expanded at codegen time and returns a JSON document
+        if computed.transform == "extract_json_subtree" {
+            if !field.is_json() {
+                return Err(format!(
+                    "Field '{}' in entity '{}' uses extract_json_subtree but has type '{}' (expected Json or Object)",
+                    field.name, entity_name, field.field_type
+                ));
+            }
+            if computed.sources.len() != 1 || computed.sources[0].field_name().is_none() {
+                return Err(format!(
+                    "Field '{}' in entity '{}': extract_json_subtree requires exactly one source field",
+                    field.name, entity_name
+                ));
+            }
+            if computed.arg_str("path").is_none() {
+                return Err(format!(
+                    "Field '{}' in entity '{}': extract_json_subtree requires a 'path' argument",
+                    field.name, entity_name
+                ));
+            }
+        }
+    }
+
+    // Defaults fill in absent (None) values, so they only make sense on nullable fields
+    if field.default.is_some() {
+        if !field.nullable {
+            return Err(format!(
+                "Field '{}' in entity '{}' has a default but is not nullable",
+                field.name, entity_name
+            ));
+        }
+        field.default_literal()
+            .map_err(|e| format!("{} in entity '{}'", e, entity_name))?;
+    }
+
+    if let Some(ref validation) = field.validate {
+        validation.check(field)
+            .map_err(|e| format!("{} in entity '{}'", e, entity_name))?;
+    }
+
+    if let Some(ref coercion) = field.coerce {
+        coercion.check(field)
+            .map_err(|e| format!("{} in entity '{}'", e, entity_name))?;
     }
 
     Ok(())
@@ -228,7 +635,8 @@ pub fn load_parent_entity<P: AsRef<Path>>(
 /// Resolve all fields for an entity, including inherited fields from parent
 ///
 /// If the entity extends a parent, this function recursively loads parent
-/// fields and merges them with the entity's own fields.
+/// fields and merges them with the entity's own fields. Parent fields come
+/// first; a field the entity redeclares keeps the parent's position.
 pub fn resolve_all_fields<P: AsRef<Path>>(
     entity: &EntityDef,
     search_dir: P,
@@ -241,18 +649,17 @@ pub fn resolve_all_fields<P: AsRef<Path>>(
             // Recursively resolve parent's fields
             let parent_fields = resolve_all_fields(&parent_entity, search_dir.as_ref());
 
-            // Add parent fields, but skip ones that are overridden in child
-            for parent_field in parent_fields {
-                let is_overridden = entity.fields.iter().any(|f| f.name == parent_field.name);
-                if !is_overridden {
-                    all_fields.push(parent_field);
-                }
-            }
+            all_fields = parent_fields;
         }
     }
 
-    // Add entity's own fields (these can override parent fields)
-    all_fields.extend(entity.fields.clone());
+    // Add entity's own fields (these replace parent fields in place)
+    for field in &entity.fields {
+        match all_fields.iter_mut().find(|f| f.name == field.name) {
+            Some(inherited) => *inherited = field.clone(),
+            None => all_fields.push(field.clone()),
+        }
+    }
 
     all_fields
 }
@@ -295,6 +702,250 @@ mod tests {
         assert!(validate_entity(&entity).is_err());
     }
 
+    #[test]
+    fn test_validate_entity_repeated_by_count() {
+        let by_count = |stride| crate::codegen::types::RepeatedByCount {
+            entity: "Record".to_string(),
+            count_field: "item_count".to_string(),
+            field: "items".to_string(),
+            stride,
+            offset: 0,
+            each_known_as: "item".to_string(),
+        };
+        let mut entity = EntityDef {
+            name: "Item".to_string(),
+            source_type: "derived".to_string(),
+            repetition: Some("repeated".to_string()),
+            repeated_by_count: Some(by_count(12)),
+            ..Default::default()
+        };
+        assert!(validate_entity(&entity).is_ok());
+        assert_eq!(entity.get_parents(), vec!["Record".to_string()]);
+
+        entity.repeated_by_count = Some(by_count(0));
+        assert!(validate_entity(&entity).unwrap_err().contains("stride"));
+    }
+
+    #[test]
+    fn test_validate_entity_derives() {
+        let mut entity = EntityDef {
+            name: "Order".to_string(),
+            source_type: "root".to_string(),
+            derives: vec!["PartialEq".to_string(), "utoipa::ToSchema".to_string()],
+            ..Default::default()
+        };
+        assert!(validate_entity(&entity).is_ok());
+
+        entity.derives.push("serde::Serialize".to_string());
+        assert!(validate_entity(&entity).unwrap_err().contains("already generated"));
+
+        entity.derives = vec!["PartialEq".to_string(), "std::cmp::PartialEq".to_string()];
+        assert!(validate_entity(&entity).unwrap_err().contains("listed twice"));
+
+        entity.derives = vec!["Hash)] #[foo".to_string()];
+        assert!(validate_entity(&entity).unwrap_err().contains("not a Rust path"));
+    }
+
+    #[test]
+    fn test_validate_entity_encoding() {
+        let mut entity = EntityDef {
+            name: "Claim".to_string(),
+            source_type: "root".to_string(),
+            encoding: Some("latin1".to_string()),
+            ..Default::default()
+        };
+        assert!(validate_entity(&entity).is_ok());
+
+        entity.encoding = Some("latin-9000".to_string());
+        assert!(validate_entity(&entity).unwrap_err().contains("Unknown encoding 'latin-9000'"));
+    }
+
+    #[test]
+    fn test_validate_entity_data_field() {
+        let mut entity = EntityDef {
+            name: "Upload".to_string(),
+            source_type: "root".to_string(),
+            fields: vec![FieldDef { name: "payload".to_string(), field_type: "String".to_string(), ..Default::default() }],
+            data_field: Some("payload".to_string()),
+            ..Default::default()
+        };
+        assert!(validate_entity(&entity).is_ok());
+
+        entity.data_field = Some("body".to_string());
+        assert!(validate_entity(&entity).unwrap_err().contains("data_field 'body' is not a field"));
+    }
+
+    #[test]
+    fn test_validate_entity_record_separator() {
+        let mut entity = EntityDef {
+            name: "Hl7Message".to_string(),
+            source_type: "root".to_string(),
+            record_separator: Some("hl7".to_string()),
+            ..Default::default()
+        };
+        assert!(validate_entity(&entity).is_ok());
+
+        entity.record_separator = Some(String::new());
+        assert!(validate_entity(&entity).unwrap_err().contains("must not be empty"));
+
+        entity.record_separator = Some("hl-7".to_string());
+        assert!(validate_entity(&entity).unwrap_err().contains("Unknown record separator 'hl-7'"));
+
+        entity.record_separator = Some("csv".to_string());
+        entity.source_type = "derived".to_string();
+        assert!(validate_entity(&entity).unwrap_err().contains("record_separator only applies to root entities"));
+    }
+
+    #[test]
+    fn test_validate_entity_csv_dialect() {
+        use crate::codegen::types::{CsvDialect, CsvPreset};
+
+        let mut entity = EntityDef {
+            name: "CsvFile".to_string(),
+            source_type: "root".to_string(),
+            csv: Some(CsvDialect { preset: CsvPreset::Tsv, columns: vec!["id".to_string()], ..Default::default() }),
+            ..Default::default()
+        };
+        assert!(validate_entity(&entity).is_ok());
+
+        entity.csv.as_mut().unwrap().delimiter = Some('§');
+        assert!(validate_entity(&entity).unwrap_err().contains("csv.delimiter must be an ASCII character"));
+
+        entity.csv = Some(CsvDialect { columns: vec!["id".to_string(), "id".to_string()], ..Default::default() });
+        assert!(validate_entity(&entity).unwrap_err().contains("listed twice"));
+    }
+
+    #[test]
+    fn test_validate_entity_json_input() {
+        let mut entity = EntityDef {
+            name: "Order".to_string(),
+            source_type: "root".to_string(),
+            fields: vec![FieldDef {
+                name: "order_key".to_string(),
+                field_type: "Integer".to_string(),
+                ..Default::default()
+            }],
+            json_input: Some(JsonInputConfig {
+                rename_all: Some("camelCase".to_string()),
+                numbers_from_strings: true,
+                aliases: [("order_key".to_string(), vec!["OrderID".to_string()])].into(),
+            }),
+            ..Default::default()
+        };
+        assert!(validate_entity(&entity).is_ok());
+
+        entity.json_input.as_mut().unwrap().rename_all = Some("camel".to_string());
+        assert!(validate_entity(&entity).unwrap_err().contains("rename_all"));
+
+        entity.json_input.as_mut().unwrap().rename_all = None;
+        entity.json_input.as_mut().unwrap().aliases.insert("total".to_string(), vec!["Total".to_string()]);
+        assert!(validate_entity(&entity).unwrap_err().contains("unknown field 'total'"));
+    }
+
+    #[test]
+    fn test_load_entities_from_multi_document_file() {
+        let yaml = r#"
+entity:
+  name: Order
+  source_type: root
+  fields:
+    - name: raw
+      type: String
+---
+entity:
+  name: OrderLine
+  source_type: derived
+  parent: Order
+  fields:
+    - name: line
+      type: String
+---
+"#;
+        let path = std::env::temp_dir().join(format!("nomnom_entities_{}.yaml", std::process::id()));
+        fs::write(&path, yaml).unwrap();
+        let entities = load_entities(&path);
+        fs::remove_file(&path).unwrap();
+
+        let names: Vec<_> = entities.unwrap().into_iter().map(|e| e.name).collect();
+        assert_eq!(names, vec!["Order", "OrderLine"]);
+
+        let err = parse_entities("entity:\n  name: A\n  source_type: root\n---\nentity:\n  name: ''\n").err().unwrap();
+        assert!(err.starts_with("Document 2"), "{}", err);
+    }
+
+    #[test]
+    fn test_include_merges_fragment_fields() {
+        let dir = std::env::temp_dir().join(format!("nomnom_fragments_{}", std::process::id()));
+        fs::create_dir_all(dir.join(FRAGMENTS_DIR)).unwrap();
+        fs::write(dir.join(FRAGMENTS_DIR).join("audit.yaml"), r#"
+fields:
+  - name: created_by
+    type: String
+  - name: note
+    type: String
+"#).unwrap();
+        fs::write(dir.join(FRAGMENTS_DIR).join("notes.yaml"), "fields:\n  - name: note\n    type: String\n").unwrap();
+        let entity = |include: &str| format!(r#"
+entity:
+  name: Order
+  source_type: root
+  include: [{}]
+  fields:
+    - name: note
+      type: Integer
+    - name: raw
+      type: String
+"#, include);
+
+        fs::write(dir.join("order.yaml"), entity("audit")).unwrap();
+        let order = load_entity(dir.join("order.yaml"));
+        // A field named by two fragments is only an error when the entity doesn't define it
+        fs::write(dir.join("order.yaml"), entity("audit, notes")).unwrap();
+        let both = load_entity(dir.join("order.yaml"));
+        fs::write(dir.join("order.yaml"), entity("missing")).unwrap();
+        let missing = load_entity(dir.join("order.yaml"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let order = order.unwrap();
+        let fields: Vec<_> = order.fields.iter().map(|f| (f.name.as_str(), f.field_type.as_str())).collect();
+        assert_eq!(fields, vec![("created_by", "String"), ("note", "Integer"), ("raw", "String")]);
+        assert!(both.is_ok());
+        assert!(missing.err().unwrap().contains("Fragment 'missing' not found"));
+        assert!(parse_entity(&entity("audit")).is_err());
+    }
+
+    #[test]
+    fn test_resolve_all_fields_keeps_parent_order() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("order.yaml"), r#"
+entity:
+  name: Order
+  source_type: root
+  fields:
+    - name: id
+      type: String
+    - name: amount
+      type: Integer
+    - name: note
+      type: String
+"#).unwrap();
+        let line = parse_entity(r#"
+entity:
+  name: OrderLine
+  source_type: derived
+  parent: Order
+  fields:
+    - name: line
+      type: String
+    - name: amount
+      type: Decimal
+"#).unwrap();
+        let fields = resolve_all_fields(&line, dir.path());
+
+        let fields: Vec<_> = fields.iter().map(|f| (f.name.as_str(), f.field_type.as_str())).collect();
+        assert_eq!(fields, vec![("id", "String"), ("amount", "Decimal"), ("note", "String"), ("line", "String")]);
+    }
+
     #[test]
     fn test_validate_field_with_computed_from() {
         use crate::codegen::types::{ComputedFrom, FieldSource};
@@ -330,4 +981,106 @@ mod tests {
 
         assert!(validate_field(&field, "TestEntity").is_err());
     }
+
+    #[test]
+    fn test_validate_field_default_type_checked() {
+        let mut field = FieldDef {
+            name: "status".to_string(),
+            field_type: "String".to_string(),
+            nullable: true,
+            default: Some(serde_yaml::Value::String("pending".to_string())),
+            ..Default::default()
+        };
+        assert!(validate_field(&field, "TestEntity").is_ok());
+        assert_eq!(field.default_literal().unwrap().as_deref(), Some("\"pending\".to_string()"));
+
+        field.field_type = "Integer".to_string();
+        assert!(validate_field(&field, "TestEntity").is_err());
+
+        field.default = Some(serde_yaml::Value::Number(3.into()));
+        assert!(validate_field(&field, "TestEntity").is_ok());
+
+        field.nullable = false;
+        assert!(validate_field(&field, "TestEntity").is_err());
+    }
+
+    #[test]
+    fn test_validate_field_validation_rules() {
+        use crate::codegen::types::FieldValidation;
+
+        let mut field = FieldDef {
+            name: "code".to_string(),
+            field_type: "String".to_string(),
+            validate: Some(FieldValidation {
+                regex: Some("^[A-Z]{3}$".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(validate_field(&field, "TestEntity").is_ok());
+
+        // Unbalanced pattern fails at load time
+        field.validate.as_mut().unwrap().regex = Some("([A-Z]".to_string());
+        assert!(validate_field(&field, "TestEntity").is_err());
+
+        // Range checks only apply to numeric fields
+        field.validate = Some(FieldValidation { min: Some(0.0), ..Default::default() });
+        assert!(validate_field(&field, "TestEntity").is_err());
+        field.field_type = "Integer".to_string();
+        assert!(validate_field(&field, "TestEntity").is_ok());
+    }
+
+    #[test]
+    fn test_validate_field_coercion() {
+        use crate::codegen::types::FieldCoercion;
+
+        let mut field = FieldDef {
+            name: "amount".to_string(),
+            field_type: "Float".to_string(),
+            nullable: true,
+            coerce: Some(FieldCoercion { locale: Some("de_DE".to_string()), ..Default::default() }),
+            ..Default::default()
+        };
+        assert!(validate_field(&field, "TestEntity").is_ok());
+        assert_eq!(
+            field.coerce.as_ref().unwrap().separators(),
+            (Some(".".to_string()), ",".to_string())
+        );
+
+        // Explicit separators must not collide with the locale's
+        field.coerce.as_mut().unwrap().thousands_separator = Some(",".to_string());
+        assert!(validate_field(&field, "TestEntity").is_err());
+
+        field.coerce = Some(FieldCoercion { locale: Some("xx_YY".to_string()), ..Default::default() });
+        assert!(validate_field(&field, "TestEntity").is_err());
+
+        field.coerce = Some(FieldCoercion::default());
+        field.field_type = "String".to_string();
+        assert!(validate_field(&field, "TestEntity").is_err());
+    }
+
+    #[test]
+    fn test_find_orphan_entities() {
+        let entity = |name: &str, source_type: &str, parent: Option<&str>| EntityDef {
+            name: name.to_string(),
+            source_type: source_type.to_string(),
+            parent: parent.map(str::to_string),
+            ..Default::default()
+        };
+        let mut base = entity("BaseRecord", "derived", None);
+        base.is_abstract = true;
+        let mut order = entity("Order", "derived", Some("OrderFile"));
+        order.extends = Some("BaseRecord".to_string());
+
+        let entities = vec![
+            entity("LineItem", "derived", Some("Order")),
+            order,
+            entity("OrderFile", "root", None),
+            base,
+            entity("Country", "reference", None),
+            entity("LegacyOrder", "derived", Some("RemovedFile")),
+            entity("LegacyLine", "derived", Some("LegacyOrder")),
+        ];
+        assert_eq!(find_orphan_entities(&entities), ["LegacyOrder", "LegacyLine"]);
+    }
 }
@@ -4,8 +4,12 @@
 
 use diesel::prelude::*;
 use diesel::r2d2::{self, ConnectionManager};
+use std::fmt;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+#[cfg(debug_assertions)]
+use std::{cell::Cell, collections::HashMap, panic::Location, sync::Mutex};
 
 // Conditional imports based on database backend
 #[cfg(feature = "postgres")]
@@ -24,6 +28,66 @@ pub type PooledConnection = r2d2::PooledConnection<ConnectionManager<DbConnectio
 /// Database connection pool manager
 pub struct Database {
     pool: Arc<Pool>,
+    /// Replica pool for reads; `None` when reads share the primary pool
+    read_pool: Option<Arc<Pool>>,
+    slow_acquire_warn: Duration,
+    #[cfg(debug_assertions)]
+    checkouts: Arc<CheckoutTracker>,
+}
+
+/// Call sites of connections checked out through `Database::get_connection`
+/// and not yet returned, keyed by r2d2 connection id (debug builds only)
+#[cfg(debug_assertions)]
+#[derive(Debug, Default)]
+struct CheckoutTracker {
+    outstanding: Mutex<HashMap<u64, &'static Location<'static>>>,
+}
+
+#[cfg(debug_assertions)]
+thread_local! {
+    /// Id of the connection most recently checked out on this thread; r2d2
+    /// fires checkout events synchronously inside `Pool::get`
+    static LAST_CHECKOUT: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+#[cfg(debug_assertions)]
+impl ::r2d2::event::HandleEvent for CheckoutTrackerHandle {
+    fn handle_checkout(&self, event: ::r2d2::event::CheckoutEvent) {
+        LAST_CHECKOUT.with(|last| last.set(Some(event.connection_id())));
+    }
+
+    fn handle_checkin(&self, event: ::r2d2::event::CheckinEvent) {
+        if let Ok(mut outstanding) = self.0.outstanding.lock() {
+            outstanding.remove(&event.connection_id());
+        }
+    }
+}
+
+#[cfg(debug_assertions)]
+#[derive(Debug)]
+struct CheckoutTrackerHandle(Arc<CheckoutTracker>);
+
+/// Snapshot of connection pool usage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Connections currently open (idle + in use)
+    pub size: u32,
+    /// Open connections waiting in the pool
+    pub idle: u32,
+    /// Connections checked out and not yet returned
+    pub in_use: u32,
+    /// Configured pool capacity
+    pub max_size: u32,
+}
+
+impl fmt::Display for PoolStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} in use, {} idle, {}/{} open",
+            self.in_use, self.idle, self.size, self.max_size
+        )
+    }
 }
 
 impl Database {
@@ -50,24 +114,134 @@ impl Database {
         database_url: &str,
         config: DatabaseConfig,
     ) -> Result<Self, Box<dyn std::error::Error>> {
-        let manager = ConnectionManager::<DbConnection>::new(database_url);
+        #[cfg(debug_assertions)]
+        let checkouts = Arc::new(CheckoutTracker::default());
+
+        let build_pool = |url: &str| {
+            let builder = r2d2::Pool::builder()
+                .max_size(config.max_connections)
+                .min_idle(Some(config.min_idle))
+                .connection_timeout(Duration::from_secs(config.connection_timeout_secs))
+                .idle_timeout(Some(Duration::from_secs(config.idle_timeout_secs)))
+                .max_lifetime(Some(Duration::from_secs(config.max_lifetime_secs)));
 
-        let pool = r2d2::Pool::builder()
-            .max_size(config.max_connections)
-            .min_idle(Some(config.min_idle))
-            .connection_timeout(Duration::from_secs(config.connection_timeout_secs))
-            .idle_timeout(Some(Duration::from_secs(config.idle_timeout_secs)))
-            .max_lifetime(Some(Duration::from_secs(config.max_lifetime_secs)))
-            .build(manager)?;
+            #[cfg(debug_assertions)]
+            let builder = builder.event_handler(Box::new(CheckoutTrackerHandle(Arc::clone(&checkouts))));
+
+            builder.build(ConnectionManager::<DbConnection>::new(url))
+        };
+
+        let pool = build_pool(database_url)?;
+        let read_pool = match config.read_replica_url {
+            Some(ref url) => Some(Arc::new(build_pool(url)?)),
+            None => None,
+        };
 
         Ok(Database {
             pool: Arc::new(pool),
+            read_pool,
+            slow_acquire_warn: Duration::from_millis(config.slow_acquire_warn_ms),
+            #[cfg(debug_assertions)]
+            checkouts,
         })
     }
 
     /// Get a connection from the pool
+    ///
+    /// Logs a warning with the pool state when the wait exceeds
+    /// `DatabaseConfig::slow_acquire_warn_ms` or the pool times out, which
+    /// usually means connections are not being returned. In debug builds the
+    /// caller location is recorded for [`Database::assert_idle`].
+    #[track_caller]
     pub fn get_connection(&self) -> Result<PooledConnection, r2d2::PoolError> {
-        self.pool.get()
+        self.checkout(&self.pool)
+    }
+
+    /// Get a connection for read-only queries from [`Database::read_pool`]
+    #[track_caller]
+    pub fn get_read_connection(&self) -> Result<PooledConnection, r2d2::PoolError> {
+        self.checkout(self.read_pool())
+    }
+
+    #[track_caller]
+    fn checkout(&self, pool: &Pool) -> Result<PooledConnection, r2d2::PoolError> {
+        let started = Instant::now();
+        let result = pool.get();
+        let waited = started.elapsed();
+
+        #[cfg(debug_assertions)]
+        if let (Ok(_), Some(id)) = (&result, LAST_CHECKOUT.with(Cell::take)) {
+            if let Ok(mut outstanding) = self.checkouts.outstanding.lock() {
+                outstanding.insert(id, Location::caller());
+            }
+        }
+
+        match result {
+            Err(ref e) => tracing::warn!(
+                "Failed to get database connection after {:?} ({}): {}",
+                waited, stats_of(pool), e
+            ),
+            Ok(_) if waited > self.slow_acquire_warn => tracing::warn!(
+                "Waited {:?} for a database connection ({})",
+                waited, stats_of(pool)
+            ),
+            Ok(_) => {}
+        }
+        result
+    }
+
+    /// Current connection pool usage (primary)
+    pub fn pool_stats(&self) -> PoolStats {
+        stats_of(&self.pool)
+    }
+
+    /// Current usage of [`Database::read_pool`]
+    pub fn read_pool_stats(&self) -> PoolStats {
+        stats_of(self.read_pool())
+    }
+
+    /// Panic unless every connection has been returned to the pool
+    ///
+    /// Call at the end of a unit of work in tests to catch connections held
+    /// too long (e.g. across an `.await`). In debug builds the panic lists
+    /// where each outstanding connection was checked out.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let db = Database::new(&url)?;
+    /// persist_batch(&db, &rows)?;
+    /// db.assert_idle();
+    /// ```
+    #[track_caller]
+    pub fn assert_idle(&self) {
+        let stats = self.pool_stats();
+        let replica = self.read_pool.as_ref().map(|pool| stats_of(pool));
+        let in_use = stats.in_use + replica.map_or(0, |r| r.in_use);
+        if in_use > 0 {
+            let replica = replica.map(|r| format!("; replica: {}", r)).unwrap_or_default();
+            panic!(
+                "{} database connection(s) not returned to the pool ({}{}){}",
+                in_use, stats, replica, self.checkout_sites()
+            );
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    fn checkout_sites(&self) -> String {
+        let Ok(outstanding) = self.checkouts.outstanding.lock() else {
+            return String::new();
+        };
+        if outstanding.is_empty() {
+            return String::new();
+        }
+        let mut sites: Vec<String> = outstanding.values().map(|l| l.to_string()).collect();
+        sites.sort();
+        format!("; checked out at: {}", sites.join(", "))
+    }
+
+    #[cfg(not(debug_assertions))]
+    fn checkout_sites(&self) -> String {
+        String::new()
     }
 
     /// Test database connectivity
@@ -77,10 +251,33 @@ impl Database {
         Ok(())
     }
 
-    /// Get a reference to the connection pool
+    /// Get a reference to the connection pool (primary, used for writes)
     pub fn pool(&self) -> &Pool {
         &self.pool
     }
+
+    /// Pool for read-only queries
+    ///
+    /// The read replica when `DatabaseConfig::read_replica_url` is set,
+    /// otherwise the primary pool.
+    pub fn read_pool(&self) -> &Pool {
+        self.read_pool.as_deref().unwrap_or(&self.pool)
+    }
+
+    /// Whether reads go to a separate replica
+    pub fn has_read_replica(&self) -> bool {
+        self.read_pool.is_some()
+    }
+}
+
+fn stats_of(pool: &Pool) -> PoolStats {
+    let state = pool.state();
+    PoolStats {
+        size: state.connections,
+        idle: state.idle_connections,
+        in_use: state.connections - state.idle_connections,
+        max_size: pool.max_size(),
+    }
 }
 
 /// Database configuration options
@@ -91,6 +288,10 @@ pub struct DatabaseConfig {
     pub connection_timeout_secs: u64,
     pub idle_timeout_secs: u64,
     pub max_lifetime_secs: u64,
+    /// Warn when acquiring a connection takes longer than this
+    pub slow_acquire_warn_ms: u64,
+    /// Optional read replica; when unset, reads and writes share one pool
+    pub read_replica_url: Option<String>,
 }
 
 impl Default for DatabaseConfig {
@@ -101,6 +302,8 @@ impl Default for DatabaseConfig {
             connection_timeout_secs: 30,  // Wait up to 30s for connection
             idle_timeout_secs: 600,   // Close idle connections after 10 min
             max_lifetime_secs: 1800,  // Recycle connections after 30 min
+            slow_acquire_warn_ms: 1000,  // Warn when a checkout waits over 1s
+            read_replica_url: None,   // Reads share the primary pool
         }
     }
 }
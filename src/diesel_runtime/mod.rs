@@ -16,8 +16,8 @@ pub mod operations;
 pub mod python;
 
 // Re-export key types
-pub use database::{Database, DatabaseConfig, Pool, PooledConnection};
-pub use operations::{GetOrCreate, BulkInsert};
+pub use database::{Database, DatabaseConfig, Pool, PoolStats, PooledConnection};
+pub use operations::{GetOrCreate, BulkInsert, BatchLimits, SqlValue, insert_rows, insert_rows_with_limits, MAX_BIND_PARAMS};
 
 #[cfg(feature = "python-bridge")]
-pub use python::PyDatabase;
+pub use python::{PyDatabase, PyTransaction};
@@ -49,7 +49,9 @@ pub trait GetOrCreate: Sized {
 
 /// Trait for bulk insert operations
 ///
-/// Provides efficient batch insertion of multiple entities.
+/// Provides efficient batch insertion of multiple entities. `bulk_insert`
+/// writes one statement, so its input must stay under the backend's bind
+/// parameter limit; [`BulkInsert::bulk_insert_chunked`] splits larger inputs.
 ///
 /// # Example
 ///
@@ -77,4 +79,274 @@ pub trait BulkInsert: Sized {
         conn: &mut DbConnection,
         instances: &[Self],
     ) -> Result<usize, DieselError>;
+
+    /// Insert any number of entities, split into statements within `limits`
+    ///
+    /// `columns` is the number of bind parameters each instance uses (its
+    /// inserted column count). All statements run in one transaction, so
+    /// either every chunk is inserted or none is.
+    ///
+    /// # Returns
+    /// Number of entities inserted
+    fn bulk_insert_chunked(
+        conn: &mut DbConnection,
+        instances: &[Self],
+        columns: usize,
+        limits: BatchLimits,
+    ) -> Result<usize, DieselError> {
+        use diesel::connection::Connection;
+
+        let rows_per_statement = limits.rows_per_statement(columns);
+        if instances.len() <= rows_per_statement {
+            return Self::bulk_insert(conn, instances);
+        }
+        conn.transaction(|conn| {
+            instances
+                .chunks(rows_per_statement)
+                .try_fold(0, |inserted, chunk| Ok(inserted + Self::bulk_insert(conn, chunk)?))
+        })
+    }
+}
+
+/// Limits used to split bulk inserts into several statements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchLimits {
+    /// Maximum bind parameters per statement
+    pub max_params: usize,
+    /// Maximum rows per statement, whatever the column count
+    pub max_rows: Option<usize>,
+}
+
+impl Default for BatchLimits {
+    fn default() -> Self {
+        BatchLimits {
+            max_params: MAX_BIND_PARAMS,
+            max_rows: None,
+        }
+    }
+}
+
+impl BatchLimits {
+    /// Rows that fit in one statement for a table with `columns` columns (at least 1)
+    pub fn rows_per_statement(&self, columns: usize) -> usize {
+        let by_params = self.max_params / columns.max(1);
+        self.max_rows.map_or(by_params, |rows| by_params.min(rows)).max(1)
+    }
+}
+
+/// Dynamically-typed value for runtime (non-generated) inserts
+///
+/// Used where rows arrive without a compiled Diesel model, e.g. from Python.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+/// Maximum number of bind parameters per statement
+///
+/// PostgreSQL encodes the parameter count in 16 bits (65535) and MySQL caps
+/// prepared statement placeholders at the same value.
+pub const MAX_BIND_PARAMS: usize = 65_535;
+
+/// Insert rows into a table by name, batching them into multi-row INSERTs
+///
+/// This is the dynamic counterpart to [`BulkInsert`]: columns and values are
+/// supplied at runtime instead of through a generated model. Table and column
+/// names must be plain identifiers; values are always bound as parameters.
+/// Uses the default [`BatchLimits`]; see [`insert_rows_with_limits`].
+///
+/// # Returns
+/// Number of rows inserted
+pub fn insert_rows(
+    conn: &mut DbConnection,
+    table: &str,
+    columns: &[String],
+    rows: &[Vec<SqlValue>],
+) -> Result<usize, DieselError> {
+    insert_rows_with_limits(conn, table, columns, rows, BatchLimits::default())
+}
+
+/// Insert rows into a table by name, with at most `limits` per statement
+///
+/// When the rows need several statements they run in one transaction.
+///
+/// # Returns
+/// Number of rows inserted
+pub fn insert_rows_with_limits(
+    conn: &mut DbConnection,
+    table: &str,
+    columns: &[String],
+    rows: &[Vec<SqlValue>],
+    limits: BatchLimits,
+) -> Result<usize, DieselError> {
+    use diesel::connection::Connection;
+
+    if rows.is_empty() {
+        return Ok(0);
+    }
+    if columns.is_empty() {
+        return Err(DieselError::QueryBuilderError(
+            format!("Cannot insert into '{}' without columns", table).into(),
+        ));
+    }
+    if let Some(row) = rows.iter().find(|row| row.len() != columns.len()) {
+        return Err(DieselError::QueryBuilderError(
+            format!("Row has {} values but {} columns were given", row.len(), columns.len()).into(),
+        ));
+    }
+
+    let chunk_size = limits.rows_per_statement(columns.len());
+    if rows.len() <= chunk_size {
+        return insert_chunk(conn, table, columns, rows);
+    }
+    conn.transaction(|conn| {
+        rows.chunks(chunk_size)
+            .try_fold(0, |inserted, chunk| Ok(inserted + insert_chunk(conn, table, columns, chunk)?))
+    })
+}
+
+/// Insert rows with a single multi-row INSERT statement
+fn insert_chunk(
+    conn: &mut DbConnection,
+    table: &str,
+    columns: &[String],
+    rows: &[Vec<SqlValue>],
+) -> Result<usize, DieselError> {
+    use diesel::connection::Connection;
+    use diesel::sql_types::{BigInt, Bool, Double, Text};
+    use diesel::RunQueryDsl;
+
+    let sql = build_insert_sql(table, columns, rows)
+        .map_err(|e| DieselError::QueryBuilderError(e.into()))?;
+
+    let mut query = diesel::sql_query(sql)
+        .into_boxed::<<DbConnection as Connection>::Backend>();
+    for value in rows.iter().flatten() {
+        query = match value {
+            // NULLs are inlined in the SQL so they take the column's type
+            SqlValue::Null => query,
+            SqlValue::Bool(b) => query.bind::<Bool, _>(*b),
+            SqlValue::Int(i) => query.bind::<BigInt, _>(*i),
+            SqlValue::Float(f) => query.bind::<Double, _>(*f),
+            SqlValue::Text(s) => query.bind::<Text, _>(s.clone()),
+        };
+    }
+
+    query.execute(conn)
+}
+
+/// Build a multi-row INSERT statement with backend-specific placeholders
+fn build_insert_sql(table: &str, columns: &[String], rows: &[Vec<SqlValue>]) -> Result<String, String> {
+    let quoted_columns = columns
+        .iter()
+        .map(|c| quote_identifier(c))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut param_index = 0;
+    let values = rows
+        .iter()
+        .map(|row| {
+            let params = row
+                .iter()
+                .map(|value| match value {
+                    SqlValue::Null => "NULL".to_string(),
+                    _ => {
+                        param_index += 1;
+                        placeholder(param_index)
+                    }
+                })
+                .collect::<Vec<_>>();
+            format!("({})", params.join(", "))
+        })
+        .collect::<Vec<_>>();
+
+    Ok(format!(
+        "INSERT INTO {} ({}) VALUES {}",
+        quote_identifier(table)?,
+        quoted_columns.join(", "),
+        values.join(", ")
+    ))
+}
+
+/// Quote a (possibly schema-qualified) identifier, rejecting anything that is not a plain name
+fn quote_identifier(name: &str) -> Result<String, String> {
+    let is_plain = |part: &str| {
+        !part.is_empty()
+            && !part.starts_with(|c: char| c.is_ascii_digit())
+            && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+
+    if !name.split('.').all(is_plain) {
+        return Err(format!("Invalid SQL identifier: '{}'", name));
+    }
+
+    #[cfg(feature = "mysql")]
+    let quote = '`';
+    #[cfg(not(feature = "mysql"))]
+    let quote = '"';
+
+    Ok(name
+        .split('.')
+        .map(|part| format!("{}{}{}", quote, part, quote))
+        .collect::<Vec<_>>()
+        .join("."))
+}
+
+/// Bind placeholder for the nth (1-based) parameter
+fn placeholder(index: usize) -> String {
+    #[cfg(feature = "mysql")]
+    {
+        let _ = index;
+        "?".to_string()
+    }
+    #[cfg(not(feature = "mysql"))]
+    {
+        format!("${}", index)
+    }
+}
+
+#[cfg(all(test, not(feature = "mysql")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_insert_sql_inlines_nulls() {
+        let columns = vec!["name".to_string(), "age".to_string()];
+        let rows = vec![
+            vec![SqlValue::Text("alice".to_string()), SqlValue::Int(30)],
+            vec![SqlValue::Text("bob".to_string()), SqlValue::Null],
+        ];
+
+        let sql = build_insert_sql("public.people", &columns, &rows).unwrap();
+        assert_eq!(
+            sql,
+            "INSERT INTO \"public\".\"people\" (\"name\", \"age\") VALUES ($1, $2), ($3, NULL)"
+        );
+    }
+
+    #[test]
+    fn test_batch_limits_keep_statements_under_param_limit() {
+        let limits = BatchLimits::default();
+        assert_eq!(limits.rows_per_statement(16), 4095);
+        assert!(limits.rows_per_statement(16) * 16 <= MAX_BIND_PARAMS);
+        // Wider than the limit still makes progress one row at a time
+        assert_eq!(limits.rows_per_statement(70_000), 1);
+        assert_eq!(limits.rows_per_statement(0), MAX_BIND_PARAMS);
+
+        let capped = BatchLimits { max_params: 1000, max_rows: Some(50) };
+        assert_eq!(capped.rows_per_statement(10), 50);
+        assert_eq!(capped.rows_per_statement(40), 25);
+    }
+
+    #[test]
+    fn test_quote_identifier_rejects_injection() {
+        assert!(quote_identifier("people; DROP TABLE x").is_err());
+        assert!(quote_identifier("1abc").is_err());
+        assert!(quote_identifier("").is_err());
+        assert!(quote_identifier("people").is_ok());
+    }
 }
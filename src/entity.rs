@@ -4,22 +4,85 @@
 //! that can be derived from any structured data format (CSV, JSON, XML, etc.).
 
 use serde::{Serialize, Deserialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt;
 
 /// Represents different types of field values in an entity
+///
+/// String values borrow from the extraction source where possible and are
+/// only allocated when a transform produces new text. Use
+/// [`FieldValue::into_owned`] to detach a value from its source, e.g. at the
+/// persistence boundary.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(untagged)]
-pub enum FieldValue {
-    String(String),
+pub enum FieldValue<'a> {
+    String(Cow<'a, str>),
     Int(i64),
     Float(f64),
     Bool(bool),
-    List(Vec<FieldValue>),
+    List(Vec<FieldValue<'a>>),
     Null,
 }
 
-impl fmt::Display for FieldValue {
+/// A field value that owns all of its data
+pub type OwnedFieldValue = FieldValue<'static>;
+
+impl<'a> FieldValue<'a> {
+    /// String value borrowing from `s`, without allocating
+    pub fn borrowed(s: &'a str) -> Self {
+        FieldValue::String(Cow::Borrowed(s))
+    }
+
+    /// String contents, if this is a string value
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            FieldValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Whether this value (or any list element) still borrows from its source
+    pub fn is_borrowed(&self) -> bool {
+        match self {
+            FieldValue::String(s) => matches!(s, Cow::Borrowed(_)),
+            FieldValue::List(items) => items.iter().any(FieldValue::is_borrowed),
+            _ => false,
+        }
+    }
+
+    /// Detach from the source, allocating only for borrowed strings
+    pub fn into_owned(self) -> OwnedFieldValue {
+        match self {
+            FieldValue::String(s) => FieldValue::String(Cow::Owned(s.into_owned())),
+            FieldValue::Int(i) => FieldValue::Int(i),
+            FieldValue::Float(f) => FieldValue::Float(f),
+            FieldValue::Bool(b) => FieldValue::Bool(b),
+            FieldValue::List(items) => FieldValue::List(items.into_iter().map(FieldValue::into_owned).collect()),
+            FieldValue::Null => FieldValue::Null,
+        }
+    }
+}
+
+impl<'a> From<&'a str> for FieldValue<'a> {
+    fn from(s: &'a str) -> Self {
+        FieldValue::borrowed(s)
+    }
+}
+
+impl From<String> for FieldValue<'_> {
+    fn from(s: String) -> Self {
+        FieldValue::String(Cow::Owned(s))
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for FieldValue<'a> {
+    fn from(s: Cow<'a, str>) -> Self {
+        FieldValue::String(s)
+    }
+}
+
+impl fmt::Display for FieldValue<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             FieldValue::String(s) => write!(f, "{}", s),
@@ -53,6 +116,88 @@ pub enum EntityError {
     ContextFieldMissing {
         field: String,
     },
+    /// Another error annotated with where it happened
+    WithContext {
+        error: Box<EntityError>,
+        context: ErrorContext,
+    },
+}
+
+/// Where an [`EntityError`] happened: the entity and field being extracted
+/// and an excerpt of the source value the failing step read
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    pub entity: Option<String>,
+    pub field: Option<String>,
+    pub source_excerpt: Option<String>,
+}
+
+/// Longest source excerpt kept in an error, in characters
+pub const SOURCE_EXCERPT_LEN: usize = 80;
+
+impl EntityError {
+    /// A transform failure while computing `entity.field`
+    pub fn transform_failed(entity: &str, field: &str, error: impl fmt::Display) -> Self {
+        EntityError::TransformError(error.to_string())
+            .in_entity(entity)
+            .at_field(field)
+    }
+
+    /// Record the entity being extracted, unless already known
+    pub fn in_entity(self, entity: &str) -> Self {
+        self.with_context(|context| {
+            context.entity.get_or_insert_with(|| entity.to_string());
+        })
+    }
+
+    /// Record the field being extracted, unless already known
+    pub fn at_field(self, field: &str) -> Self {
+        self.with_context(|context| {
+            context.field.get_or_insert_with(|| field.to_string());
+        })
+    }
+
+    /// Record the source value being read, truncated to [`SOURCE_EXCERPT_LEN`]
+    pub fn with_source_excerpt(self, source: impl AsRef<str>) -> Self {
+        let source = source.as_ref();
+        let excerpt = match source.char_indices().nth(SOURCE_EXCERPT_LEN) {
+            Some((end, _)) => format!("{}...", &source[..end]),
+            None => source.to_string(),
+        };
+        self.with_context(|context| {
+            context.source_excerpt.get_or_insert(excerpt);
+        })
+    }
+
+    fn with_context(self, update: impl FnOnce(&mut ErrorContext)) -> Self {
+        let (error, mut context) = match self {
+            EntityError::WithContext { error, context } => (error, context),
+            error => (Box::new(error), ErrorContext::default()),
+        };
+        update(&mut context);
+        EntityError::WithContext { error, context }
+    }
+
+    /// Context recorded with [`in_entity`](Self::in_entity) and friends
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            EntityError::WithContext { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// The field the error is about, from the context or the error itself
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            EntityError::RequiredFieldMissing { field }
+            | EntityError::ExtractionFailed { field, .. }
+            | EntityError::ContextFieldMissing { field } => Some(field),
+            EntityError::WithContext { error, context } => {
+                context.field.as_deref().or_else(|| error.field())
+            }
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for EntityError {
@@ -76,6 +221,23 @@ impl fmt::Display for EntityError {
             EntityError::ContextFieldMissing { field } => {
                 write!(f, "Context field '{}' not found in context", field)
             }
+            EntityError::WithContext { error, context } => {
+                write!(f, "{}", error)?;
+                let mut details = Vec::new();
+                if let Some(ref entity) = context.entity {
+                    details.push(format!("entity '{}'", entity));
+                }
+                if let Some(ref field) = context.field {
+                    details.push(format!("field '{}'", field));
+                }
+                if let Some(ref excerpt) = context.source_excerpt {
+                    details.push(format!("source {}", excerpt));
+                }
+                if !details.is_empty() {
+                    write!(f, " ({})", details.join(", "))?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -109,7 +271,7 @@ pub trait Entity: Serialize + Sized {
     const NAME: &'static str;
 
     /// Convert entity to a dictionary representation
-    fn to_dict(&self) -> HashMap<String, FieldValue> {
+    fn to_dict(&self) -> HashMap<String, OwnedFieldValue> {
         // Default implementation using serde_json
         let json_value = serde_json::to_value(self)
             .expect("Failed to serialize entity");
@@ -138,15 +300,29 @@ pub trait Entity: Serialize + Sized {
         let json = self.to_json()?;
         Ok(format!("{}\n", json))
     }
+
+    /// Rebuild the CSV line or JSON document this entity was extracted from
+    ///
+    /// Only direct field mappings can be reversed; see
+    /// [`crate::serialization::to_source_format`].
+    fn to_source_format(
+        &self,
+        definition: &crate::codegen::EntityDef,
+    ) -> Result<crate::serialization::SourceRecord, crate::serialization::SerializationError> {
+        crate::serialization::to_source_format(definition, &self.to_dict())
+    }
 }
 
 /// Helper function to convert serde_json::Value to FieldValue
-fn json_value_to_field_value(value: serde_json::Value) -> FieldValue {
+fn json_value_to_field_value(value: serde_json::Value) -> OwnedFieldValue {
     match value {
-        serde_json::Value::String(s) => FieldValue::String(s),
+        serde_json::Value::String(s) => FieldValue::from(s),
         serde_json::Value::Number(n) => {
             if let Some(i) = n.as_i64() {
                 FieldValue::Int(i)
+            } else if n.is_u64() {
+                // Beyond i64: keep every digit rather than rounding through f64
+                FieldValue::from(n.to_string())
             } else if let Some(f) = n.as_f64() {
                 FieldValue::Float(f)
             } else {
@@ -160,7 +336,7 @@ fn json_value_to_field_value(value: serde_json::Value) -> FieldValue {
         serde_json::Value::Null => FieldValue::Null,
         serde_json::Value::Object(_) => {
             // For nested objects, serialize to string
-            FieldValue::String(value.to_string())
+            FieldValue::from(value.to_string())
         }
     }
 }
@@ -199,9 +375,40 @@ mod tests {
         };
 
         let dict = entity.to_dict();
-        assert_eq!(dict.get("name"), Some(&FieldValue::String("Bob".to_string())));
+        assert_eq!(dict.get("name"), Some(&FieldValue::from("Bob")));
         assert_eq!(dict.get("age"), Some(&FieldValue::Int(25)));
     }
+
+    #[test]
+    fn test_field_value_borrows_until_owned() {
+        let source = String::from("a,b");
+        let value = FieldValue::List(vec![FieldValue::borrowed(&source[..1]), FieldValue::Int(1)]);
+        assert!(value.is_borrowed());
+
+        let owned: OwnedFieldValue = value.clone().into_owned();
+        drop(source);
+        assert!(!owned.is_borrowed());
+        assert_eq!(owned, FieldValue::List(vec![FieldValue::from("a"), FieldValue::Int(1)]));
+    }
+
+    #[test]
+    fn test_error_context() {
+        let err = EntityError::transform_failed("Order", "amount", "not a number")
+            .with_source_excerpt("x".repeat(100))
+            .in_entity("Outer");
+
+        let context = err.context().unwrap();
+        assert_eq!(context.entity.as_deref(), Some("Order"));
+        assert_eq!(err.field(), Some("amount"));
+        assert_eq!(context.source_excerpt.as_ref().unwrap().len(), SOURCE_EXCERPT_LEN + 3);
+        assert!(err.to_string().starts_with(
+            "Transform error: not a number (entity 'Order', field 'amount', source xxx"
+        ));
+
+        let missing = EntityError::RequiredFieldMissing { field: "id".to_string() };
+        assert!(missing.context().is_none());
+        assert_eq!(missing.field(), Some("id"));
+    }
 }
 
 /// Context for additional fields not in the data source.
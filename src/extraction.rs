@@ -3,8 +3,11 @@
 //! This module provides format-agnostic patterns for extracting values from
 //! structured data using path-based addressing.
 
+use std::borrow::Cow;
 use std::fmt;
 
+use crate::entity::FieldValue;
+
 /// Represents a path to a field in structured data
 ///
 /// # Examples
@@ -88,9 +91,14 @@ impl fmt::Display for FieldPath {
 /// This trait allows different data formats to implement their own
 /// extraction logic while maintaining a common interface.
 ///
+/// Implementations return a slice of their own data (`Cow::Borrowed`)
+/// whenever the value exists verbatim in the source, and only allocate
+/// (`Cow::Owned`) when the value has to be rebuilt, e.g. unescaped.
+///
 /// # Example
 ///
 /// ```ignore
+/// use std::borrow::Cow;
 /// use nomnom::{Extractor, FieldPath};
 ///
 /// struct CsvRow {
@@ -98,10 +106,10 @@ impl fmt::Display for FieldPath {
 /// }
 ///
 /// impl Extractor for CsvRow {
-///     fn extract(&self, path: &FieldPath) -> Option<String> {
+///     fn extract(&self, path: &FieldPath) -> Option<Cow<'_, str>> {
 ///         // Extract by column index
 ///         if let Some(PathSegment::Index(idx)) = path.segments.first() {
-///             self.fields.get(*idx).cloned()
+///             self.fields.get(*idx).map(|s| Cow::Borrowed(s.as_str()))
 ///         } else {
 ///             None
 ///         }
@@ -112,7 +120,17 @@ pub trait Extractor {
     /// Extract a value at the given field path
     ///
     /// Returns `Some(value)` if the path exists, `None` otherwise
-    fn extract(&self, path: &FieldPath) -> Option<String>;
+    fn extract(&self, path: &FieldPath) -> Option<Cow<'_, str>>;
+
+    /// Extract a value as an owned `String`
+    fn extract_owned(&self, path: &FieldPath) -> Option<String> {
+        self.extract(path).map(Cow::into_owned)
+    }
+
+    /// Extract a value as a [`FieldValue`] borrowing from this source
+    fn extract_value(&self, path: &FieldPath) -> Option<FieldValue<'_>> {
+        self.extract(path).map(FieldValue::String)
+    }
 
     /// Extract a value and parse it to a specific type
     fn extract_as<T>(&self, path: &FieldPath) -> Option<T>
@@ -163,9 +181,9 @@ mod tests {
     }
 
     impl Extractor for SimpleExtractor {
-        fn extract(&self, path: &FieldPath) -> Option<String> {
+        fn extract(&self, path: &FieldPath) -> Option<Cow<'_, str>> {
             if let Some(PathSegment::Field(name)) = path.segments.first() {
-                self.data.get(name).cloned()
+                self.data.get(name).map(|s| Cow::Borrowed(s.as_str()))
             } else {
                 None
             }
@@ -181,7 +199,9 @@ mod tests {
         let extractor = SimpleExtractor { data };
 
         let name_path = FieldPath::from_dotted("name");
-        assert_eq!(extractor.extract(&name_path), Some("Alice".to_string()));
+        assert_eq!(extractor.extract(&name_path).as_deref(), Some("Alice"));
+        assert_eq!(extractor.extract_owned(&name_path), Some("Alice".to_string()));
+        assert!(extractor.extract_value(&name_path).unwrap().is_borrowed());
 
         let age_path = FieldPath::from_dotted("age");
         assert_eq!(extractor.extract_as::<i32>(&age_path), Some(30));
@@ -72,13 +72,13 @@ pub mod diesel_runtime;
 pub mod nats;
 
 // Re-export key types
-pub use entity::{Entity, FieldValue, EntityError, Context, IntoOptionString};
+pub use entity::{Entity, FieldValue, OwnedFieldValue, EntityError, ErrorContext, Context, IntoOptionString};
 pub use transform_registry::{TransformRegistry, TransformError};
-pub use extraction::{FieldPath, Extractor};
+pub use extraction::{FieldPath, PathSegment, Extractor};
 
 // Re-export runtime types
 pub use runtime::{
-    ExtractionContext, MessageParser, EntityExtractor, ParserConfig, compute_extraction_order
+    ExtractionContext, MessageParser, EntityExtractor, DefinitionExtractor, CompiledExtractor, ExtractionOutcome, ExtractionReport, OnEntityError, ParserConfig, ParserConfigBuilder, compute_extraction_order
 };
 
 // Re-export codegen types when building
@@ -88,7 +88,7 @@ pub use codegen::{EntityDef, FieldDef, ComputedFrom};
 pub use diesel_runtime::{Database, DatabaseConfig, GetOrCreate, BulkInsert};
 
 // Re-export nats types
-pub use nats::{MessageEnvelope, IngestionResponse, IngestionStatus, NatsClient, NatsConfig};
+pub use nats::{MessageEnvelope, EnvelopeDecoder, IngestionResponse, IngestionStatus, NatsClient, NatsConfig};
 
 #[cfg(feature = "python-bridge")]
 pub use diesel_runtime::PyDatabase;
@@ -3,8 +3,11 @@
 /// Provides connection management and message publishing to NATS JetStream
 
 use async_nats::jetstream;
+use std::path::PathBuf;
 use std::time::Duration;
+use crate::nats::compression::BodyCodec;
 use crate::nats::message_envelope::MessageEnvelope;
+use crate::serialization::{SerializationFormat, Serializer};
 
 #[derive(Clone)]
 pub struct NatsConfig {
@@ -12,6 +15,27 @@ pub struct NatsConfig {
     pub stream_name: String,
     pub max_age: Duration,
     pub max_bytes: i64,
+    /// Wire format for published envelopes (JSON by default for compatibility)
+    pub serialization_format: SerializationFormat,
+    /// Compression applied to envelope bodies before publishing (none by default)
+    ///
+    /// An unknown `NATS_BODY_CODEC` falls back to none here and is rejected
+    /// by [`NatsClient::connect`].
+    pub body_codec: BodyCodec,
+    /// Credentials file (JWT + nkey seed) for decentralized auth
+    pub creds_file: Option<PathBuf>,
+    /// Token auth
+    pub token: Option<String>,
+    /// Nkey seed for nkey auth
+    pub nkey: Option<String>,
+    /// Require TLS even if the server doesn't announce it
+    pub require_tls: bool,
+    /// Root certificates (PEM) to trust in addition to the system roots
+    pub tls_ca: Option<PathBuf>,
+    /// Client certificate (PEM) for mutual TLS, set together with `tls_client_key`
+    pub tls_client_cert: Option<PathBuf>,
+    /// Client key (PEM) for mutual TLS, set together with `tls_client_cert`
+    pub tls_client_key: Option<PathBuf>,
 }
 
 impl Default for NatsConfig {
@@ -23,22 +47,110 @@ impl Default for NatsConfig {
                 .unwrap_or_else(|_| "MESSAGES".to_string()),
             max_age: Duration::from_secs(24 * 60 * 60), // 24 hours
             max_bytes: 1024 * 1024 * 1024, // 1GB
+            serialization_format: std::env::var("NATS_SERIALIZATION_FORMAT")
+                .ok()
+                .and_then(|f| f.parse().ok())
+                .unwrap_or_default(),
+            body_codec: body_codec_from_env().unwrap_or_default(),
+            creds_file: env_non_empty("NATS_CREDS").map(PathBuf::from),
+            token: env_non_empty("NATS_TOKEN"),
+            nkey: env_non_empty("NATS_NKEY"),
+            require_tls: env_non_empty("NATS_TLS")
+                .is_some_and(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes" | "on")),
+            tls_ca: env_non_empty("NATS_TLS_CA").map(PathBuf::from),
+            tls_client_cert: env_non_empty("NATS_TLS_CERT").map(PathBuf::from),
+            tls_client_key: env_non_empty("NATS_TLS_KEY").map(PathBuf::from),
         }
     }
 }
 
+/// Error for a client certificate without its key or the reverse, also
+/// raised by the generated `nats_connect_options()`
+pub const TLS_CLIENT_CERT_PAIR_ERROR: &str = "NATS_TLS_CERT and NATS_TLS_KEY must be set together";
+
+/// Client certificate and key for mutual TLS, which only work as a pair
+pub fn tls_client_cert_pair(
+    cert: Option<PathBuf>,
+    key: Option<PathBuf>,
+) -> Result<Option<(PathBuf, PathBuf)>, String> {
+    match (cert, key) {
+        (Some(cert), Some(key)) => Ok(Some((cert, key))),
+        (None, None) => Ok(None),
+        _ => Err(TLS_CLIENT_CERT_PAIR_ERROR.to_string()),
+    }
+}
+
+/// Codec named by `NATS_BODY_CODEC` (none when unset or blank)
+pub fn body_codec_from_env() -> Result<BodyCodec, String> {
+    parse_body_codec(env_non_empty("NATS_BODY_CODEC").as_deref())
+}
+
+fn parse_body_codec(value: Option<&str>) -> Result<BodyCodec, String> {
+    match value {
+        None => Ok(BodyCodec::None),
+        Some(codec) => codec.parse().map_err(|_| format!(
+            "Unknown NATS_BODY_CODEC '{}' (expected none, gzip or zstd)", codec
+        )),
+    }
+}
+
+/// Value of an environment variable, unless unset or blank
+fn env_non_empty(var: &str) -> Option<String> {
+    std::env::var(var).ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+impl NatsConfig {
+    /// Connect options carrying the configured auth and TLS settings
+    ///
+    /// Fails if only one of the client certificate and key is set.
+    pub async fn connect_options(&self) -> Result<async_nats::ConnectOptions, async_nats::Error> {
+        let mut options = async_nats::ConnectOptions::new();
+        if let Some(ref creds) = self.creds_file {
+            options = options.credentials_file(creds).await
+                .map_err(|e| format!("Failed to read NATS credentials file {}: {}", creds.display(), e))?;
+        }
+        if let Some(ref token) = self.token {
+            options = options.token(token.clone());
+        }
+        if let Some(ref seed) = self.nkey {
+            options = options.nkey(seed.clone());
+        }
+        if self.require_tls {
+            options = options.require_tls(true);
+        }
+        if let Some(ref ca) = self.tls_ca {
+            options = options.add_root_certificates(ca.clone());
+        }
+        if let Some((cert, key)) = tls_client_cert_pair(self.tls_client_cert.clone(), self.tls_client_key.clone())? {
+            options = options.add_client_certificate(cert, key);
+        }
+        Ok(options)
+    }
+}
+
 #[derive(Clone)]
 pub struct NatsClient {
     client: async_nats::Client,
     jetstream: jetstream::Context,
     stream_name: String,
+    serialization_format: SerializationFormat,
+    body_codec: BodyCodec,
 }
 
 impl NatsClient {
     /// Connect to NATS and initialize JetStream
+    ///
+    /// Fails if `NATS_BODY_CODEC` names an unknown codec.
     pub async fn connect(config: NatsConfig) -> Result<Self, async_nats::Error> {
+        body_codec_from_env()?;
+        if let Some(codec) = config.body_codec.content_encoding() {
+            tracing::info!("Compressing message bodies with {}", codec);
+        }
+
         // Connect to NATS
-        let client = async_nats::connect(&config.url).await?;
+        let client = config.connect_options().await?.connect(&config.url).await?;
         tracing::info!("Connected to NATS at {}", config.url);
 
         // Get JetStream context
@@ -63,6 +175,8 @@ impl NatsClient {
             client,
             jetstream,
             stream_name: config.stream_name,
+            serialization_format: config.serialization_format,
+            body_codec: config.body_codec,
         })
     }
 
@@ -74,11 +188,21 @@ impl NatsClient {
         let subject = format!("messages.ingest.{}",
             envelope.entity_type.as_deref().unwrap_or("default"));
 
-        let payload = serde_json::to_vec(envelope)?;
+        let payload = if self.body_codec == BodyCodec::None || envelope.content_encoding.is_some() {
+            self.serialization_format.serialize(envelope)?
+        } else {
+            let mut compressed = envelope.clone();
+            compressed.compress_body(self.body_codec)?;
+            self.serialization_format.serialize(&compressed)?
+        };
+
+        // Tag the payload so consumers can pick the matching deserializer
+        let mut headers = async_nats::HeaderMap::new();
+        headers.insert("Content-Type", self.serialization_format.content_type());
 
         // Publish with JetStream (durable, acknowledged)
         let ack = self.jetstream
-            .publish(subject.clone(), payload.into())
+            .publish_with_headers(subject.clone(), headers, payload.into())
             .await?;
 
         // Wait for acknowledgment
@@ -98,8 +222,40 @@ impl NatsClient {
         &self.jetstream
     }
 
+    /// Wire format used for published envelopes
+    pub fn serialization_format(&self) -> SerializationFormat {
+        self.serialization_format
+    }
+
     /// Check if the NATS connection is active
     pub fn is_connected(&self) -> bool {
         self.client.connection_state() == async_nats::connection::State::Connected
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tls_client_cert_pair() {
+        let path = |p: &str| Some(PathBuf::from(p));
+        assert_eq!(
+            tls_client_cert_pair(path("client.pem"), path("client.key")).unwrap(),
+            Some((PathBuf::from("client.pem"), PathBuf::from("client.key")))
+        );
+        assert_eq!(tls_client_cert_pair(None, None).unwrap(), None);
+        assert_eq!(tls_client_cert_pair(path("client.pem"), None).unwrap_err(), TLS_CLIENT_CERT_PAIR_ERROR);
+        assert_eq!(tls_client_cert_pair(None, path("client.key")).unwrap_err(), TLS_CLIENT_CERT_PAIR_ERROR);
+    }
+
+    #[test]
+    fn test_parse_body_codec() {
+        assert_eq!(parse_body_codec(None).unwrap(), BodyCodec::None);
+        assert_eq!(parse_body_codec(Some("GZIP")).unwrap(), BodyCodec::Gzip);
+        assert_eq!(
+            parse_body_codec(Some("brotli")).unwrap_err(),
+            "Unknown NATS_BODY_CODEC 'brotli' (expected none, gzip or zstd)"
+        );
+    }
+}
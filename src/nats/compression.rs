@@ -0,0 +1,136 @@
+/// Optional compression of message envelope bodies
+///
+/// A compressed body is stored base64-encoded in `MessageEnvelope.body`, with
+/// the codec recorded in `content_encoding`. Envelopes without
+/// `content_encoding` carry the body as-is, so consumers handle both during a
+/// rollout.
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read, Write};
+
+/// Largest body [`BodyCodec::decompress`] produces, so a small compressed
+/// payload can't expand without bound (64 MiB)
+pub const MAX_DECOMPRESSED_BODY_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Codec applied to envelope bodies before publishing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BodyCodec {
+    /// Body published as-is (default)
+    #[default]
+    None,
+    /// gzip (RFC 1952)
+    Gzip,
+    /// Zstandard
+    Zstd,
+}
+
+impl BodyCodec {
+    /// Value of the envelope's `content_encoding` field (None when uncompressed)
+    pub fn content_encoding(&self) -> Option<&'static str> {
+        match self {
+            BodyCodec::None => None,
+            BodyCodec::Gzip => Some("gzip"),
+            BodyCodec::Zstd => Some("zstd"),
+        }
+    }
+
+    /// Look up the codec named by an envelope's `content_encoding` field
+    pub fn from_content_encoding(content_encoding: Option<&str>) -> Result<Self, String> {
+        match content_encoding {
+            None | Some("") | Some("identity") => Ok(BodyCodec::None),
+            Some(encoding) => encoding.parse(),
+        }
+    }
+
+    /// Compress a body, returning its base64 text
+    pub fn compress(&self, body: &str) -> Result<String, String> {
+        let bytes = match self {
+            BodyCodec::None => return Ok(body.to_string()),
+            BodyCodec::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(body.as_bytes())
+                    .and_then(|_| encoder.finish())
+                    .map_err(|e| format!("gzip compression failed: {}", e))?
+            }
+            BodyCodec::Zstd => zstd::encode_all(body.as_bytes(), 0)
+                .map_err(|e| format!("zstd compression failed: {}", e))?,
+        };
+        Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+    }
+
+    /// Decompress a body produced by [`compress`](Self::compress)
+    ///
+    /// Fails if the result would exceed [`MAX_DECOMPRESSED_BODY_BYTES`].
+    pub fn decompress(&self, body: &str) -> Result<String, String> {
+        let compressed = || base64::engine::general_purpose::STANDARD.decode(body)
+            .map(Cursor::new)
+            .map_err(|e| format!("Compressed body is not valid base64: {}", e));
+        let mut reader: Box<dyn Read> = match self {
+            BodyCodec::None => return Ok(body.to_string()),
+            BodyCodec::Gzip => Box::new(flate2::read::GzDecoder::new(compressed()?)),
+            BodyCodec::Zstd => Box::new(zstd::Decoder::new(compressed()?)
+                .map_err(|e| format!("zstd decompression failed: {}", e))?),
+        };
+        let codec = self.content_encoding().unwrap_or_default();
+        let mut decompressed = Vec::new();
+        reader.take(MAX_DECOMPRESSED_BODY_BYTES + 1).read_to_end(&mut decompressed)
+            .map_err(|e| format!("{} decompression failed: {}", codec, e))?;
+        if decompressed.len() as u64 > MAX_DECOMPRESSED_BODY_BYTES {
+            return Err(format!(
+                "{} body exceeds {} bytes when decompressed",
+                codec, MAX_DECOMPRESSED_BODY_BYTES
+            ));
+        }
+        String::from_utf8(decompressed)
+            .map_err(|e| format!("{} decompression failed: {}", codec, e))
+    }
+}
+
+impl std::str::FromStr for BodyCodec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" | "identity" => Ok(BodyCodec::None),
+            "gzip" | "gz" => Ok(BodyCodec::Gzip),
+            "zstd" | "zst" => Ok(BodyCodec::Zstd),
+            other => Err(format!(
+                "Unknown body codec '{}' (expected none, gzip or zstd)",
+                other
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let body = "MSH|^~\\&|LAB|".repeat(100);
+        for codec in [BodyCodec::None, BodyCodec::Gzip, BodyCodec::Zstd] {
+            let compressed = codec.compress(&body).unwrap();
+            let codec = BodyCodec::from_content_encoding(codec.content_encoding()).unwrap();
+            assert_eq!(codec.decompress(&compressed).unwrap(), body);
+        }
+        assert!(BodyCodec::Gzip.compress(&body).unwrap().len() < body.len());
+    }
+
+    #[test]
+    fn test_unknown_encoding_is_rejected() {
+        assert!(BodyCodec::from_content_encoding(Some("brotli")).is_err());
+        assert!(BodyCodec::Gzip.decompress("not base64!").is_err());
+    }
+
+    #[test]
+    fn test_decompressed_size_is_capped() {
+        let body = "0".repeat(MAX_DECOMPRESSED_BODY_BYTES as usize + 1);
+        for codec in [BodyCodec::Gzip, BodyCodec::Zstd] {
+            let err = codec.decompress(&codec.compress(&body).unwrap()).unwrap_err();
+            assert!(err.contains("exceeds"), "{}", err);
+        }
+    }
+}
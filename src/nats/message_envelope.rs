@@ -3,17 +3,41 @@
 /// Wraps raw message body with metadata for tracking and processing
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use crate::nats::compression::BodyCodec;
+use crate::serialization::{SerializationFormat, Serializer};
+use std::collections::{BTreeMap, HashMap};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+/// Current envelope schema version written by this build
+///
+/// Envelopes published before versioning was introduced carry no
+/// `schema_version` and are treated as version 0.
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessageEnvelope {
+    /// Envelope schema version (0 = pre-versioning envelope)
+    #[serde(default)]
+    pub schema_version: u32,
+
     /// Unique message ID for tracking
     pub message_id: Uuid,
 
-    /// Raw message body (before parsing)
+    /// Raw message body (before parsing), base64 of the compressed bytes
+    /// when `content_encoding` is set
     pub body: String,
 
+    /// Codec `body` is compressed with (`gzip`, `zstd`); absent when uncompressed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_encoding: Option<String>,
+
+    /// Hex SHA-256 of the uncompressed body, set at ingestion
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_sha256: Option<String>,
+
     /// Entity type hint (from URL path or header)
     pub entity_type: Option<String>,
 
@@ -26,20 +50,170 @@ pub struct MessageEnvelope {
 
     /// Source IP or identifier
     pub source: Option<String>,
+
+    /// Correlation ID carried from ingestion through the worker
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+
+    /// W3C trace context (traceparent/tracestate) of the publishing span
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_context: Option<HashMap<String, String>>,
 }
 
 impl MessageEnvelope {
     /// Create a new message envelope
     pub fn new(body: String, entity_type: Option<String>) -> Self {
         Self {
+            schema_version: CURRENT_SCHEMA_VERSION,
             message_id: Uuid::new_v4(),
+            body_sha256: Some(body_sha256(&body)),
             body,
+            content_encoding: None,
             entity_type,
             received_at: Utc::now(),
             retry_count: 0,
             source: None,
+            correlation_id: None,
+            trace_context: None,
+        }
+    }
+
+    /// Compress the body with `codec`, unless it is already compressed
+    pub fn compress_body(&mut self, codec: BodyCodec) -> Result<(), String> {
+        if self.content_encoding.is_none() {
+            self.body = codec.compress(&self.body)?;
+            self.content_encoding = codec.content_encoding().map(str::to_string);
+        }
+        Ok(())
+    }
+
+    /// Body as originally received, decompressing it if needed
+    pub fn decoded_body(&self) -> Result<String, String> {
+        BodyCodec::from_content_encoding(self.content_encoding.as_deref())?.decompress(&self.body)
+    }
+
+    /// Check the decoded body against `body_sha256`, returning the body
+    ///
+    /// Envelopes without a checksum pass unchecked.
+    pub fn verified_body(&self) -> Result<String, String> {
+        let body = self.decoded_body()?;
+        match self.body_sha256 {
+            Some(ref expected) if !expected.eq_ignore_ascii_case(&body_sha256(&body)) => Err(format!(
+                "Body of message {} does not match body_sha256 {} (truncated or corrupted payload)",
+                self.message_id, expected
+            )),
+            _ => Ok(body),
+        }
+    }
+
+    /// How this envelope's schema version relates to the current one
+    pub fn compatibility(&self) -> SchemaCompatibility {
+        use std::cmp::Ordering;
+        match self.schema_version.cmp(&CURRENT_SCHEMA_VERSION) {
+            Ordering::Equal => SchemaCompatibility::Current,
+            Ordering::Less => SchemaCompatibility::Older(self.schema_version),
+            Ordering::Greater => SchemaCompatibility::Newer(self.schema_version),
+        }
+    }
+}
+
+/// Hex SHA-256 of a message body, as stored in `body_sha256`
+pub fn body_sha256(body: &str) -> String {
+    format!("{:x}", Sha256::digest(body.as_bytes()))
+}
+
+/// Relationship between an envelope's schema version and [`CURRENT_SCHEMA_VERSION`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaCompatibility {
+    /// Same version as this build
+    Current,
+    /// Older version (should be migrated)
+    Older(u32),
+    /// Newer version: known fields are read, unknown fields ignored
+    Newer(u32),
+}
+
+/// Upgrade step applied to a raw envelope of a given version
+pub type EnvelopeMigration = Box<dyn Fn(&mut Value) -> Result<(), String> + Send + Sync>;
+
+/// Decodes envelopes of any schema version into the current shape
+///
+/// Migrations are registered per source version and applied in order
+/// (v0 -> v1 -> ... -> current) to the raw JSON before deserialization.
+/// Envelopes from newer producers are decoded leniently: unknown fields are
+/// ignored rather than rejected, so a rollout never jams the consumer.
+#[derive(Default)]
+pub struct EnvelopeDecoder {
+    migrations: BTreeMap<u32, EnvelopeMigration>,
+}
+
+impl EnvelopeDecoder {
+    /// Create a decoder with no migrations (missing fields fall back to serde defaults)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the migration that upgrades envelopes from `from_version` to `from_version + 1`
+    pub fn with_migration<F>(mut self, from_version: u32, migration: F) -> Self
+    where
+        F: Fn(&mut Value) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.migrations.insert(from_version, Box::new(migration));
+        self
+    }
+
+    /// Decode a JSON payload, upgrading older envelopes to the current version
+    pub fn decode(&self, payload: &[u8]) -> Result<MessageEnvelope, String> {
+        let value: Value = serde_json::from_slice(payload)
+            .map_err(|e| format!("Invalid envelope: {}", e))?;
+        self.decode_value(value)
+    }
+
+    /// Decode a payload in the format named by its Content-Type header
+    ///
+    /// Payloads without the header are JSON. MessagePack and CBOR envelopes
+    /// are only published at the current version, so they skip migrations.
+    pub fn decode_with_content_type(&self, payload: &[u8], content_type: Option<&str>) -> Result<MessageEnvelope, String> {
+        let format = match content_type {
+            None => SerializationFormat::Json,
+            Some(content_type) => SerializationFormat::from_content_type(content_type)
+                .ok_or_else(|| format!("Unsupported envelope Content-Type '{}'", content_type))?,
+        };
+        match format {
+            SerializationFormat::Json => self.decode(payload),
+            binary => binary.deserialize(payload).map_err(|e| format!("Invalid envelope: {}", e)),
         }
     }
+
+    /// Decode an already-parsed envelope value
+    pub fn decode_value(&self, mut value: Value) -> Result<MessageEnvelope, String> {
+        let mut version = value
+            .get("schema_version")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0) as u32;
+
+        if version > CURRENT_SCHEMA_VERSION {
+            tracing::warn!(
+                "Envelope schema version {} is newer than supported version {}; ignoring unknown fields",
+                version,
+                CURRENT_SCHEMA_VERSION
+            );
+        }
+
+        while version < CURRENT_SCHEMA_VERSION {
+            if let Some(migration) = self.migrations.get(&version) {
+                migration(&mut value).map_err(|e| {
+                    format!("Envelope migration from v{} failed: {}", version, e)
+                })?;
+            }
+            version += 1;
+            if let Some(obj) = value.as_object_mut() {
+                obj.insert("schema_version".to_string(), Value::from(version));
+            }
+        }
+
+        serde_json::from_value(value).map_err(|e| format!("Invalid envelope: {}", e))
+    }
 }
 
 /// Response returned to client after ingestion
@@ -57,3 +231,113 @@ pub enum IngestionStatus {
     Persisted,  // Written to DB (for sync mode)
     Failed,     // Validation or other error
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_envelope_uses_current_version() {
+        let envelope = MessageEnvelope::new("body".to_string(), None);
+        assert_eq!(envelope.schema_version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(envelope.compatibility(), SchemaCompatibility::Current);
+    }
+
+    #[test]
+    fn test_newer_envelope_ignores_unknown_fields() {
+        let payload = serde_json::json!({
+            "schema_version": CURRENT_SCHEMA_VERSION + 1,
+            "message_id": Uuid::new_v4(),
+            "body": "MSH|...",
+            "entity_type": null,
+            "received_at": Utc::now(),
+            "source": null,
+            "priority": "high"
+        });
+
+        let envelope = EnvelopeDecoder::new()
+            .decode(payload.to_string().as_bytes())
+            .unwrap();
+        assert_eq!(envelope.body, "MSH|...");
+        assert_eq!(
+            envelope.compatibility(),
+            SchemaCompatibility::Newer(CURRENT_SCHEMA_VERSION + 1)
+        );
+    }
+
+    #[test]
+    fn test_decode_with_content_type_round_trips_every_format() {
+        let mut envelope = MessageEnvelope::new("MSH|1".to_string(), Some("Order".to_string()));
+        envelope.compress_body(BodyCodec::Zstd).unwrap();
+
+        for format in [SerializationFormat::Json, SerializationFormat::MessagePack, SerializationFormat::Cbor] {
+            let payload = format.serialize(&envelope).unwrap();
+            let decoded = EnvelopeDecoder::new()
+                .decode_with_content_type(&payload, Some(format.content_type()))
+                .unwrap();
+            assert_eq!(decoded.message_id, envelope.message_id, "{:?}", format);
+            assert_eq!(decoded.entity_type.as_deref(), Some("Order"), "{:?}", format);
+            assert_eq!(decoded.received_at, envelope.received_at, "{:?}", format);
+            assert_eq!(decoded.decoded_body().unwrap(), "MSH|1", "{:?}", format);
+        }
+
+        let json = serde_json::to_vec(&envelope).unwrap();
+        assert!(EnvelopeDecoder::new().decode_with_content_type(&json, None).is_ok());
+        assert!(EnvelopeDecoder::new().decode_with_content_type(&json, Some("text/xml")).is_err());
+    }
+
+    #[test]
+    fn test_unversioned_envelope_is_migrated() {
+        // v0 envelopes used `payload` instead of `body`
+        let payload = serde_json::json!({
+            "message_id": Uuid::new_v4(),
+            "payload": "legacy body",
+            "entity_type": "Order",
+            "received_at": Utc::now(),
+            "source": null
+        });
+
+        let decoder = EnvelopeDecoder::new().with_migration(0, |value| {
+            let obj = value.as_object_mut().ok_or("envelope is not an object")?;
+            let body = obj.remove("payload").ok_or("missing payload")?;
+            obj.insert("body".to_string(), body);
+            Ok(())
+        });
+
+        let envelope = decoder.decode(payload.to_string().as_bytes()).unwrap();
+        assert_eq!(envelope.body, "legacy body");
+        assert_eq!(envelope.schema_version, CURRENT_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_compressed_body_round_trips() {
+        let mut envelope = MessageEnvelope::new("<order id=\"1\"/>".repeat(50), None);
+        envelope.compress_body(BodyCodec::Zstd).unwrap();
+        assert_eq!(envelope.content_encoding.as_deref(), Some("zstd"));
+
+        // Compressing twice must not double-encode
+        envelope.compress_body(BodyCodec::Gzip).unwrap();
+        let payload = serde_json::to_vec(&envelope).unwrap();
+        let decoded = EnvelopeDecoder::new().decode(&payload).unwrap();
+        assert_eq!(decoded.decoded_body().unwrap(), "<order id=\"1\"/>".repeat(50));
+
+        // Envelopes from producers without compression decode unchanged
+        let plain = MessageEnvelope::new("plain".to_string(), None);
+        assert!(!serde_json::to_string(&plain).unwrap().contains("content_encoding"));
+        assert_eq!(plain.decoded_body().unwrap(), "plain");
+    }
+
+    #[test]
+    fn test_body_sha256_detects_corruption() {
+        let mut envelope = MessageEnvelope::new("MSH|^~\\&|LAB".to_string(), None);
+        envelope.compress_body(BodyCodec::Gzip).unwrap();
+        assert_eq!(envelope.verified_body().unwrap(), "MSH|^~\\&|LAB");
+
+        let mut truncated = MessageEnvelope::new("MSH|^~\\&|LAB".to_string(), None);
+        truncated.body.truncate(5);
+        assert!(truncated.verified_body().unwrap_err().contains("does not match body_sha256"));
+
+        truncated.body_sha256 = None;
+        assert_eq!(truncated.verified_body().unwrap(), "MSH|^");
+    }
+}
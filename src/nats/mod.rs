@@ -4,6 +4,13 @@
 
 pub mod message_envelope;
 pub mod client;
+pub mod compression;
+pub mod replay;
 
-pub use message_envelope::{MessageEnvelope, IngestionResponse, IngestionStatus};
+pub use message_envelope::{
+    MessageEnvelope, IngestionResponse, IngestionStatus, EnvelopeDecoder, SchemaCompatibility,
+    CURRENT_SCHEMA_VERSION, body_sha256,
+};
 pub use client::{NatsClient, NatsConfig};
+pub use compression::BodyCodec;
+pub use replay::{ReplayOptions, ReplaySummary, replay_dlq, reset_message_status};
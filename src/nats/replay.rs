@@ -0,0 +1,221 @@
+/// Replay of dead-lettered messages
+///
+/// Workers route messages that exhausted their retries (or failed
+/// permanently) to `messages.dlq.<entity>`, keeping the original envelope as
+/// the payload. Once the cause is fixed, [`replay_dlq`] moves them back to
+/// `messages.ingest.<entity_type>` for the workers to process again, putting
+/// each one's `message_status` row back to `accepted` (see
+/// [`reset_message_status`]) just before it is republished.
+
+use async_nats::jetstream;
+use futures::StreamExt;
+use std::collections::HashSet;
+use std::time::Duration;
+use uuid::Uuid;
+use crate::diesel_runtime::database::DbConnection;
+use crate::nats::message_envelope::EnvelopeDecoder;
+
+/// Which dead-lettered messages to replay
+#[derive(Debug, Clone)]
+pub struct ReplayOptions {
+    /// Stream holding `messages.dlq.>` (the ingestion server's `<NATS_STREAM>DLQ`)
+    pub dlq_stream: String,
+    /// Only replay messages ingested as this entity type (the envelope's
+    /// `entity_type`, whatever entity they were dead-lettered under)
+    pub entity_type: Option<String>,
+    /// Stop after this many messages
+    pub max_messages: Option<usize>,
+    /// Give up waiting for more messages after this long
+    pub idle_timeout: Duration,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        let stream = std::env::var("NATS_STREAM").unwrap_or_else(|_| "MESSAGES".to_string());
+        Self {
+            dlq_stream: format!("{}DLQ", stream),
+            entity_type: None,
+            max_messages: None,
+            idle_timeout: Duration::from_secs(2),
+        }
+    }
+}
+
+impl ReplayOptions {
+    /// Whether a message going back to `ingest_subject` (see
+    /// [`replay_subject`]) passes the `entity_type` filter
+    pub fn selects(&self, ingest_subject: &str) -> bool {
+        self.entity_type.as_deref().map_or(true, |entity_type| {
+            ingest_subject.strip_prefix("messages.ingest.") == Some(entity_type)
+        })
+    }
+}
+
+/// Outcome of a replay
+#[derive(Debug, Default)]
+pub struct ReplaySummary {
+    /// Messages republished to their ingest subject
+    pub replayed: usize,
+    /// Further copies of replayed messages (one is dead-lettered per failed
+    /// entity), removed without republishing
+    pub duplicates: usize,
+    /// Ids of the replayed messages whose envelope could be decoded
+    pub message_ids: Vec<Uuid>,
+    /// `message_status` rows put back to `accepted`
+    pub reset: usize,
+}
+
+/// Ingest subject a dead-lettered payload goes back to
+///
+/// The envelope's `entity_type` names it; the DLQ subject can't, since
+/// entity failures are dead-lettered under the failed entity's name. Payloads
+/// that aren't envelopes fall back to the DLQ subject's entity.
+pub fn replay_subject(payload: &[u8], content_type: Option<&str>, dlq_subject: &str) -> (String, Option<Uuid>) {
+    match EnvelopeDecoder::new().decode_with_content_type(payload, content_type) {
+        Ok(envelope) => (
+            format!("messages.ingest.{}", envelope.entity_type.as_deref().unwrap_or("default")),
+            Some(envelope.message_id),
+        ),
+        Err(_) => (
+            format!("messages.ingest.{}", dlq_subject.strip_prefix("messages.dlq.").unwrap_or("default")),
+            None,
+        ),
+    }
+}
+
+/// Republish dead-lettered messages to their ingest subject
+///
+/// Each message is removed from the DLQ stream once the ingest stream has
+/// acknowledged it, so an interrupted replay can simply be run again.
+/// Messages filtered out by `entity_type` stay in the DLQ stream. A message
+/// dead-lettered once per failed entity is republished once; its other
+/// copies are removed. With `conn`, the message's status is reset before it
+/// is republished, so a worker that finishes it first isn't overridden.
+pub async fn replay_dlq(
+    jetstream: &jetstream::Context,
+    options: &ReplayOptions,
+    mut conn: Option<&mut DbConnection>,
+) -> Result<ReplaySummary, async_nats::Error> {
+    let stream = jetstream.get_stream(&options.dlq_stream).await?;
+    let consumer = stream
+        .create_consumer(jetstream::consumer::pull::Config {
+            filter_subject: "messages.dlq.>".to_string(),
+            ack_policy: jetstream::consumer::AckPolicy::Explicit,
+            inactive_threshold: Duration::from_secs(60),
+            ..Default::default()
+        })
+        .await?;
+
+    let mut summary = ReplaySummary::default();
+    let mut seen: HashSet<Uuid> = HashSet::new();
+    loop {
+        let remaining = options.max_messages.map(|max| max - summary.replayed);
+        if remaining == Some(0) {
+            break;
+        }
+        let mut batch = consumer
+            .fetch()
+            .max_messages(remaining.unwrap_or(100).min(100))
+            .expires(options.idle_timeout)
+            .messages()
+            .await?;
+
+        let mut fetched = 0;
+        while let Some(msg) = batch.next().await {
+            let msg = msg?;
+            fetched += 1;
+            let content_type = msg.headers.as_ref()
+                .and_then(|headers| headers.get("Content-Type"))
+                .map(|value| value.as_str());
+            let (subject, message_id) = replay_subject(&msg.payload, content_type, msg.subject.as_str());
+            if !options.selects(&subject) {
+                // Acked for this consumer only; the message stays in the stream
+                msg.ack().await?;
+                continue;
+            }
+            let sequence = msg.info()?.stream_sequence;
+            if let Some(message_id) = message_id {
+                if !seen.insert(message_id) {
+                    // Another failed entity's copy of a message already republished
+                    stream.delete_message(sequence).await?;
+                    msg.ack().await?;
+                    summary.duplicates += 1;
+                    continue;
+                }
+                if let Some(conn) = conn.as_deref_mut() {
+                    summary.reset += reset_message_status(conn, &[message_id])?;
+                }
+            }
+
+            // Keep the Content-Type so the worker decodes the payload the same way
+            let mut headers = async_nats::HeaderMap::new();
+            if let Some(content_type) = content_type {
+                headers.insert("Content-Type", content_type);
+            }
+            jetstream.publish_with_headers(subject.clone(), headers, msg.payload.clone()).await?.await?;
+
+            stream.delete_message(sequence).await?;
+            msg.ack().await?;
+
+            tracing::info!(subject = %subject, message_id = ?message_id, "Replayed dead-lettered message");
+            summary.replayed += 1;
+            summary.message_ids.extend(message_id);
+        }
+        if fetched == 0 {
+            break;
+        }
+    }
+    Ok(summary)
+}
+
+/// Put replayed messages back to `accepted`, clearing their error and retries
+pub fn reset_message_status(conn: &mut DbConnection, message_ids: &[Uuid]) -> Result<usize, diesel::result::Error> {
+    use diesel::prelude::*;
+    use diesel::sql_types::Text;
+
+    #[cfg(feature = "postgres")]
+    const RESET: &str = "UPDATE message_status SET status = 'accepted', error_message = NULL, retry_count = 0, processed_at = NULL WHERE message_id = CAST($1 AS UUID)";
+    #[cfg(feature = "mysql")]
+    const RESET: &str = "UPDATE message_status SET status = 'accepted', error_message = NULL, retry_count = 0, processed_at = NULL WHERE message_id = ?";
+
+    let mut updated = 0;
+    for message_id in message_ids {
+        updated += diesel::sql_query(RESET)
+            .bind::<Text, _>(message_id.to_string())
+            .execute(conn)?;
+    }
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nats::MessageEnvelope;
+
+    #[test]
+    fn test_replay_subject() {
+        let envelope = MessageEnvelope::new("MSH|1".to_string(), Some("Hl7v2MessageFile".to_string()));
+        let payload = serde_json::to_vec(&envelope).unwrap();
+
+        // Entity failures are dead-lettered under the failed entity; the
+        // message goes back under its own entity type
+        let (subject, message_id) = replay_subject(&payload, None, "messages.dlq.Patient");
+        assert_eq!(subject, "messages.ingest.Hl7v2MessageFile");
+        assert_eq!(message_id, Some(envelope.message_id));
+
+        assert_eq!(replay_subject(b"not json", None, "messages.dlq.Order"), ("messages.ingest.Order".to_string(), None));
+
+        let cbor = crate::serialization::SerializationFormat::Cbor;
+        let payload = crate::serialization::Serializer::serialize(&cbor, &envelope).unwrap();
+        let (subject, _) = replay_subject(&payload, Some(cbor.content_type()), "messages.dlq.Patient");
+        assert_eq!(subject, "messages.ingest.Hl7v2MessageFile");
+
+        // The entity type filter goes by the envelope, not the DLQ subject
+        let (subject, _) = replay_subject(&payload, Some(cbor.content_type()), "messages.dlq.Patient");
+        let options = ReplayOptions { entity_type: Some("Hl7v2MessageFile".to_string()), ..Default::default() };
+        assert!(options.selects(&subject));
+        let options = ReplayOptions { entity_type: Some("Patient".to_string()), ..Default::default() };
+        assert!(!options.selects(&subject));
+        assert!(ReplayOptions::default().selects(&subject));
+    }
+}
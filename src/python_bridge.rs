@@ -9,12 +9,76 @@
 //! nomnom = { version = "0.1", features = ["python-bridge"] }
 //! ```
 
+use crate::entity::EntityError;
+use crate::runtime::transforms::{load_transforms_from_dir, TransformDef};
 use crate::transform_registry::{TransformError, TransformFn};
 use pyo3::prelude::*;
 use pyo3::types::{PyDict, PyList, PyModule};
 use serde_json::Value;
-use std::collections::HashMap;
-use std::sync::Mutex;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Python exception hierarchy raised by nomnom bindings
+///
+/// All exceptions derive from `NomnomError`, so Python callers can catch
+/// everything from nomnom at once or distinguish `DatabaseError` from an
+/// entity validation failure.
+pub mod exceptions {
+    use pyo3::create_exception;
+    use pyo3::exceptions::PyException;
+
+    create_exception!(nomnom, NomnomError, PyException, "Base class for all nomnom errors.");
+    create_exception!(nomnom, TransformError, NomnomError, "A transform was not found or failed to execute.");
+    create_exception!(nomnom, EntityError, NomnomError, "Entity parsing, extraction or validation failed.");
+    create_exception!(nomnom, DatabaseError, NomnomError, "A database connection or query failed.");
+}
+
+/// Register the nomnom exception classes on a Python module
+pub fn register_exceptions(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("NomnomError", py.get_type::<exceptions::NomnomError>())?;
+    m.add("TransformError", py.get_type::<exceptions::TransformError>())?;
+    m.add("EntityError", py.get_type::<exceptions::EntityError>())?;
+    m.add("DatabaseError", py.get_type::<exceptions::DatabaseError>())?;
+    Ok(())
+}
+
+/// Attach a context attribute (e.g. `field`) to a raised exception
+fn with_context(err: PyErr, attr: &str, value: &str) -> PyErr {
+    Python::with_gil(|py| {
+        // Context is best-effort: the message already carries the details
+        let _ = err.value(py).setattr(attr, value);
+    });
+    err
+}
+
+impl From<EntityError> for PyErr {
+    fn from(err: EntityError) -> PyErr {
+        let mut py_err = exceptions::EntityError::new_err(err.to_string());
+        if let Some(field) = err.field() {
+            py_err = with_context(py_err, "field", field);
+        }
+        if let Some(context) = err.context() {
+            if let Some(ref entity) = context.entity {
+                py_err = with_context(py_err, "entity", entity);
+            }
+            if let Some(ref excerpt) = context.source_excerpt {
+                py_err = with_context(py_err, "source_excerpt", excerpt);
+            }
+        }
+        py_err
+    }
+}
+
+impl From<TransformError> for PyErr {
+    fn from(err: TransformError) -> PyErr {
+        let py_err = exceptions::TransformError::new_err(err.to_string());
+        match &err {
+            TransformError::NotFound(name) => with_context(py_err, "transform", name),
+            _ => py_err,
+        }
+    }
+}
 
 /// Python transform function wrapper
 ///
@@ -23,9 +87,6 @@ use std::sync::Mutex;
 pub struct PythonTransform {
     module_name: String,
     function_name: String,
-    /// Cached Python interpreter state
-    /// We use Mutex for thread-safe access
-    py: Mutex<()>,
 }
 
 impl PythonTransform {
@@ -39,14 +100,16 @@ impl PythonTransform {
         Self {
             module_name: module_name.into(),
             function_name: function_name.into(),
-            py: Mutex::new(()),
         }
     }
 
     /// Call the Python function with given arguments
+    ///
+    /// Acquires the GIL for the duration of the call. Callers running inside
+    /// `allow_threads` re-acquire it here; the GIL itself serializes access, so
+    /// no additional lock is taken (a separate lock acquired before the GIL
+    /// could deadlock against a thread holding the GIL).
     fn call_python(&self, args: &HashMap<String, Value>) -> Result<Value, TransformError> {
-        let _guard = self.py.lock().unwrap();
-
         Python::with_gil(|py| {
             // Import the module
             let module = PyModule::import(py, self.module_name.as_str()).map_err(|e| {
@@ -195,11 +258,16 @@ fn py_to_json_value(py: Python, obj: &PyAny) -> Result<Value, TransformError> {
 /// Python Transform Registry
 ///
 /// Provides a registry-style interface for calling Python transform functions.
-/// This wraps a Python module's TRANSFORM_REGISTRY dictionary.
+/// This wraps a Python module's TRANSFORM_REGISTRY dictionary, and optionally
+/// carries the YAML transform definitions so callers can introspect parameters
+/// and return types before invoking a transform.
+#[pyclass(name = "TransformRegistry")]
 #[derive(Clone)]
 pub struct PyTransformRegistry {
     /// Python transforms module name (e.g., "myapp.transforms")
     transforms_module_name: String,
+    /// Transform metadata keyed by name (from transform YAML definitions)
+    definitions: Arc<HashMap<String, TransformDef>>,
 }
 
 impl PyTransformRegistry {
@@ -211,9 +279,32 @@ impl PyTransformRegistry {
     pub fn new(transforms_module_name: impl Into<String>) -> Self {
         Self {
             transforms_module_name: transforms_module_name.into(),
+            definitions: Arc::new(HashMap::new()),
         }
     }
 
+    /// Attach transform definitions used for introspection
+    pub fn with_definitions(mut self, definitions: impl IntoIterator<Item = TransformDef>) -> Self {
+        self.definitions = Arc::new(
+            definitions
+                .into_iter()
+                .map(|def| (def.name.clone(), def))
+                .collect(),
+        );
+        self
+    }
+
+    /// Attach transform definitions loaded from a directory of transform YAMLs
+    pub fn with_definitions_from_dir<P: AsRef<Path>>(self, dir: P) -> Result<Self, String> {
+        let definitions = load_transforms_from_dir(dir)?;
+        Ok(self.with_definitions(definitions))
+    }
+
+    /// Get the transform definition for a name, if known
+    pub fn definition(&self, name: &str) -> Option<&TransformDef> {
+        self.definitions.get(name)
+    }
+
     /// Call a transform function with keyword arguments
     ///
     /// # Arguments
@@ -239,10 +330,7 @@ impl PyTransformRegistry {
         match registry.call_method1("get", (name,)) {
             Ok(func) => {
                 if func.is_none() {
-                    return Err(pyo3::exceptions::PyKeyError::new_err(format!(
-                        "Transform '{}' not found in registry",
-                        name
-                    )));
+                    return Err(TransformError::NotFound(name.to_string()).into());
                 }
 
                 // Convert kwargs to PyDict
@@ -284,10 +372,7 @@ impl PyTransformRegistry {
 
         let func = registry.call_method1("get", (name,))?;
         if func.is_none() {
-            return Err(pyo3::exceptions::PyKeyError::new_err(format!(
-                "Transform '{}' not found in registry",
-                name
-            )));
+            return Err(TransformError::NotFound(name.to_string()).into());
         }
 
         // Convert kwargs to PyDict
@@ -312,7 +397,7 @@ impl PyTransformRegistry {
                 .collect();
             strings
         } else {
-            Err(pyo3::exceptions::PyTypeError::new_err(format!(
+            Err(exceptions::TransformError::new_err(format!(
                 "Transform '{}' did not return a list",
                 name
             )))
@@ -320,9 +405,94 @@ impl PyTransformRegistry {
     }
 }
 
+#[pymethods]
+impl PyTransformRegistry {
+    #[new]
+    #[pyo3(signature = (transforms_module_name, transforms_dir = None))]
+    fn py_new(transforms_module_name: String, transforms_dir: Option<String>) -> PyResult<Self> {
+        let registry = Self::new(transforms_module_name);
+        match transforms_dir {
+            Some(dir) => registry
+                .with_definitions_from_dir(dir)
+                .map_err(pyo3::exceptions::PyValueError::new_err),
+            None => Ok(registry),
+        }
+    }
+
+    /// List all known transform names (sorted)
+    ///
+    /// Includes transforms with YAML definitions and any registered in the
+    /// Python module's TRANSFORM_REGISTRY.
+    fn list_transforms(&self, py: Python) -> PyResult<Vec<String>> {
+        let mut names: BTreeSet<String> = self.definitions.keys().cloned().collect();
+
+        if let Ok(module) = PyModule::import(py, self.transforms_module_name.as_str()) {
+            if let Ok(registry) = module.getattr("TRANSFORM_REGISTRY") {
+                if let Ok(dict) = registry.downcast::<PyDict>() {
+                    for key in dict.keys() {
+                        names.insert(key.extract::<String>()?);
+                    }
+                }
+            }
+        }
+
+        Ok(names.into_iter().collect())
+    }
+
+    /// Describe a transform's parameters and return type
+    ///
+    /// Returns a dict with `name`, `language`, `doc`, `parameters` and `returns`.
+    fn describe(&self, py: Python, name: &str) -> PyResult<PyObject> {
+        let def = self.definition(name).ok_or_else(|| {
+            pyo3::exceptions::PyKeyError::new_err(format!(
+                "No definition found for transform '{}'",
+                name
+            ))
+        })?;
+
+        Ok(json_value_to_py(py, &describe_transform(def))?)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "TransformRegistry(module='{}', definitions={})",
+            self.transforms_module_name,
+            self.definitions.len()
+        )
+    }
+}
+
+/// Build the introspection payload for a transform definition
+fn describe_transform(def: &TransformDef) -> Value {
+    let parameters: Vec<Value> = def
+        .parameters
+        .iter()
+        .map(|param| {
+            serde_json::json!({
+                "name": param.name,
+                "type": param.param_type,
+                "default": param.default,
+                "doc": param.doc,
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "name": def.name,
+        "language": def.language,
+        "doc": def.doc,
+        "parameters": parameters,
+        "returns": {
+            "type": def.returns.return_type,
+            "doc": def.returns.doc,
+        },
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::runtime::transforms::{Implementation, Parameter, ReturnType, TransformLanguage};
 
     #[test]
     fn test_python_transform_creation() {
@@ -337,6 +507,52 @@ mod tests {
         assert_eq!(registry.transforms_module_name, "myapp.transforms");
     }
 
+    #[test]
+    fn test_describe_transform() {
+        let def = TransformDef {
+            name: "normalize".to_string(),
+            language: TransformLanguage::Python,
+            doc: Some("Normalize a value".to_string()),
+            parameters: vec![Parameter {
+                name: "value".to_string(),
+                param_type: "Option<String>".to_string(),
+                default: None,
+                doc: None,
+            }],
+            returns: ReturnType {
+                return_type: "Option<String>".to_string(),
+                doc: None,
+            },
+            implementation: Implementation::Builtin,
+            tests: vec![],
+        };
+
+        let registry = PyTransformRegistry::new("myapp.transforms").with_definitions(vec![def]);
+        let description = describe_transform(registry.definition("normalize").unwrap());
+
+        assert_eq!(description["language"], "python");
+        assert_eq!(description["parameters"][0]["name"], "value");
+        assert_eq!(description["parameters"][0]["type"], "Option<String>");
+        assert_eq!(description["returns"]["type"], "Option<String>");
+        assert!(registry.definition("missing").is_none());
+    }
+
+    #[test]
+    fn test_entity_error_maps_to_entity_exception() {
+        pyo3::prepare_freethreaded_python();
+        let err: PyErr = EntityError::RequiredFieldMissing {
+            field: "patient_id".to_string(),
+        }
+        .into();
+
+        Python::with_gil(|py| {
+            assert!(err.is_instance_of::<exceptions::EntityError>(py));
+            assert!(err.is_instance_of::<exceptions::NomnomError>(py));
+            let field: String = err.value(py).getattr("field").unwrap().extract().unwrap();
+            assert_eq!(field, "patient_id");
+        });
+    }
+
     // Note: Full integration tests with actual Python functions would require
     // a Python environment with the test modules available
 }
@@ -0,0 +1,362 @@
+//! Built-in transforms that run without generated code.
+//!
+//! These cover the format-agnostic transforms referenced throughout the docs
+//! (`copy_field`, `extract_csv_field`, `extract_json_field`,
+//! `extract_json_subtree`) and the numeric parsers (`parse_int`,
+//! `parse_float`, `parse_decimal`) so they can be exercised standalone, e.g.
+//! by `nomnom test-transform`. Every builtin reads its source value from the
+//! `input` argument.
+
+use std::collections::HashMap;
+use serde_json::Value;
+use crate::transform_registry::{TransformError, TransformFn, TransformRegistry};
+
+/// Names of all built-in transforms
+pub const BUILTIN_TRANSFORMS: &[&str] = &[
+    "copy_field",
+    "extract_csv_field",
+    "extract_json_field",
+    "extract_json_subtree",
+    "parse_int",
+    "parse_float",
+    "parse_decimal",
+];
+
+/// Create a registry with every built-in transform registered
+pub fn builtin_registry() -> TransformRegistry {
+    let mut registry = TransformRegistry::new();
+    registry.register("copy_field", Box::new(copy_field) as Box<dyn TransformFn>);
+    registry.register("extract_csv_field", Box::new(extract_csv_field) as Box<dyn TransformFn>);
+    registry.register("extract_json_field", Box::new(extract_json_field) as Box<dyn TransformFn>);
+    registry.register("extract_json_subtree", Box::new(extract_json_subtree) as Box<dyn TransformFn>);
+    registry.register("parse_int", Box::new(parse_int) as Box<dyn TransformFn>);
+    registry.register("parse_float", Box::new(parse_float) as Box<dyn TransformFn>);
+    registry.register("parse_decimal", Box::new(parse_decimal) as Box<dyn TransformFn>);
+    registry
+}
+
+/// Return the input unchanged
+fn copy_field(args: &HashMap<String, Value>) -> Result<Value, TransformError> {
+    Ok(args.get("input").cloned().unwrap_or(Value::Null))
+}
+
+/// Return one column of a delimited line
+///
+/// Args: `column_index` (0-based), or `column` with `header` (a list of
+/// column names or a header line in the same dialect); `delimiter` (default
+/// `,`), `quote` (default `"`) and `escape` (default none, quotes are escaped
+/// by doubling). Quoted columns may contain the delimiter. Missing or empty
+/// columns yield null.
+fn extract_csv_field(args: &HashMap<String, Value>) -> Result<Value, TransformError> {
+    let Some(line) = input_str(args)? else {
+        return Ok(Value::Null);
+    };
+
+    let mut builder = csv::ReaderBuilder::new();
+    builder.has_headers(false).flexible(true);
+    if let Some(delimiter) = ascii_char_arg(args, "delimiter")? {
+        builder.delimiter(delimiter);
+    }
+    if let Some(quote) = ascii_char_arg(args, "quote")? {
+        builder.quote(quote);
+    }
+    if let Some(escape) = ascii_char_arg(args, "escape")? {
+        builder.escape(Some(escape)).double_quote(false);
+    }
+
+    let index = match (args.get("column_index"), args.get("column").and_then(|v| v.as_str())) {
+        (Some(index), _) => {
+            let index = index.as_u64()
+                .or_else(|| index.as_str().and_then(|s| s.parse().ok()))
+                .ok_or_else(|| TransformError::InvalidArgs("'column_index' must be a non-negative integer".to_string()))?;
+            // An index past usize::MAX can't match a column either
+            let Ok(index) = usize::try_from(index) else {
+                return Ok(Value::Null);
+            };
+            index
+        }
+        (None, Some(column)) => {
+            let header = match args.get("header") {
+                Some(Value::Array(names)) => names.iter()
+                    .map(|n| n.as_str().unwrap_or_default().to_string())
+                    .collect(),
+                Some(Value::String(header_line)) => read_csv_record(&builder, header_line)?,
+                _ => return Err(TransformError::InvalidArgs(
+                    "'column' needs a 'header' (list of column names or header line)".to_string()
+                )),
+            };
+            header.iter().position(|name| name == column).ok_or_else(|| {
+                TransformError::InvalidArgs(format!("'{}' is not a column of the header", column))
+            })?
+        }
+        (None, None) => {
+            return Err(TransformError::InvalidArgs("'column_index' or 'column' is required".to_string()));
+        }
+    };
+
+    Ok(read_csv_record(&builder, line)?
+        .into_iter()
+        .nth(index)
+        .filter(|column| !column.is_empty())
+        .map(Value::String)
+        .unwrap_or(Value::Null))
+}
+
+/// Read a single-character ASCII arg (CSV delimiter, quote or escape)
+fn ascii_char_arg(args: &HashMap<String, Value>, name: &str) -> Result<Option<u8>, TransformError> {
+    let Some(value) = args.get(name).and_then(|v| v.as_str()) else {
+        return Ok(None);
+    };
+    match value.as_bytes() {
+        [c] if c.is_ascii() => Ok(Some(*c)),
+        _ => Err(TransformError::InvalidArgs(format!("'{}' must be a single ASCII character", name))),
+    }
+}
+
+/// Split one delimited line into its columns
+fn read_csv_record(builder: &csv::ReaderBuilder, line: &str) -> Result<Vec<String>, TransformError> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let mut record = csv::StringRecord::new();
+    builder.from_reader(line.as_bytes())
+        .read_record(&mut record)
+        .map_err(|e| TransformError::ExecutionError(format!("invalid CSV line: {}", e)))?;
+    Ok(record.iter().map(str::to_string).collect())
+}
+
+/// Return the scalar at `json_path` as a string
+///
+/// Strings are returned as-is, other scalars in their JSON form; objects and
+/// arrays are rejected (use `extract_json_subtree` for those).
+fn extract_json_field(args: &HashMap<String, Value>) -> Result<Value, TransformError> {
+    let path = required_str(args, "json_path")?;
+    match json_at(args, path)? {
+        None | Some(Value::Null) => Ok(Value::Null),
+        Some(Value::String(s)) => Ok(Value::String(s)),
+        Some(v @ (Value::Number(_) | Value::Bool(_))) => Ok(Value::String(v.to_string())),
+        Some(_) => Err(TransformError::ExecutionError(format!(
+            "value at '{}' is not a scalar (use extract_json_subtree)",
+            path
+        ))),
+    }
+}
+
+/// Return the JSON value (any shape) at `path`
+fn extract_json_subtree(args: &HashMap<String, Value>) -> Result<Value, TransformError> {
+    let path = required_str(args, "path")?;
+    Ok(json_at(args, path)?.unwrap_or(Value::Null))
+}
+
+/// Parse the input as a signed 64-bit integer
+///
+/// Args: `thousands_separator` (optional). Null or blank input yields null.
+fn parse_int(args: &HashMap<String, Value>) -> Result<Value, TransformError> {
+    let Some(text) = numeric_text(args)? else {
+        return Ok(Value::Null);
+    };
+    text.parse::<i64>()
+        .map(Value::from)
+        .map_err(|_| TransformError::ExecutionError(format!("'{}' is not an integer", text)))
+}
+
+/// Parse the input as a 64-bit float
+///
+/// Args: `thousands_separator`, `decimal_separator` (optional). Null or
+/// blank input yields null; NaN and infinities are rejected.
+fn parse_float(args: &HashMap<String, Value>) -> Result<Value, TransformError> {
+    let Some(text) = numeric_text(args)? else {
+        return Ok(Value::Null);
+    };
+    text.parse::<f64>()
+        .ok()
+        .and_then(serde_json::Number::from_f64)
+        .map(Value::Number)
+        .ok_or_else(|| TransformError::ExecutionError(format!("'{}' is not a finite number", text)))
+}
+
+/// Parse the input as an exact decimal, returned as normalized text
+///
+/// Decimals keep every digit (unlike `parse_float`), so the result is a
+/// string such as `-1234.50` ready for a NUMERIC column. Args as for
+/// `parse_float`.
+fn parse_decimal(args: &HashMap<String, Value>) -> Result<Value, TransformError> {
+    let Some(text) = numeric_text(args)? else {
+        return Ok(Value::Null);
+    };
+    let unsigned = text.strip_prefix('-').unwrap_or(&text);
+    let (whole, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if whole.len() + fraction.len() == 0 || !digits(whole) || !digits(fraction) {
+        return Err(TransformError::ExecutionError(format!("'{}' is not a decimal", text)));
+    }
+    Ok(Value::String(text))
+}
+
+/// Input as numeric text: trimmed, `+` and thousands separators dropped,
+/// decimal separator normalized to `.` (None for null/blank input)
+fn numeric_text(args: &HashMap<String, Value>) -> Result<Option<String>, TransformError> {
+    let raw = match args.get("input") {
+        None | Some(Value::Null) => return Ok(None),
+        Some(Value::Number(n)) => return Ok(Some(n.to_string())),
+        Some(Value::String(s)) => s.trim(),
+        Some(other) => {
+            return Err(TransformError::InvalidArgs(format!("'input' must be a string or number, got {}", other)));
+        }
+    };
+    if raw.is_empty() {
+        return Ok(None);
+    }
+
+    let mut text = raw.strip_prefix('+').unwrap_or(raw).to_string();
+    if let Some(thousands) = args.get("thousands_separator").and_then(|v| v.as_str()) {
+        if !thousands.is_empty() {
+            text = text.replace(thousands, "");
+        }
+    }
+    if let Some(decimal) = args.get("decimal_separator").and_then(|v| v.as_str()) {
+        if !decimal.is_empty() && decimal != "." {
+            text = text.replace(decimal, ".");
+        }
+    }
+    Ok(Some(text))
+}
+
+fn input_str(args: &HashMap<String, Value>) -> Result<Option<&str>, TransformError> {
+    match args.get("input") {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s)),
+        Some(other) => Err(TransformError::InvalidArgs(format!("'input' must be a string, got {}", other))),
+    }
+}
+
+fn required_str<'a>(args: &'a HashMap<String, Value>, key: &str) -> Result<&'a str, TransformError> {
+    args.get(key)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| TransformError::InvalidArgs(format!("missing string argument '{}'", key)))
+}
+
+/// Look up a JSONPath-style path in the input (raw JSON text or a JSON value)
+fn json_at(args: &HashMap<String, Value>, path: &str) -> Result<Option<Value>, TransformError> {
+    let doc = match args.get("input") {
+        None | Some(Value::Null) => return Ok(None),
+        Some(Value::String(raw)) => serde_json::from_str::<Value>(raw)
+            .map_err(|e| TransformError::ExecutionError(format!("input is not valid JSON: {}", e)))?,
+        Some(value) => value.clone(),
+    };
+    let pointer = crate::codegen::utils::json_pointer_from_path(path);
+    Ok(doc.pointer(&pointer).cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn args(pairs: &[(&str, Value)]) -> HashMap<String, Value> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.clone())).collect()
+    }
+
+    #[test]
+    fn test_extract_csv_field() {
+        let registry = builtin_registry();
+
+        let result = registry.call("extract_csv_field", &args(&[
+            ("input", json!("a,\"b,c\",d")),
+            ("column_index", json!(1)),
+        ])).unwrap();
+        assert_eq!(result, json!("b,c"));
+
+        let missing = registry.call("extract_csv_field", &args(&[
+            ("input", json!("a,b")),
+            ("column_index", json!(5)),
+        ])).unwrap();
+        assert_eq!(missing, Value::Null);
+
+        let huge = registry.call("extract_csv_field", &args(&[
+            ("input", json!("a,b")),
+            ("column_index", json!(u64::MAX)),
+        ])).unwrap();
+        assert_eq!(huge, Value::Null);
+
+        assert!(registry.call("extract_csv_field", &args(&[("input", json!("a,b"))])).is_err());
+
+        let escaped = registry.call("extract_csv_field", &args(&[
+            ("input", json!(r#"1;'it\'s; here';x"#)),
+            ("column_index", json!(1)),
+            ("delimiter", json!(";")),
+            ("quote", json!("'")),
+            ("escape", json!("\\")),
+        ])).unwrap();
+        assert_eq!(escaped, json!("it's; here"));
+
+        let by_name = registry.call("extract_csv_field", &args(&[
+            ("input", json!("7\tada\t36")),
+            ("column", json!("name")),
+            ("header", json!("id\tname\tage")),
+            ("delimiter", json!("\t")),
+        ])).unwrap();
+        assert_eq!(by_name, json!("ada"));
+
+        assert!(registry.call("extract_csv_field", &args(&[
+            ("input", json!("7,ada")),
+            ("column", json!("email")),
+            ("header", json!(["id", "name"])),
+        ])).is_err());
+        assert!(registry.call("extract_csv_field", &args(&[
+            ("input", json!("a,b")),
+            ("column_index", json!(0)),
+            ("delimiter", json!("||")),
+        ])).is_err());
+    }
+
+    #[test]
+    fn test_extract_json_field_and_subtree() {
+        let registry = builtin_registry();
+        let doc = json!(r#"{"user": {"name": "ada", "age": 36, "tags": ["x"]}}"#);
+
+        let name = registry.call("extract_json_field", &args(&[
+            ("input", doc.clone()),
+            ("json_path", json!("$.user.name")),
+        ])).unwrap();
+        assert_eq!(name, json!("ada"));
+
+        let age = registry.call("extract_json_field", &args(&[
+            ("input", doc.clone()),
+            ("json_path", json!("$.user.age")),
+        ])).unwrap();
+        assert_eq!(age, json!("36"));
+
+        let tags = registry.call("extract_json_subtree", &args(&[
+            ("input", doc),
+            ("path", json!("$.user.tags")),
+        ])).unwrap();
+        assert_eq!(tags, json!(["x"]));
+    }
+
+    #[test]
+    fn test_parse_numeric() {
+        let registry = builtin_registry();
+
+        let int = registry.call("parse_int", &args(&[
+            ("input", json!(" -9,007,199,254,740,993 ")),
+            ("thousands_separator", json!(",")),
+        ])).unwrap();
+        assert_eq!(int, json!(-9007199254740993i64));
+
+        let float = registry.call("parse_float", &args(&[
+            ("input", json!("1.234,5")),
+            ("thousands_separator", json!(".")),
+            ("decimal_separator", json!(",")),
+        ])).unwrap();
+        assert_eq!(float, json!(1234.5));
+
+        let decimal = registry.call("parse_decimal", &args(&[("input", json!("+90123.10"))])).unwrap();
+        assert_eq!(decimal, json!("90123.10"));
+
+        // Absent values stay absent; garbage is an error
+        assert_eq!(registry.call("parse_int", &args(&[("input", json!("  "))])).unwrap(), Value::Null);
+        assert_eq!(registry.call("parse_decimal", &args(&[("input", Value::Null)])).unwrap(), Value::Null);
+        assert!(registry.call("parse_int", &args(&[("input", json!("12.5"))])).is_err());
+        assert!(registry.call("parse_decimal", &args(&[("input", json!("1.2.3"))])).is_err());
+        assert!(registry.call("parse_float", &args(&[("input", json!("NaN"))])).is_err());
+    }
+}
@@ -0,0 +1,96 @@
+//! Transaction batching of `--execute-db` parser runs.
+//!
+//! Records are written inside a batch transaction, each in a savepoint of
+//! its own so a failed record only rolls back itself. [`CommitBatches`]
+//! says when batches begin and commit: by default a batch spans a whole
+//! input file and is committed when the file ends; with `--commit-every N`
+//! it is committed every N records, trading the file's atomicity for
+//! shorter transactions. A failed commit rolls back its whole batch only.
+
+/// When the batch transactions of an `--execute-db` run begin and commit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommitBatches {
+    every: Option<usize>,
+    pending: usize,
+}
+
+impl CommitBatches {
+    /// Commit every `every` records, or once per file with `None` (or 0)
+    pub fn new(every: Option<usize>) -> Self {
+        Self { every: every.filter(|&every| every > 0), pending: 0 }
+    }
+
+    /// Whether a batch transaction must begin before the next record
+    pub fn begins_batch(&self) -> bool {
+        self.pending == 0
+    }
+
+    /// Count a record of the open batch, whether it was written or rolled
+    /// back to its savepoint
+    ///
+    /// Returns the number of records to commit when the batch is full.
+    pub fn record_done(&mut self) -> Option<usize> {
+        self.pending += 1;
+        match self.every {
+            Some(every) if self.pending >= every => Some(std::mem::take(&mut self.pending)),
+            _ => None,
+        }
+    }
+
+    /// End of an input file: the number of records of the open batch to
+    /// commit, if any
+    pub fn file_done(&mut self) -> Option<usize> {
+        Some(std::mem::take(&mut self.pending)).filter(|&records| records > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Batch sizes committed over files of the given record counts
+    fn commits(every: Option<usize>, files: &[usize]) -> Vec<usize> {
+        let mut batches = CommitBatches::new(every);
+        let mut committed = Vec::new();
+        for &records in files {
+            for _ in 0..records {
+                committed.extend(batches.record_done());
+            }
+            committed.extend(batches.file_done());
+        }
+        committed
+    }
+
+    #[test]
+    fn test_single_transaction_per_file_by_default() {
+        assert_eq!(commits(None, &[5, 0, 2]), [5, 2]);
+        assert_eq!(commits(Some(0), &[5, 2]), [5, 2]);
+    }
+
+    #[test]
+    fn test_commit_every() {
+        assert_eq!(commits(Some(2), &[5, 3]), [2, 2, 1, 2, 1]);
+        assert_eq!(commits(Some(1), &[3]), [1, 1, 1]);
+        // A file ending on a batch boundary leaves nothing to commit
+        assert_eq!(commits(Some(2), &[4]), [2, 2]);
+    }
+
+    #[test]
+    fn test_batch_boundary_starts_a_new_transaction() {
+        let mut batches = CommitBatches::new(Some(2));
+        assert!(batches.begins_batch());
+        assert_eq!(batches.record_done(), None);
+        assert!(!batches.begins_batch());
+
+        // The record closing a batch counts even if it rolled back to its
+        // savepoint; if the commit then fails, only this batch is lost and
+        // the next record opens a fresh transaction
+        assert_eq!(batches.record_done(), Some(2));
+        assert!(batches.begins_batch());
+        assert_eq!(batches.file_done(), None);
+
+        assert_eq!(batches.record_done(), None);
+        assert_eq!(batches.file_done(), Some(1));
+        assert!(batches.begins_batch());
+    }
+}
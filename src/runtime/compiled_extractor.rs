@@ -0,0 +1,271 @@
+//! Pre-resolved extraction plans for high-volume parsing.
+//!
+//! [`DefinitionExtractor`](crate::runtime::DefinitionExtractor) resolves
+//! transforms and sources by name on every record. A [`CompiledExtractor`]
+//! does that resolution once: each entity becomes a flat `Vec` of field steps
+//! holding the transform function, its base arguments and the positions of
+//! sibling fields it reads, so per-record work is just evaluating the plan.
+//!
+//! Results match `DefinitionExtractor` field for field. Because transforms are
+//! bound at compile time, recompile after a `TransformRegistry::reload`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use serde_json::Value as JsonValue;
+use crate::codegen::types::FieldSource;
+use crate::codegen::EntityDef;
+use crate::runtime::config_loader::ParserConfig;
+use crate::runtime::context::ExtractionContext;
+use crate::runtime::message_parser::{transform_error, yaml_args, EntityExtractor};
+use crate::transform_registry::{TransformFn, TransformRegistry};
+
+/// Where a transform input comes from, resolved at compile time
+#[derive(Debug, Clone)]
+enum SourceRef {
+    /// Field already computed for this record (index into the record slots)
+    Sibling(usize),
+    /// Field of an extracted entity, or of the message when the entity is absent
+    Entity { entity: String, field: String },
+    /// A whole extracted entity (`repeated_for`-style direct source)
+    Whole(String),
+}
+
+/// One transform input and the argument name it is also bound under
+#[derive(Debug, Clone)]
+struct Input {
+    source: SourceRef,
+    arg: Option<String>,
+}
+
+enum FieldStep {
+    /// Plain field read from the message by name
+    Message(String),
+    /// Computed field
+    Transform {
+        transform: String,
+        func: Arc<dyn TransformFn>,
+        args: HashMap<String, JsonValue>,
+        inputs: Vec<Input>,
+    },
+}
+
+struct FieldPlan {
+    name: String,
+    step: FieldStep,
+}
+
+/// Entity extractor executing a pre-resolved field plan
+pub struct CompiledExtractor {
+    entity_name: String,
+    plan: Vec<FieldPlan>,
+}
+
+impl CompiledExtractor {
+    /// Compile an entity definition against a transform registry
+    ///
+    /// Fails if the entity's fields form a `source: self` cycle or use a
+    /// transform that is not registered.
+    pub fn compile(entity: &EntityDef, transforms: &TransformRegistry) -> Result<Self, String> {
+        let ordered = entity.fields_in_dependency_order()?;
+        let slots: HashMap<&str, usize> = ordered.iter()
+            .enumerate()
+            .map(|(i, f)| (f.name.as_str(), i))
+            .collect();
+
+        let plan = ordered.iter()
+            .map(|field| {
+                let step = match field.computed_from {
+                    None => FieldStep::Message(field.name.clone()),
+                    Some(ref computed) => {
+                        let func = transforms
+                            .resolve(entity.transform_namespace.as_deref(), &computed.transform)
+                            .ok_or_else(|| format!(
+                                "Field '{}' of '{}': transform '{}' is not registered",
+                                field.name, entity.name, computed.transform
+                            ))?;
+
+                        let inputs = computed.sources.iter()
+                            .map(|source| {
+                                let resolved = match source {
+                                    FieldSource::Direct(name) => SourceRef::Whole(name.clone()),
+                                    FieldSource::Parent { source, field: sibling, .. } if source == "self" => {
+                                        let slot = slots.get(sibling.as_str()).copied().ok_or_else(|| format!(
+                                            "Field '{}' of '{}' reads unknown field '{}'",
+                                            field.name, entity.name, sibling
+                                        ))?;
+                                        SourceRef::Sibling(slot)
+                                    }
+                                    FieldSource::Parent { source, field, .. } => SourceRef::Entity {
+                                        entity: source.clone(),
+                                        field: field.clone(),
+                                    },
+                                };
+                                Ok(Input { source: resolved, arg: source.field_name().map(str::to_string) })
+                            })
+                            .collect::<Result<Vec<_>, String>>()?;
+
+                        FieldStep::Transform {
+                            transform: computed.transform.clone(),
+                            func,
+                            args: yaml_args(&field.name, computed)?,
+                            inputs,
+                        }
+                    }
+                };
+                Ok(FieldPlan { name: field.name.clone(), step })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(Self { entity_name: entity.name.clone(), plan })
+    }
+
+    /// Compile every entity of a parser config, in extraction order
+    pub fn from_config(
+        config: &ParserConfig,
+        entities: &[EntityDef],
+        transforms: &TransformRegistry,
+    ) -> Result<Vec<Self>, String> {
+        config.extraction_order.iter()
+            .map(|name| {
+                let entity = entities.iter()
+                    .find(|e| &e.name == name)
+                    .ok_or_else(|| format!("No definition for entity '{}' in parser config", name))?;
+                Self::compile(entity, transforms)
+            })
+            .collect()
+    }
+
+    fn input_value(
+        source: &SourceRef,
+        slots: &[JsonValue],
+        context: &ExtractionContext,
+        message: &JsonValue,
+    ) -> JsonValue {
+        let value = match source {
+            SourceRef::Sibling(i) => Some(&slots[*i]),
+            SourceRef::Entity { entity, field } => match context.get_entity(entity) {
+                Some(extracted) => extracted.get(field),
+                None => message.get(field),
+            },
+            SourceRef::Whole(entity) => context.get_entity(entity),
+        };
+        value.cloned().unwrap_or(JsonValue::Null)
+    }
+}
+
+impl EntityExtractor for CompiledExtractor {
+    fn extract(
+        &self,
+        context: &ExtractionContext,
+        message: &JsonValue,
+    ) -> Result<Option<JsonValue>, String> {
+        let mut slots: Vec<JsonValue> = Vec::with_capacity(self.plan.len());
+
+        for field in &self.plan {
+            let value = match field.step {
+                FieldStep::Message(ref key) => message.get(key).cloned().unwrap_or(JsonValue::Null),
+                FieldStep::Transform { ref transform, ref func, ref args, ref inputs } => {
+                    let mut args = args.clone();
+                    for (i, input) in inputs.iter().enumerate() {
+                        let value = Self::input_value(&input.source, &slots, context, message);
+                        if i == 0 {
+                            args.insert("input".to_string(), value.clone());
+                        }
+                        if let Some(ref name) = input.arg {
+                            args.entry(name.clone()).or_insert(value);
+                        }
+                    }
+                    func.execute(&args)
+                        .map_err(|e| transform_error(&self.entity_name, &field.name, transform, &args, e))?
+                }
+            };
+            slots.push(value);
+        }
+
+        let record = self.plan.iter()
+            .map(|f| f.name.clone())
+            .zip(slots)
+            .collect();
+        Ok(Some(JsonValue::Object(record)))
+    }
+
+    fn name(&self) -> &str {
+        &self.entity_name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::parse_entity;
+    use crate::runtime::builtins::builtin_registry;
+    use crate::runtime::DefinitionExtractor;
+    use serde_json::json;
+
+    fn csv_entity() -> EntityDef {
+        parse_entity(r#"
+entity:
+  name: CsvRow
+  source_type: root
+  fields:
+    - name: raw_line
+      type: String
+    - name: amount
+      type: Float
+      computed_from:
+        transform: parse_float
+        sources:
+          - source: self
+            field: price
+    - name: price
+      type: String
+      computed_from:
+        transform: extract_csv_field
+        sources:
+          - source: self
+            field: raw_line
+        args:
+          column_index: 1
+"#).unwrap()
+    }
+
+    #[test]
+    fn test_compiled_matches_definition_extractor() {
+        let registry = builtin_registry();
+        let compiled = CompiledExtractor::compile(&csv_entity(), &registry).unwrap();
+        let interpreted = DefinitionExtractor::new(csv_entity(), Arc::new(builtin_registry()));
+
+        let context = ExtractionContext::empty();
+        for line in ["a,12.5,c", "b,,d", "only"] {
+            let message = json!({"raw_line": line});
+            assert_eq!(
+                compiled.extract(&context, &message).unwrap(),
+                interpreted.extract(&context, &message).unwrap(),
+            );
+        }
+
+        let row = compiled.extract(&context, &json!({"raw_line": "a,12.5,c"})).unwrap().unwrap();
+        assert_eq!(row["price"], json!("12.5"));
+        assert_eq!(row["amount"], json!(12.5));
+    }
+
+    #[test]
+    fn test_transform_errors_carry_context() {
+        let compiled = CompiledExtractor::compile(&csv_entity(), &builtin_registry()).unwrap();
+        let err = compiled
+            .extract(&ExtractionContext::empty(), &json!({"raw_line": "a,abc,c"}))
+            .err()
+            .unwrap();
+        assert!(err.contains("entity 'CsvRow', field 'amount', source \"abc\""), "{}", err);
+        assert!(err.contains("parse_float"), "{}", err);
+    }
+
+    #[test]
+    fn test_compile_rejects_unknown_transform() {
+        let mut entity = csv_entity();
+        entity.fields[1].computed_from.as_mut().unwrap().transform = "parse_money".to_string();
+
+        let err = CompiledExtractor::compile(&entity, &builtin_registry()).err().unwrap();
+        assert!(err.contains("parse_money"));
+    }
+}
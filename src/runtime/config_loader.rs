@@ -1,22 +1,71 @@
 //! Parser configuration loader and topological sort.
 //!
-//! Loads parser configuration from YAML and computes entity extraction order
-//! using topological sort (Kahn's algorithm).
+//! Loads parser configuration from YAML (or builds it in code with
+//! [`ParserConfigBuilder`]) and computes entity extraction order using
+//! topological sort (Kahn's algorithm).
 
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::fs;
 use std::path::Path;
 use serde::{Deserialize, Serialize};
-use serde_json::Value as JsonValue;
+use serde_json::{json, Value as JsonValue};
+use crate::codegen::EntityDef;
+use crate::runtime::builtins::BUILTIN_TRANSFORMS;
+use crate::transform_registry::TransformRegistry;
+
+/// What happens when an entity fails to extract or persist
+///
+/// Shared by the runtime [`MessageParser`](crate::runtime::MessageParser) and
+/// the parser binary and worker generators. The unit a failure can abort is a
+/// message (a file for the parser binary). The default, `abort`, fails the
+/// message on any failure; `required` entities fail it under every policy.
+/// The worker retries database and publish failures whatever the policy, so
+/// `skip` and `dlq` only ever drop entities that failed to extract or validate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnEntityError {
+    /// Fail the whole message
+    #[default]
+    Abort,
+    /// Drop the failed entity and its dependents, keep the rest
+    Skip,
+    /// Like `skip`, and dead-letter each failure (runtime callers do so from
+    /// [`ExtractionReport::failures`](crate::runtime::ExtractionReport::failures))
+    Dlq,
+}
+
+impl OnEntityError {
+    pub fn as_str(&self) -> &str {
+        match self {
+            OnEntityError::Abort => "abort",
+            OnEntityError::Skip => "skip",
+            OnEntityError::Dlq => "dlq",
+        }
+    }
+
+    /// Parse a policy name (case-insensitive)
+    pub fn from_str(s: &str) -> Result<OnEntityError, String> {
+        match s.to_lowercase().as_str() {
+            "abort" => Ok(OnEntityError::Abort),
+            "skip" => Ok(OnEntityError::Skip),
+            "dlq" => Ok(OnEntityError::Dlq),
+            _ => Err(format!("Unsupported on_entity_error policy: '{}' (expected abort, skip or dlq)", s)),
+        }
+    }
+}
 
 /// Parser configuration defining entities and extraction order.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ParserConfig {
     /// Entity configurations: entity_name -> config
     pub entities: HashMap<String, HashMap<String, JsonValue>>,
 
     /// Entity extraction order (topologically sorted by dependencies)
     pub extraction_order: Vec<String>,
+
+    /// Failure policy; `required` entities fail the message under any policy
+    #[serde(default)]
+    pub on_entity_error: OnEntityError,
 }
 
 impl ParserConfig {
@@ -60,12 +109,19 @@ impl ParserConfig {
         )
         .map_err(|e| format!("Failed to parse entities: {}", e))?;
 
+        let on_entity_error = match yaml.get("on_entity_error") {
+            Some(policy) => serde_yaml::from_value(policy.clone())
+                .map_err(|e| format!("Failed to parse on_entity_error: {}", e))?,
+            None => OnEntityError::default(),
+        };
+
         // Compute extraction order via topological sort
         let extraction_order = compute_extraction_order(&entities)?;
 
         Ok(Self {
             entities,
             extraction_order,
+            on_entity_error,
         })
     }
 
@@ -86,6 +142,7 @@ impl ParserConfig {
         Ok(Self {
             entities,
             extraction_order,
+            on_entity_error: OnEntityError::default(),
         })
     }
 
@@ -103,6 +160,175 @@ impl ParserConfig {
     pub fn entity_names(&self) -> Vec<&String> {
         self.entities.keys().collect()
     }
+
+    /// Start building a config in code.
+    pub fn builder() -> ParserConfigBuilder {
+        ParserConfigBuilder::new()
+    }
+}
+
+/// Fluent builder for [`ParserConfig`].
+///
+/// Assembles entity configurations in code (e.g. for tests) instead of
+/// loading YAML from disk. `build()` computes the extraction order and
+/// validates the result.
+///
+/// # Example
+/// ```ignore
+/// use nomnom::runtime::ParserConfigBuilder;
+///
+/// let config = ParserConfigBuilder::new()
+///     .entity("Order", &[])
+///     .entity("LineItem", &["Order"])
+///     .root("Order")
+///     .required("Order")
+///     .build()?;
+/// assert_eq!(config.extraction_order, vec!["Order", "LineItem"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ParserConfigBuilder {
+    entities: HashMap<String, HashMap<String, JsonValue>>,
+    root: Option<String>,
+    required: Vec<String>,
+    transforms: HashSet<String>,
+    on_entity_error: OnEntityError,
+}
+
+impl ParserConfigBuilder {
+    /// Create an empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a builder from entity definitions.
+    ///
+    /// Each non-abstract entity depends on its parents and source entities
+    /// and records the transforms its fields use. A single root entity
+    /// becomes the config root.
+    pub fn from_entities(entities: Vec<EntityDef>) -> Self {
+        let mut builder = Self::new();
+
+        for entity in entities.iter().filter(|e| !e.is_abstract) {
+            let mut dependencies: BTreeSet<String> = entity.get_parents().into_iter().collect();
+            dependencies.extend(entity.get_source_entity_specs().into_values().map(|(name, _)| name));
+
+            let transforms: BTreeSet<&str> = entity.fields.iter()
+                .filter_map(|f| f.computed_from.as_ref())
+                .map(|c| c.transform.as_str())
+                .collect();
+
+            let mut config = HashMap::new();
+            config.insert("dependencies".to_string(), json!(dependencies));
+            config.insert("transforms".to_string(), json!(transforms));
+            if entity.is_root() {
+                config.insert("root".to_string(), json!(true));
+            }
+            builder = builder.entity_config(&entity.name, config);
+        }
+
+        let mut roots = entities.iter().filter(|e| !e.is_abstract && e.is_root());
+        if let (Some(root), None) = (roots.next(), roots.next()) {
+            builder = builder.root(&root.name);
+        }
+        builder
+    }
+
+    /// Add an entity that depends on the given entities.
+    pub fn entity(self, name: &str, dependencies: &[&str]) -> Self {
+        let mut config = HashMap::new();
+        config.insert("dependencies".to_string(), json!(dependencies));
+        self.entity_config(name, config)
+    }
+
+    /// Add an entity with a raw configuration (as found under `entities:` in YAML).
+    pub fn entity_config(mut self, name: &str, config: HashMap<String, JsonValue>) -> Self {
+        self.entities.insert(name.to_string(), config);
+        self
+    }
+
+    /// Set the root entity (extracted first; must have no dependencies).
+    pub fn root(mut self, name: &str) -> Self {
+        self.root = Some(name.to_string());
+        self
+    }
+
+    /// Mark an entity as required (its extraction failure fails the message).
+    pub fn required(mut self, name: &str) -> Self {
+        self.required.push(name.to_string());
+        self
+    }
+
+    /// Set what happens when an entity fails to extract.
+    pub fn on_entity_error(mut self, policy: OnEntityError) -> Self {
+        self.on_entity_error = policy;
+        self
+    }
+
+    /// Register a transform name entities may reference.
+    pub fn transform(mut self, name: &str) -> Self {
+        self.transforms.insert(name.to_string());
+        self
+    }
+
+    /// Register every transform in a registry.
+    pub fn transforms(mut self, registry: &TransformRegistry) -> Self {
+        self.transforms.extend(registry.list_transforms());
+        self
+    }
+
+    /// Compute the extraction order and return the validated config.
+    ///
+    /// Fails on unknown or cyclic dependencies, an unknown or dependent
+    /// root, unknown required entities and, once any transform has been
+    /// registered, on entity transforms that are neither registered nor
+    /// built in.
+    pub fn build(self) -> Result<ParserConfig, String> {
+        let Self { mut entities, root, required, transforms, on_entity_error } = self;
+
+        if entities.is_empty() {
+            return Err("Parser config has no entities".to_string());
+        }
+
+        for name in &required {
+            let config = entities.get_mut(name)
+                .ok_or_else(|| format!("Required entity '{}' is not defined", name))?;
+            config.insert("required".to_string(), json!(true));
+        }
+
+        if !transforms.is_empty() {
+            let mut names: Vec<_> = entities.keys().collect();
+            names.sort();
+            for name in names {
+                let used = entities[name].get("transforms").and_then(|v| v.as_array());
+                for transform in used.into_iter().flatten().filter_map(|t| t.as_str()) {
+                    if !transforms.contains(transform) && !BUILTIN_TRANSFORMS.contains(&transform) {
+                        return Err(format!(
+                            "Entity '{}' uses unregistered transform '{}'",
+                            name, transform
+                        ));
+                    }
+                }
+            }
+        }
+
+        let mut extraction_order = compute_extraction_order(&entities)?;
+
+        if let Some(root) = root {
+            let config = entities.get(&root)
+                .ok_or_else(|| format!("Root entity '{}' is not defined", root))?;
+            if !extract_dependencies(config).is_empty() {
+                return Err(format!("Root entity '{}' cannot have dependencies", root));
+            }
+            extraction_order.retain(|name| name != &root);
+            extraction_order.insert(0, root);
+        }
+
+        Ok(ParserConfig {
+            entities,
+            extraction_order,
+            on_entity_error,
+        })
+    }
 }
 
 /// Compute entity extraction order using topological sort (Kahn's algorithm).
@@ -215,7 +441,7 @@ pub fn compute_extraction_order(
 ///
 /// # Returns
 /// List of entity names this entity depends on
-fn extract_dependencies(config: &HashMap<String, JsonValue>) -> Vec<String> {
+pub(crate) fn extract_dependencies(config: &HashMap<String, JsonValue>) -> Vec<String> {
     config
         .get("dependencies")
         .and_then(|v| v.as_array())
@@ -364,6 +590,65 @@ mod tests {
         assert!(config.get_entity("MissingEntity").is_none());
     }
 
+    #[test]
+    fn test_parser_config_builder() {
+        let config = ParserConfigBuilder::new()
+            .entity("Item", &["Order"])
+            .entity("Order", &[])
+            .entity("Audit", &[])
+            .root("Order")
+            .required("Item")
+            .build()
+            .unwrap();
+
+        assert_eq!(config.extraction_order[0], "Order");
+        assert_eq!(config.extraction_order.len(), 3);
+        assert_eq!(config.get_entity("Item").unwrap().get("required"), Some(&json!(true)));
+
+        assert!(ParserConfigBuilder::new().entity("A", &["B"]).build().is_err());
+        assert!(ParserConfigBuilder::new().entity("A", &[]).entity("B", &["A"]).root("B").build().is_err());
+        assert!(ParserConfigBuilder::new().entity("A", &[]).required("C").build().is_err());
+    }
+
+    #[test]
+    fn test_parser_config_builder_from_entities() {
+        use crate::codegen::types::{ComputedFrom, FieldDef, FieldSource};
+
+        let entities = vec![
+            EntityDef {
+                name: "Order".to_string(),
+                source_type: "root".to_string(),
+                ..Default::default()
+            },
+            EntityDef {
+                name: "LineItem".to_string(),
+                source_type: "derived".to_string(),
+                parent: Some("Order".to_string()),
+                fields: vec![FieldDef {
+                    name: "price".to_string(),
+                    field_type: "Float".to_string(),
+                    computed_from: Some(ComputedFrom {
+                        transform: "price_of".to_string(),
+                        sources: vec![FieldSource::Direct("raw".to_string())],
+                        args: None,
+                        condition: None,
+                    }),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        ];
+
+        let config = ParserConfigBuilder::from_entities(entities.clone()).build().unwrap();
+        assert_eq!(config.extraction_order, vec!["Order", "LineItem"]);
+        assert_eq!(config.get_entity("Order").unwrap().get("root"), Some(&json!(true)));
+
+        // Registering transforms turns on the transform check
+        let unregistered = ParserConfigBuilder::from_entities(entities.clone()).transform("other").build();
+        assert!(unregistered.unwrap_err().contains("price_of"));
+        assert!(ParserConfigBuilder::from_entities(entities).transform("price_of").build().is_ok());
+    }
+
     #[test]
     fn test_parser_config_entity_names() {
         let mut entities = HashMap::new();
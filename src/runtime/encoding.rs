@@ -0,0 +1,224 @@
+//! Transcoding of non-UTF-8 input to UTF-8.
+//!
+//! Legacy feeds are often Latin-1 / Windows-1252 (or UTF-16 with a BOM).
+//! An [`InputEncoding`] names the encoding of a source, either by WHATWG
+//! label (`utf-8`, `latin1`, `windows-1252`, `utf-16le`, ...) or as `auto`:
+//!
+//! - a byte order mark selects UTF-8, UTF-16LE or UTF-16BE (and is removed)
+//! - without one, text that is valid UTF-8 is read as UTF-8, anything else
+//!   as Windows-1252
+//!
+//! Following WHATWG, `latin1` and `iso-8859-1` decode as Windows-1252.
+
+use std::borrow::Cow;
+use std::io::BufRead;
+use encoding_rs::{CoderResult, Decoder, Encoding, UTF_8, WINDOWS_1252};
+
+/// Encoding of an input source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEncoding {
+    /// Detect from a BOM, falling back to UTF-8 or Windows-1252
+    Auto,
+    /// A fixed encoding
+    Fixed(&'static Encoding),
+}
+
+impl Default for InputEncoding {
+    fn default() -> Self {
+        InputEncoding::Fixed(UTF_8)
+    }
+}
+
+impl InputEncoding {
+    /// Name of the encoding (`auto` or its canonical WHATWG name)
+    pub fn name(&self) -> &'static str {
+        match self {
+            InputEncoding::Auto => "auto",
+            InputEncoding::Fixed(encoding) => encoding.name(),
+        }
+    }
+
+    /// Decode a complete input to UTF-8
+    ///
+    /// Fails if the input isn't valid in a fixed encoding rather than
+    /// producing replacement characters.
+    pub fn decode<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, str>, String> {
+        let encoding = match self {
+            InputEncoding::Fixed(encoding) => *encoding,
+            InputEncoding::Auto => match Encoding::for_bom(bytes) {
+                Some((encoding, _)) => encoding,
+                None if std::str::from_utf8(bytes).is_ok() => UTF_8,
+                None => WINDOWS_1252,
+            },
+        };
+        let (text, had_errors) = encoding.decode_with_bom_removal(bytes);
+        if had_errors {
+            return Err(format!("Input is not valid {}", encoding.name()));
+        }
+        Ok(text)
+    }
+}
+
+impl std::str::FromStr for InputEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().eq_ignore_ascii_case("auto") {
+            return Ok(InputEncoding::Auto);
+        }
+        Encoding::for_label(s.trim().as_bytes())
+            .map(InputEncoding::Fixed)
+            .ok_or_else(|| format!(
+                "Unknown encoding '{}' (expected auto or a label such as utf-8, latin1, windows-1252, utf-16le)",
+                s
+            ))
+    }
+}
+
+/// How [`DecodedLines`] turns bytes into text, settled on the first read
+enum LineMode {
+    Undetected,
+    /// Streaming decoder of a known encoding
+    Decoder(Decoder),
+    /// `auto` without a BOM: each line is UTF-8 if valid, else Windows-1252
+    PerLine,
+}
+
+/// Iterator over the lines of a byte source, transcoded to UTF-8
+///
+/// Lines are split on `\n` after decoding (so UTF-16 sources split
+/// correctly) and a trailing `\r` is removed, like [`BufRead::lines`].
+pub struct DecodedLines<R> {
+    reader: R,
+    encoding: InputEncoding,
+    mode: LineMode,
+    pending: String,
+    line_bytes: Vec<u8>,
+    eof: bool,
+}
+
+impl<R: BufRead> DecodedLines<R> {
+    /// Read the lines of `reader`, decoding it as `encoding`
+    pub fn new(reader: R, encoding: InputEncoding) -> Self {
+        Self {
+            reader,
+            encoding,
+            mode: LineMode::Undetected,
+            pending: String::new(),
+            line_bytes: Vec::new(),
+            eof: false,
+        }
+    }
+
+    /// Decode more input into `pending`
+    fn fill(&mut self) -> Result<(), String> {
+        if let LineMode::Undetected = self.mode {
+            self.mode = match self.encoding {
+                InputEncoding::Fixed(encoding) => LineMode::Decoder(encoding.new_decoder_with_bom_removal()),
+                InputEncoding::Auto => {
+                    let head = self.reader.fill_buf().map_err(|e| format!("Failed to read input: {}", e))?;
+                    match Encoding::for_bom(head) {
+                        Some((encoding, _)) => LineMode::Decoder(encoding.new_decoder_with_bom_removal()),
+                        None => LineMode::PerLine,
+                    }
+                }
+            };
+        }
+
+        match self.mode {
+            LineMode::Undetected => unreachable!("line mode is detected above"),
+            LineMode::PerLine => {
+                self.line_bytes.clear();
+                let read = self.reader.read_until(b'\n', &mut self.line_bytes)
+                    .map_err(|e| format!("Failed to read input: {}", e))?;
+                if read == 0 {
+                    self.eof = true;
+                }
+                match std::str::from_utf8(&self.line_bytes) {
+                    Ok(text) => self.pending.push_str(text),
+                    Err(_) => self.pending.push_str(&WINDOWS_1252.decode_without_bom_handling(&self.line_bytes).0),
+                }
+            }
+            LineMode::Decoder(ref mut decoder) => {
+                let chunk = self.reader.fill_buf().map_err(|e| format!("Failed to read input: {}", e))?;
+                let last = chunk.is_empty();
+                if let Some(needed) = decoder.max_utf8_buffer_length(chunk.len()) {
+                    self.pending.reserve(needed);
+                }
+                let (result, read, had_errors) = decoder.decode_to_string(chunk, &mut self.pending, last);
+                debug_assert!(matches!(result, CoderResult::InputEmpty));
+                if had_errors {
+                    return Err(format!("Input is not valid {}", decoder.encoding().name()));
+                }
+                self.reader.consume(read);
+                self.eof = last;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Iterator for DecodedLines<R> {
+    type Item = Result<String, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(end) = self.pending.find('\n') {
+                let mut line: String = self.pending.drain(..=end).collect();
+                line.pop();
+                if line.ends_with('\r') {
+                    line.pop();
+                }
+                return Some(Ok(line));
+            }
+            if self.eof {
+                return (!self.pending.is_empty()).then(|| Ok(std::mem::take(&mut self.pending)));
+            }
+            if let Err(e) = self.fill() {
+                self.eof = true;
+                self.pending.clear();
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(bytes: &[u8], encoding: &str) -> Vec<String> {
+        DecodedLines::new(bytes, encoding.parse().unwrap())
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_decode_latin1_and_auto() {
+        let latin1 = b"Caf\xe9 cr\xe8me";
+        let latin1_encoding: InputEncoding = "latin1".parse().unwrap();
+        assert_eq!(latin1_encoding.decode(latin1).unwrap(), "Café crème");
+        assert_eq!(InputEncoding::Auto.decode(latin1).unwrap(), "Café crème");
+        assert_eq!(InputEncoding::Auto.decode("Café".as_bytes()).unwrap(), "Café");
+        assert!(InputEncoding::default().decode(latin1).is_err());
+        assert!("ebcdic-ish".parse::<InputEncoding>().is_err());
+    }
+
+    #[test]
+    fn test_decoded_lines() {
+        assert_eq!(lines(b"na\xefve\r\nplain\nlast", "windows-1252"), ["naïve", "plain", "last"]);
+
+        // auto: per-line UTF-8 detection without a BOM
+        let mixed = [&b"\xc3\xbc\n"[..], b"d\xe9j\xe0\n"].concat();
+        assert_eq!(lines(&mixed, "auto"), ["ü", "déjà"]);
+
+        // auto: a UTF-16LE BOM switches to a streaming decoder
+        let utf16: Vec<u8> = [0xFF, 0xFE].into_iter()
+            .chain("a\nbé\n".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        assert_eq!(lines(&utf16, "auto"), ["a", "bé"]);
+
+        let mut invalid = DecodedLines::new(&b"ok\n\xff\n"[..], InputEncoding::default());
+        assert!(invalid.any(|line| line.is_err()));
+    }
+}
@@ -0,0 +1,205 @@
+//! Opening of (possibly compressed) input files.
+//!
+//! Source files often arrive gzip- or zstd-compressed. [`open_input`]
+//! decompresses them while reading, so a file is never fully decompressed
+//! in memory. With [`InputCodec::Auto`] the codec comes from the `.gz` /
+//! `.zst` extension, or else from the file's magic bytes. [`expand_inputs`]
+//! lists the files matched by glob patterns and directories.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression of an input file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputCodec {
+    /// Detect from the extension or magic bytes
+    #[default]
+    Auto,
+    /// Read as-is
+    None,
+    /// gzip (including concatenated members)
+    Gzip,
+    /// Zstandard
+    Zstd,
+}
+
+impl InputCodec {
+    /// Codec named by a file extension, if it is a compressed one
+    pub fn from_extension(path: &Path) -> Option<InputCodec> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "gz" | "gzip" => Some(InputCodec::Gzip),
+            "zst" | "zstd" => Some(InputCodec::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Codec announced by the first bytes of a file
+    pub fn sniff(head: &[u8]) -> InputCodec {
+        if head.starts_with(GZIP_MAGIC) {
+            InputCodec::Gzip
+        } else if head.starts_with(ZSTD_MAGIC) {
+            InputCodec::Zstd
+        } else {
+            InputCodec::None
+        }
+    }
+}
+
+impl std::str::FromStr for InputCodec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(InputCodec::Auto),
+            "none" => Ok(InputCodec::None),
+            "gzip" | "gz" => Ok(InputCodec::Gzip),
+            "zstd" | "zst" => Ok(InputCodec::Zstd),
+            other => Err(format!(
+                "Unknown input codec '{}' (expected auto, none, gzip or zstd)",
+                other
+            )),
+        }
+    }
+}
+
+/// Open a file for reading, decompressing it according to `codec`
+pub fn open_input(path: &Path, codec: InputCodec) -> io::Result<Box<dyn BufRead>> {
+    let codec = match codec {
+        InputCodec::Auto => InputCodec::from_extension(path).unwrap_or(InputCodec::Auto),
+        codec => codec,
+    };
+    decompress_reader(BufReader::new(File::open(path)?), codec)
+}
+
+/// Wrap a reader in the decoder for `codec` (sniffing magic bytes for `Auto`)
+pub fn decompress_reader<R: BufRead + 'static>(mut reader: R, codec: InputCodec) -> io::Result<Box<dyn BufRead>> {
+    let codec = match codec {
+        InputCodec::Auto => InputCodec::sniff(reader.fill_buf()?),
+        codec => codec,
+    };
+    Ok(match codec {
+        InputCodec::Auto | InputCodec::None => Box::new(reader),
+        InputCodec::Gzip => Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(reader))),
+        InputCodec::Zstd => Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?)),
+    })
+}
+
+/// List the files matching `globs` or found in `dirs` (descending into
+/// subdirectories when `recursive`), sorted and without duplicates
+///
+/// A non-empty `extensions` keeps only files with one of those extensions
+/// (compared case-insensitively, with or without the leading dot; a
+/// compression suffix such as `.gz` is looked through).
+pub fn expand_inputs(
+    globs: &[String],
+    dirs: &[PathBuf],
+    recursive: bool,
+    extensions: &[String],
+) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    for pattern in globs {
+        let paths = glob::glob(pattern).map_err(|e| format!("Invalid glob '{}': {}", pattern, e))?;
+        for path in paths {
+            let path = path.map_err(|e| format!("Failed to read {}: {}", e.path().display(), e.error()))?;
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    for dir in dirs {
+        let walker = walkdir::WalkDir::new(dir).max_depth(if recursive { usize::MAX } else { 1 });
+        for entry in walker {
+            let entry = entry.map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+            if entry.file_type().is_file() {
+                files.push(entry.into_path());
+            }
+        }
+    }
+
+    let extensions: Vec<String> = extensions.iter()
+        .map(|e| e.trim_start_matches('.').to_lowercase())
+        .collect();
+    files.retain(|path| extensions.is_empty() || has_extension(path, &extensions));
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Whether a file has one of `extensions`, ignoring a compression suffix
+fn has_extension(path: &Path, extensions: &[String]) -> bool {
+    let path = match InputCodec::from_extension(path) {
+        Some(_) => path.with_extension(""),
+        None => path.to_path_buf(),
+    };
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| extensions.contains(&e.to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Read, Write};
+
+    fn read_all(reader: io::Result<Box<dyn BufRead>>) -> String {
+        let mut text = String::new();
+        reader.unwrap().read_to_string(&mut text).unwrap();
+        text
+    }
+
+    #[test]
+    fn test_decompress_sniffs_magic_bytes() {
+        let text = "MSH|1\nMSH|2\n";
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(text.as_bytes()).unwrap();
+        let gzip = gzip.finish().unwrap();
+        let zstd = zstd::encode_all(text.as_bytes(), 0).unwrap();
+
+        assert_eq!(read_all(decompress_reader(Cursor::new(gzip), InputCodec::Auto)), text);
+        assert_eq!(read_all(decompress_reader(Cursor::new(zstd), InputCodec::Auto)), text);
+        assert_eq!(read_all(decompress_reader(Cursor::new(text.as_bytes().to_vec()), InputCodec::Auto)), text);
+        assert!(decompress_reader(Cursor::new(text.as_bytes().to_vec()), InputCodec::Zstd)
+            .and_then(|mut r| r.read_to_end(&mut Vec::new()))
+            .is_err());
+    }
+
+    #[test]
+    fn test_open_input_uses_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("claims.csv.zst");
+        std::fs::write(&path, zstd::encode_all(&b"a,b\n"[..], 0).unwrap()).unwrap();
+        assert_eq!(read_all(open_input(&path, InputCodec::Auto)), "a,b\n");
+
+        assert_eq!(InputCodec::from_extension(Path::new("claims.csv")), None);
+        assert_eq!("gz".parse::<InputCodec>().unwrap(), InputCodec::Gzip);
+        assert!("lz4".parse::<InputCodec>().is_err());
+    }
+
+    #[test]
+    fn test_expand_inputs() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("2024")).unwrap();
+        for name in ["b.csv", "a.CSV", "notes.txt", "2024/c.csv.gz"] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+        let names = |files: Vec<PathBuf>| -> Vec<String> {
+            files.iter()
+                .map(|f| f.strip_prefix(dir.path()).unwrap().to_string_lossy().replace('\\', "/"))
+                .collect()
+        };
+        let dirs = [dir.path().to_path_buf()];
+        let csv = ["csv".to_string()];
+
+        assert_eq!(names(expand_inputs(&[], &dirs, false, &csv).unwrap()), ["a.CSV", "b.csv"]);
+        assert_eq!(names(expand_inputs(&[], &dirs, true, &[".csv".to_string()]).unwrap()), ["2024/c.csv.gz", "a.CSV", "b.csv"]);
+
+        // Overlapping globs and directories list each file once
+        let glob = format!("{}/*.csv", dir.path().display());
+        assert_eq!(names(expand_inputs(&[glob], &dirs, false, &csv).unwrap()), ["a.CSV", "b.csv"]);
+        assert!(expand_inputs(&["[".to_string()], &[], false, &[]).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+//! Streaming reader for JSON record sources.
+//!
+//! Large JSON sources are read one record at a time instead of being
+//! deserialized as a whole, so memory stays bounded by the largest record.
+//! Two layouts are accepted, detected from the first non-whitespace byte:
+//!
+//! - a top-level array: `[{...}, {...}]`
+//! - newline-delimited JSON (JSON Lines): one value per line (any whitespace
+//!   between values is accepted)
+
+use std::fmt;
+use std::io::{BufRead, BufReader, Read};
+use serde::de::{self, Deserializer as _, SeqAccess, Visitor};
+use serde_json::Value as JsonValue;
+
+/// Call `on_record` for each record of a JSON array or JSON Lines source
+///
+/// Records are passed in source order with their 0-based index. An error
+/// returned by `on_record` stops the stream and is returned as is; malformed
+/// JSON is reported with the index of the offending record. Returns the
+/// number of records read.
+pub fn for_each_json_record<R, F>(reader: R, mut on_record: F) -> Result<usize, String>
+where
+    R: Read,
+    F: FnMut(usize, JsonValue) -> Result<(), String>,
+{
+    let mut reader = BufReader::new(reader);
+
+    let is_array = loop {
+        let buf = reader.fill_buf().map_err(|e| format!("Failed to read JSON source: {}", e))?;
+        match buf.iter().position(|b| !b.is_ascii_whitespace()) {
+            Some(i) => {
+                let first = buf[i];
+                reader.consume(i);
+                break first == b'[';
+            }
+            None if buf.is_empty() => return Ok(0),
+            None => {
+                let len = buf.len();
+                reader.consume(len);
+            }
+        }
+    };
+
+    let mut count = 0;
+    if is_array {
+        let mut failed = None;
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        let result = deserializer.deserialize_seq(RecordVisitor {
+            on_record: &mut on_record,
+            count: &mut count,
+            failed: &mut failed,
+        });
+        if let Some(err) = failed {
+            return Err(err);
+        }
+        result.map_err(|e| format!("Malformed JSON in record {}: {}", count, e))?;
+        deserializer.end().map_err(|e| format!("Trailing data after JSON array: {}", e))?;
+    } else {
+        for value in serde_json::Deserializer::from_reader(reader).into_iter::<JsonValue>() {
+            let value = value.map_err(|e| format!("Malformed JSON in record {}: {}", count, e))?;
+            on_record(count, value)?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Visits the top-level array, handing each element over as soon as it is parsed
+struct RecordVisitor<'a, F> {
+    on_record: &'a mut F,
+    count: &'a mut usize,
+    /// Error from `on_record`, kept apart from JSON syntax errors
+    failed: &'a mut Option<String>,
+}
+
+impl<'de, F> Visitor<'de> for RecordVisitor<'_, F>
+where
+    F: FnMut(usize, JsonValue) -> Result<(), String>,
+{
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an array of JSON records")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(value) = seq.next_element::<JsonValue>()? {
+            if let Err(err) = (self.on_record)(*self.count, value) {
+                *self.failed = Some(err);
+                return Err(de::Error::custom("record handler failed"));
+            }
+            *self.count += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn collect(input: &str) -> Result<Vec<JsonValue>, String> {
+        let mut records = Vec::new();
+        for_each_json_record(input.as_bytes(), |i, value| {
+            assert_eq!(i, records.len());
+            records.push(value);
+            Ok(())
+        })?;
+        Ok(records)
+    }
+
+    #[test]
+    fn test_array_and_json_lines_yield_same_records() {
+        let expected = vec![json!({"id": 1}), json!({"id": 2, "tags": [1, 2]}), json!(3)];
+
+        assert_eq!(collect("  [{\"id\": 1}, {\"id\": 2, \"tags\": [1, 2]}, 3]\n").unwrap(), expected);
+        assert_eq!(collect("{\"id\": 1}\n{\"id\": 2, \"tags\": [1, 2]}\n\n3\n").unwrap(), expected);
+        assert!(collect("").unwrap().is_empty());
+        assert!(collect("[]").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_errors_report_record_and_stop_stream() {
+        let err = collect("[{\"id\": 1}, {\"id\": }]").err().unwrap();
+        assert!(err.contains("record 1"), "{}", err);
+
+        let err = collect("{\"id\": 1}\n{oops}\n").err().unwrap();
+        assert!(err.contains("record 1"), "{}", err);
+
+        // Bad UTF-8 and truncated records are errors, not panics
+        let err = for_each_json_record(&b"{\"id\": \"\xff\xfe\"}"[..], |_, _| Ok(())).err().unwrap();
+        assert!(err.contains("record 0"), "{}", err);
+        assert!(collect("[{\"id\": 1}, {\"id\"").is_err());
+        assert!(collect("{\"id\": 1").is_err());
+
+        let mut seen = 0;
+        let err = for_each_json_record("[1, 2, 3]".as_bytes(), |i, _| {
+            seen += 1;
+            if i == 1 { Err("stop".to_string()) } else { Ok(()) }
+        });
+        assert_eq!(err, Err("stop".to_string()));
+        assert_eq!(seen, 2);
+    }
+}
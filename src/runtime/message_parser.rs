@@ -4,9 +4,14 @@
 //! structured messages in dependency order.
 
 use std::collections::HashMap;
+use std::sync::Arc;
 use serde_json::Value as JsonValue;
+use crate::codegen::types::FieldSource;
+use crate::codegen::EntityDef;
+use crate::entity::EntityError;
 use crate::runtime::context::ExtractionContext;
-use crate::runtime::config_loader::ParserConfig;
+use crate::runtime::config_loader::{extract_dependencies, OnEntityError, ParserConfig};
+use crate::transform_registry::TransformRegistry;
 
 /// Trait for entity extraction from structured data.
 ///
@@ -39,6 +44,180 @@ pub trait EntityExtractor: Send + Sync {
     }
 }
 
+/// Reflective extractor that evaluates an entity definition without codegen.
+///
+/// Plain fields are read from the message by name; computed fields call their
+/// transform through the shared registry, looked up by name on every
+/// extraction, so [`TransformRegistry::reload`] takes effect without
+/// restarting. The first source is passed as `input` and every source is also
+/// bound under its field name, alongside the field's YAML `args`.
+pub struct DefinitionExtractor {
+    entity: EntityDef,
+    transforms: Arc<TransformRegistry>,
+}
+
+impl DefinitionExtractor {
+    /// Create an extractor for `entity` calling transforms from `transforms`
+    pub fn new(entity: EntityDef, transforms: Arc<TransformRegistry>) -> Self {
+        Self { entity, transforms }
+    }
+
+    /// Value of a transform source: a sibling field, a field of an extracted
+    /// entity, or (for entities not in the context) a field of the message
+    fn source_value(
+        &self,
+        source: &FieldSource,
+        record: &serde_json::Map<String, JsonValue>,
+        context: &ExtractionContext,
+        message: &JsonValue,
+    ) -> JsonValue {
+        let Some(field) = source.field_name() else {
+            return context.get_entity(source.source_name()).cloned().unwrap_or(JsonValue::Null);
+        };
+
+        let value = match source.source_name() {
+            "self" => record.get(field),
+            name => match context.get_entity(name) {
+                Some(entity) => entity.get(field),
+                None => message.get(field),
+            },
+        };
+        value.cloned().unwrap_or(JsonValue::Null)
+    }
+}
+
+impl EntityExtractor for DefinitionExtractor {
+    fn extract(
+        &self,
+        context: &ExtractionContext,
+        message: &JsonValue,
+    ) -> Result<Option<JsonValue>, String> {
+        let mut record = serde_json::Map::new();
+
+        for field in self.entity.fields_in_dependency_order()? {
+            let value = match field.computed_from {
+                None => message.get(&field.name).cloned().unwrap_or(JsonValue::Null),
+                Some(ref computed) => {
+                    let mut args = yaml_args(&field.name, computed)?;
+                    for (i, source) in computed.sources.iter().enumerate() {
+                        let value = self.source_value(source, &record, context, message);
+                        if i == 0 {
+                            args.insert("input".to_string(), value.clone());
+                        }
+                        if let Some(name) = source.field_name() {
+                            args.entry(name.to_string()).or_insert(value);
+                        }
+                    }
+
+                    self.transforms
+                        .call_in(self.entity.transform_namespace.as_deref(), &computed.transform, &args)
+                        .map_err(|e| transform_error(&self.entity.name, &field.name, &computed.transform, &args, e))?
+                }
+            };
+            record.insert(field.name.clone(), value);
+        }
+
+        Ok(Some(JsonValue::Object(record)))
+    }
+
+    fn name(&self) -> &str {
+        &self.entity.name
+    }
+}
+
+/// Describe a failed transform call with the entity, field and input it read
+pub(crate) fn transform_error(
+    entity: &str,
+    field: &str,
+    transform: &str,
+    args: &HashMap<String, JsonValue>,
+    error: impl std::fmt::Display,
+) -> String {
+    let err = EntityError::transform_failed(entity, field, format!("'{}': {}", transform, error));
+    match args.get("input") {
+        Some(input) => err.with_source_excerpt(input.to_string()).to_string(),
+        None => err.to_string(),
+    }
+}
+
+/// A computed field's YAML `args` as transform arguments
+pub(crate) fn yaml_args(
+    field_name: &str,
+    computed: &crate::codegen::ComputedFrom,
+) -> Result<HashMap<String, JsonValue>, String> {
+    let Some(ref args) = computed.args else {
+        return Ok(HashMap::new());
+    };
+    serde_json::to_value(args)
+        .and_then(serde_json::from_value)
+        .map_err(|e| format!("Field '{}': args must be a mapping: {}", field_name, e))
+}
+
+/// Outcome of extracting one entity of a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtractionOutcome {
+    /// Extracted and stored in the context
+    Ok,
+    /// Not extracted: no extractor is registered, the extractor found
+    /// nothing, or one of the entity's dependencies failed
+    Skipped,
+    /// The extractor failed; the reason names the field and source involved
+    Failed(String),
+}
+
+/// Per-entity outcomes of parsing one message
+///
+/// Entities that were extracted are in `context` even when others failed,
+/// so callers can keep what succeeded and report or retry the rest.
+#[derive(Debug, Clone)]
+pub struct ExtractionReport {
+    pub context: ExtractionContext,
+    /// Outcome of every entity, in extraction order
+    pub outcomes: Vec<(String, ExtractionOutcome)>,
+}
+
+impl ExtractionReport {
+    /// Outcome for one entity
+    pub fn outcome(&self, entity_name: &str) -> Option<&ExtractionOutcome> {
+        self.outcomes.iter()
+            .find(|(name, _)| name == entity_name)
+            .map(|(_, outcome)| outcome)
+    }
+
+    /// Failed entities and their reasons
+    pub fn failures(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.outcomes.iter().filter_map(|(name, outcome)| match outcome {
+            ExtractionOutcome::Failed(reason) => Some((name.as_str(), reason.as_str())),
+            _ => None,
+        })
+    }
+
+    /// Whether no entity failed
+    pub fn is_complete(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// One-line summary, e.g. `3 extracted, 1 skipped, 1 failed (Order: ...)`
+    pub fn summary(&self) -> String {
+        let count = |wanted: fn(&ExtractionOutcome) -> bool| {
+            self.outcomes.iter().filter(|(_, outcome)| wanted(outcome)).count()
+        };
+        let mut summary = format!(
+            "{} extracted, {} skipped, {} failed",
+            count(|o| *o == ExtractionOutcome::Ok),
+            count(|o| *o == ExtractionOutcome::Skipped),
+            count(|o| matches!(o, ExtractionOutcome::Failed(_))),
+        );
+        let failures: Vec<String> = self.failures()
+            .map(|(name, reason)| format!("{}: {}", name, reason))
+            .collect();
+        if !failures.is_empty() {
+            summary.push_str(&format!(" ({})", failures.join("; ")));
+        }
+        summary
+    }
+}
+
 /// Generic message parser that extracts entities in dependency order.
 ///
 /// Coordinates entity extraction using:
@@ -107,7 +286,8 @@ impl MessageParser {
     /// ExtractionContext with all extracted entities
     ///
     /// # Errors
-    /// Returns error if extraction fails for a required entity
+    /// Returns error if an entity fails under the `abort` policy, or a
+    /// required entity fails under any policy
     ///
     /// # Example
     /// ```ignore
@@ -129,43 +309,136 @@ impl MessageParser {
         message: &JsonValue,
         global_context: HashMap<String, String>,
     ) -> Result<ExtractionContext, String> {
+        let report = self.parse_message_report(message, global_context);
+        self.check_policy(&report.outcomes)?;
+        Ok(report.context)
+    }
+
+    /// Parse a message, recording every entity's outcome instead of failing.
+    ///
+    /// A failed entity doesn't stop extraction: entities that depend on it
+    /// (directly or transitively) are skipped, and everything else is still
+    /// extracted. Use [`ExtractionReport::failures`] to route the failed part.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let report = parser.parse_message_report(&message, HashMap::new());
+    /// if !report.is_complete() {
+    ///     eprintln!("Partial extraction: {}", report.summary());
+    /// }
+    /// ```
+    pub fn parse_message_report(
+        &self,
+        message: &JsonValue,
+        global_context: HashMap<String, String>,
+    ) -> ExtractionReport {
         let mut context = ExtractionContext::new(global_context);
+        let outcomes = self.extract_all(message, &mut context);
+        ExtractionReport { context, outcomes }
+    }
+
+    /// Extract every entity missing from `context`, in extraction order
+    fn extract_all(
+        &self,
+        message: &JsonValue,
+        context: &mut ExtractionContext,
+    ) -> Vec<(String, ExtractionOutcome)> {
+        let mut outcomes = Vec::with_capacity(self.config.extraction_order.len());
+        // Entities that failed or were skipped because of a failure
+        let mut blocked: Vec<&str> = Vec::new();
 
-        // Extract entities in topological order
         for entity_name in &self.config.extraction_order {
-            // Get entity extractor
-            let extractor = match self.extractors.get(entity_name) {
-                Some(ext) => ext,
-                None => {
-                    // No extractor registered - skip this entity
-                    continue;
+            // Already provided by the caller
+            if context.has_entity(entity_name) {
+                outcomes.push((entity_name.clone(), ExtractionOutcome::Ok));
+                continue;
+            }
+
+            let depends_on_failure = self.config.get_entity(entity_name)
+                .map(extract_dependencies)
+                .unwrap_or_default()
+                .iter()
+                .any(|dependency| blocked.contains(&dependency.as_str()));
+
+            let outcome = if depends_on_failure {
+                blocked.push(entity_name);
+                ExtractionOutcome::Skipped
+            } else {
+                match self.extractors.get(entity_name).map(|e| e.extract(context, message)) {
+                    // No extractor registered
+                    None => ExtractionOutcome::Skipped,
+                    Some(Ok(Some(value))) => {
+                        context.set_entity(entity_name.clone(), value);
+                        ExtractionOutcome::Ok
+                    }
+                    // Entity not found (optional entity)
+                    Some(Ok(None)) => ExtractionOutcome::Skipped,
+                    Some(Err(err)) => {
+                        blocked.push(entity_name);
+                        ExtractionOutcome::Failed(err)
+                    }
                 }
             };
+            outcomes.push((entity_name.clone(), outcome));
+        }
 
-            // Extract entity
-            match extractor.extract(&context, message) {
-                Ok(Some(value)) => {
-                    // Entity extracted successfully
-                    context.set_entity(entity_name.clone(), value);
-                }
-                Ok(None) => {
-                    // Entity not found (optional entity)
-                    continue;
+        outcomes
+    }
+
+    /// Fail on entity failures the config's `on_entity_error` policy doesn't
+    /// allow: any failure under `abort`, and required entities that failed or
+    /// were skipped (e.g. because a dependency failed) under every policy
+    fn check_policy(&self, outcomes: &[(String, ExtractionOutcome)]) -> Result<(), String> {
+        for (entity_name, outcome) in outcomes {
+            if *outcome == ExtractionOutcome::Skipped && self.is_required_entity(entity_name) {
+                return Err(format!(
+                    "Required entity '{}' was not extracted (not found, or a dependency failed)",
+                    entity_name
+                ));
+            }
+            if let ExtractionOutcome::Failed(err) = outcome {
+                if self.is_required_entity(entity_name) {
+                    return Err(format!(
+                        "Failed to extract required entity '{}': {}",
+                        entity_name, err
+                    ));
                 }
-                Err(err) => {
-                    // Extraction failed - check if entity is required
-                    if self.is_required_entity(entity_name) {
-                        return Err(format!(
-                            "Failed to extract required entity '{}': {}",
-                            entity_name, err
-                        ));
-                    }
-                    // Optional entity - continue
+                if self.config.on_entity_error == OnEntityError::Abort {
+                    return Err(format!("Failed to extract entity '{}': {}", entity_name, err));
                 }
             }
         }
+        Ok(())
+    }
 
-        Ok(context)
+    /// Parse every record of a JSON array or JSON Lines source.
+    ///
+    /// Records are read and extracted one at a time (see
+    /// [`for_each_json_record`](crate::runtime::json_stream::for_each_json_record)),
+    /// so the source is never held in memory as a whole. Each record gets a
+    /// fresh context seeded with `global_context` and is handed to `on_record`
+    /// with its 0-based index.
+    ///
+    /// # Returns
+    /// Number of records parsed
+    ///
+    /// # Errors
+    /// Returns the first malformed record, extraction failure or error from `on_record`
+    pub fn parse_json_stream<R, F>(
+        &self,
+        reader: R,
+        global_context: &HashMap<String, String>,
+        mut on_record: F,
+    ) -> Result<usize, String>
+    where
+        R: std::io::Read,
+        F: FnMut(usize, ExtractionContext) -> Result<(), String>,
+    {
+        crate::runtime::json_stream::for_each_json_record(reader, |index, message| {
+            let context = self.parse_message(&message, global_context.clone())
+                .map_err(|e| format!("Record {}: {}", index, e))?;
+            on_record(index, context)
+        })
     }
 
     /// Parse a message with an existing context.
@@ -184,37 +457,8 @@ impl MessageParser {
         message: &JsonValue,
         context: &mut ExtractionContext,
     ) -> Result<(), String> {
-        // Extract entities in topological order
-        for entity_name in &self.config.extraction_order {
-            // Skip if already extracted
-            if context.has_entity(entity_name) {
-                continue;
-            }
-
-            // Get entity extractor
-            let extractor = match self.extractors.get(entity_name) {
-                Some(ext) => ext,
-                None => continue,
-            };
-
-            // Extract entity
-            match extractor.extract(context, message) {
-                Ok(Some(value)) => {
-                    context.set_entity(entity_name.clone(), value);
-                }
-                Ok(None) => continue,
-                Err(err) => {
-                    if self.is_required_entity(entity_name) {
-                        return Err(format!(
-                            "Failed to extract required entity '{}': {}",
-                            entity_name, err
-                        ));
-                    }
-                }
-            }
-        }
-
-        Ok(())
+        let outcomes = self.extract_all(message, context);
+        self.check_policy(&outcomes)
     }
 
     /// Extract a single entity by name.
@@ -315,11 +559,235 @@ mod tests {
         }
     }
 
+    struct FailingExtractor {
+        name: String,
+    }
+
+    impl EntityExtractor for FailingExtractor {
+        fn extract(
+            &self,
+            _context: &ExtractionContext,
+            _message: &JsonValue,
+        ) -> Result<Option<JsonValue>, String> {
+            Err("bad amount".to_string())
+        }
+
+        fn name(&self) -> &str {
+            &self.name
+        }
+    }
+
+    #[test]
+    fn test_parse_message_report_keeps_partial_results() {
+        let config = ParserConfig::builder()
+            .entity("Order", &[])
+            .entity("Payment", &["Order"])
+            .entity("Refund", &["Payment"])
+            .entity("Customer", &["Order"])
+            .required("Payment")
+            .build()
+            .unwrap();
+        let mut parser = MessageParser::new(config);
+        for name in ["Order", "Refund", "Customer"] {
+            parser.register_extractor(Box::new(MockExtractor {
+                name: name.to_string(),
+                result: Some(json!({"name": name})),
+            }));
+        }
+        parser.register_extractor(Box::new(FailingExtractor { name: "Payment".to_string() }));
+
+        let report = parser.parse_message_report(&json!({}), HashMap::new());
+        assert_eq!(report.outcome("Order"), Some(&ExtractionOutcome::Ok));
+        assert_eq!(report.outcome("Customer"), Some(&ExtractionOutcome::Ok));
+        assert_eq!(report.outcome("Payment"), Some(&ExtractionOutcome::Failed("bad amount".to_string())));
+        assert_eq!(report.outcome("Refund"), Some(&ExtractionOutcome::Skipped));
+        assert!(report.context.has_entity("Customer"));
+        assert!(!report.is_complete());
+        assert_eq!(report.summary(), "2 extracted, 1 skipped, 1 failed (Payment: bad amount)");
+
+        let err = parser.parse_message(&json!({}), HashMap::new()).unwrap_err();
+        assert!(err.contains("required entity 'Payment'"), "{}", err);
+    }
+
+    fn policy_parser(policy: Option<OnEntityError>, payment_required: bool) -> MessageParser {
+        let mut builder = ParserConfig::builder()
+            .entity("Order", &[])
+            .entity("Payment", &["Order"]);
+        if let Some(policy) = policy {
+            builder = builder.on_entity_error(policy);
+        }
+        if payment_required {
+            builder = builder.required("Payment");
+        }
+        let mut parser = MessageParser::new(builder.build().unwrap());
+        parser.register_extractor(Box::new(MockExtractor {
+            name: "Order".to_string(),
+            result: Some(json!({"id": 1})),
+        }));
+        parser.register_extractor(Box::new(FailingExtractor { name: "Payment".to_string() }));
+        parser
+    }
+
+    #[test]
+    fn test_on_entity_error_policy() {
+        let err = policy_parser(Some(OnEntityError::Abort), false)
+            .parse_message(&json!({}), HashMap::new())
+            .unwrap_err();
+        assert_eq!(err, "Failed to extract entity 'Payment': bad amount");
+
+        for policy in [OnEntityError::Skip, OnEntityError::Dlq] {
+            let context = policy_parser(Some(policy), false).parse_message(&json!({}), HashMap::new()).unwrap();
+            assert!(context.has_entity("Order"));
+            assert!(!context.has_entity("Payment"));
+
+            let mut context = ExtractionContext::new(HashMap::new());
+            policy_parser(Some(policy), false).parse_message_with_context(&json!({}), &mut context).unwrap();
+            assert!(context.has_entity("Order"));
+        }
+    }
+
+    #[test]
+    fn test_on_entity_error_default_aborts() {
+        assert_eq!(ParserConfig::default().on_entity_error, OnEntityError::Abort);
+
+        let parser = policy_parser(None, false);
+        let err = parser.parse_message(&json!({}), HashMap::new()).unwrap_err();
+        assert_eq!(err, "Failed to extract entity 'Payment': bad amount");
+
+        let mut context = ExtractionContext::new(HashMap::new());
+        assert!(parser.parse_message_with_context(&json!({}), &mut context).is_err());
+    }
+
+    #[test]
+    fn test_on_entity_error_required_entities_fail_under_every_policy() {
+        for policy in [None, Some(OnEntityError::Abort), Some(OnEntityError::Skip), Some(OnEntityError::Dlq)] {
+            let parser = policy_parser(policy, true);
+            let err = parser.parse_message(&json!({}), HashMap::new()).unwrap_err();
+            assert!(err.contains("required entity 'Payment'"), "{:?}: {}", policy, err);
+
+            let mut context = ExtractionContext::new(HashMap::new());
+            assert!(parser.parse_message_with_context(&json!({}), &mut context).is_err());
+        }
+    }
+
+    #[test]
+    fn test_required_entity_skipped_after_parent_failure_fails_the_message() {
+        let config = ParserConfig::builder()
+            .entity("Order", &[])
+            .entity("Payment", &["Order"])
+            .entity("Refund", &["Payment"])
+            .required("Refund")
+            .on_entity_error(OnEntityError::Skip)
+            .build()
+            .unwrap();
+        let mut parser = MessageParser::new(config);
+        for name in ["Order", "Refund"] {
+            parser.register_extractor(Box::new(MockExtractor {
+                name: name.to_string(),
+                result: Some(json!({"name": name})),
+            }));
+        }
+        parser.register_extractor(Box::new(FailingExtractor { name: "Payment".to_string() }));
+
+        let report = parser.parse_message_report(&json!({}), HashMap::new());
+        assert_eq!(report.outcome("Refund"), Some(&ExtractionOutcome::Skipped));
+
+        let err = parser.parse_message(&json!({}), HashMap::new()).unwrap_err();
+        assert!(err.contains("Required entity 'Refund'"), "{}", err);
+
+        let mut context = ExtractionContext::new(HashMap::new());
+        assert!(parser.parse_message_with_context(&json!({}), &mut context).is_err());
+    }
+
+    #[test]
+    fn test_definition_extractor_picks_up_reloaded_transforms() {
+        use crate::codegen::parse_entity;
+        use crate::transform_registry::{TransformError, TransformFn};
+
+        let entity = parse_entity(r#"
+entity:
+  name: Order
+  source_type: root
+  fields:
+    - name: status_code
+      type: String
+    - name: status
+      type: String
+      computed_from:
+        transform: status_label
+        sources:
+          - source: self
+            field: status_code
+"#).unwrap();
+
+        let label = |text: &'static str| {
+            Box::new(move |args: &HashMap<String, JsonValue>| -> Result<JsonValue, TransformError> {
+                let code = args.get("input").and_then(|v| v.as_str()).unwrap_or_default();
+                Ok(json!(format!("{} {}", text, code)))
+            }) as Box<dyn TransformFn>
+        };
+
+        let mut registry = TransformRegistry::new();
+        registry.register("status_label", label("old"));
+        let registry = Arc::new(registry);
+
+        let extractor = DefinitionExtractor::new(entity, Arc::clone(&registry));
+        let context = ExtractionContext::empty();
+        let message = json!({"status_code": "S"});
+
+        let order = extractor.extract(&context, &message).unwrap().unwrap();
+        assert_eq!(order, json!({"status_code": "S", "status": "old S"}));
+
+        // Reload through a second handle, as a config watcher thread would
+        let watcher = Arc::clone(&registry);
+        std::thread::spawn(move || watcher.reload("status_label", label("new")).unwrap())
+            .join()
+            .unwrap();
+        let order = extractor.extract(&context, &message).unwrap().unwrap();
+        assert_eq!(order["status"], json!("new S"));
+
+        assert!(registry.reload("missing", label("x")).is_err());
+    }
+
+    #[test]
+    fn test_parse_json_stream_extracts_each_record() {
+        use crate::codegen::parse_entity;
+
+        let entity = parse_entity(r#"
+entity:
+  name: Order
+  source_type: root
+  fields:
+    - name: id
+      type: String
+"#).unwrap();
+
+        let mut entities = HashMap::new();
+        entities.insert("Order".to_string(), HashMap::new());
+        let mut parser = MessageParser::new(ParserConfig {
+            entities,
+            extraction_order: vec!["Order".to_string()],
+            ..Default::default()
+        });
+        parser.register_extractor(Box::new(DefinitionExtractor::new(entity, Arc::new(TransformRegistry::new()))));
+
+        let mut ids = Vec::new();
+        let source = "[{\"id\": \"a\"}, {\"id\": \"b\", \"extra\": 1}]";
+        let count = parser.parse_json_stream(source.as_bytes(), &HashMap::new(), |_, context| {
+            ids.push(context.get_entity("Order").unwrap()["id"].clone());
+            Ok(())
+        }).unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(ids, vec![json!("a"), json!("b")]);
+    }
+
     #[test]
     fn test_create_message_parser() {
         let config = ParserConfig {
             entities: HashMap::new(),
             extraction_order: vec![],
+            ..Default::default()
         };
 
         let parser = MessageParser::new(config);
@@ -332,6 +800,7 @@ mod tests {
         let config = ParserConfig {
             entities: HashMap::new(),
             extraction_order: vec![],
+            ..Default::default()
         };
 
         let mut parser = MessageParser::new(config);
@@ -356,6 +825,7 @@ mod tests {
         let config = ParserConfig {
             entities,
             extraction_order: vec!["Entity1".to_string(), "Entity2".to_string()],
+            ..Default::default()
         };
 
         let mut parser = MessageParser::new(config);
@@ -397,6 +867,7 @@ mod tests {
         let config = ParserConfig {
             entities,
             extraction_order: vec!["Entity1".to_string()],
+            ..Default::default()
         };
 
         let mut parser = MessageParser::new(config);
@@ -424,6 +895,7 @@ mod tests {
         let config = ParserConfig {
             entities,
             extraction_order: vec!["TestEntity".to_string()],
+            ..Default::default()
         };
 
         let mut parser = MessageParser::new(config);
@@ -448,6 +920,7 @@ mod tests {
         let config = ParserConfig {
             entities: HashMap::new(),
             extraction_order: vec![],
+            ..Default::default()
         };
 
         let parser = MessageParser::new(config);
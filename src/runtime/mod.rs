@@ -8,14 +8,31 @@ pub mod message_parser;
 pub mod config_loader;
 pub mod transforms;
 pub mod transform_registry;
+pub mod builtins;
+pub mod compiled_extractor;
+pub mod json_stream;
+pub mod encoding;
+pub mod records;
+pub mod input;
+pub mod progress;
+pub mod commit_batches;
 
 // Re-export key types
 pub use context::ExtractionContext;
-pub use message_parser::{MessageParser, EntityExtractor};
-pub use config_loader::{ParserConfig, compute_extraction_order};
+pub use message_parser::{MessageParser, EntityExtractor, DefinitionExtractor, ExtractionOutcome, ExtractionReport};
+pub use compiled_extractor::CompiledExtractor;
+pub use json_stream::for_each_json_record;
+pub use encoding::{DecodedLines, InputEncoding};
+pub use records::{DecodedRecords, RecordSeparator};
+pub use input::{decompress_reader, expand_inputs, open_input, InputCodec};
+pub use progress::Progress;
+pub use commit_batches::CommitBatches;
+pub use config_loader::{OnEntityError, ParserConfig, ParserConfigBuilder, compute_extraction_order};
 pub use transforms::{
     TransformDef, TransformLanguage, Parameter, ReturnType,
-    Implementation, TransformStep, TransformTest, TransformRegistry as TransformRegistryLoader,
+    Implementation, TransformStep, TransformTest, TransformTestOutcome, TransformTestResult,
+    TransformRegistry as TransformRegistryLoader,
     load_transform, load_transforms_from_dir
 };
 pub use transform_registry::TransformRegistry;
+pub use builtins::{builtin_registry, BUILTIN_TRANSFORMS};
@@ -0,0 +1,129 @@
+//! Progress reporting for long parser runs.
+//!
+//! A [`Progress`] counts files, records, extracted entities per type and
+//! errors, and prints a summary to stderr at most once per interval plus a
+//! final totals block. In JSON mode every report is one JSON object per line
+//! (`"event": "progress"` or `"summary"`) for orchestrators to follow.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Running counts of a parser run
+#[derive(Debug)]
+pub struct Progress {
+    started: Instant,
+    last_report: Instant,
+    interval: Duration,
+    json: bool,
+    files: usize,
+    records: usize,
+    errors: usize,
+    entities: BTreeMap<String, usize>,
+}
+
+impl Progress {
+    /// Report at most once per `interval`, as JSON lines when `json`
+    pub fn new(interval: Duration, json: bool) -> Self {
+        let now = Instant::now();
+        Self {
+            started: now,
+            last_report: now,
+            interval,
+            json,
+            files: 0,
+            records: 0,
+            errors: 0,
+            entities: BTreeMap::new(),
+        }
+    }
+
+    /// Count a finished input file
+    pub fn file_done(&mut self) {
+        self.files += 1;
+    }
+
+    /// Count a parsed record and the entities extracted from it
+    pub fn record_done<'a>(&mut self, entity_counts: impl IntoIterator<Item = (&'a str, usize)>) {
+        self.records += 1;
+        for (entity, count) in entity_counts {
+            *self.entities.entry(entity.to_string()).or_default() += count;
+        }
+    }
+
+    /// Count failed records or entities
+    pub fn add_errors(&mut self, errors: usize) {
+        self.errors += errors;
+    }
+
+    /// Print a progress report if the interval has passed since the last one
+    pub fn tick(&mut self) {
+        if self.last_report.elapsed() >= self.interval {
+            self.last_report = Instant::now();
+            eprintln!("{}", self.report("progress"));
+        }
+    }
+
+    /// Print the final totals
+    pub fn finish(&self) {
+        eprintln!("{}", self.report("summary"));
+    }
+
+    /// One report: a JSON object, or human-readable text
+    fn report(&self, event: &str) -> String {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let records_per_sec = if elapsed > 0.0 { self.records as f64 / elapsed } else { 0.0 };
+        if self.json {
+            return serde_json::json!({
+                "event": event,
+                "elapsed_secs": elapsed,
+                "files": self.files,
+                "records": self.records,
+                "records_per_sec": records_per_sec,
+                "entities": self.entities,
+                "errors": self.errors,
+            }).to_string();
+        }
+
+        let entities: Vec<String> = self.entities.iter()
+            .map(|(entity, count)| format!("{}={}", entity, count))
+            .collect();
+        let line = format!(
+            "files: {}, records: {} ({:.1}/s), errors: {}",
+            self.files, self.records, records_per_sec, self.errors
+        );
+        if event == "summary" {
+            let mut summary = format!("Totals after {:.1}s\n  {}", elapsed, line);
+            for entity in &entities {
+                summary.push_str(&format!("\n  {}", entity));
+            }
+            summary
+        } else {
+            format!("[{:.1}s] {}, entities: {}", elapsed, line, entities.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let mut progress = Progress::new(Duration::from_secs(60), false);
+        progress.record_done([("Order", 1), ("LineItem", 3)]);
+        progress.record_done([("Order", 1), ("LineItem", 2)]);
+        progress.add_errors(1);
+        progress.file_done();
+
+        let line = progress.report("progress");
+        assert!(line.contains("files: 1, records: 2"), "{}", line);
+        assert!(line.ends_with("errors: 1, entities: LineItem=5, Order=2"), "{}", line);
+        assert!(progress.report("summary").starts_with("Totals after"));
+
+        progress.json = true;
+        let json: serde_json::Value = serde_json::from_str(&progress.report("summary")).unwrap();
+        assert_eq!(json["event"], "summary");
+        assert_eq!(json["entities"]["LineItem"], 5);
+        assert_eq!(json["errors"], 1);
+    }
+}
@@ -0,0 +1,219 @@
+//! Grouping of input lines into records.
+//!
+//! Line-oriented input doesn't always hold one record per line: quoted CSV
+//! columns can contain newlines, and HL7 messages are made of `\r`-separated
+//! segments that may or may not also be broken with `\n`. A
+//! [`RecordSeparator`] says where one record ends and the next begins:
+//!
+//! - `newline` (default): every line is a record
+//! - `blank_line`: records are separated by empty lines
+//! - `csv`: newlines inside quoted columns don't end the record
+//! - `hl7`: a record starts at each `MSH` segment; its segments are joined
+//!   with `\r`
+//! - `terminator:<text>`: a line consisting of `<text>` ends the record
+//!   (e.g. `terminator:$$$$` or `terminator:---`)
+
+use std::collections::VecDeque;
+
+/// Where records end in a line-oriented input
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum RecordSeparator {
+    /// One record per line
+    #[default]
+    Newline,
+    /// Records separated by empty lines
+    BlankLine,
+    /// One record per line, except inside quoted columns
+    Csv { quote: char, escape: Option<char> },
+    /// One record per HL7 message (starting at an `MSH` segment)
+    Hl7,
+    /// Records terminated by a line equal to the given text
+    Terminator(String),
+}
+
+impl RecordSeparator {
+    /// Use the quote and escape characters of a CSV dialect (no-op for
+    /// other separators)
+    pub fn with_quote(self, quote: char, escape: Option<char>) -> Self {
+        match self {
+            RecordSeparator::Csv { .. } => RecordSeparator::Csv { quote, escape },
+            other => other,
+        }
+    }
+}
+
+impl std::str::FromStr for RecordSeparator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(terminator) = s.strip_prefix("terminator:") {
+            if terminator.trim().is_empty() {
+                return Err("Record separator terminator must not be empty".to_string());
+            }
+            return Ok(RecordSeparator::Terminator(terminator.to_string()));
+        }
+        match s {
+            "" => Err("Record separator must not be empty".to_string()),
+            "newline" | "line" => Ok(RecordSeparator::Newline),
+            "blank_line" => Ok(RecordSeparator::BlankLine),
+            "csv" => Ok(RecordSeparator::Csv { quote: '"', escape: None }),
+            "hl7" => Ok(RecordSeparator::Hl7),
+            other => Err(format!(
+                "Unknown record separator '{}' (expected newline, blank_line, csv, hl7 or terminator:<text>)",
+                other
+            )),
+        }
+    }
+}
+
+/// Iterator over the records of a line iterator such as
+/// [`DecodedLines`](super::DecodedLines)
+pub struct DecodedRecords<I> {
+    lines: I,
+    separator: RecordSeparator,
+    /// HL7 segments read but not yet part of a record
+    segments: VecDeque<String>,
+}
+
+impl<I: Iterator<Item = Result<String, String>>> DecodedRecords<I> {
+    /// Group `lines` into records ending at `separator`
+    pub fn new(lines: I, separator: RecordSeparator) -> Self {
+        Self { lines, separator, segments: VecDeque::new() }
+    }
+
+    /// Next CSV record: lines are joined while a quoted column is open
+    fn next_csv(&mut self, quote: char, escape: Option<char>) -> Option<Result<String, String>> {
+        let mut record = match self.lines.next()? {
+            Ok(line) => line,
+            Err(e) => return Some(Err(e)),
+        };
+        let mut in_quotes = false;
+        let mut scanned = 0;
+        loop {
+            let mut chars = record[scanned..].chars();
+            while let Some(c) = chars.next() {
+                if in_quotes && Some(c) == escape {
+                    chars.next();
+                } else if c == quote {
+                    in_quotes = !in_quotes;
+                }
+            }
+            if !in_quotes {
+                return Some(Ok(record));
+            }
+            scanned = record.len();
+            match self.lines.next() {
+                Some(Ok(line)) => {
+                    record.push('\n');
+                    record.push_str(&line);
+                }
+                Some(Err(e)) => return Some(Err(e)),
+                None => return Some(Err(format!("Unterminated quoted CSV column in record: {}", record))),
+            }
+        }
+    }
+
+    /// Next HL7 message: segments up to the next `MSH`, joined with `\r`
+    fn next_hl7(&mut self) -> Option<Result<String, String>> {
+        let mut segments: Vec<String> = Vec::new();
+        loop {
+            while self.segments.is_empty() {
+                match self.lines.next() {
+                    Some(Ok(line)) => self.segments.extend(
+                        line.split('\r').filter(|s| !s.trim().is_empty()).map(str::to_string)
+                    ),
+                    Some(Err(e)) => return Some(Err(e)),
+                    None => return (!segments.is_empty()).then(|| Ok(segments.join("\r"))),
+                }
+            }
+            if !segments.is_empty() && self.segments[0].starts_with("MSH") {
+                return Some(Ok(segments.join("\r")));
+            }
+            segments.extend(self.segments.pop_front());
+        }
+    }
+
+    /// Next record ending at a line matching `is_end`
+    fn next_terminated(&mut self, is_end: impl Fn(&str) -> bool) -> Option<Result<String, String>> {
+        let mut lines: Vec<String> = Vec::new();
+        loop {
+            match self.lines.next() {
+                Some(Ok(line)) if is_end(&line) => {
+                    if !lines.is_empty() {
+                        return Some(Ok(lines.join("\n")));
+                    }
+                }
+                Some(Ok(line)) => lines.push(line),
+                Some(Err(e)) => return Some(Err(e)),
+                None => return (!lines.is_empty()).then(|| Ok(lines.join("\n"))),
+            }
+        }
+    }
+}
+
+impl<I: Iterator<Item = Result<String, String>>> Iterator for DecodedRecords<I> {
+    type Item = Result<String, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.separator.clone() {
+            RecordSeparator::Newline => self.lines.next(),
+            RecordSeparator::BlankLine => self.next_terminated(|line| line.trim().is_empty()),
+            RecordSeparator::Csv { quote, escape } => self.next_csv(quote, escape),
+            RecordSeparator::Hl7 => self.next_hl7(),
+            RecordSeparator::Terminator(terminator) => self.next_terminated(|line| line.trim_end() == terminator),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::DecodedLines;
+
+    fn records(input: &str, separator: &str) -> Vec<String> {
+        let lines = DecodedLines::new(input.as_bytes(), Default::default());
+        DecodedRecords::new(lines, separator.parse().unwrap())
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_csv_records_span_quoted_newlines() {
+        let input = "1,\"first\nsecond\",x\n2,\"say \"\"hi\"\"\",y\n";
+        assert_eq!(records(input, "csv"), ["1,\"first\nsecond\",x", "2,\"say \"\"hi\"\"\",y"]);
+
+        let escaped = RecordSeparator::Csv { quote: '"', escape: None }.with_quote('\'', Some('\\'));
+        let lines = DecodedLines::new("1,'it\\'s\nlong'\n2,b\n".as_bytes(), Default::default());
+        let rows: Vec<_> = DecodedRecords::new(lines, escaped).collect::<Result<_, _>>().unwrap();
+        assert_eq!(rows, ["1,'it\\'s\nlong'", "2,b"]);
+
+        let lines = DecodedLines::new("1,\"open\n".as_bytes(), Default::default());
+        assert!(DecodedRecords::new(lines, "csv".parse().unwrap()).any(|r| r.is_err()));
+    }
+
+    #[test]
+    fn test_hl7_records() {
+        // Segments split by \r, by \n, or both
+        let input = "MSH|1\rPID|a\nOBX|x\r\nMSH|2\rPID|b\rMSH|3\n";
+        assert_eq!(records(input, "hl7"), ["MSH|1\rPID|a\rOBX|x", "MSH|2\rPID|b", "MSH|3"]);
+    }
+
+    #[test]
+    fn test_terminated_records() {
+        assert_eq!(records("a\nb\n\n\nc\n", "blank_line"), ["a\nb", "c"]);
+        assert_eq!(records("a\n$$$$\nb\nc\n$$$$\n", "terminator:$$$$"), ["a", "b\nc"]);
+        assert_eq!(records("a\nb\n", "newline"), ["a", "b"]);
+    }
+
+    #[test]
+    fn test_record_separator_from_str() {
+        assert_eq!("terminator:---".parse::<RecordSeparator>().unwrap(), RecordSeparator::Terminator("---".to_string()));
+        assert_eq!("line".parse::<RecordSeparator>().unwrap(), RecordSeparator::Newline);
+
+        // Typos of known names are rejected rather than read as terminators
+        for invalid in ["", "newlines", "CSV", "blank-line", "$$$$", "terminator:", "terminator: "] {
+            assert!(invalid.parse::<RecordSeparator>().is_err(), "{:?}", invalid);
+        }
+        assert!("hl-7".parse::<RecordSeparator>().unwrap_err().contains("Unknown record separator 'hl-7'"));
+    }
+}
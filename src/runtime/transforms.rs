@@ -98,6 +98,17 @@ pub enum Implementation {
     },
 }
 
+impl Implementation {
+    /// Implementation type as written in YAML
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Implementation::Builtin => "builtin",
+            Implementation::Inline { .. } => "inline",
+            Implementation::Reference { .. } => "reference",
+        }
+    }
+}
+
 /// Step in a transform chain.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransformStep {
@@ -124,6 +135,28 @@ pub struct TransformTest {
     pub expected: serde_json::Value,
 }
 
+/// Outcome of running one declared [`TransformTest`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransformTestOutcome {
+    /// The transform returned the expected value
+    Passed,
+    /// The transform returned something else
+    Failed { actual: serde_json::Value },
+    /// The transform returned an error
+    Error(String),
+    /// The transform can't run standalone (inline implementation)
+    Skipped(String),
+}
+
+/// Result of one declared test case of one transform.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransformTestResult {
+    pub transform: String,
+    pub test: String,
+    pub expected: serde_json::Value,
+    pub outcome: TransformTestOutcome,
+}
+
 /// Registry of transforms loaded from YAMLs.
 #[derive(Debug, Clone)]
 pub struct TransformRegistry {
@@ -295,6 +328,232 @@ impl TransformRegistry {
             .collect()
     }
 
+    /// Call a transform standalone, without generated code.
+    ///
+    /// Defined parameters are checked and defaulted, builtin implementations
+    /// dispatch to `builtins` (with the first parameter also passed as
+    /// `input`), and reference chains are evaluated step by step. Inline
+    /// implementations only exist as generated source and cannot be called.
+    /// Names with no YAML definition fall back to `builtins` directly.
+    /// A reference chain that calls back into a transform already being
+    /// evaluated is an error rather than unbounded recursion.
+    pub fn call(
+        &self,
+        name: &str,
+        args: &HashMap<String, serde_json::Value>,
+        builtins: &crate::transform_registry::TransformRegistry,
+    ) -> Result<serde_json::Value, String> {
+        self.call_guarded(name, args, builtins, &mut Vec::new())
+    }
+
+    /// [`call`](Self::call), tracking the chain of transforms being evaluated
+    fn call_guarded(
+        &self,
+        name: &str,
+        args: &HashMap<String, serde_json::Value>,
+        builtins: &crate::transform_registry::TransformRegistry,
+        active: &mut Vec<String>,
+    ) -> Result<serde_json::Value, String> {
+        let Some(transform) = self.get_transform(name) else {
+            return builtins.call(name, args).map_err(|e| e.to_string());
+        };
+
+        if active.iter().any(|n| n == name) {
+            return Err(format!(
+                "Transform '{}' contains circular reference: {} -> {}",
+                name, active.join(" -> "), name
+            ));
+        }
+
+        let mut bound = args.clone();
+        for param in &transform.parameters {
+            if bound.contains_key(&param.name) {
+                continue;
+            }
+            match param.default {
+                Some(ref default) => {
+                    let value = serde_json::from_str(default)
+                        .unwrap_or_else(|_| serde_json::Value::String(default.clone()));
+                    bound.insert(param.name.clone(), value);
+                }
+                None if param.param_type.starts_with("Option<") => {
+                    bound.insert(param.name.clone(), serde_json::Value::Null);
+                }
+                None => {
+                    return Err(format!(
+                        "Transform '{}' is missing argument '{}'",
+                        name, param.name
+                    ));
+                }
+            }
+        }
+
+        match &transform.implementation {
+            Implementation::Builtin => {
+                if let Some(first) = transform.parameters.first() {
+                    let input = bound[&first.name].clone();
+                    bound.entry("input".to_string()).or_insert(input);
+                }
+                builtins.call(name, &bound).map_err(|e| e.to_string())
+            }
+            Implementation::Inline { .. } => Err(format!(
+                "Transform '{}' has an inline {:?} implementation and only runs in generated code",
+                name, transform.language
+            )),
+            Implementation::Reference { steps, return_var } => {
+                active.push(name.to_string());
+                let result = self.evaluate_steps(name, steps, return_var, bound, builtins, active);
+                active.pop();
+                result
+            }
+        }
+    }
+
+    /// Evaluate the steps of reference transform `name` in order
+    fn evaluate_steps(
+        &self,
+        name: &str,
+        steps: &[TransformStep],
+        return_var: &str,
+        mut vars: HashMap<String, serde_json::Value>,
+        builtins: &crate::transform_registry::TransformRegistry,
+        active: &mut Vec<String>,
+    ) -> Result<serde_json::Value, String> {
+        for step in steps {
+            let step_args = step.args.iter()
+                .map(|(key, value)| {
+                    let value = match value.as_str().and_then(|s| s.strip_prefix('$')) {
+                        Some(var) => vars.get(var).cloned().ok_or_else(|| format!(
+                            "Transform '{}' step '{}' references unknown variable '${}'",
+                            name, step.transform, var
+                        ))?,
+                        None => value.clone(),
+                    };
+                    Ok((key.clone(), value))
+                })
+                .collect::<Result<HashMap<_, _>, String>>()?;
+
+            let result = self.call_guarded(&step.transform, &step_args, builtins, active)?;
+            vars.insert(step.output.trim_start_matches('$').to_string(), result);
+        }
+
+        let var = return_var.trim_start_matches('$');
+        vars.get(var).cloned().ok_or_else(|| format!(
+            "Transform '{}' returns unknown variable '${}'",
+            name, var
+        ))
+    }
+
+    /// Run every declared `tests:` case through [`call`](Self::call).
+    ///
+    /// Results are ordered by transform then declaration order. Numbers
+    /// compare by value, so an expected `2` matches a returned `2.0`.
+    pub fn run_tests(
+        &self,
+        builtins: &crate::transform_registry::TransformRegistry,
+    ) -> Vec<TransformTestResult> {
+        let mut names: Vec<&String> = self.transforms.keys().collect();
+        names.sort();
+
+        names.into_iter()
+            .flat_map(|name| {
+                let transform = &self.transforms[name];
+                transform.tests.iter().map(move |test| {
+                    let outcome = if matches!(transform.implementation, Implementation::Inline { .. }) {
+                        TransformTestOutcome::Skipped(format!(
+                            "inline {:?} implementation only runs in generated code",
+                            transform.language
+                        ))
+                    } else {
+                        match self.call(name, &test.args, builtins) {
+                            Ok(actual) if json_values_match(&actual, &test.expected) => TransformTestOutcome::Passed,
+                            Ok(actual) => TransformTestOutcome::Failed { actual },
+                            Err(e) => TransformTestOutcome::Error(e),
+                        }
+                    };
+                    TransformTestResult {
+                        transform: name.clone(),
+                        test: test.name.clone(),
+                        expected: test.expected.clone(),
+                        outcome,
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Loaded transforms sorted by name, for stable listings
+    fn sorted(&self) -> Vec<&TransformDef> {
+        let mut transforms: Vec<&TransformDef> = self.transforms.values().collect();
+        transforms.sort_by(|a, b| a.name.cmp(&b.name));
+        transforms
+    }
+
+    /// Catalog of every loaded transform as JSON.
+    ///
+    /// One object per transform, sorted by name, with its language,
+    /// implementation kind, doc, parameters and return type.
+    pub fn catalog_json(&self) -> serde_json::Value {
+        let entries = self.sorted().into_iter()
+            .map(|t| serde_json::json!({
+                "name": t.name,
+                "language": t.language,
+                "implementation": t.implementation.kind(),
+                "doc": t.doc,
+                "parameters": t.parameters.iter().map(|p| serde_json::json!({
+                    "name": p.name,
+                    "type": p.param_type,
+                    "default": p.default,
+                    "doc": p.doc,
+                })).collect::<Vec<_>>(),
+                "returns": {
+                    "type": t.returns.return_type,
+                    "doc": t.returns.doc,
+                },
+            }))
+            .collect();
+        serde_json::Value::Array(entries)
+    }
+
+    /// Catalog of every loaded transform as Markdown, one section per transform.
+    pub fn catalog_markdown(&self) -> String {
+        let mut md = String::from("# Transforms\n");
+        for t in self.sorted() {
+            md.push_str(&format!("\n## `{}`\n\n", t.name));
+            if let Some(ref doc) = t.doc {
+                md.push_str(doc.trim());
+                md.push_str("\n\n");
+            }
+            md.push_str(&format!(
+                "*{:?}, {}*\n\n",
+                t.language,
+                t.implementation.kind()
+            ));
+
+            if !t.parameters.is_empty() {
+                md.push_str("| Parameter | Type | Default | Description |\n");
+                md.push_str("|---|---|---|---|\n");
+                for p in &t.parameters {
+                    md.push_str(&format!(
+                        "| `{}` | `{}` | {} | {} |\n",
+                        p.name,
+                        p.param_type,
+                        p.default.as_deref().map(|d| format!("`{}`", d)).unwrap_or_default(),
+                        markdown_cell(p.doc.as_deref().unwrap_or("")),
+                    ));
+                }
+                md.push('\n');
+            }
+
+            md.push_str(&format!("**Returns** `{}`", t.returns.return_type));
+            if let Some(ref doc) = t.returns.doc {
+                md.push_str(&format!(": {}", doc.trim()));
+            }
+            md.push('\n');
+        }
+        md
+    }
+
     /// Get all transforms with inline implementations (need code generation).
     pub fn inline_transforms(&self) -> Vec<&TransformDef> {
         self.transforms
@@ -304,6 +563,26 @@ impl TransformRegistry {
     }
 }
 
+/// Flatten text into one Markdown table cell
+fn markdown_cell(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").replace('|', "\\|")
+}
+
+/// JSON equality where numbers compare by value rather than representation
+fn json_values_match(actual: &serde_json::Value, expected: &serde_json::Value) -> bool {
+    use serde_json::Value;
+    match (actual, expected) {
+        (Value::Number(a), Value::Number(b)) => a == b || a.as_f64() == b.as_f64(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| json_values_match(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len() && a.iter().all(|(k, v)| b.get(k).is_some_and(|e| json_values_match(v, e)))
+        }
+        _ => actual == expected,
+    }
+}
+
 impl Default for TransformRegistry {
     fn default() -> Self {
         Self::new()
@@ -472,6 +751,180 @@ transform:
         assert!(registry.has_transform("transform2"));
     }
 
+    #[test]
+    fn test_call_reference_chain_with_builtins() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_transform_yaml(
+            temp_dir.path(),
+            "extract_csv_field",
+            r#"
+transform:
+  name: extract_csv_field
+  language: rust
+  parameters:
+    - name: line
+      type: String
+    - name: column_index
+      type: usize
+    - name: delimiter
+      type: String
+      default: ","
+  returns:
+    type: Option<String>
+  implementation:
+    type: builtin
+"#,
+        );
+        let mut registry = TransformRegistry::new();
+        registry.load_transforms_from_dir(temp_dir.path()).unwrap();
+
+        let chain = create_test_transform_yaml(
+            temp_dir.path(),
+            "second_then_first",
+            r#"
+transform:
+  name: second_then_first
+  language: rust
+  parameters:
+    - name: line
+      type: String
+  returns:
+    type: Option<String>
+  implementation:
+    type: reference
+    steps:
+      - transform: extract_csv_field
+        args:
+          line: $line
+          column_index: 1
+          delimiter: "|"
+        output: field
+      - transform: extract_csv_field
+        args:
+          line: $field
+          column_index: 0
+        output: result
+    return: $result
+"#,
+        );
+        registry.load_transform(&chain).unwrap();
+
+        let builtins = crate::runtime::builtin_registry();
+        let mut args = HashMap::new();
+        args.insert("line".to_string(), serde_json::json!("a|b,c|d"));
+
+        let result = registry.call("second_then_first", &args, &builtins).unwrap();
+        assert_eq!(result, serde_json::json!("b"));
+
+        let missing = registry.call("extract_csv_field", &HashMap::new(), &builtins);
+        assert!(missing.unwrap_err().contains("missing argument 'line'"));
+    }
+
+    #[test]
+    fn test_run_declared_tests() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_transform_yaml(
+            temp_dir.path(),
+            "parse_float",
+            r#"
+transform:
+  name: parse_float
+  language: rust
+  parameters:
+    - name: value
+      type: String
+  returns:
+    type: Option<f64>
+  implementation:
+    type: builtin
+  tests:
+    - name: whole_number
+      args:
+        value: "2"
+      expected: 2
+    - name: wrong_expectation
+      args:
+        value: "1.5"
+      expected: 2.5
+"#,
+        );
+        create_test_transform_yaml(
+            temp_dir.path(),
+            "shout",
+            r#"
+transform:
+  name: shout
+  language: rust
+  parameters:
+    - name: text
+      type: String
+  returns:
+    type: String
+  implementation:
+    type: inline
+    code: "text.to_uppercase()"
+  tests:
+    - name: uppercases
+      args:
+        text: "hi"
+      expected: "HI"
+"#,
+        );
+        let mut registry = TransformRegistry::new();
+        registry.load_transforms_from_dir(temp_dir.path()).unwrap();
+
+        let results = registry.run_tests(&crate::runtime::builtin_registry());
+        let outcomes: Vec<(&str, &TransformTestOutcome)> = results.iter()
+            .map(|r| (r.test.as_str(), &r.outcome))
+            .collect();
+        assert_eq!(outcomes.len(), 3);
+        assert_eq!(outcomes[0], ("whole_number", &TransformTestOutcome::Passed));
+        assert_eq!(
+            outcomes[1],
+            ("wrong_expectation", &TransformTestOutcome::Failed { actual: serde_json::json!(1.5) })
+        );
+        assert!(matches!(outcomes[2], ("uppercases", TransformTestOutcome::Skipped(_))));
+    }
+
+    #[test]
+    fn test_catalog() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_transform_yaml(
+            temp_dir.path(),
+            "extract_csv_field",
+            r#"
+transform:
+  name: extract_csv_field
+  language: rust
+  doc: Pick one column of a delimited line
+  parameters:
+    - name: line
+      type: String
+    - name: delimiter
+      type: String
+      default: ","
+      doc: Column separator, e.g. `,` or `|`
+  returns:
+    type: Option<String>
+  implementation:
+    type: builtin
+"#,
+        );
+        let mut registry = TransformRegistry::new();
+        registry.load_transforms_from_dir(temp_dir.path()).unwrap();
+
+        let json = registry.catalog_json();
+        assert_eq!(json[0]["name"], "extract_csv_field");
+        assert_eq!(json[0]["implementation"], "builtin");
+        assert_eq!(json[0]["parameters"][1]["default"], ",");
+        assert_eq!(json[0]["returns"]["type"], "Option<String>");
+
+        let md = registry.catalog_markdown();
+        assert!(md.contains("## `extract_csv_field`\n\nPick one column of a delimited line"), "{}", md);
+        assert!(md.contains("| `delimiter` | `String` | `,` | Column separator, e.g. `,` or `\\|` |"), "{}", md);
+        assert!(md.contains("**Returns** `Option<String>`"), "{}", md);
+    }
+
     #[test]
     fn test_validate_circular_reference() {
         let temp_dir = TempDir::new().unwrap();
@@ -503,6 +956,57 @@ transform:
         assert!(result.unwrap_err().contains("circular reference"));
     }
 
+    #[test]
+    fn test_call_rejects_indirect_cycle() {
+        let temp_dir = TempDir::new().unwrap();
+        let reference = |name: &str, target: &str| format!(r#"
+transform:
+  name: {}
+  language: rust
+  parameters:
+    - name: input
+      type: String
+  returns:
+    type: String
+  implementation:
+    type: reference
+    steps:
+      - transform: {}
+        args:
+          input: $input
+        output: result
+    return: $result
+"#, name, target);
+
+        let mut registry = TransformRegistry::new();
+        let ping = create_test_transform_yaml(temp_dir.path(), "ping", r#"
+transform:
+  name: ping
+  language: rust
+  parameters:
+    - name: input
+      type: String
+  returns:
+    type: String
+  implementation:
+    type: builtin
+"#);
+        registry.load_transform(&ping).unwrap();
+        let pong = create_test_transform_yaml(temp_dir.path(), "pong", &reference("pong", "ping"));
+        registry.load_transform(&pong).unwrap();
+
+        // Redefining ping in terms of pong closes the loop after validation
+        let ping = create_test_transform_yaml(temp_dir.path(), "ping", &reference("ping", "pong"));
+        registry.load_transform(&ping).unwrap();
+
+        let builtins = crate::runtime::builtin_registry();
+        let mut args = HashMap::new();
+        args.insert("input".to_string(), serde_json::json!("x"));
+
+        let err = registry.call("ping", &args, &builtins).unwrap_err();
+        assert!(err.contains("circular reference: ping -> pong -> ping"), "{}", err);
+    }
+
     #[test]
     fn test_rust_and_python_transforms() {
         let mut registry = TransformRegistry::new();
@@ -2,14 +2,23 @@
 //!
 //! This module provides utilities for serializing entities to various formats.
 
-use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::{BTreeMap, HashMap};
 use std::io::Write;
+use crate::codegen::EntityDef;
+use crate::entity::FieldValue;
 
 /// Error type for serialization operations
 #[derive(Debug)]
 pub enum SerializationError {
     JsonError(serde_json::Error),
     IoError(std::io::Error),
+    MessagePackError(String),
+    CborError(String),
+    /// The entity's field mappings cannot be reversed into its source format
+    SourceFormatError(String),
 }
 
 impl From<serde_json::Error> for SerializationError {
@@ -29,12 +38,345 @@ impl std::fmt::Display for SerializationError {
         match self {
             SerializationError::JsonError(e) => write!(f, "JSON error: {}", e),
             SerializationError::IoError(e) => write!(f, "IO error: {}", e),
+            SerializationError::MessagePackError(msg) => write!(f, "MessagePack error: {}", msg),
+            SerializationError::CborError(msg) => write!(f, "CBOR error: {}", msg),
+            SerializationError::SourceFormatError(msg) => write!(f, "Source format error: {}", msg),
         }
     }
 }
 
 impl std::error::Error for SerializationError {}
 
+/// Wire format for serialized entities and message envelopes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SerializationFormat {
+    /// JSON (default, human-readable)
+    #[default]
+    Json,
+    /// MessagePack (compact binary)
+    #[serde(alias = "msgpack")]
+    MessagePack,
+    /// CBOR (compact binary, RFC 8949)
+    Cbor,
+}
+
+impl SerializationFormat {
+    /// MIME type for this format (used as the Content-Type header)
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            SerializationFormat::Json => "application/json",
+            SerializationFormat::MessagePack => "application/msgpack",
+            SerializationFormat::Cbor => "application/cbor",
+        }
+    }
+
+    /// Look up a format by its Content-Type header value
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        match content_type {
+            "application/json" => Some(SerializationFormat::Json),
+            "application/msgpack" => Some(SerializationFormat::MessagePack),
+            "application/cbor" => Some(SerializationFormat::Cbor),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for SerializationFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(SerializationFormat::Json),
+            "msgpack" | "messagepack" => Ok(SerializationFormat::MessagePack),
+            "cbor" => Ok(SerializationFormat::Cbor),
+            other => Err(format!(
+                "Unknown serialization format '{}' (expected json, msgpack or cbor)",
+                other
+            )),
+        }
+    }
+}
+
+/// Pluggable serializer for entities and envelopes
+pub trait Serializer {
+    /// Format produced by this serializer
+    fn format(&self) -> SerializationFormat;
+
+    /// Serialize a value to bytes
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, SerializationError>;
+
+    /// Deserialize a value from bytes
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, SerializationError>;
+}
+
+/// JSON serializer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonSerializer;
+
+impl Serializer for JsonSerializer {
+    fn format(&self) -> SerializationFormat {
+        SerializationFormat::Json
+    }
+
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, SerializationError> {
+        Ok(serde_json::to_vec(value)?)
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, SerializationError> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+/// MessagePack serializer (structs encoded as maps so fields can evolve)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MessagePackSerializer;
+
+impl Serializer for MessagePackSerializer {
+    fn format(&self) -> SerializationFormat {
+        SerializationFormat::MessagePack
+    }
+
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, SerializationError> {
+        rmp_serde::to_vec_named(value).map_err(|e| SerializationError::MessagePackError(e.to_string()))
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, SerializationError> {
+        rmp_serde::from_slice(bytes).map_err(|e| SerializationError::MessagePackError(e.to_string()))
+    }
+}
+
+/// CBOR serializer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CborSerializer;
+
+impl Serializer for CborSerializer {
+    fn format(&self) -> SerializationFormat {
+        SerializationFormat::Cbor
+    }
+
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, SerializationError> {
+        let mut buf = Vec::new();
+        ciborium::ser::into_writer(value, &mut buf)
+            .map_err(|e| SerializationError::CborError(e.to_string()))?;
+        Ok(buf)
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, SerializationError> {
+        ciborium::de::from_reader(bytes).map_err(|e| SerializationError::CborError(e.to_string()))
+    }
+}
+
+/// A format is itself a serializer, dispatching to the matching implementation
+impl Serializer for SerializationFormat {
+    fn format(&self) -> SerializationFormat {
+        *self
+    }
+
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, SerializationError> {
+        match self {
+            SerializationFormat::Json => JsonSerializer.serialize(value),
+            SerializationFormat::MessagePack => MessagePackSerializer.serialize(value),
+            SerializationFormat::Cbor => CborSerializer.serialize(value),
+        }
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, SerializationError> {
+        match self {
+            SerializationFormat::Json => JsonSerializer.deserialize(bytes),
+            SerializationFormat::MessagePack => MessagePackSerializer.deserialize(bytes),
+            SerializationFormat::Cbor => CborSerializer.deserialize(bytes),
+        }
+    }
+}
+
+/// Source record rebuilt from an entity's field values
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceRecord {
+    /// Delimited line (fields mapped with `extract_csv_field`)
+    Csv(String),
+    /// JSON document (fields mapped with `extract_json_field`,
+    /// `extract_json_subtree` or `copy_field`, and plain root fields)
+    Json(JsonValue),
+}
+
+impl std::fmt::Display for SourceRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SourceRecord::Csv(line) => write!(f, "{}", line),
+            SourceRecord::Json(doc) => write!(f, "{}", doc),
+        }
+    }
+}
+
+/// Where a field's value lives in the source record
+enum SourceSlot {
+    Column { index: usize, delimiter: char },
+    Pointer { pointer: String, parse_json: bool },
+}
+
+/// Rebuild the source record an entity was extracted from
+///
+/// `values` are the entity's fields keyed by serialized name (as produced by
+/// `Entity::to_dict`). Only direct mappings can be reversed: every field must
+/// be a plain (non-computed) field or use `copy_field`, `extract_csv_field`,
+/// `extract_json_field` or `extract_json_subtree`, all reading the same
+/// source, and CSV and JSON mappings cannot be mixed. Null values become
+/// empty CSV columns and are left out of JSON documents.
+pub fn to_source_format(
+    entity: &EntityDef,
+    values: &HashMap<String, FieldValue<'_>>,
+) -> Result<SourceRecord, SerializationError> {
+    let unsupported = |msg: String| SerializationError::SourceFormatError(format!("{}: {}", entity.name, msg));
+
+    let mut source: Option<String> = None;
+    let mut slots = Vec::new();
+    for field in &entity.fields {
+        let (slot, reads) = match field.computed_from {
+            None => (SourceSlot::Pointer { pointer: format!("/{}", escape_pointer(field.serialized_name())), parse_json: field.is_json() }, entity.name.clone()),
+            Some(ref computed) => {
+                let [from] = computed.sources.as_slice() else {
+                    return Err(unsupported(format!("field '{}' must read exactly one source", field.name)));
+                };
+                let from_field = from.field_name().unwrap_or(from.source_name());
+                let arg = |key: &str| computed.args.as_ref().and_then(|a| a.get(key));
+                match computed.transform.as_str() {
+                    "copy_field" => (
+                        SourceSlot::Pointer { pointer: format!("/{}", escape_pointer(from_field)), parse_json: field.is_json() },
+                        from.source_name().to_string(),
+                    ),
+                    "extract_csv_field" => {
+                        let index = arg("column_index")
+                            .and_then(|v| v.as_u64().or_else(|| v.as_str().and_then(|s| s.parse().ok())))
+                            .ok_or_else(|| unsupported(format!("field '{}' has no column_index", field.name)))?;
+                        let delimiter = match arg("delimiter").and_then(|v| v.as_str()) {
+                            None => ',',
+                            Some(d) if d.chars().count() == 1 => d.chars().next().unwrap_or(','),
+                            Some(d) => return Err(unsupported(format!("field '{}' has multi-character delimiter '{}'", field.name, d))),
+                        };
+                        (SourceSlot::Column { index: index as usize, delimiter }, format!("{}.{}", from.source_name(), from_field))
+                    }
+                    transform @ ("extract_json_field" | "extract_json_subtree") => {
+                        let key = if transform == "extract_json_field" { "json_path" } else { "path" };
+                        let path = arg(key).and_then(|v| v.as_str())
+                            .ok_or_else(|| unsupported(format!("field '{}' has no {}", field.name, key)))?;
+                        let pointer = crate::codegen::utils::json_pointer_from_path(path);
+                        (SourceSlot::Pointer { pointer, parse_json: transform == "extract_json_subtree" }, format!("{}.{}", from.source_name(), from_field))
+                    }
+                    other => {
+                        return Err(unsupported(format!(
+                            "field '{}' uses transform '{}'; only copy_field, extract_csv_field, extract_json_field and extract_json_subtree can be reversed",
+                            field.name, other
+                        )));
+                    }
+                }
+            }
+        };
+
+        match source {
+            Some(ref seen) if *seen != reads => {
+                return Err(unsupported(format!("fields read different sources ('{}' and '{}')", seen, reads)));
+            }
+            _ => source = Some(reads),
+        }
+        let value = values.get(field.serialized_name()).cloned().unwrap_or(FieldValue::Null);
+        slots.push((field.name.as_str(), slot, value));
+    }
+
+    let is_csv = matches!(slots.first(), Some((_, SourceSlot::Column { .. }, _)));
+    if is_csv {
+        let mut columns: BTreeMap<usize, String> = BTreeMap::new();
+        let mut line_delimiter = None;
+        for (name, slot, value) in slots {
+            let SourceSlot::Column { index, delimiter } = slot else {
+                return Err(unsupported("CSV and JSON mappings cannot be mixed".to_string()));
+            };
+            if *line_delimiter.get_or_insert(delimiter) != delimiter {
+                return Err(unsupported("fields use different CSV delimiters".to_string()));
+            }
+            let text = match value {
+                FieldValue::Null => String::new(),
+                other => other.to_string(),
+            };
+            if columns.insert(index, text.clone()).is_some_and(|previous| previous != text) {
+                return Err(unsupported(format!("field '{}' disagrees with another field on column {}", name, index)));
+            }
+        }
+
+        let delimiter = line_delimiter.unwrap_or(',');
+        let width = columns.keys().next_back().map_or(0, |last| last + 1);
+        let line = (0..width)
+            .map(|i| quote_csv(columns.get(&i).map(String::as_str).unwrap_or(""), delimiter))
+            .collect::<Vec<_>>()
+            .join(&delimiter.to_string());
+        return Ok(SourceRecord::Csv(line));
+    }
+
+    let mut doc = JsonValue::Object(Default::default());
+    for (name, slot, value) in slots {
+        let SourceSlot::Pointer { pointer, parse_json } = slot else {
+            return Err(unsupported("CSV and JSON mappings cannot be mixed".to_string()));
+        };
+        let value = match value {
+            FieldValue::Null => continue,
+            // Entity::to_dict stringifies nested objects; restore them
+            FieldValue::String(s) if parse_json => serde_json::from_str(&s).unwrap_or_else(|_| JsonValue::String(s.into_owned())),
+            other => serde_json::to_value(other)?,
+        };
+        let slot = pointer_slot(&mut doc, &pointer)
+            .ok_or_else(|| unsupported(format!("field '{}' conflicts with another field at '{}'", name, pointer)))?;
+        if !slot.is_null() && *slot != value {
+            return Err(unsupported(format!("field '{}' disagrees with another field at '{}'", name, pointer)));
+        }
+        *slot = value;
+    }
+    Ok(SourceRecord::Json(doc))
+}
+
+fn escape_pointer(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+/// Mutable slot at a JSON pointer, creating objects (or arrays, for numeric
+/// segments) along the way; None if an existing scalar is in the way
+fn pointer_slot<'a>(doc: &'a mut JsonValue, pointer: &str) -> Option<&'a mut JsonValue> {
+    let segments: Vec<String> = pointer.split('/')
+        .skip(1)
+        .map(|s| s.replace("~1", "/").replace("~0", "~"))
+        .collect();
+
+    let mut node = doc;
+    for segment in segments {
+        let index = segment.parse::<usize>().ok();
+        if node.is_null() {
+            *node = match index {
+                Some(_) => JsonValue::Array(Vec::new()),
+                None => JsonValue::Object(Default::default()),
+            };
+        }
+        node = match (node, index) {
+            (JsonValue::Array(items), Some(i)) => {
+                if items.len() <= i {
+                    items.resize(i + 1, JsonValue::Null);
+                }
+                &mut items[i]
+            }
+            (JsonValue::Object(map), _) => map.entry(segment).or_insert(JsonValue::Null),
+            _ => return None,
+        };
+    }
+    Some(node)
+}
+
+/// Quote a CSV column if it contains the delimiter, a quote or a line break
+fn quote_csv(column: &str, delimiter: char) -> String {
+    if column.contains(delimiter) || column.contains(['"', '\n', '\r']) {
+        format!("\"{}\"", column.replace('"', "\"\""))
+    } else {
+        column.to_string()
+    }
+}
+
 /// NDJSON (Newline Delimited JSON) writer
 ///
 /// Writes entities as NDJSON, one JSON object per line.
@@ -114,9 +456,9 @@ impl<W: Write> JsonArrayWriter<W> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serde::Serialize;
+    use serde::{Deserialize, Serialize};
 
-    #[derive(Serialize)]
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
     struct TestEntity {
         name: String,
         value: i32,
@@ -173,4 +515,98 @@ mod tests {
         assert!(output.contains("Alice"));
         assert!(output.contains("Bob"));
     }
+
+    #[test]
+    fn test_serializer_roundtrip_all_formats() {
+        let entity = TestEntity {
+            name: "Alice".to_string(),
+            value: 42,
+        };
+
+        for format in [
+            SerializationFormat::Json,
+            SerializationFormat::MessagePack,
+            SerializationFormat::Cbor,
+        ] {
+            let bytes = format.serialize(&entity).unwrap();
+            let decoded: TestEntity = format.deserialize(&bytes).unwrap();
+            assert_eq!(decoded, entity, "roundtrip failed for {:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_to_source_format() {
+        use crate::codegen::parse_entity;
+
+        let csv = parse_entity(r#"
+entity:
+  name: Row
+  source_type: derived
+  parent: CsvFile
+  fields:
+    - name: id
+      type: String
+      computed_from:
+        transform: extract_csv_field
+        sources: [{source: CsvFile, field: raw_line}]
+        args: {column_index: 0}
+    - name: city
+      type: String
+      computed_from:
+        transform: extract_csv_field
+        sources: [{source: CsvFile, field: raw_line}]
+        args: {column_index: 2}
+"#).unwrap();
+        let values = HashMap::from([
+            ("id".to_string(), FieldValue::from("7")),
+            ("city".to_string(), FieldValue::from("Paris, FR")),
+        ]);
+        assert_eq!(to_source_format(&csv, &values).unwrap().to_string(), "7,,\"Paris, FR\"");
+
+        let json = parse_entity(r#"
+entity:
+  name: User
+  source_type: derived
+  parent: JsonDocument
+  fields:
+    - name: name
+      type: String
+      computed_from:
+        transform: extract_json_field
+        sources: [{source: JsonDocument, field: raw_json}]
+        args: {json_path: "$.user.name"}
+    - name: first_tag
+      type: String
+      computed_from:
+        transform: extract_json_field
+        sources: [{source: JsonDocument, field: raw_json}]
+        args: {json_path: "$.tags[0]"}
+"#).unwrap();
+        let values = HashMap::from([
+            ("name".to_string(), FieldValue::from("ada")),
+            ("first_tag".to_string(), FieldValue::from("x")),
+        ]);
+        assert_eq!(
+            to_source_format(&json, &values).unwrap(),
+            SourceRecord::Json(serde_json::json!({"user": {"name": "ada"}, "tags": ["x"]}))
+        );
+
+        let mut computed = json.clone();
+        computed.fields[0].computed_from.as_mut().unwrap().transform = "upper_case".to_string();
+        let err = to_source_format(&computed, &values).unwrap_err().to_string();
+        assert!(err.contains("uses transform 'upper_case'"));
+    }
+
+    #[test]
+    fn test_serialization_format_parsing() {
+        assert_eq!("json".parse::<SerializationFormat>().unwrap(), SerializationFormat::Json);
+        assert_eq!("msgpack".parse::<SerializationFormat>().unwrap(), SerializationFormat::MessagePack);
+        assert_eq!("CBOR".parse::<SerializationFormat>().unwrap(), SerializationFormat::Cbor);
+        assert!("xml".parse::<SerializationFormat>().is_err());
+        assert_eq!(SerializationFormat::default(), SerializationFormat::Json);
+        assert_eq!(
+            SerializationFormat::from_content_type("application/cbor"),
+            Some(SerializationFormat::Cbor)
+        );
+    }
 }
@@ -5,6 +5,7 @@
 
 use std::collections::HashMap;
 use std::fmt;
+use std::sync::{Arc, RwLock};
 use serde_json::Value;
 
 /// Error type for transform operations
@@ -53,16 +54,38 @@ where
     }
 }
 
+/// Separator between namespace and name in a qualified transform name (`hl7::parse_name`)
+pub const NAMESPACE_SEPARATOR: &str = "::";
+
+/// Split a transform name into `(namespace, name)`; unqualified names have no namespace
+pub fn split_transform_name(name: &str) -> (Option<&str>, &str) {
+    match name.rsplit_once(NAMESPACE_SEPARATOR) {
+        Some((namespace, name)) => (Some(namespace), name),
+        None => (None, name),
+    }
+}
+
 /// Registry for storing and calling transformation functions
+///
+/// Transforms are keyed by `(namespace, name)` so source systems can each
+/// define e.g. a `parse_name` (`hl7::parse_name`, `csv::parse_name`).
+/// Unqualified names live in the global namespace (`""`).
+///
+/// Transforms are looked up by name on every call, so a registry shared
+/// behind an `Arc` can have implementations swapped with [`reload`] while
+/// extractions are running. Calls already in progress finish with the
+/// implementation they started with.
+///
+/// [`reload`]: TransformRegistry::reload
 pub struct TransformRegistry {
-    transforms: HashMap<String, Box<dyn TransformFn>>,
+    transforms: RwLock<HashMap<(String, String), Arc<dyn TransformFn>>>,
 }
 
 impl TransformRegistry {
     /// Create a new empty transform registry
     pub fn new() -> Self {
         Self {
-            transforms: HashMap::new(),
+            transforms: RwLock::new(HashMap::new()),
         }
     }
 
@@ -82,7 +105,58 @@ impl TransformRegistry {
     /// }));
     /// ```
     pub fn register(&mut self, name: impl Into<String>, func: Box<dyn TransformFn>) {
-        self.transforms.insert(name.into(), func);
+        let name = name.into();
+        let (namespace, name) = split_transform_name(&name);
+        self.register_in(namespace.unwrap_or(""), name, func);
+    }
+
+    /// Register a transformation function under a namespace
+    pub fn register_in(
+        &mut self,
+        namespace: impl Into<String>,
+        name: impl Into<String>,
+        func: Box<dyn TransformFn>,
+    ) {
+        self.transforms
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert((namespace.into(), name.into()), Arc::from(func));
+    }
+
+    /// Swap the implementation of an already registered transform
+    ///
+    /// Takes `&self` so a shared registry can be updated in place; extractions
+    /// pick up the new implementation on their next call.
+    pub fn reload(&self, name: &str, func: Box<dyn TransformFn>) -> Result<(), TransformError> {
+        let (namespace, short_name) = split_transform_name(name);
+        let key = (namespace.unwrap_or("").to_string(), short_name.to_string());
+
+        let mut transforms = self.transforms.write().unwrap_or_else(|e| e.into_inner());
+        match transforms.get_mut(&key) {
+            Some(slot) => {
+                *slot = Arc::from(func);
+                Ok(())
+            }
+            None => Err(TransformError::NotFound(name.to_string())),
+        }
+    }
+
+    /// Find a transform, resolving unqualified names in `namespace` before the global namespace
+    ///
+    /// The read lock is released before returning so a slow transform never
+    /// blocks a concurrent reload.
+    pub fn resolve(&self, namespace: Option<&str>, name: &str) -> Option<Arc<dyn TransformFn>> {
+        let transforms = self.transforms.read().unwrap_or_else(|e| e.into_inner());
+        let get = |ns: &str, name: &str| {
+            transforms.get(&(ns.to_string(), name.to_string())).cloned()
+        };
+
+        match split_transform_name(name) {
+            (Some(qualified_ns), name) => get(qualified_ns, name),
+            (None, name) => namespace
+                .and_then(|ns| get(ns, name))
+                .or_else(|| get("", name)),
+        }
     }
 
     /// Call a registered transformation function
@@ -100,23 +174,41 @@ impl TransformRegistry {
         &self,
         name: &str,
         args: &HashMap<String, Value>,
+    ) -> Result<Value, TransformError> {
+        self.call_in(None, name, args)
+    }
+
+    /// Call a transform on behalf of an entity declared in `namespace`
+    ///
+    /// Qualified names (`csv::parse_name`) are looked up as-is; unqualified
+    /// names resolve within `namespace` first, then in the global namespace.
+    pub fn call_in(
+        &self,
+        namespace: Option<&str>,
+        name: &str,
+        args: &HashMap<String, Value>,
     ) -> Result<Value, TransformError> {
         let transform = self
-            .transforms
-            .get(name)
+            .resolve(namespace, name)
             .ok_or_else(|| TransformError::NotFound(name.to_string()))?;
 
         transform.execute(args)
     }
 
-    /// Check if a transform is registered
+    /// Check if a transform is registered (qualified or global name)
     pub fn has_transform(&self, name: &str) -> bool {
-        self.transforms.contains_key(name)
+        self.resolve(None, name).is_some()
     }
 
-    /// Get list of all registered transform names
+    /// Get list of all registered transform names (namespaced ones qualified)
     pub fn list_transforms(&self) -> Vec<String> {
-        self.transforms.keys().cloned().collect()
+        self.transforms.read().unwrap_or_else(|e| e.into_inner()).keys()
+            .map(|(namespace, name)| if namespace.is_empty() {
+                name.clone()
+            } else {
+                format!("{}{}{}", namespace, NAMESPACE_SEPARATOR, name)
+            })
+            .collect()
     }
 }
 
@@ -170,4 +262,31 @@ mod tests {
         assert!(registry.has_transform("test_fn"));
         assert!(!registry.has_transform("other_fn"));
     }
+
+    #[test]
+    fn test_namespaced_transforms() {
+        let constant = |value: &'static str| {
+            Box::new(move |_args: &HashMap<String, Value>| Ok(Value::String(value.to_string())))
+                as Box<dyn TransformFn>
+        };
+
+        let mut registry = TransformRegistry::new();
+        registry.register("hl7::parse_name", constant("hl7"));
+        registry.register_in("csv", "parse_name", constant("csv"));
+        registry.register("trim", constant("global"));
+
+        let args = HashMap::new();
+        let call = |namespace: Option<&str>, name: &str| registry.call_in(namespace, name, &args).unwrap();
+
+        assert_eq!(call(None, "hl7::parse_name"), Value::String("hl7".to_string()));
+        assert_eq!(call(Some("csv"), "parse_name"), Value::String("csv".to_string()));
+        assert_eq!(call(Some("csv"), "hl7::parse_name"), Value::String("hl7".to_string()));
+        // Unqualified names fall back to the global namespace
+        assert_eq!(call(Some("hl7"), "trim"), Value::String("global".to_string()));
+        assert!(matches!(registry.call("parse_name", &args), Err(TransformError::NotFound(_))));
+
+        let mut names = registry.list_transforms();
+        names.sort();
+        assert_eq!(names, vec!["csv::parse_name", "hl7::parse_name", "trim"]);
+    }
 }
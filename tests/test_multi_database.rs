@@ -230,6 +230,7 @@ fn test_ingestion_server_generation_postgresql() {
         port: 8080,
         database_type: codegen::ingestion_server::DatabaseType::PostgreSQL,
         server_name: "test_ingestion_pg".to_string(),
+        ..Default::default()
     };
 
     codegen::ingestion_server::generate_all(&entities, &test_dir, &config)
@@ -242,6 +243,14 @@ fn test_ingestion_server_generation_postgresql() {
     assert!(cargo_toml.contains("diesel/postgres"),
         "Ingestion server Cargo.toml should reference postgres feature");
 
+    // OpenAPI spec references the envelope and entity message schemas
+    let main_rs = fs::read_to_string(test_dir.join("src/main.rs"))
+        .expect("Failed to read main.rs");
+    assert!(main_rs.contains("message_envelope::IngestionResponse,"),
+        "OpenAPI components should include IngestionResponse");
+    assert!(main_rs.contains("Message,") && main_rs.contains("parsers::"),
+        "OpenAPI components should include entity message schemas");
+
     println!("✅ PostgreSQL ingestion server generation test passed");
 }
 
@@ -254,6 +263,7 @@ fn test_ingestion_server_generation_mysql() {
         port: 8080,
         database_type: codegen::ingestion_server::DatabaseType::MySQL,
         server_name: "test_ingestion_mysql".to_string(),
+        ..Default::default()
     };
 
     codegen::ingestion_server::generate_all(&entities, &test_dir, &config)